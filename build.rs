@@ -1,4 +1,4 @@
-//! Generates Rust types for the GTFS-Realtime protobuf schema at build time.
+//! Generates Rust types for the GTFS-Realtime and gRPC API protobuf schemas at build time.
 //!
 //! We deliberately compile the `.proto` ourselves rather than depending on a
 //! pre-built bindings crate: it keeps the build self-contained (the protoc
@@ -10,10 +10,20 @@ fn main() {
     let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc available");
 
     println!("cargo:rerun-if-changed=proto/gtfs-realtime.proto");
+    println!("cargo:rerun-if-changed=proto/maas.proto");
 
     let mut config = prost_build::Config::new();
-    config.protoc_executable(protoc);
+    config.protoc_executable(&protoc);
     config
         .compile_protos(&["proto/gtfs-realtime.proto"], &["proto/"])
         .expect("failed to compile gtfs-realtime.proto");
+
+    // Messages only; the PlanService router is hand-written in `web/grpc.rs`.
+    if std::env::var_os("CARGO_FEATURE_GRPC").is_some() {
+        let mut config = prost_build::Config::new();
+        config.protoc_executable(&protoc);
+        config
+            .compile_protos(&["proto/maas.proto"], &["proto/"])
+            .expect("failed to compile maas.proto");
+    }
 }
@@ -1,17 +1,201 @@
+use chrono::{Local, NaiveDate, NaiveTime};
+
+use crate::{
+    services::{
+        accessibility::{AccessibilityOptions, parse_cutoff},
+        bench::{BenchOptions, LoadTestOptions},
+        travel_export::{RasterFormat, TravelExportOptions},
+    },
+    structures::{LatLng, Mode},
+};
+
 pub fn parse_config_path(args: &[String]) -> Result<String, String> {
+    Ok(flag_value(args, "--config")?.unwrap_or_else(|| "config.yaml".to_string()))
+}
+
+/// Value of `--name <value>` or `--name=<value>`; `None` when the flag is absent.
+pub fn flag_value(args: &[String], name: &str) -> Result<Option<String>, String> {
     let mut iter = args.iter();
     while let Some(arg) = iter.next() {
-        if let Some(value) = arg.strip_prefix("--config=") {
-            return Ok(value.to_string());
+        if let Some(value) = arg.strip_prefix(name).and_then(|r| r.strip_prefix('=')) {
+            return Ok(Some(value.to_string()));
         }
-        if arg == "--config" {
+        if arg == name {
             return match iter.next() {
-                Some(value) => Ok(value.to_string()),
-                None => Err("--config requires a path argument".to_string()),
+                Some(value) => Ok(Some(value.to_string())),
+                None => Err(format!("{name} requires a value")),
             };
         }
     }
-    Ok("config.yaml".to_string())
+    Ok(None)
+}
+
+/// `--diff <old.bin> <new.bin>`; `None` when the flag is absent.
+pub fn parse_diff_paths(args: &[String]) -> Result<Option<(String, String)>, String> {
+    let Some(i) = args.iter().position(|a| a == "--diff") else {
+        return Ok(None);
+    };
+    match (args.get(i + 1), args.get(i + 2)) {
+        (Some(old), Some(new)) if !old.starts_with("--") && !new.starts_with("--") => {
+            Ok(Some((old.clone(), new.clone())))
+        }
+        _ => Err("--diff requires two graph files: --diff <old.bin> <new.bin>".to_string()),
+    }
+}
+
+/// `--restore [<graph.bin>]` (or `--restore=<graph.bin>`): `Some(None)` restores the
+/// configured graph (newest snapshot when `build.snapshots` is set), `Some(Some(path))`
+/// that file; `None` when the flag is absent.
+pub fn parse_restore(args: &[String]) -> Option<Option<String>> {
+    let i = args.iter().position(|a| a == "--restore" || a.starts_with("--restore="))?;
+    if let Some(path) = args[i].strip_prefix("--restore=") {
+        return Some(Some(path.to_string()));
+    }
+    Some(args.get(i + 1).filter(|a| !a.starts_with("--")).cloned())
+}
+
+fn parsed_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    flag_value(args, name)?
+        .map(|v| v.parse().map_err(|_| format!("invalid {name} '{v}'")))
+        .transpose()
+}
+
+/// GraphQL-style mode name (`WALK_TRANSIT`, case-insensitive).
+fn parse_mode(name: &str) -> Result<Mode, String> {
+    Mode::from_name(name).ok_or_else(|| format!("unknown mode '{name}'"))
+}
+
+/// `--bench-samples` (100), `--bench-seed` (1), `--bench-date` (today),
+/// `--bench-time` (08:00), `--bench-window` minutes and `--bench-modes WALK,BIKE`.
+pub fn parse_bench_options(args: &[String]) -> Result<BenchOptions, String> {
+    let date = match flag_value(args, "--bench-date")? {
+        Some(d) => NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|_| format!("invalid --bench-date '{d}' (YYYY-MM-DD)"))?,
+        None => Local::now().date_naive(),
+    };
+    let time = match flag_value(args, "--bench-time")? {
+        Some(t) => NaiveTime::parse_from_str(&t, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(&t, "%H:%M:%S"))
+            .map_err(|_| format!("invalid --bench-time '{t}' (HH:MM)"))?,
+        None => NaiveTime::from_hms_opt(8, 0, 0).unwrap_or_default(),
+    };
+    let modes = flag_value(args, "--bench-modes")?
+        .map(|list| list.split(',').map(parse_mode).collect::<Result<Vec<_>, _>>())
+        .transpose()?;
+    Ok(BenchOptions {
+        samples: parsed_flag(args, "--bench-samples")?.unwrap_or(100),
+        seed: parsed_flag(args, "--bench-seed")?.unwrap_or(1),
+        date,
+        time,
+        window_minutes: parsed_flag(args, "--bench-window")?,
+        modes,
+    })
+}
+
+/// `--loadtest`: the `--bench-*` sample flags plus `--loadtest-concurrency` (one client
+/// per available core).
+pub fn parse_loadtest_options(args: &[String]) -> Result<LoadTestOptions, String> {
+    let concurrency = parsed_flag(args, "--loadtest-concurrency")?
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(4, |n| n.get()));
+    if concurrency == 0 {
+        return Err("--loadtest-concurrency must be positive".to_string());
+    }
+    Ok(LoadTestOptions {
+        bench: parse_bench_options(args)?,
+        concurrency,
+    })
+}
+
+/// `--export-travel-map <file>` with `--tt-origin LAT,LNG` (required), `--tt-date`
+/// (today), `--tt-time` (08:00), `--tt-max-secs` (3600), `--tt-step` metres, `--tt-modes`
+/// and `--tt-format geotiff|raw` (default from the file extension).
+pub fn parse_travel_export_options(args: &[String]) -> Result<TravelExportOptions, String> {
+    let output = flag_value(args, "--export-travel-map")?
+        .ok_or_else(|| "--export-travel-map requires an output file".to_string())?;
+    let origin = flag_value(args, "--tt-origin")?
+        .ok_or_else(|| "--export-travel-map requires --tt-origin LAT,LNG".to_string())?;
+    let origin = origin
+        .split_once(',')
+        .and_then(|(lat, lng)| Some(LatLng {
+            latitude: lat.trim().parse().ok()?,
+            longitude: lng.trim().parse().ok()?,
+        }))
+        .ok_or_else(|| format!("invalid --tt-origin '{origin}' (LAT,LNG)"))?;
+    let date = match flag_value(args, "--tt-date")? {
+        Some(d) => NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|_| format!("invalid --tt-date '{d}' (YYYY-MM-DD)"))?,
+        None => Local::now().date_naive(),
+    };
+    let time = match flag_value(args, "--tt-time")? {
+        Some(t) => NaiveTime::parse_from_str(&t, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(&t, "%H:%M:%S"))
+            .map_err(|_| format!("invalid --tt-time '{t}' (HH:MM)"))?,
+        None => NaiveTime::from_hms_opt(8, 0, 0).unwrap_or_default(),
+    };
+    let format = match flag_value(args, "--tt-format")?.as_deref() {
+        None => RasterFormat::from_path(&output),
+        Some("geotiff") => RasterFormat::GeoTiff,
+        Some("raw") => RasterFormat::Raw,
+        Some(f) => return Err(format!("invalid --tt-format '{f}' (geotiff|raw)")),
+    };
+    let max_secs = parsed_flag(args, "--tt-max-secs")?.unwrap_or(3600);
+    if max_secs == 0 {
+        return Err("--tt-max-secs must be positive".to_string());
+    }
+    let modes = flag_value(args, "--tt-modes")?
+        .map(|list| list.split(',').map(parse_mode).collect::<Result<Vec<_>, _>>())
+        .transpose()?;
+    Ok(TravelExportOptions {
+        output,
+        format,
+        origin,
+        date,
+        time,
+        max_secs,
+        grid_step_m: parsed_flag(args, "--tt-step")?,
+        modes,
+    })
+}
+
+/// `--accessibility <out.csv>` with `--origins <csv>` and `--pois <csv>` (required),
+/// `--cutoff` (45min), `--access-date` (today), `--access-time` (08:00) and
+/// `--access-modes`.
+pub fn parse_accessibility_options(args: &[String]) -> Result<AccessibilityOptions, String> {
+    let output = flag_value(args, "--accessibility")?
+        .ok_or_else(|| "--accessibility requires an output file".to_string())?;
+    let origins = flag_value(args, "--origins")?
+        .ok_or_else(|| "--accessibility requires --origins <csv>".to_string())?;
+    let pois = flag_value(args, "--pois")?
+        .ok_or_else(|| "--accessibility requires --pois <csv>".to_string())?;
+    let cutoff_secs = match flag_value(args, "--cutoff")? {
+        Some(c) => parse_cutoff(&c).ok_or_else(|| format!("invalid --cutoff '{c}' (e.g. 45min)"))?,
+        None => 45 * 60,
+    };
+    let date = match flag_value(args, "--access-date")? {
+        Some(d) => NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|_| format!("invalid --access-date '{d}' (YYYY-MM-DD)"))?,
+        None => Local::now().date_naive(),
+    };
+    let time = match flag_value(args, "--access-time")? {
+        Some(t) => NaiveTime::parse_from_str(&t, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(&t, "%H:%M:%S"))
+            .map_err(|_| format!("invalid --access-time '{t}' (HH:MM)"))?,
+        None => NaiveTime::from_hms_opt(8, 0, 0).unwrap_or_default(),
+    };
+    let modes = flag_value(args, "--access-modes")?
+        .map(|list| list.split(',').map(parse_mode).collect::<Result<Vec<_>, _>>())
+        .transpose()?;
+    Ok(AccessibilityOptions { output, origins, pois, cutoff_secs, date, time, modes })
+}
+
+/// `--feed <name>` of `--update-gtfs`: the one GTFS input to download again and replace
+/// in place in `transit.bin`; `None` refreshes every feed.
+pub fn parse_update_feed(args: &[String]) -> Result<Option<String>, String> {
+    let feed = flag_value(args, "--feed")?;
+    if feed.is_some() && !args.iter().any(|a| a == "--update-gtfs") {
+        return Err("--feed requires --update-gtfs".to_string());
+    }
+    Ok(feed)
 }
 
 #[cfg(test)]
@@ -45,4 +229,122 @@ mod tests {
         let a = args(&["maas-rs", "--config"]);
         assert!(parse_config_path(&a).is_err());
     }
+
+    #[test]
+    fn flag_prefix_does_not_match_longer_flags() {
+        let a = args(&["maas-rs", "--bench-seed=3", "--serve"]);
+        assert_eq!(flag_value(&a, "--bench").unwrap(), None);
+        assert_eq!(flag_value(&a, "--bench-seed").unwrap().as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn diff_takes_two_paths() {
+        let a = args(&["maas-rs", "--diff", "old.bin", "new.bin"]);
+        assert_eq!(parse_diff_paths(&a).unwrap(), Some(("old.bin".into(), "new.bin".into())));
+        assert_eq!(parse_diff_paths(&args(&["maas-rs", "--serve"])).unwrap(), None);
+        assert!(parse_diff_paths(&args(&["maas-rs", "--diff", "old.bin"])).is_err());
+        assert!(parse_diff_paths(&args(&["maas-rs", "--diff", "old.bin", "--serve"])).is_err());
+    }
+
+    #[test]
+    fn restore_takes_an_optional_path() {
+        assert_eq!(parse_restore(&args(&["maas-rs", "--serve"])), None);
+        assert_eq!(parse_restore(&args(&["maas-rs", "--restore", "--serve"])), Some(None));
+        assert_eq!(parse_restore(&args(&["maas-rs", "--restore"])), Some(None));
+        assert_eq!(
+            parse_restore(&args(&["maas-rs", "--restore", "snap.bin", "--serve"])),
+            Some(Some("snap.bin".into()))
+        );
+        assert_eq!(parse_restore(&args(&["maas-rs", "--restore=snap.bin"])), Some(Some("snap.bin".into())));
+    }
+
+    #[test]
+    fn bench_options_parse_and_default() {
+        let a = args(&[
+            "maas-rs",
+            "--bench",
+            "--bench-samples",
+            "25",
+            "--bench-date=2025-03-03",
+            "--bench-time",
+            "17:30",
+            "--bench-modes",
+            "walk_transit,BIKE",
+        ]);
+        let o = parse_bench_options(&a).unwrap();
+        assert_eq!(o.samples, 25);
+        assert_eq!(o.seed, 1);
+        assert_eq!(o.date, NaiveDate::from_ymd_opt(2025, 3, 3).unwrap());
+        assert_eq!(o.time, NaiveTime::from_hms_opt(17, 30, 0).unwrap());
+        assert_eq!(o.modes, Some(vec![Mode::WalkTransit, Mode::Bike]));
+
+        assert!(parse_bench_options(&args(&["maas-rs", "--bench-modes", "TELEPORT"])).is_err());
+        assert!(parse_bench_options(&args(&["maas-rs", "--bench-samples", "x"])).is_err());
+    }
+
+    #[test]
+    fn loadtest_options_reuse_bench_flags() {
+        let a = args(&["maas-rs", "--loadtest", "--loadtest-concurrency=8", "--bench-samples", "40"]);
+        let o = parse_loadtest_options(&a).unwrap();
+        assert_eq!((o.concurrency, o.bench.samples), (8, 40));
+        assert!(parse_loadtest_options(&args(&["maas-rs", "--loadtest"])).unwrap().concurrency >= 1);
+        assert!(parse_loadtest_options(&args(&["maas-rs", "--loadtest-concurrency", "0"])).is_err());
+    }
+
+    #[test]
+    fn travel_export_options_parse_and_default() {
+        let a = args(&[
+            "maas-rs",
+            "--export-travel-map",
+            "iso.tif",
+            "--tt-origin",
+            "50.85, 4.35",
+            "--tt-max-secs=1800",
+        ]);
+        let o = parse_travel_export_options(&a).unwrap();
+        assert_eq!(o.output, "iso.tif");
+        assert_eq!(o.format, RasterFormat::GeoTiff);
+        assert_eq!((o.origin.latitude, o.origin.longitude), (50.85, 4.35));
+        assert_eq!(o.max_secs, 1800);
+        assert_eq!(o.time, NaiveTime::from_hms_opt(8, 0, 0).unwrap());
+        assert_eq!(o.grid_step_m, None);
+
+        let raw = args(&["maas-rs", "--export-travel-map", "iso.tif", "--tt-origin", "50,4", "--tt-format", "raw"]);
+        assert_eq!(parse_travel_export_options(&raw).unwrap().format, RasterFormat::Raw);
+        assert!(parse_travel_export_options(&args(&["maas-rs", "--export-travel-map", "x.bin"])).is_err());
+        let bad = args(&["maas-rs", "--export-travel-map", "x.bin", "--tt-origin", "50"]);
+        assert!(parse_travel_export_options(&bad).is_err());
+    }
+
+    #[test]
+    fn accessibility_options_parse_and_default() {
+        let a = args(&[
+            "maas-rs",
+            "--accessibility",
+            "access.csv",
+            "--origins",
+            "origins.csv",
+            "--pois=pois.csv",
+            "--cutoff",
+            "30min",
+        ]);
+        let o = parse_accessibility_options(&a).unwrap();
+        assert_eq!((o.output.as_str(), o.origins.as_str(), o.pois.as_str()), ("access.csv", "origins.csv", "pois.csv"));
+        assert_eq!(o.cutoff_secs, 1800);
+        assert_eq!(o.time, NaiveTime::from_hms_opt(8, 0, 0).unwrap());
+        assert_eq!(o.modes, None);
+
+        let no_pois = args(&["maas-rs", "--accessibility", "a.csv", "--origins", "o.csv"]);
+        assert!(parse_accessibility_options(&no_pois).is_err());
+        let bad = args(&["maas-rs", "--accessibility", "a.csv", "--origins", "o.csv", "--pois", "p.csv", "--cutoff", "x"]);
+        assert!(parse_accessibility_options(&bad).is_err());
+    }
+
+    #[test]
+    fn update_feed_needs_update_gtfs() {
+        let a = args(&["maas-rs", "--update-gtfs", "--feed", "stib", "--save"]);
+        assert_eq!(parse_update_feed(&a).unwrap().as_deref(), Some("stib"));
+        assert_eq!(parse_update_feed(&args(&["maas-rs", "--update-gtfs"])).unwrap(), None);
+        assert!(parse_update_feed(&args(&["maas-rs", "--build", "--feed", "stib"])).is_err());
+    }
 }
@@ -0,0 +1,429 @@
+//! Intermediate on-disk form of a read GTFS feed, so a rebuild with an unchanged feed
+//! skips the CSV parsing. Only the tables and columns the loader reads are kept; stop
+//! times point at their stop by index instead of repeating it.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate};
+use gtfs_structures::{
+    Agency, Availability, BikesAllowedType, Calendar, CalendarDate, DirectionType, Exception,
+    FeedInfo, Gtfs, LocationType, PickupDropOffType, RawTranslation, Route, RouteType, Shape, Stop,
+    StopTime, Trip,
+};
+use serde::{Deserialize, Serialize};
+
+use super::ParsedGtfs;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedStop {
+    id: String,
+    code: Option<String>,
+    name: Option<String>,
+    location_type: LocationType,
+    parent_station: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    wheelchair_boarding: Availability,
+    level_id: Option<String>,
+    platform_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedRoute {
+    id: String,
+    short_name: Option<String>,
+    long_name: Option<String>,
+    route_type: RouteType,
+    agency_id: Option<String>,
+    color: Option<(u8, u8, u8)>,
+    text_color: Option<(u8, u8, u8)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedStopTime {
+    stop: u32,
+    arrival_time: Option<u32>,
+    departure_time: Option<u32>,
+    pickup_type: PickupDropOffType,
+    drop_off_type: PickupDropOffType,
+    stop_sequence: u32,
+    shape_dist_traveled: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedTrip {
+    id: String,
+    service_id: String,
+    route_id: String,
+    shape_id: Option<String>,
+    trip_headsign: Option<String>,
+    trip_short_name: Option<String>,
+    direction_id: Option<DirectionType>,
+    wheelchair_accessible: Availability,
+    bikes_allowed: BikesAllowedType,
+    stop_times: Vec<CachedStopTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedCalendar {
+    id: String,
+    /// Monday = bit 0.
+    days: u8,
+    start_date: i32,
+    end_date: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedTranslation {
+    table_name: String,
+    field_name: String,
+    language: String,
+    translation: String,
+    record_id: Option<String>,
+    record_sub_id: Option<String>,
+    field_value: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFeedInfo {
+    name: String,
+    url: String,
+    lang: String,
+    version: Option<String>,
+    start_date: Option<i32>,
+    end_date: Option<i32>,
+}
+
+/// `(lat, lon, sequence, dist_traveled)`.
+type ShapePoint = (f64, f64, usize, Option<f32>);
+
+/// A [`ParsedGtfs`] in serializable form.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedFeed {
+    stops: Vec<CachedStop>,
+    routes: Vec<CachedRoute>,
+    trips: Vec<CachedTrip>,
+    agencies: Vec<(Option<String>, String, String, String)>,
+    calendar: Vec<CachedCalendar>,
+    /// `(service_id, [(day, added)])`.
+    calendar_dates: Vec<(String, Vec<(i32, bool)>)>,
+    shapes: Vec<(String, Vec<ShapePoint>)>,
+    translations: Vec<CachedTranslation>,
+    feed_info: Vec<CachedFeedInfo>,
+    invalid_route_colors: usize,
+}
+
+fn day(d: NaiveDate) -> i32 {
+    d.num_days_from_ce()
+}
+
+fn date(d: i32) -> NaiveDate {
+    NaiveDate::from_num_days_from_ce_opt(d).unwrap_or_default()
+}
+
+impl CachedFeed {
+    pub fn from_parsed(parsed: &ParsedGtfs) -> Self {
+        let gtfs = &parsed.gtfs;
+        let mut stop_index: HashMap<&str, u32> = HashMap::new();
+        let stops = gtfs
+            .stops
+            .values()
+            .enumerate()
+            .map(|(i, s)| {
+                stop_index.insert(&s.id, i as u32);
+                CachedStop {
+                    id: s.id.clone(),
+                    code: s.code.clone(),
+                    name: s.name.clone(),
+                    location_type: s.location_type,
+                    parent_station: s.parent_station.clone(),
+                    latitude: s.latitude,
+                    longitude: s.longitude,
+                    wheelchair_boarding: s.wheelchair_boarding,
+                    level_id: s.level_id.clone(),
+                    platform_code: s.platform_code.clone(),
+                }
+            })
+            .collect();
+        let routes = gtfs
+            .routes
+            .values()
+            .map(|r| CachedRoute {
+                id: r.id.clone(),
+                short_name: r.short_name.clone(),
+                long_name: r.long_name.clone(),
+                route_type: r.route_type,
+                agency_id: r.agency_id.clone(),
+                color: r.color.map(|c| (c.r, c.g, c.b)),
+                text_color: r.text_color.map(|c| (c.r, c.g, c.b)),
+            })
+            .collect();
+        let trips = gtfs
+            .trips
+            .values()
+            .map(|t| CachedTrip {
+                id: t.id.clone(),
+                service_id: t.service_id.clone(),
+                route_id: t.route_id.clone(),
+                shape_id: t.shape_id.clone(),
+                trip_headsign: t.trip_headsign.clone(),
+                trip_short_name: t.trip_short_name.clone(),
+                direction_id: t.direction_id,
+                wheelchair_accessible: t.wheelchair_accessible,
+                bikes_allowed: t.bikes_allowed,
+                stop_times: t
+                    .stop_times
+                    .iter()
+                    .filter_map(|st| {
+                        Some(CachedStopTime {
+                            stop: *stop_index.get(st.stop.id.as_str())?,
+                            arrival_time: st.arrival_time,
+                            departure_time: st.departure_time,
+                            pickup_type: st.pickup_type,
+                            drop_off_type: st.drop_off_type,
+                            stop_sequence: st.stop_sequence,
+                            shape_dist_traveled: st.shape_dist_traveled,
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+        let calendar = gtfs
+            .calendar
+            .values()
+            .map(|c| CachedCalendar {
+                id: c.id.clone(),
+                days: [c.monday, c.tuesday, c.wednesday, c.thursday, c.friday, c.saturday, c.sunday]
+                    .iter()
+                    .enumerate()
+                    .fold(0, |acc, (i, &on)| acc | ((on as u8) << i)),
+                start_date: day(c.start_date),
+                end_date: day(c.end_date),
+            })
+            .collect();
+        CachedFeed {
+            stops,
+            routes,
+            trips,
+            agencies: gtfs
+                .agencies
+                .iter()
+                .map(|a| (a.id.clone(), a.name.clone(), a.url.clone(), a.timezone.clone()))
+                .collect(),
+            calendar,
+            calendar_dates: gtfs
+                .calendar_dates
+                .iter()
+                .map(|(id, dates)| {
+                    let dates = dates.iter().map(|d| (day(d.date), d.exception_type == Exception::Added));
+                    (id.clone(), dates.collect())
+                })
+                .collect(),
+            shapes: gtfs
+                .shapes
+                .iter()
+                .map(|(id, pts)| {
+                    let pts = pts.iter().map(|p| (p.latitude, p.longitude, p.sequence, p.dist_traveled));
+                    (id.clone(), pts.collect())
+                })
+                .collect(),
+            translations: parsed
+                .translation_rows
+                .iter()
+                .map(|t| CachedTranslation {
+                    table_name: t.table_name.clone(),
+                    field_name: t.field_name.clone(),
+                    language: t.language.clone(),
+                    translation: t.translation.clone(),
+                    record_id: t.record_id.clone(),
+                    record_sub_id: t.record_sub_id.clone(),
+                    field_value: t.field_value.clone(),
+                })
+                .collect(),
+            feed_info: gtfs
+                .feed_info
+                .iter()
+                .map(|f| CachedFeedInfo {
+                    name: f.name.clone(),
+                    url: f.url.clone(),
+                    lang: f.lang.clone(),
+                    version: f.version.clone(),
+                    start_date: f.start_date.map(day),
+                    end_date: f.end_date.map(day),
+                })
+                .collect(),
+            invalid_route_colors: parsed.invalid_route_colors,
+        }
+    }
+
+    /// The feed back as read from `path`.
+    pub fn into_parsed(self, path: &str) -> ParsedGtfs {
+        let stops: Vec<Arc<Stop>> = self
+            .stops
+            .into_iter()
+            .map(|s| {
+                Arc::new(Stop {
+                    id: s.id,
+                    code: s.code,
+                    name: s.name,
+                    location_type: s.location_type,
+                    parent_station: s.parent_station,
+                    latitude: s.latitude,
+                    longitude: s.longitude,
+                    wheelchair_boarding: s.wheelchair_boarding,
+                    level_id: s.level_id,
+                    platform_code: s.platform_code,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        // `RGB8` is not re-exported; start from the one a default route yields.
+        let rgb = |c: Option<(u8, u8, u8)>| {
+            c.map(|(r, g, b)| {
+                let mut c = Route::default().text_color();
+                (c.r, c.g, c.b) = (r, g, b);
+                c
+            })
+        };
+        let mut gtfs = Gtfs {
+            routes: self
+                .routes
+                .into_iter()
+                .map(|r| {
+                    let route = Route {
+                        id: r.id.clone(),
+                        short_name: r.short_name,
+                        long_name: r.long_name,
+                        route_type: r.route_type,
+                        agency_id: r.agency_id,
+                        color: rgb(r.color),
+                        text_color: rgb(r.text_color),
+                        ..Default::default()
+                    };
+                    (r.id, route)
+                })
+                .collect(),
+            trips: self
+                .trips
+                .into_iter()
+                .map(|t| {
+                    let stop_times = t
+                        .stop_times
+                        .into_iter()
+                        .map(|st| StopTime {
+                            stop: Arc::clone(&stops[st.stop as usize]),
+                            arrival_time: st.arrival_time,
+                            departure_time: st.departure_time,
+                            pickup_type: st.pickup_type,
+                            drop_off_type: st.drop_off_type,
+                            stop_sequence: st.stop_sequence,
+                            shape_dist_traveled: st.shape_dist_traveled,
+                            ..Default::default()
+                        })
+                        .collect();
+                    let trip = Trip {
+                        id: t.id.clone(),
+                        service_id: t.service_id,
+                        route_id: t.route_id,
+                        stop_times,
+                        shape_id: t.shape_id,
+                        trip_headsign: t.trip_headsign,
+                        trip_short_name: t.trip_short_name,
+                        direction_id: t.direction_id,
+                        wheelchair_accessible: t.wheelchair_accessible,
+                        bikes_allowed: t.bikes_allowed,
+                        ..Default::default()
+                    };
+                    (t.id, trip)
+                })
+                .collect(),
+            agencies: self
+                .agencies
+                .into_iter()
+                .map(|(id, name, url, timezone)| Agency { id, name, url, timezone, ..Default::default() })
+                .collect(),
+            calendar: self
+                .calendar
+                .into_iter()
+                .map(|c| {
+                    let on = |i: u8| c.days & (1 << i) != 0;
+                    let cal = Calendar {
+                        id: c.id.clone(),
+                        monday: on(0),
+                        tuesday: on(1),
+                        wednesday: on(2),
+                        thursday: on(3),
+                        friday: on(4),
+                        saturday: on(5),
+                        sunday: on(6),
+                        start_date: date(c.start_date),
+                        end_date: date(c.end_date),
+                    };
+                    (c.id, cal)
+                })
+                .collect(),
+            calendar_dates: self
+                .calendar_dates
+                .into_iter()
+                .map(|(id, dates)| {
+                    let dates = dates.into_iter().map(|(d, added)| CalendarDate {
+                        service_id: id.clone(),
+                        date: date(d),
+                        exception_type: if added { Exception::Added } else { Exception::Deleted },
+                    });
+                    (id.clone(), dates.collect())
+                })
+                .collect(),
+            shapes: self
+                .shapes
+                .into_iter()
+                .map(|(id, pts)| {
+                    let pts = pts.into_iter().map(|(latitude, longitude, sequence, dist_traveled)| Shape {
+                        id: id.clone(),
+                        latitude,
+                        longitude,
+                        sequence,
+                        dist_traveled,
+                    });
+                    (id.clone(), pts.collect())
+                })
+                .collect(),
+            feed_info: self
+                .feed_info
+                .into_iter()
+                .map(|f| FeedInfo {
+                    name: f.name,
+                    url: f.url,
+                    lang: f.lang,
+                    default_lang: None,
+                    start_date: f.start_date.map(date),
+                    end_date: f.end_date.map(date),
+                    version: f.version,
+                    contact_email: None,
+                    contact_url: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        gtfs.stops = stops.into_iter().map(|s| (s.id.clone(), s)).collect();
+        ParsedGtfs {
+            path: path.to_string(),
+            gtfs,
+            translation_rows: self
+                .translations
+                .into_iter()
+                .map(|t| RawTranslation {
+                    table_name: t.table_name,
+                    field_name: t.field_name,
+                    language: t.language,
+                    translation: t.translation,
+                    record_id: t.record_id,
+                    record_sub_id: t.record_sub_id,
+                    field_value: t.field_value,
+                })
+                .collect(),
+            invalid_route_colors: self.invalid_route_colors,
+        }
+    }
+}
@@ -1,20 +1,115 @@
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
 use gtfs_structures::{PickupDropOffType, RouteType};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    ingestion::gtfs::IdMapper,
+    ingestion::gtfs::{IdMapper, collect_translations},
     ingestion::osm::{PLATFORM_MATCH_RADIUS_M, PlatformMatch, StopPlatformQuery, offset_stats},
     structures::{
-        BikeAttrs, EdgeData, Graph, LatLng, NodeData, NodeID, StreetEdgeData, TransitEdgeData,
-        TransitStopData,
+        BikeAttrs, EdgeData, Graph, LatLng, NodeData, NodeID, StreetEdgeData, StreetProfile,
+        TransitEdgeData, TransitStopData,
         cost::VarGen,
         raptor::{Lookup, PatternInfo},
     },
 };
 
-static MAX_NEIGHBOR_DISTANCE: f64 = 1000.0;
+/// How a feed's stops attach to the street network: up to `k` walkable street nodes
+/// within `radius_m`, each by its own straight-line connector. A stop with no walkable
+/// node in range falls back to the closest node of any kind in range; one with no node
+/// in range is left unlinked (and counted in `IngestionReport::stops_unlinked`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopLinking {
+    pub radius_m: f64,
+    pub k: usize,
+}
+
+impl Default for StopLinking {
+    fn default() -> Self {
+        StopLinking { radius_m: 1000.0, k: 1 }
+    }
+}
+
+/// Part of a feed [`load_gtfs`] keeps: routes by type and agency, stops by area. Trips
+/// of a dropped route go with it and, once routes are filtered, so do the stops no kept
+/// trip serves. Dropped items are counted in `IngestionReport::*_filtered`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedFilter {
+    /// Keep only routes of these types; `None` keeps all.
+    pub route_types: Option<Vec<RouteType>>,
+    pub exclude_route_types: Vec<RouteType>,
+    /// Keep only routes of agencies with one of these ids or names; `None` keeps all.
+    pub agencies: Option<Vec<String>>,
+    /// Keep only stops inside `[min_lon, min_lat, max_lon, max_lat]`.
+    pub bbox: Option<[f64; 4]>,
+}
+
+impl FeedFilter {
+    fn filters_routes(&self) -> bool {
+        self.route_types.is_some() || !self.exclude_route_types.is_empty() || self.agencies.is_some()
+    }
+
+    fn keeps_route(
+        &self,
+        route: &gtfs_structures::Route,
+        agencies: Option<&HashSet<&str>>,
+        sole_agency: Option<&str>,
+    ) -> bool {
+        let ty = route.route_type;
+        let agency = route.agency_id.as_deref().or(sole_agency).unwrap_or_default();
+        self.route_types.as_ref().is_none_or(|t| t.contains(&ty))
+            && !self.exclude_route_types.contains(&ty)
+            && agencies.is_none_or(|a| a.contains(agency))
+    }
+
+    fn apply(&self, gtfs: &mut gtfs_structures::Gtfs, report: &mut IngestionReport) {
+        if self.filters_routes() {
+            // A route without `agency_id` belongs to the feed's only agency.
+            let sole_agency = match gtfs.agencies.as_slice() {
+                [a] => a.id.as_deref(),
+                _ => None,
+            };
+            let agencies: Option<HashSet<&str>> = self.agencies.as_ref().map(|wanted| {
+                gtfs.agencies
+                    .iter()
+                    .filter(|a| wanted.iter().any(|w| a.id.as_ref() == Some(w) || a.name == *w))
+                    .map(|a| a.id.as_deref().unwrap_or_default())
+                    .collect()
+            });
+            let dropped: HashSet<String> = gtfs
+                .routes
+                .iter()
+                .filter(|(_, r)| !self.keeps_route(r, agencies.as_ref(), sole_agency))
+                .map(|(id, _)| id.clone())
+                .collect();
+            gtfs.routes.retain(|id, _| !dropped.contains(id));
+            let trips = gtfs.trips.len();
+            gtfs.trips.retain(|_, t| !dropped.contains(&t.route_id));
+            report.routes_filtered = dropped.len();
+            report.trips_filtered = trips - gtfs.trips.len();
+
+            let mut served: HashSet<String> = HashSet::new();
+            for st in gtfs.trips.values().flat_map(|t| &t.stop_times) {
+                served.insert(st.stop.id.clone());
+                served.extend(st.stop.parent_station.clone());
+            }
+            let stops = gtfs.stops.len();
+            gtfs.stops.retain(|id, _| served.contains(id));
+            report.stops_filtered += stops - gtfs.stops.len();
+        }
+        if let Some([min_lon, min_lat, max_lon, max_lat]) = self.bbox {
+            let stops = gtfs.stops.len();
+            gtfs.stops.retain(|_, s| match (s.latitude, s.longitude) {
+                (Some(lat), Some(lon)) => {
+                    (min_lat..=max_lat).contains(&lat) && (min_lon..=max_lon).contains(&lon)
+                }
+                _ => true,
+            });
+            report.stops_filtered += stops - gtfs.stops.len();
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct AgencyId(pub u16);
@@ -53,11 +148,11 @@ impl Default for StopTime {
     }
 }
 
+/// One trip's departure on one route hop. The hop's stop order lives once per pattern
+/// (`transit_pattern_stops`), not on every departure, so this stays 16 bytes.
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TripSegment {
     pub trip_id: TripId,
-    pub origin_stop_sequence: u32,
-    pub destination_stop_sequence: u32,
     pub departure: u32,
     pub arrival: u32,
     pub service_id: ServiceId,
@@ -70,10 +165,95 @@ struct RouteSegment {
     pub route_id: RouteId,
 }
 
+/// A hop's run of departures in [`TripSegments`], sorted by departure.
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TimetableSegment {
-    pub start: usize,
-    pub len: usize,
+    pub start: u32,
+    pub len: u32,
+}
+
+impl TimetableSegment {
+    pub fn range(self) -> Range<usize> {
+        self.start as usize..self.start as usize + self.len as usize
+    }
+}
+
+/// Every hop's departures as struct-of-arrays: the departure column is what
+/// `partition_point` scans, so it stays contiguous instead of strided through
+/// [`TripSegment`]s. Indices are absolute, as in `TimetableSegment::start`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TripSegments {
+    pub trip_ids: Vec<TripId>,
+    pub departures: Vec<u32>,
+    pub arrivals: Vec<u32>,
+    pub service_ids: Vec<ServiceId>,
+}
+
+impl TripSegments {
+    pub fn len(&self) -> usize {
+        self.departures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.departures.is_empty()
+    }
+
+    /// Segment `i`; panics when out of range, like slice indexing.
+    pub fn at(&self, i: usize) -> TripSegment {
+        TripSegment {
+            trip_id: self.trip_ids[i],
+            departure: self.departures[i],
+            arrival: self.arrivals[i],
+            service_id: self.service_ids[i],
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Option<TripSegment> {
+        (i < self.len()).then(|| self.at(i))
+    }
+
+    pub fn push(&mut self, seg: TripSegment) {
+        self.trip_ids.push(seg.trip_id);
+        self.departures.push(seg.departure);
+        self.arrivals.push(seg.arrival);
+        self.service_ids.push(seg.service_id);
+    }
+
+    /// Segments in `range` by value, or `None` when it runs past the end.
+    pub fn slice(
+        &self,
+        range: Range<usize>,
+    ) -> Option<impl DoubleEndedIterator<Item = TripSegment> + ExactSizeIterator + '_> {
+        (range.start <= range.end && range.end <= self.len()).then(|| range.map(|i| self.at(i)))
+    }
+
+    /// Segments of `tt` by value, earliest departure first.
+    pub fn of(
+        &self,
+        tt: TimetableSegment,
+    ) -> impl DoubleEndedIterator<Item = TripSegment> + ExactSizeIterator + '_ {
+        tt.range().map(|i| self.at(i))
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = TripSegment> + ExactSizeIterator + '_ {
+        (0..self.len()).map(|i| self.at(i))
+    }
+}
+
+impl Extend<TripSegment> for TripSegments {
+    fn extend<I: IntoIterator<Item = TripSegment>>(&mut self, iter: I) {
+        for seg in iter {
+            self.push(seg);
+        }
+    }
+}
+
+impl FromIterator<TripSegment> for TripSegments {
+    fn from_iter<I: IntoIterator<Item = TripSegment>>(iter: I) -> Self {
+        let mut segs = TripSegments::default();
+        segs.extend(iter);
+        segs
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -122,6 +302,117 @@ impl ServicePattern {
     }
 }
 
+/// Longest span expanded into bits; dates outside it fall back to [`ServicePattern::is_active`].
+const CALENDAR_MAX_DAYS: u32 = 4 * 366;
+
+/// Every service pre-expanded into one bit per day over the feeds' validity period, so
+/// the hot-path service check is a single bit test instead of two binary searches.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceCalendar {
+    /// Day number (`date_to_days`) of bit 0.
+    base: u32,
+    days: u32,
+    /// `u64` words per service.
+    stride: usize,
+    bits: Vec<u64>,
+}
+
+impl ServiceCalendar {
+    pub fn build(services: &[ServicePattern]) -> Self {
+        // Calendar-dates-only services carry an open `0..=u32::MAX` range; only their
+        // explicit dates bound the period.
+        let bounded = |s: &&ServicePattern| s.end_date != u32::MAX;
+        let lo = services
+            .iter()
+            .filter(bounded)
+            .map(|s| s.start_date)
+            .chain(services.iter().flat_map(|s| s.added_dates.first().copied()))
+            .min();
+        let hi = services
+            .iter()
+            .filter(bounded)
+            .map(|s| s.end_date)
+            .chain(services.iter().flat_map(|s| s.added_dates.last().copied()))
+            .max();
+        let (Some(base), Some(hi)) = (lo, hi) else {
+            return Self::default();
+        };
+        let days = (hi.saturating_sub(base) + 1).min(CALENDAR_MAX_DAYS);
+        let stride = (days as usize).div_ceil(64);
+        let mut bits = vec![0u64; stride * services.len()];
+        for (i, sp) in services.iter().enumerate() {
+            let row = &mut bits[i * stride..(i + 1) * stride];
+            for d in 0..days {
+                let date = base + d;
+                if sp.is_active(date, weekday_bit(date)) {
+                    row[d as usize / 64] |= 1 << (d % 64);
+                }
+            }
+        }
+        Self {
+            base,
+            days,
+            stride,
+            bits,
+        }
+    }
+
+    /// `None` when the service or date is not expanded, or `weekday` disagrees with the
+    /// date (callers then use [`ServicePattern::is_active`] with their own weekday).
+    #[inline]
+    pub fn is_active(&self, service: ServiceId, date: u32, weekday: u8) -> Option<bool> {
+        let d = date.checked_sub(self.base).filter(|&d| d < self.days)?;
+        if weekday != weekday_bit(date) {
+            return None;
+        }
+        let word = self.bits.get(service.0 as usize * self.stride + d as usize / 64)?;
+        Some(word >> (d % 64) & 1 == 1)
+    }
+
+    pub fn heap_bytes(&self) -> usize {
+        self.bits.capacity() * 8
+    }
+}
+
+/// Which services run on one service day, one bit each: built once per request so a
+/// departure scan tests a bit instead of re-resolving the calendar per candidate, and
+/// a day without any service is answered before touching a timetable.
+#[derive(Debug, Clone, Default)]
+pub struct ActiveServices {
+    bits: Vec<u64>,
+    any: bool,
+}
+
+impl ActiveServices {
+    pub fn from_fn(n_services: usize, mut active: impl FnMut(ServiceId) -> bool) -> Self {
+        let mut bits = vec![0u64; n_services.div_ceil(64)];
+        for s in 0..n_services {
+            if active(ServiceId(s as u32)) {
+                bits[s / 64] |= 1 << (s % 64);
+            }
+        }
+        let any = bits.iter().any(|&w| w != 0);
+        Self { bits, any }
+    }
+
+    #[inline]
+    pub fn contains(&self, service: ServiceId) -> bool {
+        let s = service.0 as usize;
+        self.bits.get(s / 64).is_some_and(|w| w >> (s % 64) & 1 == 1)
+    }
+
+    /// No service runs that day.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        !self.any
+    }
+}
+
+/// `days_of_week` bit of a `date_to_days` day number (day 0 = Sat 2000-01-01).
+pub fn weekday_bit(date: u32) -> u8 {
+    1 << ((date + 5) % 7)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GtfsProvider {
     Sncb,
@@ -263,36 +554,286 @@ fn absorb_orphan_stops(
     }
 }
 
-pub fn load_gtfs(gtfs_path: &str, g: &mut Graph) -> Result<(), gtfs_structures::Error> {
-    load_gtfs_with_hook(gtfs_path, g, GtfsProvider::Generic, |_, _| None)
+/// Upper bounds (metres) of the stop-to-street link distance histogram; the last bucket
+/// ends at `MAX_NEIGHBOR_DISTANCE`.
+pub const LINK_DISTANCE_BUCKETS_M: [u32; 8] = [5, 10, 25, 50, 100, 250, 500, 1000];
+
+/// Sample identifiers kept per report list.
+const REPORT_MAX_IDS: usize = 20;
+
+/// Warnings kept per report.
+pub const REPORT_MAX_WARNINGS: usize = 10_000;
+
+/// Per-feed data-quality counters from one GTFS load, persisted on the graph
+/// (`Graph::ingestion_reports`) for operators.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestionReport {
+    /// Ingestor label (the GTFS path until the build pipeline names it).
+    pub feed: String,
+    pub stops_total: usize,
+    pub stops_loaded: usize,
+    pub stops_without_coords: usize,
+    pub stops_without_name: usize,
+    /// Loaded but left without a street link (no street node, or none within range).
+    pub stops_unlinked: usize,
+    /// Linked to a matched OSM platform instead of the nearest street node.
+    pub stops_platform_matched: usize,
+    /// Counts per `LINK_DISTANCE_BUCKETS_M` bucket of nearest-street link distances.
+    pub link_distance_histogram: Vec<usize>,
+    pub trips_total: usize,
+    pub trips_loaded: usize,
+    pub trips_without_service: usize,
+    pub trips_without_route: usize,
+    /// Fewer than two stop times that resolve to a loaded stop.
+    pub trips_too_short: usize,
+    pub routes_total: usize,
+    /// Routes dropped because their `agency_id` is not in `agency.txt`.
+    pub routes_unmatched_agency: usize,
+    pub unmatched_agency_ids: Vec<String>,
+    /// `route_color` / `route_text_color` values dropped as malformed (not six hex
+    /// digits once a leading `#` is stripped); the route loads without that colour.
+    pub route_colors_invalid: usize,
+    /// Dropped by the input's `filter` before loading; not part of the totals above.
+    pub routes_filtered: usize,
+    pub trips_filtered: usize,
+    pub stops_filtered: usize,
+    /// The skipped or unlinked records behind the counters, in load order, up to
+    /// `REPORT_MAX_WARNINGS`; the counters stay exact past the cap.
+    pub warnings: Vec<IngestionWarning>,
 }
 
-pub(crate) fn load_gtfs_with_hook<F>(
+/// Why `load_gtfs` skipped (or left unreachable) a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IngestionWarningReason {
+    StopWithoutCoords,
+    StopWithoutName,
+    /// Loaded, but the graph has no street node to link it to.
+    StopWithoutStreet,
+    /// Loaded, but no street node lies within the linking radius.
+    StopTooFarFromStreet,
+    RouteUnmatchedAgency,
+    TripWithoutService,
+    TripWithoutRoute,
+    TripTooShort,
+}
+
+/// One skipped record: its namespaced GTFS id and, where useful, the offending value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngestionWarning {
+    pub reason: IngestionWarningReason,
+    pub entity_id: String,
+    pub detail: Option<String>,
+}
+
+impl IngestionReport {
+    fn new(feed: &str) -> Self {
+        IngestionReport {
+            feed: feed.to_string(),
+            link_distance_histogram: vec![0; LINK_DISTANCE_BUCKETS_M.len()],
+            ..Default::default()
+        }
+    }
+
+    fn record_link(&mut self, metres: usize) {
+        let bucket = LINK_DISTANCE_BUCKETS_M
+            .iter()
+            .position(|&max| metres <= max as usize)
+            .unwrap_or(LINK_DISTANCE_BUCKETS_M.len() - 1);
+        self.link_distance_histogram[bucket] += 1;
+    }
+
+    fn warn(&mut self, reason: IngestionWarningReason, entity_id: String, detail: Option<String>) {
+        if self.warnings.len() < REPORT_MAX_WARNINGS {
+            self.warnings.push(IngestionWarning { reason, entity_id, detail });
+        }
+    }
+}
+
+/// `"{namespace}:{id}"`, or `id` unchanged for a feed loaded without a namespace.
+pub fn namespaced_id(namespace: Option<&str>, id: &str) -> String {
+    match namespace {
+        Some(ns) => format!("{ns}:{id}"),
+        None => id.to_string(),
+    }
+}
+
+pub fn load_gtfs(
     gtfs_path: &str,
     g: &mut Graph,
-    provider: GtfsProvider,
-    bikes_fn: F,
-) -> Result<(), gtfs_structures::Error>
-where
-    F: Fn(&gtfs_structures::Trip, RouteType) -> Option<bool>,
-{
-    let mut gtfs = gtfs_structures::Gtfs::new(gtfs_path).map_err(|e| {
+    linking: StopLinking,
+    filter: &FeedFilter,
+) -> Result<IngestionReport, gtfs_structures::Error> {
+    load_parsed_gtfs(read_gtfs(gtfs_path)?, g, linking, filter)
+}
+
+/// [`load_gtfs`] of a feed already read by [`read_gtfs`].
+pub fn load_parsed_gtfs(
+    parsed: ParsedGtfs,
+    g: &mut Graph,
+    linking: StopLinking,
+    filter: &FeedFilter,
+) -> Result<IngestionReport, gtfs_structures::Error> {
+    load_gtfs_with_hook(parsed, g, GtfsProvider::Generic, linking, filter, |_, _| None)
+}
+
+/// A GTFS feed read from disk but not yet merged into a graph. Reading touches no
+/// graph state, so the feeds of one build phase can be read in parallel and then
+/// merged one by one in config order.
+pub struct ParsedGtfs {
+    pub(super) path: String,
+    pub(super) gtfs: gtfs_structures::Gtfs,
+    pub(super) translation_rows: Vec<gtfs_structures::RawTranslation>,
+    /// Colour values [`read_gtfs`] had to drop from `routes.txt`.
+    pub(super) invalid_route_colors: usize,
+}
+
+impl ParsedGtfs {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The first `feed_info.txt` row, if any.
+    pub fn feed_info(&self) -> Option<&gtfs_structures::FeedInfo> {
+        self.gtfs.feed_info.first()
+    }
+}
+
+pub fn read_gtfs(gtfs_path: &str) -> Result<ParsedGtfs, gtfs_structures::Error> {
+    // Read raw first: `Gtfs` drops `translations.txt`.
+    let mut raw = gtfs_structures::RawGtfs::new(gtfs_path).map_err(|e| {
         tracing::error!(
             "failed to open GTFS '{gtfs_path}': {e}. If this is a cached download, the file may \
              be corrupt or an HTML error page; delete '{gtfs_path}' to force a re-download."
         );
         e
     })?;
+    let translation_rows = match raw.translations.take() {
+        Some(Ok(rows)) => rows,
+        Some(Err(e)) => {
+            tracing::warn!("ignoring unreadable translations.txt in '{gtfs_path}': {e}");
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    // One malformed colour fails the whole of `routes.txt` (and so the feed).
+    let mut invalid_route_colors = 0;
+    if raw.routes.is_err()
+        && let Some((routes, invalid)) = read_routes_lenient(gtfs_path)
+    {
+        tracing::warn!(
+            "'{gtfs_path}': repaired route colours in routes.txt, {invalid} malformed value(s) dropped"
+        );
+        raw.routes = Ok(routes);
+        invalid_route_colors = invalid;
+    }
+    Ok(ParsedGtfs {
+        path: gtfs_path.to_string(),
+        gtfs: gtfs_structures::Gtfs::try_from(raw)?,
+        translation_rows,
+        invalid_route_colors,
+    })
+}
+
+/// A `route_color` / `route_text_color` value as the GTFS parser accepts it: six hex
+/// digits, tolerating a leading `#`. `None` when it cannot be repaired.
+fn normalize_route_color(raw: &str) -> Option<String> {
+    let hex = raw.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    (hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit())).then(|| hex.to_string())
+}
+
+/// `routes.txt` of the feed at `gtfs_path` (a directory or a zip) re-read with its
+/// colour columns passed through [`normalize_route_color`], blanking what cannot be
+/// repaired. Returns the routes and the number of blanked values, or `None` when the
+/// file still does not parse.
+fn read_routes_lenient(gtfs_path: &str) -> Option<(Vec<gtfs_structures::Route>, usize)> {
+    let path = std::path::Path::new(gtfs_path);
+    let bytes = if path.is_dir() {
+        std::fs::read(path.join("routes.txt")).ok()?
+    } else {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path).ok()?).ok()?;
+        let mut bytes = None;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).ok()?;
+            if std::path::Path::new(file.name()).file_name() == Some("routes.txt".as_ref()) {
+                let mut buf = Vec::new();
+                std::io::Read::read_to_end(&mut file, &mut buf).ok()?;
+                bytes = Some(buf);
+                break;
+            }
+        }
+        bytes?
+    };
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::Fields)
+        .from_reader(bytes);
+    let headers: csv::StringRecord = reader.headers().ok()?.iter().map(str::trim).collect();
+    let is_color = |i: usize| matches!(headers.get(i), Some("route_color" | "route_text_color"));
+    let mut invalid = 0;
+    let mut routes = Vec::new();
+    for record in reader.records() {
+        let record: csv::StringRecord = record
+            .ok()?
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if !is_color(i) || v.is_empty() {
+                    return v.to_string();
+                }
+                normalize_route_color(v).unwrap_or_else(|| {
+                    invalid += 1;
+                    String::new()
+                })
+            })
+            .collect();
+        routes.push(record.deserialize(Some(&headers)).ok()?);
+    }
+    Some((routes, invalid))
+}
+
+pub(crate) fn load_gtfs_with_hook<F>(
+    parsed: ParsedGtfs,
+    g: &mut Graph,
+    provider: GtfsProvider,
+    linking: StopLinking,
+    filter: &FeedFilter,
+    bikes_fn: F,
+) -> Result<IngestionReport, gtfs_structures::Error>
+where
+    F: Fn(&gtfs_structures::Trip, RouteType) -> Option<bool>,
+{
+    let ParsedGtfs { path, mut gtfs, translation_rows, invalid_route_colors } = parsed;
+    let gtfs_path = path.as_str();
+    let translations = collect_translations(&translation_rows, &gtfs);
+    if !translations.is_empty() {
+        tracing::info!(
+            "{} translation rows loaded ({})",
+            translation_rows.len(),
+            translations.languages().join(", ")
+        );
+    }
+    let mut report = IngestionReport::new(gtfs_path);
+    filter.apply(&mut gtfs, &mut report);
     preprocess_parent_stations(provider, &mut gtfs.stops, g.station_merge_radius_m());
+    if !g.is_feed_open() {
+        g.begin_feed(gtfs_path, None);
+    }
+    if let Some(info) = gtfs.feed_info.first() {
+        g.set_feed_info_days(info.start_date.map(date_to_days), info.end_date.map(date_to_days));
+    }
+    let namespace = g.open_feed_namespace().map(str::to_string);
+    let ns = namespace.as_deref();
 
     let mut gtfs_nodes_mapper = HashMap::<String, NodeID>::new();
 
-    let mut count_node_no_latlng = 0;
-    let mut count_node_no_name = 0;
     let mut count_node_no_neighbor = 0;
     let mut count_node_too_far_neighbor = 0;
+    let mut too_far_sample: Vec<String> = Vec::new();
 
     let n_stops = gtfs.stops.len();
+    report.stops_total = n_stops;
 
     let mut plat_queries: Vec<PlatQuery> = Vec::new();
 
@@ -303,7 +844,8 @@ where
                 longitude: lng,
             },
             _ => {
-                count_node_no_latlng += 1;
+                report.stops_without_coords += 1;
+                report.warn(IngestionWarningReason::StopWithoutCoords, namespaced_id(ns, &stop_id), None);
                 continue;
             }
         };
@@ -311,7 +853,8 @@ where
         let name = match &raw.name {
             Some(name) => name,
             _ => {
-                count_node_no_name += 1;
+                report.stops_without_name += 1;
+                report.warn(IngestionWarningReason::StopWithoutName, namespaced_id(ns, &stop_id), None);
                 continue;
             }
         };
@@ -320,12 +863,14 @@ where
             name: name.clone(),
             lat_lng: loc,
             accessibility: raw.wheelchair_boarding,
-            id: stop_id.clone(),
+            id: namespaced_id(ns, &stop_id),
             platform_code: raw.platform_code.clone(),
+            code: raw.code.clone(),
             parent_station: raw
                 .parent_station
-                .clone()
-                .filter(|s| !s.is_empty()),
+                .as_deref()
+                .filter(|s| !s.is_empty())
+                .map(|s| namespaced_id(ns, s)),
         };
 
         if raw.parent_station.is_some() {
@@ -340,21 +885,24 @@ where
         let id = g.add_node(transit_stop);
         gtfs_nodes_mapper.insert(stop_id, id);
 
-        let nearest_node_dist = match g.nearest_node_dist(loc.latitude, loc.longitude) {
-            Some(node_dist) => node_dist,
-            _ => {
+        let targets = stop_link_targets(g, loc, linking);
+        let Some(&(nearest_dist, nearest_node)) = targets.first() else {
+            if g.nearest_node_dist(loc.latitude, loc.longitude).is_some() {
+                count_node_too_far_neighbor += 1;
+                if too_far_sample.len() < 5 {
+                    too_far_sample.push(raw.id.clone());
+                }
+                report.warn(
+                    IngestionWarningReason::StopTooFarFromStreet,
+                    namespaced_id(ns, &raw.id),
+                    Some(format!("no street node within {} m", linking.radius_m)),
+                );
+            } else {
                 count_node_no_neighbor += 1;
-                continue;
+                report.warn(IngestionWarningReason::StopWithoutStreet, namespaced_id(ns, &raw.id), None);
             }
-        };
-
-        if nearest_node_dist.0 > MAX_NEIGHBOR_DISTANCE {
-            count_node_too_far_neighbor += 1;
             continue;
-        }
-
-        let nearest_node = *nearest_node_dist.1;
-        let distance = nearest_node_dist.0 as usize;
+        };
 
         if raw.parent_station.is_some()
             && relocate_matched_stop(
@@ -366,18 +914,32 @@ where
                 raw.level_id.as_deref(),
             )
         {
+            report.stops_platform_matched += 1;
             continue;
         }
 
-        g.add_edge(id, foot_connector_edge(id, nearest_node, distance));
-        g.add_edge(nearest_node, foot_connector_edge(nearest_node, id, distance));
+        report.record_link(nearest_dist as usize);
+        for (dist, node) in targets {
+            let distance = dist as usize;
+            g.add_edge(id, foot_connector_edge(id, node, distance));
+            g.add_edge(node, foot_connector_edge(node, id, distance));
+        }
     }
 
+    report.stops_loaded = gtfs_nodes_mapper.len();
+    report.stops_unlinked = count_node_no_neighbor + count_node_too_far_neighbor;
     tracing::info!("{n_stops} stops loaded");
-    tracing::debug!(" - {count_node_no_latlng} without coordinates");
-    tracing::debug!(" - {count_node_no_name} without name");
+    tracing::debug!(" - {} without coordinates", report.stops_without_coords);
+    tracing::debug!(" - {} without name", report.stops_without_name);
     tracing::debug!(" - {count_node_no_neighbor} without street neighbour");
-    tracing::debug!(" - {count_node_too_far_neighbor} too far from any street node");
+    if count_node_too_far_neighbor > 0 {
+        tracing::warn!(
+            "{count_node_too_far_neighbor} stops have no street node within {} m and stay \
+             unlinked (e.g. {}); raise `link_radius_m` if they should be reachable",
+            linking.radius_m,
+            too_far_sample.join(", ")
+        );
+    }
 
     report_platform_match(g, &plat_queries, gtfs_path);
 
@@ -468,13 +1030,28 @@ where
     let mut route_infos: Vec<RouteInfo> = Vec::new();
     let routes_offset = g.get_transit_routes_size();
 
+    report.routes_total = gtfs.routes.len();
+    report.route_colors_invalid = invalid_route_colors;
     for (_, route) in gtfs.routes {
-        let route_id = route_mapper.get_or_insert(route.id);
+        let route_id = route_mapper.get_or_insert(route.id.clone());
 
         let agency_id_str = route.agency_id.unwrap_or("default".to_string());
         let agency_id = match agency_mapper.get(&agency_id_str) {
             Some(v) => AgencyId((v + agencies_offset) as u16),
-            None => continue,
+            None => {
+                report.routes_unmatched_agency += 1;
+                report.warn(
+                    IngestionWarningReason::RouteUnmatchedAgency,
+                    namespaced_id(ns, &route.id),
+                    Some(format!("agency_id {agency_id_str}")),
+                );
+                if report.unmatched_agency_ids.len() < REPORT_MAX_IDS
+                    && !report.unmatched_agency_ids.contains(&agency_id_str)
+                {
+                    report.unmatched_agency_ids.push(agency_id_str);
+                }
+                continue;
+            }
         };
 
         route_infos.resize_with(route_id + 1, || RouteInfo {
@@ -525,15 +1102,32 @@ where
     let mut pattern_trip_data: Vec<Vec<(TripId, Vec<StopTime>)>> = Vec::new();
     let mut pattern_shape_data: Vec<Option<(String, Vec<Option<f32>>)>> = Vec::new();
 
+    report.trips_total = gtfs.trips.len();
     for (_, trip) in gtfs.trips {
         let trip_id = trip_mapper.get_or_insert(trip.id.clone());
         let service_id = match service_mapper.get(&trip.service_id) {
             Some(id) => id,
-            None => continue,
+            None => {
+                report.trips_without_service += 1;
+                report.warn(
+                    IngestionWarningReason::TripWithoutService,
+                    namespaced_id(ns, &trip.id),
+                    Some(format!("service_id {}", trip.service_id)),
+                );
+                continue;
+            }
         };
         let route_id = match route_mapper.get(&trip.route_id) {
             Some(id) => id,
-            None => continue,
+            None => {
+                report.trips_without_route += 1;
+                report.warn(
+                    IngestionWarningReason::TripWithoutRoute,
+                    namespaced_id(ns, &trip.id),
+                    Some(format!("route_id {}", trip.route_id)),
+                );
+                continue;
+            }
         };
 
         trip_infos.resize_with(trip_id + 1, || TripInfo {
@@ -581,8 +1175,15 @@ where
         }
 
         if trip_nodes.len() < 2 {
+            report.trips_too_short += 1;
+            report.warn(
+                IngestionWarningReason::TripTooShort,
+                namespaced_id(ns, &trip.id),
+                Some(format!("{} loaded stop times", trip_nodes.len())),
+            );
             continue;
         }
+        report.trips_loaded += 1;
 
         let global_trip_id = TripId((trip_id + trips_offset) as u32);
         let global_route_id = RouteId((route_id + routes_offset) as u32);
@@ -598,8 +1199,6 @@ where
                 .or_default()
                 .push(TripSegment {
                     trip_id: global_trip_id,
-                    origin_stop_sequence: i as u32,
-                    destination_stop_sequence: (i + 1) as u32,
                     departure: trip_stop_times[i].departure,
                     arrival: trip_stop_times[i + 1].arrival,
                     service_id: global_service_id,
@@ -688,8 +1287,8 @@ where
         trip_segments.sort_unstable_by_key(|ts| ts.departure);
 
         let timetable = TimetableSegment {
-            start: g.get_transit_departures_size(),
-            len: trip_segments.len(),
+            start: g.get_transit_departures_size() as u32,
+            len: trip_segments.len() as u32,
         };
 
         g.add_transit_departures(trip_segments);
@@ -714,17 +1313,38 @@ where
         );
     }
 
-    g.add_transit_trip_ids(trip_mapper.strings().to_vec());
+    g.add_transit_trip_ids(trip_mapper.strings().iter().map(|s| namespaced_id(ns, s)).collect());
     g.add_transit_trips(trip_infos);
-    g.add_transit_route_ids(route_mapper.strings().to_vec());
+    g.add_transit_route_ids(route_mapper.strings().iter().map(|s| namespaced_id(ns, s)).collect());
     g.add_transit_routes(route_infos);
+    g.add_name_translations(translations);
     g.add_transit_services(services);
     g.add_transit_agencies(agencies);
+    g.record_feed_stops(gtfs_nodes_mapper);
 
-    Ok(())
+    Ok(report)
 }
 
-fn foot_connector_edge(origin: NodeID, destination: NodeID, length: usize) -> EdgeData {
+/// Street nodes a stop links to, closest first: the `k` closest walkable nodes within
+/// the radius, else the closest snap-indexed node of any kind within it.
+fn stop_link_targets(g: &Graph, loc: LatLng, linking: StopLinking) -> Vec<(f64, NodeID)> {
+    let walkable = g.nearest_street_nodes(
+        loc.latitude,
+        loc.longitude,
+        StreetProfile::Foot,
+        linking.radius_m,
+        linking.k.max(1),
+    );
+    if !walkable.is_empty() {
+        return walkable;
+    }
+    g.nearest_node_dist(loc.latitude, loc.longitude)
+        .filter(|&(d, _)| d <= linking.radius_m)
+        .map(|(d, &n)| vec![(d, n)])
+        .unwrap_or_default()
+}
+
+pub(crate) fn foot_connector_edge(origin: NodeID, destination: NodeID, length: usize) -> EdgeData {
     EdgeData::Street(StreetEdgeData {
         origin,
         destination,
@@ -976,7 +1596,7 @@ fn report_connector_coverage(g: &Graph, matched: &std::collections::HashSet<usiz
         "reachability-sanity: feed='{feed}' transit_stops={stops} \
          reachable_after={after} reachable_before={before} \
          (coarse accessibility indicator, NOT a non-regression proof; the structural \
-         guarantee is the unchanged snap loop + platform nodes excluded from the snap KD-tree)"
+         guarantee is the unchanged snap loop + platform nodes excluded from the snap index)"
     );
 }
 
@@ -1152,6 +1772,161 @@ pub fn tec_route_is_express(
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn link_distances_fall_into_the_first_bucket_that_fits() {
+        let mut r = IngestionReport::new("f");
+        for m in [0, 5, 6, 1000, 5000] {
+            r.record_link(m);
+        }
+        assert_eq!(r.link_distance_histogram, vec![2, 1, 0, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn malformed_route_colors_are_repaired_or_dropped_instead_of_failing_the_feed() {
+        assert_eq!(normalize_route_color(" #ff0000 ").as_deref(), Some("ff0000"));
+        assert_eq!(normalize_route_color("F00"), None);
+        assert_eq!(normalize_route_color("GG0000"), None);
+
+        let dir = std::env::temp_dir().join(format!("maas-route-colors-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in [
+            ("agency.txt", "agency_id,agency_name,agency_url,agency_timezone\nA,Bus,http://a,Europe/Brussels\n"),
+            ("stops.txt", "stop_id,stop_name,stop_lat,stop_lon\nS1,One,50.85,4.35\nS2,Two,50.86,4.36\n"),
+            (
+                "routes.txt",
+                "\u{feff}route_id,agency_id,route_short_name,route_long_name,route_type,route_color,route_text_color\n\
+                 R1,A,1,Red,3,#FF0000,ZZZ\nR2,A,2,Plain,3,,\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nR1,WK,T1\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T1,08:00:00,08:00:00,S1,1\nT1,08:10:00,08:10:00,S2,2\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+                 WK,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+        ] {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        let parsed = read_gtfs(dir.to_str().unwrap()).unwrap();
+        assert_eq!(parsed.invalid_route_colors, 1);
+        let mut g = Graph::new();
+        let report = load_parsed_gtfs(parsed, &mut g, Default::default(), &FeedFilter::default()).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(report.route_colors_invalid, 1);
+        let red = g.raptor.transit_routes.iter().find(|r| r.route_short_name == "1").unwrap();
+        assert_eq!((red.route_color, red.route_text_color), (Some((255, 0, 0)), None));
+        assert_eq!(g.raptor.transit_routes.len(), 2);
+    }
+
+    #[test]
+    fn skipped_records_are_kept_as_warnings() {
+        let dir = std::env::temp_dir().join(format!("maas-ingest-warnings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in [
+            (
+                "agency.txt",
+                "agency_id,agency_name,agency_url,agency_timezone\nA,A,http://a,Europe/Brussels\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon\n\
+                 S1,One,50.85,4.35\nS2,Two,50.86,4.36\nS3,Nowhere,,\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,agency_id,route_short_name,route_long_name,route_type\nR1,A,1,One,3\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nR1,WK,T1\nR1,GONE,T2\nR1,WK,T3\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T1,08:00:00,08:00:00,S1,1\nT1,08:10:00,08:10:00,S2,2\n\
+                 T2,08:00:00,08:00:00,S1,1\nT2,08:10:00,08:10:00,S2,2\n\
+                 T3,08:00:00,08:00:00,S1,1\nT3,08:10:00,08:10:00,S3,2\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+                 WK,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+        ] {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        let parsed = read_gtfs(dir.to_str().unwrap()).unwrap();
+        let mut g = Graph::new();
+        let report = load_parsed_gtfs(parsed, &mut g, Default::default(), &FeedFilter::default()).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let has = |reason, id: &str| report.warnings.iter().any(|w| w.reason == reason && w.entity_id == id);
+        assert!(has(IngestionWarningReason::StopWithoutCoords, "S3"));
+        assert!(has(IngestionWarningReason::StopWithoutStreet, "S1"), "an empty graph has no street to link to");
+        assert!(has(IngestionWarningReason::TripTooShort, "T3"));
+        let gone = report.warnings.iter().find(|w| w.entity_id == "T2").unwrap();
+        assert_eq!(gone.reason, IngestionWarningReason::TripWithoutService);
+        assert_eq!(gone.detail.as_deref(), Some("service_id GONE"));
+        assert_eq!(report.warnings.len(), 5);
+    }
+
+    #[test]
+    fn feed_filter_keeps_only_the_selected_routes_and_their_stops() {
+        let dir = std::env::temp_dir().join(format!("maas-feed-filter-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in [
+            (
+                "agency.txt",
+                "agency_id,agency_name,agency_url,agency_timezone\n\
+                 T,Trams,http://t,Europe/Brussels\nB,Buses,http://b,Europe/Brussels\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon\n\
+                 S1,One,50.85,4.35\nS2,Two,50.86,4.36\nS3,Bus only,50.87,4.37\nS4,Far,51.5,5.5\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,agency_id,route_short_name,route_long_name,route_type\n\
+                 R1,T,1,Tram,0\nR2,B,2,Bus,3\nR3,T,3,Long tram,0\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nR1,WK,T1\nR2,WK,T2\nR3,WK,T3\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T1,08:00:00,08:00:00,S1,1\nT1,08:10:00,08:10:00,S2,2\n\
+                 T2,08:00:00,08:00:00,S2,1\nT2,08:10:00,08:10:00,S3,2\n\
+                 T3,08:00:00,08:00:00,S1,1\nT3,09:00:00,09:00:00,S4,2\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+                 WK,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+        ] {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        let filter = FeedFilter {
+            route_types: Some(vec![RouteType::Tramway]),
+            agencies: Some(vec!["Trams".to_string()]),
+            bbox: Some([4.0, 50.5, 4.5, 51.0]),
+            ..Default::default()
+        };
+        let parsed = read_gtfs(dir.to_str().unwrap()).unwrap();
+        let mut g = Graph::new();
+        let report = load_parsed_gtfs(parsed, &mut g, Default::default(), &filter).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!((report.routes_filtered, report.trips_filtered), (1, 1));
+        assert_eq!(report.stops_filtered, 2, "S3 serves only the bus, S4 is outside the bbox");
+        assert_eq!((report.stops_loaded, report.trips_loaded, report.trips_too_short), (2, 1, 1));
+        let mut names: Vec<_> =
+            g.raptor.transit_routes.iter().map(|r| r.route_short_name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["1", "3"]);
+    }
     use chrono::NaiveDate;
 
     fn trip_with_bikes(bikes_allowed: Option<bool>) -> TripInfo {
@@ -1163,6 +1938,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stops_link_to_the_k_closest_walkable_nodes_within_the_radius() {
+        use crate::structures::{OsmNodeData, cost::VarGen};
+        let mut g = Graph::new();
+        // East of the stop at 50°N: 0.0001° of longitude is ~7 m.
+        let mut node = |eid: &str, dlng: f64| {
+            g.add_node(NodeData::OsmNode(OsmNodeData {
+                eid: eid.to_string(),
+                lat_lng: LatLng { latitude: 50.0, longitude: 4.0 + dlng },
+            }))
+        };
+        let motorway = node("motorway", 0.0002);
+        let near = node("near", 0.0005);
+        let mid = node("mid", 0.0010);
+        let far = node("far", 0.0200);
+        let street = |origin, destination, foot| {
+            EdgeData::Street(StreetEdgeData {
+                origin,
+                destination,
+                length: 1,
+                partial: false,
+                foot,
+                bike: true,
+                car: true,
+                attrs: BikeAttrs::road_default(),
+                elev_delta: 0,
+                surface_speed: 100,
+                var_gen: VarGen::NONE,
+            })
+        };
+        g.add_edge(motorway, street(motorway, near, false));
+        for (a, b) in [(near, mid), (mid, far), (far, mid)] {
+            g.add_edge(a, street(a, b, true));
+        }
+        let stop = LatLng { latitude: 50.0, longitude: 4.0 };
+        let nodes = |radius_m, k| -> Vec<NodeID> {
+            stop_link_targets(&g, stop, StopLinking { radius_m, k })
+                .into_iter()
+                .map(|(_, n)| n)
+                .collect()
+        };
+
+        assert_eq!(nodes(1000.0, 1), vec![near], "the closer motorway node is not walkable");
+        assert_eq!(nodes(1000.0, 2), vec![near, mid]);
+        assert_eq!(nodes(1000.0, 5), vec![near, mid], "`far` is ~1.4 km out");
+        assert_eq!(nodes(20.0, 2), vec![motorway], "no walkable node in range");
+        assert!(nodes(5.0, 2).is_empty());
+        let lengths: Vec<f64> = stop_link_targets(&g, stop, StopLinking { radius_m: 1000.0, k: 2 })
+            .into_iter()
+            .map(|(d, _)| d)
+            .collect();
+        assert!((lengths[0] - 35.8).abs() < 1.0 && (lengths[1] - 71.6).abs() < 1.0, "{lengths:?}");
+    }
+
+    #[test]
+    fn trip_segment_carries_no_per_hop_stop_order() {
+        // One record per trip per hop: the dominant timetable array, so keep it tight.
+        assert_eq!(std::mem::size_of::<TripSegment>(), 16);
+        // Stored once per transit edge.
+        assert_eq!(std::mem::size_of::<TimetableSegment>(), 8);
+    }
+
+    #[test]
+    fn trip_segments_round_trip_through_columns() {
+        let seg = |t: u32, dep: u32| TripSegment {
+            trip_id: TripId(t),
+            departure: dep,
+            arrival: dep + 60,
+            service_id: ServiceId(t % 2),
+        };
+        let segs: TripSegments = [seg(0, 100), seg(1, 200), seg(2, 300)].into_iter().collect();
+        assert_eq!(segs.departures, vec![100, 200, 300]);
+        assert_eq!(segs.get(1), Some(seg(1, 200)));
+        assert_eq!(segs.get(3), None);
+        let tt = TimetableSegment { start: 1, len: 2 };
+        assert_eq!(segs.of(tt).collect::<Vec<_>>(), vec![seg(1, 200), seg(2, 300)]);
+        assert!(segs.slice(2..4).is_none());
+    }
 
     fn stop_with_parent(id: &str, parent: Option<&str>) -> std::sync::Arc<gtfs_structures::Stop> {
         std::sync::Arc::new(gtfs_structures::Stop {
@@ -1620,6 +2473,56 @@ mod tests {
         assert!(!sp.is_active(250, MON));
     }
 
+    #[test]
+    fn weekday_bit_matches_chrono() {
+        use chrono::Datelike;
+        let d = NaiveDate::from_ymd_opt(2026, 3, 27).unwrap();
+        assert_eq!(weekday_bit(date_to_days(d)), 1 << d.weekday().num_days_from_monday());
+        assert_eq!(weekday_bit(0), SAT);
+    }
+
+    #[test]
+    fn calendar_bits_agree_with_binary_search_check() {
+        let services = vec![
+            weekday_service(),
+            ServicePattern {
+                days_of_week: WEEKDAYS,
+                start_date: 100,
+                end_date: 200,
+                added_dates: vec![300],
+                removed_dates: vec![150, 151],
+            },
+            // Calendar-dates-only service: open range, explicit dates.
+            ServicePattern {
+                days_of_week: 0,
+                start_date: 0,
+                end_date: u32::MAX,
+                added_dates: vec![120, 250],
+                removed_dates: vec![],
+            },
+        ];
+        let cal = ServiceCalendar::build(&services);
+        for (i, sp) in services.iter().enumerate() {
+            for date in 100..=300 {
+                assert_eq!(
+                    cal.is_active(ServiceId(i as u32), date, weekday_bit(date)),
+                    Some(sp.is_active(date, weekday_bit(date))),
+                    "service {i} date {date}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn calendar_defers_outside_its_span_or_on_weekday_mismatch() {
+        let cal = ServiceCalendar::build(&[weekday_service()]);
+        assert_eq!(cal.is_active(ServiceId(0), 99, weekday_bit(99)), None);
+        assert_eq!(cal.is_active(ServiceId(0), 201, weekday_bit(201)), None);
+        assert_eq!(cal.is_active(ServiceId(1), 150, weekday_bit(150)), None);
+        let other_day = weekday_bit(151);
+        assert_eq!(cal.is_active(ServiceId(0), 150, other_day), None);
+    }
+
     fn route_named(short: &str) -> RouteInfo {
         route_named_long(short, "")
     }
@@ -1,9 +1,13 @@
+mod feed_cache;
 mod gtfs;
 mod sncb;
 mod stib;
+mod translations;
 mod utils;
 
+pub use feed_cache::CachedFeed;
 pub use gtfs::*;
-pub use sncb::{build_sncb_operator, load_gtfs_sncb, prepare_sncb};
-pub use stib::{build_time_window_operator, load_gtfs_stib};
+pub use sncb::{build_sncb_operator, load_gtfs_sncb, load_parsed_gtfs_sncb, prepare_sncb};
+pub use stib::{build_time_window_operator, load_gtfs_stib, load_parsed_gtfs_stib};
+pub use translations::*;
 pub use utils::*;
@@ -1,12 +1,16 @@
 use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::BinaryHeap;
+#[cfg(feature = "pbf")]
+use std::collections::{HashMap, HashSet};
 
 use kdtree::{KdTree, distance::squared_euclidean};
+#[cfg(feature = "pbf")]
 use osmpbf::{Element, ElementReader};
 
-use super::load_gtfs_with_hook;
+use super::{FeedFilter, IngestionReport, ParsedGtfs, StopLinking, load_gtfs_with_hook, read_gtfs};
 use crate::structures::{Graph, LatLng, NodeID};
 
+#[cfg_attr(not(feature = "pbf"), allow(dead_code))]
 fn is_railway_way(tags: &[(&str, &str)]) -> bool {
     let railway = tags.iter().find(|t| t.0 == "railway").map(|t| t.1);
     if !matches!(railway, Some("rail" | "light_rail" | "narrow_gauge")) {
@@ -45,7 +49,18 @@ impl RailwayGraph {
         RailwayGraph { nodes, adj, tree }
     }
 
-    fn build(osm_path: &str) -> Result<Self, osmpbf::Error> {
+    #[cfg(feature = "pbf")]
+    fn build(osm_path: &str) -> Result<Self, String> {
+        Self::read_pbf(osm_path).map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(feature = "pbf"))]
+    fn build(osm_path: &str) -> Result<Self, String> {
+        Err(format!("cannot read '{osm_path}': built without the `pbf` feature"))
+    }
+
+    #[cfg(feature = "pbf")]
+    fn read_pbf(osm_path: &str) -> Result<Self, osmpbf::Error> {
         let mut valid_ids: HashSet<i64> = HashSet::new();
         ElementReader::from_path(osm_path)?.for_each(|el| {
             if let Element::Way(w) = el {
@@ -244,7 +259,7 @@ fn compute_railway_shape(
     Some((all_pts, stop_idx, fallback_segments > 0))
 }
 
-pub fn prepare_sncb(osm_path: &str, g: &mut Graph) -> Result<(), osmpbf::Error> {
+pub fn prepare_sncb(osm_path: &str, g: &mut Graph) -> Result<(), String> {
     tracing::info!("caching railway graph from {osm_path}...");
     let railway = RailwayGraph::build(osm_path)?;
     let nodes: Vec<(f64, f64)> = railway.nodes.iter().map(|n| (n.lat, n.lon)).collect();
@@ -268,7 +283,16 @@ pub fn load_gtfs_sncb(
     gtfs_path: &str,
     osm_path: &str,
     g: &mut Graph,
-) -> Result<(), gtfs_structures::Error> {
+) -> Result<IngestionReport, gtfs_structures::Error> {
+    load_parsed_gtfs_sncb(read_gtfs(gtfs_path)?, osm_path, g)
+}
+
+/// [`load_gtfs_sncb`] of a feed already read by [`read_gtfs`].
+pub fn load_parsed_gtfs_sncb(
+    parsed: ParsedGtfs,
+    osm_path: &str,
+    g: &mut Graph,
+) -> Result<IngestionReport, gtfs_structures::Error> {
     let railway = if let Some((nodes, adj)) = g.get_railway_graph_data() {
         tracing::info!("using cached railway graph ({} nodes)", nodes.len());
         RailwayGraph::from_raw(nodes, adj)
@@ -281,9 +305,11 @@ pub fn load_gtfs_sncb(
                     "failed to build railway graph: {e} — falling back to generic GTFS load"
                 );
                 return load_gtfs_with_hook(
-                    gtfs_path,
+                    parsed,
                     g,
                     super::GtfsProvider::Sncb,
+                    StopLinking::default(),
+                    &FeedFilter::default(),
                     |_, _| None,
                 );
             }
@@ -291,9 +317,14 @@ pub fn load_gtfs_sncb(
     };
 
     let patterns_before = g.transit_pattern_count();
-    load_gtfs_with_hook(gtfs_path, g, super::GtfsProvider::Sncb, |trip, _| {
-        sncb_bikes_decision(trip.bikes_allowed)
-    })?;
+    let report = load_gtfs_with_hook(
+        parsed,
+        g,
+        super::GtfsProvider::Sncb,
+        StopLinking::default(),
+        &FeedFilter::default(),
+        |trip, _| sncb_bikes_decision(trip.bikes_allowed),
+    )?;
     let patterns_after = g.transit_pattern_count();
 
     let mut n_computed = 0usize;
@@ -322,7 +353,7 @@ pub fn load_gtfs_sncb(
             "{n_partial} patterns partially mapped — some segments fell back to straight lines"
         );
     }
-    Ok(())
+    Ok(report)
 }
 
 /// Builds the SNCB `OperatorModel` from its `distance_base_per_km` config, plus the
@@ -1,10 +1,26 @@
 use gtfs_structures::RouteType;
 
+use super::{FeedFilter, IngestionReport, ParsedGtfs, StopLinking};
 use crate::structures::Graph;
 
-pub fn load_gtfs_stib(path: &str, g: &mut Graph) -> Result<(), gtfs_structures::Error> {
+pub fn load_gtfs_stib(
+    path: &str,
+    g: &mut Graph,
+    linking: StopLinking,
+    filter: &FeedFilter,
+) -> Result<IngestionReport, gtfs_structures::Error> {
+    load_parsed_gtfs_stib(super::read_gtfs(path)?, g, linking, filter)
+}
+
+/// [`load_gtfs_stib`] of a feed already read by [`super::read_gtfs`].
+pub fn load_parsed_gtfs_stib(
+    parsed: ParsedGtfs,
+    g: &mut Graph,
+    linking: StopLinking,
+    filter: &FeedFilter,
+) -> Result<IngestionReport, gtfs_structures::Error> {
     tracing::info!("applying STIB bike-allowance rules");
-    super::load_gtfs_with_hook(path, g, super::GtfsProvider::Stib, bikes_allowed_stib)
+    super::load_gtfs_with_hook(parsed, g, super::GtfsProvider::Stib, linking, filter, bikes_allowed_stib)
 }
 
 fn bikes_allowed_stib(trip: &gtfs_structures::Trip, route_type: RouteType) -> Option<bool> {
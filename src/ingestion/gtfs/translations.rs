@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use gtfs_structures::{Gtfs, RawTranslation};
+use serde::{Deserialize, Serialize};
+
+/// Index into [`NameTranslations`]' language list; small and `Copy` so plan places
+/// can carry the language chosen for a request down to their lazy name resolvers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LanguageId(pub u16);
+
+/// Which feed text a translation replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameField {
+    StopName,
+    RouteShortName,
+    RouteLongName,
+}
+
+type Translated = HashMap<String, Vec<(LanguageId, String)>>;
+
+/// Per-language stop and route names from GTFS `translations.txt`, keyed by the
+/// feed's own text. Record-id rows are resolved to that text at ingestion, so two
+/// stops sharing a name share its translations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NameTranslations {
+    /// Lower-cased language tags as written in the feeds; `LanguageId` indexes it.
+    languages: Vec<String>,
+    stop_names: Translated,
+    route_short_names: Translated,
+    route_long_names: Translated,
+}
+
+impl NameTranslations {
+    pub fn is_empty(&self) -> bool {
+        self.stop_names.is_empty()
+            && self.route_short_names.is_empty()
+            && self.route_long_names.is_empty()
+    }
+
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    fn language_id(&mut self, tag: &str) -> LanguageId {
+        let tag = tag.trim().to_ascii_lowercase();
+        let idx = match self.languages.iter().position(|l| *l == tag) {
+            Some(i) => i,
+            None => {
+                self.languages.push(tag);
+                self.languages.len() - 1
+            }
+        };
+        LanguageId(idx as u16)
+    }
+
+    fn table_mut(&mut self, field: NameField) -> &mut Translated {
+        match field {
+            NameField::StopName => &mut self.stop_names,
+            NameField::RouteShortName => &mut self.route_short_names,
+            NameField::RouteLongName => &mut self.route_long_names,
+        }
+    }
+
+    fn table(&self, field: NameField) -> &Translated {
+        match field {
+            NameField::StopName => &self.stop_names,
+            NameField::RouteShortName => &self.route_short_names,
+            NameField::RouteLongName => &self.route_long_names,
+        }
+    }
+
+    /// Records `translation` of `original` in `language`; a later row for the same
+    /// text and language replaces the earlier one.
+    pub fn insert(&mut self, field: NameField, original: &str, language: &str, translation: &str) {
+        if original.is_empty() || translation.is_empty() || language.trim().is_empty() {
+            return;
+        }
+        let lang = self.language_id(language);
+        let entries = self.table_mut(field).entry(original.to_string()).or_default();
+        match entries.iter_mut().find(|(l, _)| *l == lang) {
+            Some(entry) => entry.1 = translation.to_string(),
+            None => entries.push((lang, translation.to_string())),
+        }
+    }
+
+    /// Merges another feed's translations in, re-indexing its languages.
+    pub fn extend(&mut self, other: NameTranslations) {
+        for field in [NameField::StopName, NameField::RouteShortName, NameField::RouteLongName] {
+            for (original, entries) in other.table(field) {
+                for (lang, text) in entries {
+                    self.insert(field, original, &other.languages[lang.0 as usize], text);
+                }
+            }
+        }
+    }
+
+    /// Best available language for an `Accept-Language`-style list (`"fr-BE, nl;q=0.8"`):
+    /// tags by descending `q`, each matched exactly, then by primary subtag (`fr-BE`
+    /// accepts `fr` and vice versa). `None` when nothing matches.
+    pub fn match_language(&self, accept: &str) -> Option<LanguageId> {
+        let mut tags: Vec<(f32, String)> = accept
+            .split(',')
+            .filter_map(|part| {
+                let mut pieces = part.split(';');
+                let tag = pieces.next()?.trim().to_ascii_lowercase();
+                let q = pieces
+                    .filter_map(|p| p.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (!tag.is_empty() && tag != "*" && q > 0.0).then_some((q, tag))
+            })
+            .collect();
+        tags.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let primary = |tag: &str| tag.split('-').next().unwrap_or("").to_string();
+        for (_, tag) in &tags {
+            if let Some(i) = self.languages.iter().position(|l| l == tag) {
+                return Some(LanguageId(i as u16));
+            }
+            let p = primary(tag);
+            if let Some(i) = self.languages.iter().position(|l| primary(l) == p) {
+                return Some(LanguageId(i as u16));
+            }
+        }
+        None
+    }
+
+    /// `text` in `language` (or a regional variant sharing its primary subtag), or
+    /// `None` when the feed has no such translation.
+    pub fn translate(&self, field: NameField, text: &str, language: LanguageId) -> Option<&str> {
+        let entries = self.table(field).get(text)?;
+        if let Some((_, t)) = entries.iter().find(|(l, _)| *l == language) {
+            return Some(t);
+        }
+        let primary = |l: LanguageId| self.languages[l.0 as usize].split('-').next();
+        let wanted = primary(language);
+        entries.iter().find(|(l, _)| primary(*l) == wanted).map(|(_, t)| t.as_str())
+    }
+}
+
+/// Stop and route name rows of a feed's `translations.txt`. Rows keyed by
+/// `record_id` are resolved through the feed's own names; other tables and fields
+/// are ignored.
+pub(crate) fn collect_translations(rows: &[RawTranslation], gtfs: &Gtfs) -> NameTranslations {
+    let mut out = NameTranslations::default();
+    for row in rows {
+        let field = match (row.table_name.as_str(), row.field_name.as_str()) {
+            ("stops", "stop_name") => NameField::StopName,
+            ("routes", "route_short_name") => NameField::RouteShortName,
+            ("routes", "route_long_name") => NameField::RouteLongName,
+            _ => continue,
+        };
+        let original = match (&row.field_value, &row.record_id) {
+            (Some(value), _) => Some(value.as_str()),
+            (None, Some(id)) => match field {
+                NameField::StopName => gtfs.stops.get(id).and_then(|s| s.name.as_deref()),
+                NameField::RouteShortName => gtfs.routes.get(id).and_then(|r| r.short_name.as_deref()),
+                NameField::RouteLongName => gtfs.routes.get(id).and_then(|r| r.long_name.as_deref()),
+            },
+            (None, None) => None,
+        };
+        if let Some(original) = original {
+            out.insert(field, original, &row.language, &row.translation);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brussels() -> NameTranslations {
+        let mut t = NameTranslations::default();
+        t.insert(NameField::StopName, "Gare du Midi", "nl", "Zuidstation");
+        t.insert(NameField::StopName, "Gare du Midi", "fr", "Gare du Midi");
+        t.insert(NameField::RouteLongName, "Gare du Nord - Stockel", "nl-BE", "Noordstation - Stokkel");
+        t
+    }
+
+    #[test]
+    fn accept_language_prefers_highest_q_then_primary_subtag() {
+        let t = brussels();
+        let nl = t.match_language("nl").unwrap();
+        assert_eq!(t.languages()[nl.0 as usize], "nl");
+        assert_eq!(t.match_language("de, nl;q=0.5, fr;q=0.9"), t.match_language("fr"));
+        assert_eq!(t.match_language("nl-NL"), Some(nl), "falls back to the primary subtag");
+        let nl_be = t.match_language("NL-be").unwrap();
+        assert_eq!(t.languages()[nl_be.0 as usize], "nl-be");
+        assert_eq!(t.match_language("de, *;q=0.1"), None);
+        assert_eq!(t.match_language("en;q=0, nl;q=0"), None);
+        assert_eq!(
+            t.translate(NameField::RouteLongName, "Gare du Nord - Stockel", nl_be),
+            Some("Noordstation - Stokkel")
+        );
+    }
+
+    #[test]
+    fn translate_misses_return_none() {
+        let t = brussels();
+        let nl = t.match_language("nl").unwrap();
+        assert_eq!(t.translate(NameField::StopName, "Gare du Midi", nl), Some("Zuidstation"));
+        assert_eq!(t.translate(NameField::StopName, "De Brouckère", nl), None);
+        assert_eq!(
+            t.translate(NameField::RouteLongName, "Gare du Nord - Stockel", nl),
+            Some("Noordstation - Stokkel"),
+            "`nl` accepts the `nl-BE` row"
+        );
+        assert_eq!(t.translate(NameField::RouteShortName, "Gare du Midi", nl), None);
+    }
+
+    #[test]
+    fn collect_resolves_record_ids_and_skips_other_fields() {
+        use std::sync::Arc;
+        let mut gtfs = Gtfs::default();
+        let stop = gtfs_structures::Stop { name: Some("Bourse".into()), ..Default::default() };
+        gtfs.stops.insert("S1".into(), Arc::new(stop));
+        let route = gtfs_structures::Route { short_name: Some("T4".into()), ..Default::default() };
+        gtfs.routes.insert("R1".into(), route);
+        let row = |table: &str, field: &str, record: Option<&str>, value: Option<&str>, text: &str| {
+            RawTranslation {
+                table_name: table.into(),
+                field_name: field.into(),
+                language: "nl".into(),
+                translation: text.into(),
+                record_id: record.map(str::to_string),
+                record_sub_id: None,
+                field_value: value.map(str::to_string),
+            }
+        };
+        let rows = [
+            row("stops", "stop_name", Some("S1"), None, "Beurs"),
+            row("stops", "stop_name", None, Some("Gare du Midi"), "Zuidstation"),
+            row("routes", "route_short_name", Some("R1"), None, "T4 NL"),
+            row("stops", "stop_desc", Some("S1"), None, "ignored"),
+            row("stops", "stop_name", Some("missing"), None, "ignored"),
+        ];
+        let t = collect_translations(&rows, &gtfs);
+        let nl = t.match_language("nl").unwrap();
+        assert_eq!(t.translate(NameField::StopName, "Bourse", nl), Some("Beurs"));
+        assert_eq!(t.translate(NameField::StopName, "Gare du Midi", nl), Some("Zuidstation"));
+        assert_eq!(t.translate(NameField::RouteShortName, "T4", nl), Some("T4 NL"));
+        assert_eq!(t.stop_names.len(), 2);
+    }
+
+    #[test]
+    fn extend_reindexes_languages() {
+        let mut a = NameTranslations::default();
+        a.insert(NameField::StopName, "Bruxelles-Central", "de", "Brüssel-Zentral");
+        a.extend(brussels());
+        let nl = a.match_language("nl").unwrap();
+        assert_eq!(nl, LanguageId(1));
+        assert_eq!(a.translate(NameField::StopName, "Gare du Midi", nl), Some("Zuidstation"));
+        let de = a.match_language("de").unwrap();
+        assert_eq!(a.translate(NameField::StopName, "Bruxelles-Central", de), Some("Brüssel-Zentral"));
+    }
+}
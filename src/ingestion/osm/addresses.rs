@@ -0,0 +1,477 @@
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "pbf")]
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+#[cfg(feature = "pbf")]
+use osmpbf::{Element, ElementReader};
+use serde::{Deserialize, Serialize};
+
+use crate::structures::{LatLng, PointIndex, normalize_address as normalize};
+
+#[cfg(feature = "pbf")]
+use super::{ClipArea, platforms::centroid_of};
+
+// A front door is rarely more than a building depth from the street node a plan
+// starts on; past that the nearest address names the wrong house.
+pub const ADDRESS_LABEL_RADIUS_M: f64 = 50.0;
+
+/// One `addr:*`-tagged node or building. `street` indexes [`OsmAddressIndex`]'s
+/// interned street names.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OsmAddress {
+    pub street: u32,
+    pub house_number: String,
+    pub loc: LatLng,
+}
+
+/// A named amenity / shop / tourism / leisure / office feature. `category` is the
+/// tag value (`cafe`, `bakery`, `museum`, …).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OsmPoi {
+    pub name: String,
+    pub category: String,
+    pub loc: LatLng,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(async_graphql::Enum))]
+pub enum GeocodeKind {
+    /// A street and house number.
+    Address,
+    /// A street without a house number in the query, placed at one of its addresses.
+    Street,
+    Poi,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeocodeHit {
+    pub label: String,
+    pub kind: GeocodeKind,
+    pub category: Option<String>,
+    pub loc: LatLng,
+    pub score: f64,
+}
+
+// Same decay shape as BeST-Add search: a hit this far from the focus scores half.
+const GEOCODE_FOCUS_HALF_KM: f64 = 5.0;
+// A query word matching only the start of a name token counts this much of an exact one.
+const GEOCODE_PREFIX_WEIGHT: f64 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Target {
+    Street(u32),
+    Poi(u32),
+}
+
+/// Token → street / POI postings and per-street address lists; derived, so rebuilt
+/// on first search instead of persisted.
+#[derive(Debug, Default)]
+struct SearchIndex {
+    tokens: BTreeMap<String, Vec<Target>>,
+    street_addresses: Vec<Vec<u32>>,
+}
+
+/// Side index over OSM `addr:street` / `addr:housenumber` tags (reverse geocoding)
+/// and, when built with POIs, named features (forward geocoding).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OsmAddressIndex {
+    streets: Vec<String>,
+    addresses: Vec<OsmAddress>,
+    /// Address positions, keyed by their index in `addresses`.
+    tree: PointIndex<u32>,
+    pois: Vec<OsmPoi>,
+    #[serde(skip)]
+    search: OnceLock<SearchIndex>,
+}
+
+impl OsmAddressIndex {
+    /// `(street, house_number, location)` rows; street names are interned.
+    pub fn from_entries(entries: Vec<(String, String, LatLng)>) -> Self {
+        let mut street_ids: HashMap<String, u32> = HashMap::new();
+        let mut streets: Vec<String> = Vec::new();
+        let mut addresses = Vec::with_capacity(entries.len());
+        let mut points = Vec::with_capacity(entries.len());
+        for (street, house_number, loc) in entries {
+            let id = *street_ids.entry(street).or_insert_with_key(|s| {
+                streets.push(s.clone());
+                (streets.len() - 1) as u32
+            });
+            points.push(([loc.latitude, loc.longitude], addresses.len() as u32));
+            addresses.push(OsmAddress { street: id, house_number, loc });
+        }
+        OsmAddressIndex { streets, addresses, tree: PointIndex::bulk_load(points), ..Default::default() }
+    }
+
+    pub fn with_pois(mut self, pois: Vec<OsmPoi>) -> Self {
+        self.pois = pois;
+        self.search = OnceLock::new();
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.pois.is_empty()
+    }
+
+    pub fn poi_count(&self) -> usize {
+        self.pois.len()
+    }
+
+    /// Closest address within `radius_m` of `loc`.
+    pub fn nearest(&self, loc: LatLng, radius_m: f64) -> Option<&OsmAddress> {
+        let (_, &i) = self.tree.nearest_matching(loc.latitude, loc.longitude, radius_m, |_| true)?;
+        Some(&self.addresses[i as usize])
+    }
+
+    /// "Street 12" for the closest address within `radius_m` of `loc`.
+    pub fn label_near(&self, loc: LatLng, radius_m: f64) -> Option<String> {
+        let a = self.nearest(loc, radius_m)?;
+        Some(format!("{} {}", self.streets[a.street as usize], a.house_number))
+    }
+
+    /// Free-text forward geocoding, best first. Every word of `text` must match
+    /// (exactly or as a prefix) a token of the street or POI name; a leading-digit
+    /// token selects the house number. With `focus`, the score halves every
+    /// `GEOCODE_FOCUS_HALF_KM` of distance.
+    pub fn search(&self, text: &str, focus: Option<LatLng>, limit: usize) -> Vec<GeocodeHit> {
+        let norm = normalize(text);
+        let (numbers, words): (Vec<&str>, Vec<&str>) = norm
+            .split(' ')
+            .filter(|t| !t.is_empty())
+            .partition(|t| t.starts_with(|c: char| c.is_ascii_digit()));
+        let Some(seed) = words.iter().max_by_key(|w| w.len()) else {
+            return vec![];
+        };
+        let idx = self.search.get_or_init(|| self.build_search_index());
+
+        let mut targets: Vec<Target> = idx
+            .tokens
+            .range(seed.to_string()..)
+            .take_while(|(k, _)| k.starts_with(seed))
+            .flat_map(|(_, v)| v.iter().copied())
+            .collect();
+        targets.sort_unstable();
+        targets.dedup();
+
+        let nearest_to_focus = |addrs: &[u32]| {
+            addrs.iter().copied().min_by(|&a, &b| {
+                let d = |i: u32| focus.map_or(0.0, |f| f.dist(self.addresses[i as usize].loc));
+                d(a).total_cmp(&d(b))
+            })
+        };
+        let mut hits = Vec::new();
+        for target in targets {
+            match target {
+                Target::Street(s) => {
+                    let street = &self.streets[s as usize];
+                    let Some(text_score) = word_score(&words, street) else { continue };
+                    let addrs = &idx.street_addresses[s as usize];
+                    match numbers.first() {
+                        Some(num) => hits.extend(
+                            addrs
+                                .iter()
+                                .map(|&a| &self.addresses[a as usize])
+                                .filter(|a| normalize(&a.house_number) == *num)
+                                .map(|a| GeocodeHit {
+                                    label: format!("{street} {}", a.house_number),
+                                    kind: GeocodeKind::Address,
+                                    category: None,
+                                    loc: a.loc,
+                                    score: text_score,
+                                }),
+                        ),
+                        None => {
+                            if let Some(a) = nearest_to_focus(addrs) {
+                                hits.push(GeocodeHit {
+                                    label: street.clone(),
+                                    kind: GeocodeKind::Street,
+                                    category: None,
+                                    loc: self.addresses[a as usize].loc,
+                                    score: text_score,
+                                });
+                            }
+                        }
+                    }
+                }
+                Target::Poi(p) => {
+                    let poi = &self.pois[p as usize];
+                    let Some(text_score) = word_score(&words, &poi.name) else { continue };
+                    hits.push(GeocodeHit {
+                        label: poi.name.clone(),
+                        kind: GeocodeKind::Poi,
+                        category: Some(poi.category.clone()),
+                        loc: poi.loc,
+                        score: text_score,
+                    });
+                }
+            }
+        }
+
+        if let Some(f) = focus {
+            for h in &mut hits {
+                h.score *= 0.5f64.powf(f.dist(h.loc) / 1000.0 / GEOCODE_FOCUS_HALF_KM);
+            }
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.label.cmp(&b.label)));
+        hits.truncate(limit);
+        hits
+    }
+
+    fn build_search_index(&self) -> SearchIndex {
+        let mut idx = SearchIndex {
+            street_addresses: vec![Vec::new(); self.streets.len()],
+            ..Default::default()
+        };
+        for (i, a) in self.addresses.iter().enumerate() {
+            idx.street_addresses[a.street as usize].push(i as u32);
+        }
+        let names = self
+            .streets
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s, Target::Street(i as u32)))
+            .chain(self.pois.iter().enumerate().map(|(i, p)| (&p.name, Target::Poi(i as u32))));
+        for (name, target) in names {
+            for token in normalize(name).split(' ').filter(|t| !t.is_empty()) {
+                let postings = idx.tokens.entry(token.to_string()).or_default();
+                if postings.last() != Some(&target) {
+                    postings.push(target);
+                }
+            }
+        }
+        idx
+    }
+}
+
+/// Mean match of `words` against `name`'s tokens (1 exact, `GEOCODE_PREFIX_WEIGHT`
+/// prefix); `None` when a word matches no token.
+fn word_score(words: &[&str], name: &str) -> Option<f64> {
+    let norm = normalize(name);
+    let tokens: Vec<&str> = norm.split(' ').collect();
+    let mut total = 0.0;
+    for w in words {
+        total += if tokens.contains(w) {
+            1.0
+        } else if tokens.iter().any(|t| t.starts_with(w)) {
+            GEOCODE_PREFIX_WEIGHT
+        } else {
+            return None;
+        };
+    }
+    Some(total / words.len().max(1) as f64)
+}
+
+const POI_KEYS: [&str; 7] = ["amenity", "shop", "tourism", "leisure", "office", "healthcare", "historic"];
+
+/// `(name, category)` of a named POI: `name` plus one of `POI_KEYS`.
+pub fn parse_poi_tags(tags: &[(&str, &str)]) -> Option<(String, String)> {
+    let name = tags
+        .iter()
+        .find(|(k, _)| *k == "name")
+        .map(|(_, v)| v.trim())
+        .filter(|v| !v.is_empty())?;
+    let category = POI_KEYS
+        .iter()
+        .find_map(|key| tags.iter().find(|(k, v)| k == key && !v.is_empty() && *v != "no"))
+        .map(|(_, v)| *v)?;
+    Some((name.to_string(), category.to_string()))
+}
+
+// addr:place stands in for addr:street on squares and hamlets without named streets.
+pub fn parse_address_tags(tags: &[(&str, &str)]) -> Option<(String, String)> {
+    let get = |key: &str| {
+        tags.iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.trim())
+            .filter(|v| !v.is_empty())
+    };
+    let number = get("addr:housenumber")?;
+    let street = get("addr:street").or_else(|| get("addr:place"))?;
+    Some((street.to_string(), number.to_string()))
+}
+
+/// Every `addr:*`-tagged node, and every tagged way at its node centroid (buildings),
+/// inside `clip`; named POIs too when `with_pois`.
+#[cfg(feature = "pbf")]
+pub fn build_address_index(
+    osm_path: &str,
+    clip: Option<&ClipArea>,
+    with_pois: bool,
+) -> Result<OsmAddressIndex, osmpbf::Error> {
+    let inside = |lat: f64, lon: f64| clip.is_none_or(|c| c.contains(lat, lon));
+    let mut entries: Vec<(String, String, LatLng)> = Vec::new();
+    let mut pois: Vec<OsmPoi> = Vec::new();
+    // (node refs, (street, number), (name, category)) of tagged ways.
+    type TaggedWay = (Vec<i64>, Option<(String, String)>, Option<(String, String)>);
+    let mut ways: Vec<TaggedWay> = Vec::new();
+    let mut needed: HashSet<i64> = HashSet::new();
+
+    ElementReader::from_path(osm_path)?.for_each(|el| {
+        let (tags, node): (Vec<(&str, &str)>, _) = match &el {
+            Element::DenseNode(n) => (n.tags().collect(), Some((n.lat(), n.lon()))),
+            Element::Node(n) => (n.tags().collect(), Some((n.lat(), n.lon()))),
+            Element::Way(w) => (w.tags().collect(), None),
+            _ => return,
+        };
+        let address = parse_address_tags(&tags);
+        let poi = if with_pois { parse_poi_tags(&tags) } else { None };
+        if address.is_none() && poi.is_none() {
+            return;
+        }
+        match (node, &el) {
+            (Some((lat, lon)), _) if inside(lat, lon) => {
+                let loc = LatLng { latitude: lat, longitude: lon };
+                if let Some((street, number)) = address {
+                    entries.push((street, number, loc));
+                }
+                if let Some((name, category)) = poi {
+                    pois.push(OsmPoi { name, category, loc });
+                }
+            }
+            (None, Element::Way(w)) => {
+                let ids: Vec<i64> = w.refs().collect();
+                needed.extend(ids.iter().copied());
+                ways.push((ids, address, poi));
+            }
+            _ => {}
+        }
+    })?;
+
+    let mut coords: HashMap<i64, (f64, f64)> = HashMap::new();
+    if !ways.is_empty() {
+        ElementReader::from_path(osm_path)?.for_each(|el| match el {
+            Element::DenseNode(n) if needed.contains(&n.id()) => {
+                coords.insert(n.id(), (n.lat(), n.lon()));
+            }
+            Element::Node(n) if needed.contains(&n.id()) => {
+                coords.insert(n.id(), (n.lat(), n.lon()));
+            }
+            _ => {}
+        })?;
+    }
+    for (ids, address, poi) in ways {
+        let Some(loc) = centroid_of(&ids, &coords).filter(|c| inside(c.latitude, c.longitude))
+        else {
+            continue;
+        };
+        if let Some((street, number)) = address {
+            entries.push((street, number, loc));
+        }
+        if let Some((name, category)) = poi {
+            pois.push(OsmPoi { name, category, loc });
+        }
+    }
+
+    let idx = OsmAddressIndex::from_entries(entries).with_pois(pois);
+    tracing::info!(
+        "address index: {} OSM addresses on {} streets, {} POIs",
+        idx.len(),
+        idx.streets.len(),
+        idx.poi_count()
+    );
+    Ok(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ll(lat: f64, lon: f64) -> LatLng {
+        LatLng { latitude: lat, longitude: lon }
+    }
+
+    #[test]
+    fn parse_needs_a_house_number_and_a_street_or_place() {
+        assert_eq!(
+            parse_address_tags(&[("addr:street", "Rue Neuve"), ("addr:housenumber", "12")]),
+            Some(("Rue Neuve".into(), "12".into()))
+        );
+        assert_eq!(
+            parse_address_tags(&[("addr:place", "Grand-Place"), ("addr:housenumber", "1")]),
+            Some(("Grand-Place".into(), "1".into()))
+        );
+        assert_eq!(parse_address_tags(&[("addr:street", "Rue Neuve")]), None);
+        assert_eq!(parse_address_tags(&[("addr:housenumber", "12")]), None);
+        assert_eq!(
+            parse_address_tags(&[("addr:street", " "), ("addr:housenumber", "12")]),
+            None
+        );
+    }
+
+    #[test]
+    fn label_is_the_nearest_address_within_the_radius() {
+        let idx = OsmAddressIndex::from_entries(vec![
+            ("Rue Neuve".into(), "12".into(), ll(50.8500, 4.3550)),
+            ("Rue Neuve".into(), "14".into(), ll(50.8502, 4.3550)),
+            ("Boulevard Anspach".into(), "3".into(), ll(50.8480, 4.3500)),
+        ]);
+        assert_eq!(idx.len(), 3);
+        assert_eq!(idx.streets.len(), 2, "street names are interned");
+        assert_eq!(
+            idx.label_near(ll(50.85005, 4.3550), ADDRESS_LABEL_RADIUS_M).as_deref(),
+            Some("Rue Neuve 12")
+        );
+        // ~180 m from the nearest address.
+        assert_eq!(idx.label_near(ll(50.8490, 4.3570), ADDRESS_LABEL_RADIUS_M), None);
+        assert_eq!(OsmAddressIndex::default().label_near(ll(50.85, 4.355), 1e6), None);
+    }
+
+    fn geocoder() -> OsmAddressIndex {
+        OsmAddressIndex::from_entries(vec![
+            ("Rue Neuve".into(), "12".into(), ll(50.8500, 4.3550)),
+            ("Rue Neuve".into(), "14".into(), ll(50.8502, 4.3550)),
+            ("Rue Neuve".into(), "12".into(), ll(50.4100, 4.4400)),
+            ("Boulevard Anspach".into(), "3".into(), ll(50.8480, 4.3500)),
+        ])
+        .with_pois(vec![OsmPoi {
+            name: "Café Neuvième".into(),
+            category: "cafe".into(),
+            loc: ll(50.8490, 4.3520),
+        }])
+    }
+
+    #[test]
+    fn parse_poi_needs_a_name_and_a_poi_key() {
+        assert_eq!(
+            parse_poi_tags(&[("name", "Musée Magritte"), ("tourism", "museum")]),
+            Some(("Musée Magritte".into(), "museum".into()))
+        );
+        assert_eq!(parse_poi_tags(&[("name", "Rue Neuve"), ("highway", "residential")]), None);
+        assert_eq!(parse_poi_tags(&[("amenity", "bench")]), None);
+    }
+
+    #[test]
+    fn house_number_queries_return_matching_addresses_nearest_the_focus_first() {
+        let idx = geocoder();
+        let brussels = ll(50.85, 4.355);
+        let hits = idx.search("rue neuve 12", Some(brussels), 10);
+        assert_eq!(hits.len(), 2, "both towns' Rue Neuve 12, not 14");
+        assert!(hits.iter().all(|h| h.kind == GeocodeKind::Address && h.label == "Rue Neuve 12"));
+        assert!((hits[0].loc.latitude - 50.85).abs() < 1e-9, "Brussels first");
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn words_match_street_and_poi_tokens_by_prefix_and_accent_free() {
+        let idx = geocoder();
+        let hits = idx.search("neuv", None, 10);
+        let kinds: Vec<(GeocodeKind, &str)> =
+            hits.iter().map(|h| (h.kind, h.label.as_str())).collect();
+        assert_eq!(
+            kinds,
+            vec![(GeocodeKind::Poi, "Café Neuvième"), (GeocodeKind::Street, "Rue Neuve")]
+        );
+        assert_eq!(hits[0].category.as_deref(), Some("cafe"));
+
+        let cafe = idx.search("CAFE neuvieme", None, 10);
+        assert_eq!(cafe.len(), 1);
+        assert_eq!(cafe[0].score, 1.0, "exact tokens after accent folding");
+        assert!(idx.search("rue anspach", None, 10).is_empty(), "every word must match");
+        assert!(idx.search("12", None, 10).is_empty(), "a number alone matches nothing");
+        assert_eq!(idx.search("rue", None, 1).len(), 1);
+    }
+}
@@ -0,0 +1,219 @@
+#[cfg(feature = "pbf")]
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "pbf")]
+use osmpbf::{Element, ElementReader};
+use serde::{Deserialize, Serialize};
+
+use crate::structures::{LatLng, PointIndex};
+
+#[cfg(feature = "pbf")]
+use super::{ClipArea, platforms::centroid_of};
+
+// Shelter, bench and lighting are mapped on or next to the pole; past this they
+// belong to another stop or to the street.
+pub const STOP_AMENITY_RADIUS_M: f64 = 30.0;
+// Toilets are worth a short detour: within this a rider can still see the stop.
+pub const STOP_TOILETS_RADIUS_M: f64 = 150.0;
+
+pub const AMENITY_SHELTER: u8 = 1 << 0;
+pub const AMENITY_BENCH: u8 = 1 << 1;
+pub const AMENITY_TOILETS: u8 = 1 << 2;
+pub const AMENITY_WHEELCHAIR_TOILETS: u8 = 1 << 3;
+pub const AMENITY_LIT: u8 = 1 << 4;
+
+/// Facilities found at or near a transit stop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(async_graphql::SimpleObject))]
+pub struct StopAmenities {
+    /// A shelter or covered platform.
+    pub shelter: bool,
+    pub bench: bool,
+    /// Public toilets within `STOP_TOILETS_RADIUS_M`.
+    pub toilets: bool,
+    /// Some of those toilets are tagged wheelchair-accessible.
+    pub wheelchair_toilets: bool,
+    /// The stop itself is tagged `lit=yes`, or a street lamp stands next to it.
+    pub lit: bool,
+}
+
+/// Side index of OSM features that describe stop facilities, as `AMENITY_*` bits
+/// per point.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StopAmenityIndex {
+    points: Vec<(LatLng, u8)>,
+    tree: PointIndex<u32>,
+}
+
+impl StopAmenityIndex {
+    /// `(location, AMENITY_* bits)` rows; rows without bits are dropped.
+    pub fn from_points(points: Vec<(LatLng, u8)>) -> Self {
+        let points: Vec<(LatLng, u8)> = points.into_iter().filter(|(_, f)| *f != 0).collect();
+        let tree = PointIndex::bulk_load(
+            points.iter().enumerate().map(|(i, (loc, _))| ([loc.latitude, loc.longitude], i as u32)).collect(),
+        );
+        StopAmenityIndex { points, tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// What is mapped around a stop at `loc`: toilets within
+    /// [`STOP_TOILETS_RADIUS_M`], everything else within [`STOP_AMENITY_RADIUS_M`].
+    pub fn amenities_near(&self, loc: LatLng) -> StopAmenities {
+        let mut near = 0u8;
+        for (d, &i) in self.tree.within(loc.latitude, loc.longitude, STOP_TOILETS_RADIUS_M) {
+            let flags = self.points[i as usize].1;
+            near |= if d <= STOP_AMENITY_RADIUS_M {
+                flags
+            } else {
+                flags & (AMENITY_TOILETS | AMENITY_WHEELCHAIR_TOILETS)
+            };
+        }
+        StopAmenities {
+            shelter: near & AMENITY_SHELTER != 0,
+            bench: near & AMENITY_BENCH != 0,
+            toilets: near & AMENITY_TOILETS != 0,
+            wheelchair_toilets: near & AMENITY_WHEELCHAIR_TOILETS != 0,
+            lit: near & AMENITY_LIT != 0,
+        }
+    }
+}
+
+/// `AMENITY_*` bits an OSM element contributes. Shelter and bench come from the
+/// amenity itself or from `shelter=yes` / `bench=yes` on the stop; `lit=yes` only
+/// counts on stop and platform elements, where it describes the waiting area.
+pub fn parse_amenity_tags(tags: &[(&str, &str)]) -> u8 {
+    let get = |key: &str| tags.iter().find(|(k, _)| *k == key).map(|(_, v)| v.trim());
+    let yes = |key: &str| get(key) == Some("yes");
+    let is_stop = matches!(get("public_transport"), Some("platform" | "stop_position"))
+        || matches!(get("highway"), Some("bus_stop" | "platform"))
+        || get("railway") == Some("platform");
+
+    let mut flags = 0u8;
+    if get("amenity") == Some("shelter") || yes("shelter") || (is_stop && yes("covered")) {
+        flags |= AMENITY_SHELTER;
+    }
+    if get("amenity") == Some("bench") || yes("bench") {
+        flags |= AMENITY_BENCH;
+    }
+    if get("amenity") == Some("toilets") || yes("toilets") {
+        flags |= AMENITY_TOILETS;
+        if yes("toilets:wheelchair") || (get("amenity") == Some("toilets") && yes("wheelchair")) {
+            flags |= AMENITY_WHEELCHAIR_TOILETS;
+        }
+    }
+    if (is_stop && yes("lit")) || get("highway") == Some("street_lamp") {
+        flags |= AMENITY_LIT;
+    }
+    flags
+}
+
+/// Every element with stop facility tags inside `clip`: nodes at their position,
+/// ways (shelters, platforms) at their node centroid.
+#[cfg(feature = "pbf")]
+pub fn build_amenity_index(
+    osm_path: &str,
+    clip: Option<&ClipArea>,
+) -> Result<StopAmenityIndex, osmpbf::Error> {
+    let inside = |lat: f64, lon: f64| clip.is_none_or(|c| c.contains(lat, lon));
+    let mut points: Vec<(LatLng, u8)> = Vec::new();
+    let mut ways: Vec<(Vec<i64>, u8)> = Vec::new();
+    let mut needed: HashSet<i64> = HashSet::new();
+
+    ElementReader::from_path(osm_path)?.for_each(|el| {
+        let (tags, node): (Vec<(&str, &str)>, _) = match &el {
+            Element::DenseNode(n) => (n.tags().collect(), Some((n.lat(), n.lon()))),
+            Element::Node(n) => (n.tags().collect(), Some((n.lat(), n.lon()))),
+            Element::Way(w) => (w.tags().collect(), None),
+            _ => return,
+        };
+        let flags = parse_amenity_tags(&tags);
+        if flags == 0 {
+            return;
+        }
+        match (node, &el) {
+            (Some((lat, lon)), _) if inside(lat, lon) => {
+                points.push((LatLng { latitude: lat, longitude: lon }, flags));
+            }
+            (None, Element::Way(w)) => {
+                let ids: Vec<i64> = w.refs().collect();
+                needed.extend(ids.iter().copied());
+                ways.push((ids, flags));
+            }
+            _ => {}
+        }
+    })?;
+
+    let mut coords: HashMap<i64, (f64, f64)> = HashMap::new();
+    if !ways.is_empty() {
+        ElementReader::from_path(osm_path)?.for_each(|el| match el {
+            Element::DenseNode(n) if needed.contains(&n.id()) => {
+                coords.insert(n.id(), (n.lat(), n.lon()));
+            }
+            Element::Node(n) if needed.contains(&n.id()) => {
+                coords.insert(n.id(), (n.lat(), n.lon()));
+            }
+            _ => {}
+        })?;
+    }
+    for (ids, flags) in ways {
+        if let Some(loc) = centroid_of(&ids, &coords).filter(|c| inside(c.latitude, c.longitude)) {
+            points.push((loc, flags));
+        }
+    }
+
+    let idx = StopAmenityIndex::from_points(points);
+    tracing::info!("stop amenity index: {} OSM features", idx.len());
+    Ok(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ll(lat: f64, lon: f64) -> LatLng {
+        LatLng { latitude: lat, longitude: lon }
+    }
+
+    #[test]
+    fn parse_reads_stop_attributes_and_standalone_amenities() {
+        assert_eq!(
+            parse_amenity_tags(&[("highway", "bus_stop"), ("shelter", "yes"), ("bench", "yes"), ("lit", "yes")]),
+            AMENITY_SHELTER | AMENITY_BENCH | AMENITY_LIT
+        );
+        assert_eq!(parse_amenity_tags(&[("amenity", "shelter"), ("shelter_type", "public_transport")]), AMENITY_SHELTER);
+        assert_eq!(
+            parse_amenity_tags(&[("amenity", "toilets"), ("wheelchair", "yes")]),
+            AMENITY_TOILETS | AMENITY_WHEELCHAIR_TOILETS
+        );
+        assert_eq!(parse_amenity_tags(&[("amenity", "toilets"), ("wheelchair", "no")]), AMENITY_TOILETS);
+        assert_eq!(parse_amenity_tags(&[("highway", "residential"), ("lit", "yes")]), 0, "a lit road is not a lit stop");
+        assert_eq!(parse_amenity_tags(&[("highway", "street_lamp")]), AMENITY_LIT);
+        assert_eq!(parse_amenity_tags(&[("highway", "bus_stop"), ("shelter", "no")]), 0);
+    }
+
+    #[test]
+    fn toilets_reach_further_than_stop_furniture() {
+        let stop = ll(50.8500, 4.3550);
+        let idx = StopAmenityIndex::from_points(vec![
+            (ll(50.85005, 4.3550), AMENITY_SHELTER | AMENITY_LIT),
+            // ~100 m away: too far for a bench, close enough for toilets.
+            (ll(50.8509, 4.3550), AMENITY_BENCH | AMENITY_TOILETS | AMENITY_WHEELCHAIR_TOILETS),
+            (ll(50.8520, 4.3550), AMENITY_TOILETS),
+            (ll(50.8500, 4.3551), 0),
+        ]);
+        assert_eq!(idx.len(), 3, "featureless points are dropped");
+        assert_eq!(
+            idx.amenities_near(stop),
+            StopAmenities { shelter: true, bench: false, toilets: true, wheelchair_toilets: true, lit: true }
+        );
+        assert_eq!(idx.amenities_near(ll(50.8600, 4.3550)), StopAmenities::default());
+        assert_eq!(StopAmenityIndex::default().amenities_near(stop), StopAmenities::default());
+    }
+}
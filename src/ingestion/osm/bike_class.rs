@@ -28,31 +28,7 @@ pub fn is_cycle_route_relation(r: &Relation) -> bool {
 }
 
 fn classify_highway(v: Option<&str>) -> HighwayClass {
-    match v {
-        Some("motorway") => HighwayClass::Motorway,
-        Some("motorway_link") => HighwayClass::MotorwayLink,
-        Some("trunk") => HighwayClass::Trunk,
-        Some("trunk_link") => HighwayClass::TrunkLink,
-        Some("primary") => HighwayClass::Primary,
-        Some("primary_link") => HighwayClass::PrimaryLink,
-        Some("secondary") => HighwayClass::Secondary,
-        Some("secondary_link") => HighwayClass::SecondaryLink,
-        Some("tertiary") => HighwayClass::Tertiary,
-        Some("tertiary_link") => HighwayClass::TertiaryLink,
-        Some("unclassified") => HighwayClass::Unclassified,
-        Some("residential") => HighwayClass::Residential,
-        Some("living_street") => HighwayClass::LivingStreet,
-        Some("service") => HighwayClass::Service,
-        Some("cycleway") => HighwayClass::Cycleway,
-        Some("footway") => HighwayClass::Footway,
-        Some("path") => HighwayClass::Path,
-        Some("track") => HighwayClass::Track,
-        Some("bridleway") => HighwayClass::Bridleway,
-        Some("pedestrian") => HighwayClass::Pedestrian,
-        Some("steps") => HighwayClass::Steps,
-        Some("road") => HighwayClass::Road,
-        _ => HighwayClass::Other,
-    }
+    v.and_then(HighwayClass::from_osm_tag).unwrap_or(HighwayClass::Other)
 }
 
 fn classify_surface(v: Option<&str>) -> Surface {
@@ -63,6 +39,29 @@ fn classify_surface(v: Option<&str>) -> Surface {
     }
 }
 
+/// No sidewalk on this way: `sidewalk` itself, else `sidewalk:both`, else both sides
+/// `no`. `separate` counts: the sidewalk is another (footway) edge.
+fn classify_no_sidewalk(
+    sidewalk: Option<&str>,
+    both: Option<&str>,
+    left: Option<&str>,
+    right: Option<&str>,
+) -> bool {
+    let none = |v: Option<&str>| matches!(v, Some("no" | "none" | "separate"));
+    match (sidewalk, both) {
+        (Some(_), _) => none(sidewalk),
+        (None, Some(_)) => none(both),
+        (None, None) => none(left) && none(right),
+    }
+}
+
+fn classify_lit(v: Option<&str>) -> bool {
+    matches!(
+        v,
+        Some("yes" | "24/7" | "automatic" | "limited" | "interval" | "sunset-sunrise" | "dusk-dawn")
+    )
+}
+
 fn any_cycleroute(w: &Way) -> bool {
     ["icn", "ncn", "rcn", "lcn"]
         .iter()
@@ -158,12 +157,20 @@ pub fn classify(w: &Way, forward: bool, in_cycle_route: bool) -> BikeAttrs {
         bikeaccess,
         footaccess: foot_access(w, bikeaccess),
         wrong_way: wrong_way(w, forward),
+        no_sidewalk: classify_no_sidewalk(
+            tag(w, "sidewalk"),
+            tag(w, "sidewalk:both"),
+            tag(w, "sidewalk:left"),
+            tag(w, "sidewalk:right"),
+        ),
+        lit: classify_lit(tag(w, "lit")),
+        escalator: tag(w, "conveying").is_some_and(|v| v != "no"),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::tags_are_cycle_route;
+    use super::{classify_lit, classify_no_sidewalk, tags_are_cycle_route};
 
     fn check(tags: &[(&str, &str)]) -> bool {
         tags_are_cycle_route(tags.iter().copied())
@@ -189,4 +196,17 @@ mod tests {
         assert!(!check(&[("type", "route")]));
         assert!(!check(&[]));
     }
+
+    #[test]
+    fn sidewalk_and_lighting_tags() {
+        assert!(classify_no_sidewalk(Some("no"), None, None, None));
+        assert!(classify_no_sidewalk(Some("separate"), None, None, None));
+        assert!(!classify_no_sidewalk(Some("both"), Some("no"), None, None), "`sidewalk` wins");
+        assert!(classify_no_sidewalk(None, Some("no"), None, None));
+        assert!(!classify_no_sidewalk(None, None, Some("no"), Some("yes")));
+        assert!(classify_no_sidewalk(None, None, Some("no"), Some("none")));
+        assert!(!classify_no_sidewalk(None, None, None, None), "untagged is not penalised");
+        assert!(classify_lit(Some("yes")) && classify_lit(Some("sunset-sunrise")));
+        assert!(!classify_lit(Some("no")) && !classify_lit(None));
+    }
 }
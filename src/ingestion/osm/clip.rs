@@ -0,0 +1,227 @@
+//! Area-of-interest clipping for OSM ingestion. A node outside the area is never
+//! added to the graph, and a way segment touching such a node is dropped, so a
+//! country extract can be cut down to a region without external pre-clipping.
+
+use std::fs;
+
+use serde_json::Value;
+
+use crate::structures::OsmPbfIngestor;
+
+/// One polygon: outer ring first, then holes. Rings are `(lon, lat)` as in GeoJSON.
+type Polygon = Vec<Vec<(f64, f64)>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct ClipArea {
+    /// `[min_lon, min_lat, max_lon, max_lat]`.
+    bbox: Option<[f64; 4]>,
+    polygons: Vec<Polygon>,
+    /// Envelope of `polygons`, checked first to skip the ring scan for far points.
+    envelope: Option<[f64; 4]>,
+}
+
+impl ClipArea {
+    pub fn from_bbox(bbox: [f64; 4]) -> Self {
+        Self {
+            bbox: Some(bbox),
+            ..Default::default()
+        }
+    }
+
+    /// Parse a GeoJSON `Polygon`, `MultiPolygon`, `Feature` or `FeatureCollection`.
+    /// Every polygon found is part of the area (their union).
+    pub fn from_geojson(text: &str) -> Result<Self, String> {
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| format!("invalid GeoJSON: {e}"))?;
+        let mut polygons = Vec::new();
+        collect_polygons(&value, &mut polygons)?;
+        if polygons.is_empty() {
+            return Err("GeoJSON boundary contains no Polygon or MultiPolygon".into());
+        }
+        let envelope = polygons
+            .iter()
+            .flat_map(|p| p.first().into_iter().flatten())
+            .fold(None, |acc: Option<[f64; 4]>, &(lon, lat)| {
+                Some(match acc {
+                    None => [lon, lat, lon, lat],
+                    Some([a, b, c, d]) => [a.min(lon), b.min(lat), c.max(lon), d.max(lat)],
+                })
+            });
+        Ok(Self {
+            bbox: None,
+            polygons,
+            envelope,
+        })
+    }
+
+    /// Build the clip area configured on an `osm/pbf` input, or `None` when the input
+    /// sets neither `bbox` nor `boundary_geojson`. With both, a node must be inside both.
+    pub fn from_ingestor(c: &OsmPbfIngestor) -> Result<Option<Self>, String> {
+        let mut area = match &c.boundary_geojson {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("failed to read boundary '{path}': {e}"))?;
+                Self::from_geojson(&text).map_err(|e| format!("boundary '{path}': {e}"))?
+            }
+            None => match c.bbox {
+                Some(_) => Self::default(),
+                None => return Ok(None),
+            },
+        };
+        area.bbox = c.bbox;
+        Ok(Some(area))
+    }
+
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        if let Some(b) = self.bbox
+            && !in_box(b, lat, lon)
+        {
+            return false;
+        }
+        if self.polygons.is_empty() {
+            return true;
+        }
+        if let Some(e) = self.envelope
+            && !in_box(e, lat, lon)
+        {
+            return false;
+        }
+        self.polygons.iter().any(|p| polygon_contains(p, lat, lon))
+    }
+}
+
+fn in_box(b: [f64; 4], lat: f64, lon: f64) -> bool {
+    lon >= b[0] && lat >= b[1] && lon <= b[2] && lat <= b[3]
+}
+
+/// Even-odd ray casting over all rings, so holes fall out naturally.
+fn polygon_contains(rings: &Polygon, lat: f64, lon: f64) -> bool {
+    let mut inside = false;
+    for ring in rings {
+        let n = ring.len();
+        if n < 3 {
+            continue;
+        }
+        let mut j = n - 1;
+        for i in 0..n {
+            let (xi, yi) = ring[i];
+            let (xj, yj) = ring[j];
+            if (yi > lat) != (yj > lat) && lon < (xj - xi) * (lat - yi) / (yj - yi) + xi {
+                inside = !inside;
+            }
+            j = i;
+        }
+    }
+    inside
+}
+
+fn parse_ring(v: &Value) -> Result<Vec<(f64, f64)>, String> {
+    v.as_array()
+        .ok_or("ring is not an array")?
+        .iter()
+        .map(|pt| {
+            let pt = pt.as_array().ok_or("position is not an array")?;
+            match (pt.first().and_then(Value::as_f64), pt.get(1).and_then(Value::as_f64)) {
+                (Some(lon), Some(lat)) => Ok((lon, lat)),
+                _ => Err("position must hold [lon, lat] numbers".to_string()),
+            }
+        })
+        .collect()
+}
+
+fn parse_polygon(v: &Value) -> Result<Polygon, String> {
+    v.as_array()
+        .ok_or("polygon coordinates are not an array")?
+        .iter()
+        .map(parse_ring)
+        .collect()
+}
+
+fn collect_polygons(v: &Value, out: &mut Vec<Polygon>) -> Result<(), String> {
+    match v.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => {
+            for f in v.get("features").and_then(Value::as_array).into_iter().flatten() {
+                collect_polygons(f, out)?;
+            }
+        }
+        Some("Feature") => {
+            if let Some(geom) = v.get("geometry") {
+                collect_polygons(geom, out)?;
+            }
+        }
+        Some("Polygon") => {
+            out.push(parse_polygon(v.get("coordinates").unwrap_or(&Value::Null))?);
+        }
+        Some("MultiPolygon") => {
+            let polys = v
+                .get("coordinates")
+                .and_then(Value::as_array)
+                .ok_or("MultiPolygon coordinates are not an array")?;
+            for p in polys {
+                out.push(parse_polygon(p)?);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE_WITH_HOLE: &str = r#"{
+        "type": "Feature",
+        "properties": {},
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [
+                [[4.0, 50.0], [5.0, 50.0], [5.0, 51.0], [4.0, 51.0], [4.0, 50.0]],
+                [[4.4, 50.4], [4.6, 50.4], [4.6, 50.6], [4.4, 50.6], [4.4, 50.4]]
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn bbox_contains_inside_and_rejects_outside() {
+        let area = ClipArea::from_bbox([4.0, 50.0, 5.0, 51.0]);
+        assert!(area.contains(50.5, 4.5));
+        assert!(!area.contains(51.5, 4.5));
+        assert!(!area.contains(50.5, 3.9));
+    }
+
+    #[test]
+    fn polygon_hole_is_excluded() {
+        let area = ClipArea::from_geojson(SQUARE_WITH_HOLE).unwrap();
+        assert!(area.contains(50.2, 4.2));
+        assert!(!area.contains(50.5, 4.5), "point in the hole must be outside");
+        assert!(!area.contains(52.0, 4.5));
+    }
+
+    #[test]
+    fn multipolygon_is_a_union() {
+        let gj = r#"{"type":"MultiPolygon","coordinates":[
+            [[[0,0],[1,0],[1,1],[0,1],[0,0]]],
+            [[[10,10],[11,10],[11,11],[10,11],[10,10]]]
+        ]}"#;
+        let area = ClipArea::from_geojson(gj).unwrap();
+        assert!(area.contains(0.5, 0.5));
+        assert!(area.contains(10.5, 10.5));
+        assert!(!area.contains(5.0, 5.0));
+    }
+
+    #[test]
+    fn bbox_and_polygon_intersect() {
+        let mut area = ClipArea::from_geojson(SQUARE_WITH_HOLE).unwrap();
+        area.bbox = Some([4.0, 50.0, 4.3, 51.0]);
+        assert!(area.contains(50.2, 4.2));
+        assert!(!area.contains(50.2, 4.8), "inside the polygon but outside the bbox");
+    }
+
+    #[test]
+    fn geojson_without_polygons_is_rejected() {
+        let gj = r#"{"type":"Point","coordinates":[4.0,50.0]}"#;
+        assert!(ClipArea::from_geojson(gj).is_err());
+        assert!(ClipArea::from_geojson("not json").is_err());
+    }
+}
@@ -1,10 +1,22 @@
+mod addresses;
+mod amenities;
+#[cfg(feature = "pbf")]
 mod bike_class;
+mod clip;
 pub mod elevation;
+#[cfg_attr(not(feature = "pbf"), allow(dead_code))]
 mod elevation_smooth;
 mod lambert;
+mod parking;
+#[cfg(feature = "pbf")]
 mod pbf;
 mod platforms;
 
+pub use addresses::*;
+pub use amenities::*;
+pub use clip::ClipArea;
 pub use elevation::{Dem, DemProjection, DemSet, ElevationSource};
+pub use parking::*;
+#[cfg(feature = "pbf")]
 pub use pbf::*;
 pub use platforms::*;
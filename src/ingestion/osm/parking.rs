@@ -0,0 +1,225 @@
+#[cfg(feature = "pbf")]
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "pbf")]
+use osmpbf::{Element, ElementReader};
+use serde::{Deserialize, Serialize};
+
+use crate::structures::{LatLng, PointIndex};
+
+#[cfg(feature = "pbf")]
+use super::{ClipArea, platforms::centroid_of};
+
+// A car park this close to a stop is one a park-and-ride driver would use; further
+// out the walk to the platform is no longer the short hop the access leg assumes.
+pub const PARK_RIDE_RADIUS_M: f64 = 300.0;
+// Extra access seconds on a car-access stop with no car park of known capacity within
+// `PARK_RIDE_RADIUS_M`: the time a driver may spend looking for a space.
+pub const PARK_RIDE_UNKNOWN_CAPACITY_SECS: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(async_graphql::Enum))]
+pub enum ParkingKind {
+    /// `amenity=parking`.
+    Car,
+    /// `amenity=bicycle_parking`.
+    Bicycle,
+}
+
+/// One OSM parking facility: a node at its position, an area at its node centroid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParkingFacility {
+    pub loc: LatLng,
+    pub kind: ParkingKind,
+    pub name: Option<String>,
+    /// Spaces (car) or stands (bicycle), from `capacity`.
+    pub capacity: Option<u32>,
+    /// `fee=yes` / `fee=no`; `None` when untagged or conditional.
+    pub fee: Option<bool>,
+    /// Tagged `park_ride=*` (anything but `no`).
+    pub park_ride: bool,
+}
+
+/// Side index of OSM car and bicycle parking facilities.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ParkingIndex {
+    facilities: Vec<ParkingFacility>,
+    tree: PointIndex<u32>,
+}
+
+impl ParkingIndex {
+    pub fn from_facilities(facilities: Vec<ParkingFacility>) -> Self {
+        let tree = PointIndex::bulk_load(
+            facilities.iter().enumerate().map(|(i, f)| ([f.loc.latitude, f.loc.longitude], i as u32)).collect(),
+        );
+        ParkingIndex { facilities, tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.facilities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.facilities.is_empty()
+    }
+
+    /// Facilities within `radius_m` of `loc` (of `kind` when given), closest first,
+    /// with their distance in meters.
+    pub fn near(
+        &self,
+        loc: LatLng,
+        radius_m: f64,
+        kind: Option<ParkingKind>,
+    ) -> Vec<(f64, &ParkingFacility)> {
+        self.tree
+            .within(loc.latitude, loc.longitude, radius_m)
+            .into_iter()
+            .map(|(d, &i)| (d, &self.facilities[i as usize]))
+            .filter(|(_, f)| kind.is_none_or(|k| f.kind == k))
+            .collect()
+    }
+
+    /// Whether a car park with a tagged, non-zero capacity lies within `radius_m`.
+    pub fn known_car_capacity_near(&self, loc: LatLng, radius_m: f64) -> bool {
+        self.near(loc, radius_m, Some(ParkingKind::Car))
+            .iter()
+            .any(|(_, f)| f.capacity.is_some_and(|c| c > 0))
+    }
+}
+
+/// The facility an OSM element describes, placed at `loc`; `None` for anything but
+/// public car or bicycle parking.
+pub fn parse_parking_tags(tags: &[(&str, &str)], loc: LatLng) -> Option<ParkingFacility> {
+    let get = |key: &str| tags.iter().find(|(k, _)| *k == key).map(|(_, v)| v.trim());
+    let kind = match get("amenity")? {
+        "parking" => ParkingKind::Car,
+        "bicycle_parking" => ParkingKind::Bicycle,
+        _ => return None,
+    };
+    if matches!(get("access"), Some("private" | "no")) {
+        return None;
+    }
+    let name = get("name").filter(|n| !n.is_empty()).map(str::to_string);
+    let capacity = get("capacity").and_then(|c| c.parse::<u32>().ok());
+    let fee = match get("fee") {
+        Some("yes") => Some(true),
+        Some("no") => Some(false),
+        _ => None,
+    };
+    let park_ride = get("park_ride").is_some_and(|v| v != "no");
+    Some(ParkingFacility { loc, kind, name, capacity, fee, park_ride })
+}
+
+/// Every public parking facility inside `clip`.
+#[cfg(feature = "pbf")]
+pub fn build_parking_index(
+    osm_path: &str,
+    clip: Option<&ClipArea>,
+) -> Result<ParkingIndex, osmpbf::Error> {
+    let inside = |lat: f64, lon: f64| clip.is_none_or(|c| c.contains(lat, lon));
+    let mut facilities: Vec<ParkingFacility> = Vec::new();
+    // Areas are placed once their node centroid is known.
+    let mut ways: Vec<(Vec<i64>, ParkingFacility)> = Vec::new();
+    let mut needed: HashSet<i64> = HashSet::new();
+
+    ElementReader::from_path(osm_path)?.for_each(|el| {
+        let (tags, node): (Vec<(&str, &str)>, _) = match &el {
+            Element::DenseNode(n) => (n.tags().collect(), Some((n.lat(), n.lon()))),
+            Element::Node(n) => (n.tags().collect(), Some((n.lat(), n.lon()))),
+            Element::Way(w) => (w.tags().collect(), None),
+            _ => return,
+        };
+        let (lat, lon) = node.unwrap_or((0.0, 0.0));
+        let Some(facility) = parse_parking_tags(&tags, LatLng { latitude: lat, longitude: lon }) else {
+            return;
+        };
+        match (node, &el) {
+            (Some(_), _) if inside(lat, lon) => facilities.push(facility),
+            (None, Element::Way(w)) => {
+                let ids: Vec<i64> = w.refs().collect();
+                needed.extend(ids.iter().copied());
+                ways.push((ids, facility));
+            }
+            _ => {}
+        }
+    })?;
+
+    let mut coords: HashMap<i64, (f64, f64)> = HashMap::new();
+    if !ways.is_empty() {
+        ElementReader::from_path(osm_path)?.for_each(|el| match el {
+            Element::DenseNode(n) if needed.contains(&n.id()) => {
+                coords.insert(n.id(), (n.lat(), n.lon()));
+            }
+            Element::Node(n) if needed.contains(&n.id()) => {
+                coords.insert(n.id(), (n.lat(), n.lon()));
+            }
+            _ => {}
+        })?;
+    }
+    for (ids, mut facility) in ways {
+        if let Some(loc) = centroid_of(&ids, &coords).filter(|c| inside(c.latitude, c.longitude)) {
+            facility.loc = loc;
+            facilities.push(facility);
+        }
+    }
+
+    let idx = ParkingIndex::from_facilities(facilities);
+    tracing::info!("parking index: {} OSM facilities", idx.len());
+    Ok(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ll(lat: f64, lon: f64) -> LatLng {
+        LatLng { latitude: lat, longitude: lon }
+    }
+
+    fn car(loc: LatLng, capacity: Option<u32>) -> ParkingFacility {
+        ParkingFacility { loc, kind: ParkingKind::Car, name: None, capacity, fee: None, park_ride: false }
+    }
+
+    #[test]
+    fn parse_reads_capacity_fee_and_park_ride() {
+        let at = ll(50.0, 4.0);
+        let pr = parse_parking_tags(
+            &[
+                ("amenity", "parking"),
+                ("capacity", "250"),
+                ("fee", "yes"),
+                ("park_ride", "train"),
+                ("name", "P+R Ceria"),
+            ],
+            at,
+        )
+        .unwrap();
+        assert_eq!(pr.kind, ParkingKind::Car);
+        assert_eq!(pr.name.as_deref(), Some("P+R Ceria"));
+        assert_eq!((pr.capacity, pr.fee, pr.park_ride), (Some(250), Some(true), true));
+        let bikes = parse_parking_tags(&[("amenity", "bicycle_parking"), ("capacity", "many"), ("fee", "Mo-Fr")], at)
+            .unwrap();
+        assert_eq!((bikes.kind, bikes.capacity, bikes.fee, bikes.park_ride), (ParkingKind::Bicycle, None, None, false));
+        assert!(parse_parking_tags(&[("amenity", "parking"), ("access", "private")], at).is_none());
+        assert!(parse_parking_tags(&[("amenity", "bench")], at).is_none());
+    }
+
+    #[test]
+    fn known_capacity_only_counts_nearby_car_parks() {
+        let stop = ll(50.8500, 4.3550);
+        let mut bikes = car(ll(50.85001, 4.3550), Some(40));
+        bikes.kind = ParkingKind::Bicycle;
+        let idx = ParkingIndex::from_facilities(vec![
+            car(ll(50.8502, 4.3550), None),
+            bikes,
+            // ~560 m away.
+            car(ll(50.8550, 4.3550), Some(300)),
+        ]);
+        assert!(!idx.known_car_capacity_near(stop, PARK_RIDE_RADIUS_M));
+        assert!(idx.known_car_capacity_near(stop, 1000.0));
+        let near = idx.near(stop, PARK_RIDE_RADIUS_M, None);
+        assert_eq!(near.len(), 2);
+        assert_eq!(near[0].1.kind, ParkingKind::Bicycle, "closest first");
+        assert_eq!(idx.near(stop, PARK_RIDE_RADIUS_M, Some(ParkingKind::Car)).len(), 1);
+    }
+}
@@ -6,12 +6,13 @@ use std::{
 use osmpbf::{Element, ElementReader, RelMemberType, Way};
 
 use crate::ingestion::osm::{
-    ElevationSource, bike_class, build_platform_index, effective_highway, elevation_smooth,
+    ClipArea, ElevationSource, bike_class, build_platform_index, effective_highway, elevation_smooth,
     is_platform_way, parse_connector, parse_way_level,
 };
 use crate::structures::cost::VarGen;
 use crate::structures::{
-    BikeAttrs, Connector, EdgeData, Graph, NodeData, NodeID, OsmNodeData, StreetEdgeData,
+    BikeAttrs, Connector, EdgeData, Graph, NodeData, NodeID, OSM_EID_SOURCE, OsmNodeData,
+    StreetEdgeData,
 };
 
 fn node_var_gen<'a>(tags: impl Iterator<Item = (&'a str, &'a str)>) -> VarGen {
@@ -34,6 +35,29 @@ pub fn load_pbf_file(
     smoothing_epsilon: f64,
     surface_speed_factors: &crate::structures::SurfaceSpeedFactors,
     g: &mut Graph,
+) -> result::Result<(), osmpbf::Error> {
+    load_pbf_file_clipped(pbf_path, dem, smoothing_epsilon, surface_speed_factors, None, g)
+}
+
+/// The extract's header replication timestamp (unix seconds): how current its data is.
+/// Only the leading header blob is read.
+pub fn pbf_replication_timestamp(pbf_path: &str) -> Option<i64> {
+    let mut blobs = osmpbf::BlobReader::from_path(pbf_path).ok()?;
+    match blobs.next()?.ok()?.decode().ok()? {
+        osmpbf::BlobDecode::OsmHeader(h) => h.osmosis_replication_timestamp(),
+        _ => None,
+    }
+}
+
+/// [`load_pbf_file`] restricted to `clip`: nodes outside the area are never added and
+/// way segments touching them are dropped (counted separately from failed inserts).
+pub fn load_pbf_file_clipped(
+    pbf_path: &str,
+    dem: Option<&dyn ElevationSource>,
+    smoothing_epsilon: f64,
+    surface_speed_factors: &crate::structures::SurfaceSpeedFactors,
+    clip: Option<&ClipArea>,
+    g: &mut Graph,
 ) -> result::Result<(), osmpbf::Error> {
     let reader = ElementReader::from_path(pbf_path)?;
     let mut street_node_ids: HashSet<i64> = HashSet::new();
@@ -104,12 +128,23 @@ pub fn load_pbf_file(
 
     let reader = ElementReader::from_path(pbf_path)?;
     let mut node_vargen: HashMap<i64, VarGen> = HashMap::new();
+    let mut clipped_node_ids: HashSet<i64> = HashSet::new();
     reader.for_each(|element| {
         let (id, lat, lon, vg) = match element {
             Element::DenseNode(n) => (n.id(), n.lat(), n.lon(), node_var_gen(n.tags())),
             Element::Node(n) => (n.id(), n.lat(), n.lon(), node_var_gen(n.tags())),
             _ => return,
         };
+        if let Some(area) = clip
+            && !area.contains(lat, lon)
+        {
+            // Only nodes a street or platform way goes through: the way pass checks
+            // no others, and every untagged node of a country extract would not fit.
+            if street_node_ids.contains(&id) || platform_only_node_ids.contains(&id) {
+                clipped_node_ids.insert(id);
+            }
+            return;
+        }
         if street_node_ids.contains(&id) {
             add_osm_node(g, id, lat, lon, true);
             if vg != VarGen::NONE {
@@ -128,8 +163,10 @@ pub fn load_pbf_file(
     let mut failed = 0;
     let mut n_cycleroute = 0;
     let mut n_platform = 0;
+    let mut n_clipped = 0;
     let mut osm_levels: HashMap<i64, i16> = HashMap::new();
     let mut osm_connectors: HashMap<(i64, i64), Connector> = HashMap::new();
+    let mut osm_elevators: HashMap<(i64, i64), String> = HashMap::new();
 
     reader.for_each(|element| {
         let Element::Way(w) = element else { return };
@@ -141,6 +178,10 @@ pub fn load_pbf_file(
         }
 
         let node_ids = w.refs().collect::<Vec<_>>();
+        if !clipped_node_ids.is_empty() && node_ids.iter().all(|id| clipped_node_ids.contains(id)) {
+            n_clipped += node_ids.len().saturating_sub(1);
+            return;
+        }
 
         if let Some(lvl) = parse_way_level(&tags) {
             for &id in &node_ids {
@@ -155,7 +196,7 @@ pub fn load_pbf_file(
             (
                 true,
                 false,
-                false,
+                (false, false),
                 BikeAttrs::road_default(),
                 BikeAttrs::road_default(),
                 100u8,
@@ -170,10 +211,7 @@ pub fn load_pbf_file(
                 .iter()
                 .find(|t| t.0 == "bicycle")
                 .is_none_or(|t| t.1 != "no");
-            let car = tags
-                .iter()
-                .find(|t| t.0 == "motorcar")
-                .is_none_or(|t| t.1 != "no");
+            let car = car_directions(&tags);
             let in_cycle_route = cycle_route_ways.contains(&w.id());
             let attrs_fwd = bike_class::classify(&w, true, in_cycle_route);
             let attrs_rev = bike_class::classify(&w, false, in_cycle_route);
@@ -185,6 +223,10 @@ pub fn load_pbf_file(
         };
 
         for i in 0..node_ids.len().saturating_sub(1) {
+            if clipped_node_ids.contains(&node_ids[i]) || clipped_node_ids.contains(&node_ids[i + 1]) {
+                n_clipped += 1;
+                continue;
+            }
             n += 1;
             if is_plat && !is_street {
                 n_platform += 1;
@@ -192,10 +234,26 @@ pub fn load_pbf_file(
             if attrs_fwd.cycleroute {
                 n_cycleroute += 1;
             }
+            // A segment into a `highway=elevator` node rides that elevator unless the
+            // way itself is a connector.
+            let elevator_node = [node_ids[i], node_ids[i + 1]].into_iter().find(|id| {
+                node_vargen.get(id).is_some_and(|vg| vg.contains(VarGen::ELEVATOR))
+            });
+            let (connector, elevator) = match (connector, elevator_node) {
+                (Some(Connector::Elevator), _) => {
+                    (Some(Connector::Elevator), Some(format!("way/{}", w.id())))
+                }
+                (None, Some(node)) => (Some(Connector::Elevator), Some(format!("node/{node}"))),
+                (kind, _) => (kind, None),
+            };
             if let Some(kind) = connector {
                 osm_connectors.insert((node_ids[i], node_ids[i + 1]), kind);
                 osm_connectors.insert((node_ids[i + 1], node_ids[i]), kind);
             }
+            if let Some(id) = elevator {
+                osm_elevators.insert((node_ids[i], node_ids[i + 1]), id.clone());
+                osm_elevators.insert((node_ids[i + 1], node_ids[i]), id);
+            }
 
             let seg_vg = node_vargen
                 .get(&node_ids[i])
@@ -234,12 +292,19 @@ pub fn load_pbf_file(
         "imported {} / {} edges ({}%) - ({}% cycleroutes, {} platform segments)",
         n - failed,
         n,
-        (n - failed) * 100 / n,
+        (n - failed) * 100 / n.max(1),
         cycleroute_rate * 100.0,
         n_platform
     );
+    if clip.is_some() {
+        tracing::info!(
+            "clipped {} way nodes and {} way segments outside the area of interest",
+            clipped_node_ids.len(),
+            n_clipped
+        );
+    }
 
-    let to_nid = |id: i64| g.get_id(&format!("map#osm#{id}")).copied();
+    let to_nid = |id: i64| g.get_osm_id(id);
     let node_levels: HashMap<NodeID, i16> = osm_levels
         .into_iter()
         .filter_map(|(id, lvl)| to_nid(id).map(|n| (n, lvl)))
@@ -248,12 +313,18 @@ pub fn load_pbf_file(
         .into_iter()
         .filter_map(|((a, b), k)| Some(((to_nid(a)?, to_nid(b)?), k)))
         .collect();
+    let elevator_edges: HashMap<(NodeID, NodeID), String> = osm_elevators
+        .into_iter()
+        .filter_map(|((a, b), id)| Some(((to_nid(a)?, to_nid(b)?), id)))
+        .collect();
     tracing::info!(
-        "osm level/connector data: {} leveled nodes, {} connector edges",
+        "osm level/connector data: {} leveled nodes, {} connector edges ({} elevator)",
         node_levels.len(),
-        connector_edges.len()
+        connector_edges.len(),
+        elevator_edges.len()
     );
     g.set_osm_level_data(node_levels, connector_edges);
+    g.set_elevator_edges(elevator_edges);
 
     g.set_platform_index(build_platform_index(pbf_path, g)?);
 
@@ -283,8 +354,7 @@ fn smoothed_segment_deltas(
     let mut prev_loc: Option<crate::structures::LatLng> = None;
     let mut all_sampled = true;
     for id in node_ids {
-        let eid = format!("map#osm#{}", id);
-        let loc = g.get_id(eid.as_str()).and_then(|nid| g.get_node(*nid)).map(|nd| nd.loc());
+        let loc = g.get_osm_id(*id).and_then(|nid| g.get_node(nid)).map(|nd| nd.loc());
         let Some(loc) = loc else {
             all_sampled = false;
             break;
@@ -319,7 +389,7 @@ fn smoothed_segment_deltas(
 }
 
 fn add_osm_node(g: &mut Graph, id: i64, lat: f64, lon: f64, indexed: bool) {
-    let eid = format!("map#osm#{}", id);
+    let eid = format!("{OSM_EID_SOURCE}#{id}");
     let node = OsmNodeData {
         eid,
         lat_lng: crate::structures::LatLng {
@@ -380,7 +450,7 @@ fn validate_way_tags(tags: &[(&str, &str)]) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{add_osm_node, insert_from_osm_ids, validate_way_tags};
+    use super::{add_osm_node, car_directions, insert_from_osm_ids, validate_way_tags};
     use crate::ingestion::osm::{ConnectorCost, is_platform_way, parse_connector, parse_way_level};
     use crate::structures::cost::VarGen;
     use crate::structures::{BikeAttrs, Connector, Graph};
@@ -409,7 +479,7 @@ mod tests {
                 true,
                 true,
                 false,
-                false,
+                (false, false),
                 BikeAttrs::road_default(),
                 BikeAttrs::road_default(),
                 VarGen::NONE,
@@ -490,7 +560,7 @@ mod tests {
             true,
             true,
             false,
-            false,
+            (false, false),
             BikeAttrs::road_default(),
             BikeAttrs::road_default(),
             VarGen::NONE,
@@ -511,6 +581,7 @@ mod tests {
             stairs_speed_mps: 0.5,
             ramp_speed_mps: 0.9,
             elevator_secs: 45.0,
+            escalator_speed_mps: 0.75,
             relocation_fallback_secs: 60.0,
         });
 
@@ -534,6 +605,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reverse_car_edge_follows_oneway_tags() {
+        assert_eq!(car_directions(&[("highway", "residential")]), (true, true));
+        assert_eq!(car_directions(&[("highway", "residential"), ("oneway", "yes")]), (true, false));
+        assert_eq!(car_directions(&[("highway", "residential"), ("oneway", "-1")]), (false, true));
+        assert_eq!(car_directions(&[("highway", "tertiary"), ("junction", "roundabout")]), (true, false));
+        assert_eq!(car_directions(&[("highway", "motorway"), ("oneway", "no")]), (true, true));
+        assert_eq!(car_directions(&[("highway", "motorway")]), (true, false));
+        assert_eq!(car_directions(&[("highway", "footway"), ("motorcar", "no")]), (false, false));
+
+        let mut g = Graph::new();
+        add_osm_node(&mut g, 3001, 50.0, 4.0, true);
+        add_osm_node(&mut g, 3002, 50.0, 4.001, true);
+        let oneway = car_directions(&[("highway", "residential"), ("oneway", "yes")]);
+        let road = BikeAttrs::road_default();
+        assert!(insert_from_osm_ids(&mut g, 3001, 3002, true, true, true, true, oneway, road, road, VarGen::NONE, 0, 100));
+        let access_from = |from: i64| match &g.out_edges(g.get_osm_id(from).unwrap())[0] {
+            crate::structures::EdgeData::Street(s) => (s.foot, s.bike, s.car),
+            _ => unreachable!(),
+        };
+        assert_eq!(access_from(3001), (true, true, true));
+        assert_eq!(access_from(3002), (true, true, false), "walking and cycling stay two-way");
+    }
+
     #[test]
     fn virtual_highway_footway_accepted_when_highway_absent() {
         assert!(
@@ -603,6 +698,27 @@ mod tests {
     }
 }
 
+/// Car access `(forward, reverse)` along a way: `motorcar=no` closes both, a one-way
+/// (`oneway=yes|true|1`, or implied by `junction=roundabout` and `highway=motorway`)
+/// closes the reverse and `oneway=-1` the forward direction. Foot and bike stay two-way;
+/// riding against a one-way is priced through `BikeAttrs::wrong_way` instead.
+fn car_directions(tags: &[(&str, &str)]) -> (bool, bool) {
+    let tag = |key: &str| tags.iter().find(|t| t.0 == key).map(|t| t.1);
+    if tag("motorcar") == Some("no") {
+        return (false, false);
+    }
+    match tag("oneway") {
+        Some("yes" | "true" | "1") => (true, false),
+        Some("-1") => (false, true),
+        Some(_) => (true, true),
+        None => {
+            let implied = tag("junction") == Some("roundabout") || tag("highway") == Some("motorway");
+            (true, !implied)
+        }
+    }
+}
+
+/// `car` is `(forward, reverse)`, see [`car_directions`].
 #[allow(clippy::too_many_arguments)]
 fn insert_from_osm_ids(
     g: &mut Graph,
@@ -612,22 +728,20 @@ fn insert_from_osm_ids(
     partial: bool,
     foot: bool,
     bike: bool,
-    car: bool,
+    car: (bool, bool),
     attrs_fwd: BikeAttrs,
     attrs_rev: BikeAttrs,
     var_gen: VarGen,
     delta: i16,
     surface_speed: u8,
 ) -> bool {
-    let from_eid = format!("map#osm#{}", from);
-    let to_eid = format!("map#osm#{}", to);
-    let from_id = *match g.get_id(from_eid.as_str()) {
+    let from_id = match g.get_osm_id(from) {
         Some(x) => x,
         None => {
             return false;
         }
     };
-    let to_id = *match g.get_id(to_eid.as_str()) {
+    let to_id = match g.get_osm_id(to) {
         Some(x) => x,
         None => {
             return false;
@@ -659,7 +773,7 @@ fn insert_from_osm_ids(
             partial,
             foot,
             bike,
-            car,
+            car: car.0,
             attrs: attrs_fwd,
             elev_delta: delta,
             surface_speed,
@@ -676,7 +790,7 @@ fn insert_from_osm_ids(
                 partial,
                 foot,
                 bike,
-                car,
+                car: car.1,
                 attrs: attrs_rev,
                 elev_delta: -delta,
                 surface_speed,
@@ -1,10 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+#[cfg(feature = "pbf")]
+use std::collections::HashSet;
 
 use kdtree::KdTree;
+#[cfg(feature = "pbf")]
 use osmpbf::{Element, ElementReader, RelMemberType};
 use serde::{Deserialize, Serialize};
 
-use crate::structures::{Connector, Graph, LatLng, NodeID};
+use crate::structures::{Connector, LatLng, NodeID};
+#[cfg(feature = "pbf")]
+use crate::structures::Graph;
 
 // Generous: SNCB platform stops collapse to the station centroid, so the real
 // platform geometry can sit a few hundred metres away.
@@ -186,14 +191,19 @@ pub(crate) fn effective_highway<'a>(tags: &[(&str, &'a str)]) -> Option<&'a str>
 }
 
 // A ramp must be explicitly ramp=yes (a bare incline would mis-flag every sloped street).
+// `conveying` on steps is an escalator; on a flat way it is a moving walkway, which
+// stays a plain footway.
 pub fn parse_connector(tags: &[(&str, &str)]) -> Option<Connector> {
     let get = |key: &str| tags.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
     match effective_highway(tags) {
+        Some("steps") if get("conveying").is_some_and(|v| v != "no") => {
+            return Some(Connector::Escalator);
+        }
         Some("steps") => return Some(Connector::Steps),
         Some("elevator") => return Some(Connector::Elevator),
         _ => {}
     }
-    if get("elevator") == Some("yes") || get("conveying").is_some() {
+    if get("elevator") == Some("yes") {
         return Some(Connector::Elevator);
     }
     if get("ramp") == Some("yes") {
@@ -207,6 +217,7 @@ pub struct ConnectorCost {
     pub stairs_speed_mps: f64,
     pub ramp_speed_mps: f64,
     pub elevator_secs: f64,
+    pub escalator_speed_mps: f64,
     // Charged ON TOP of pricing the run as stairs, so a real mapped connector
     // always undercuts this synthetic fallback.
     pub relocation_fallback_secs: f64,
@@ -218,6 +229,7 @@ impl Default for ConnectorCost {
             stairs_speed_mps: 0.5,
             ramp_speed_mps: 0.9,
             elevator_secs: 45.0,
+            escalator_speed_mps: 0.75,
             relocation_fallback_secs: 60.0,
         }
     }
@@ -229,6 +241,7 @@ impl ConnectorCost {
             Connector::Steps => length_m / self.stairs_speed_mps,
             Connector::Ramp => length_m / self.ramp_speed_mps,
             Connector::Elevator => self.elevator_secs,
+            Connector::Escalator => length_m / self.escalator_speed_mps,
         }
     }
 
@@ -237,7 +250,8 @@ impl ConnectorCost {
     }
 }
 
-fn centroid_of(ids: &[i64], coords: &HashMap<i64, (f64, f64)>) -> Option<LatLng> {
+#[cfg_attr(not(feature = "pbf"), allow(dead_code))]
+pub(super) fn centroid_of(ids: &[i64], coords: &HashMap<i64, (f64, f64)>) -> Option<LatLng> {
     let pts: Vec<(f64, f64)> = ids.iter().filter_map(|id| coords.get(id).copied()).collect();
     if pts.is_empty() {
         return None;
@@ -249,6 +263,7 @@ fn centroid_of(ids: &[i64], coords: &HashMap<i64, (f64, f64)>) -> Option<LatLng>
     })
 }
 
+#[cfg_attr(not(feature = "pbf"), allow(dead_code))]
 pub(crate) fn platform_from_way_data(
     ids: &[i64],
     refs: Vec<String>,
@@ -261,6 +276,7 @@ pub(crate) fn platform_from_way_data(
     Some(OsmPlatform { refs, level, centroid, node_ids })
 }
 
+#[cfg_attr(not(feature = "pbf"), allow(dead_code))]
 pub(crate) fn platform_from_node_data(
     osm_id: i64,
     lat: f64,
@@ -274,6 +290,7 @@ pub(crate) fn platform_from_node_data(
     OsmPlatform { refs, level, centroid, node_ids }
 }
 
+#[cfg(feature = "pbf")]
 pub fn build_platform_index(osm_path: &str, g: &Graph) -> Result<PlatformIndex, osmpbf::Error> {
     // Relation tags live on the relation itself; member ways are typically untagged,
     // and PBF ordering puts relations last.
@@ -350,7 +367,7 @@ pub fn build_platform_index(osm_path: &str, g: &Graph) -> Result<PlatformIndex,
         _ => {}
     })?;
 
-    let resolve = |id: i64| g.get_id(&format!("map#osm#{id}")).copied();
+    let resolve = |id: i64| g.get_osm_id(id);
 
     let mut platforms: Vec<OsmPlatform> = Vec::new();
     for (ids, refs, level) in platform_ways {
@@ -489,6 +506,21 @@ mod tests {
         assert_eq!(parse_connector(&[("highway", "footway")]), None);
     }
 
+    #[test]
+    fn conveying_steps_are_escalators_and_walkways_stay_flat() {
+        assert_eq!(
+            parse_connector(&[("highway", "steps"), ("conveying", "reversible")]),
+            Some(Connector::Escalator)
+        );
+        assert_eq!(
+            parse_connector(&[("highway", "steps"), ("conveying", "no")]),
+            Some(Connector::Steps)
+        );
+        assert_eq!(parse_connector(&[("highway", "footway"), ("conveying", "yes")]), None);
+        assert!(!Connector::Escalator.step_free());
+        assert!(Connector::Elevator.step_free());
+    }
+
     #[test]
     fn connector_cost_stairs_slower_than_elevator_constant() {
         let c = ConnectorCost::default();
@@ -503,10 +535,12 @@ mod tests {
             stairs_speed_mps: 0.25,
             ramp_speed_mps: 1.0,
             elevator_secs: 60.0,
+            escalator_speed_mps: 0.5,
             relocation_fallback_secs: 30.0,
         };
         assert!((c.seconds(Connector::Steps, 10.0) - 40.0).abs() < 1e-9);
         assert_eq!(c.seconds(Connector::Elevator, 0.0), 60.0);
+        assert!((c.seconds(Connector::Escalator, 10.0) - 20.0).abs() < 1e-9);
     }
 
     #[test]
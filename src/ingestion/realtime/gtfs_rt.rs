@@ -5,17 +5,32 @@ use prost::Message;
 use crate::ingestion::realtime::fetcher::{FetchError, Fetcher};
 use crate::ingestion::realtime::proto::FeedMessage;
 use crate::ingestion::realtime::proto::trip_descriptor::ScheduleRelationship;
-use crate::ingestion::realtime::{ActualStopId, AlertEntitySelector, FeedUpdate, RealtimeFeed, ServiceAlert, TripDelay};
+use crate::ingestion::realtime::{
+    ActualStopId, AlertEntitySelector, FeedUpdate, RealtimeFeed, ServiceAlert, TripDelay,
+    TripOccupancy,
+};
 
 pub struct GtfsRtFeed {
     name: String,
     url: String,
     headers: HashMap<String, String>,
+    namespace: Option<String>,
 }
 
 impl GtfsRtFeed {
     pub fn new(name: String, url: String, headers: HashMap<String, String>) -> Self {
-        Self { name, url, headers }
+        Self {
+            name,
+            url,
+            headers,
+            namespace: None,
+        }
+    }
+
+    /// Prefix the feed's ids to match a GTFS input loaded with `namespace`.
+    pub fn with_namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
     }
 }
 
@@ -26,7 +41,7 @@ impl RealtimeFeed for GtfsRtFeed {
 
     fn poll(&self, fetcher: &Fetcher) -> Result<FeedUpdate, FetchError> {
         let bytes = fetcher.get(&self.url, &self.headers)?;
-        let update = parse_trip_updates(&bytes)?;
+        let update = parse_trip_updates(&bytes)?.namespaced(self.namespace.as_deref());
         tracing::info!(
             feed = %self.name,
             delays = update.delays.len(),
@@ -58,8 +73,19 @@ pub fn parse_trip_updates(bytes: &[u8]) -> Result<FeedUpdate, String> {
     let mut alerts = Vec::new();
     let mut actual_stops = Vec::new();
     let mut skipped_stops = Vec::new();
+    let mut occupancy = Vec::new();
 
     for entity in &feed.entity {
+        if let Some(vp) = &entity.vehicle
+            && let Some(status) = vp.occupancy_status
+            && let Some(trip_id) = vp.trip.as_ref().and_then(|t| t.trip_id.clone())
+        {
+            occupancy.push(TripOccupancy {
+                trip_id,
+                stop_id: None,
+                status,
+            });
+        }
         if let Some(tu) = &entity.trip_update {
             let Some(trip_id) = tu.trip.trip_id.clone() else {
                 continue;
@@ -89,6 +115,15 @@ pub fn parse_trip_updates(bytes: &[u8]) -> Result<FeedUpdate, String> {
                     }
                     _ => {}
                 }
+                if let (Some(stop_id), Some(status)) =
+                    (&stu.stop_id, stu.departure_occupancy_status)
+                {
+                    occupancy.push(TripOccupancy {
+                        trip_id: trip_id.clone(),
+                        stop_id: Some(stop_id.clone()),
+                        status,
+                    });
+                }
                 if let Some(stop_id) = &stu.stop_id {
                     actual_stops.push(ActualStopId {
                         trip_id: trip_id.clone(),
@@ -142,6 +177,7 @@ pub fn parse_trip_updates(bytes: &[u8]) -> Result<FeedUpdate, String> {
         alerts,
         actual_stops,
         skipped_stops,
+        occupancy,
     })
 }
 
@@ -278,6 +314,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn namespace_prefixes_every_parsed_id() {
+        let bytes = encode_feed(vec![
+            trip_update_entity("t1", vec![stop_update(1, Some(60)), skipped_stop_update(2)]),
+            canceled_trip_entity("t2"),
+        ]);
+        let update = parse_trip_updates(&bytes).unwrap().namespaced(Some("sncb"));
+        assert_eq!(update.delays[0].trip_id, "sncb:t1");
+        assert_eq!(update.delays[0].stop_id.as_deref(), Some("sncb:stop_1"));
+        assert_eq!(update.canceled, vec!["sncb:t2".to_string()]);
+        assert_eq!(
+            update.skipped_stops,
+            vec![("sncb:t1".to_string(), "sncb:stop_2".to_string())]
+        );
+        assert!(update.actual_stops.iter().all(|a| a.trip_id == "sncb:t1"));
+    }
+
     #[test]
     fn no_data_stop_update_is_ignored_entirely() {
         let bytes = encode_feed(vec![trip_update_entity(
@@ -411,6 +464,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_stop_and_vehicle_occupancy() {
+        use crate::ingestion::realtime::proto::{TripDescriptor, VehiclePosition};
+
+        let stu = StopTimeUpdate {
+            departure_occupancy_status: Some(5),
+            ..stop_update(2, Some(60))
+        };
+        let vehicle = FeedEntity {
+            id: "vp".to_string(),
+            vehicle: Some(VehiclePosition {
+                trip: Some(TripDescriptor {
+                    trip_id: Some("trip_busy".to_string()),
+                    ..Default::default()
+                }),
+                occupancy_status: Some(3),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let bytes = encode_feed(vec![trip_update_entity("trip_busy", vec![stu]), vehicle]);
+        let update = parse_trip_updates(&bytes).unwrap();
+
+        assert_eq!(
+            update.occupancy,
+            vec![
+                TripOccupancy {
+                    trip_id: "trip_busy".into(),
+                    stop_id: Some("stop_2".into()),
+                    status: 5,
+                },
+                TripOccupancy {
+                    trip_id: "trip_busy".into(),
+                    stop_id: None,
+                    status: 3,
+                },
+            ]
+        );
+        assert_eq!(update.delays.len(), 1, "occupancy does not displace the delay");
+    }
+
     #[test]
     fn empty_feed_yields_no_delays() {
         let bytes = encode_feed(vec![]);
@@ -1,6 +1,7 @@
 pub mod fetcher;
 pub mod gtfs_rt;
 pub mod proto;
+pub mod siri;
 pub mod stib;
 
 /// `delay` is seconds off schedule (positive = late). `trip_id`/`stop_id` are raw
@@ -45,6 +46,20 @@ pub struct ServiceAlert {
     pub informed_entity: Vec<AlertEntitySelector>,
 }
 
+impl ServiceAlert {
+    /// GTFS-RT `Effect::ACCESSIBILITY_ISSUE`.
+    pub const ACCESSIBILITY_ISSUE: i32 = 11;
+
+    /// Active if `active_period` is empty or some period has `start ≤ now < end`
+    /// (missing bound = open).
+    pub fn is_active(&self, now_unix_secs: u64) -> bool {
+        self.active_period.is_empty()
+            || self.active_period.iter().any(|(start, end)| {
+                start.is_none_or(|s| now_unix_secs >= s) && end.is_none_or(|e| now_unix_secs < e)
+            })
+    }
+}
+
 /// RT `stop_id` for a trip stop, captured regardless of delay; used to detect
 /// platform reassignments against the scheduled stop.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -53,6 +68,16 @@ pub struct ActualStopId {
     pub stop_id: String,
 }
 
+/// Load the feed reported for a trip: leaving `stop_id` (a StopTimeUpdate's
+/// `departure_occupancy_status`) or vehicle-wide when `None` (a VehiclePosition).
+/// `status` is the raw protobuf `OccupancyStatus` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripOccupancy {
+    pub trip_id: String,
+    pub stop_id: Option<String>,
+    pub status: i32,
+}
+
 /// One poll cycle's observations. `canceled` holds GTFS `trip_id`s that will not
 /// run; `skipped_stops` holds `(trip_id, stop_id)` pairs the feed marked SKIPPED.
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -63,6 +88,44 @@ pub struct FeedUpdate {
     pub alerts: Vec<ServiceAlert>,
     pub actual_stops: Vec<ActualStopId>,
     pub skipped_stops: Vec<(String, String)>,
+    pub occupancy: Vec<TripOccupancy>,
+}
+
+impl FeedUpdate {
+    /// Prefix every GTFS id with `namespace`, matching a feed ingested with the same one.
+    pub fn namespaced(mut self, namespace: Option<&str>) -> Self {
+        let Some(ns) = namespace else {
+            return self;
+        };
+        let ns_id = |id: &mut String| *id = crate::ingestion::gtfs::namespaced_id(Some(ns), id);
+        for d in &mut self.delays {
+            ns_id(&mut d.trip_id);
+            d.stop_id.iter_mut().for_each(ns_id);
+        }
+        self.canceled.iter_mut().for_each(ns_id);
+        for p in &mut self.positions {
+            ns_id(&mut p.trip_id);
+            p.stop_id.iter_mut().for_each(ns_id);
+        }
+        for e in self.alerts.iter_mut().flat_map(|a| &mut a.informed_entity) {
+            e.trip_id.iter_mut().for_each(ns_id);
+            e.route_id.iter_mut().for_each(ns_id);
+            e.stop_id.iter_mut().for_each(ns_id);
+        }
+        for a in &mut self.actual_stops {
+            ns_id(&mut a.trip_id);
+            ns_id(&mut a.stop_id);
+        }
+        for (trip, stop) in &mut self.skipped_stops {
+            ns_id(trip);
+            ns_id(stop);
+        }
+        for o in &mut self.occupancy {
+            ns_id(&mut o.trip_id);
+            o.stop_id.iter_mut().for_each(ns_id);
+        }
+        self
+    }
 }
 
 pub trait RealtimeFeed: Send + Sync {
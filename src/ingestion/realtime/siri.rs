@@ -0,0 +1,534 @@
+//! SIRI (CEN/TS 15531) realtime: Estimated Timetable (ET) deliveries become trip
+//! delays, cancellations, skipped stops and occupancy, Situation Exchange (SX)
+//! deliveries become service alerts, both on the same [`FeedUpdate`] GTFS-RT fills.
+//! `DatedVehicleJourneyRef`, `StopPointRef` and `LineRef` must carry the GTFS
+//! `trip_id`, `stop_id` and `route_id` of the feed they update (after `namespace`),
+//! as operators publishing both formats do.
+
+use std::collections::HashMap;
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::ingestion::realtime::fetcher::{FetchError, Fetcher};
+use crate::ingestion::realtime::{
+    AlertEntitySelector, FeedUpdate, RealtimeFeed, ServiceAlert, TripDelay, TripOccupancy,
+};
+
+pub struct SiriFeed {
+    name: String,
+    estimated_timetable_url: Option<String>,
+    situation_exchange_url: Option<String>,
+    headers: HashMap<String, String>,
+    namespace: Option<String>,
+}
+
+impl SiriFeed {
+    pub fn new(
+        name: String,
+        estimated_timetable_url: Option<String>,
+        situation_exchange_url: Option<String>,
+        headers: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            name,
+            estimated_timetable_url,
+            situation_exchange_url,
+            headers,
+            namespace: None,
+        }
+    }
+
+    /// Prefix the feed's ids to match a GTFS input loaded with `namespace`.
+    pub fn with_namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
+    }
+}
+
+impl RealtimeFeed for SiriFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// One request per configured service. A failing service is logged and skipped;
+    /// the poll only fails when every configured one does.
+    fn poll(&self, fetcher: &Fetcher) -> Result<FeedUpdate, FetchError> {
+        type Parse = fn(&[u8]) -> Result<FeedUpdate, String>;
+        let services: [(&str, &Option<String>, Parse); 2] = [
+            ("ET", &self.estimated_timetable_url, parse_estimated_timetable),
+            ("SX", &self.situation_exchange_url, parse_situation_exchange),
+        ];
+        let mut update = FeedUpdate::default();
+        let mut last_err = None;
+        let mut any_ok = false;
+        for (service, url, parse) in services {
+            let Some(url) = url else {
+                continue;
+            };
+            match fetcher.get(url, &self.headers).and_then(|b| Ok(parse(&b)?)) {
+                Ok(part) => {
+                    any_ok = true;
+                    update.delays.extend(part.delays);
+                    update.canceled.extend(part.canceled);
+                    update.alerts.extend(part.alerts);
+                    update.skipped_stops.extend(part.skipped_stops);
+                    update.occupancy.extend(part.occupancy);
+                }
+                Err(FetchError::Throttled) => last_err = Some(FetchError::Throttled),
+                Err(FetchError::Failed(e)) => {
+                    tracing::error!(feed = %self.name, "SIRI-{service} poll failed: {e}");
+                    last_err = Some(FetchError::Failed(e));
+                }
+            }
+        }
+        if let (false, Some(e)) = (any_ok, last_err) {
+            return Err(e);
+        }
+        let update = update.namespaced(self.namespace.as_deref());
+        tracing::info!(
+            feed = %self.name,
+            delays = update.delays.len(),
+            canceled = update.canceled.len(),
+            alerts = update.alerts.len(),
+            "polled SIRI feed"
+        );
+        Ok(update)
+    }
+}
+
+/// Element name without its namespace prefix (`siri:StopPointRef` → `StopPointRef`).
+fn local_name(raw: &[u8]) -> String {
+    let name = match raw.iter().rposition(|&b| b == b':') {
+        Some(i) => &raw[i + 1..],
+        None => raw,
+    };
+    String::from_utf8_lossy(name).into_owned()
+}
+
+fn lang_of(e: &BytesStart) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == "lang")
+        .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+}
+
+#[derive(Clone, Copy)]
+enum Element<'a> {
+    Open(&'a str),
+    /// Closing tag with the element's own text (entities resolved) and `xml:lang`.
+    Close { name: &'a str, text: &'a str, lang: Option<&'a str> },
+}
+
+/// Walks `xml`, calling `f` with each element event and the names of its enclosing
+/// elements (outermost first, the element itself excluded).
+fn walk(xml: &[u8], mut f: impl FnMut(Element, &[String])) -> Result<(), String> {
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut frames: Vec<(String, Option<String>)> = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Err(e) => return Err(format!("SIRI XML parse error: {e}")),
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.name().as_ref());
+                f(Element::Open(&name), &names);
+                frames.push((String::new(), lang_of(&e)));
+                names.push(name);
+            }
+            Ok(Event::Empty(e)) => {
+                let name = local_name(e.name().as_ref());
+                f(Element::Open(&name), &names);
+                f(Element::Close { name: &name, text: "", lang: None }, &names);
+            }
+            Ok(Event::Text(e)) => {
+                let text = e.xml10_content().map_err(|err| format!("SIRI XML text: {err}"))?;
+                if let Some((t, _)) = frames.last_mut() {
+                    t.push_str(&text);
+                }
+            }
+            Ok(Event::CData(e)) => {
+                let text = e.decode().map_err(|err| format!("SIRI XML text: {err}"))?;
+                if let Some((t, _)) = frames.last_mut() {
+                    t.push_str(&text);
+                }
+            }
+            Ok(Event::GeneralRef(e)) => {
+                let resolved = match e.resolve_char_ref() {
+                    Ok(Some(c)) => Some(c.to_string()),
+                    _ => e
+                        .decode()
+                        .ok()
+                        .and_then(|n| quick_xml::escape::resolve_predefined_entity(&n))
+                        .map(str::to_string),
+                };
+                if let (Some((t, _)), Some(r)) = (frames.last_mut(), resolved) {
+                    t.push_str(&r);
+                }
+            }
+            Ok(Event::End(_)) => {
+                let (Some(name), Some((text, lang))) = (names.pop(), frames.pop()) else {
+                    continue;
+                };
+                f(Element::Close { name: &name, text: text.trim(), lang: lang.as_deref() }, &names);
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+fn unix_secs(timestamp: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.timestamp())
+}
+
+/// SIRI `OccupancyEnumeration` (and the SIRI 1.x values) as a GTFS-RT
+/// `OccupancyStatus`.
+fn occupancy_status(value: &str) -> Option<i32> {
+    Some(match value {
+        "empty" => 0,
+        "manySeatsAvailable" | "seatsAvailable" => 1,
+        "fewSeatsAvailable" => 2,
+        "standingRoomOnly" | "standingAvailable" => 3,
+        "crushedStandingRoomOnly" => 4,
+        "full" => 5,
+        "notAcceptingPassengers" => 6,
+        _ => return None,
+    })
+}
+
+#[derive(Default)]
+struct Call {
+    stop_id: Option<String>,
+    order: Option<u32>,
+    aimed_arrival: Option<i64>,
+    expected_arrival: Option<i64>,
+    aimed_departure: Option<i64>,
+    expected_departure: Option<i64>,
+    cancelled: bool,
+    occupancy: Option<i32>,
+}
+
+impl Call {
+    /// Departure deviation, else arrival deviation.
+    fn delay(&self) -> Option<i32> {
+        let dev = |aimed: Option<i64>, expected: Option<i64>| Some((expected? - aimed?) as i32);
+        dev(self.aimed_departure, self.expected_departure)
+            .or_else(|| dev(self.aimed_arrival, self.expected_arrival))
+    }
+}
+
+#[derive(Default)]
+struct Journey {
+    trip_id: Option<String>,
+    cancelled: bool,
+    calls: Vec<Call>,
+}
+
+/// Delays, cancellations, skipped stops and occupancy from a SIRI-ET
+/// `ServiceDelivery`. Recorded calls count too: their actual times give the delay
+/// the vehicle carries into the calls ahead.
+pub fn parse_estimated_timetable(xml: &[u8]) -> Result<FeedUpdate, String> {
+    let mut update = FeedUpdate::default();
+    let mut journey: Option<Journey> = None;
+    let mut call: Option<Call> = None;
+    walk(xml, |el, parents| match el {
+        Element::Open("EstimatedVehicleJourney") => journey = Some(Journey::default()),
+        Element::Open("EstimatedCall" | "RecordedCall") => call = Some(Call::default()),
+        Element::Open(_) => {}
+        Element::Close { name: "EstimatedCall" | "RecordedCall", .. } => {
+            if let (Some(j), Some(c)) = (journey.as_mut(), call.take()) {
+                j.calls.push(c);
+            }
+        }
+        Element::Close { name, text, .. } => {
+            if let Some(c) = call.as_mut() {
+                let parent = parents.last().map(String::as_str);
+                match name {
+                    "StopPointRef" => c.stop_id = Some(text.to_string()),
+                    "Order" => c.order = text.parse().ok(),
+                    "AimedArrivalTime" => c.aimed_arrival = unix_secs(text),
+                    "ExpectedArrivalTime" | "ActualArrivalTime" => c.expected_arrival = unix_secs(text),
+                    "AimedDepartureTime" => c.aimed_departure = unix_secs(text),
+                    "ExpectedDepartureTime" | "ActualDepartureTime" => {
+                        c.expected_departure = unix_secs(text)
+                    }
+                    "Cancellation" if matches!(parent, Some("EstimatedCall" | "RecordedCall")) => {
+                        c.cancelled = text == "true"
+                    }
+                    "Occupancy" => c.occupancy = occupancy_status(text),
+                    _ => {}
+                }
+                return;
+            }
+            let Some(j) = journey.as_mut() else {
+                return;
+            };
+            match name {
+                "DatedVehicleJourneyRef" => j.trip_id = Some(text.to_string()),
+                "Cancellation" => j.cancelled = text == "true",
+                "EstimatedVehicleJourney" => {
+                    if let Some(j) = journey.take() {
+                        emit_journey(j, &mut update);
+                    }
+                }
+                _ => {}
+            }
+        }
+    })?;
+    Ok(update)
+}
+
+fn emit_journey(j: Journey, update: &mut FeedUpdate) {
+    let Some(trip_id) = j.trip_id else {
+        return;
+    };
+    if j.cancelled {
+        update.canceled.push(trip_id);
+        return;
+    }
+    for c in j.calls {
+        if c.cancelled {
+            if let Some(stop_id) = c.stop_id {
+                update.skipped_stops.push((trip_id.clone(), stop_id));
+            }
+            continue;
+        }
+        if let (Some(stop_id), Some(status)) = (&c.stop_id, c.occupancy) {
+            update.occupancy.push(TripOccupancy {
+                trip_id: trip_id.clone(),
+                stop_id: Some(stop_id.clone()),
+                status,
+            });
+        }
+        if c.stop_id.is_none() && c.order.is_none() {
+            continue;
+        }
+        if let Some(delay) = c.delay() {
+            update.delays.push(TripDelay {
+                trip_id: trip_id.clone(),
+                stop_id: c.stop_id,
+                stop_sequence: c.order,
+                delay,
+            });
+        }
+    }
+}
+
+/// SIRI `AlertCause` (or the SIRI 1.x `*Reason` values) as a GTFS-RT `Cause`.
+fn alert_cause(reason: &str, value: &str) -> i32 {
+    match value {
+        "accident" | "collision" | "derailment" => 6,
+        "roadworks" | "constructionWork" => 10,
+        "maintenanceWork" | "repairWork" => 9,
+        "policeActivity" | "policeOrder" | "securityAlert" | "bombAlert" => 11,
+        "medicalEmergency" | "illVehicleOccupants" | "passengerInjured" => 12,
+        "demonstration" | "march" => 5,
+        "industrialAction" | "strike" | "staffAbsence" => 4,
+        "holiday" => 7,
+        "technicalProblem" | "signalProblem" | "vehicleFailure" | "powerProblem" => 3,
+        "undefinedAlertCause" | "unknown" => 1,
+        _ => match reason {
+            "EquipmentReason" => 3,
+            "EnvironmentReason" => 8,
+            _ => 2,
+        },
+    }
+}
+
+/// SIRI `ServiceConditionEnumeration` as a GTFS-RT `Effect`.
+fn alert_effect(condition: &str) -> i32 {
+    match condition {
+        "cancelled" | "noService" => 1,
+        "intermittentService" | "shortFormedService" | "reducedService" => 2,
+        "delayed" | "disrupted" => 3,
+        "diverted" => 4,
+        "additionalService" | "extendedService" => 5,
+        "altered" | "replacementTransport" | "specialService" => 6,
+        "stopMoved" => 9,
+        "normalService" | "onTime" => 10,
+        "unknown" => 8,
+        _ => 7,
+    }
+}
+
+/// English when offered, else the first text.
+fn pick_translation(texts: &[(Option<String>, String)]) -> Option<String> {
+    texts
+        .iter()
+        .find(|(lang, _)| lang.as_deref().is_some_and(|l| l.starts_with("en")))
+        .or_else(|| texts.first())
+        .map(|(_, t)| t.clone())
+}
+
+#[derive(Default)]
+struct Situation {
+    summary: Vec<(Option<String>, String)>,
+    description: Vec<(Option<String>, String)>,
+    periods: Vec<(Option<u64>, Option<u64>)>,
+    period: (Option<u64>, Option<u64>),
+    closed: bool,
+    cause: Option<i32>,
+    effect: Option<i32>,
+    entities: Vec<AlertEntitySelector>,
+    journey: Option<AlertEntitySelector>,
+}
+
+/// Service alerts from a SIRI-SX `ServiceDelivery`; closed situations are dropped.
+/// Every affected line, stop point/place and vehicle journey becomes one informed
+/// entity.
+pub fn parse_situation_exchange(xml: &[u8]) -> Result<FeedUpdate, String> {
+    let mut update = FeedUpdate::default();
+    let mut situation: Option<Situation> = None;
+    let selector = |trip_id, route_id, stop_id| AlertEntitySelector { trip_id, route_id, stop_id };
+    walk(xml, |el, parents| {
+        if let Element::Open(name) = el {
+            match name {
+                "PtSituationElement" => situation = Some(Situation::default()),
+                "ValidityPeriod" => {
+                    if let Some(s) = situation.as_mut() {
+                        s.period = (None, None);
+                    }
+                }
+                "AffectedVehicleJourney" => {
+                    if let Some(s) = situation.as_mut() {
+                        s.journey = Some(selector(None, None, None));
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+        let Element::Close { name, text, lang } = el else {
+            return;
+        };
+        let Some(s) = situation.as_mut() else {
+            return;
+        };
+        let parent = parents.last().map(String::as_str);
+        let in_affects = parents.iter().any(|p| p == "Affects");
+        let owned = || Some(text.to_string());
+        match name {
+            "Summary" if parent == Some("PtSituationElement") => {
+                s.summary.push((lang.map(str::to_string), text.to_string()))
+            }
+            "Description" if parent == Some("PtSituationElement") => {
+                s.description.push((lang.map(str::to_string), text.to_string()))
+            }
+            "StartTime" if parent == Some("ValidityPeriod") => {
+                s.period.0 = unix_secs(text).map(|t| t.max(0) as u64)
+            }
+            "EndTime" if parent == Some("ValidityPeriod") => {
+                s.period.1 = unix_secs(text).map(|t| t.max(0) as u64)
+            }
+            "ValidityPeriod" => s.periods.push(s.period),
+            "Progress" => s.closed = text == "closed",
+            "AlertCause" | "MiscellaneousReason" | "PersonnelReason" | "EquipmentReason"
+            | "EnvironmentReason" => {
+                s.cause.get_or_insert(alert_cause(name, text));
+            }
+            "Condition" if parents.iter().any(|p| p == "Consequence") => {
+                s.effect.get_or_insert(alert_effect(text));
+            }
+            "MobilityImpairedAccess" if text == "false" => {
+                s.effect.get_or_insert(ServiceAlert::ACCESSIBILITY_ISSUE);
+            }
+            "DatedVehicleJourneyRef" if s.journey.is_some() => {
+                s.journey.as_mut().unwrap().trip_id = owned()
+            }
+            "LineRef" if s.journey.is_some() => s.journey.as_mut().unwrap().route_id = owned(),
+            "AffectedVehicleJourney" => {
+                if let Some(j) = s.journey.take().filter(|j| j.trip_id.is_some() || j.route_id.is_some()) {
+                    s.entities.push(j);
+                }
+            }
+            "LineRef" if in_affects => s.entities.push(selector(None, owned(), None)),
+            "StopPointRef" | "StopPlaceRef" if in_affects => {
+                s.entities.push(selector(None, None, owned()))
+            }
+            "PtSituationElement" => {
+                if let Some(s) = situation.take().filter(|s| !s.closed) {
+                    update.alerts.push(ServiceAlert {
+                        header: pick_translation(&s.summary),
+                        description: pick_translation(&s.description),
+                        cause: s.cause,
+                        effect: s.effect,
+                        active_period: s.periods,
+                        informed_entity: s.entities,
+                    });
+                }
+            }
+            _ => {}
+        }
+    })?;
+    Ok(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ET: &str = include_str!("fixtures/siri_et.xml");
+    const SX: &str = include_str!("fixtures/siri_sx.xml");
+
+    #[test]
+    fn estimated_timetable_maps_delays_cancellations_and_skips() {
+        let u = parse_estimated_timetable(ET.as_bytes()).unwrap();
+        assert_eq!(
+            u.delays,
+            vec![
+                TripDelay {
+                    trip_id: "T1".into(),
+                    stop_id: Some("S1".into()),
+                    stop_sequence: Some(1),
+                    delay: 60,
+                },
+                TripDelay {
+                    trip_id: "T1".into(),
+                    stop_id: Some("S2".into()),
+                    stop_sequence: Some(2),
+                    delay: 120,
+                },
+            ],
+            "recorded call from actual departure, estimated call from expected arrival"
+        );
+        assert_eq!(u.skipped_stops, vec![("T1".to_string(), "S3".to_string())]);
+        assert_eq!(u.canceled, vec!["T2".to_string()]);
+        assert_eq!(
+            u.occupancy,
+            vec![TripOccupancy { trip_id: "T1".into(), stop_id: Some("S2".into()), status: 3 }]
+        );
+    }
+
+    #[test]
+    fn situation_exchange_maps_alerts_and_drops_closed_ones() {
+        let u = parse_situation_exchange(SX.as_bytes()).unwrap();
+        assert_eq!(u.alerts.len(), 1, "the closed situation is dropped");
+        let a = &u.alerts[0];
+        assert_eq!(a.header.as_deref(), Some("Works & diversion"));
+        assert_eq!(a.description.as_deref(), Some("Line 7 diverted"));
+        assert_eq!((a.cause, a.effect), (Some(10), Some(4)));
+        assert_eq!(a.active_period, vec![(Some(1_782_201_600), Some(1_782_288_000))]);
+        assert_eq!(
+            a.informed_entity,
+            vec![
+                AlertEntitySelector { trip_id: None, route_id: Some("L7".into()), stop_id: None },
+                AlertEntitySelector { trip_id: None, route_id: None, stop_id: Some("S9".into()) },
+                AlertEntitySelector {
+                    trip_id: Some("T5".into()),
+                    route_id: Some("L7".into()),
+                    stop_id: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn namespace_prefixes_siri_refs() {
+        let u = parse_estimated_timetable(ET.as_bytes()).unwrap().namespaced(Some("de"));
+        assert_eq!(u.delays[0].trip_id, "de:T1");
+        assert_eq!(u.canceled, vec!["de:T2".to_string()]);
+    }
+}
@@ -25,6 +25,7 @@ pub struct StibFeed {
     headers: HashMap<String, String>,
     graph: Arc<Graph>,
     params: MatchParams,
+    namespace: Option<String>,
 }
 
 impl StibFeed {
@@ -42,8 +43,21 @@ impl StibFeed {
             headers,
             graph,
             params: MatchParams::default(),
+            namespace: None,
         }
     }
+
+    /// Resolve point ids against a STIB GTFS input loaded with `namespace`. Emitted ids
+    /// are read back from the graph, so they already carry it.
+    pub fn with_namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    fn stop_indices(&self, pointid: &str) -> Vec<usize> {
+        let id = crate::ingestion::gtfs::namespaced_id(self.namespace.as_deref(), pointid);
+        self.graph.stib_stop_indices(&id)
+    }
 }
 
 #[derive(Deserialize)]
@@ -106,7 +120,7 @@ impl RealtimeFeed for StibFeed {
                 Ok(p) => p,
                 Err(_) => continue,
             };
-            let stops = self.graph.stib_stop_indices(&row.pointid);
+            let stops = self.stop_indices(&row.pointid);
             if stops.is_empty() {
                 continue;
             }
@@ -166,6 +180,7 @@ impl RealtimeFeed for StibFeed {
             alerts: Vec::new(),
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         })
     }
 }
@@ -315,7 +330,7 @@ impl StibFeed {
             };
 
             for rec in &records {
-                let stops = self.graph.stib_stop_indices(&rec.point_id);
+                let stops = self.stop_indices(&rec.point_id);
                 if stops.is_empty() {
                     unmatched += 1;
                     continue;
@@ -385,6 +400,7 @@ mod tests {
             accessibility: Availability::Available,
             id: "2073".into(),
             platform_code: None,
+            code: None,
             parent_station: None,
         }));
 
@@ -533,6 +549,7 @@ mod tests {
             accessibility: Availability::Available,
             id: "2073".into(),
             platform_code: None,
+            code: None,
             parent_station: None,
         }));
 
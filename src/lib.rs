@@ -4,4 +4,7 @@ pub mod logging;
 pub mod routing;
 pub mod services;
 pub mod structures;
+#[cfg(feature = "web")]
 pub mod web;
+#[cfg(feature = "wasm")]
+pub mod wasm;
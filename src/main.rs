@@ -1,20 +1,28 @@
-use std::{env, process::ExitCode, sync::Arc};
+use std::{env, process::ExitCode, sync::Arc, time::SystemTime};
 
 use arc_swap::ArcSwap;
 use chrono::Local;
 use maas_rs::{
-    cli::parse_config_path,
+    cli::{
+        parse_accessibility_options, parse_bench_options, parse_config_path, parse_diff_paths,
+        parse_loadtest_options, parse_restore, parse_travel_export_options, parse_update_feed,
+    },
     ingestion::cache::save_last_checked,
     logging,
     services::{
-        build::{build_gtfs_phase, build_osm_phase},
+        build::{
+            Refresh, build_gtfs_phase, build_osm_phase, check_gtfs_feed, finish_gtfs_phase, merge_gtfs_phase,
+            replace_gtfs_feed,
+        },
         fingerprint::{graph_fingerprint, osm_fingerprint},
+        graph_diff::GraphDiff,
         persistence::{
-            load_osm_graph, save_graph, save_graph_with_rollback, save_osm_graph,
+            load_graph_unchecked, load_newest_snapshot, load_osm_graph, load_transit_graph, save_graph,
+            save_graph_with_rollback, save_osm_graph, save_transit_graph, snapshot_saved_graph,
         },
         rebuild::plan_rebuild,
     },
-    structures::Config,
+    structures::{Config, ConfigOverride},
     web::app,
 };
 
@@ -22,6 +30,26 @@ use maas_rs::{
 async fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
 
+    // `--diff old.bin new.bin`: config-less comparison of two built graphs.
+    match parse_diff_paths(&args) {
+        Ok(Some((old, new))) => {
+            let load = |path: &str| {
+                load_graph_unchecked(path).inspect_err(|e| eprintln!("{path}: {e}")).ok()
+            };
+            let (Some(old_g), Some(new_g)) = (load(&old), load(&new)) else {
+                return ExitCode::FAILURE;
+            };
+            println!("{old} -> {new}");
+            println!("{}", GraphDiff::between(&old_g, &new_g));
+            return ExitCode::SUCCESS;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
     let config_path = match parse_config_path(&args) {
         Ok(p) => p,
         Err(e) => {
@@ -30,7 +58,15 @@ async fn main() -> ExitCode {
         }
     };
 
-    let config = match Config::load(&config_path) {
+    let flag_overrides = match ConfigOverride::from_args(&args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let config = match Config::load_with(&config_path, &flag_overrides) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("Failed to load config '{config_path}': {e}");
@@ -38,15 +74,50 @@ async fn main() -> ExitCode {
         }
     };
 
+    // `--check-config`: parse + validate only, no graph work.
+    if args.contains(&"--check-config".to_string()) {
+        for w in &config.warnings {
+            eprintln!("warning: {w}");
+        }
+        println!(
+            "config '{config_path}' OK: {} input(s), output '{}'",
+            config.build.inputs.len(),
+            config.build.output
+        );
+        return ExitCode::SUCCESS;
+    }
+
     logging::init(&config.log_level);
+    for w in &config.warnings {
+        tracing::warn!("config '{config_path}': {w}");
+    }
 
     let cache_dir = config.cache_dir();
 
     let build_mode = args.contains(&"--build".to_string());
     let save_mode = args.contains(&"--save".to_string());
-    let restore_mode = args.contains(&"--restore".to_string());
+    let restore = parse_restore(&args);
+    let restore_mode = restore.is_some();
     let serve_mode = args.contains(&"--serve".to_string());
     let update_gtfs_mode = args.contains(&"--update-gtfs".to_string());
+    let update_feed = match parse_update_feed(&args) {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(feed) = &update_feed
+        && let Err(e) = check_gtfs_feed(&config.build, feed)
+    {
+        tracing::error!("{e}");
+        return ExitCode::FAILURE;
+    }
+    let bench_mode = args.contains(&"--bench".to_string());
+    let loadtest_mode = args.contains(&"--loadtest".to_string());
+    let validate_mode = args.contains(&"--validate".to_string());
+    let export_mode = args.iter().any(|a| a.starts_with("--export-travel-map"));
+    let access_mode = args.iter().any(|a| a.starts_with("--accessibility"));
 
     let mode_count = [build_mode, restore_mode, update_gtfs_mode]
         .iter()
@@ -61,6 +132,73 @@ async fn main() -> ExitCode {
         return ExitCode::FAILURE;
     }
 
+    if [bench_mode, loadtest_mode, validate_mode, serve_mode, export_mode, access_mode]
+        .iter()
+        .filter(|&&x| x)
+        .count()
+        > 1
+    {
+        tracing::error!(
+            "at most one of --bench, --loadtest, --validate, --export-travel-map, --accessibility, \
+             or --serve may be set"
+        );
+        return ExitCode::FAILURE;
+    }
+    let serves = serve_mode
+        || (mode_count == 0 && ![bench_mode, loadtest_mode, validate_mode, export_mode, access_mode].contains(&true));
+    // Before the (possibly long) graph build, not after it.
+    if serves && let Err(e) = config.check_servable() {
+        tracing::error!("{e}");
+        return ExitCode::FAILURE;
+    }
+    let bench_options = if bench_mode {
+        match parse_bench_options(&args) {
+            Ok(o) => Some(o),
+            Err(e) => {
+                tracing::error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let loadtest_options = if loadtest_mode {
+        match parse_loadtest_options(&args) {
+            Ok(o) => Some(o),
+            Err(e) => {
+                tracing::error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let export_options = if export_mode {
+        match parse_travel_export_options(&args) {
+            Ok(o) => Some(o),
+            Err(e) => {
+                tracing::error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let access_options = if access_mode {
+        match parse_accessibility_options(&args) {
+            Ok(o) => Some(o),
+            Err(e) => {
+                tracing::error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
     let auto = mode_count == 0;
 
     let mut g = if auto {
@@ -85,14 +223,16 @@ async fn main() -> ExitCode {
             }
         }
 
-        match build_gtfs_phase(
+        let started = SystemTime::now();
+        let merged = merge_gtfs_phase(
             osm_graph,
             &config.build,
             &cache_dir,
-            false,
+            Refresh::Cached,
             config.default_routing.station_merge_radius_m,
             &config.default_routing,
-        ) {
+        );
+        match merged.and_then(|g| finish_transit(&config, &cache_dir, g, save_mode, started)) {
             Some(g) => g,
             None => {
                 tracing::error!("GTFS phase failed");
@@ -101,25 +241,65 @@ async fn main() -> ExitCode {
         }
     } else if update_gtfs_mode {
         let osm_fp = osm_fingerprint(&config, &cache_dir);
-        let osm_graph = match load_osm_graph(&config.build.osm_output, &osm_fp) {
-            Ok(g) => g,
-            Err(e) => {
-                tracing::error!(
-                    "'{}' unusable ({e}) — run '--build --save' first",
-                    config.build.osm_output
-                );
-                return ExitCode::FAILURE;
+        let started = SystemTime::now();
+        // With --feed only that feed is downloaded and read again: it is replaced in place
+        // in transit.bin, the other feeds keeping their stops, trips and ids. Without a
+        // usable transit.bin (or without --feed) the transit layer is rebuilt on top of
+        // osm.bin, the other feeds coming from the download and feed caches.
+        let saved = update_feed.as_deref().and_then(|feed| {
+            match load_transit_graph(&config.build.transit_output, &osm_fp) {
+                Ok(g) => Some((feed, g)),
+                Err(e) => {
+                    tracing::warn!(
+                        "'{}' unusable ({e}); rebuilding the transit layer from osm.bin",
+                        config.build.transit_output
+                    );
+                    None
+                }
+            }
+        });
+        let merged = match saved {
+            Some((feed, g)) => {
+                tracing::info!("replacing feed '{feed}' in place");
+                replace_gtfs_feed(
+                    g,
+                    &config.build,
+                    &cache_dir,
+                    feed,
+                    config.default_routing.station_merge_radius_m,
+                    &config.default_routing,
+                )
+            }
+            None => {
+                let refresh = match &update_feed {
+                    Some(feed) => {
+                        tracing::info!("refreshing feed '{feed}'; rebuilding the transit layer from osm.bin and cached feeds");
+                        Refresh::Feed(feed)
+                    }
+                    None => Refresh::All,
+                };
+                let osm_graph = match load_osm_graph(&config.build.osm_output, &osm_fp) {
+                    Ok(g) => g,
+                    Err(e) => {
+                        tracing::error!(
+                            "'{}' unusable ({e}) — run '--build --save' first",
+                            config.build.osm_output
+                        );
+                        return ExitCode::FAILURE;
+                    }
+                };
+                merge_gtfs_phase(
+                    osm_graph,
+                    &config.build,
+                    &cache_dir,
+                    refresh,
+                    config.default_routing.station_merge_radius_m,
+                    &config.default_routing,
+                )
             }
         };
 
-        match build_gtfs_phase(
-            osm_graph,
-            &config.build,
-            &cache_dir,
-            true,
-            config.default_routing.station_merge_radius_m,
-            &config.default_routing,
-        ) {
+        match merged.and_then(|g| finish_transit(&config, &cache_dir, g, save_mode, started)) {
             Some(g) => g,
             None => {
                 tracing::error!("GTFS phase failed");
@@ -127,8 +307,21 @@ async fn main() -> ExitCode {
             }
         }
     } else {
-        let graph_fp = graph_fingerprint(&config, &cache_dir);
-        match maas_rs::services::persistence::load_graph(&config.build.output, &graph_fp) {
+        // An explicit file or a snapshot is a rollback: built from other inputs, so only
+        // the schema is checked, not the fingerprint.
+        let restored = match (restore.flatten(), &config.build.snapshots.output) {
+            (Some(path), _) => load_graph_unchecked(&path).map_err(|e| format!("{path}: {e}")),
+            (None, Some(dir)) => load_newest_snapshot(dir).map(|(path, g)| {
+                tracing::info!("restored snapshot {path}");
+                g
+            }),
+            (None, None) => {
+                let graph_fp = graph_fingerprint(&config, &cache_dir);
+                maas_rs::services::persistence::load_graph(&config.build.output, &graph_fp)
+                    .map_err(|e| e.to_string())
+            }
+        };
+        match restored {
             Ok(g) => g,
             Err(e) => {
                 tracing::error!("{e}");
@@ -156,16 +349,77 @@ async fn main() -> ExitCode {
             tracing::error!("{e}");
             return ExitCode::FAILURE;
         }
+        snapshot_saved_graph(&config.build.output, &config.build.snapshots);
         if let Err(e) = save_last_checked(&cache_dir, Local::now()) {
             tracing::warn!("failed to persist last_checked: {e}");
         }
     }
 
-    if !auto && !serve_mode {
+    if validate_mode {
+        let report = g.validate();
+        println!("{report}");
+        return if report.has_errors() {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+
+    if let Some(opts) = bench_options {
+        tracing::info!("benchmarking {} random OD pairs (seed {})", opts.samples, opts.seed);
+        println!("{}", maas_rs::services::bench::run(&g, &opts));
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(opts) = export_options {
+        return match maas_rs::services::travel_export::run(&g, &opts) {
+            Ok(summary) => {
+                println!("{summary}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                tracing::error!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(opts) = access_options {
+        return match maas_rs::services::accessibility::run(&g, &opts) {
+            Ok(summary) => {
+                println!("{summary}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                tracing::error!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if !auto && !serve_mode && loadtest_options.is_none() {
         return ExitCode::SUCCESS;
     }
 
     let shared: maas_rs::services::scheduler::SharedGraph = Arc::new(ArcSwap::from_pointee(g));
+    if let Some(opts) = loadtest_options {
+        tracing::info!(
+            "load testing {} random OD pairs with {} clients (seed {})",
+            opts.bench.samples,
+            opts.concurrency,
+            opts.bench.seed
+        );
+        return match maas_rs::web::loadtest::run(shared, &opts, config.server.max_concurrent_searches).await {
+            Ok(report) => {
+                println!("{report}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                tracing::error!("load test failed: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
     let config = Arc::new(config);
     if let Err(e) = app::server(shared, config).await {
         tracing::error!("server failed: {e}");
@@ -217,7 +471,7 @@ fn acquire_auto(config: &Config, cache_dir: &str) -> Option<maas_rs::structures:
         osm,
         &config.build,
         cache_dir,
-        false,
+        Refresh::Cached,
         config.default_routing.station_merge_radius_m,
         &config.default_routing,
     )?;
@@ -230,8 +484,9 @@ fn acquire_auto(config: &Config, cache_dir: &str) -> Option<maas_rs::structures:
         tracing::error!("{e}");
         return None;
     }
-    if let Err(e) = save_graph_with_rollback(&g, &plan.graph_fp, &config.build.output) {
-        tracing::error!("{e}");
+    match save_graph_with_rollback(&g, &plan.graph_fp, &config.build.output) {
+        Ok(()) => snapshot_saved_graph(&config.build.output, &config.build.snapshots),
+        Err(e) => tracing::error!("{e}"),
     }
     if let Err(e) = save_last_checked(cache_dir, Local::now()) {
         tracing::warn!("failed to persist last_checked: {e}");
@@ -239,6 +494,25 @@ fn acquire_auto(config: &Config, cache_dir: &str) -> Option<maas_rs::structures:
     Some(g)
 }
 
+/// Finalize a merged transit layer, first saving it as `transit.bin` when `save` is set
+/// so a later `--update-gtfs --feed` can replace one feed in it.
+fn finish_transit(
+    config: &Config,
+    cache_dir: &str,
+    merged: maas_rs::structures::Graph,
+    save: bool,
+    started: SystemTime,
+) -> Option<maas_rs::structures::Graph> {
+    if save {
+        let osm_fp = osm_fingerprint(config, cache_dir);
+        if let Err(e) = save_transit_graph(&merged, &osm_fp, &config.build.transit_output) {
+            tracing::error!("{e}");
+            return None;
+        }
+    }
+    finish_gtfs_phase(merged, &config.build, started)
+}
+
 /// Rebuild the OSM network from scratch and persist it under `osm_fp`.
 fn rebuild_osm(
     config: &Config,
@@ -0,0 +1,149 @@
+//! Routing over a [`GraphSet`]: pick the shard covering both endpoints, or stitch a
+//! plan into a boundary station in the origin's shard with a plan out of it in the
+//! destination's shard.
+
+use chrono::{Days, NaiveTime};
+
+use crate::routing::routing_raptor::{RouteQuery, route as route_shard, snap_distance_m};
+use crate::services::graph_set::GraphSet;
+use crate::structures::plan::{ArrivalScenario, Plan, PlanError};
+use crate::structures::{Graph, RealtimeIndex};
+
+/// A plan plus the shard each of its legs was planned on (node/trip ids in a leg are
+/// only meaningful within that shard's graph).
+#[derive(Debug)]
+pub struct ShardedPlan {
+    pub plan: Plan,
+    pub leg_shards: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardChoice {
+    Within(usize),
+    Across { from: usize, to: usize },
+}
+
+/// The shard covering both endpoints (closest snaps first, then lowest index); else
+/// the closest-snapping shard of each endpoint.
+pub fn select_shard(set: &GraphSet, query: &RouteQuery) -> Result<ShardChoice, PlanError> {
+    let snaps = |lat, lng| -> Vec<Option<f64>> {
+        set.shards.iter().map(|s| snap_distance_m(&s.graph, lat, lng)).collect()
+    };
+    let from = snaps(query.from_lat, query.from_lng);
+    let to = snaps(query.to_lat, query.to_lng);
+    let closest = |d: &[Option<f64>]| {
+        (0..d.len())
+            .filter_map(|i| d[i].map(|m| (i, m)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    };
+    let both: Vec<Option<f64>> = from
+        .iter()
+        .zip(&to)
+        .map(|(f, t)| Some((*f)? + (*t)?))
+        .collect();
+    if let Some(i) = closest(&both) {
+        return Ok(ShardChoice::Within(i));
+    }
+    let outside = |endpoint: &str| {
+        PlanError::new(format!("{endpoint} is outside every shard")).with_code("OUTSIDE_COVERAGE")
+    };
+    Ok(ShardChoice::Across {
+        from: closest(&from).ok_or_else(|| outside("departure"))?,
+        to: closest(&to).ok_or_else(|| outside("arrival"))?,
+    })
+}
+
+/// Plan `query` over the set. `rt[i]` is shard `i`'s realtime index (missing = none).
+/// Cross-shard results hold one stitched plan per usable boundary station, earliest
+/// arrival first; an empty list means no boundary connects the two shards in time.
+pub fn route(
+    set: &GraphSet,
+    query: &RouteQuery,
+    rt: &[&RealtimeIndex],
+) -> Result<Vec<ShardedPlan>, PlanError> {
+    let empty = RealtimeIndex::new();
+    let rt_of = |i: usize| rt.get(i).copied().unwrap_or(&empty);
+    match select_shard(set, query)? {
+        ShardChoice::Within(i) => Ok(route_shard(&set.shards[i].graph, query, rt_of(i))?
+            .into_iter()
+            .map(|plan| ShardedPlan { leg_shards: vec![i; plan.legs.len()], plan })
+            .collect()),
+        ShardChoice::Across { from, to } => {
+            let (ga, gb) = (&set.shards[from].graph, &set.shards[to].graph);
+            let mut out = Vec::new();
+            for (station, loc_a, loc_b) in set.boundaries_between(from, to) {
+                let into = RouteQuery {
+                    to_lat: loc_a.latitude,
+                    to_lng: loc_a.longitude,
+                    to_station_id: Some(station.to_string()),
+                    ..query.clone()
+                };
+                let Some(first) = earliest(route_shard(ga, &into, rt_of(from))?) else {
+                    continue;
+                };
+                let Some((second, day)) =
+                    continue_from(gb, query, station, loc_b, first.end + set.transfer_secs, rt_of(to))?
+                else {
+                    continue;
+                };
+                out.push(stitch(first, second, day, from, to));
+            }
+            out.sort_by_key(|p| (p.plan.end, p.plan.start));
+            Ok(out)
+        }
+    }
+}
+
+fn earliest(plans: Vec<Plan>) -> Option<Plan> {
+    plans.into_iter().min_by_key(|p| (p.end, std::cmp::Reverse(p.start)))
+}
+
+/// Second half: depart `station` no earlier than `at` (query-day clock, may pass
+/// midnight). Returns the plan and how many days after the query date it was planned.
+fn continue_from(
+    g: &Graph,
+    query: &RouteQuery,
+    station: &str,
+    loc: crate::structures::LatLng,
+    at: u32,
+    rt: &RealtimeIndex,
+) -> Result<Option<(Plan, u32)>, PlanError> {
+    let day = at / 86400;
+    let out = RouteQuery {
+        from_lat: loc.latitude,
+        from_lng: loc.longitude,
+        from_station_id: Some(station.to_string()),
+        date: query.date + Days::new(day as u64),
+        time: NaiveTime::from_num_seconds_from_midnight_opt(at % 86400, 0).unwrap_or_default(),
+        window_minutes: None,
+        onboard_origin: None,
+        ..query.clone()
+    };
+    Ok(earliest(route_shard(g, &out, rt)?).map(|p| (p, day)))
+}
+
+fn stitch(first: Plan, second: Plan, day: u32, from: usize, to: usize) -> ShardedPlan {
+    let second = Graph::shift_plan(second, -((day * 86400) as i64));
+    let mut leg_shards = vec![from; first.legs.len()];
+    leg_shards.extend(std::iter::repeat_n(to, second.legs.len()));
+    let mut legs = first.legs;
+    legs.extend(second.legs);
+    let price = None; // fares are per-graph; a cross-shard total is not computed
+    let mut plan = Plan {
+        legs,
+        start: first.start,
+        end: second.end,
+        mode: first.mode,
+        access_alternatives: Vec::new(),
+        arrival_distribution: vec![ArrivalScenario { time: second.end, probability: 1.0 }],
+        expected_end: second.expected_end,
+        price,
+        debug: None,
+        warnings: Vec::new(),
+        itinerary_id: None,
+    };
+    // The wait at the boundary station spans the two halves.
+    plan.fill_waiting_times();
+    ShardedPlan { plan, leg_shards }
+}
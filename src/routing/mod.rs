@@ -1 +1,5 @@
+pub mod federation;
+pub mod paging;
+pub mod plan_share;
 pub mod routing_raptor;
+pub mod via;
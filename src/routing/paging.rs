@@ -0,0 +1,127 @@
+//! Earlier/later itinerary pages over one `RouteQuery`. A page remembers the
+//! departure range it covered; the next page searches the adjacent range of the same
+//! width and keeps only itineraries outside what was already shown, so a client can
+//! scroll without re-solving (or re-sending) the whole window.
+
+use chrono::{Duration, NaiveDate, NaiveTime, Timelike};
+
+use crate::{
+    routing::{
+        plan_share,
+        routing_raptor::{RouteQuery, route, with_snap_warnings},
+    },
+    structures::{Graph, RealtimeIndex, plan::{Plan, PlanError}},
+};
+
+/// Page width when the query has no `window_minutes` of its own.
+pub const DEFAULT_PAGE_WINDOW_MINUTES: u32 = 60;
+
+const DAY_SECS: i64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageDirection {
+    Earlier,
+    Later,
+}
+
+/// What a page covered: the departure range `[lo, hi)` in seconds from midnight of
+/// `query.date` (negative or past 24 h once paging crosses midnight).
+#[derive(Clone)]
+pub struct PageState {
+    pub query: RouteQuery,
+    pub lo: i64,
+    pub hi: i64,
+}
+
+fn page_window_secs(graph: &Graph, query: &RouteQuery) -> i64 {
+    let minutes = query
+        .window_minutes
+        .filter(|&w| w > 0)
+        .unwrap_or(DEFAULT_PAGE_WINDOW_MINUTES);
+    (minutes as i64 * 60).min(graph.raptor.max_window_secs as i64).max(60)
+}
+
+/// `start` of the latest plan, moved by `offset` into the original query date.
+fn latest_start(plans: &[Plan], offset: i64) -> Option<i64> {
+    plans.iter().map(|p| p.start as i64 + offset).max()
+}
+
+/// The query's own search; its state covers at least one page width from `time`.
+pub fn first_page(
+    graph: &Graph,
+    query: &RouteQuery,
+    rt: &RealtimeIndex,
+) -> Result<(Vec<Plan>, PageState), PlanError> {
+    let plans = with_snap_warnings(graph, query, route(graph, query, rt)?);
+    let plans = plan_share::with_itinerary_ids(graph, query, plans);
+    let lo = query.time.num_seconds_from_midnight() as i64;
+    let hi = (lo + page_window_secs(graph, query)).max(latest_start(&plans, 0).map_or(lo, |s| s + 1));
+    Ok((plans, PageState { query: query.clone(), lo, hi }))
+}
+
+/// The page right before (`Earlier`) or after (`Later`) `state`, and the state that
+/// covers it. Itineraries already inside `state` are dropped.
+pub fn next_page(
+    graph: &Graph,
+    state: &PageState,
+    direction: PageDirection,
+    rt: &RealtimeIndex,
+) -> Result<(Vec<Plan>, PageState), PlanError> {
+    let window = page_window_secs(graph, &state.query);
+    let from = match direction {
+        PageDirection::Earlier => state.lo - window,
+        PageDirection::Later => state.hi,
+    };
+    let (date, time, offset) = shifted_departure(state.query.date, from);
+    let query = RouteQuery {
+        date,
+        time,
+        window_minutes: Some((window / 60) as u32),
+        ..state.query.clone()
+    };
+    let plans = with_snap_warnings(graph, &query, route(graph, &query, rt)?);
+    let mut plans = plan_share::with_itinerary_ids(graph, &query, plans);
+    plans.retain(|p| {
+        let start = p.start as i64 + offset;
+        match direction {
+            PageDirection::Earlier => start >= from && start < state.lo,
+            PageDirection::Later => start >= state.hi,
+        }
+    });
+    let (lo, hi) = match direction {
+        PageDirection::Earlier => (from, state.lo),
+        PageDirection::Later => (
+            state.hi,
+            (state.hi + window).max(latest_start(&plans, offset).map_or(state.hi, |s| s + 1)),
+        ),
+    };
+    Ok((plans, PageState { query: state.query.clone(), lo, hi }))
+}
+
+/// Calendar date and time for `secs` from midnight of `base`, plus the offset that
+/// maps that date's plan times back into `base`'s frame.
+fn shifted_departure(base: NaiveDate, secs: i64) -> (NaiveDate, NaiveTime, i64) {
+    let days = secs.div_euclid(DAY_SECS);
+    let date = base + Duration::days(days);
+    let time = NaiveTime::from_num_seconds_from_midnight_opt(secs.rem_euclid(DAY_SECS) as u32, 0)
+        .unwrap_or(NaiveTime::MIN);
+    (date, time, days * DAY_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifted_departure_crosses_midnight_both_ways() {
+        let base = NaiveDate::from_ymd_opt(2026, 6, 23).unwrap();
+        let (d, t, off) = shifted_departure(base, 9 * 3600);
+        assert_eq!((d, t.num_seconds_from_midnight(), off), (base, 9 * 3600, 0));
+        let (d, t, off) = shifted_departure(base, DAY_SECS + 600);
+        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 6, 24).unwrap());
+        assert_eq!((t.num_seconds_from_midnight(), off), (600, DAY_SECS));
+        let (d, t, off) = shifted_departure(base, -1800);
+        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 6, 22).unwrap());
+        assert_eq!((t.num_seconds_from_midnight(), off), (DAY_SECS as u32 - 1800, -DAY_SECS));
+    }
+}
@@ -0,0 +1,296 @@
+//! Shareable itineraries (`Plan.itineraryId`, `planById`). An id is the unpadded
+//! base64url of a versioned postcard record: the search that found the plan (endpoints,
+//! date, time, modes, routing profile) and the plan's shape, each transit leg named by
+//! stable trip and stop ids and its service day so it survives a rebuild. Re-hydrating
+//! replays the search on the current data and realtime and returns the plan with the
+//! same shape; when none is found any more (a trip was canceled or removed, or retimed
+//! so that another day's run is the one caught) it fails with code
+//! `ITINERARY_UNAVAILABLE`.
+//!
+//! Every other option that changes which plans are found (`windowMinutes`, walk limits,
+//! `avoidStairs`, bike and fare profiles, …) is carried too and replayed as given.
+//! One-off simulations (`whatIf`) and `via` hops are not.
+
+use chrono::{NaiveDate, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::routing::routing_raptor::{FareProfile, NO_ROUTE, RouteQuery, route};
+use crate::structures::plan::{Plan, PlanError, PlanLeg, PlanLegStep};
+use crate::structures::{BikeProfile, Graph, Mode, RealtimeIndex};
+
+const FORMAT_VERSION: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SharedItinerary {
+    /// Endpoints in microdegrees.
+    from: (i32, i32),
+    to: (i32, i32),
+    /// Days since 0001-01-01 (`num_days_from_ce`).
+    date: i32,
+    time: u32,
+    /// `Mode::ALL` indices; `None` = the default modes.
+    modes: Option<Vec<u8>>,
+    profile: Option<String>,
+    options: SharedOptions,
+    shape: Shape,
+}
+
+/// The query's other result-changing options, as [`RouteQuery`] holds them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct SharedOptions {
+    window_minutes: Option<u32>,
+    min_access_secs: Option<u32>,
+    arrival_slack_secs: Option<u32>,
+    unrestricted_transfers: Option<bool>,
+    reliability_bucket_edges: Option<Vec<f32>>,
+    bike_profile: Option<BikeProfile>,
+    terminal_deadline: bool,
+    from_station_id: Option<String>,
+    to_station_id: Option<String>,
+    fare_profile: Option<FareProfile>,
+    avoid_crowding: bool,
+    night_safety: Option<f64>,
+    avoid_stairs: Option<bool>,
+    max_access_walk_m: Option<u32>,
+    max_egress_walk_m: Option<u32>,
+}
+
+impl SharedOptions {
+    fn of(query: &RouteQuery) -> Self {
+        SharedOptions {
+            window_minutes: query.window_minutes,
+            min_access_secs: query.min_access_secs,
+            arrival_slack_secs: query.arrival_slack_secs,
+            unrestricted_transfers: query.unrestricted_transfers,
+            reliability_bucket_edges: query.reliability_bucket_edges.clone(),
+            bike_profile: query.bike_profile,
+            terminal_deadline: query.terminal_deadline,
+            from_station_id: query.from_station_id.clone(),
+            to_station_id: query.to_station_id.clone(),
+            fare_profile: query.fare_profile,
+            avoid_crowding: query.avoid_crowding,
+            night_safety: query.night_safety,
+            avoid_stairs: query.avoid_stairs,
+            max_access_walk_m: query.max_access_walk_m,
+            max_egress_walk_m: query.max_egress_walk_m,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Shape {
+    mode: u8,
+    legs: Vec<SharedLeg>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum SharedLeg {
+    Street { mode: u8 },
+    /// `date`: the run's service day (`date_to_days`).
+    Transit { trip: String, board: String, alight: String, date: u32 },
+}
+
+fn mode_index(mode: Mode) -> u8 {
+    Mode::ALL.iter().position(|&m| m == mode).unwrap_or(0) as u8
+}
+
+fn micro(deg: f64) -> i32 {
+    (deg * 1e6).round() as i32
+}
+
+/// The plan's legs as stable ids; `None` when a stop or trip has no stable id.
+fn shape_of(graph: &Graph, plan: &Plan) -> Option<Shape> {
+    let stop_of = |node: crate::structures::NodeID| {
+        let stop = *graph.raptor.transit_node_to_stop.get(node.0)?;
+        graph.stop_stable_id((stop != u32::MAX).then_some(stop as usize)?)
+    };
+    let legs = plan
+        .legs
+        .iter()
+        .map(|leg| match leg {
+            PlanLeg::Transit(t) => Some(SharedLeg::Transit {
+                trip: graph.trip_stable_id(t.trip_id)?,
+                board: stop_of(t.from.node_id)?,
+                alight: stop_of(t.to.node_id)?,
+                date: t.steps.iter().find_map(|s| match s {
+                    PlanLegStep::Transit(s) => Some(s.date),
+                    PlanLegStep::Walk(_) => None,
+                })?,
+            }),
+            street => Some(SharedLeg::Street { mode: mode_index(street.as_street()?.street_mode) }),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Shape { mode: mode_index(plan.mode), legs })
+}
+
+/// `itineraryId` of `plan`, found by `query`.
+pub fn itinerary_id(graph: &Graph, query: &RouteQuery, plan: &Plan) -> Option<String> {
+    let shared = SharedItinerary {
+        from: (micro(query.from_lat), micro(query.from_lng)),
+        to: (micro(query.to_lat), micro(query.to_lng)),
+        date: chrono::Datelike::num_days_from_ce(&query.date),
+        time: query.time.num_seconds_from_midnight(),
+        modes: query.modes.as_ref().map(|m| m.iter().map(|&m| mode_index(m)).collect()),
+        profile: query.profile.clone(),
+        options: SharedOptions::of(query),
+        shape: shape_of(graph, plan)?,
+    };
+    let mut bytes = vec![FORMAT_VERSION];
+    bytes.extend(postcard::to_allocvec(&shared).ok()?);
+    Some(base64url_encode(&bytes))
+}
+
+/// Sets `itinerary_id` on every plan of `query`.
+pub fn with_itinerary_ids(graph: &Graph, query: &RouteQuery, mut plans: Vec<Plan>) -> Vec<Plan> {
+    for plan in &mut plans {
+        plan.itinerary_id = itinerary_id(graph, query, plan);
+    }
+    plans
+}
+
+fn invalid() -> PlanError {
+    PlanError::new("malformed itinerary id").with_code("INVALID_ITINERARY_ID")
+}
+
+fn decode(id: &str) -> Result<SharedItinerary, PlanError> {
+    let bytes = base64url_decode(id).ok_or_else(invalid)?;
+    match bytes.split_first() {
+        Some((&FORMAT_VERSION, rest)) => postcard::from_bytes(rest).map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
+/// Re-hydrate `id` against the current data and realtime.
+pub fn plan_by_id(graph: &Graph, id: &str, rt: &RealtimeIndex) -> Result<Plan, PlanError> {
+    let shared = decode(id)?;
+    let mode = |i: u8| Mode::ALL.get(i as usize).copied().ok_or_else(invalid);
+    let modes = match &shared.modes {
+        Some(m) => Some(m.iter().map(|&i| mode(i)).collect::<Result<Vec<_>, _>>()?),
+        None => None,
+    };
+    let options = shared.options.clone();
+    let query = RouteQuery {
+        from_lat: shared.from.0 as f64 / 1e6,
+        from_lng: shared.from.1 as f64 / 1e6,
+        to_lat: shared.to.0 as f64 / 1e6,
+        to_lng: shared.to.1 as f64 / 1e6,
+        date: NaiveDate::from_num_days_from_ce_opt(shared.date).ok_or_else(invalid)?,
+        time: NaiveTime::from_num_seconds_from_midnight_opt(shared.time, 0).ok_or_else(invalid)?,
+        window_minutes: options.window_minutes,
+        min_access_secs: options.min_access_secs,
+        arrival_slack_secs: options.arrival_slack_secs,
+        unrestricted_transfers: options.unrestricted_transfers,
+        use_cch_access: None,
+        reliability_bucket_edges: options.reliability_bucket_edges,
+        modes,
+        bike_profile: options.bike_profile,
+        terminal_deadline: options.terminal_deadline,
+        onboard_origin: None,
+        from_station_id: options.from_station_id,
+        to_station_id: options.to_station_id,
+        profile_latency: None,
+        fare_profile: options.fare_profile,
+        avoid_crowding: options.avoid_crowding,
+        profile: shared.profile.clone(),
+        what_if: None,
+        night_safety: options.night_safety,
+        avoid_stairs: options.avoid_stairs,
+        max_access_walk_m: options.max_access_walk_m,
+        max_egress_walk_m: options.max_egress_walk_m,
+        search_profile: None,
+    };
+    let unavailable = || {
+        PlanError::new("this itinerary is no longer available").with_code("ITINERARY_UNAVAILABLE")
+    };
+    let plans = match route(graph, &query, rt) {
+        Ok(plans) => plans,
+        Err(e) if e.code.is_none_or(|c| c == NO_ROUTE) => return Err(unavailable()),
+        Err(e) => return Err(e),
+    };
+    let mut plan = plans
+        .into_iter()
+        .find(|p| shape_of(graph, p).as_ref() == Some(&shared.shape))
+        .ok_or_else(unavailable)?;
+    plan.itinerary_id = Some(id.to_string());
+    Ok(plan)
+}
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+fn base64url_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let v = ALPHABET.iter().position(|&a| a == c)? as u32;
+            n |= v << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64url_round_trips_every_tail_length() {
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 97 + 251) as u8).collect();
+            let s = base64url_encode(&bytes);
+            assert!(s.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+            assert_eq!(base64url_decode(&s), Some(bytes));
+        }
+        assert_eq!(base64url_decode("A"), None);
+        assert_eq!(base64url_decode("AB=C"), None);
+    }
+
+    #[test]
+    fn ids_carry_a_version_and_reject_garbage() {
+        let shared = SharedItinerary {
+            from: (50_850_000, 4_350_000),
+            to: (50_840_000, 4_360_000),
+            date: 739_000,
+            time: 8 * 3600,
+            modes: Some(vec![mode_index(Mode::WalkTransit)]),
+            profile: Some("senior".into()),
+            options: SharedOptions { window_minutes: Some(60), avoid_stairs: Some(true), ..Default::default() },
+            shape: Shape {
+                mode: mode_index(Mode::WalkTransit),
+                legs: vec![
+                    SharedLeg::Street { mode: mode_index(Mode::Walk) },
+                    SharedLeg::Transit {
+                        trip: "gtfs:trip:T1".into(),
+                        board: "gtfs:stop:A".into(),
+                        alight: "gtfs:stop:B".into(),
+                        date: 9_670,
+                    },
+                ],
+            },
+        };
+        let mut bytes = vec![FORMAT_VERSION];
+        bytes.extend(postcard::to_allocvec(&shared).unwrap());
+        assert_eq!(decode(&base64url_encode(&bytes)).unwrap(), shared);
+        bytes[0] = FORMAT_VERSION + 1;
+        let err = decode(&base64url_encode(&bytes)).unwrap_err();
+        assert_eq!(err.code, Some("INVALID_ITINERARY_ID"));
+        assert!(decode("not base64!").is_err());
+    }
+}
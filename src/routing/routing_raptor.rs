@@ -1,12 +1,25 @@
 use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
 
 use crate::ingestion::gtfs::date_to_days;
-use crate::structures::plan::{ExplainResult, Plan};
+use std::sync::Arc;
+
+use crate::structures::{
+    query_budget, query_profile, query_trace, rush_hour, search_profiles, what_if,
+};
+use crate::structures::plan::{
+    ExplainResult, Plan, PlanDebug, PlanError, PlanLeg, PlanWarning, SnapDebug,
+};
+use gtfs_structures::RouteType;
+
 use crate::structures::{
-    ActiveModes, Graph, Mode, RealtimeIndex, ReliabilityBuckets,
-    valid_reliability_edges,
+    ActiveModes, Graph, Mode, ModePreference, OUTSIDE_FEED_VALIDITY, RealtimeIndex, ReliabilityBuckets, ResolvedWhatIf, RoutingProfile,
+    StreetProfile, WhatIf, valid_reliability_edges,
 };
 
+/// Code of a search that ran to completion without finding any plan.
+pub const NO_ROUTE: &str = "NO_ROUTE";
+
+#[derive(Clone)]
 pub struct RouteQuery {
     pub from_lat: f64,
     pub from_lng: f64,
@@ -28,9 +41,26 @@ pub struct RouteQuery {
     pub to_station_id: Option<String>,
     pub profile_latency: Option<bool>,
     pub fare_profile: Option<FareProfile>,
+    /// Re-rank plans by arrival plus `crowding_penalty_secs` per crowding step of
+    /// each transit leg's live occupancy.
+    pub avoid_crowding: bool,
+    /// Named routing profile (`default_routing.routing_profiles`); `None` = graph defaults.
+    pub profile: Option<String>,
+    /// Routes, trips, stops and street segments closed for this request only.
+    pub what_if: Option<WhatIf>,
+    /// 0–1 preference for lit streets on walk legs; overrides the profile's.
+    pub night_safety: Option<f64>,
+    /// Never walk stairs (escalators stay usable); overrides the profile's.
+    pub avoid_stairs: Option<bool>,
+    /// Farthest walk (metres) from the origin to a first stop; overrides the profile's.
+    pub max_access_walk_m: Option<u32>,
+    /// Farthest walk (metres) from a last stop to the destination; overrides the profile's.
+    pub max_egress_walk_m: Option<u32>,
+    /// Precomputed street-search profile (`default_routing.search_profiles`).
+    pub search_profile: Option<String>,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct FareProfile {
     pub category: crate::structures::cost::PassengerCategory,
     pub stib_subscription: bool,
@@ -77,13 +107,43 @@ fn resolve_fare_profile(query: &RouteQuery) -> crate::structures::cost::FareProf
         .unwrap_or_default()
 }
 
+fn resolve_routing_profile(graph: &Graph, query: &RouteQuery) -> Result<RoutingProfile, PlanError> {
+    match &query.profile {
+        None => Ok(RoutingProfile::default()),
+        Some(name) => graph.routing_profile(name).copied().ok_or_else(|| {
+            PlanError::new(format!("Unknown routing profile {name}")).with_code("UNKNOWN_PROFILE")
+        }),
+    }
+}
+
+fn resolve_search_profile(graph: &Graph, name: Option<&str>) -> Result<Option<usize>, PlanError> {
+    name.map(|name| {
+        graph.search_profile_index(name).ok_or_else(|| {
+            PlanError::new(format!("Unknown search profile {name}")).with_code("UNKNOWN_SEARCH_PROFILE")
+        })
+    })
+    .transpose()
+}
+
+/// The CCH metric bakes the graph walking speed and every stair, so a profile
+/// overriding either falls back to the query-time foot search.
+fn resolve_use_cch(graph: &Graph, query: &RouteQuery) -> bool {
+    query
+        .use_cch_access
+        .unwrap_or(graph.raptor.use_cch_access)
+        && query_profile::walking_speed().is_none()
+        && !query_profile::avoid_stairs()
+        && !what_if::streets_closed()
+}
+
 fn resolve_tuning(
     graph: &Graph,
     query: &RouteQuery,
-) -> Result<(ReliabilityBuckets, u32), async_graphql::Error> {
+    profile: &RoutingProfile,
+) -> Result<(ReliabilityBuckets, u32), PlanError> {
     let buckets = match &query.reliability_bucket_edges {
         Some(edges) if !valid_reliability_edges(edges) => {
-            return Err(async_graphql::Error::new(
+            return Err(PlanError::new(
                 "reliabilityBucketEdges must be sorted, strictly increasing, each in (0,1)",
             ));
         }
@@ -92,14 +152,15 @@ fn resolve_tuning(
     };
     let slack = query
         .arrival_slack_secs
+        .or(profile.transfer_slack_secs)
         .unwrap_or(graph.raptor.arrival_slack_secs);
     Ok((buckets, slack))
 }
 
-fn resolve_modes(query: &RouteQuery) -> Result<ActiveModes, async_graphql::Error> {
+fn resolve_modes(query: &RouteQuery) -> Result<ActiveModes, PlanError> {
     match &query.modes {
         None => Ok(ActiveModes::default()),
-        Some(m) if m.is_empty() => Err(async_graphql::Error::new("modes must not be empty")),
+        Some(m) if m.is_empty() => Err(PlanError::new("modes must not be empty")),
         Some(m) => Ok(ActiveModes::new(m)),
     }
 }
@@ -108,32 +169,61 @@ fn effective_window_secs(window_minutes: u32, max_window_secs: u32) -> u32 {
     window_minutes.saturating_mul(60).min(max_window_secs)
 }
 
+fn check_snap_distance(endpoint: &str, dist_m: f64, max: u32) -> Result<(), PlanError> {
+    if dist_m > max as f64 {
+        return Err(PlanError::new(format!(
+            "{endpoint} is too far from the network (nearest street {:.0} m away, max {} m)",
+            dist_m, max
+        )));
+    }
+    Ok(())
+}
+
 fn arena_snap_node(
     graph: &Graph,
     lat: f64,
     lng: f64,
     endpoint: &str,
-) -> Result<(crate::structures::NodeID, crate::structures::LatLng), async_graphql::Error> {
+) -> Result<(crate::structures::NodeID, crate::structures::LatLng), PlanError> {
+    let no_node = || PlanError::new(format!("No node near {endpoint}"));
+    let max = graph.raptor.max_snap_distance_m;
+    let radius = graph.raptor.edge_snap_radius_m;
     let Some(cg) = graph.contracted.as_ref() else {
-        return Err(async_graphql::Error::new(format!("No node near {endpoint}")));
+        // Uncontracted graph: snap straight onto the nearest walkable street node.
+        let (dist_m, node) = graph
+            .nearest_street_node(lat, lng, StreetProfile::Foot, radius)
+            .ok_or_else(no_node)?;
+        check_snap_distance(endpoint, dist_m, max)?;
+        let loc = graph.get_node(node).ok_or_else(no_node)?.loc();
+        return Ok((node, loc));
     };
-    let radius = graph.raptor.edge_snap_radius_m;
     let (proj, dist_m) = cg
         .arena_snap_proj(lat, lng, radius, |s| s.foot)
-        .ok_or_else(|| async_graphql::Error::new(format!("No node near {endpoint}")))?;
-    let max = graph.raptor.max_snap_distance_m;
-    if dist_m > max as f64 {
-        return Err(async_graphql::Error::new(format!(
-            "{endpoint} is too far from the network (nearest node {:.0} m away, max {} m)",
-            dist_m, max
-        )));
-    }
+        .ok_or_else(no_node)?;
+    check_snap_distance(endpoint, dist_m, max)?;
     let junction = cg
         .foot_bounding_junction(graph, lat, lng, radius)
-        .ok_or_else(|| async_graphql::Error::new(format!("No node near {endpoint}")))?;
+        .ok_or_else(no_node)?;
     Ok((junction, proj))
 }
 
+/// The junction a query endpoint snaps to, i.e. where [`route`] starts its street
+/// search; `None` when it would not snap. Lets callers key on the snapped endpoint.
+pub fn snapped_junction(graph: &Graph, lat: f64, lng: f64) -> Option<crate::structures::NodeID> {
+    arena_snap_node(graph, lat, lng, "endpoint").ok().map(|(n, _)| n)
+}
+
+/// Distance (m) from `(lat, lng)` to where [`route`] would snap it; `None` when the
+/// point lies outside this graph's coverage (beyond `max_snap_distance_m`).
+pub fn snap_distance_m(graph: &Graph, lat: f64, lng: f64) -> Option<f64> {
+    let radius = graph.raptor.edge_snap_radius_m;
+    let dist = match graph.contracted.as_ref() {
+        Some(cg) => cg.arena_snap_proj(lat, lng, radius, |s| s.foot)?.1,
+        None => graph.nearest_street_node(lat, lng, StreetProfile::Foot, radius)?.0,
+    };
+    (dist <= graph.raptor.max_snap_distance_m as f64).then_some(dist)
+}
+
 use crate::structures::QueryEndpoints;
 
 fn resolve_endpoint(
@@ -148,7 +238,7 @@ fn resolve_endpoint(
         crate::structures::LatLng,
         Option<Vec<usize>>,
     ),
-    async_graphql::Error,
+    PlanError,
 > {
     if let Some(id) = station_id
         && let Some((coord, platforms)) = graph.station_endpoint(id)
@@ -163,6 +253,7 @@ fn resolve_endpoint(
 fn resolve_query_params(
     graph: &Graph,
     query: &RouteQuery,
+    profile: &RoutingProfile,
 ) -> Result<
     (
         crate::structures::NodeID,
@@ -173,7 +264,7 @@ fn resolve_query_params(
         u32,
         Option<QueryEndpoints>,
     ),
-    async_graphql::Error,
+    PlanError,
 > {
     let time = query.time.num_seconds_from_midnight();
     let date = date_to_days(query.date);
@@ -208,6 +299,7 @@ fn resolve_query_params(
 
     let min_access = query
         .min_access_secs
+        .or(profile.max_walk_secs)
         .unwrap_or(graph.raptor.min_access_secs);
 
     Ok((origin, destination, time, date, weekday, min_access, endpoints))
@@ -216,9 +308,10 @@ fn resolve_query_params(
 fn route_onboard(
     graph: &Graph,
     query: &RouteQuery,
+    profile: &RoutingProfile,
     onboard: &OnboardOrigin,
     rt: &RealtimeIndex,
-) -> Result<Vec<Plan>, async_graphql::Error> {
+) -> Result<Vec<Plan>, PlanError> {
     let time = query.time.num_seconds_from_midnight();
     let date = date_to_days(query.date);
     let weekday = 1u8 << query.date.weekday().num_days_from_monday();
@@ -233,10 +326,10 @@ fn route_onboard(
 
     let trip = graph
         .trip_index_of(&onboard.trip_id)
-        .ok_or_else(|| async_graphql::Error::new(format!("Unknown trip_id {}", onboard.trip_id)))?;
+        .ok_or_else(|| PlanError::new(format!("Unknown trip_id {}", onboard.trip_id)))?;
     let from_stop = match &onboard.from_stop_id {
         Some(sid) => Some(graph.stop_index_of(sid).ok_or_else(|| {
-            async_graphql::Error::new(format!("Unknown from_stop_id {sid}"))
+            PlanError::new(format!("Unknown from_stop_id {sid}"))
         })?),
         None => None,
     };
@@ -244,20 +337,19 @@ fn route_onboard(
     let (pattern, trip_within, current_pos) = graph
         .locate_onboard_trip(trip, from_stop, onboard.from_stop_seq, time, rt)
         .ok_or_else(|| {
-            async_graphql::Error::new("Could not locate the onboard position (no downstream stops)")
+            PlanError::new("Could not locate the onboard position (no downstream stops)")
         })?;
 
     let ride = graph.build_onboard_ride(pattern, trip_within, current_pos, rt);
 
-    let (buckets, slack) = resolve_tuning(graph, query)?;
+    let (buckets, slack) = resolve_tuning(graph, query, profile)?;
     let unrestricted = query
         .unrestricted_transfers
         .unwrap_or(graph.raptor.unrestricted_transfers);
-    let use_cch = query
-        .use_cch_access
-        .unwrap_or(graph.raptor.use_cch_access);
+    let use_cch = resolve_use_cch(graph, query);
     let egress_secs = query
         .min_access_secs
+        .or(profile.max_walk_secs)
         .unwrap_or(graph.raptor.min_access_secs);
     let am = ActiveModes::new(&[Mode::WalkTransit]);
 
@@ -278,13 +370,45 @@ fn route_onboard(
 
     let bike = crate::structures::BikeCost::new(resolve_bike_profile(graph, query));
     graph.enrich_street_legs(&mut plans, destination, destination, &bike, query.terminal_deadline);
+    plans.iter_mut().for_each(Plan::fill_waiting_times);
+    rank_plans(graph, query, &mut plans);
 
     if plans.is_empty() {
-        return Err(async_graphql::Error::new("No plan found"));
+        return Err(PlanError::new("No plan found").with_code(NO_ROUTE));
     }
     Ok(plans)
 }
 
+/// Orders the Pareto plans the search returned by the crowding penalty (when the query
+/// asks to `avoidCrowding`) and the configured per-mode preferences. The search itself
+/// already ranks labels and plans by the mode preferences (`Plan::ranked_end`); this
+/// puts the survivors in that order.
+fn rank_plans(graph: &Graph, query: &RouteQuery, plans: &mut [Plan]) {
+    let crowding = if query.avoid_crowding { graph.raptor.crowding_penalty_secs } else { 0 };
+    let modes = &graph.raptor.mode_preferences;
+    if crowding > 0 || !modes.is_empty() {
+        rank_by_cost(plans, crowding, modes);
+    }
+}
+
+/// Stable re-rank by arrival plus `crowding_secs` per crowding step of every transit
+/// leg, so a slightly later but emptier departure comes first, plus each leg's
+/// [`ModePreference::extra_secs`]. Legs without occupancy data count as uncrowded;
+/// unlisted modes are neutral.
+fn rank_by_cost(plans: &mut [Plan], crowding_secs: u32, modes: &[(RouteType, ModePreference)]) {
+    plans.sort_by_cached_key(|p| {
+        let crowding: f64 = p
+            .legs
+            .iter()
+            .filter_map(|l| match l {
+                PlanLeg::Transit(t) => Some(t.occupancy.map_or(0, |o| o.crowding_level()) as f64),
+                _ => None,
+            })
+            .sum();
+        (p.end as f64 + crowding * crowding_secs as f64 + p.mode_cost(modes)).round() as i64
+    });
+}
+
 fn now_unix_secs() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -321,32 +445,161 @@ fn gate_realtime<'a>(
     rt
 }
 
+/// Typed error for a search stopped by its budget or by cancellation: the message plus a
+/// `code` (`SEARCH_TIMEOUT` / `SEARCH_EXPANSION_LIMIT` / `SEARCH_CANCELLED`).
+fn exhausted_error(why: query_budget::Exhausted) -> PlanError {
+    PlanError::new(format!(
+        "{why} after {} expansions",
+        query_budget::expansions()
+    ))
+    .with_code(why.code())
+}
+
 pub fn route(
     graph: &Graph,
     query: &RouteQuery,
     rt: &RealtimeIndex,
-) -> Result<Vec<Plan>, async_graphql::Error> {
+) -> Result<Vec<Plan>, PlanError> {
+    let profile = resolve_routing_profile(graph, query)?;
+    let profile = RoutingProfile {
+        night_safety: query.night_safety.or(profile.night_safety),
+        avoid_stairs: query.avoid_stairs.unwrap_or(profile.avoid_stairs),
+        max_access_walk_m: query.max_access_walk_m.or(profile.max_access_walk_m),
+        max_egress_walk_m: query.max_egress_walk_m.or(profile.max_egress_walk_m),
+        ..profile
+    };
+    let closures = match &query.what_if {
+        Some(w) => Some(
+            graph
+                .resolve_what_if(w)
+                .map_err(|e| PlanError::new(e).with_code("UNKNOWN_WHAT_IF_ID"))?,
+        ),
+        None => None,
+    };
+    let search = resolve_search_profile(graph, query.search_profile.as_deref())?;
+    let _profile = query_profile::begin(&profile);
+    let _search = search_profiles::begin(search);
+    let _closures = what_if::begin(closures.as_ref());
+    let _clock = rush_hour::begin(Some(query.time.num_seconds_from_midnight()));
+    let _budget = query_budget::begin(graph.raptor.query_limits());
+    let result = route_budgeted(graph, query, &profile, rt, closures.as_ref());
+    match query_budget::exhausted() {
+        Some(why) => Err(exhausted_error(why)),
+        None => flag_feed_validity(graph, query, result),
+    }
+}
+
+/// [`PlanWarning::code`] for an endpoint farther than `snap_warning_m` from the street
+/// it was snapped to.
+pub const FAR_SNAP: &str = "FAR_SNAP";
+/// [`PlanWarning::code`] for an endpoint snapped onto a street component of fewer than
+/// `snap_island_nodes` nodes, so the plan may start somewhere unreachable on foot.
+pub const SNAPPED_TO_ISLAND: &str = "SNAPPED_TO_ISLAND";
+
+/// Caveats about where the query's coordinates snapped. Station and onboard endpoints
+/// are not snapped by coordinate and get none.
+pub fn snap_warnings(graph: &Graph, query: &RouteQuery) -> Vec<PlanWarning> {
+    let at_station = |id: Option<&str>| id.is_some_and(|id| graph.station_endpoint(id).is_some());
+    let endpoints = [
+        ("origin", query.from_lat, query.from_lng, query.onboard_origin.is_some()
+            || at_station(query.from_station_id.as_deref())),
+        ("destination", query.to_lat, query.to_lng, at_station(query.to_station_id.as_deref())),
+    ];
+    let (max_m, min_nodes) = (graph.raptor.snap_warning_m, graph.raptor.snap_island_nodes);
+    let mut warnings = Vec::new();
+    for (endpoint, lat, lng, skip) in endpoints {
+        if skip {
+            continue;
+        }
+        if max_m > 0
+            && let Some(dist) = snap_distance_m(graph, lat, lng)
+            && dist > max_m as f64
+        {
+            warnings.push(PlanWarning {
+                code: FAR_SNAP.to_string(),
+                message: format!("{endpoint} is {dist:.0} m from the nearest street"),
+                distance_m: Some(dist),
+            });
+        }
+        if min_nodes > 0
+            && let Some(node) = snapped_junction(graph, lat, lng)
+            && graph.on_street_island(node, min_nodes)
+        {
+            warnings.push(PlanWarning {
+                code: SNAPPED_TO_ISLAND.to_string(),
+                message: format!("{endpoint} snapped onto a small street network cut off from the rest"),
+                distance_m: None,
+            });
+        }
+    }
+    warnings
+}
+
+/// `plans` with the [`snap_warnings`] of `query` added. Kept out of [`route`]: the
+/// plan cache shares results between nearby coordinates, and each request gets the
+/// warnings of its own.
+pub fn with_snap_warnings(graph: &Graph, query: &RouteQuery, mut plans: Vec<Plan>) -> Vec<Plan> {
+    if plans.is_empty() {
+        return plans;
+    }
+    let warnings = snap_warnings(graph, query);
+    for plan in &mut plans {
+        plan.warnings.extend(warnings.iter().cloned());
+    }
+    plans
+}
+
+/// Outside every feed's validity: plans carry the warning, and "no plan" becomes an
+/// `OUTSIDE_FEED_VALIDITY` error naming the covered dates.
+fn flag_feed_validity(
+    graph: &Graph,
+    query: &RouteQuery,
+    result: Result<Vec<Plan>, PlanError>,
+) -> Result<Vec<Plan>, PlanError> {
+    let Some(warning) = graph.feed_validity_warning(query.date) else {
+        return result;
+    };
+    match result {
+        Ok(mut plans) => {
+            plans.iter_mut().for_each(|p| p.warnings.push(warning.clone()));
+            Ok(plans)
+        }
+        Err(e) if e.code.is_none_or(|c| c == NO_ROUTE) => {
+            Err(PlanError::new(warning.message).with_code(OUTSIDE_FEED_VALIDITY))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn route_budgeted(
+    graph: &Graph,
+    query: &RouteQuery,
+    profile: &RoutingProfile,
+    rt: &RealtimeIndex,
+    closures: Option<&ResolvedWhatIf>,
+) -> Result<Vec<Plan>, PlanError> {
     let empty = RealtimeIndex::new();
     let rt = gate_realtime(rt, &empty, date_to_days(query.date), now_unix_secs());
+    let closed = closures.map(|c| c.apply(rt));
+    let rt = closed.as_ref().unwrap_or(rt);
     if let Some(onboard) = &query.onboard_origin {
-        return route_onboard(graph, query, onboard, rt);
+        return route_onboard(graph, query, profile, onboard, rt);
     }
     let (origin, destination, time, date, weekday, min_access, endpoints) =
-        resolve_query_params(graph, query)?;
+        resolve_query_params(graph, query, profile)?;
     let ep = endpoints.as_ref();
-    let (buckets, slack) = resolve_tuning(graph, query)?;
+    let (buckets, slack) = resolve_tuning(graph, query, profile)?;
     let unrestricted = query
         .unrestricted_transfers
         .unwrap_or(graph.raptor.unrestricted_transfers);
-    let use_cch = query
-        .use_cch_access
-        .unwrap_or(graph.raptor.use_cch_access);
+    let use_cch = resolve_use_cch(graph, query);
     let am = resolve_modes(query)?;
 
     let profiling = query
         .profile_latency
         .unwrap_or(graph.raptor.profile_latency);
-    let profile_start = crate::structures::latency_profile::begin_query(profiling);
+    let profile_start =
+        crate::structures::latency_profile::begin_query(profiling || query_trace::enabled());
 
     let bike = crate::structures::BikeCost::new(resolve_bike_profile(graph, query));
     let fare_profile = resolve_fare_profile(query);
@@ -398,36 +651,79 @@ pub fn route(
         &bike,
         query.terminal_deadline,
     );
+    plans.iter_mut().for_each(Plan::fill_waiting_times);
+
+    rank_plans(graph, query, &mut plans);
 
     if let Some(profile) = crate::structures::latency_profile::end_query(profile_start) {
-        tracing::info!(target: "latency_profile", "{}", profile.report());
+        query_trace::latency(&profile);
+        if profiling {
+            tracing::info!(target: "latency_profile", "{}", profile.report());
+        }
     }
 
     if plans.is_empty() {
-        return Err(async_graphql::Error::new("No plan found"));
+        return Err(PlanError::new("No plan found").with_code(NO_ROUTE));
     }
 
     Ok(plans)
 }
 
+/// Where `(lat, lng)` snaps for a search, for [`route_debug`].
+fn snap_debug(graph: &Graph, lat: f64, lng: f64) -> Option<SnapDebug> {
+    let (node, loc) = arena_snap_node(graph, lat, lng, "endpoint").ok()?;
+    Some(SnapDebug {
+        node: node.0,
+        lat: loc.latitude,
+        lon: loc.longitude,
+        distance_m: snap_distance_m(graph, lat, lng)?,
+    })
+}
+
+/// [`route`] with a [`PlanDebug`] (snaps, expansions, prunes by reason, heuristic
+/// tightness, per-phase latency) attached to every plan.
+pub fn route_debug(
+    graph: &Graph,
+    query: &RouteQuery,
+    rt: &RealtimeIndex,
+) -> Result<Vec<Plan>, PlanError> {
+    let start = std::time::Instant::now();
+    query_trace::begin(true);
+    let result = route(graph, query, rt);
+    let trace = query_trace::end().unwrap_or_default();
+    let mut plans = result?;
+    let debug = Arc::new(PlanDebug::new(
+        snap_debug(graph, query.from_lat, query.from_lng),
+        snap_debug(graph, query.to_lat, query.to_lng),
+        query_budget::expansions(),
+        trace,
+        start.elapsed().as_secs_f64() * 1000.0,
+    ));
+    plans.iter_mut().for_each(|p| p.debug = Some(debug.clone()));
+    Ok(plans)
+}
+
 /// Unlike `route`, does NOT error on empty results (empty is itself a debug signal).
 pub fn route_explain(
     graph: &Graph,
     query: &RouteQuery,
     rt: &RealtimeIndex,
-) -> Result<ExplainResult, async_graphql::Error> {
+) -> Result<ExplainResult, PlanError> {
+    let profile = &resolve_routing_profile(graph, query)?;
+    let search = resolve_search_profile(graph, query.search_profile.as_deref())?;
+    let _profile = query_profile::begin(profile);
+    let _search = search_profiles::begin(search);
+    let _clock = rush_hour::begin(Some(query.time.num_seconds_from_midnight()));
     let empty = RealtimeIndex::new();
     let rt = gate_realtime(rt, &empty, date_to_days(query.date), now_unix_secs());
     let (origin, destination, time, date, weekday, min_access, endpoints) =
-        resolve_query_params(graph, query)?;
+        resolve_query_params(graph, query, profile)?;
     let ep = endpoints.as_ref();
-    let (buckets, slack) = resolve_tuning(graph, query)?;
+    let (buckets, slack) = resolve_tuning(graph, query, profile)?;
     let unrestricted = query
         .unrestricted_transfers
         .unwrap_or(graph.raptor.unrestricted_transfers);
-    let use_cch = query
-        .use_cch_access
-        .unwrap_or(graph.raptor.use_cch_access);
+    let use_cch = resolve_use_cch(graph, query);
     let am = resolve_modes(query)?;
 
     // The explain path deliberately skips the overnight pass (it would complicate
@@ -482,13 +778,69 @@ pub fn route_explain(
         &bike,
         query.terminal_deadline,
     );
+    result.plans.iter_mut().for_each(Plan::fill_waiting_times);
 
     Ok(result)
 }
 
+/// A street-only request: one walk, bike or car leg, no transit.
+#[derive(Clone)]
+pub struct StreetRouteQuery {
+    pub from_lat: f64,
+    pub from_lng: f64,
+    pub to_lat: f64,
+    pub to_lng: f64,
+    pub time: NaiveTime,
+    /// `Walk`, `Bike` or `Car`; any other mode is rejected.
+    pub mode: Mode,
+    pub bike_profile: Option<crate::structures::BikeProfile>,
+    /// Precomputed street-search profile (`default_routing.search_profiles`).
+    pub search_profile: Option<String>,
+}
+
+/// Direct street route for `query.mode`, skipping the transit search entirely: the
+/// single leg highlights the balanced option, the rest of the front are its
+/// `alternatives`. Same snapping and search budget as [`route`].
+pub fn street_route(graph: &Graph, query: &StreetRouteQuery) -> Result<Plan, PlanError> {
+    use crate::structures::cost::{LegRole, RoutingMode};
+    let mode = match query.mode {
+        Mode::Walk => RoutingMode::Walk,
+        Mode::Bike => RoutingMode::Bike,
+        Mode::Car => RoutingMode::Drive,
+        other => {
+            return Err(PlanError::new(format!(
+                "streetRoute mode must be WALK, BIKE or CAR, got {}",
+                other.name()
+            )));
+        }
+    };
+    let (origin, _) = arena_snap_node(graph, query.from_lat, query.from_lng, "origin")?;
+    let (destination, _) = arena_snap_node(graph, query.to_lat, query.to_lng, "destination")?;
+    let bike = crate::structures::BikeCost::new(
+        query.bike_profile.unwrap_or(graph.raptor.bike_profile),
+    );
+
+    let search = resolve_search_profile(graph, query.search_profile.as_deref())?;
+    let _search = search_profiles::begin(search);
+    let _budget = query_budget::begin(graph.raptor.query_limits());
+    let plan = graph.multiobj_direct_plan(
+        origin,
+        destination,
+        mode,
+        LegRole::Neutral,
+        &bike,
+        query.time.num_seconds_from_midnight(),
+    );
+    if let Some(why) = query_budget::exhausted() {
+        return Err(exhausted_error(why));
+    }
+    plan.ok_or_else(|| PlanError::new("No street route found").with_code(NO_ROUTE))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::structures::plan::PlanBikeLeg;
     use crate::structures::{LatLng, NodeData, NodeID, OsmNodeData};
 
     fn enable_contraction(g: &mut Graph) {
@@ -548,11 +900,117 @@ mod tests {
             to_station_id: None,
             profile_latency: None,
             fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
         }
     }
 
     use crate::ingestion::gtfs::TripId;
 
+    fn one_leg_plan(end: u32, occupancy: Option<crate::structures::Occupancy>) -> Plan {
+        use crate::structures::NodeID;
+        use crate::structures::plan::{ArrivalScenario, PlanPlace, PlanTransitLeg, RealtimeState};
+        let place = |t| PlanPlace {
+            node_id: NodeID(0),
+            stop_position: None,
+            arrival: Some(t),
+            departure: Some(t),
+            language: None,
+        };
+        let leg = PlanTransitLeg {
+            length: 0,
+            start: end - 600,
+            end,
+            duration: 600,
+            scheduled_start: end - 600,
+            scheduled_end: end,
+            realtime: false,
+            realtime_state: RealtimeState::Scheduled,
+            occupancy,
+            from: place(end - 600),
+            to: place(end),
+            steps: vec![],
+            geometry: vec![],
+            transfer_risk: None,
+            trip_id: TripId(0),
+            preceding_arrival: None,
+            preceding_route_type: None,
+            route_type: None,
+            following_route_type: None,
+            following_margin_secs: None,
+            bikes_allowed: None,
+            time_shift: 0,
+            waiting_time: 0,
+            display: Default::default(),
+        };
+        Plan {
+            legs: vec![PlanLeg::Transit(leg)],
+            start: end - 600,
+            end,
+            mode: Mode::WalkTransit,
+            access_alternatives: vec![],
+            arrival_distribution: vec![ArrivalScenario {
+                time: end,
+                probability: 1.0,
+            }],
+            expected_end: end,
+            price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
+        }
+    }
+
+    #[test]
+    fn crowding_rank_prefers_later_emptier_departure() {
+        use crate::structures::Occupancy;
+        let mut plans = vec![
+            one_leg_plan(1000, Some(Occupancy::Full)),
+            one_leg_plan(1300, Some(Occupancy::ManySeatsAvailable)),
+            one_leg_plan(2500, None),
+        ];
+        rank_by_cost(&mut plans, 300, &[]);
+        let ends: Vec<u32> = plans.iter().map(|p| p.end).collect();
+        // Full costs 3 × 300 s: 1900 ranks after the seated 1300, before 2500.
+        assert_eq!(ends, vec![1300, 1000, 2500]);
+
+        rank_by_cost(&mut plans, 0, &[]);
+        let ends: Vec<u32> = plans.iter().map(|p| p.end).collect();
+        assert_eq!(ends, vec![1000, 1300, 2500], "no penalty ranks by arrival");
+    }
+
+    #[test]
+    fn mode_preferences_rank_a_later_metro_before_a_bus() {
+        let with_mode = |end, rt| {
+            let mut p = one_leg_plan(end, None);
+            if let PlanLeg::Transit(t) = &mut p.legs[0] {
+                t.route_type = Some(rt);
+            }
+            p
+        };
+        let mut plans = vec![with_mode(1000, RouteType::Bus), with_mode(1200, RouteType::Subway)];
+        let bus = ModePreference { cost_multiplier: 1.5, board_penalty_secs: 0 };
+        rank_by_cost(&mut plans, 0, &[(RouteType::Bus, bus)]);
+        let ends: Vec<u32> = plans.iter().map(|p| p.end).collect();
+        // The 600 s bus ride counts as 900 s: 1300 ranks after the metro's 1200.
+        assert_eq!(ends, vec![1200, 1000]);
+
+        let metro = ModePreference { cost_multiplier: 0.5, board_penalty_secs: 0 };
+        rank_by_cost(&mut plans, 0, &[(RouteType::Subway, metro)]);
+        assert_eq!(plans[0].end, 1200, "a discounted metro ride ranks at 900 s");
+
+        let bus = ModePreference { cost_multiplier: 1.0, board_penalty_secs: 100 };
+        rank_by_cost(&mut plans, 0, &[(RouteType::Bus, bus)]);
+        let ends: Vec<u32> = plans.iter().map(|p| p.end).collect();
+        assert_eq!(ends, vec![1000, 1200], "a 100 s board penalty is not enough");
+    }
+
     /// Non-empty snapshot (one delay) so it does NOT hit the `is_empty()`
     /// short-circuit; the staleness/date checks are actually exercised.
     fn rt_snapshot(gen_unix: i64, ttl: i64) -> RealtimeIndex {
@@ -679,6 +1137,123 @@ mod tests {
         );
     }
 
+    fn street_query(mode: Mode) -> StreetRouteQuery {
+        StreetRouteQuery {
+            from_lat: 50.0,
+            from_lng: 4.0,
+            to_lat: 50.002,
+            to_lng: 4.003,
+            time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            mode,
+            bike_profile: None,
+            search_profile: None,
+        }
+    }
+
+    #[test]
+    fn street_route_returns_one_street_leg_with_its_turns() {
+        use crate::structures::plan::{TurnDirection, turn_instructions};
+        let mut g = Graph::new();
+        let mut node = |eid: &str, lat: f64, lon: f64| {
+            g.add_node(NodeData::OsmNode(OsmNodeData {
+                eid: eid.to_string(),
+                lat_lng: LatLng { latitude: lat, longitude: lon },
+            }))
+        };
+        // North 222 m, then east 214 m.
+        let a = node("a", 50.0, 4.0);
+        let b = node("b", 50.002, 4.0);
+        let c = node("c", 50.002, 4.003);
+        for (o, d, len) in [(a, b, 222), (b, c, 214)] {
+            street(&mut g, o, d, len, true, true);
+            street(&mut g, d, o, len, true, true);
+        }
+        g.build_raptor_index();
+        enable_contraction(&mut g);
+
+        let plan = street_route(&g, &street_query(Mode::Walk)).unwrap();
+        assert_eq!(plan.mode, Mode::Walk);
+        assert_eq!(plan.start, 28_800);
+        let [PlanLeg::Walk(leg)] = plan.legs.as_slice() else {
+            panic!("expected a single street leg, got {:?}", plan.legs)
+        };
+        assert_eq!(leg.length, 436);
+        assert_eq!(plan.end, 28_800 + leg.duration);
+        let turns: Vec<TurnDirection> =
+            turn_instructions(&leg.geometry).iter().map(|i| i.direction).collect();
+        assert_eq!(turns, vec![TurnDirection::Depart, TurnDirection::Right, TurnDirection::Arrive]);
+
+        let bike = street_route(&g, &street_query(Mode::Bike)).unwrap();
+        assert_eq!(bike.mode, Mode::Bike);
+        assert!(bike.end < plan.end, "cycling beats walking the same streets");
+
+        let err = street_route(&g, &street_query(Mode::WalkTransit)).unwrap_err();
+        assert!(err.message.contains("WALK_TRANSIT"), "{}", err.message);
+    }
+
+    /// Canal case: the nearest NODE is on the far bank, but the nearest EDGE runs right
+    /// past the point; the endpoint must link onto that edge's projection.
+    #[test]
+    fn endpoint_links_to_nearest_edge_not_nearest_node() {
+        let mut g = Graph::new();
+        let mut node = |eid: &str, lat: f64, lon: f64| {
+            g.add_node(NodeData::OsmNode(OsmNodeData {
+                eid: eid.to_string(),
+                lat_lng: LatLng {
+                    latitude: lat,
+                    longitude: lon,
+                },
+            }))
+        };
+        let a1 = node("a1", 50.0, 4.000);
+        let a2 = node("a2", 50.0, 4.004);
+        let b1 = node("b1", 50.0004, 4.002);
+        let b2 = node("b2", 50.0004, 4.006);
+        for (o, d, len) in [(a1, a2, 286), (b1, b2, 286)] {
+            street(&mut g, o, d, len, true, true);
+            street(&mut g, d, o, len, true, true);
+        }
+        g.build_raptor_index();
+        enable_contraction(&mut g);
+
+        let (lat, lon) = (50.0001, 4.002);
+        assert_eq!(g.nearest_node(lat, lon), Some(b1), "test setup: far-bank node is nearest");
+
+        let (junction, proj) = arena_snap_node(&g, lat, lon, "departure").unwrap();
+        assert!(
+            (proj.latitude - 50.0).abs() < 1e-6 && (proj.longitude - 4.002).abs() < 1e-5,
+            "endpoint must link onto the near-bank edge, got {proj:?}"
+        );
+        assert!([a1, a2].contains(&junction), "seed junction must be on the near bank");
+    }
+
+    #[test]
+    fn uncontracted_endpoint_skips_nodes_walkers_cannot_leave() {
+        let mut g = Graph::new();
+        let mut node = |eid: &str, lat: f64, lon: f64| {
+            g.add_node(NodeData::OsmNode(OsmNodeData {
+                eid: eid.to_string(),
+                lat_lng: LatLng {
+                    latitude: lat,
+                    longitude: lon,
+                },
+            }))
+        };
+        let c1 = node("c1", 50.0, 4.0000);
+        let c2 = node("c2", 50.0, 4.0010);
+        let w1 = node("w1", 50.0005, 4.0000);
+        let w2 = node("w2", 50.0005, 4.0010);
+        street(&mut g, c1, c2, 71, false, false);
+        street(&mut g, c2, c1, 71, false, false);
+        street(&mut g, w1, w2, 71, true, false);
+        street(&mut g, w2, w1, 71, true, false);
+        g.build_raptor_index();
+
+        assert_eq!(g.nearest_node(50.0, 4.0), Some(c1), "test setup: car-only node is nearest");
+        let (n, _) = arena_snap_node(&g, 50.0, 4.0, "departure").unwrap();
+        assert_eq!(n, w1, "endpoint must land on a node with a foot edge");
+    }
+
     #[test]
     fn route_accepts_origin_within_snap_distance() {
         let graph = graph_with_node_at(50.85, 4.35);
@@ -693,6 +1268,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn far_and_island_snaps_are_flagged_on_plans() {
+        let mut graph = graph_with_node_at(50.85, 4.35);
+        graph.set_snap_island_nodes(0);
+        let q = query(50.855, 4.35, 50.85, 4.35);
+        let warnings = snap_warnings(&graph, &q);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert_eq!(warnings[0].code, FAR_SNAP);
+        assert!(warnings[0].message.starts_with("origin is"));
+        let dist = warnings[0].distance_m.unwrap();
+        assert!((500.0..600.0).contains(&dist), "{dist}");
+        let plans = route(&graph, &q, &RealtimeIndex::new()).unwrap();
+        assert!(plans.iter().all(|p| p.warnings.is_empty()), "cached results carry no snap warnings");
+        let plans = with_snap_warnings(&graph, &q, plans);
+        assert!(plans.iter().all(|p| p.warnings.contains(&warnings[0])));
+
+        graph.set_snap_warning_m(1_000);
+        assert!(snap_warnings(&graph, &q).is_empty());
+        // Two nodes far from the pair: both endpoints sit on a 2-node island.
+        let lone = [(50.9, 4.4), (50.9001, 4.4001)].map(|(lat, lon)| {
+            graph.add_node(NodeData::OsmNode(OsmNodeData {
+                eid: format!("far{lat}"),
+                lat_lng: LatLng { latitude: lat, longitude: lon },
+            }))
+        });
+        street(&mut graph, lone[0], lone[1], 15, true, true);
+        street(&mut graph, lone[1], lone[0], 15, true, true);
+        graph.build_raptor_index();
+        graph.raptor.edge_snap_radius_m = f64::MAX;
+        enable_contraction(&mut graph);
+        graph.set_snap_island_nodes(3);
+        let codes: Vec<String> = snap_warnings(&graph, &q).into_iter().map(|w| w.code).collect();
+        assert_eq!(codes, [SNAPPED_TO_ISLAND, SNAPPED_TO_ISLAND]);
+    }
+
     #[test]
     fn direct_walk_plan_carries_multiobj_alternatives() {
         use crate::structures::cost::VarGen;
@@ -762,6 +1372,14 @@ mod tests {
             to_station_id: None,
             profile_latency: None,
             fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
         };
         let plans = route(&g, &q, &RealtimeIndex::new()).unwrap();
         let walk = plans
@@ -848,14 +1466,22 @@ mod tests {
             to_station_id: None,
             profile_latency: None,
             fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
         };
         let plans = route(&g, &q, &RealtimeIndex::new()).unwrap();
         let bike = plans
             .iter()
             .find(|p| p.mode == Mode::Bike)
             .expect("a bike plan");
-        let PlanLeg::Walk(leg) = &bike.legs[0] else {
-            panic!("expected a walk leg in a bike plan")
+        let PlanLeg::Bike(PlanBikeLeg { leg }) = &bike.legs[0] else {
+            panic!("expected a bike leg in a bike plan")
         };
         assert!(
             leg.alternatives.len() >= 2,
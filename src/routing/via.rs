@@ -0,0 +1,120 @@
+//! Journeys through intermediate places (`raptor(via: …)`): one search per hop, each
+//! leaving its via point `min_stay_secs` after arriving there with the same modes and
+//! options, stitched into a single plan.
+
+use chrono::{Days, NaiveTime, Timelike};
+
+use crate::routing::routing_raptor::{RouteQuery, route as route_hop, with_snap_warnings};
+use crate::structures::plan::{ArrivalScenario, Plan, PlanError, PlanPrice};
+use crate::structures::{Graph, RealtimeIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViaPoint {
+    pub lat: f64,
+    pub lng: f64,
+    /// Shortest stay at the point before the next hop departs.
+    pub min_stay_secs: u32,
+}
+
+/// Plan `query` through every `via` point in order. Each hop takes the earliest
+/// arrival; the result is one plan whose legs run hop after hop, on the query day's
+/// clock (past 86400 after midnight). Without via points this is a plain search.
+/// A hop with no plan fails with code `VIA_UNREACHABLE`.
+pub fn route(
+    graph: &Graph,
+    query: &RouteQuery,
+    via: &[ViaPoint],
+    rt: &RealtimeIndex,
+) -> Result<Vec<Plan>, PlanError> {
+    if via.is_empty() {
+        return route_hop(graph, query, rt).map(|plans| with_snap_warnings(graph, query, plans));
+    }
+    let mut stops: Vec<(f64, f64)> = via.iter().map(|v| (v.lat, v.lng)).collect();
+    stops.push((query.to_lat, query.to_lng));
+    let mut from = (query.from_lat, query.from_lng);
+    let mut at = query.time.num_seconds_from_midnight();
+    let mut combined: Option<Plan> = None;
+    for (i, &to) in stops.iter().enumerate() {
+        let (first, last) = (i == 0, i == via.len());
+        let day = at / 86400;
+        let hop = RouteQuery {
+            from_lat: from.0,
+            from_lng: from.1,
+            to_lat: to.0,
+            to_lng: to.1,
+            date: query.date + Days::new(day as u64),
+            time: NaiveTime::from_num_seconds_from_midnight_opt(at % 86400, 0).unwrap_or_default(),
+            window_minutes: None,
+            onboard_origin: query.onboard_origin.clone().filter(|_| first),
+            from_station_id: query.from_station_id.clone().filter(|_| first),
+            to_station_id: query.to_station_id.clone().filter(|_| last),
+            ..query.clone()
+        };
+        let plan = with_snap_warnings(graph, &hop, route_hop(graph, &hop, rt)?)
+            .into_iter()
+            .min_by_key(|p| (p.end, std::cmp::Reverse(p.start)))
+            .ok_or_else(|| {
+                let target = if last { "the destination".to_string() } else { format!("via point {}", i + 1) };
+                PlanError::new(format!("no route to {target}")).with_code("VIA_UNREACHABLE")
+            })?;
+        let plan = Graph::shift_plan(plan, -((day * 86400) as i64));
+        if let Some(v) = via.get(i) {
+            at = plan.end + v.min_stay_secs;
+        }
+        combined = Some(match combined {
+            None => plan,
+            Some(done) => stitch(done, plan),
+        });
+        from = to;
+    }
+    Ok(combined.into_iter().collect())
+}
+
+/// `second` appended to `first`. The stay between them is neither a leg nor waiting
+/// time; fares add up, one journey per hop.
+fn stitch(first: Plan, second: Plan) -> Plan {
+    let mut legs = first.legs;
+    legs.extend(second.legs);
+    let mut warnings = first.warnings;
+    for w in second.warnings {
+        if !warnings.contains(&w) {
+            warnings.push(w);
+        }
+    }
+    Plan {
+        legs,
+        start: first.start,
+        end: second.end,
+        mode: first.mode,
+        access_alternatives: Vec::new(),
+        arrival_distribution: vec![ArrivalScenario { time: second.end, probability: 1.0 }],
+        expected_end: second.expected_end,
+        price: add_prices(first.price, second.price),
+        debug: None,
+        warnings,
+        itinerary_id: None,
+    }
+}
+
+fn add_prices(a: Option<PlanPrice>, b: Option<PlanPrice>) -> Option<PlanPrice> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let mut unknown_operators = a.unknown_operators;
+            for op in b.unknown_operators {
+                if !unknown_operators.contains(&op) {
+                    unknown_operators.push(op);
+                }
+            }
+            let mut breakdown = a.breakdown;
+            breakdown.extend(b.breakdown);
+            Some(PlanPrice {
+                known_euros: a.known_euros + b.known_euros,
+                capped_euros: a.capped_euros + b.capped_euros,
+                unknown_operators,
+                sncb_fare_km: b.sncb_fare_km.or(a.sncb_fare_km),
+                breakdown,
+            })
+        }
+        (price, None) | (None, price) => price,
+    }
+}
@@ -0,0 +1,167 @@
+//! `--accessibility`: cumulative-opportunity accessibility. For every origin, counts
+//! the points of interest reachable within a cutoff at one departure (static timetable,
+//! no realtime), reusing the travel-map field via [`Graph::travel_times_to`], and
+//! writes one CSV row per origin: `id,lat,lon,reachable`.
+//!
+//! Input CSVs need a header with a latitude column (`lat` / `latitude`) and a
+//! longitude column (`lon` / `lng` / `longitude`); an `id` column is optional and
+//! defaults to the 1-based row number.
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
+
+use crate::{
+    ingestion::gtfs::date_to_days,
+    structures::{ActiveModes, BikeCost, Graph, LatLng, Mode, RealtimeIndex, ReliabilityBuckets},
+};
+
+#[derive(Debug, Clone)]
+pub struct AccessibilityOptions {
+    pub output: String,
+    pub origins: String,
+    pub pois: String,
+    pub cutoff_secs: u32,
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    pub modes: Option<Vec<Mode>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Place {
+    pub id: String,
+    pub loc: LatLng,
+}
+
+/// `45min`, `2700s`, `1h` or a bare number of minutes.
+pub fn parse_cutoff(value: &str) -> Option<u32> {
+    let v = value.trim();
+    let (number, scale) = if let Some(n) = v.strip_suffix("min") {
+        (n, 60)
+    } else if let Some(n) = v.strip_suffix('s') {
+        (n, 1)
+    } else if let Some(n) = v.strip_suffix('h') {
+        (n, 3600)
+    } else {
+        (v, 60)
+    };
+    let secs = number.trim().parse::<u32>().ok()?.checked_mul(scale)?;
+    (secs > 0).then_some(secs)
+}
+
+fn parse_places(reader: impl std::io::Read, path: &str) -> Result<Vec<Place>, String> {
+    let mut csv = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    let headers = csv.headers().map_err(|e| format!("'{path}': {e}"))?.clone();
+    let column = |names: &[&str]| {
+        headers.iter().position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)))
+    };
+    let (Some(lat), Some(lon)) = (column(&["lat", "latitude"]), column(&["lon", "lng", "longitude"]))
+    else {
+        return Err(format!("'{path}': header needs lat and lon columns"));
+    };
+    let id = column(&["id"]);
+    let mut places = Vec::new();
+    for (row, record) in csv.records().enumerate() {
+        let record = record.map_err(|e| format!("'{path}': {e}"))?;
+        let coord = |i: usize| record.get(i).and_then(|v| v.parse::<f64>().ok());
+        let (Some(latitude), Some(longitude)) = (coord(lat), coord(lon)) else {
+            return Err(format!("'{path}' row {}: invalid coordinates", row + 1));
+        };
+        places.push(Place {
+            id: id
+                .and_then(|i| record.get(i))
+                .map_or_else(|| (row + 1).to_string(), str::to_string),
+            loc: LatLng { latitude, longitude },
+        });
+    }
+    Ok(places)
+}
+
+pub fn read_places(path: &str) -> Result<Vec<Place>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("failed to open '{path}': {e}"))?;
+    parse_places(file, path)
+}
+
+/// Counts reachable POIs per origin and writes the CSV to `opts.output`; returns a
+/// one-line summary.
+pub fn run(g: &Graph, opts: &AccessibilityOptions) -> Result<String, String> {
+    let origins = read_places(&opts.origins)?;
+    let pois: Vec<LatLng> = read_places(&opts.pois)?.into_iter().map(|p| p.loc).collect();
+    let am = match &opts.modes {
+        None => ActiveModes::default(),
+        Some(m) => ActiveModes::new(m),
+    };
+    let buckets = ReliabilityBuckets::new(&g.raptor.reliability_bucket_edges);
+    let bike = BikeCost::new(g.raptor.bike_profile);
+    let rt = RealtimeIndex::new();
+
+    let mut out = csv::Writer::from_path(&opts.output)
+        .map_err(|e| format!("failed to write '{}': {e}", opts.output))?;
+    let write_err = |e: csv::Error| format!("failed to write '{}': {e}", opts.output);
+    out.write_record(["id", "lat", "lon", "reachable"]).map_err(write_err)?;
+    let mut total = 0;
+    for origin in &origins {
+        let reachable = g
+            .travel_times_to(
+                origin.loc,
+                &pois,
+                opts.time.num_seconds_from_midnight(),
+                date_to_days(opts.date),
+                1u8 << opts.date.weekday().num_days_from_monday(),
+                opts.cutoff_secs,
+                &am,
+                &buckets,
+                g.raptor.arrival_slack_secs,
+                g.raptor.unrestricted_transfers,
+                g.raptor.use_cch_access,
+                &rt,
+                &bike,
+            )
+            .iter()
+            .filter(|t| t.is_some())
+            .count();
+        total += reachable;
+        out.write_record([
+            origin.id.clone(),
+            origin.loc.latitude.to_string(),
+            origin.loc.longitude.to_string(),
+            reachable.to_string(),
+        ])
+        .map_err(write_err)?;
+    }
+    out.flush().map_err(|e| format!("failed to write '{}': {e}", opts.output))?;
+    Ok(format!(
+        "wrote {} ({} origins, {} POIs, {total} origin-POI pairs within {} s)",
+        opts.output,
+        origins.len(),
+        pois.len(),
+        opts.cutoff_secs
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutoffs_take_a_unit_and_default_to_minutes() {
+        assert_eq!(parse_cutoff("45min"), Some(2700));
+        assert_eq!(parse_cutoff("90s"), Some(90));
+        assert_eq!(parse_cutoff("1h"), Some(3600));
+        assert_eq!(parse_cutoff("30"), Some(1800));
+        assert_eq!(parse_cutoff("0min"), None);
+        assert_eq!(parse_cutoff("soon"), None);
+    }
+
+    #[test]
+    fn places_read_named_columns_and_number_unnamed_rows() {
+        let csv = "name, Longitude ,lat\nschool,4.35,50.85\nclinic,4.36,50.86\n";
+        let places = parse_places(csv.as_bytes(), "pois.csv").unwrap();
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[1].id, "2");
+        assert_eq!((places[1].loc.latitude, places[1].loc.longitude), (50.86, 4.36));
+
+        let with_id = parse_places("id,lat,lng\nA,50.0,4.0\n".as_bytes(), "o.csv").unwrap();
+        assert_eq!(with_id[0].id, "A");
+        assert!(parse_places("id,x,y\nA,1,2\n".as_bytes(), "o.csv").is_err());
+        assert!(parse_places("lat,lon\n50.0,east\n".as_bytes(), "o.csv").is_err());
+    }
+}
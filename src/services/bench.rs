@@ -0,0 +1,325 @@
+//! `--bench`: plan N random origin/destination pairs on the loaded graph and report
+//! latency percentiles and search expansion counts, so releases can be compared
+//! without an external load-testing harness. Pairs are drawn from junctions of the
+//! largest street component so every pair is at least street-reachable; the seed makes
+//! a run reproducible.
+
+use std::time::Instant;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::{
+    routing::routing_raptor::{self, RouteQuery},
+    structures::{Graph, LatLng, Mode, RealtimeIndex, query_budget},
+};
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub samples: usize,
+    pub seed: u64,
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    pub window_minutes: Option<u32>,
+    pub modes: Option<Vec<Mode>>,
+}
+
+/// `--loadtest`: the bench sample set (`bench.samples` requests) sent as `raptor`
+/// queries through the GraphQL server by `concurrency` clients at once.
+#[derive(Debug, Clone)]
+pub struct LoadTestOptions {
+    pub bench: BenchOptions,
+    pub concurrency: usize,
+}
+
+/// Nearest-rank percentiles over the sampled queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Percentiles {
+    pub fn of(mut v: Vec<f64>) -> Self {
+        if v.is_empty() {
+            return Percentiles::default();
+        }
+        v.sort_by(f64::total_cmp);
+        let rank = |p: f64| v[((p * v.len() as f64).ceil() as usize).clamp(1, v.len()) - 1];
+        Percentiles {
+            p50: rank(0.5),
+            p90: rank(0.9),
+            p99: rank(0.99),
+            max: v[v.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BenchReport {
+    pub samples: usize,
+    pub planned: usize,
+    pub no_plan: usize,
+    pub errors: usize,
+    pub latency_ms: Percentiles,
+    pub expansions: Percentiles,
+    pub total_secs: f64,
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "samples {}  planned {}  no_plan {}  errors {}  wall {:.2}s",
+            self.samples, self.planned, self.no_plan, self.errors, self.total_secs
+        )?;
+        let l = self.latency_ms;
+        writeln!(
+            f,
+            "latency_ms  p50 {:.1}  p90 {:.1}  p99 {:.1}  max {:.1}",
+            l.p50, l.p90, l.p99, l.max
+        )?;
+        let e = self.expansions;
+        write!(
+            f,
+            "expansions  p50 {:.0}  p90 {:.0}  p99 {:.0}  max {:.0}",
+            e.p50, e.p90, e.p99, e.max
+        )
+    }
+}
+
+/// SplitMix64: tiny, seedable and good enough to pick sample junctions.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Junction indices of the largest undirected component of the contracted graph.
+fn largest_component(g: &Graph) -> Vec<usize> {
+    let Some(cg) = g.contracted.as_ref() else {
+        return Vec::new();
+    };
+    let n = cg.junctions.len();
+    let mut undirected: Vec<Vec<u32>> = vec![Vec::new(); n];
+    for (from, adj) in cg.adjacency.iter().enumerate() {
+        for se in adj {
+            undirected[from].push(se.to);
+            undirected[se.to as usize].push(from as u32);
+        }
+    }
+    let mut comp = vec![u32::MAX; n];
+    let (mut best, mut best_size) = (u32::MAX, 0);
+    let mut stack = Vec::new();
+    for start in 0..n {
+        if comp[start] != u32::MAX {
+            continue;
+        }
+        let c = start as u32;
+        let mut size = 0;
+        comp[start] = c;
+        stack.push(start);
+        while let Some(u) = stack.pop() {
+            size += 1;
+            for &v in &undirected[u] {
+                if comp[v as usize] == u32::MAX {
+                    comp[v as usize] = c;
+                    stack.push(v as usize);
+                }
+            }
+        }
+        if size > best_size {
+            (best, best_size) = (c, size);
+        }
+    }
+    (0..n).filter(|&j| comp[j] == best).collect()
+}
+
+/// `count` distinct-endpoint OD pairs drawn from the largest street component.
+pub fn sample_pairs(g: &Graph, count: usize, seed: u64) -> Vec<(LatLng, LatLng)> {
+    let pool = largest_component(g);
+    let Some(cg) = g.contracted.as_ref().filter(|_| pool.len() >= 2) else {
+        return Vec::new();
+    };
+    let mut rng = SplitMix(seed);
+    (0..count)
+        .map(|_| {
+            let a = pool[rng.below(pool.len())];
+            let mut b = pool[rng.below(pool.len())];
+            while b == a {
+                b = pool[rng.below(pool.len())];
+            }
+            (cg.junction_coord[a], cg.junction_coord[b])
+        })
+        .collect()
+}
+
+/// Plan every sampled pair sequentially on this thread (static timetable, no realtime).
+pub fn run(g: &Graph, opts: &BenchOptions) -> BenchReport {
+    let pairs = sample_pairs(g, opts.samples, opts.seed);
+    let rt = RealtimeIndex::new();
+    let mut report = BenchReport {
+        samples: pairs.len(),
+        ..Default::default()
+    };
+    let mut latencies = Vec::with_capacity(pairs.len());
+    let mut expansions = Vec::with_capacity(pairs.len());
+    let started = Instant::now();
+    for (from, to) in pairs {
+        let query = RouteQuery {
+            from_lat: from.latitude,
+            from_lng: from.longitude,
+            to_lat: to.latitude,
+            to_lng: to.longitude,
+            date: opts.date,
+            time: opts.time,
+            window_minutes: opts.window_minutes,
+            min_access_secs: None,
+            arrival_slack_secs: None,
+            unrestricted_transfers: None,
+            use_cch_access: None,
+            reliability_bucket_edges: None,
+            modes: opts.modes.clone(),
+            bike_profile: None,
+            terminal_deadline: false,
+            onboard_origin: None,
+            from_station_id: None,
+            to_station_id: None,
+            profile_latency: None,
+            fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
+        };
+        let t = Instant::now();
+        let result = routing_raptor::route(g, &query, &rt);
+        latencies.push(t.elapsed().as_secs_f64() * 1000.0);
+        expansions.push(query_budget::expansions() as f64);
+        match result {
+            Ok(plans) if plans.is_empty() => report.no_plan += 1,
+            Ok(_) => report.planned += 1,
+            Err(_) => report.errors += 1,
+        }
+    }
+    report.total_secs = started.elapsed().as_secs_f64();
+    report.latency_ms = Percentiles::of(latencies);
+    report.expansions = Percentiles::of(expansions);
+    report
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::structures::contraction::ContractedGraph;
+    use crate::structures::cost::VarGen;
+    use crate::structures::{BikeAttrs, EdgeData, NodeData, NodeID, OsmNodeData, StreetEdgeData};
+
+    /// A 5-junction foot line plus a detached 2-node pair.
+    pub(crate) fn graph() -> Graph {
+        let mut g = Graph::new();
+        let node = |g: &mut Graph, i: usize, lat: f64, lon: f64| {
+            g.add_node(NodeData::OsmNode(OsmNodeData {
+                eid: format!("map#osm#{i}"),
+                lat_lng: LatLng {
+                    latitude: lat,
+                    longitude: lon,
+                },
+            }))
+        };
+        let line: Vec<_> = (0..5)
+            .map(|i| node(&mut g, i, 50.0, 4.0 + i as f64 * 0.001))
+            .collect();
+        let island = [node(&mut g, 10, 50.1, 4.0), node(&mut g, 11, 50.1, 4.001)];
+        let street = |g: &mut Graph, a: NodeID, b: NodeID| {
+            for (o, d) in [(a, b), (b, a)] {
+                g.add_edge(
+                    o,
+                    EdgeData::Street(StreetEdgeData {
+                        origin: o,
+                        destination: d,
+                        partial: false,
+                        length: 72,
+                        foot: true,
+                        bike: false,
+                        car: false,
+                        attrs: BikeAttrs::road_default(),
+                        elev_delta: 0,
+                        surface_speed: 100,
+                        var_gen: VarGen::NONE,
+                    }),
+                );
+            }
+        };
+        for w in line.windows(2) {
+            street(&mut g, w[0], w[1]);
+        }
+        street(&mut g, island[0], island[1]);
+        g.build_raptor_index();
+        let mut cg = ContractedGraph::from_graph_union(&g);
+        cg.build_seg_index();
+        g.contracted = Some(cg);
+        g
+    }
+
+    #[test]
+    fn samples_are_seeded_and_stay_on_the_main_component() {
+        let g = graph();
+        let coords = |seed| -> Vec<[f64; 4]> {
+            sample_pairs(&g, 20, seed)
+                .iter()
+                .map(|(o, d)| [o.latitude, o.longitude, d.latitude, d.longitude])
+                .collect()
+        };
+        let a = coords(7);
+        assert_eq!(a.len(), 20);
+        assert_eq!(a, coords(7), "same seed, same pairs");
+        assert_ne!(a, coords(8));
+        for [olat, olng, dlat, dlng] in a {
+            assert_ne!((olat, olng), (dlat, dlng));
+            assert!(olat < 50.05 && dlat < 50.05, "island never sampled");
+        }
+    }
+
+    #[test]
+    fn run_reports_every_sample() {
+        let g = graph();
+        let report = run(
+            &g,
+            &BenchOptions {
+                samples: 5,
+                seed: 1,
+                date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+                time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                window_minutes: None,
+                modes: Some(vec![Mode::Walk]),
+            },
+        );
+        assert_eq!(report.samples, 5);
+        assert_eq!(report.planned + report.no_plan + report.errors, 5);
+        assert!(report.latency_ms.max >= report.latency_ms.p50);
+        assert!(report.to_string().contains("latency_ms"));
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let p = Percentiles::of((1..=100).map(f64::from).collect());
+        assert_eq!((p.p50, p.p90, p.p99, p.max), (50.0, 90.0, 99.0, 100.0));
+        assert_eq!(Percentiles::of(Vec::new()), Percentiles::default());
+    }
+}
@@ -2,28 +2,80 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::time::{Duration, SystemTime};
 
+use gtfs_structures::FeedInfo;
+
 use crate::{
     ingestion::{
         address::bestadd::load_bestadd_zip,
         cache::{SourceLocation, download_to, resolve_source},
-        gtfs::{load_gtfs, load_gtfs_sncb, load_gtfs_stib, prepare_sncb},
-        osm::{self, Dem, DemSet, ElevationSource},
+        gtfs::{
+            CachedFeed, ParsedGtfs, load_parsed_gtfs, load_parsed_gtfs_sncb, load_parsed_gtfs_stib, prepare_sncb,
+            read_gtfs,
+        },
+        osm::{Dem, DemSet, ElevationSource},
     },
+    services::fingerprint::file_digest,
     services::persistence::{
-        cch_cache_path, load_address_index, load_cch, save_address_index, save_cch,
+        cch_cache_path, load_address_index, load_cch, load_feed_cache, save_address_index,
+        save_cch, save_feed_cache,
+    },
+    structures::{
+        AddressIndex, BuildConfig, DelayCDF, Graph, Ingestor, RoutingDefaultConfig, SourceInfo, redact_location,
     },
-    structures::{AddressIndex, BuildConfig, DelayCDF, Graph, Ingestor, RoutingDefaultConfig},
 };
+#[cfg(feature = "pbf")]
+use crate::ingestion::osm;
 
 const ADDRESS_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
 
+/// Which remote inputs of a phase are downloaded again instead of reusing their copy
+/// in `cache_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refresh<'a> {
+    Cached,
+    All,
+    /// Only the GTFS input with this name (`--update-gtfs --feed <name>`); the other
+    /// feeds come from the download and feed caches.
+    Feed(&'a str),
+}
+
+impl Refresh<'_> {
+    fn downloads(self, input: &Ingestor) -> bool {
+        match self {
+            Refresh::Cached => false,
+            Refresh::All => true,
+            Refresh::Feed(name) => input.label() == name,
+        }
+    }
+}
+
+/// Checks that `name` is a configured GTFS input, for `--update-gtfs --feed`.
+pub fn check_gtfs_feed(config: &BuildConfig, name: &str) -> Result<(), String> {
+    let feeds: Vec<&str> = config.inputs.iter().filter(|i| i.is_gtfs()).map(|i| i.label()).collect();
+    if feeds.contains(&name) {
+        return Ok(());
+    }
+    Err(format!("no GTFS input named '{name}' (configured: {})", feeds.join(", ")))
+}
+
 pub fn build_osm_phase(
     config: &BuildConfig,
     cache_dir: &str,
     force_download: bool,
 ) -> Option<Graph> {
     let mut g = Graph::new();
-    run_phase(config, &mut g, 0, cache_dir, force_download)?;
+    let refresh = if force_download { Refresh::All } else { Refresh::Cached };
+    run_phase(config, &mut g, 0, cache_dir, refresh)?;
+    let merged = g.dedup_street_edges();
+    tracing::info!("street network: merged {merged} parallel street edges");
+    let islands = g.prune_street_islands(config.min_island_nodes);
+    tracing::info!(
+        "street network: {} components, pruned {} islands ({} nodes) below {} nodes",
+        islands.components,
+        islands.pruned_components,
+        islands.pruned_nodes,
+        config.min_island_nodes
+    );
     for input in &config.inputs {
         if input.phase() != 0
             && let Err(e) = prepare_ingestor(input, &mut g)
@@ -42,19 +94,66 @@ pub fn build_osm_phase(
 /// GTFS phase bakes fallback relocation connectors using `g.connector_cost()`. The same
 /// connector cost is re-set idempotently later by `apply_connector_cost`.
 pub fn build_gtfs_phase(
+    g: Graph,
+    config: &BuildConfig,
+    cache_dir: &str,
+    refresh: Refresh,
+    station_merge_radius_m: Option<f64>,
+    routing: &RoutingDefaultConfig,
+) -> Option<Graph> {
+    let started = SystemTime::now();
+    let g = merge_gtfs_phase(g, config, cache_dir, refresh, station_merge_radius_m, routing)?;
+    finish_gtfs_phase(g, config, started)
+}
+
+/// The ingestion half of [`build_gtfs_phase`]: every feed merged, nothing finalized.
+/// This is the graph `transit.bin` keeps for [`replace_gtfs_feed`].
+pub fn merge_gtfs_phase(
     mut g: Graph,
     config: &BuildConfig,
     cache_dir: &str,
-    force_download: bool,
+    refresh: Refresh,
     station_merge_radius_m: Option<f64>,
     routing: &RoutingDefaultConfig,
 ) -> Option<Graph> {
+    prepare_gtfs_phase(&mut g, station_merge_radius_m, routing);
+    run_phase(config, &mut g, 1, cache_dir, refresh)?;
+    Some(g)
+}
+
+/// Replace the GTFS input `name` of a merged, unfinalized graph in place: the old copy
+/// is tombstoned ([`Graph::remove_feed`]) and a freshly downloaded one appended, so the
+/// other feeds keep their stops, trips and ids and are not read again.
+pub fn replace_gtfs_feed(
+    mut g: Graph,
+    config: &BuildConfig,
+    cache_dir: &str,
+    name: &str,
+    station_merge_radius_m: Option<f64>,
+    routing: &RoutingDefaultConfig,
+) -> Option<Graph> {
+    let input = config.inputs.iter().find(|i| i.is_gtfs() && i.label() == name)?;
+    prepare_gtfs_phase(&mut g, station_merge_radius_m, routing);
+    if g.remove_feed(name).is_none() {
+        tracing::warn!("feed '{name}' is not in the graph yet; appending it");
+    }
+    run_inputs(config, &mut g, &[input], cache_dir, Refresh::Feed(name))?;
+    Some(g)
+}
+
+/// Finalize a merged graph and stamp the build; `started` is when the GTFS phase began.
+pub fn finish_gtfs_phase(g: Graph, config: &BuildConfig, started: SystemTime) -> Option<Graph> {
+    let mut g = finalize(g, config)?;
+    let built_at = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    g.finish_build_info(built_at, started.elapsed().unwrap_or_default().as_secs_f64());
+    Some(g)
+}
+
+fn prepare_gtfs_phase(g: &mut Graph, station_merge_radius_m: Option<f64>, routing: &RoutingDefaultConfig) {
     if let Some(r) = station_merge_radius_m {
         g.set_station_merge_radius_m(r);
     }
     g.set_connector_cost(resolve_connector_cost(routing));
-    run_phase(config, &mut g, 1, cache_dir, force_download)?;
-    finalize(g, config)
 }
 
 /// Bake the pedestrian connector cost into edge lengths so they survive contraction
@@ -80,6 +179,9 @@ fn resolve_connector_cost(routing: &RoutingDefaultConfig) -> crate::ingestion::o
         if let Some(v) = c.elevator_secs {
             cost.elevator_secs = v;
         }
+        if let Some(v) = c.escalator_speed_mps {
+            cost.escalator_speed_mps = v;
+        }
         if let Some(v) = c.relocation_fallback_secs {
             cost.relocation_fallback_secs = v;
         }
@@ -190,7 +292,7 @@ fn prepare_ingestor(input: &Ingestor, g: &mut Graph) -> Result<(), String> {
                 .strip_prefix("path:")
                 .map(|s| s.to_string())
                 .unwrap_or_else(|| c.osm_url.clone());
-            prepare_sncb(&osm_path, g).map_err(|e| e.to_string())
+            prepare_sncb(&osm_path, g)
         }
         _ => Ok(()),
     }
@@ -240,15 +342,31 @@ fn run_phase(
     g: &mut Graph,
     phase: u8,
     cache_dir: &str,
-    force_download: bool,
+    refresh: Refresh,
 ) -> Option<()> {
     let ordered: Vec<&Ingestor> = config
         .inputs
         .iter()
         .filter(|i| i.phase() == phase)
         .collect();
+    let kept_feeds = run_inputs(config, g, &ordered, cache_dir, refresh)?;
+    // Only a phase that read the GTFS inputs knows which cached feeds are still used;
+    // the OSM phase reads none and would drop them all.
+    if config.gtfs_feed_cache && ordered.iter().any(|i| i.is_gtfs()) {
+        prune_feed_cache(cache_dir, &kept_feeds.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+    Some(())
+}
 
-    if let Err(e) = preflight_inputs(&ordered) {
+/// Load `ordered` into `g` in order; returns the feed cache entries they read.
+fn run_inputs(
+    config: &BuildConfig,
+    g: &mut Graph,
+    ordered: &[&Ingestor],
+    cache_dir: &str,
+    refresh: Refresh,
+) -> Option<Vec<String>> {
+    if let Err(e) = preflight_inputs(ordered) {
         tracing::error!("preflight failed: {e}");
         return None;
     }
@@ -256,7 +374,7 @@ fn run_phase(
     // Resolve every source once, reused for both DEM loading and ingestion.
     let resolved: Vec<Result<String, String>> = ordered
         .iter()
-        .map(|input| resolve_source(input, cache_dir, force_download))
+        .map(|input| resolve_source(input, cache_dir, refresh.downloads(input)))
         .collect();
 
     // A DEM is only useful for OSM elevation sampling; skip it for a phase with no OSM.
@@ -284,13 +402,32 @@ fn run_phase(
         }
     }
     let dem_set = DemSet(dems);
+    #[cfg_attr(not(feature = "pbf"), allow(unused_variables))]
     let dem: Option<&dyn ElevationSource> = if dem_set.0.is_empty() {
         None
     } else {
         Some(&dem_set)
     };
 
-    for (input, resolved_path) in ordered.iter().zip(&resolved) {
+    let threads = ingest_threads(config.ingest_threads);
+    let read_feed = |input: &Ingestor, path: &Result<String, String>| {
+        let path = path.as_ref().ok()?;
+        input.is_gtfs().then(|| read_gtfs_cached(path, cache_dir, config.gtfs_feed_cache))
+    };
+    // Reading a feed touches no graph state: with several threads read them all at once,
+    // then merge one by one in config order so ids come out the same as a sequential
+    // build. With one, each feed is read right before its merge, so only one parsed feed
+    // is held at a time.
+    let mut read: Vec<_> = if threads > 1 {
+        parallel_map(&ordered.iter().zip(&resolved).collect::<Vec<_>>(), threads, |(input, path)| {
+            read_feed(input, path)
+        })
+    } else {
+        ordered.iter().map(|_| None).collect()
+    };
+    let mut kept_feeds: Vec<String> = Vec::new();
+
+    for ((input, resolved_path), read) in ordered.iter().zip(&resolved).zip(&mut read) {
         // DEM resolution failure is non-fatal (already warned above).
         if input.dem_projection().is_some() && resolved_path.is_err() {
             continue;
@@ -307,26 +444,94 @@ fn run_phase(
             }
         };
 
-        let result = match input {
-            Ingestor::OsmPbf(_) => {
-                osm::load_pbf_file(
-                    path,
-                    dem,
-                    config.elevation_smoothing_epsilon,
-                    &config.surface_speed_factors,
-                    g,
-                )
-                .map_err(|e| e.to_string())
+        let mut parsed = match read.take().or_else(|| read_feed(input, resolved_path)) {
+            Some(Ok((parsed, cached))) => {
+                kept_feeds.extend(cached);
+                Some(Ok(parsed))
             }
-            Ingestor::GtfsGeneric(_) => load_gtfs(path, g).map_err(|e| e.to_string()),
-            Ingestor::GtfsStib(_) => load_gtfs_stib(path, g).map_err(|e| e.to_string()),
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        };
+        let feed_info = parsed
+            .as_ref()
+            .and_then(|p| p.as_ref().ok())
+            .and_then(|p| p.feed_info().cloned());
+        if input.is_gtfs() {
+            g.begin_feed(input.label(), input.namespace());
+            g.set_feed_service_day_cutover(input.service_day_cutover());
+        }
+        let result = match input {
+            #[cfg(feature = "pbf")]
+            Ingestor::OsmPbf(c) => osm::ClipArea::from_ingestor(c).and_then(|clip| {
+                let clip = clip.as_ref();
+                // The side indices re-read the file on their own; only the street
+                // network needs the graph.
+                let addresses = || {
+                    (c.address_labels || c.geocoder)
+                        .then(|| osm::build_address_index(path, clip, c.geocoder))
+                        .transpose()
+                };
+                let amenities = || c.stop_amenities.then(|| osm::build_amenity_index(path, clip)).transpose();
+                let parking = || c.parking.then(|| osm::build_parking_index(path, clip)).transpose();
+                let streets = |g: &mut Graph| {
+                    osm::load_pbf_file_clipped(
+                        path,
+                        dem,
+                        config.elevation_smoothing_epsilon,
+                        &config.surface_speed_factors,
+                        clip,
+                        g,
+                    )
+                };
+                let (streets, addresses, amenities, parking) = if threads > 1 {
+                    std::thread::scope(|s| {
+                        let addresses = s.spawn(addresses);
+                        let amenities = s.spawn(amenities);
+                        let parking = s.spawn(parking);
+                        let streets = streets(g);
+                        (streets, join(addresses), join(amenities), join(parking))
+                    })
+                } else {
+                    (streets(g), addresses(), amenities(), parking())
+                };
+                streets
+                    .and_then(|()| {
+                        if let Some(idx) = addresses? {
+                            g.set_address_index(idx);
+                        }
+                        if let Some(idx) = amenities? {
+                            g.set_amenity_index(idx);
+                        }
+                        if let Some(idx) = parking? {
+                            g.set_parking_index(idx);
+                        }
+                        Ok(())
+                    })
+                    .map_err(|e| e.to_string())
+            }),
+            #[cfg(not(feature = "pbf"))]
+            Ingestor::OsmPbf(_) => Err("built without the `pbf` feature".to_string()),
+            Ingestor::GtfsGeneric(c) => take_parsed(&mut parsed).and_then(|p| {
+                load_parsed_gtfs(p, g, c.stop_linking(), &c.feed_filter())
+                    .map(|r| g.finish_feed(r))
+                    .map_err(|e| e.to_string())
+            }),
+            Ingestor::GtfsStib(c) => take_parsed(&mut parsed).and_then(|p| {
+                load_parsed_gtfs_stib(p, g, c.stop_linking(), &c.feed_filter())
+                    .map(|r| g.finish_feed(r))
+                    .map_err(|e| e.to_string())
+            }),
             Ingestor::GtfsSncb(c) => {
                 let osm_path = c
                     .osm_url
                     .strip_prefix("path:")
                     .map(|s| s.to_string())
                     .unwrap_or_else(|| c.osm_url.clone());
-                load_gtfs_sncb(path, &osm_path, g).map_err(|e| e.to_string())
+                take_parsed(&mut parsed).and_then(|p| {
+                    load_parsed_gtfs_sncb(p, &osm_path, g)
+                        .map(|r| g.finish_feed(r))
+                        .map_err(|e| e.to_string())
+                })
             }
             Ingestor::AddressBestAdd(_) => Ok(()),
             Ingestor::DemBelgianLambert2008(_) => Ok(()),
@@ -334,9 +539,9 @@ fn run_phase(
 
         match result {
             Ok(_) => {
-                if let Ok(elapsed) = before.elapsed() {
-                    tracing::info!("loaded '{}' in {}ms", input.label(), elapsed.as_millis());
-                }
+                let elapsed = before.elapsed().unwrap_or_default();
+                tracing::info!("loaded '{}' in {}ms", input.label(), elapsed.as_millis());
+                g.record_source(source_info(input, path, feed_info, elapsed));
             }
             Err(e) => {
                 tracing::error!(
@@ -348,12 +553,172 @@ fn run_phase(
             }
         }
     }
-    Some(())
+    Some(kept_feeds)
+}
+
+/// Provenance of one loaded input, for `Graph::build_info`.
+fn source_info(input: &Ingestor, path: &str, feed_info: Option<FeedInfo>, elapsed: Duration) -> SourceInfo {
+    let sha256 = Path::new(path)
+        .is_file()
+        .then(|| file_digest(path).ok())
+        .flatten()
+        .map(|d| d.iter().map(|b| format!("{b:02x}")).collect());
+    #[cfg(feature = "pbf")]
+    let osm_timestamp = matches!(input, Ingestor::OsmPbf(_)).then(|| osm::pbf_replication_timestamp(path)).flatten();
+    #[cfg(not(feature = "pbf"))]
+    let osm_timestamp = None;
+    let feed_info = feed_info.as_ref();
+    let ymd = |d: Option<chrono::NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+    SourceInfo {
+        label: input.label().to_string(),
+        kind: input.kind().to_string(),
+        location: redact_location(input.url().strip_prefix("path:").unwrap_or(input.url())),
+        sha256,
+        load_secs: elapsed.as_secs_f64(),
+        osm_timestamp,
+        feed_publisher: feed_info.map(|f| f.name.clone()).filter(|n| !n.is_empty()),
+        feed_version: feed_info.and_then(|f| f.version.clone()),
+        feed_start_date: ymd(feed_info.and_then(|f| f.start_date)),
+        feed_end_date: ymd(feed_info.and_then(|f| f.end_date)),
+    }
+}
+
+/// `configured` ingest threads, `0` meaning one per available core.
+fn ingest_threads(configured: usize) -> usize {
+    match configured {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// `f` over `items` on up to `threads` scoped workers, results in `items` order.
+fn parallel_map<T: Sync, R: Send>(items: &[T], threads: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    if threads <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let mut out: Vec<(usize, R)> = std::thread::scope(|s| {
+        let workers: Vec<_> = (0..threads.min(items.len()))
+            .map(|_| {
+                s.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        let Some(item) = items.get(i) else { break done };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        workers.into_iter().flat_map(join).collect()
+    });
+    out.sort_by_key(|(i, _)| *i);
+    out.into_iter().map(|(_, r)| r).collect()
+}
+
+fn join<T>(handle: std::thread::ScopedJoinHandle<'_, T>) -> T {
+    handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e))
+}
+
+fn feed_cache_dir(cache_dir: &str) -> String {
+    format!("{cache_dir}/gtfs")
+}
+
+/// [`read_gtfs`] through the feed cache, keyed by the file's digest, with the cache
+/// file the feed now lives in. A directory feed is not cached.
+fn read_gtfs_cached(
+    path: &str,
+    cache_dir: &str,
+    enabled: bool,
+) -> Result<(ParsedGtfs, Option<String>), String> {
+    let digest = enabled.then(|| file_digest(path).ok()).flatten();
+    let Some(digest) = digest else {
+        return read_gtfs(path).map(|p| (p, None)).map_err(|e| e.to_string());
+    };
+    let hex: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+    let cache_path = format!("{}/{hex}.bin", feed_cache_dir(cache_dir));
+    match load_feed_cache(&cache_path, &digest) {
+        Ok(feed) => {
+            tracing::info!("'{path}' unchanged since the last build, read from {cache_path}");
+            return Ok((feed.into_parsed(path), Some(cache_path)));
+        }
+        Err(e) if Path::new(&cache_path).exists() => tracing::warn!("re-reading '{path}': {e}"),
+        Err(_) => {}
+    }
+    let parsed = read_gtfs(path).map_err(|e| e.to_string())?;
+    if let Err(e) = save_feed_cache(&CachedFeed::from_parsed(&parsed), &digest, &cache_path) {
+        tracing::warn!("GTFS feed cache not written: {e}");
+        return Ok((parsed, None));
+    }
+    Ok((parsed, Some(cache_path)))
+}
+
+/// Drop cached feeds no input reads any more (a feed update leaves its old digest
+/// behind).
+fn prune_feed_cache(cache_dir: &str, kept: &[&str]) {
+    let Ok(entries) = std::fs::read_dir(feed_cache_dir(cache_dir)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let stale = path.extension().is_some_and(|e| e == "bin")
+            && !kept.iter().any(|k| Path::new(k) == path);
+        if stale && std::fs::remove_file(&path).is_ok() {
+            tracing::info!("removed stale GTFS feed cache {}", path.display());
+        }
+    }
+}
+
+fn take_parsed(parsed: &mut Option<Result<ParsedGtfs, String>>) -> Result<ParsedGtfs, String> {
+    parsed.take().unwrap_or_else(|| Err("feed was not read".to_string()))
 }
 
 fn finalize(mut g: Graph, config: &BuildConfig) -> Option<Graph> {
+    let sl = config.stop_links;
+    let refined = g.refine_stop_links(sl.segment_radius_m, sl.network_budget_m);
+    if refined.lengthened + refined.relinked > 0 {
+        tracing::info!(
+            "stop links: {} checked, {} lengthened to the street-network walk, {} relinked",
+            refined.checked,
+            refined.lengthened,
+            refined.relinked
+        );
+    }
+    let cross = config.cross_feed_stops;
+    let links = g.link_cross_feed_stops(cross.radius_m, cross.min_name_similarity);
+    if links.linked > 0 {
+        tracing::info!(
+            "linked {} cross-feed stop pairs, merged {} stations",
+            links.linked,
+            links.merged_stations
+        );
+    }
+    let te = config.transfer_edges;
+    let transfers = g.build_transfer_edges(te.radius_m, te.max_detour);
+    if transfers.pairs > 0 {
+        tracing::info!(
+            "transfer edges: {} of {} nearby stop pairs joined ({} without a short enough walk)",
+            transfers.linked,
+            transfers.pairs,
+            transfers.unreachable
+        );
+    }
+
     tracing::info!("building RAPTOR index...");
     g.build_raptor_index();
+    let shapes = g.apply_geometry_level(config.geometry, config.geometry_tolerance_m);
+    tracing::info!(
+        "transit shapes ({:?}): {} patterns share {} polylines, {} points in {} KiB",
+        config.geometry,
+        shapes.patterns,
+        shapes.distinct,
+        shapes.points,
+        shapes.bytes / 1024
+    );
+    let elevator_stops = g.build_stop_elevator_access();
+    if elevator_stops > 0 {
+        tracing::info!("{elevator_stops} stops need an elevator for step-free access");
+    }
 
     let models = config
         .delay_models
@@ -401,6 +766,25 @@ pub fn apply_routing_defaults(
     if let Some(v) = routing.driving_speed_mps {
         g.set_driving_speed_mps(v);
     }
+    if let Some(speeds) = &routing.car_speeds_kmh {
+        for (tag, &kmh) in speeds {
+            match crate::structures::HighwayClass::from_osm_tag(tag) {
+                Some(class) => g.set_car_speed_kmh(class, kmh),
+                None => tracing::warn!(highway = %tag, "ignoring unknown car_speeds_kmh entry"),
+            }
+        }
+    }
+    if let Some(windows) = &routing.car_rush_hours {
+        let windows = windows
+            .iter()
+            .filter_map(|w| {
+                w.to_window()
+                    .inspect_err(|e| tracing::warn!(error = %e, "ignoring car_rush_hours entry"))
+                    .ok()
+            })
+            .collect();
+        g.set_car_rush_hours(windows);
+    }
     if let Some(v) = routing.vehicle_access_secs {
         g.set_vehicle_access_secs(v);
     }
@@ -428,6 +812,54 @@ pub fn apply_routing_defaults(
     if let Some(m) = routing.max_window_minutes {
         g.set_max_window_secs(m.saturating_mul(60));
     }
+    if let Some(v) = routing.crowding_penalty_secs {
+        g.set_crowding_penalty_secs(v);
+    }
+    if let Some(ids) = &routing.elevator_outages {
+        let known = g.known_elevators();
+        for id in ids.iter().filter(|id| !known.contains(id.as_str())) {
+            tracing::warn!(elevator = %id, "elevator_outages entry is not on any stop's step-free path");
+        }
+        let blocked = g.set_elevator_outages(ids);
+        tracing::info!("elevator outages: {} listed, {blocked} stop(s) without step-free access", ids.len());
+    }
+    if let Some(modes) = &routing.bike_route_types {
+        let route_types = modes
+            .iter()
+            .filter_map(|m| {
+                let rt = crate::structures::route_type_from_mode(m);
+                if rt.is_none() {
+                    tracing::warn!(mode = %m, "ignoring unknown bike_route_types entry");
+                }
+                rt
+            })
+            .collect();
+        g.set_bike_route_types(route_types);
+    }
+    if let Some(modes) = &routing.modes {
+        let preferences = modes
+            .iter()
+            .filter_map(|(m, &pref)| {
+                let rt = crate::structures::route_type_from_mode(m);
+                if rt.is_none() {
+                    tracing::warn!(mode = %m, "ignoring unknown modes entry");
+                }
+                Some((rt?, pref))
+            })
+            .collect();
+        g.set_mode_preferences(preferences);
+    }
+    if let Some(profiles) = &routing.routing_profiles {
+        for (name, profile) in profiles {
+            g.set_routing_profile(name.clone(), *profile);
+        }
+    }
+    if routing.max_query_ms.is_some() || routing.max_search_expansions.is_some() {
+        g.set_query_budget(
+            routing.max_query_ms.unwrap_or(0),
+            routing.max_search_expansions.unwrap_or(0),
+        );
+    }
     if let Some(v) = routing.travel_map_grid_step_m {
         g.set_travel_map_grid_step_m(v);
     }
@@ -440,6 +872,12 @@ pub fn apply_routing_defaults(
     if let Some(m) = routing.max_snap_distance_m {
         g.set_max_snap_distance_m(m);
     }
+    if let Some(m) = routing.snap_warning_m {
+        g.set_snap_warning_m(m);
+    }
+    if let Some(n) = routing.snap_island_nodes {
+        g.set_snap_island_nodes(n);
+    }
     if let Some(db) = routing.distance_budget {
         g.set_distance_budget(db);
     }
@@ -479,6 +917,9 @@ pub fn apply_routing_defaults(
     if let Some(b) = routing.balance {
         g.set_balance(b);
     }
+    if let Some(overrides) = &routing.route_overrides {
+        g.set_route_overrides(overrides.iter().map(|o| o.to_route_override()).collect());
+    }
     if let Some(fares) = &routing.fares {
         g.set_fare_model(fares.to_fare_model());
     }
@@ -492,10 +933,40 @@ pub fn apply_routing_defaults(
     if g.contracted.is_some() {
         g.bake_bike_on_contracted_default();
     }
+    g.compute_estimator_factors();
+    if let Some(es) = routing.estimator_speeds {
+        use crate::structures::cost::RoutingMode;
+        for (mode, v) in [
+            (RoutingMode::Walk, es.walk_mps),
+            (RoutingMode::Bike, es.bike_mps),
+            (RoutingMode::Drive, es.car_mps),
+        ] {
+            if let Some(v) = v
+                && let Err(e) = g.set_estimator_speed(mode, v)
+            {
+                tracing::warn!("ignoring estimator_speeds override: {e}");
+            }
+        }
+    }
 
     if routing.prepare_cch_access.unwrap_or(true) && g.contracted.is_some() {
         prepare_cch_access(g, graph_output);
     }
+    if let Some(h) = routing.street_heuristic {
+        g.set_street_heuristic(h);
+        if h == crate::structures::StreetHeuristic::Landmarks {
+            g.prepare_landmarks(routing.alt_landmarks.unwrap_or(8));
+        }
+    }
+    // Runs before the build saves graph.bin, so the tables are persisted; on restore
+    // profiles with unchanged settings are reused rather than rebuilt.
+    let specs: Vec<_> = routing
+        .search_profiles
+        .iter()
+        .flatten()
+        .map(|(name, p)| p.to_spec(name))
+        .collect();
+    g.prepare_search_profiles(&specs);
 }
 
 /// Install the foot-access CCH on `g`, reusing the cached nested-dissection ORDER from
@@ -561,11 +1032,21 @@ mod tests {
             inputs: vec![],
             output: "out.bin".into(),
             osm_output: "osm.bin".into(),
+            transit_output: "transit.bin".into(),
             address_output: "address.bin".into(),
             cache_dir: None,
             elevation_smoothing_epsilon: 4.0,
             surface_speed_factors: Default::default(),
+            min_island_nodes: 40,
             delay_models: vec![],
+            cross_feed_stops: Default::default(),
+            stop_links: Default::default(),
+            transfer_edges: Default::default(),
+            ingest_threads: 0,
+            gtfs_feed_cache: false,
+            geometry: Default::default(),
+            geometry_tolerance_m: 5.0,
+            snapshots: Default::default(),
         }
     }
 
@@ -614,18 +1095,230 @@ mod tests {
         assert!(preflight_inputs(&refs).is_ok());
     }
 
+    #[test]
+    fn feed_refresh_downloads_only_the_named_gtfs_input() {
+        let mut config = empty_config();
+        config.inputs = parse_inputs(
+            "inputs:\n  - ingestor: osm/pbf\n    url: \"https://x/be.pbf\"\n  \
+             - ingestor: gtfs/generic\n    name: bus\n    url: \"https://x/bus.zip\"\n  \
+             - ingestor: gtfs/stib\n    name: stib\n    url: \"https://x/stib.zip\"\n",
+        );
+        let refresh = Refresh::Feed("stib");
+        let downloaded: Vec<bool> = config.inputs.iter().map(|i| refresh.downloads(i)).collect();
+        assert_eq!(downloaded, vec![false, false, true]);
+        assert!(config.inputs.iter().all(|i| Refresh::All.downloads(i)));
+
+        assert!(check_gtfs_feed(&config, "stib").is_ok());
+        let err = check_gtfs_feed(&config, "osm/pbf").unwrap_err();
+        assert!(err.contains("bus, stib"), "lists the feeds: {err}");
+    }
+
     #[test]
     fn run_phase_empty_osm_succeeds() {
         let config = empty_config();
         let mut g = Graph::new();
-        assert!(run_phase(&config, &mut g, 0, "cache", false).is_some());
+        assert!(run_phase(&config, &mut g, 0, "cache", Refresh::Cached).is_some());
     }
 
     #[test]
     fn run_phase_empty_gtfs_succeeds() {
         let config = empty_config();
         let mut g = Graph::new();
-        assert!(run_phase(&config, &mut g, 1, "cache", false).is_some());
+        assert!(run_phase(&config, &mut g, 1, "cache", Refresh::Cached).is_some());
+    }
+
+    #[test]
+    fn parallel_map_keeps_input_order() {
+        let items: Vec<u64> = (0..50).collect();
+        let slow_first = |&i: &u64| {
+            std::thread::sleep(Duration::from_millis(50u64.saturating_sub(i)));
+            i * 2
+        };
+        let expected: Vec<u64> = items.iter().map(|i| i * 2).collect();
+        assert_eq!(parallel_map(&items, 4, slow_first), expected);
+        assert_eq!(parallel_map(&items, 1, slow_first), expected);
+        assert_eq!(ingest_threads(3), 3);
+        assert!(ingest_threads(0) >= 1);
+    }
+
+    /// A one-trip GTFS zip at `dir/feed.zip`.
+    fn write_feed_zip(dir: &Path) -> std::path::PathBuf {
+        use std::io::Write;
+        std::fs::create_dir_all(dir).unwrap();
+        let zip_path = dir.join("feed.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        for (name, content) in [
+            ("agency.txt", "agency_id,agency_name,agency_url,agency_timezone\nA,Bus,http://a,Europe/Brussels\n"),
+            ("stops.txt", "stop_id,stop_name,stop_lat,stop_lon\nS1,One,50.85,4.35\nS2,Two,50.86,4.36\n"),
+            ("routes.txt", "route_id,agency_id,route_short_name,route_long_name,route_type,route_color\nR1,A,1,Line,3,FF0000\n"),
+            ("trips.txt", "route_id,service_id,trip_id,trip_headsign\nR1,WK,T1,Two\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence,pickup_type\n\
+                 T1,08:00:00,08:00:00,S1,1,0\nT1,08:10:00,08:10:00,S2,2,1\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+                 WK,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+            ("calendar_dates.txt", "service_id,date,exception_type\nWK,20260704,1\n"),
+            (
+                "feed_info.txt",
+                "feed_publisher_name,feed_publisher_url,feed_lang,feed_start_date,feed_end_date,feed_version\n\
+                 Bus Co,http://a,fr,20260101,20261231,v7\n",
+            ),
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        zip_path
+    }
+
+    #[test]
+    fn unchanged_feed_is_read_from_the_feed_cache() {
+        let dir = std::env::temp_dir().join(format!("maas-feed-cache-{}", std::process::id()));
+        let zip_path = write_feed_zip(&dir);
+        let cache = dir.join("cache");
+        let (cache_dir, path) = (cache.to_str().unwrap(), zip_path.to_str().unwrap());
+        std::fs::create_dir_all(feed_cache_dir(cache_dir)).unwrap();
+        let stale = format!("{}/0000000000000000.bin", feed_cache_dir(cache_dir));
+        std::fs::write(&stale, b"old").unwrap();
+
+        let (fresh, written) = read_gtfs_cached(path, cache_dir, true).unwrap();
+        let written = written.expect("feed cached");
+        assert!(Path::new(&written).exists());
+        let (cached, reused) = read_gtfs_cached(path, cache_dir, true).unwrap();
+        assert_eq!(reused.as_deref(), Some(written.as_str()));
+        prune_feed_cache(cache_dir, &[&written]);
+        assert!(!Path::new(&stale).exists() && Path::new(&written).exists());
+
+        let info = cached.feed_info().unwrap();
+        assert_eq!((info.name.as_str(), info.version.as_deref()), ("Bus Co", Some("v7")));
+        assert_eq!(info.end_date, fresh.feed_info().unwrap().end_date);
+        let input = &parse_inputs(&format!("inputs:\n  - ingestor: gtfs/generic\n    name: bus\n    url: \"path:{path}\"\n"))[0];
+        let source = source_info(input, path, Some(info.clone()), Duration::ZERO);
+        assert_eq!(source.kind, "gtfs/generic");
+        assert_eq!(source.feed_end_date.as_deref(), Some("2026-12-31"));
+        assert_eq!(source.location, path);
+        assert_eq!(source.sha256.as_deref().map(str::len), Some(64));
+        let load = |p| {
+            let mut g = Graph::new();
+            load_parsed_gtfs(p, &mut g, Default::default(), &Default::default()).unwrap();
+            g
+        };
+        let (a, b) = (load(fresh), load(cached));
+        assert_eq!(a.node_count(), b.node_count());
+        assert_eq!(a.raptor.transit_trip_ids, b.raptor.transit_trip_ids);
+        assert_eq!(a.raptor.transit_routes[0].route_color, Some((255, 0, 0)));
+        assert_eq!(b.raptor.transit_routes[0].route_color, Some((255, 0, 0)));
+        assert_eq!(format!("{:?}", a.raptor.transit_services), format!("{:?}", b.raptor.transit_services));
+        assert_eq!(b.raptor.transit_services[0].added_dates.len(), 1);
+        assert_eq!(
+            format!("{:?}", a.raptor.transit_pattern_stop_times),
+            format!("{:?}", b.raptor.transit_pattern_stop_times),
+            "times and pickup flags survive the cache"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn osm_phase_keeps_the_feed_cache_for_the_gtfs_phase() {
+        let dir = std::env::temp_dir().join(format!("maas-feed-cache-phases-{}", std::process::id()));
+        let zip_path = write_feed_zip(&dir);
+        let cache = dir.join("cache");
+        let cache_dir = cache.to_str().unwrap();
+        let yaml = format!(
+            "inputs:\n  - ingestor: gtfs/generic\n    name: bus\n    url: \"path:{}\"\n",
+            zip_path.display()
+        );
+        let config = BuildConfig { inputs: parse_inputs(&yaml), gtfs_feed_cache: true, ..empty_config() };
+        let (_, written) = read_gtfs_cached(zip_path.to_str().unwrap(), cache_dir, true).unwrap();
+        let written = written.expect("feed cached");
+
+        assert!(run_phase(&config, &mut Graph::new(), 0, cache_dir, Refresh::Cached).is_some());
+        assert!(Path::new(&written).exists(), "the OSM phase reads no feed and prunes none");
+        let mut g = Graph::new();
+        assert!(run_phase(&config, &mut g, 1, cache_dir, Refresh::Cached).is_some());
+        assert!(Path::new(&written).exists(), "the GTFS phase still uses it");
+        assert_eq!(g.raptor.transit_trip_ids, ["T1"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn one_ingest_thread_merges_feeds_like_many() {
+        let dir = std::env::temp_dir().join(format!("maas-feed-threads-{}", std::process::id()));
+        let zip_path = write_feed_zip(&dir);
+        let cache_dir = dir.join("cache");
+        let yaml = format!(
+            "inputs:\n  - ingestor: gtfs/generic\n    name: bus\n    url: \"path:{0}\"\n  \
+             - ingestor: gtfs/generic\n    name: tram\n    url: \"path:{0}\"\n",
+            zip_path.display()
+        );
+        let build = |ingest_threads| {
+            let config = BuildConfig { inputs: parse_inputs(&yaml), ingest_threads, ..empty_config() };
+            let mut g = Graph::new();
+            assert!(run_phase(&config, &mut g, 1, cache_dir.to_str().unwrap(), Refresh::Cached).is_some());
+            g
+        };
+        let (one, many) = (build(1), build(4));
+        assert_eq!(one.raptor.transit_trip_ids.len(), 2);
+        assert_eq!(one.raptor.transit_trip_ids, many.raptor.transit_trip_ids);
+        assert_eq!(one.node_count(), many.node_count());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replacing_a_feed_keeps_the_other_feeds_stops_and_trips() {
+        use crate::ingestion::gtfs::TripId;
+        use crate::services::persistence::{load_transit_graph, save_transit_graph};
+        let dir = std::env::temp_dir().join(format!("maas-feed-replace-{}", std::process::id()));
+        let zip_path = write_feed_zip(&dir);
+        let cache = dir.join("cache");
+        let cache_dir = cache.to_str().unwrap();
+        let yaml = format!(
+            "inputs:\n  - ingestor: gtfs/generic\n    name: bus\n    namespace: bus\n    url: \"path:{0}\"\n  \
+             - ingestor: gtfs/generic\n    name: tram\n    namespace: tram\n    url: \"path:{0}\"\n",
+            zip_path.display()
+        );
+        let config = BuildConfig { inputs: parse_inputs(&yaml), ..empty_config() };
+        let routing = RoutingDefaultConfig::default();
+        let merged = merge_gtfs_phase(Graph::new(), &config, cache_dir, Refresh::Cached, None, &routing).unwrap();
+        let transit = dir.join("transit.bin");
+        save_transit_graph(&merged, &[0; 32], transit.to_str().unwrap()).unwrap();
+        let stop = |g: &Graph, id| g.gtfs_stop_node("tram", id).map(|n| format!("{:?}", g.get_node(n)));
+        let tram_before = merged.feed("tram").unwrap().clone();
+        let s1_before = stop(&merged, "S1");
+        assert!(s1_before.is_some());
+
+        let g = load_transit_graph(transit.to_str().unwrap(), &[0; 32]).unwrap();
+        let g = replace_gtfs_feed(g, &config, cache_dir, "bus", None, &routing).unwrap();
+        let labels: Vec<&str> = g.feeds.iter().map(|f| f.label.as_str()).collect();
+        assert_eq!(labels, ["tram", "bus"], "the new copy is appended");
+        let tram = g.feed("tram").unwrap();
+        assert_eq!(tram.nodes, tram_before.nodes);
+        assert_eq!(tram.trips, tram_before.trips);
+        assert_eq!(tram.stops, tram_before.stops);
+        assert_eq!(stop(&g, "S1"), s1_before);
+        assert_eq!(g.feed("bus").unwrap().nodes, 4..6);
+        assert_eq!(g.raptor.transit_trip_ids, ["", "tram:T1", "bus:T1"], "the old bus trip is blanked");
+        assert_eq!(g.ingestion_reports.iter().filter(|r| r.feed == "bus").count(), 1);
+
+        let g = finish_gtfs_phase(g, &config, SystemTime::now()).unwrap();
+        let r = &g.raptor;
+        assert_eq!(r.trip_id_to_index.get("tram:T1"), Some(&TripId(1)));
+        assert_eq!(r.trip_id_to_index.get("bus:T1"), Some(&TripId(2)));
+        assert_eq!(r.transit_stop_ids.len(), 4, "the old bus stops are no stops any more");
+        assert_eq!(r.transit_patterns.len(), 2, "the old bus pattern is gone");
+        let s1 = r.transit_node_to_stop[g.gtfs_stop_node("tram", "S1").unwrap().0] as usize;
+        let &[(pattern, 0)] = r.transit_idx_stop_patterns[s1].of(&r.transit_stop_patterns) else {
+            panic!("tram S1 keeps its one pattern");
+        };
+        let p = pattern.0 as usize;
+        assert_eq!(r.transit_idx_pattern_trips[p].of(&r.transit_pattern_trips), [TripId(1)]);
+        assert_eq!(r.transit_idx_pattern_stop_times[p].of(&r.transit_pattern_stop_times)[0].departure, 8 * 3600);
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[test]
@@ -640,7 +1333,7 @@ mod tests {
     fn build_gtfs_phase_empty_finalizes() {
         let config = empty_config();
         let g = Graph::new();
-        let result = build_gtfs_phase(g, &config, "cache", false, None, &RoutingDefaultConfig::default());
+        let result = build_gtfs_phase(g, &config, "cache", Refresh::Cached, None, &RoutingDefaultConfig::default());
         assert!(result.is_some());
     }
 
@@ -655,11 +1348,12 @@ mod tests {
                 stairs_speed_mps: None,
                 ramp_speed_mps: None,
                 elevator_secs: None,
+                escalator_speed_mps: None,
                 relocation_fallback_secs: Some(123.0),
             }),
             ..Default::default()
         };
-        let g = build_gtfs_phase(Graph::new(), &config, "cache", false, None, &routing).unwrap();
+        let g = build_gtfs_phase(Graph::new(), &config, "cache", Refresh::Cached, None, &routing).unwrap();
         assert_eq!(g.connector_cost().relocation_fallback_secs, 123.0);
         let run_m = 10.0;
         assert_eq!(
@@ -12,11 +12,18 @@ use std::io::Read;
 use sha2::{Digest, Sha256};
 
 use crate::ingestion::cache::{gtfs_content_hash, resolve_source};
-use crate::structures::{BuildConfig, Config, Ingestor, RoutingDefaultConfig};
+use crate::structures::{
+    BuildConfig, Config, GtfsFilterConfig, Ingestor, OsmPbfIngestor, RoutingDefaultConfig,
+};
 
 use super::persistence::Fingerprint;
 
 fn file_content_hash(path: &str) -> Result<String, String> {
+    file_digest(path).map(|d| d.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// SHA-256 of a file's bytes.
+pub fn file_digest(path: &str) -> Result<Fingerprint, String> {
     let mut f = fs::File::open(path).map_err(|e| format!("failed to open '{path}': {e}"))?;
     let mut hasher = Sha256::new();
     let mut buf = [0u8; 1 << 16];
@@ -29,7 +36,7 @@ fn file_content_hash(path: &str) -> Result<String, String> {
         }
         hasher.update(&buf[..n]);
     }
-    Ok(format!("{:x}", hasher.finalize()))
+    Ok(hasher.finalize().into())
 }
 
 type FileHashStore = BTreeMap<String, String>;
@@ -165,6 +172,8 @@ fn hash_input_identity(
 fn hash_osm_params(h: &mut Sha256, build: &BuildConfig) {
     push_f64(h, build.elevation_smoothing_epsilon);
     sep(h);
+    h.update((build.min_island_nodes as u64).to_le_bytes());
+    sep(h);
     for (surface, factor) in build.surface_speed_factors.sorted_entries() {
         h.update(surface.as_bytes());
         h.update([b'=']);
@@ -173,6 +182,34 @@ fn hash_osm_params(h: &mut Sha256, build: &BuildConfig) {
     }
 }
 
+/// The clip area and address index are baked into osm.bin: a changed bbox, boundary
+/// CONTENT, `address_labels`, `geocoder`, `stop_amenities` or `parking` must rebuild.
+fn hash_osm_clip(h: &mut Sha256, c: &OsmPbfIngestor, cache: &mut FileHashCache) {
+    match c.bbox {
+        Some(b) => {
+            h.update([1u8]);
+            for v in b {
+                push_f64(h, v);
+            }
+        }
+        None => h.update([0u8]),
+    }
+    sep(h);
+    if let Some(path) = &c.boundary_geojson {
+        h.update(b"boundary");
+        sep(h);
+        h.update(cache.raw(path).as_bytes());
+    }
+    sep(h);
+    h.update([
+        c.address_labels as u8,
+        c.geocoder as u8,
+        c.stop_amenities as u8,
+        c.parking as u8,
+    ]);
+    sep(h);
+}
+
 pub fn osm_fingerprint(config: &Config, cache_dir: &str) -> Fingerprint {
     let mut cache = FileHashCache::load(cache_dir);
     let fp = osm_fingerprint_inner(config, cache_dir, &mut cache);
@@ -198,6 +235,9 @@ fn osm_fingerprint_inner(config: &Config, cache_dir: &str, cache: &mut FileHashC
     });
     for input in osm_inputs {
         hash_input_identity(&mut h, input, cache_dir, &mut |p| cache.raw(p));
+        if let Ingestor::OsmPbf(c) = input {
+            hash_osm_clip(&mut h, c, cache);
+        }
     }
 
     hash_osm_params(&mut h, build);
@@ -246,6 +286,30 @@ fn graph_fingerprint_inner(
         hash_input_identity(&mut h, input, cache_dir, &mut |p| {
             gtfs_content_hash(p).unwrap_or_default()
         });
+        // The namespace is baked into every stop/trip/route id of the feed.
+        if let Some(ns) = input.namespace() {
+            h.update(b"ns");
+            sep(&mut h);
+            h.update(ns.as_bytes());
+            sep(&mut h);
+        }
+        // Stop-link radius and fan-out decide which connector edges get baked.
+        if let Ingestor::GtfsGeneric(c) | Ingestor::GtfsStib(c) = input {
+            h.update(b"links");
+            sep(&mut h);
+            push_opt_f64(&mut h, c.link_radius_m);
+            h.update(c.link_k.map_or(0, |k| k as u64).to_le_bytes());
+            sep(&mut h);
+        }
+        // The filter decides which routes, trips and stops are loaded at all.
+        if let Ingestor::GtfsGeneric(c) | Ingestor::GtfsStib(c) = input
+            && c.filter != GtfsFilterConfig::default()
+        {
+            h.update(b"filter");
+            sep(&mut h);
+            h.update(format!("{:?}", c.filter).as_bytes());
+            sep(&mut h);
+        }
         // SNCB's companion OSM railway topology (`osm_url`) is baked into stop-snapping.
         if let Ingestor::GtfsSncb(c) = input {
             let osm_path = c
@@ -279,6 +343,7 @@ fn hash_graph_params(h: &mut Sha256, routing: &RoutingDefaultConfig, build: &Bui
             push_opt_f64(h, c.stairs_speed_mps);
             push_opt_f64(h, c.ramp_speed_mps);
             push_opt_f64(h, c.elevator_secs);
+            push_opt_f64(h, c.escalator_speed_mps);
             // Baked into fallback relocation connectors during the GTFS phase (see
             // `build_gtfs_phase`), so it is a real graph-build input.
             push_opt_f64(h, c.relocation_fallback_secs);
@@ -286,6 +351,18 @@ fn hash_graph_params(h: &mut Sha256, routing: &RoutingDefaultConfig, build: &Bui
         None => h.update([0u8]),
     }
     sep(h);
+    push_f64(h, build.cross_feed_stops.radius_m);
+    push_f64(h, build.cross_feed_stops.min_name_similarity);
+    sep(h);
+    push_f64(h, build.stop_links.segment_radius_m);
+    h.update((build.stop_links.network_budget_m as u64).to_le_bytes());
+    sep(h);
+    push_f64(h, build.transfer_edges.radius_m);
+    push_f64(h, build.transfer_edges.max_detour);
+    sep(h);
+    h.update([build.geometry as u8]);
+    push_f64(h, build.geometry_tolerance_m);
+    sep(h);
     let mut models: Vec<&crate::structures::DelayModelConfig> = build.delay_models.iter().collect();
     models.sort_by(|a, b| a.mode.cmp(&b.mode));
     for m in models {
@@ -480,6 +557,45 @@ default_routing: {{}}
         assert_eq!(a0, a1, "osm content change must NOT change address fingerprint");
     }
 
+    #[test]
+    fn osm_clip_change_invalidates_osm() {
+        let dir = tmp("clip");
+        std::fs::create_dir_all(&dir).unwrap();
+        let pbf = dir.join("p.pbf");
+        std::fs::write(&pbf, b"P").unwrap();
+        let boundary = dir.join("b.geojson");
+        std::fs::write(&boundary, r#"{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,0]]]}"#)
+            .unwrap();
+        let mk = |clip: &str| -> Config {
+            let yaml = format!(
+                r#"
+build:
+  inputs:
+    - ingestor: osm/pbf
+      url: "path:{}"
+{clip}
+default_routing: {{}}
+"#,
+                pbf.display()
+            );
+            serde_yaml_ng::from_str(&yaml).unwrap()
+        };
+        let cache = dir.to_str().unwrap();
+        let none = osm_fingerprint(&mk(""), cache);
+        let bbox = osm_fingerprint(&mk("      bbox: [0, 0, 1, 1]"), cache);
+        let bbox2 = osm_fingerprint(&mk("      bbox: [0, 0, 2, 1]"), cache);
+        let with_boundary = format!("      boundary_geojson: {}", boundary.display());
+        let poly0 = osm_fingerprint(&mk(&with_boundary), cache);
+        std::fs::write(&boundary, r#"{"type":"Polygon","coordinates":[[[0,0],[2,0],[2,2],[0,2],[0,0]]]}"#)
+            .unwrap();
+        let poly1 = osm_fingerprint(&mk(&with_boundary), cache);
+
+        assert_ne!(none, bbox);
+        assert_ne!(bbox, bbox2);
+        assert_ne!(none, poly0);
+        assert_ne!(poly0, poly1, "boundary content change must rebuild");
+    }
+
     #[test]
     fn elevation_epsilon_change_invalidates_osm_and_graph_not_address() {
         let dir = tmp("eps");
@@ -607,6 +723,65 @@ default_routing:
         );
     }
 
+    #[test]
+    fn gtfs_namespace_change_invalidates_graph_not_osm() {
+        let dir = tmp("namespace");
+        let (base, cache) = config_with_files(&dir, b"P", &[("stops.txt", "a")], &[("x", "y")]);
+        let gtfs_path = base.build.inputs[1].url().to_string();
+        let mk = |ns: &str| -> Config {
+            let yaml = format!(
+                r#"
+build:
+  inputs:
+    - ingestor: gtfs/generic
+      name: bus
+      url: "{gtfs_path}"
+      {ns}
+default_routing: {{}}
+"#
+            );
+            serde_yaml_ng::from_str(&yaml).unwrap()
+        };
+        assert_ne!(
+            graph_fingerprint(&mk(""), &cache),
+            graph_fingerprint(&mk("namespace: bus"), &cache)
+        );
+        assert_eq!(
+            osm_fingerprint(&mk(""), &cache),
+            osm_fingerprint(&mk("namespace: bus"), &cache)
+        );
+    }
+
+    #[test]
+    fn gtfs_filter_change_invalidates_graph_not_osm() {
+        let dir = tmp("filter");
+        let (base, cache) = config_with_files(&dir, b"P", &[("stops.txt", "a")], &[("x", "y")]);
+        let gtfs_path = base.build.inputs[1].url().to_string();
+        let mk = |filter: &str| -> Config {
+            let yaml = format!(
+                r#"
+build:
+  inputs:
+    - ingestor: gtfs/generic
+      name: bus
+      url: "{gtfs_path}"
+      {filter}
+default_routing: {{}}
+"#
+            );
+            serde_yaml_ng::from_str(&yaml).unwrap()
+        };
+        assert_eq!(graph_fingerprint(&mk(""), &cache), graph_fingerprint(&mk("filter: {}"), &cache));
+        assert_ne!(
+            graph_fingerprint(&mk(""), &cache),
+            graph_fingerprint(&mk("filter: { route_types: [tram] }"), &cache)
+        );
+        assert_eq!(
+            osm_fingerprint(&mk(""), &cache),
+            osm_fingerprint(&mk("filter: { route_types: [tram] }"), &cache)
+        );
+    }
+
     #[test]
     fn connector_cost_change_invalidates_graph() {
         let dir = tmp("conn");
@@ -0,0 +1,227 @@
+//! `--diff old.bin new.bin`: compare two serialized graphs (stops, routes, trips,
+//! service date range, street network size) so an operator can sanity-check a new
+//! import before swapping it live. Config-less: the files are loaded without their
+//! fingerprint check, only the schema version must match this binary.
+
+use std::collections::BTreeSet;
+
+use chrono::{Days, NaiveDate};
+
+use crate::structures::Graph;
+
+/// Added/removed ids printed per category; the counts are always complete.
+const LISTED_IDS: usize = 10;
+
+/// Ids present in only one of the two graphs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdDiff {
+    pub old_count: usize,
+    pub new_count: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl IdDiff {
+    fn between(old: BTreeSet<String>, new: BTreeSet<String>) -> Self {
+        IdDiff {
+            old_count: old.len(),
+            new_count: new.len(),
+            added: new.difference(&old).cloned().collect(),
+            removed: old.difference(&new).cloned().collect(),
+        }
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Street network size; `junctions`/`super_edges` are the contracted graph's, the
+/// only street network left in a finalized graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkSize {
+    pub nodes: usize,
+    pub edges: usize,
+    pub junctions: usize,
+    pub super_edges: usize,
+}
+
+impl NetworkSize {
+    fn of(g: &Graph) -> Self {
+        let (junctions, super_edges) = g
+            .contracted
+            .as_ref()
+            .map_or((0, 0), |cg| (cg.junction_count(), cg.edge_count()));
+        NetworkSize {
+            nodes: g.node_count(),
+            edges: (0..g.edge_count()).map(|u| g.out_edges(crate::structures::NodeID(u)).len()).sum(),
+            junctions,
+            super_edges,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDiff {
+    pub stops: IdDiff,
+    pub routes: IdDiff,
+    pub trips: IdDiff,
+    /// First and last date any service runs, per graph.
+    pub old_service_range: Option<(NaiveDate, NaiveDate)>,
+    pub new_service_range: Option<(NaiveDate, NaiveDate)>,
+    pub old_network: NetworkSize,
+    pub new_network: NetworkSize,
+}
+
+impl GraphDiff {
+    pub fn between(old: &Graph, new: &Graph) -> Self {
+        GraphDiff {
+            stops: IdDiff::between(stop_ids(old), stop_ids(new)),
+            routes: IdDiff::between(route_ids(old), route_ids(new)),
+            trips: IdDiff::between(
+                old.raptor.transit_trip_ids.iter().cloned().collect(),
+                new.raptor.transit_trip_ids.iter().cloned().collect(),
+            ),
+            old_service_range: service_range(old),
+            new_service_range: service_range(new),
+            old_network: NetworkSize::of(old),
+            new_network: NetworkSize::of(new),
+        }
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        self.stops.is_unchanged()
+            && self.routes.is_unchanged()
+            && self.trips.is_unchanged()
+            && self.old_service_range == self.new_service_range
+            && self.old_network == self.new_network
+    }
+}
+
+/// GTFS stop ids, or the stop names of a graph built before ids were kept.
+fn stop_ids(g: &Graph) -> BTreeSet<String> {
+    if g.raptor.transit_stop_ids.is_empty() {
+        return g.raptor.transit_stop_names.iter().cloned().collect();
+    }
+    g.raptor.transit_stop_ids.iter().cloned().collect()
+}
+
+/// GTFS route ids, or short names when a graph kept none.
+fn route_ids(g: &Graph) -> BTreeSet<String> {
+    if g.raptor.transit_route_ids.is_empty() {
+        return g.raptor.transit_routes.iter().map(|r| r.route_short_name.clone()).collect();
+    }
+    g.raptor.transit_route_ids.iter().cloned().collect()
+}
+
+fn service_range(g: &Graph) -> Option<(NaiveDate, NaiveDate)> {
+    let days = g.raptor.transit_services.iter().flat_map(|s| {
+        let range = (s.start_date <= s.end_date).then_some([s.start_date, s.end_date]);
+        range.into_iter().flatten().chain(s.added_dates.iter().copied())
+    });
+    let (min, max) = days.fold(None, |acc: Option<(u32, u32)>, d| match acc {
+        Some((lo, hi)) => Some((lo.min(d), hi.max(d))),
+        None => Some((d, d)),
+    })?;
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    Some((epoch + Days::new(min as u64), epoch + Days::new(max as u64)))
+}
+
+fn fmt_ids(f: &mut std::fmt::Formatter<'_>, name: &str, d: &IdDiff) -> std::fmt::Result {
+    writeln!(
+        f,
+        "{name:<7} {} -> {} ({:+}): {} added, {} removed",
+        d.old_count,
+        d.new_count,
+        d.new_count as i64 - d.old_count as i64,
+        d.added.len(),
+        d.removed.len()
+    )?;
+    for (sign, ids) in [('+', &d.added), ('-', &d.removed)] {
+        for id in ids.iter().take(LISTED_IDS) {
+            writeln!(f, "  {sign} {id}")?;
+        }
+        if ids.len() > LISTED_IDS {
+            writeln!(f, "  {sign} … {} more", ids.len() - LISTED_IDS)?;
+        }
+    }
+    Ok(())
+}
+
+impl std::fmt::Display for GraphDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_ids(f, "stops", &self.stops)?;
+        fmt_ids(f, "routes", &self.routes)?;
+        fmt_ids(f, "trips", &self.trips)?;
+        let range = |r: Option<(NaiveDate, NaiveDate)>| {
+            r.map_or_else(|| "none".to_string(), |(a, b)| format!("{a}..{b}"))
+        };
+        writeln!(
+            f,
+            "service {} -> {}",
+            range(self.old_service_range),
+            range(self.new_service_range)
+        )?;
+        let (o, n) = (self.old_network, self.new_network);
+        for (name, a, b) in [
+            ("nodes", o.nodes, n.nodes),
+            ("edges", o.edges, n.edges),
+            ("junctions", o.junctions, n.junctions),
+            ("super_edges", o.super_edges, n.super_edges),
+        ] {
+            writeln!(f, "{name:<11} {a} -> {b} ({:+})", b as i64 - a as i64)?;
+        }
+        write!(f, "{}", if self.is_unchanged() { "unchanged" } else { "changed" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::gtfs::ServicePattern;
+
+    fn graph(stops: &[&str], trips: &[&str], service: (u32, u32)) -> Graph {
+        let mut g = Graph::new();
+        g.raptor.transit_stop_ids = stops.iter().map(|s| s.to_string()).collect();
+        g.raptor.transit_trip_ids = trips.iter().map(|s| s.to_string()).collect();
+        g.raptor.transit_route_ids = vec!["R1".into()];
+        g.add_transit_services(vec![ServicePattern {
+            days_of_week: 0x7F,
+            start_date: service.0,
+            end_date: service.1,
+            added_dates: vec![],
+            removed_dates: vec![],
+        }]);
+        g
+    }
+
+    #[test]
+    fn reports_added_and_removed_ids_and_service_range() {
+        let old = graph(&["S1", "S2"], &["T1", "T2"], (9497, 9587));
+        let new = graph(&["S2", "S3", "S4"], &["T1", "T2"], (9497, 9680));
+        let d = GraphDiff::between(&old, &new);
+        assert_eq!(d.stops.added, vec!["S3", "S4"]);
+        assert_eq!(d.stops.removed, vec!["S1"]);
+        assert!(d.trips.is_unchanged() && d.routes.is_unchanged());
+        assert_eq!(
+            d.new_service_range,
+            Some((NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(), NaiveDate::from_ymd_opt(2026, 7, 3).unwrap()))
+        );
+        let text = d.to_string();
+        assert!(text.starts_with("stops   2 -> 3 (+1): 2 added, 1 removed\n  + S3\n  + S4\n  - S1\n"), "{text}");
+        assert!(text.contains("service 2026-01-01..2026-04-01 -> 2026-01-01..2026-07-03"), "{text}");
+        assert!(text.ends_with("changed"));
+    }
+
+    #[test]
+    fn identical_graphs_are_unchanged_and_long_lists_are_truncated() {
+        let trips: Vec<String> = (0..15).map(|i| format!("T{i:02}")).collect();
+        let trips: Vec<&str> = trips.iter().map(String::as_str).collect();
+        let g = graph(&["S1"], &trips, (1, 2));
+        assert!(GraphDiff::between(&g, &graph(&["S1"], &trips, (1, 2))).is_unchanged());
+
+        let text = GraphDiff::between(&graph(&["S1"], &[], (1, 2)), &g).to_string();
+        assert!(text.contains("  + T09\n  + … 5 more\n"), "{text}");
+        assert!(!text.contains("T10"));
+    }
+}
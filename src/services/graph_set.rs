@@ -0,0 +1,72 @@
+//! Several regional graphs served as one (national deployments). Each shard is a
+//! complete `graph.bin` built from its own config; [`crate::routing::federation`]
+//! routes within the shard covering both endpoints or stitches two shards' plans at a
+//! configured boundary station.
+
+use std::sync::Arc;
+
+use crate::{
+    services::{build::apply_routing_defaults, persistence::load_graph_unchecked},
+    structures::{FederationConfig, Graph, LatLng, RoutingDefaultConfig},
+};
+
+pub struct Shard {
+    pub name: String,
+    pub graph: Arc<Graph>,
+}
+
+pub struct GraphSet {
+    pub shards: Vec<Shard>,
+    /// Station ids where a plan may leave one shard for another.
+    pub boundary_stations: Vec<String>,
+    /// Minimum wait at the boundary station between stitched halves.
+    pub transfer_secs: u32,
+}
+
+impl GraphSet {
+    pub fn new(boundary_stations: Vec<String>, transfer_secs: u32) -> Self {
+        GraphSet { shards: Vec::new(), boundary_stations, transfer_secs }
+    }
+
+    pub fn add_shard(&mut self, name: impl Into<String>, graph: Arc<Graph>) -> usize {
+        self.shards.push(Shard { name: name.into(), graph });
+        self.shards.len() - 1
+    }
+
+    /// Load every shard file and apply the serving config's routing defaults to each, so
+    /// all shards answer with the same tunables. Shard files are built (and
+    /// fingerprinted) by their own configs, hence the unchecked load.
+    pub fn load(cfg: &FederationConfig, routing: &RoutingDefaultConfig) -> Result<Self, String> {
+        if cfg.shards.is_empty() {
+            return Err("federation.shards is empty".into());
+        }
+        let mut set = GraphSet::new(cfg.boundary_stations.clone(), cfg.transfer_secs);
+        for shard in &cfg.shards {
+            let mut g = load_graph_unchecked(&shard.graph)
+                .map_err(|e| format!("shard '{}': {e}", shard.name))?;
+            apply_routing_defaults(&mut g, routing, &shard.graph);
+            tracing::info!("federation: loaded shard '{}' from {}", shard.name, shard.graph);
+            set.add_shard(shard.name.clone(), Arc::new(g));
+        }
+        for id in &set.boundary_stations {
+            let holders = set.shards.iter().filter(|s| s.graph.station_endpoint(id).is_some()).count();
+            if holders < 2 {
+                tracing::warn!("federation: boundary station '{id}' is known to {holders} shard(s); it cannot stitch plans");
+            }
+        }
+        Ok(set)
+    }
+
+    /// Boundary stations shared by shards `a` and `b`, with their coordinate in each.
+    pub fn boundaries_between(&self, a: usize, b: usize) -> Vec<(&str, LatLng, LatLng)> {
+        let (ga, gb) = (&self.shards[a].graph, &self.shards[b].graph);
+        self.boundary_stations
+            .iter()
+            .filter_map(|id| {
+                let (la, _) = ga.station_endpoint(id)?;
+                let (lb, _) = gb.station_endpoint(id)?;
+                Some((id.as_str(), la, lb))
+            })
+            .collect()
+    }
+}
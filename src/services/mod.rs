@@ -1,6 +1,11 @@
+pub mod accessibility;
+pub mod bench;
 pub mod build;
 pub mod fingerprint;
+pub mod graph_diff;
+pub mod graph_set;
 pub mod persistence;
 pub mod realtime_poller;
 pub mod rebuild;
 pub mod scheduler;
+pub mod travel_export;
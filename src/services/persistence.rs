@@ -2,11 +2,12 @@ use std::fs;
 
 use postcard::{from_bytes, to_allocvec};
 
-use crate::structures::{AddressIndex, Graph};
+use crate::ingestion::gtfs::CachedFeed;
+use crate::structures::{AddressIndex, Graph, SnapshotConfig};
 
 /// Magic prefix identifying a maas-rs cache file.
 const MAGIC: &[u8; 4] = b"MAAS";
-/// Bump when any OSM-side `Graph` field (nodes/edges/kdtree/id_mapper) changes layout.
+/// Bump when any OSM-side `Graph` field (nodes/edges/nodes_tree/id_mapper) changes layout.
 /// v3: bike-route membership now propagated from OSM relations onto edges' `cycleroute`.
 /// v4: `StreetEdgeData` gained a `var_gen` variance-generator field.
 /// v5: `elev_delta` is now DEM-denoised per-way at ingestion (RDP smoothing), so
@@ -46,7 +47,23 @@ const MAGIC: &[u8; 4] = b"MAAS";
 /// v14: header layout changed: osm.bin now carries a 32-byte input+param fingerprint
 ///      after the version field (dependency-aware cache invalidation), so a v13 header is
 ///      unreadable and must rebuild.
-pub const OSM_SCHEMA_VERSION: u32 = 14;
+/// v15: the street-node snap index is an R-tree serialized as its flat point list
+///      instead of a kd-tree.
+/// v16: `id_mapper` keys are `(interned source, i64)` pairs instead of eid strings.
+/// v17: the OSM view persists `elevator_edges`; escalators (`steps` + `conveying`) and
+///      segments into `highway=elevator` nodes become connectors.
+/// v18: the OSM view persists the `addr:*` address index (`address_labels`).
+/// v19: the address index also carries named POIs (`geocoder`).
+/// v20: the OSM view persists the stop amenity index (`stop_amenities`).
+/// v21: the OSM view persists the parking index (`parking`).
+/// v22: the OSM view persists the street-phase `build_info` sources.
+/// v23: `BikeAttrs` gained `no_sidewalk` and `lit` (walking comfort).
+/// v24: `BikeAttrs` gained `escalator` (`avoidStairs`).
+/// v25: one-way streets close the reverse edge to cars; parallel street edges are merged.
+/// v26: the address index keeps its positions in a `PointIndex` (flat point list).
+/// v27: so does the stop amenity index.
+/// v28: and the parking index.
+pub const OSM_SCHEMA_VERSION: u32 = 28;
 /// Bump when any `Graph`/`RaptorIndex` field changes layout (or, like v5, the baked
 /// `elev_delta` edge values change meaning).
 /// v7: `Graph` gained a serialized `contracted: Option<ContractedGraph>` (P3 node
@@ -95,7 +112,46 @@ pub const OSM_SCHEMA_VERSION: u32 = 14;
 ///      the version field (dependency-aware cache invalidation), so a v21 header is unreadable
 ///      and must rebuild. The graph fingerprint embeds the osm fingerprint, so an OSM/DEM
 ///      change cascades to graph.bin; this bump also invalidates cch.bin via the XOR header.
-pub const GRAPH_SCHEMA_VERSION: u32 = 22;
+/// v23: `TripSegment` drops its per-hop `origin/destination_stop_sequence` (unused; the stop
+///      order lives once per pattern), shrinking `transit_departures` from 24 to 16 B/entry.
+/// v24: `Graph` persists `ingestion_reports` (per-feed GTFS quality counters).
+/// v25: `Graph` persists the `feeds` registry (label, id namespace, owned id ranges).
+/// v26: each `FeedRecord` persists its raw GTFS `stop_id -> NodeID` map.
+/// v27: `nodes_tree` and `transit_stops_tree` are R-trees serialized as flat point lists;
+///      GTFS stops link to the nearest walkable street node within 100 m.
+/// v28: `id_mapper` keys are `(interned source, i64)` pairs instead of eid strings.
+/// v29: RaptorIndex carries `transit_stop_wheelchair` (per-stop `wheelchair_boarding`) for
+///      the wheelchair routing profile; rebuild so the array is populated.
+/// v30: RaptorIndex carries `stop_elevator_routes` (elevators on each stop's step-free
+///      access paths) for elevator outages.
+/// v31: stop → street link lengths are street-network walks from the stop's own segment
+///      (`build.stop_links`), not straight lines.
+/// v32: stops link to the closest walkable node within `link_radius_m` (was: walkable
+///      within 100 m, else any node), optionally to `link_k` of them.
+/// v33: nearby stops are joined by street-network walk edges (`build.transfer_edges`).
+/// v34: `Graph` carries the OSM address index labelling plan street places.
+/// v35: the OSM address index also carries named POIs for `geocode`.
+/// v36: RaptorIndex carries per-language stop/route names from `translations.txt`.
+/// v37: `Graph` carries the OSM stop amenity index.
+/// v38: stops carry their GTFS `stop_code` (`TransitStopData::code`, RaptorIndex `transit_stop_codes`).
+/// v39: `FeedRecord` carries the feed's `service_day_cutover`.
+/// v40: transit shapes live deduplicated and delta-encoded in `Graph::shapes`; patterns
+///      keep a shape id (`transit_pattern_shape_ids`).
+/// v41: `Graph` carries the OSM parking index.
+/// v42: `Graph` carries `build_info` (sources, hashes, feed validity, crate version).
+/// v43: `FeedRecord` carries `feed_info.txt` validity and the feed's service day range.
+/// v44: `BikeAttrs` gained `no_sidewalk` and `lit` (walking comfort).
+/// v45: `BikeAttrs` gained `escalator` (`avoidStairs`).
+/// v46: `transit_departures` is struct-of-arrays (`TripSegments`); `TimetableSegment` is u32.
+/// v47: `IngestionReport` counts malformed route colours (`route_colors_invalid`).
+/// v48: `IngestionReport` counts routes, trips and stops dropped by a feed `filter`.
+/// v49: `IngestionReport` keeps the skipped records themselves (`warnings`).
+/// v50: `Graph` persists the `search_profiles` registry (named ALT tables).
+/// v51: street edges follow OSM_SCHEMA_VERSION 25 (car one-ways, merged parallel edges).
+/// v52: the address index follows OSM_SCHEMA_VERSION 26 (`PointIndex`).
+/// v53: the stop amenity index follows OSM_SCHEMA_VERSION 27.
+/// v54: the parking index follows OSM_SCHEMA_VERSION 28.
+pub const GRAPH_SCHEMA_VERSION: u32 = 54;
 
 /// Bump when the persisted (`#[serde]`-non-skipped) fields of [`AddressIndex`] change
 /// layout. Sibling cache `address.bin`, independent of the routing graph.
@@ -112,6 +168,12 @@ pub const GRAPH_SCHEMA_VERSION: u32 = 22;
 ///     unreadable and must rebuild.
 pub const ADDRESS_SCHEMA_VERSION: u32 = 4;
 
+/// Bump when the layout of [`CachedFeed`] (the per-feed GTFS read cache) changes.
+/// v1: initial — stops, routes, trips with stop times, calendars, shapes, translations.
+/// v2: keeps `feed_info.txt` (publisher, version, validity) for `dataInfo`.
+/// v3: keeps the number of route colours dropped as malformed.
+pub const FEED_SCHEMA_VERSION: u32 = 3;
+
 /// Bump when the persisted `cch.bin` payload layout (the metric-independent nested-
 /// dissection ORDER + vertex count) changes. Independent of the CCH *metric*, which is
 /// re-customized from the live graph on every load. `cch.bin` is ALSO implicitly gated
@@ -126,6 +188,15 @@ pub const CCH_SCHEMA_VERSION: u32 = 1;
 /// invalidates a cached order automatically.
 const CCH_HEADER_VERSION: u32 = CCH_SCHEMA_VERSION ^ GRAPH_SCHEMA_VERSION;
 
+/// Bump when the `transit.bin` wrapper (the OSM view plus the merged, unfinalized GTFS
+/// feeds) changes. Its payload also carries the RAPTOR tables and the OSM view, both
+/// covered by `GRAPH_SCHEMA_VERSION` (bumped with every OSM layout change), so the
+/// header stores `GRAPH_SCHEMA_VERSION ^ TRANSIT_SCHEMA_VERSION`.
+/// v1: initial — OSM view, `raptor`, `shapes`, ingestion reports and the feed registry.
+pub const TRANSIT_SCHEMA_VERSION: u32 = 1;
+
+const TRANSIT_HEADER_VERSION: u32 = TRANSIT_SCHEMA_VERSION ^ GRAPH_SCHEMA_VERSION;
+
 const HEADER_LEN: usize = 8;
 
 /// MAGIC(4) + version(4) + fingerprint(32).
@@ -250,12 +321,7 @@ pub fn load_graph(path: &str, fp: &Fingerprint) -> Result<Graph, StaleCache> {
         tracing::error!("{e}");
         StaleCache(e)
     })?;
-    // Rebuild #[serde(skip)] runtime indices / spatial R-trees.
-    graph.raptor.build_runtime_indices();
-    graph.build_edge_index();
-    if let Some(cg) = graph.contracted.as_mut() {
-        cg.build_seg_index();
-    }
+    graph.rebuild_derived_indices();
     tracing::info!("graph restored from {path}");
     Ok(graph)
 }
@@ -264,15 +330,21 @@ pub fn load_graph(path: &str, fp: &Fingerprint) -> Result<Graph, StaleCache> {
 /// path (fingerprint-gated); for config-less diagnostics only.
 pub fn load_graph_unchecked(path: &str) -> Result<Graph, String> {
     let bytes = fs::read(path).map_err(|e| format!("Failed to read graph file: {e}"))?;
-    let payload = split_header_fp_any(&bytes, GRAPH_SCHEMA_VERSION, path)?;
+    graph_from_bytes_named(&bytes, path)
+}
+
+/// `load_graph_unchecked` over an in-memory `graph.bin` (no file system), for
+/// embedders such as the wasm build that receive the graph as bytes.
+pub fn graph_from_bytes(bytes: &[u8]) -> Result<Graph, String> {
+    graph_from_bytes_named(bytes, "<bytes>")
+}
+
+fn graph_from_bytes_named(bytes: &[u8], path: &str) -> Result<Graph, String> {
+    let payload = split_header_fp_any(bytes, GRAPH_SCHEMA_VERSION, path)?;
     let mut graph: Graph =
         from_bytes(payload).map_err(|e| format!("Failed to deserialize graph: {e}"))?;
     graph.raptor.validate()?;
-    graph.raptor.build_runtime_indices();
-    graph.build_edge_index();
-    if let Some(cg) = graph.contracted.as_mut() {
-        cg.build_seg_index();
-    }
+    graph.rebuild_derived_indices();
     Ok(graph)
 }
 
@@ -300,6 +372,26 @@ pub fn load_osm_graph(path: &str, fp: &Fingerprint) -> Result<Graph, StaleCache>
     Ok(graph)
 }
 
+/// `fp` is the OSM fingerprint: the feeds merged in are replaced one by one, the street
+/// graph under them must not change.
+pub fn save_transit_graph(graph: &Graph, fp: &Fingerprint, path: &str) -> Result<(), String> {
+    let payload = graph.to_transit_postcard()?;
+    let bytes = with_header_fp(TRANSIT_HEADER_VERSION, fp, &payload);
+    fs::write(path, &bytes).map_err(|e| format!("Failed to save transit graph: {e}"))?;
+    tracing::info!("transit graph saved to {path}");
+    Ok(())
+}
+
+pub fn load_transit_graph(path: &str, fp: &Fingerprint) -> Result<Graph, StaleCache> {
+    tracing::info!("restoring transit graph from {path}…");
+    let bytes =
+        fs::read(path).map_err(|e| StaleCache(format!("Failed to read transit graph file: {e}")))?;
+    let payload = split_header_fp(&bytes, TRANSIT_HEADER_VERSION, fp, path)?;
+    let graph = Graph::from_transit_postcard(payload).map_err(StaleCache)?;
+    tracing::info!("transit graph restored from {path}");
+    Ok(graph)
+}
+
 /// Sibling CCH-order path derived from the graph output, so distinct presets never
 /// share a CCH file.
 pub fn cch_cache_path(graph_output: &str) -> String {
@@ -376,6 +468,24 @@ pub fn load_address_index(path: &str, fp: &Fingerprint) -> Result<AddressIndex,
     Ok(index)
 }
 
+/// `fp` is the digest of the feed file the cache was read from.
+pub fn save_feed_cache(feed: &CachedFeed, fp: &Fingerprint, path: &str) -> Result<(), String> {
+    let payload = to_allocvec(feed).map_err(|e| format!("Failed to serialize GTFS feed: {e}"))?;
+    let bytes = with_header_fp(FEED_SCHEMA_VERSION, fp, &payload);
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {e}", dir.display()))?;
+    }
+    fs::write(path, &bytes).map_err(|e| format!("Failed to save GTFS feed cache: {e}"))?;
+    Ok(())
+}
+
+pub fn load_feed_cache(path: &str, fp: &Fingerprint) -> Result<CachedFeed, StaleCache> {
+    let bytes =
+        fs::read(path).map_err(|e| StaleCache(format!("Failed to read GTFS feed cache: {e}")))?;
+    let payload = split_header_fp(&bytes, FEED_SCHEMA_VERSION, fp, path)?;
+    from_bytes(payload).map_err(|e| StaleCache(format!("Failed to deserialize GTFS feed cache: {e}")))
+}
+
 /// Write-new, rotate-prev, atomic-rename so a crash always leaves a valid `<path>`
 /// or `<path>.prev` for a later `--restore`.
 pub fn save_graph_with_rollback(graph: &Graph, fp: &Fingerprint, path: &str) -> Result<(), String> {
@@ -394,6 +504,74 @@ pub fn save_graph_with_rollback(graph: &Graph, fp: &Fingerprint, path: &str) ->
     Ok(())
 }
 
+const SNAPSHOT_STAMP: &str = "%Y%m%dT%H%M%S";
+
+/// Copy the graph just saved at `path` into `snapshots.output` as
+/// `graph-<stamp>.bin` (written aside, then renamed), then delete all but the newest
+/// `keep`. `Ok(None)` when snapshots are disabled.
+pub fn snapshot_graph(
+    path: &str,
+    snapshots: &SnapshotConfig,
+    at: chrono::NaiveDateTime,
+) -> Result<Option<String>, String> {
+    let Some(dir) = &snapshots.output else {
+        return Ok(None);
+    };
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{dir}': {e}"))?;
+    let name = format!("graph-{}.bin", at.format(SNAPSHOT_STAMP));
+    let snapshot = std::path::Path::new(dir).join(name).to_string_lossy().into_owned();
+    let new_path = format!("{snapshot}.new");
+    fs::copy(path, &new_path).map_err(|e| format!("Failed to copy '{path}' to '{new_path}': {e}"))?;
+    fs::rename(&new_path, &snapshot).map_err(|e| format!("Failed to publish '{snapshot}': {e}"))?;
+    for old in list_snapshots(dir).into_iter().skip(snapshots.keep.max(1)) {
+        fs::remove_file(&old).map_err(|e| format!("Failed to prune '{old}': {e}"))?;
+    }
+    Ok(Some(snapshot))
+}
+
+/// [`snapshot_graph`] at the local time, logging rather than failing: the graph
+/// itself is already saved.
+pub fn snapshot_saved_graph(path: &str, snapshots: &SnapshotConfig) {
+    match snapshot_graph(path, snapshots, chrono::Local::now().naive_local()) {
+        Ok(Some(snapshot)) => tracing::info!("graph snapshot written to {snapshot}"),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("graph snapshot failed: {e}"),
+    }
+}
+
+/// Snapshot files in `dir`, newest first (the stamps sort chronologically).
+pub fn list_snapshots(dir: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut out: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            name.strip_prefix("graph-")
+                .and_then(|r| r.strip_suffix(".bin"))
+                .is_some_and(|stamp| chrono::NaiveDateTime::parse_from_str(stamp, SNAPSHOT_STAMP).is_ok())
+        })
+        .map(|e| e.path().to_string_lossy().into_owned())
+        .collect();
+    out.sort_unstable_by(|a, b| b.cmp(a));
+    out
+}
+
+/// The newest snapshot in `dir` that loads under the current schema, skipping (and
+/// logging) unreadable ones. The fingerprint is not checked: rolling back means
+/// serving a graph built from other inputs.
+pub fn load_newest_snapshot(dir: &str) -> Result<(String, Graph), String> {
+    for path in list_snapshots(dir) {
+        match load_graph_unchecked(&path) {
+            Ok(graph) => return Ok((path, graph)),
+            Err(e) => tracing::warn!("skipping snapshot {path}: {e}"),
+        }
+    }
+    Err(format!("no usable graph snapshot in '{dir}'"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -421,6 +599,36 @@ mod tests {
         assert!(load_graph(path_s, &FP0).is_ok());
     }
 
+    #[test]
+    fn snapshots_rotate_and_restore_the_newest_valid() {
+        let dir = std::env::temp_dir().join("maas_persist_snapshot_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let graph = dir.join("graph.bin");
+        let graph_s = graph.to_str().unwrap();
+        let snaps = dir.join("snapshots").to_str().unwrap().to_string();
+        let cfg = SnapshotConfig { output: Some(snaps.clone()), keep: 2 };
+        let at = |h: u32| chrono::NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(h, 0, 0).unwrap();
+
+        assert_eq!(snapshot_graph(graph_s, &SnapshotConfig::default(), at(1)).unwrap(), None);
+        save_graph(&Graph::new(), &FP0, graph_s).unwrap();
+        for h in 1..=3 {
+            snapshot_graph(graph_s, &cfg, at(h)).unwrap().unwrap();
+        }
+        std::fs::write(format!("{snaps}/notes.txt"), "kept").unwrap();
+        let names: Vec<String> = list_snapshots(&snaps)
+            .iter()
+            .map(|p| p.rsplit('/').next().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["graph-20260301T030000.bin", "graph-20260301T020000.bin"]);
+        assert!(std::path::Path::new(&format!("{snaps}/notes.txt")).exists());
+
+        // A truncated newest snapshot falls back to the one before it.
+        std::fs::write(format!("{snaps}/graph-20260301T030000.bin"), b"MAAS").unwrap();
+        let (path, _) = load_newest_snapshot(&snaps).unwrap();
+        assert!(path.ends_with("graph-20260301T020000.bin"), "{path}");
+    }
+
     #[test]
     fn load_graph_rebuilds_edge_index_for_snapping() {
         use crate::structures::{
@@ -524,8 +732,17 @@ mod tests {
         assert!(cg.junction_count() >= 2, "endpoints a,e are junctions");
         g.contracted = Some(cg);
 
+        g.begin_feed("bus", Some("bus"));
+        g.record_feed_stops([("s1".to_string(), ids[2])].into());
+        g.drop_full_node_arrays();
         save_graph(&g, &FP0, path_s).unwrap();
         let mut loaded = load_graph(path_s, &FP0).unwrap();
+        assert_eq!(
+            loaded.nearest_node(50.000, 4.0001),
+            Some(ids[0]),
+            "a restored, node-less graph still answers nearest-node queries"
+        );
+        assert_eq!(loaded.gtfs_stop_node_by_id("bus:s1"), Some(ids[2]));
         let cg = loaded.contracted.take().expect("contracted survives the round trip");
         let entries = cg.walk_entries_arena(&loaded, 50.000, 4.0015, 100.0);
         assert!(!entries.is_empty(), "snap near an edge yields junction entries");
@@ -573,6 +790,16 @@ mod tests {
         assert!(err.to_string().contains("fingerprint mismatch"), "got: {err}");
     }
 
+    #[test]
+    fn graph_from_bytes_ignores_fingerprint_but_checks_header() {
+        let payload = to_allocvec(&Graph::new()).unwrap();
+        let bytes = with_header_fp(GRAPH_SCHEMA_VERSION, &FP1, &payload);
+        assert_eq!(graph_from_bytes(&bytes).unwrap().node_count(), 0);
+
+        let err = graph_from_bytes(&payload).unwrap_err();
+        assert!(err.contains("'<bytes>'") && err.contains("missing header"), "got: {err}");
+    }
+
     #[test]
     fn osm_graph_round_trip_drops_raptor() {
         let dir = std::env::temp_dir().join("maas_persist_osm_test");
@@ -632,6 +859,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn osm_address_labels_survive_osm_and_graph_round_trips() {
+        use crate::ingestion::osm::OsmAddressIndex;
+        use crate::structures::LatLng;
+
+        let dir = std::env::temp_dir().join("maas_persist_osm_address_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let loc = LatLng { latitude: 50.85, longitude: 4.355 };
+        let mut g = Graph::new();
+        g.set_address_index(OsmAddressIndex::from_entries(vec![(
+            "Rue Neuve".into(),
+            "12".into(),
+            loc,
+        )]));
+
+        let osm = dir.join("osm.bin");
+        save_osm_graph(&g, &FP0, osm.to_str().unwrap()).unwrap();
+        let restored = load_osm_graph(osm.to_str().unwrap(), &FP0).unwrap();
+        assert_eq!(restored.address_label(loc).as_deref(), Some("Rue Neuve 12"));
+
+        let graph = dir.join("graph.bin");
+        save_graph(&restored, &FP0, graph.to_str().unwrap()).unwrap();
+        let served = load_graph(graph.to_str().unwrap(), &FP0).unwrap();
+        assert_eq!(served.address_label(loc).as_deref(), Some("Rue Neuve 12"));
+    }
+
+    #[test]
+    fn stop_amenities_survive_osm_and_graph_round_trips() {
+        use crate::ingestion::osm::{AMENITY_BENCH, StopAmenityIndex};
+        use crate::structures::LatLng;
+
+        let dir = std::env::temp_dir().join("maas_persist_stop_amenity_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let loc = LatLng { latitude: 50.85, longitude: 4.355 };
+        let mut g = Graph::new();
+        assert_eq!(g.stop_amenities(loc), None);
+        g.set_amenity_index(StopAmenityIndex::from_points(vec![(loc, AMENITY_BENCH)]));
+
+        let osm = dir.join("osm.bin");
+        save_osm_graph(&g, &FP0, osm.to_str().unwrap()).unwrap();
+        let restored = load_osm_graph(osm.to_str().unwrap(), &FP0).unwrap();
+        let graph = dir.join("graph.bin");
+        save_graph(&restored, &FP0, graph.to_str().unwrap()).unwrap();
+        let served = load_graph(graph.to_str().unwrap(), &FP0).unwrap();
+        assert!(served.stop_amenities(loc).is_some_and(|a| a.bench && !a.shelter));
+    }
+
+    #[test]
+    fn parking_survives_osm_and_graph_round_trips() {
+        use crate::ingestion::osm::{ParkingFacility, ParkingIndex, ParkingKind};
+        use crate::structures::LatLng;
+
+        let dir = std::env::temp_dir().join("maas_persist_parking_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let loc = LatLng { latitude: 50.85, longitude: 4.355 };
+        let mut g = Graph::new();
+        assert_eq!(g.park_ride_allowance_secs(loc), 0, "no index: no allowance");
+        g.set_parking_index(ParkingIndex::from_facilities(vec![ParkingFacility {
+            loc,
+            kind: ParkingKind::Car,
+            name: None,
+            capacity: Some(80),
+            fee: None,
+            park_ride: true,
+        }]));
+
+        let osm = dir.join("osm.bin");
+        save_osm_graph(&g, &FP0, osm.to_str().unwrap()).unwrap();
+        let restored = load_osm_graph(osm.to_str().unwrap(), &FP0).unwrap();
+        let graph = dir.join("graph.bin");
+        save_graph(&restored, &FP0, graph.to_str().unwrap()).unwrap();
+        let served = load_graph(graph.to_str().unwrap(), &FP0).unwrap();
+        let near = served.parking_nearby(loc, 50.0, None);
+        assert_eq!(near.len(), 1);
+        assert_eq!(near[0].1.capacity, Some(80));
+        assert_eq!(served.park_ride_allowance_secs(loc), 0);
+        let far = LatLng { latitude: 50.86, longitude: 4.355 };
+        assert_eq!(served.park_ride_allowance_secs(far), crate::ingestion::osm::PARK_RIDE_UNKNOWN_CAPACITY_SECS);
+    }
+
     #[test]
     fn address_index_round_trip_rebuilds_search() {
         use crate::structures::{AddressIndexBuilder, Named};
@@ -3,10 +3,11 @@
 //! Builds the configured realtime feeds, then on an interval polls them all,
 //! folds their [`TripDelay`]s into a fresh [`RealtimeIndex`] (resolving GTFS
 //! string ids to internal indices via the live graph), and atomically swaps it
-//! into a shared `ArcSwap`. Per-feed failures are isolated; a cycle where *every*
-//! feed fails keeps the last good index rather than clearing delays.
+//! into a shared `ArcSwap`, announcing it on a [`RealtimeUpdates`] channel. Per-feed
+//! failures are isolated; a cycle where *every* feed fails keeps the last good index
+//! rather than clearing delays.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -14,13 +15,26 @@ use arc_swap::ArcSwap;
 
 use crate::ingestion::realtime::fetcher::{FetchError, Fetcher, RateLimitConfig};
 use crate::ingestion::realtime::gtfs_rt::GtfsRtFeed;
+use crate::ingestion::realtime::siri::SiriFeed;
 use crate::ingestion::realtime::stib::StibFeed;
-use crate::ingestion::realtime::{FeedUpdate, RealtimeFeed, VehicleObservation};
+use crate::ingestion::realtime::{FeedUpdate, RealtimeFeed, ServiceAlert, VehicleObservation};
 use crate::services::scheduler::SharedGraph;
-use crate::structures::{Config, Graph, RealtimeConfig, RealtimeFeedConfig, RealtimeIndex, VehiclePos};
+use crate::structures::{
+    Config, Graph, Occupancy, RealtimeConfig, RealtimeFeedConfig, RealtimeIndex, VehiclePos,
+};
 
 pub type SharedRealtime = Arc<ArcSwap<RealtimeIndex>>;
 
+/// Announces each index the poller publishes (its `generated_at`) to push consumers
+/// such as the `stopDepartures` subscription, which then read the live index.
+pub type RealtimeUpdates = tokio::sync::broadcast::Sender<i64>;
+
+/// A channel for [`RealtimeUpdates`]; a receiver lagging behind it only misses
+/// notifications for indices already superseded.
+pub fn realtime_updates() -> RealtimeUpdates {
+    tokio::sync::broadcast::channel(16).0
+}
+
 /// True when every URL/header value in `f` interpolates today. A deterministic
 /// interpolation failure (an unset `${VAR}`/missing secret) never fixes itself
 /// mid-run, so such a feed is disabled up front rather than re-attempted — and
@@ -40,6 +54,19 @@ fn feed_config_resolvable(f: &RealtimeFeedConfig) -> Result<(), String> {
             }
             (u, headers)
         }
+        RealtimeFeedConfig::Siri {
+            estimated_timetable_url,
+            situation_exchange_url,
+            headers,
+            ..
+        } => {
+            let u = [estimated_timetable_url, situation_exchange_url]
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            (u, headers)
+        }
     };
     for u in urls {
         crate::ingestion::secrets::interpolate(u)?;
@@ -67,23 +94,54 @@ pub fn build_feeds(cfg: &RealtimeConfig, graph: Arc<Graph>) -> Vec<Box<dyn Realt
                 );
                 return None;
             }
+            if f.requests_per_poll() == 0 {
+                tracing::warn!(
+                    feed = feed_name(f),
+                    "realtime feed disabled: no endpoint URL configured"
+                );
+                return None;
+            }
             Some(match f {
-                RealtimeFeedConfig::GtfsRt { name, url, headers } => {
-                    Box::new(GtfsRtFeed::new(name.clone(), url.clone(), headers.clone()))
-                        as Box<dyn RealtimeFeed>
-                }
+                RealtimeFeedConfig::GtfsRt {
+                    name,
+                    url,
+                    headers,
+                    namespace,
+                } => Box::new(
+                    GtfsRtFeed::new(name.clone(), url.clone(), headers.clone())
+                        .with_namespace(namespace.clone()),
+                ) as Box<dyn RealtimeFeed>,
                 RealtimeFeedConfig::Stib {
                     name,
                     waiting_time_url,
                     vehicle_position_url,
                     headers,
-                } => Box::new(StibFeed::new(
-                    name.clone(),
-                    waiting_time_url.clone(),
-                    vehicle_position_url.clone(),
-                    headers.clone(),
-                    graph.clone(),
-                )) as Box<dyn RealtimeFeed>,
+                    namespace,
+                } => Box::new(
+                    StibFeed::new(
+                        name.clone(),
+                        waiting_time_url.clone(),
+                        vehicle_position_url.clone(),
+                        headers.clone(),
+                        graph.clone(),
+                    )
+                    .with_namespace(namespace.clone()),
+                ) as Box<dyn RealtimeFeed>,
+                RealtimeFeedConfig::Siri {
+                    name,
+                    estimated_timetable_url,
+                    situation_exchange_url,
+                    headers,
+                    namespace,
+                } => Box::new(
+                    SiriFeed::new(
+                        name.clone(),
+                        estimated_timetable_url.clone(),
+                        situation_exchange_url.clone(),
+                        headers.clone(),
+                    )
+                    .with_namespace(namespace.clone()),
+                ) as Box<dyn RealtimeFeed>,
             })
         })
         .collect()
@@ -91,7 +149,9 @@ pub fn build_feeds(cfg: &RealtimeConfig, graph: Arc<Graph>) -> Vec<Box<dyn Realt
 
 fn feed_name(f: &RealtimeFeedConfig) -> &str {
     match f {
-        RealtimeFeedConfig::GtfsRt { name, .. } | RealtimeFeedConfig::Stib { name, .. } => name,
+        RealtimeFeedConfig::GtfsRt { name, .. }
+        | RealtimeFeedConfig::Stib { name, .. }
+        | RealtimeFeedConfig::Siri { name, .. } => name,
     }
 }
 
@@ -183,7 +243,6 @@ pub fn build_index(graph: &Graph, update: &FeedUpdate, generated_at: i64) -> Rea
     };
 
     use crate::ingestion::gtfs::TripId;
-    use std::collections::HashSet;
 
     // Skips resolved to scheduled pattern stops (bug 6/8): guard the router AND
     // break the forward-fill chain below (bug 7).
@@ -269,6 +328,28 @@ pub fn build_index(graph: &Graph, update: &FeedUpdate, generated_at: i64) -> Rea
         platform_swaps.insert((trip, parent.to_string()), actual_compact as u32);
     }
 
+    // Stop-level occupancy lands on the scheduled stop like delays; vehicle-wide
+    // readings are the per-trip fallback. No-data statuses are dropped.
+    let mut stop_occupancy = HashMap::new();
+    let mut trip_occupancy = HashMap::new();
+    for o in &update.occupancy {
+        let Some(level) = Occupancy::from_gtfs_rt(o.status) else {
+            continue;
+        };
+        match &o.stop_id {
+            Some(stop_id) => {
+                if let Some(key) = resolve(&o.trip_id, stop_id) {
+                    stop_occupancy.insert(key, level);
+                }
+            }
+            None => {
+                if let Some(trip) = graph.trip_index_of(&o.trip_id) {
+                    trip_occupancy.insert(trip, level);
+                }
+            }
+        }
+    }
+
     RealtimeIndex::with_all(
         generated_at,
         entries,
@@ -278,6 +359,28 @@ pub fn build_index(graph: &Graph, update: &FeedUpdate, generated_at: i64) -> Rea
         platform_swaps,
     )
     .with_skipped(skipped)
+    .with_occupancy(stop_occupancy, trip_occupancy)
+    .with_inaccessible_stops(inaccessible_stops(graph, &update.alerts, generated_at))
+}
+
+/// Compact stops named by `ACCESSIBILITY_ISSUE` alerts active at `now`; a station id
+/// covers all of its platforms.
+fn inaccessible_stops(graph: &Graph, alerts: &[ServiceAlert], now: i64) -> HashSet<u32> {
+    let mut stops = HashSet::new();
+    for alert in alerts {
+        if alert.effect != Some(ServiceAlert::ACCESSIBILITY_ISSUE) || !alert.is_active(now.max(0) as u64) {
+            continue;
+        }
+        for stop_id in alert.informed_entity.iter().filter_map(|e| e.stop_id.as_deref()) {
+            match graph.stop_index_of(stop_id) {
+                Some(c) => {
+                    stops.insert(c as u32);
+                }
+                None => stops.extend(graph.station_platforms(stop_id).into_iter().flatten().map(|c| c as u32)),
+            }
+        }
+    }
+    stops
 }
 
 fn fold_positions(
@@ -345,6 +448,7 @@ fn poll_cycle(
                 all.alerts.append(&mut update.alerts);
                 all.actual_stops.append(&mut update.actual_stops);
                 all.skipped_stops.append(&mut update.skipped_stops);
+                all.occupancy.append(&mut update.occupancy);
             }
             Err(FetchError::Throttled) => stats.throttled += 1,
             Err(FetchError::Failed(e)) => {
@@ -376,7 +480,12 @@ fn merge_sticky(
 }
 
 /// Spawn the realtime poller if `realtime` is enabled with at least one feed.
-pub fn spawn(graph: SharedGraph, realtime: SharedRealtime, config: Arc<Config>) {
+pub fn spawn(
+    graph: SharedGraph,
+    realtime: SharedRealtime,
+    updates: RealtimeUpdates,
+    config: Arc<Config>,
+) {
     let cfg = match &config.realtime {
         Some(c) if c.enabled => c.clone(),
         _ => return,
@@ -454,7 +563,10 @@ pub fn spawn(graph: SharedGraph, realtime: SharedRealtime, config: Arc<Config>)
                     let positions = index.positions_len();
                     let alerts = index.alerts_len();
                     let sticky = index.sticky_len();
+                    let generated_at = index.generated_at;
                     realtime.store(Arc::new(index));
+                    // Fails only without subscribers.
+                    let _ = updates.send(generated_at);
                     tracing::info!(
                         delays,
                         canceled,
@@ -488,10 +600,31 @@ mod tests {
             name: "bus".into(),
             url: "https://example.com/rt.pb".into(),
             headers: HashMap::new(),
+            namespace: None,
         };
         assert!(feed_config_resolvable(&f).is_ok());
     }
 
+    #[test]
+    fn siri_feed_without_endpoints_is_not_built() {
+        let siri = |et: Option<&str>| RealtimeFeedConfig::Siri {
+            name: "siri".into(),
+            estimated_timetable_url: et.map(str::to_string),
+            situation_exchange_url: None,
+            headers: HashMap::new(),
+            namespace: None,
+        };
+        let cfg = |feeds| RealtimeConfig {
+            feeds,
+            ..serde_yaml_ng::from_str("enabled: true").unwrap()
+        };
+        let graph = Arc::new(Graph::new());
+        assert!(build_feeds(&cfg(vec![siri(None)]), graph.clone()).is_empty());
+        let built = build_feeds(&cfg(vec![siri(Some("https://example.com/et"))]), graph);
+        assert_eq!(built.len(), 1);
+        assert_eq!(built[0].name(), "siri");
+    }
+
     #[test]
     fn feed_with_unset_key_is_disabled() {
         let mut headers = HashMap::new();
@@ -503,6 +636,7 @@ mod tests {
             name: "bus".into(),
             url: "https://example.com/rt.pb".into(),
             headers,
+            namespace: None,
         };
         assert!(feed_config_resolvable(&f).is_err(), "unset key ⇒ feed disabled");
     }
@@ -652,6 +786,7 @@ mod tests {
             alerts: Vec::new(),
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 42);
@@ -702,6 +837,7 @@ mod tests {
             alerts: Vec::new(),
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 100);
@@ -758,6 +894,7 @@ mod tests {
             alerts: Vec::new(),
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 200);
@@ -799,6 +936,7 @@ mod tests {
             alerts: Vec::new(),
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 0);
@@ -817,6 +955,7 @@ mod tests {
             alerts: Vec::new(),
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 0);
@@ -848,6 +987,7 @@ mod tests {
             alerts: vec![alert.clone()],
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 500);
@@ -857,6 +997,36 @@ mod tests {
         assert_eq!(found[0].header.as_deref(), Some("Strike"));
     }
 
+    #[test]
+    fn accessibility_alerts_mark_their_stops_while_active() {
+        use crate::ingestion::realtime::{AlertEntitySelector, ServiceAlert};
+
+        let g = base_graph();
+        let outage = |stop: &str, effect: i32| ServiceAlert {
+            header: Some("Elevator out of service".to_string()),
+            description: None,
+            cause: None,
+            effect: Some(effect),
+            active_period: vec![(Some(1_000), Some(2_000))],
+            informed_entity: vec![AlertEntitySelector {
+                trip_id: None,
+                route_id: None,
+                stop_id: Some(stop.to_string()),
+            }],
+        };
+        let update = FeedUpdate {
+            alerts: vec![outage("s5", ServiceAlert::ACCESSIBILITY_ISSUE), outage("s9", 1)],
+            ..Default::default()
+        };
+        let (s5, s9) = (1, 2);
+
+        let idx = build_index(&g, &update, 1_500);
+        assert!(idx.stop_inaccessible(s5));
+        assert!(!idx.stop_inaccessible(s9), "only ACCESSIBILITY_ISSUE alerts count");
+        assert!(!idx.is_empty());
+        assert!(!build_index(&g, &update, 2_500).stop_inaccessible(s5), "expired");
+    }
+
     #[test]
     fn build_index_expired_alert_still_stored_filtering_done_by_accessor() {
         use crate::ingestion::realtime::{AlertEntitySelector, ServiceAlert};
@@ -881,6 +1051,7 @@ mod tests {
             alerts: vec![expired_alert],
             actual_stops: Vec::new(),
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 1_000);
@@ -1012,6 +1183,30 @@ mod tests {
         assert!(idx.is_skipped(TripId(0), 4), "the skip is still recorded for routing");
     }
 
+    #[test]
+    fn build_index_folds_stop_and_vehicle_occupancy() {
+        use crate::ingestion::realtime::TripOccupancy;
+        let g = six_stop_graph();
+        let occ = |stop: Option<&str>, status: i32| TripOccupancy {
+            trip_id: "t0".into(),
+            stop_id: stop.map(Into::into),
+            status,
+        };
+        let update = FeedUpdate {
+            // FULL leaving stop 3, STANDING_ROOM_ONLY vehicle-wide, NO_DATA at stop 5.
+            occupancy: vec![occ(Some("stop_3"), 5), occ(None, 3), occ(Some("stop_5"), 7)],
+            ..Default::default()
+        };
+        let idx = build_index(&g, &update, 0);
+        assert_eq!(idx.occupancy(TripId(0), 3), Some(Occupancy::Full));
+        assert_eq!(
+            idx.occupancy(TripId(0), 5),
+            Some(Occupancy::StandingRoomOnly),
+            "no-data stop falls back to the vehicle-wide reading"
+        );
+        assert!(!idx.is_empty(), "occupancy alone makes the index consulted");
+    }
+
     #[test]
     fn build_index_keys_platform_swapped_delay_on_scheduled_stop() {
         let g = pattern_graph();
@@ -1113,6 +1308,7 @@ mod tests {
                 ("ghost".into(), "s5".into()), // unknown trip → dropped
                 ("t0".into(), "nope".into()),  // unknown stop → dropped
             ],
+            occupancy: Vec::new(),
         };
         let idx = build_index(&g, &update, 7);
         assert_eq!(idx.skipped_len(), 1, "only the resolvable skip is kept");
@@ -1150,6 +1346,7 @@ mod tests {
                 },
             ],
             skipped_stops: Vec::new(),
+            occupancy: Vec::new(),
         };
 
         let idx = build_index(&g, &update, 0);
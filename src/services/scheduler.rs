@@ -5,28 +5,36 @@ use std::sync::Arc;
 use arc_swap::ArcSwap;
 use chrono::{DateTime, Local};
 use cron::Schedule;
+use tokio::{sync::watch, task::JoinHandle};
 
 use crate::ingestion::cache::{
     SourceLocation, gtfs_content_hash, load_feed_hashes, load_last_checked, resolve_source,
     save_feed_hashes, save_last_checked,
 };
-use crate::services::build::{apply_routing_defaults, build_gtfs_phase, finalize_contraction};
+use crate::services::build::{Refresh, apply_routing_defaults, build_gtfs_phase, finalize_contraction};
 use crate::services::fingerprint::{graph_fingerprint, osm_fingerprint};
-use crate::services::persistence::{load_osm_graph, save_graph_with_rollback};
+use crate::services::persistence::{load_osm_graph, save_graph_with_rollback, snapshot_saved_graph};
 use crate::structures::{Config, Graph, Ingestor};
 
 pub type SharedGraph = Arc<ArcSwap<Graph>>;
 
-pub fn spawn(graph: SharedGraph, config: Arc<Config>) {
+/// Starts the auto-update loop; `None` when auto-update is off. Once `shutdown` flips
+/// to `true` no new cycle starts, and the returned task ends after any cycle (and its
+/// graph save) in progress completes.
+pub fn spawn(
+    graph: SharedGraph,
+    config: Arc<Config>,
+    shutdown: watch::Receiver<bool>,
+) -> Option<JoinHandle<()>> {
     let au = match &config.auto_update {
         Some(a) if a.enabled => a.clone(),
-        _ => return,
+        _ => return None,
     };
     let schedule = match parse_cron(&au.schedule) {
         Ok(s) => s,
         Err(e) => {
             tracing::error!("auto_update disabled: {e}");
-            return;
+            return None;
         }
     };
     let cache_dir = config.cache_dir();
@@ -35,12 +43,18 @@ pub fn spawn(graph: SharedGraph, config: Arc<Config>) {
         au.schedule,
         cache_dir
     );
-    tokio::spawn(async move {
-        run_loop(graph, config, schedule, cache_dir).await;
-    });
+    Some(tokio::spawn(async move {
+        run_loop(graph, config, schedule, cache_dir, shutdown).await;
+    }))
 }
 
-async fn run_loop(graph: SharedGraph, config: Arc<Config>, schedule: Schedule, cache_dir: String) {
+async fn run_loop(
+    graph: SharedGraph,
+    config: Arc<Config>,
+    schedule: Schedule,
+    cache_dir: String,
+    mut shutdown: watch::Receiver<bool>,
+) {
     // Startup catch-up: refresh once if a scheduled tick elapsed while down.
     let due = match load_last_checked(&cache_dir) {
         Some(last) => feeds_stale(&schedule, last, Local::now()),
@@ -60,7 +74,10 @@ async fn run_loop(graph: SharedGraph, config: Arc<Config>, schedule: Schedule, c
             .to_std()
             .unwrap_or(std::time::Duration::from_secs(1));
         tracing::info!("auto_update: next run at {next}");
-        tokio::time::sleep(wait).await;
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = shutdown.wait_for(|&stop| stop) => return,
+        }
 
         run_once(&graph, &config, &cache_dir).await;
     }
@@ -114,7 +131,7 @@ fn run_update_cycle(graph: &SharedGraph, config: &Config, cache_dir: &str) -> Re
         osm,
         &config.build,
         cache_dir,
-        false,
+        Refresh::Cached,
         config.default_routing.station_merge_radius_m,
         &config.default_routing,
     )
@@ -128,6 +145,7 @@ fn run_update_cycle(graph: &SharedGraph, config: &Config, cache_dir: &str) -> Re
     // reuses it instead of rebuilding.
     let graph_fp = graph_fingerprint(config, cache_dir);
     save_graph_with_rollback(&new_graph, &graph_fp, &config.build.output)?;
+    snapshot_saved_graph(&config.build.output, &config.build.snapshots);
     save_feed_hashes(cache_dir, &new_hashes)?;
     graph.store(Arc::new(new_graph));
     Ok(true)
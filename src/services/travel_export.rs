@@ -0,0 +1,312 @@
+//! `--export-travel-map`: one-to-all travel times from an origin, rasterised on the
+//! travel-map lattice and written as a GeoTIFF (EPSG:4326, `uint16` seconds) or as a
+//! compact raw raster, for accessibility heatmaps in GIS tools. Reuses
+//! [`Graph::travel_time_map`]; unreachable pixels are [`NO_DATA`].
+//!
+//! Raw layout (little-endian): `b"MTTR"`, `u8` version (1), `f64` west, `f64` north
+//! (centre of the north-west pixel), `f64` lng step, `f64` lat step (degrees),
+//! `u32` width, `u32` height, then `width * height` `u16` seconds, row-major from
+//! the north-west pixel.
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
+
+use crate::{
+    ingestion::gtfs::date_to_days,
+    structures::{
+        ActiveModes, BikeCost, Graph, LatLng, Mode, RealtimeIndex, ReliabilityBuckets, TravelCell,
+    },
+};
+
+/// Pixel value for points not reachable within the budget.
+pub const NO_DATA: u16 = u16::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RasterFormat {
+    GeoTiff,
+    Raw,
+}
+
+impl RasterFormat {
+    /// `.tif` / `.tiff` → GeoTIFF, anything else → raw.
+    pub fn from_path(path: &str) -> Self {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".tif") || lower.ends_with(".tiff") {
+            RasterFormat::GeoTiff
+        } else {
+            RasterFormat::Raw
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TravelExportOptions {
+    pub output: String,
+    pub format: RasterFormat,
+    pub origin: LatLng,
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    pub max_secs: u32,
+    /// `None` uses `travel_map_grid_step_m`.
+    pub grid_step_m: Option<f64>,
+    pub modes: Option<Vec<Mode>>,
+}
+
+/// North-up grid of travel times; `seconds` is row-major from the north-west pixel,
+/// whose centre is at (`north`, `west`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TravelRaster {
+    pub west: f64,
+    pub north: f64,
+    pub dlng: f64,
+    pub dlat: f64,
+    pub width: u32,
+    pub height: u32,
+    pub seconds: Vec<u16>,
+}
+
+/// Smallest gap between distinct sorted values: the lattice step along one axis.
+fn lattice_step(mut values: Vec<f64>) -> Option<f64> {
+    values.sort_by(f64::total_cmp);
+    values
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|&d| d > 1e-9)
+        .min_by(f64::total_cmp)
+}
+
+impl TravelRaster {
+    /// Rasterises cells sampled on one lattice (as returned by the travel-map
+    /// functions). The step is recovered from the cells, falling back to
+    /// `fallback_step_m` along an axis with a single row/column. `None` if empty.
+    pub fn from_cells(cells: &[TravelCell], fallback_step_m: f64) -> Option<Self> {
+        let first = cells.first()?;
+        let cos = first.loc.latitude.to_radians().cos().max(0.2);
+        let dlat = lattice_step(cells.iter().map(|c| c.loc.latitude).collect())
+            .unwrap_or(fallback_step_m / 111_320.0);
+        let dlng = lattice_step(cells.iter().map(|c| c.loc.longitude).collect())
+            .unwrap_or(fallback_step_m / (111_320.0 * cos));
+        let north = cells.iter().map(|c| c.loc.latitude).fold(f64::MIN, f64::max);
+        let south = cells.iter().map(|c| c.loc.latitude).fold(f64::MAX, f64::min);
+        let west = cells.iter().map(|c| c.loc.longitude).fold(f64::MAX, f64::min);
+        let east = cells.iter().map(|c| c.loc.longitude).fold(f64::MIN, f64::max);
+        let width = ((east - west) / dlng).round() as u32 + 1;
+        let height = ((north - south) / dlat).round() as u32 + 1;
+        let mut seconds = vec![NO_DATA; width as usize * height as usize];
+        for c in cells {
+            let col = ((c.loc.longitude - west) / dlng).round() as usize;
+            let row = ((north - c.loc.latitude) / dlat).round() as usize;
+            let px = &mut seconds[row * width as usize + col];
+            *px = (*px).min(c.seconds.min(NO_DATA as u32 - 1) as u16);
+        }
+        Some(TravelRaster { west, north, dlng, dlat, width, height, seconds })
+    }
+
+    pub fn encode(&self, format: RasterFormat) -> Vec<u8> {
+        match format {
+            RasterFormat::GeoTiff => self.to_geotiff(),
+            RasterFormat::Raw => self.to_raw(),
+        }
+    }
+
+    pub fn to_raw(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(45 + 2 * self.seconds.len());
+        out.extend_from_slice(b"MTTR");
+        out.push(1);
+        for v in [self.west, self.north, self.dlng, self.dlat] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(&self.width.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+        for s in &self.seconds {
+            out.extend_from_slice(&s.to_le_bytes());
+        }
+        out
+    }
+
+    /// Baseline little-endian TIFF, one uncompressed strip, georeferenced with a
+    /// pixel-is-area tie point on the north-west corner and a `GDAL_NODATA` tag.
+    pub fn to_geotiff(&self) -> Vec<u8> {
+        const SHORT: u16 = 3;
+        const LONG: u16 = 4;
+        const DOUBLE: u16 = 12;
+        const ASCII: u16 = 2;
+
+        let pixels: Vec<u8> = self.seconds.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let scale: Vec<u8> = [self.dlng, self.dlat, 0.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let tiepoint: Vec<u8> = [
+            0.0,
+            0.0,
+            0.0,
+            self.west - self.dlng / 2.0,
+            self.north + self.dlat / 2.0,
+            0.0,
+        ]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+        // Version 1.1.0, three keys: model type geographic, raster pixel-is-area, WGS 84.
+        let geokeys: Vec<u8> = [1u16, 1, 0, 3, 1024, 0, 1, 2, 1025, 0, 1, 1, 2048, 0, 1, 4326]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let nodata = format!("{NO_DATA}\0").into_bytes();
+
+        // (tag, type, count, inline value or out-of-line bytes), sorted by tag.
+        enum Value {
+            Inline(u32),
+            Data(Vec<u8>),
+        }
+        let strip_len = pixels.len() as u32;
+        let mut entries: Vec<(u16, u16, u32, Value)> = vec![
+            (256, LONG, 1, Value::Inline(self.width)),
+            (257, LONG, 1, Value::Inline(self.height)),
+            (258, SHORT, 1, Value::Inline(16)),
+            (259, SHORT, 1, Value::Inline(1)),
+            (262, SHORT, 1, Value::Inline(1)),
+            (273, LONG, 1, Value::Inline(0)), // patched below
+            (277, SHORT, 1, Value::Inline(1)),
+            (278, LONG, 1, Value::Inline(self.height)),
+            (279, LONG, 1, Value::Inline(strip_len)),
+            (284, SHORT, 1, Value::Inline(1)),
+            (339, SHORT, 1, Value::Inline(1)),
+            (33550, DOUBLE, 3, Value::Data(scale)),
+            (33922, DOUBLE, 6, Value::Data(tiepoint)),
+            (34735, SHORT, 16, Value::Data(geokeys)),
+            (42113, ASCII, nodata.len() as u32, Value::Data(nodata)),
+        ];
+
+        let ifd_len = 2 + entries.len() * 12 + 4;
+        let mut data_offset = (8 + ifd_len) as u32;
+        let mut ifd = Vec::with_capacity(ifd_len);
+        let mut extra = Vec::new();
+        ifd.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        let pixel_offset = data_offset
+            + entries
+                .iter()
+                .map(|e| match &e.3 {
+                    Value::Data(d) => d.len().next_multiple_of(2) as u32,
+                    Value::Inline(_) => 0,
+                })
+                .sum::<u32>();
+        for (tag, ty, count, value) in &mut entries {
+            if *tag == 273 {
+                *value = Value::Inline(pixel_offset);
+            }
+            ifd.extend_from_slice(&tag.to_le_bytes());
+            ifd.extend_from_slice(&ty.to_le_bytes());
+            ifd.extend_from_slice(&count.to_le_bytes());
+            match value {
+                Value::Inline(v) if *ty == SHORT => {
+                    ifd.extend_from_slice(&(*v as u16).to_le_bytes());
+                    ifd.extend_from_slice(&[0, 0]);
+                }
+                Value::Inline(v) => ifd.extend_from_slice(&v.to_le_bytes()),
+                Value::Data(d) => {
+                    ifd.extend_from_slice(&data_offset.to_le_bytes());
+                    extra.extend_from_slice(d);
+                    if d.len() % 2 == 1 {
+                        extra.push(0);
+                    }
+                    data_offset += d.len().next_multiple_of(2) as u32;
+                }
+            }
+        }
+        ifd.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut out = Vec::with_capacity(pixel_offset as usize + pixels.len());
+        out.extend_from_slice(b"II");
+        out.extend_from_slice(&42u16.to_le_bytes());
+        out.extend_from_slice(&8u32.to_le_bytes());
+        out.extend_from_slice(&ifd);
+        out.extend_from_slice(&extra);
+        out.extend_from_slice(&pixels);
+        out
+    }
+}
+
+/// Computes the map (static timetable, no realtime) and writes it to `opts.output`;
+/// returns a one-line summary.
+pub fn run(g: &Graph, opts: &TravelExportOptions) -> Result<String, String> {
+    let am = match &opts.modes {
+        None => ActiveModes::default(),
+        Some(m) => ActiveModes::new(m),
+    };
+    let step = opts.grid_step_m.unwrap_or(g.raptor.travel_map_grid_step_m);
+    let cells = g.travel_time_map(
+        opts.origin,
+        opts.time.num_seconds_from_midnight(),
+        date_to_days(opts.date),
+        1u8 << opts.date.weekday().num_days_from_monday(),
+        opts.max_secs,
+        step,
+        &am,
+        &ReliabilityBuckets::new(&g.raptor.reliability_bucket_edges),
+        g.raptor.arrival_slack_secs,
+        g.raptor.unrestricted_transfers,
+        g.raptor.use_cch_access,
+        &RealtimeIndex::new(),
+        &BikeCost::new(g.raptor.bike_profile),
+    );
+    let raster = TravelRaster::from_cells(&cells, step)
+        .ok_or_else(|| "origin reaches no point within the budget".to_string())?;
+    std::fs::write(&opts.output, raster.encode(opts.format))
+        .map_err(|e| format!("failed to write '{}': {e}", opts.output))?;
+    let reached = raster.seconds.iter().filter(|&&s| s != NO_DATA).count();
+    Ok(format!(
+        "wrote {} ({}x{} pixels, {reached} reached)",
+        opts.output, raster.width, raster.height
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(lat: f64, lng: f64, seconds: u32) -> TravelCell {
+        TravelCell { loc: LatLng { latitude: lat, longitude: lng }, seconds }
+    }
+
+    #[test]
+    fn cells_land_on_their_lattice_pixel() {
+        let cells = [
+            cell(50.002, 4.0, 10),
+            cell(50.0, 4.0, 20),
+            cell(50.0, 4.003, 30),
+            cell(50.001, 4.003, 70_000),
+        ];
+        let r = TravelRaster::from_cells(&cells, 100.0).unwrap();
+        assert_eq!((r.width, r.height), (2, 3));
+        assert_eq!((r.north, r.west), (50.002, 4.0));
+        assert_eq!(
+            r.seconds,
+            vec![10, NO_DATA, NO_DATA, NO_DATA - 1, 20, 30],
+            "row-major from the north-west, saturated below NO_DATA"
+        );
+        assert!(TravelRaster::from_cells(&[], 100.0).is_none());
+    }
+
+    #[test]
+    fn encodings_carry_header_and_pixels() {
+        let r = TravelRaster::from_cells(&[cell(50.0, 4.0, 5), cell(50.0, 4.001, 6)], 100.0)
+            .unwrap();
+        let raw = r.to_raw();
+        assert_eq!(&raw[..5], b"MTTR\x01");
+        assert_eq!(raw.len(), 45 + 4);
+        assert_eq!(&raw[45..], &[5, 0, 6, 0]);
+
+        let tif = r.to_geotiff();
+        assert_eq!(&tif[..4], b"II\x2a\x00");
+        let entries = u16::from_le_bytes([tif[8], tif[9]]) as usize;
+        let entry = |i: usize| &tif[10 + 12 * i..22 + 12 * i];
+        let tags: Vec<u16> = (0..entries).map(|i| u16::from_le_bytes([entry(i)[0], entry(i)[1]])).collect();
+        assert!(tags.windows(2).all(|w| w[0] < w[1]), "tags must be sorted: {tags:?}");
+        let strip = entry(tags.iter().position(|&t| t == 273).unwrap());
+        let offset = u32::from_le_bytes([strip[8], strip[9], strip[10], strip[11]]) as usize;
+        assert_eq!(&tif[offset..], &[5, 0, 6, 0]);
+        assert_eq!(RasterFormat::from_path("out/Map.TIF"), RasterFormat::GeoTiff);
+        assert_eq!(RasterFormat::from_path("map.bin"), RasterFormat::Raw);
+    }
+}
@@ -1,4 +1,5 @@
-//! Bike-routing attributes classified once at OSM ingest, stored per directed edge.
+//! Bike-routing and walking-comfort attributes classified once at OSM ingest, stored
+//! per directed edge.
 
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +30,37 @@ pub enum HighwayClass {
     Other,
 }
 
+impl HighwayClass {
+    /// The class of an OSM `highway=*` value; `None` for values without one.
+    pub fn from_osm_tag(v: &str) -> Option<Self> {
+        Some(match v {
+            "motorway" => Self::Motorway,
+            "motorway_link" => Self::MotorwayLink,
+            "trunk" => Self::Trunk,
+            "trunk_link" => Self::TrunkLink,
+            "primary" => Self::Primary,
+            "primary_link" => Self::PrimaryLink,
+            "secondary" => Self::Secondary,
+            "secondary_link" => Self::SecondaryLink,
+            "tertiary" => Self::Tertiary,
+            "tertiary_link" => Self::TertiaryLink,
+            "unclassified" => Self::Unclassified,
+            "residential" => Self::Residential,
+            "living_street" => Self::LivingStreet,
+            "service" => Self::Service,
+            "cycleway" => Self::Cycleway,
+            "footway" => Self::Footway,
+            "path" => Self::Path,
+            "track" => Self::Track,
+            "bridleway" => Self::Bridleway,
+            "pedestrian" => Self::Pedestrian,
+            "steps" => Self::Steps,
+            "road" => Self::Road,
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Surface {
     Paved,
@@ -48,6 +80,12 @@ pub struct BikeAttrs {
     pub footaccess: bool,
     /// True when this directed edge goes against a bike-relevant oneway.
     pub wrong_way: bool,
+    /// `sidewalk=no|none|separate`: walking here means walking on the carriageway.
+    pub no_sidewalk: bool,
+    /// `lit=yes` (or a lighting schedule).
+    pub lit: bool,
+    /// `highway=steps` + `conveying=*`: an escalator, not stairs to climb.
+    pub escalator: bool,
 }
 
 impl BikeAttrs {
@@ -58,6 +96,11 @@ impl BikeAttrs {
         (ispaved || self.isbike || matches!(self.highway, HighwayClass::Footway)) && !isunpaved
     }
 
+    /// Stairs to climb on foot; escalators don't count.
+    pub fn is_stairs(&self) -> bool {
+        matches!(self.highway, HighwayClass::Steps) && !self.escalator
+    }
+
     pub fn is_residential_or_living(&self) -> bool {
         matches!(
             self.highway,
@@ -76,6 +119,9 @@ impl BikeAttrs {
             bikeaccess: true,
             footaccess: true,
             wrong_way: false,
+            no_sidewalk: false,
+            lit: false,
+            escalator: false,
         }
     }
 }
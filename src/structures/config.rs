@@ -9,6 +9,7 @@ use crate::ingestion::osm::DemProjection;
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    #[serde(default)]
     pub build: BuildConfig,
     #[serde(default)]
     pub default_routing: RoutingDefaultConfig,
@@ -18,9 +19,41 @@ pub struct Config {
     pub auto_update: Option<AutoUpdateConfig>,
     #[serde(default)]
     pub realtime: Option<RealtimeConfig>,
+    /// Regional graph shards for [`GraphSet::load`](crate::services::graph_set) and
+    /// `routing::federation`; `None` = the single `build.output`. The server does not
+    /// serve shards and refuses to start with this set (see [`Config::check_servable`]).
+    #[serde(default)]
+    pub federation: Option<FederationConfig>,
     /// trace | debug | info | warn | error
     #[serde(default = "default_log_level")]
     pub log_level: String,
+    /// Non-fatal findings from [`Config::load`] (unknown keys, empty inputs), logged once
+    /// logging is up.
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FederationConfig {
+    pub shards: Vec<ShardConfig>,
+    /// Station ids (`station_id_to_index` keys) present in two neighbouring shards, where
+    /// a cross-shard plan hands over from one shard's itinerary to the other's.
+    #[serde(default)]
+    pub boundary_stations: Vec<String>,
+    /// Minimum wait at a boundary station between the two halves of a stitched plan.
+    #[serde(default = "default_federation_transfer_secs")]
+    pub transfer_secs: u32,
+}
+
+fn default_federation_transfer_secs() -> u32 {
+    300
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ShardConfig {
+    pub name: String,
+    /// Path to the shard's `graph.bin`, built from its own config.
+    pub graph: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -117,6 +150,9 @@ pub enum RealtimeFeedConfig {
         url: String,
         #[serde(default)]
         headers: HashMap<String, String>,
+        /// Must match the `namespace` of the GTFS input this feed updates.
+        #[serde(default)]
+        namespace: Option<String>,
     },
     #[serde(rename = "stib")]
     Stib {
@@ -126,6 +162,22 @@ pub enum RealtimeFeedConfig {
         vehicle_position_url: Option<String>,
         #[serde(default)]
         headers: HashMap<String, String>,
+        #[serde(default)]
+        namespace: Option<String>,
+    },
+    /// SIRI Estimated Timetable and/or Situation Exchange endpoints (XML); at least
+    /// one URL must be set.
+    #[serde(rename = "siri")]
+    Siri {
+        name: String,
+        #[serde(default)]
+        estimated_timetable_url: Option<String>,
+        #[serde(default)]
+        situation_exchange_url: Option<String>,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        namespace: Option<String>,
     },
 }
 
@@ -144,6 +196,11 @@ impl RealtimeFeedConfig {
                     1
                 }
             }
+            RealtimeFeedConfig::Siri {
+                estimated_timetable_url,
+                situation_exchange_url,
+                ..
+            } => estimated_timetable_url.is_some() as u32 + situation_exchange_url.is_some() as u32,
         }
     }
 }
@@ -194,8 +251,29 @@ pub struct ServerConfig {
     pub graphql_max_complexity: usize,
     #[serde(default = "default_graphiql_enabled")]
     pub graphiql_enabled: bool,
+    /// Schema introspection (`__schema` / `__type`) on `/graphql`. GraphiQL needs it.
+    #[serde(default)]
+    pub introspection_enabled: bool,
     #[serde(default)]
     pub tiles: TilesConfig,
+    #[serde(default)]
+    pub plan_cache: PlanCacheConfig,
+    /// Shared secret for admin mutations (`flushPlanCache`). Unset disables them.
+    /// `MAAS_ADMIN_TOKEN` overrides it at load time.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+    /// On SIGTERM/SIGINT: how long in-flight requests and a running graph update
+    /// may take to finish before the process exits.
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+    /// Routing searches run at once (GraphQL and gRPC together); further ones queue.
+    /// Unset: one per available core.
+    #[serde(default)]
+    pub max_concurrent_searches: Option<usize>,
 }
 
 impl Default for ServerConfig {
@@ -206,11 +284,89 @@ impl Default for ServerConfig {
             graphql_max_depth: default_graphql_max_depth(),
             graphql_max_complexity: default_graphql_max_complexity(),
             graphiql_enabled: default_graphiql_enabled(),
+            introspection_enabled: false,
             tiles: TilesConfig::default(),
+            plan_cache: PlanCacheConfig::default(),
+            admin_token: None,
+            grpc: GrpcConfig::default(),
+            shutdown_grace_secs: default_shutdown_grace_secs(),
+            analytics: AnalyticsConfig::default(),
+            max_concurrent_searches: None,
         }
     }
 }
 
+fn default_shutdown_grace_secs() -> u64 {
+    30
+}
+
+/// Anonymised plan-request log (`web/analytics.rs`): JSON lines at `path`, rotated
+/// past `max_bytes` keeping `keep` old files, OD snapped to `cell_m` cells. Off by default.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnalyticsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_analytics_path")]
+    pub path: String,
+    #[serde(default = "default_analytics_max_bytes")]
+    pub max_bytes: u64,
+    #[serde(default = "default_analytics_keep")]
+    pub keep: usize,
+    #[serde(default = "default_analytics_cell_m")]
+    pub cell_m: f64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        AnalyticsConfig {
+            enabled: false,
+            path: default_analytics_path(),
+            max_bytes: default_analytics_max_bytes(),
+            keep: default_analytics_keep(),
+            cell_m: default_analytics_cell_m(),
+        }
+    }
+}
+
+fn default_analytics_path() -> String {
+    "cache/analytics.jsonl".to_string()
+}
+
+fn default_analytics_max_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_analytics_keep() -> usize {
+    4
+}
+
+fn default_analytics_cell_m() -> f64 {
+    500.0
+}
+
+/// gRPC `PlanService` (`proto/maas.proto`) on `server.host:port`, next to GraphQL.
+/// Off by default; needs the `grpc` cargo feature.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrpcConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_grpc_port")]
+    pub port: u16,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        GrpcConfig {
+            enabled: false,
+            port: default_grpc_port(),
+        }
+    }
+}
+
+fn default_grpc_port() -> u16 {
+    50051
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct TilesConfig {
     #[serde(default = "default_tile_url")]
@@ -228,6 +384,40 @@ impl Default for TilesConfig {
     }
 }
 
+/// LRU of `raptor` results keyed by snapped OD nodes, departure bucket and parameters.
+/// `capacity: 0` disables it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlanCacheConfig {
+    #[serde(default = "default_plan_cache_capacity")]
+    pub capacity: usize,
+    #[serde(default = "default_plan_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    #[serde(default = "default_plan_cache_bucket_secs")]
+    pub bucket_secs: u32,
+}
+
+impl Default for PlanCacheConfig {
+    fn default() -> Self {
+        PlanCacheConfig {
+            capacity: default_plan_cache_capacity(),
+            ttl_secs: default_plan_cache_ttl_secs(),
+            bucket_secs: default_plan_cache_bucket_secs(),
+        }
+    }
+}
+
+fn default_plan_cache_capacity() -> usize {
+    1024
+}
+
+fn default_plan_cache_ttl_secs() -> u64 {
+    30
+}
+
+fn default_plan_cache_bucket_secs() -> u32 {
+    60
+}
+
 fn default_host() -> String {
     "127.0.0.1".to_string()
 }
@@ -256,12 +446,26 @@ fn default_tile_attribution() -> String {
     "© OpenStreetMap contributors".to_string()
 }
 
+/// 1-based line/column of the `nth` (0-based) occurrence of `needle` in `content` (the
+/// raw YAML), so a semantic error can point at the entry that caused it.
+fn line_col(content: &str, needle: &str, nth: usize) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+    let (at, _) = content.match_indices(needle).nth(nth)?;
+    let before = &content[..at];
+    let line = before.matches('\n').count() + 1;
+    let col = at - before.rfind('\n').map_or(0, |nl| nl + 1) + 1;
+    Some((line, col))
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
 
 #[derive(Debug, Deserialize)]
 pub struct BuildConfig {
+    #[serde(default)]
     pub inputs: Vec<Ingestor>,
     #[serde(default = "default_output")]
     pub output: String,
@@ -270,6 +474,10 @@ pub struct BuildConfig {
     pub cache_dir: Option<String>,
     #[serde(default = "default_osm_output")]
     pub osm_output: String,
+    /// `osm_output` plus the merged GTFS feeds before finalizing, saved next to `output`
+    /// so `--update-gtfs --feed` can replace one feed without re-reading the others.
+    #[serde(default = "default_transit_output")]
+    pub transit_output: String,
     #[serde(default = "default_address_output")]
     pub address_output: String,
     /// RDP vertical tolerance (m) denoising DEM elevation at ingest. Baked; re-tuning requires a rebuild.
@@ -278,18 +486,201 @@ pub struct BuildConfig {
     /// OSM `surface=*` → bike cruise-speed factor (asphalt = 1.0), baked per-edge. Re-tuning requires a rebuild.
     #[serde(default)]
     pub surface_speed_factors: crate::structures::SurfaceSpeedFactors,
+    /// Street components smaller than this many nodes (other than the largest) are pruned
+    /// so snapping never lands on an unreachable island. `0` disables. Baked.
+    #[serde(default = "default_min_island_nodes")]
+    pub min_island_nodes: usize,
     #[serde(default)]
     pub delay_models: Vec<DelayModelConfig>,
+    /// Linking of same-station stops published by different GTFS feeds. Baked.
+    #[serde(default)]
+    pub cross_feed_stops: CrossFeedStopsConfig,
+    /// Street-network lengths for GTFS stop → street links. Baked.
+    #[serde(default)]
+    pub stop_links: StopLinksConfig,
+    /// Direct walk edges between nearby stops. Baked.
+    #[serde(default)]
+    pub transfer_edges: TransferEdgesConfig,
+    /// Workers reading the inputs of one build phase side by side (`0`: one per core,
+    /// `1`: strictly sequential). Merging stays in config order, so the graph is the same.
+    #[serde(default)]
+    pub ingest_threads: usize,
+    /// Keep each read GTFS feed under `<cache_dir>/gtfs/`, keyed by the file's digest,
+    /// so a rebuild skips parsing the feeds that did not change.
+    #[serde(default = "default_gtfs_feed_cache")]
+    pub gtfs_feed_cache: bool,
+    /// Transit shape geometry kept in the graph: `full`, `simplified` or `none`. Baked.
+    #[serde(default)]
+    pub geometry: crate::structures::GeometryLevel,
+    /// Max offset (m) of a dropped shape vertex under `geometry: simplified`. Baked.
+    #[serde(default = "default_geometry_tolerance_m")]
+    pub geometry_tolerance_m: f64,
+    /// Timestamped copies of every saved `output`, for rolling back a bad feed.
+    #[serde(default)]
+    pub snapshots: SnapshotConfig,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        BuildConfig {
+            inputs: Vec::new(),
+            output: default_output(),
+            cache_dir: None,
+            osm_output: default_osm_output(),
+            transit_output: default_transit_output(),
+            address_output: default_address_output(),
+            elevation_smoothing_epsilon: default_elevation_smoothing_epsilon(),
+            surface_speed_factors: Default::default(),
+            min_island_nodes: default_min_island_nodes(),
+            delay_models: Vec::new(),
+            cross_feed_stops: CrossFeedStopsConfig::default(),
+            stop_links: StopLinksConfig::default(),
+            transfer_edges: TransferEdgesConfig::default(),
+            ingest_threads: 0,
+            gtfs_feed_cache: default_gtfs_feed_cache(),
+            geometry: Default::default(),
+            geometry_tolerance_m: default_geometry_tolerance_m(),
+            snapshots: SnapshotConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SnapshotConfig {
+    /// Directory receiving a `graph-<YYYYMMDDTHHMMSS>.bin` copy of each saved graph;
+    /// unset disables snapshots.
+    #[serde(default)]
+    pub output: Option<String>,
+    /// Newest snapshots kept; older ones are deleted after each save.
+    #[serde(default = "default_snapshot_keep")]
+    pub keep: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        SnapshotConfig { output: None, keep: default_snapshot_keep() }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct CrossFeedStopsConfig {
+    /// Max straight-line distance (m) between two stops to link them. `0` disables.
+    #[serde(default = "default_cross_feed_radius_m")]
+    pub radius_m: f64,
+    /// Min share of the shorter name's words (3+ letters) found in the other name.
+    #[serde(default = "default_cross_feed_min_name_similarity")]
+    pub min_name_similarity: f64,
+}
+
+impl Default for CrossFeedStopsConfig {
+    fn default() -> Self {
+        CrossFeedStopsConfig {
+            radius_m: default_cross_feed_radius_m(),
+            min_name_similarity: default_cross_feed_min_name_similarity(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct StopLinksConfig {
+    /// How far (m) from a stop to look for the street segment it stands on.
+    #[serde(default = "default_stop_link_radius_m")]
+    pub segment_radius_m: f64,
+    /// Walk budget (m) when measuring a link over the street network; a linked node
+    /// out of reach is replaced by the stop's own segment. `0` keeps straight lines.
+    #[serde(default = "default_stop_link_budget_m")]
+    pub network_budget_m: usize,
+}
+
+impl Default for StopLinksConfig {
+    fn default() -> Self {
+        StopLinksConfig {
+            segment_radius_m: default_stop_link_radius_m(),
+            network_budget_m: default_stop_link_budget_m(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct TransferEdgesConfig {
+    /// Max straight-line distance (m) between two stops to join them. `0` disables.
+    #[serde(default = "default_transfer_edge_radius_m")]
+    pub radius_m: f64,
+    /// Pairs whose street-network walk exceeds this multiple of the straight line
+    /// stay unjoined.
+    #[serde(default = "default_transfer_edge_max_detour")]
+    pub max_detour: f64,
+}
+
+impl Default for TransferEdgesConfig {
+    fn default() -> Self {
+        TransferEdgesConfig {
+            radius_m: default_transfer_edge_radius_m(),
+            max_detour: default_transfer_edge_max_detour(),
+        }
+    }
+}
+
+/// `"HH:MM"` (or `"HH:MM:SS"`) before noon → seconds after midnight.
+fn parse_service_day_cutover(s: &str) -> Result<u32, String> {
+    let t = chrono::NaiveTime::parse_from_str(s, "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(s, "%H:%M"))
+        .map_err(|_| format!("service_day_cutover must be \"HH:MM\", got \"{s}\""))?;
+    let secs = chrono::Timelike::num_seconds_from_midnight(&t);
+    if secs > 12 * 3600 {
+        return Err(format!("service_day_cutover must be at or before 12:00, got \"{s}\""));
+    }
+    Ok(secs)
+}
+
+fn default_gtfs_feed_cache() -> bool {
+    true
+}
+
+fn default_geometry_tolerance_m() -> f64 {
+    5.0
+}
+
+fn default_transfer_edge_radius_m() -> f64 {
+    200.0
+}
+
+fn default_transfer_edge_max_detour() -> f64 {
+    2.0
+}
+
+fn default_stop_link_radius_m() -> f64 {
+    100.0
+}
+
+fn default_stop_link_budget_m() -> usize {
+    1000
+}
+
+fn default_cross_feed_radius_m() -> f64 {
+    100.0
+}
+
+fn default_cross_feed_min_name_similarity() -> f64 {
+    0.5
 }
 
 fn default_output() -> String {
     "graph.bin".to_string()
 }
 
+fn default_snapshot_keep() -> usize {
+    7
+}
+
 fn default_osm_output() -> String {
     "osm.bin".to_string()
 }
 
+fn default_transit_output() -> String {
+    "transit.bin".to_string()
+}
+
 fn default_address_output() -> String {
     "address.bin".to_string()
 }
@@ -298,6 +689,10 @@ fn default_elevation_smoothing_epsilon() -> f64 {
     4.0
 }
 
+fn default_min_island_nodes() -> usize {
+    40
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DelayModelConfig {
     pub mode: String,
@@ -306,16 +701,92 @@ pub struct DelayModelConfig {
 
 impl DelayModelConfig {
     pub fn route_type(&self) -> Option<RouteType> {
-        match self.mode.as_str() {
-            "tram" => Some(RouteType::Tramway),
-            "subway" | "metro" => Some(RouteType::Subway),
-            "rail" | "train" => Some(RouteType::Rail),
-            "bus" => Some(RouteType::Bus),
-            "ferry" => Some(RouteType::Ferry),
-            "cable_car" | "cablecar" => Some(RouteType::CableCar),
-            "gondola" => Some(RouteType::Gondola),
-            "funicular" => Some(RouteType::Funicular),
-            _ => None,
+        route_type_from_mode(&self.mode)
+    }
+}
+
+/// Per-mode itinerary preference (`default_routing.modes`): a ride on this mode is
+/// ranked as if it took `cost_multiplier` × its in-vehicle time, plus
+/// `board_penalty_secs` per boarding.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ModePreference {
+    pub cost_multiplier: f64,
+    pub board_penalty_secs: u32,
+}
+
+impl Default for ModePreference {
+    fn default() -> Self {
+        Self { cost_multiplier: 1.0, board_penalty_secs: 0 }
+    }
+}
+
+impl ModePreference {
+    /// Seconds a `ride_secs` ride on this mode adds to its plain duration when ranked:
+    /// the board penalty plus `(multiplier - 1)` × the ride (negative for a favoured mode).
+    pub fn extra_secs(&self, ride_secs: u32) -> f64 {
+        self.board_penalty_secs as f64 + (self.cost_multiplier - 1.0) * ride_secs as f64
+    }
+}
+
+/// Config mode name (`tram`, `metro`, `train`, …) → GTFS route type.
+pub fn route_type_from_mode(mode: &str) -> Option<RouteType> {
+    match mode {
+        "tram" => Some(RouteType::Tramway),
+        "subway" | "metro" => Some(RouteType::Subway),
+        "rail" | "train" => Some(RouteType::Rail),
+        "bus" => Some(RouteType::Bus),
+        "ferry" => Some(RouteType::Ferry),
+        "cable_car" | "cablecar" => Some(RouteType::CableCar),
+        "gondola" => Some(RouteType::Gondola),
+        "funicular" => Some(RouteType::Funicular),
+        _ => None,
+    }
+}
+
+/// One `default_routing.route_overrides` entry: approximate figures for the routes of
+/// `agency` and/or `mode` (either may be omitted to match any), for feeds without
+/// machine-readable fares or emission data. A modeled fare operator keeps its model.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RouteOverrideConfig {
+    pub agency: Option<String>,
+    pub mode: Option<String>,
+    pub co2_g_per_km: Option<f64>,
+    pub flat_fare_euros: Option<f64>,
+    pub average_speed_kmh: Option<f64>,
+}
+
+impl RouteOverrideConfig {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(mode) = &self.mode
+            && route_type_from_mode(mode).is_none()
+        {
+            return Err(format!("unknown mode '{mode}'"));
+        }
+        let values = [
+            ("co2_g_per_km", self.co2_g_per_km),
+            ("flat_fare_euros", self.flat_fare_euros),
+            ("average_speed_kmh", self.average_speed_kmh),
+        ];
+        for (name, v) in values {
+            if v.is_some_and(|v| !(v.is_finite() && v >= 0.0)) {
+                return Err(format!("{name} must be a non-negative number"));
+            }
+        }
+        if self.average_speed_kmh == Some(0.0) {
+            return Err("average_speed_kmh must be positive".into());
+        }
+        Ok(())
+    }
+
+    pub fn to_route_override(&self) -> crate::structures::RouteOverride {
+        crate::structures::RouteOverride {
+            agency: self.agency.clone(),
+            route_type: self.mode.as_deref().and_then(route_type_from_mode),
+            co2_g_per_km: self.co2_g_per_km,
+            flat_fare_cents: self.flat_fare_euros.map(|e| (e * 100.0).round() as u32),
+            average_speed_kmh: self.average_speed_kmh,
         }
     }
 }
@@ -343,6 +814,45 @@ pub struct OsmPbfIngestor {
     pub phase: Option<u8>,
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Area of interest as `[min_lon, min_lat, max_lon, max_lat]`; nodes outside it
+    /// (and the way segments touching them) are discarded at load time.
+    #[serde(default)]
+    pub bbox: Option<[f64; 4]>,
+    /// Path to a GeoJSON (Multi)Polygon boundary, applied like `bbox`. When both are
+    /// set a node must be inside both.
+    #[serde(default)]
+    pub boundary_geojson: Option<String>,
+    /// Index `addr:*` tags so street places in plans get an address label
+    /// (`PlanNode.label`). Off by default: the index is kept in memory when serving.
+    #[serde(default)]
+    pub address_labels: bool,
+    /// Also index named POIs (`amenity`/`shop`/`tourism`/… + `name`) next to the
+    /// addresses, for the `geocode` query. Implies `address_labels`.
+    #[serde(default)]
+    pub geocoder: bool,
+    /// Index shelters, benches, toilets and lighting so transit stops expose their
+    /// facilities (`PlanNode.amenities`, `gtfsStops { amenities }`).
+    #[serde(default)]
+    pub stop_amenities: bool,
+    /// Index car and bicycle parking (capacity, fee, `park_ride`) for the
+    /// `parkingNearby` query; park-and-ride then prefers stops next to a car park of
+    /// known capacity.
+    #[serde(default)]
+    pub parking: bool,
+}
+
+impl OsmPbfIngestor {
+    fn validate_clip(&self) -> Result<(), String> {
+        if let Some([min_lon, min_lat, max_lon, max_lat]) = self.bbox
+            && !(min_lon < max_lon && min_lat < max_lat)
+        {
+            return Err(format!(
+                "osm/pbf bbox [{min_lon}, {min_lat}, {max_lon}, {max_lat}] is empty; \
+                 expected [min_lon, min_lat, max_lon, max_lat]"
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -352,6 +862,89 @@ pub struct GtfsGenericIngestor {
     pub phase: Option<u8>,
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Prefix for this feed's stop/trip/route ids (`"{namespace}:{id}"`) so two feeds
+    /// cannot collide. Realtime feeds for it must set the same `namespace`. Baked.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Stops link to street nodes within this many metres (default 1000); farther
+    /// stops stay unlinked. Baked.
+    #[serde(default)]
+    pub link_radius_m: Option<f64>,
+    /// Walkable street nodes each stop links to, closest first (default 1). Baked.
+    #[serde(default)]
+    pub link_k: Option<usize>,
+    /// Time of day (`"HH:MM"`) at which this feed's service day rolls over: earlier
+    /// queries also ride the previous service day's after-midnight trips (default 05:00).
+    #[serde(default)]
+    pub service_day_cutover: Option<String>,
+    /// Part of the feed to load (all of it by default). Baked.
+    #[serde(default)]
+    pub filter: GtfsFilterConfig,
+}
+
+/// Route types (by `modes` name: `tram`, `metro`, `bus`, …), agencies and area a GTFS
+/// input is cut down to at load time.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GtfsFilterConfig {
+    /// Load only routes of these types; all when unset.
+    pub route_types: Option<Vec<String>>,
+    pub exclude_route_types: Vec<String>,
+    /// Load only routes of agencies with one of these `agency_id`s or names; all when unset.
+    pub agencies: Option<Vec<String>>,
+    /// Load only stops inside `[min_lon, min_lat, max_lon, max_lat]`.
+    pub bbox: Option<[f64; 4]>,
+}
+
+impl GtfsGenericIngestor {
+    pub fn feed_filter(&self) -> crate::ingestion::gtfs::FeedFilter {
+        let types = |names: &[String]| names.iter().filter_map(|n| route_type_from_mode(n)).collect();
+        let f = &self.filter;
+        crate::ingestion::gtfs::FeedFilter {
+            route_types: f.route_types.as_deref().map(types),
+            exclude_route_types: types(&f.exclude_route_types),
+            agencies: f.agencies.clone(),
+            bbox: f.bbox,
+        }
+    }
+
+    fn validate_filter(&self) -> Result<(), String> {
+        let f = &self.filter;
+        for name in f.route_types.iter().flatten().chain(&f.exclude_route_types) {
+            if route_type_from_mode(name).is_none() {
+                return Err(format!("filter: unknown route type '{name}'"));
+            }
+        }
+        if let Some([min_lon, min_lat, max_lon, max_lat]) = f.bbox
+            && !(min_lon < max_lon && min_lat < max_lat)
+        {
+            return Err(format!(
+                "filter.bbox [{min_lon}, {min_lat}, {max_lon}, {max_lat}] is empty; \
+                 expected [min_lon, min_lat, max_lon, max_lat]"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn stop_linking(&self) -> crate::ingestion::gtfs::StopLinking {
+        let default = crate::ingestion::gtfs::StopLinking::default();
+        crate::ingestion::gtfs::StopLinking {
+            radius_m: self.link_radius_m.unwrap_or(default.radius_m),
+            k: self.link_k.unwrap_or(default.k),
+        }
+    }
+
+    fn validate_linking(&self) -> Result<(), String> {
+        if let Some(r) = self.link_radius_m
+            && !(r.is_finite() && r > 0.0)
+        {
+            return Err(format!("link_radius_m must be a positive number of metres, got {r}"));
+        }
+        if self.link_k == Some(0) {
+            return Err("link_k must be at least 1".into());
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -362,6 +955,12 @@ pub struct GtfsSncbIngestor {
     pub phase: Option<u8>,
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Time of day (`"HH:MM"`) at which this feed's service day rolls over: earlier
+    /// queries also ride the previous service day's after-midnight trips (default 05:00).
+    #[serde(default)]
+    pub service_day_cutover: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -405,6 +1004,14 @@ pub struct RoutingDefaultConfig {
     pub cycling_speed_mps: Option<f64>,
     #[serde(default)]
     pub driving_speed_mps: Option<f64>,
+    /// Free-flow car speed (km/h) per OSM `highway` value; unlisted classes drive at
+    /// `driving_speed_mps`. Also sets the A* car bound (the fastest class present).
+    #[serde(default)]
+    pub car_speeds_kmh: Option<std::collections::BTreeMap<String, f64>>,
+    /// Daily windows slowing car edges per OSM `highway` value (travel time multiplier,
+    /// at least 1), timed from the query's departure; car egress stays at free flow.
+    #[serde(default)]
+    pub car_rush_hours: Option<Vec<RushHourConfig>>,
     #[serde(default)]
     pub vehicle_access_secs: Option<u32>,
     /// Fraction of crow-flies walk-time the bike/car access budget grows to, above the floor.
@@ -429,12 +1036,53 @@ pub struct RoutingDefaultConfig {
     /// True ⇒ build/load the foot-access CCH at startup; false ⇒ `g.cch` stays `None` and access falls back to two-pass Dijkstra.
     #[serde(default)]
     pub prepare_cch_access: Option<bool>,
+    /// A* street-search lower bound: `geometric` (default) or `landmarks` (ALT tables
+    /// built at startup over the contracted graph, tighter around barriers).
+    #[serde(default)]
+    pub street_heuristic: Option<crate::structures::StreetHeuristic>,
+    /// Number of ALT landmarks when `street_heuristic: landmarks` (default 8).
+    #[serde(default)]
+    pub alt_landmarks: Option<usize>,
+    /// Named ALT profiles (per mode or all modes) precomputed with the graph and
+    /// persisted in graph.bin; a request picks one with `searchProfile`.
+    #[serde(default)]
+    pub search_profiles: Option<std::collections::BTreeMap<String, SearchProfileConfig>>,
     /// Upper bound on `windowMinutes`; larger requests are clamped.
     #[serde(default)]
     pub max_window_minutes: Option<u32>,
+    /// Seconds of arrival penalty per crowding step of a transit leg, applied when a
+    /// query sets `avoidCrowding` (default 300).
+    #[serde(default)]
+    pub crowding_penalty_secs: Option<u32>,
+    /// GTFS modes (`tram`, `rail`, …) whose trips accept bikes for `BIKE_ON_TRANSIT`
+    /// when `trips.txt` leaves `bikes_allowed` unset.
+    #[serde(default)]
+    pub bike_route_types: Option<Vec<String>>,
+    /// Per-mode cost multipliers and board penalties keyed by GTFS mode (`metro`,
+    /// `bus`, …), e.g. to prefer the metro over a parallel bus.
+    #[serde(default)]
+    pub modes: Option<std::collections::BTreeMap<String, ModePreference>>,
+    /// Named routing profiles (`raptor(profile:)`), merged over the built-in
+    /// `default` / `senior` / `fast` / `wheelchair` by name.
+    #[serde(default)]
+    pub routing_profiles: Option<std::collections::BTreeMap<String, crate::structures::RoutingProfile>>,
+    /// Per-query search budget (wall-clock ms / expansions); an exhausted query fails
+    /// with a `SEARCH_*` error code. Absent or `0` = unbounded.
+    #[serde(default)]
+    pub max_query_ms: Option<u64>,
+    #[serde(default)]
+    pub max_search_expansions: Option<u64>,
     /// Max snap distance (m) to the street network; farther queries are rejected.
     #[serde(default)]
     pub max_snap_distance_m: Option<u32>,
+    /// Snap distance (m, default 300) above which plans carry a `FAR_SNAP` warning;
+    /// `0` disables it.
+    #[serde(default)]
+    pub snap_warning_m: Option<u32>,
+    /// An endpoint snapped onto a street component of fewer nodes (default 40) gets a
+    /// `SNAPPED_TO_ISLAND` warning; `0` disables it.
+    #[serde(default)]
+    pub snap_island_nodes: Option<usize>,
     #[serde(default)]
     pub travel_map_grid_step_m: Option<f64>,
     /// Cap on total isochrone grid cells; a step producing more is coarsened.
@@ -452,6 +1100,10 @@ pub struct RoutingDefaultConfig {
     pub distance_budget: Option<f64>,
     #[serde(default)]
     pub epsilon: Option<EpsilonConfig>,
+    /// A* top speeds (m/s) overriding the ones derived from the network; a value
+    /// below the derived bound is rejected at startup (inadmissible heuristic).
+    #[serde(default)]
+    pub estimator_speeds: Option<EstimatorSpeedsConfig>,
     /// Bike grid-bucketing cell-size coefficients per metre of origin→dest distance, on
     /// the CyclewayDeficit and Dplus axes (cell = k·D). `0` disables bucketing.
     #[serde(default)]
@@ -490,10 +1142,19 @@ pub struct RoutingDefaultConfig {
     /// byte-identical to pre-feature (price axis, dominance, and output all gone).
     #[serde(default)]
     pub fares: Option<FaresConfig>,
+    /// Per-agency/mode emission factors, flat fares and average speeds, first match
+    /// per field wins. A flat fare prices an agency no fare model covers (needs
+    /// `fares.enabled`).
+    #[serde(default)]
+    pub route_overrides: Option<Vec<RouteOverrideConfig>>,
     /// Pedestrian vertical-connector cost model, used only by the Stage B1
     /// connector-coverage measurement; B1 does not charge this in routing.
     #[serde(default)]
     pub connector_cost: Option<ConnectorCostConfig>,
+    /// OSM elevators out of service (`node/<id>` or `way/<id>`): the wheelchair profile
+    /// avoids stops whose every step-free access path uses one of them.
+    #[serde(default)]
+    pub elevator_outages: Option<Vec<String>>,
     #[serde(default)]
     pub address_geo_offset_km: Option<f64>,
     /// Distance (km) at which the geo score has decayed to half; the exponential scale
@@ -576,9 +1237,101 @@ pub struct ConnectorCostConfig {
     #[serde(default)]
     pub elevator_secs: Option<f64>,
     #[serde(default)]
+    pub escalator_speed_mps: Option<f64>,
+    #[serde(default)]
     pub relocation_fallback_secs: Option<f64>,
 }
 
+/// One `default_routing.car_rush_hours` entry: between `start` and `end` (`"HH:MM"`,
+/// daily) driving a listed highway class takes its factor times the free-flow time.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RushHourConfig {
+    pub start: String,
+    pub end: String,
+    pub highways: std::collections::BTreeMap<String, f64>,
+}
+
+impl RushHourConfig {
+    fn parse_clock(s: &str) -> Result<u32, String> {
+        chrono::NaiveTime::parse_from_str(s, "%H:%M")
+            .map(|t| chrono::Timelike::num_seconds_from_midnight(&t))
+            .map_err(|_| format!("times must be \"HH:MM\", got \"{s}\""))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if Self::parse_clock(&self.start)? >= Self::parse_clock(&self.end)? {
+            return Err(format!("start {} must be before end {}", self.start, self.end));
+        }
+        for (tag, &factor) in &self.highways {
+            if crate::structures::HighwayClass::from_osm_tag(tag).is_none() {
+                return Err(format!("unknown highway '{tag}'"));
+            }
+            if !(factor.is_finite() && factor >= 1.0) {
+                return Err(format!("highways.{tag} must be a factor of at least 1"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_window(&self) -> Result<crate::structures::rush_hour::RushHourWindow, String> {
+        self.validate()?;
+        let mut window = crate::structures::rush_hour::RushHourWindow::new(
+            Self::parse_clock(&self.start)?,
+            Self::parse_clock(&self.end)?,
+        );
+        for (tag, &factor) in &self.highways {
+            if let Some(class) = crate::structures::HighwayClass::from_osm_tag(tag) {
+                window.set(class, factor);
+            }
+        }
+        Ok(window)
+    }
+}
+
+/// One `default_routing.search_profiles` entry: ALT tables built with the graph and
+/// selected per request with `searchProfile`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SearchProfileConfig {
+    /// Only this mode's streets, for tighter bounds on its searches; absent = all modes.
+    pub mode: Option<SearchProfileMode>,
+    /// Number of landmarks (default 8).
+    pub landmarks: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchProfileMode {
+    Walk,
+    Bike,
+    Car,
+}
+
+impl SearchProfileConfig {
+    pub fn to_spec(self, name: &str) -> crate::structures::SearchProfileSpec {
+        use crate::structures::cost::RoutingMode;
+        crate::structures::SearchProfileSpec {
+            name: name.to_string(),
+            mode: self.mode.map(|m| match m {
+                SearchProfileMode::Walk => RoutingMode::Walk,
+                SearchProfileMode::Bike => RoutingMode::Bike,
+                SearchProfileMode::Car => RoutingMode::Drive,
+            }),
+            landmarks: self.landmarks.unwrap_or(8),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct EstimatorSpeedsConfig {
+    #[serde(default)]
+    pub walk_mps: Option<f64>,
+    #[serde(default)]
+    pub bike_mps: Option<f64>,
+    #[serde(default)]
+    pub car_mps: Option<f64>,
+}
+
 /// Per-axis ε-dominance tuning: `ε_i = a_i + b_i·value`.
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
@@ -1003,6 +1756,18 @@ impl Ingestor {
         }
     }
 
+    /// The config `ingestor` tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Ingestor::OsmPbf(_) => "osm/pbf",
+            Ingestor::GtfsGeneric(_) => "gtfs/generic",
+            Ingestor::GtfsStib(_) => "gtfs/stib",
+            Ingestor::GtfsSncb(_) => "gtfs/sncb",
+            Ingestor::AddressBestAdd(_) => "address/bestadd",
+            Ingestor::DemBelgianLambert2008(_) => "dem/belgian-lambert-2008",
+        }
+    }
+
     pub fn url(&self) -> &str {
         match self {
             Ingestor::OsmPbf(c) => &c.url,
@@ -1013,6 +1778,30 @@ impl Ingestor {
         }
     }
 
+    pub fn is_gtfs(&self) -> bool {
+        matches!(self, Ingestor::GtfsGeneric(_) | Ingestor::GtfsStib(_) | Ingestor::GtfsSncb(_))
+    }
+
+    /// GTFS id namespace; `None` for raw ids and for non-GTFS inputs.
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            Ingestor::GtfsGeneric(c) | Ingestor::GtfsStib(c) => c.namespace.as_deref(),
+            Ingestor::GtfsSncb(c) => c.namespace.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Service day cutover in seconds after midnight; `None` for the router default and
+    /// for non-GTFS inputs.
+    pub fn service_day_cutover(&self) -> Option<u32> {
+        let cutover = match self {
+            Ingestor::GtfsGeneric(c) | Ingestor::GtfsStib(c) => c.service_day_cutover.as_deref(),
+            Ingestor::GtfsSncb(c) => c.service_day_cutover.as_deref(),
+            _ => None,
+        };
+        cutover.and_then(|s| parse_service_day_cutover(s).ok())
+    }
+
     pub fn headers(&self) -> &HashMap<String, String> {
         match self {
             Ingestor::OsmPbf(c) => &c.headers,
@@ -1103,18 +1892,132 @@ pub enum AddressKind {
 
 impl Config {
     pub fn load(path: &str) -> Result<Self, String> {
+        Self::load_with(path, &[])
+    }
+
+    /// Load `path`, then layer `MAAS_*` environment overrides and finally `flags`
+    /// (command-line overrides) on top, so one image can be deployed anywhere without
+    /// editing the file. Later overrides of the same key win.
+    pub fn load_with(path: &str, flags: &[ConfigOverride]) -> Result<Self, String> {
         let content =
             fs::read_to_string(path).map_err(|e| format!("Failed to read config: {e}"))?;
-        let mut config: Self = serde_yaml_ng::from_str(&content)
+        let mut overrides = ConfigOverride::from_env(std::env::vars());
+        overrides.extend_from_slice(flags);
+        let config = if overrides.is_empty() {
+            Self::parse(&content)?
+        } else {
+            Self::parse_overridden(&content, &overrides)?
+        };
+        config.validate(&content)?;
+        Ok(config)
+    }
+
+    /// Overrides are applied to the YAML tree before deserializing, so they go through
+    /// the same defaults and type checks as the file (serde positions are lost, so the
+    /// error names the overrides instead).
+    fn parse_overridden(content: &str, overrides: &[ConfigOverride]) -> Result<Self, String> {
+        let mut tree: serde_yaml_ng::Value = serde_yaml_ng::from_str(content)
             .map_err(|e| format!("Failed to parse config: {e}"))?;
-        config.apply_env_overrides();
-        config.validate()?;
+        if tree.is_null() {
+            tree = serde_yaml_ng::Value::Mapping(Default::default());
+        }
+        for o in overrides {
+            o.apply(&mut tree)?;
+        }
+        let text = serde_yaml_ng::to_string(&tree)
+            .map_err(|e| format!("Failed to apply config overrides: {e}"))?;
+        Self::parse(&text).map_err(|e| {
+            let sources: Vec<&str> = overrides.iter().map(|o| o.source.as_str()).collect();
+            format!("{e} (with overrides from {})", sources.join(", "))
+        })
+    }
+
+    /// Deserialize, recording every key serde skipped as a warning: a typo such as
+    /// `walking_sped_mps` would otherwise silently fall back to the default.
+    /// Syntax/type errors carry serde's line/column.
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut unknown = Vec::new();
+        let de = serde_yaml_ng::Deserializer::from_str(content);
+        let mut config: Self = serde_ignored::deserialize(de, |path| unknown.push(path.to_string()))
+            .map_err(|e| format!("Failed to parse config: {e}"))?;
+        config.warnings = unknown
+            .into_iter()
+            .map(|path| format!("unknown config key '{path}' ignored"))
+            .collect();
+        if config.build.inputs.is_empty() {
+            config
+                .warnings
+                .push("build.inputs is empty: only a previously saved graph can be served".into());
+        }
         Ok(config)
     }
 
-    fn validate(&self) -> Result<(), String> {
-        for input in &self.build.inputs {
-            input.validate_phase()?;
+    fn validate(&self, content: &str) -> Result<(), String> {
+        if self.build.snapshots.output.is_some() && self.build.snapshots.keep == 0 {
+            return Err("build.snapshots.keep must be at least 1".into());
+        }
+        for (mode, pref) in self.default_routing.modes.iter().flatten() {
+            if !(pref.cost_multiplier.is_finite() && pref.cost_multiplier > 0.0) {
+                return Err(format!("default_routing.modes.{mode}.cost_multiplier must be a positive number"));
+            }
+        }
+        for (name, p) in self.default_routing.search_profiles.iter().flatten() {
+            if p.landmarks == Some(0) {
+                return Err(format!("default_routing.search_profiles.{name}.landmarks must be at least 1"));
+            }
+        }
+        for (i, r) in self.default_routing.car_rush_hours.iter().flatten().enumerate() {
+            r.validate().map_err(|e| format!("default_routing.car_rush_hours[{i}]: {e}"))?;
+        }
+        for (i, o) in self.default_routing.route_overrides.iter().flatten().enumerate() {
+            o.validate().map_err(|e| format!("default_routing.route_overrides[{i}]: {e}"))?;
+        }
+        for (i, input) in self.build.inputs.iter().enumerate() {
+            let check = || -> Result<(), String> {
+                if input.url().is_empty() {
+                    return Err("url is empty".into());
+                }
+                input.location()?;
+                input.validate_phase()?;
+                match input {
+                    Ingestor::OsmPbf(c) => c.validate_clip()?,
+                    Ingestor::GtfsGeneric(c) | Ingestor::GtfsStib(c) => {
+                        c.validate_linking()?;
+                        c.validate_filter()?;
+                    }
+                    _ => {}
+                }
+                let cutover = match input {
+                    Ingestor::GtfsGeneric(c) | Ingestor::GtfsStib(c) => c.service_day_cutover.as_deref(),
+                    Ingestor::GtfsSncb(c) => c.service_day_cutover.as_deref(),
+                    _ => None,
+                };
+                if let Some(s) = cutover {
+                    parse_service_day_cutover(s)?;
+                }
+                Ok(())
+            };
+            if let Err(e) = check() {
+                // Same url on earlier inputs: point at this entry's occurrence.
+                let nth = self.build.inputs[..i].iter().filter(|p| p.url() == input.url()).count();
+                let at = match line_col(content, input.url(), nth) {
+                    Some((line, col)) => format!(" at line {line} column {col}"),
+                    None => String::new(),
+                };
+                return Err(format!("build.inputs[{i}] ({}){at}: {e}", input.label()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the GraphQL/gRPC server can run on this config. It serves the single
+    /// `build.output` graph, so a `federation` section is refused rather than silently
+    /// ignored; shards are served one `maas-rs` process each, or federated as a library.
+    pub fn check_servable(&self) -> Result<(), String> {
+        if self.federation.is_some() {
+            return Err("federation is not supported by the server: serve each shard's graph with its own \
+                        config, or plan across shards with GraphSet and routing::federation as a library"
+                .into());
         }
         Ok(())
     }
@@ -1131,21 +2034,132 @@ impl Config {
             .map(|a| a.cache_dir.clone())
             .unwrap_or_else(default_cache_dir)
     }
+}
 
-    fn apply_env_overrides(&mut self) {
-        if let Ok(host) = std::env::var("MAAS_HOST") {
-            if !host.is_empty() {
-                self.server.host = host;
+/// One `key.path = value` override of a config.yaml value.
+#[derive(Debug, Clone)]
+pub struct ConfigOverride {
+    /// Dotted path into the YAML tree (`server.port`, `build.inputs.0.url`).
+    pub key: String,
+    pub value: serde_yaml_ng::Value,
+    /// Where it came from (`MAAS_PORT`, `--set`), for error messages.
+    pub source: String,
+}
+
+/// Shortcut env vars / flags for the values that differ per deployment, with the key
+/// they set and whether it is string-typed (the raw value is then never YAML-parsed, so
+/// a numeric token stays a string). The admin token has no flag: argv is world-readable.
+const SHORTCUTS: [(&str, &str, &str, bool); 6] = [
+    ("MAAS_HOST", "--host", "server.host", true),
+    ("MAAS_PORT", "--port", "server.port", false),
+    ("MAAS_ADMIN_TOKEN", "", "server.admin_token", true),
+    ("MAAS_GRAPH", "--graph", "build.output", true),
+    ("MAAS_CACHE_DIR", "--cache-dir", "build.cache_dir", true),
+    ("MAAS_LOG_LEVEL", "--log-level", "log_level", true),
+];
+
+/// Prefix of the generic env form: `MAAS_CONFIG__DEFAULT_ROUTING__WALKING_SPEED_MPS=1.4`
+/// sets `default_routing.walking_speed_mps` (`__` separates keys, lower-cased).
+const ENV_PREFIX: &str = "MAAS_CONFIG__";
+
+impl ConfigOverride {
+    /// `raw` is parsed as a YAML scalar/flow value unless `string` (shortcut keys).
+    fn new(key: &str, raw: &str, source: String, string: bool) -> Result<Self, String> {
+        let value = if string {
+            serde_yaml_ng::Value::String(raw.to_string())
+        } else {
+            serde_yaml_ng::from_str(raw).map_err(|e| format!("{source}: invalid value '{raw}': {e}"))?
+        };
+        Ok(ConfigOverride { key: key.to_string(), value, source })
+    }
+
+    /// `MAAS_*` shortcuts, then the generic `MAAS_CONFIG__*` form. Empty values are
+    /// skipped (an unset-but-exported variable must not blank the file's value); values
+    /// that are not valid YAML are kept as strings.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Vec<ConfigOverride> {
+        let vars: Vec<(String, String)> =
+            vars.into_iter().filter(|(_, v)| !v.is_empty()).collect();
+        let mut out = Vec::new();
+        for (var, _, key, string) in SHORTCUTS {
+            if let Some((_, v)) = vars.iter().find(|(k, _)| k == var) {
+                out.extend(Self::new(key, v, var.to_string(), string).ok());
             }
         }
-        if let Ok(port) = std::env::var("MAAS_PORT") {
-            match port.parse::<u16>() {
-                Ok(port) => self.server.port = port,
-                Err(_) => {
-                    tracing::warn!("ignoring invalid MAAS_PORT '{port}' (not a u16)");
+        let mut generic: Vec<&(String, String)> =
+            vars.iter().filter(|(k, _)| k.starts_with(ENV_PREFIX)).collect();
+        generic.sort();
+        for (var, v) in generic {
+            let key = var[ENV_PREFIX.len()..].to_ascii_lowercase().replace("__", ".");
+            out.extend(
+                Self::new(&key, v, var.clone(), false)
+                    .or_else(|_| Self::new(&key, v, var.clone(), true))
+                    .ok(),
+            );
+        }
+        out
+    }
+
+    /// Command-line overrides: the shortcut flags (`--port 9000`, `--graph x.bin`, …)
+    /// and any number of `--set key.path=value`.
+    pub fn from_args(args: &[String]) -> Result<Vec<ConfigOverride>, String> {
+        let mut out = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((n, v)) if n.starts_with("--") => (n, Some(v.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let shortcut = SHORTCUTS.iter().find(|(_, flag, _, _)| !flag.is_empty() && *flag == name);
+            if shortcut.is_none() && name != "--set" {
+                continue;
+            }
+            let value = match inline {
+                Some(v) => v,
+                None => iter.next().cloned().ok_or_else(|| format!("{name} requires a value"))?,
+            };
+            out.push(match shortcut {
+                Some(&(_, flag, key, string)) => Self::new(key, &value, flag.to_string(), string)?,
+                None => {
+                    let (key, raw) = value
+                        .split_once('=')
+                        .ok_or_else(|| format!("--set expects key.path=value, got '{value}'"))?;
+                    Self::new(key.trim(), raw, format!("--set {key}"), false)?
                 }
+            });
+        }
+        Ok(out)
+    }
+
+    /// Set `key` in `tree`, creating missing mappings; numeric segments index into an
+    /// existing sequence.
+    fn apply(&self, tree: &mut serde_yaml_ng::Value) -> Result<(), String> {
+        use serde_yaml_ng::Value;
+        let mut node = tree;
+        for seg in self.key.split('.') {
+            if seg.is_empty() {
+                return Err(format!("{}: invalid config key '{}'", self.source, self.key));
             }
+            node = match node {
+                Value::Sequence(items) => {
+                    let i: usize = seg.parse().map_err(|_| {
+                        format!("{}: '{seg}' in '{}' must be a list index", self.source, self.key)
+                    })?;
+                    let len = items.len();
+                    items.get_mut(i).ok_or_else(|| {
+                        format!("{}: index {i} in '{}' out of range ({len} entries)", self.source, self.key)
+                    })?
+                }
+                other => {
+                    if !other.is_mapping() {
+                        *other = Value::Mapping(Default::default());
+                    }
+                    let Value::Mapping(map) = other else { unreachable!() };
+                    map.entry(Value::String(seg.to_string())).or_insert(Value::Null)
+                }
+            };
         }
+        *node = self.value.clone();
+        Ok(())
     }
 }
 
@@ -1162,8 +2176,15 @@ mod tests {
         assert_eq!(cfg.graphql_max_depth, 15);
         assert_eq!(cfg.graphql_max_complexity, 1000);
         assert!(!cfg.graphiql_enabled);
+        assert!(!cfg.introspection_enabled);
         assert_eq!(cfg.tiles.url, "https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png");
         assert_eq!(cfg.tiles.attribution, "© OpenStreetMap contributors");
+        assert!(!cfg.grpc.enabled);
+        assert_eq!(cfg.grpc.port, 50051);
+        assert_eq!(cfg.shutdown_grace_secs, 30);
+        assert!(!cfg.analytics.enabled);
+        assert_eq!(cfg.analytics.cell_m, 500.0);
+        assert_eq!(cfg.max_concurrent_searches, None);
     }
 
     #[test]
@@ -1189,10 +2210,12 @@ tiles:
 
     #[test]
     fn server_config_custom_values() {
-        let yaml = "host: 127.0.0.1\nport: 8080";
+        let yaml = "host: 127.0.0.1\nport: 8080\ngrpc:\n  enabled: true\n  port: 9090";
         let cfg: ServerConfig = serde_yaml_ng::from_str(yaml).unwrap();
         assert_eq!(cfg.host, "127.0.0.1");
         assert_eq!(cfg.port, 8080);
+        assert!(cfg.grpc.enabled);
+        assert_eq!(cfg.grpc.port, 9090);
     }
 
     #[test]
@@ -1245,6 +2268,8 @@ default_routing: {}
         let cfg: Config = serde_yaml_ng::from_str(yaml).unwrap();
         assert_eq!(cfg.server.host, "127.0.0.1");
         assert_eq!(cfg.server.port, 8000);
+        assert_eq!(cfg.server.plan_cache.capacity, 1024);
+        assert!(cfg.server.admin_token.is_none());
         assert_eq!(cfg.build.elevation_smoothing_epsilon, 4.0);
     }
 
@@ -1259,6 +2284,7 @@ build:
         let cfg: Config = serde_yaml_ng::from_str(yaml).unwrap();
         assert_eq!(cfg.build.output, "graph.bin", "output defaults to graph.bin");
         assert_eq!(cfg.build.osm_output, "osm.bin");
+        assert_eq!(cfg.build.transit_output, "transit.bin");
         assert_eq!(cfg.build.address_output, "address.bin");
         assert!(cfg.default_routing.walking_speed_mps.is_none());
         assert_eq!(cfg.server.port, 8000);
@@ -1314,6 +2340,42 @@ default_routing: {}
         assert_eq!(cfg.build.elevation_smoothing_epsilon, 4.0);
     }
 
+    #[test]
+    fn geometry_level_parses_and_defaults() {
+        use crate::structures::GeometryLevel;
+        let with = r#"
+build:
+  inputs: []
+  geometry: simplified
+  geometry_tolerance_m: 2.5
+default_routing: {}
+"#;
+        let cfg: Config = serde_yaml_ng::from_str(with).unwrap();
+        assert_eq!(cfg.build.geometry, GeometryLevel::Simplified);
+        assert_eq!(cfg.build.geometry_tolerance_m, 2.5);
+
+        let cfg: Config = serde_yaml_ng::from_str("build:\n  inputs: []\n  geometry: none\n").unwrap();
+        assert_eq!(cfg.build.geometry, GeometryLevel::None);
+        let cfg: Config = serde_yaml_ng::from_str("build:\n  inputs: []\n").unwrap();
+        assert_eq!((cfg.build.geometry, cfg.build.geometry_tolerance_m), (GeometryLevel::Full, 5.0));
+        assert!(serde_yaml_ng::from_str::<Config>("build:\n  geometry: partial\n").is_err());
+    }
+
+    #[test]
+    fn min_island_nodes_parses_and_defaults() {
+        let with = r#"
+build:
+  inputs: []
+  min_island_nodes: 0
+default_routing: {}
+"#;
+        let cfg: Config = serde_yaml_ng::from_str(with).unwrap();
+        assert_eq!(cfg.build.min_island_nodes, 0);
+
+        let cfg: Config = serde_yaml_ng::from_str("build:\n  inputs: []\n").unwrap();
+        assert_eq!(cfg.build.min_island_nodes, 40);
+    }
+
     #[test]
     fn surface_speed_factors_absent_uses_default_table() {
         let yaml = r#"
@@ -1425,6 +2487,38 @@ server:
         assert_eq!(bp.downhillcost, 100.0); // untouched default
     }
 
+    #[test]
+    fn mode_preferences_parse_with_defaults() {
+        let yaml = "modes:\n  bus:\n    cost_multiplier: 1.5\n    board_penalty_secs: 120\n  metro:\n    board_penalty_secs: 0";
+        let cfg: RoutingDefaultConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        let modes = cfg.modes.unwrap();
+        assert_eq!(modes["bus"], ModePreference { cost_multiplier: 1.5, board_penalty_secs: 120 });
+        assert_eq!(modes["metro"], ModePreference::default(), "unset multiplier stays neutral");
+    }
+
+    #[test]
+    fn search_profiles_parse_with_default_landmarks() {
+        use crate::structures::cost::RoutingMode;
+        let yaml = "search_profiles:\n  car:\n    mode: car\n    landmarks: 16\n  any: {}";
+        let cfg: RoutingDefaultConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        let profiles = cfg.search_profiles.unwrap();
+        let car = profiles["car"].to_spec("car");
+        assert_eq!((car.mode, car.landmarks), (Some(RoutingMode::Drive), 16));
+        let any = profiles["any"].to_spec("any");
+        assert_eq!((any.mode, any.landmarks), (None, 8));
+        assert!(serde_yaml_ng::from_str::<RoutingDefaultConfig>("search_profiles:\n  x:\n    mode: boat").is_err());
+    }
+
+    #[test]
+    fn routing_profiles_parse_sparsely() {
+        let yaml = "routing_profiles:\n  stroller:\n    max_walk_secs: 900\n    wheelchair: true";
+        let cfg: RoutingDefaultConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        let p = cfg.routing_profiles.unwrap()["stroller"];
+        assert_eq!(p.max_walk_secs, Some(900));
+        assert!(p.wheelchair);
+        assert_eq!(p.walking_speed_mps, None, "unset keeps the graph speed");
+    }
+
     #[test]
     fn parses_street_time_block() {
         let yaml = "street_time:\n  access_percentile: 0.9\n  sigma_floor: 0.1";
@@ -1566,7 +2660,7 @@ feeds:
         assert_eq!(rt.request_timeout_secs, 20); // default
         assert_eq!(rt.feeds.len(), 2);
         match &rt.feeds[0] {
-            RealtimeFeedConfig::GtfsRt { name, url, headers } => {
+            RealtimeFeedConfig::GtfsRt { name, url, headers, .. } => {
                 assert_eq!(name, "sncb");
                 assert!(url.contains("protobuf"));
                 assert_eq!(
@@ -1631,6 +2725,28 @@ feeds:
         }
     }
 
+    #[test]
+    fn siri_feed_parses_and_counts_one_request_per_service() {
+        let yaml = r#"
+enabled: true
+feeds:
+  - type: siri
+    name: vbb
+    estimated_timetable_url: "https://example.com/siri/et"
+    situation_exchange_url: "https://example.com/siri/sx"
+    namespace: vbb
+"#;
+        let rt: RealtimeConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        match &rt.feeds[0] {
+            RealtimeFeedConfig::Siri { name, namespace, .. } => {
+                assert_eq!(name, "vbb");
+                assert_eq!(namespace.as_deref(), Some("vbb"));
+            }
+            _ => panic!("expected siri feed"),
+        }
+        assert_eq!(rt.feeds[0].requests_per_poll(), 2);
+    }
+
     #[test]
     fn realtime_rate_limit_defaults() {
         let yaml = "enabled: true";
@@ -1656,6 +2772,7 @@ feeds:
             name: "sncb".into(),
             url: "https://x/rt".into(),
             headers: HashMap::new(),
+            namespace: None,
         };
         assert_eq!(gtfs.requests_per_poll(), 1);
 
@@ -1664,6 +2781,7 @@ feeds:
             waiting_time_url: "https://x/WaitingTimes/".into(),
             vehicle_position_url: Some("https://x/VehiclePositions/".into()),
             headers: HashMap::new(),
+            namespace: None,
         };
         assert_eq!(stib_vp.requests_per_poll(), 2, "waiting-times + vehicle-positions");
 
@@ -1672,6 +2790,7 @@ feeds:
             waiting_time_url: "https://x/WaitingTimes/".into(),
             vehicle_position_url: None,
             headers: HashMap::new(),
+            namespace: None,
         };
         assert_eq!(stib_no_vp.requests_per_poll(), 1);
     }
@@ -1681,6 +2800,7 @@ feeds:
             name: name.into(),
             url: "https://x/rt".into(),
             headers: HashMap::new(),
+            namespace: None,
         };
         vec![
             gtfs("sncb"),
@@ -1692,6 +2812,7 @@ feeds:
                 waiting_time_url: "https://x/WaitingTimes/".into(),
                 vehicle_position_url: Some("https://x/VehiclePositions/".into()),
                 headers: HashMap::new(),
+                namespace: None,
             },
         ]
     }
@@ -1721,6 +2842,16 @@ feeds:
         assert!(!rt.within_quota());
     }
 
+    #[test]
+    fn shipped_car_speeds_name_known_highway_classes() {
+        let cfg = Config::load("presets/belgium.yaml").expect("presets/belgium.yaml must parse");
+        let speeds = cfg.default_routing.car_speeds_kmh.expect("preset lists car speeds");
+        assert_eq!(speeds.get("motorway"), Some(&120.0));
+        for tag in speeds.keys() {
+            assert!(crate::structures::HighwayClass::from_osm_tag(tag).is_some(), "{tag}");
+        }
+    }
+
     #[test]
     fn shipped_belgium_config_has_stib_fares_enabled() {
         let cfg = Config::load("presets/belgium.yaml").expect("presets/belgium.yaml must parse");
@@ -2404,4 +3535,287 @@ default_routing: {}
         let (_p, path) = write_config(yaml);
         assert!(Config::load(&path).is_ok());
     }
+
+    #[test]
+    fn config_load_rejects_non_positive_mode_multiplier() {
+        let yaml = r#"
+build:
+  inputs: []
+default_routing:
+  modes:
+    bus:
+      cost_multiplier: 0
+"#;
+        let (_p, path) = write_config(yaml);
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.contains("modes.bus.cost_multiplier"), "got: {err}");
+    }
+
+    #[test]
+    fn car_rush_hours_parse_into_windows_and_reject_speedups() {
+        use crate::structures::HighwayClass;
+        let yaml = "car_rush_hours:\n  - start: \"07:30\"\n    end: \"09:00\"\n    highways: { motorway: 1.8, primary: 1.4 }";
+        let cfg: RoutingDefaultConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        let w = cfg.car_rush_hours.unwrap()[0].to_window().unwrap();
+        assert_eq!((w.start, w.end), (27_000, 32_400));
+        assert_eq!(w.factor(HighwayClass::Motorway), 1.8);
+        assert_eq!(w.factor(HighwayClass::Residential), 1.0);
+
+        let yaml = r#"
+build:
+  inputs: []
+default_routing:
+  car_rush_hours:
+    - start: "16:00"
+      end: "18:30"
+      highways: { trunk: 0.5 }
+"#;
+        let (_p, path) = write_config(yaml);
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.contains("car_rush_hours[0]: highways.trunk must be a factor of at least 1"), "got: {err}");
+        let bad = RushHourConfig { start: "18:00".into(), end: "17:00".into(), ..Default::default() };
+        assert!(bad.to_window().unwrap_err().contains("before end"));
+    }
+
+    #[test]
+    fn route_overrides_convert_euros_to_cents_and_reject_unknown_modes() {
+        let yaml = "route_overrides:\n  - agency: De Lijn\n    mode: tram\n    flat_fare_euros: 2.5\n    co2_g_per_km: 30";
+        let cfg: RoutingDefaultConfig = serde_yaml_ng::from_str(yaml).unwrap();
+        let o = cfg.route_overrides.unwrap()[0].to_route_override();
+        assert_eq!(o.agency.as_deref(), Some("De Lijn"));
+        assert_eq!(o.route_type, Some(RouteType::Tramway));
+        assert_eq!((o.flat_fare_cents, o.co2_g_per_km, o.average_speed_kmh), (Some(250), Some(30.0), None));
+
+        let yaml = r#"
+build:
+  inputs: []
+default_routing:
+  route_overrides:
+    - mode: hovercraft
+      co2_g_per_km: 100
+"#;
+        let (_p, path) = write_config(yaml);
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.contains("route_overrides[0]: unknown mode 'hovercraft'"), "got: {err}");
+    }
+
+    #[test]
+    fn osm_pbf_parses_bbox_and_boundary() {
+        let yaml = r#"
+build:
+  inputs:
+    - ingestor: osm/pbf
+      url: "path:data/x.pbf"
+      bbox: [4.2, 50.7, 4.5, 50.95]
+      boundary_geojson: data/brussels.geojson
+default_routing: {}
+"#;
+        let cfg: Config = serde_yaml_ng::from_str(yaml).unwrap();
+        let Ingestor::OsmPbf(c) = &cfg.build.inputs[0] else {
+            panic!("expected osm/pbf");
+        };
+        assert_eq!(c.bbox, Some([4.2, 50.7, 4.5, 50.95]));
+        assert_eq!(c.boundary_geojson.as_deref(), Some("data/brussels.geojson"));
+    }
+
+    #[test]
+    fn config_load_rejects_inverted_bbox() {
+        let yaml = r#"
+build:
+  inputs:
+    - ingestor: osm/pbf
+      url: "path:data/x.pbf"
+      bbox: [50.7, 4.2, 50.95, 4.1]
+default_routing: {}
+"#;
+        let (_p, path) = write_config(yaml);
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.contains("bbox"), "got: {err}");
+    }
+
+    #[test]
+    fn gtfs_filter_maps_mode_names_and_rejects_unknown_ones() {
+        let yaml = |filter: &str| {
+            format!(
+                "build:\n  inputs:\n    - ingestor: gtfs/generic\n      name: tec\n      url: \"path:data/tec.zip\"\n      filter:\n{filter}default_routing: {{}}\n"
+            )
+        };
+        let (_p, path) = write_config(&yaml(
+            "        route_types: [tram, metro]\n        agencies: [TEC]\n        bbox: [4.2, 50.7, 4.5, 50.95]\n",
+        ));
+        let cfg = Config::load(&path).unwrap();
+        let Ingestor::GtfsGeneric(c) = &cfg.build.inputs[0] else {
+            panic!("expected gtfs/generic");
+        };
+        let f = c.feed_filter();
+        assert_eq!(f.route_types, Some(vec![RouteType::Tramway, RouteType::Subway]));
+        assert!(f.exclude_route_types.is_empty());
+        assert_eq!(f.agencies, Some(vec!["TEC".to_string()]));
+        assert_eq!(f.bbox, Some([4.2, 50.7, 4.5, 50.95]));
+
+        let (_p, path) = write_config(&yaml("        exclude_route_types: [hovercraft]\n"));
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.contains("hovercraft"), "got: {err}");
+        let (_p, path) = write_config(&yaml("        bbox: [4.5, 50.7, 4.2, 50.95]\n"));
+        assert!(Config::load(&path).unwrap_err().contains("filter.bbox"));
+    }
+
+    #[test]
+    fn gtfs_service_day_cutover_parses_and_rejects_garbage() {
+        let yaml = |cutover: &str| {
+            format!(
+                "build:\n  inputs:\n    - ingestor: gtfs/sncb\n      name: sncb\n      url: \"path:data/sncb.zip\"\n      osm_url: \"path:data/x.pbf\"\n      service_day_cutover: \"{cutover}\"\ndefault_routing: {{}}\n"
+            )
+        };
+        let (_p, path) = write_config(&yaml("03:30"));
+        let cfg = Config::load(&path).unwrap();
+        assert_eq!(cfg.build.inputs[0].service_day_cutover(), Some(3 * 3600 + 1800));
+
+        for bad in ["3h", "18:00"] {
+            let (_p, path) = write_config(&yaml(bad));
+            let err = Config::load(&path).unwrap_err();
+            assert!(err.contains("service_day_cutover"), "got: {err}");
+        }
+    }
+
+    #[test]
+    fn empty_config_takes_defaults() {
+        let cfg = Config::parse("{}").unwrap();
+        assert!(cfg.build.inputs.is_empty());
+        assert_eq!(cfg.build.output, "graph.bin");
+        assert_eq!(cfg.log_level, "info");
+        assert!(cfg.warnings.iter().any(|w| w.contains("build.inputs is empty")));
+    }
+
+    #[test]
+    fn unknown_keys_become_warnings() {
+        let yaml = r#"
+build:
+  inputs: []
+  outptu: x.bin
+default_routing:
+  walking_sped_mps: 1.4
+"#;
+        let cfg = Config::parse(yaml).unwrap();
+        let joined = cfg.warnings.join("\n");
+        assert!(joined.contains("build.outptu"), "got: {joined}");
+        assert!(joined.contains("default_routing.walking_sped_mps"), "got: {joined}");
+        assert_eq!(cfg.build.output, "graph.bin");
+    }
+
+    #[test]
+    fn federation_is_parsed_but_not_servable() {
+        assert!(Config::parse("{}").unwrap().check_servable().is_ok());
+        let yaml = r#"
+federation:
+  shards:
+    - name: north
+      graph: north.bin
+"#;
+        let cfg = Config::parse(yaml).unwrap();
+        assert!(cfg.warnings.iter().all(|w| !w.contains("federation")), "{:?}", cfg.warnings);
+        let err = cfg.check_servable().unwrap_err();
+        assert!(err.contains("not supported by the server"), "got: {err}");
+    }
+
+    #[test]
+    fn bad_url_scheme_reports_line_and_column() {
+        let yaml = r#"
+build:
+  inputs:
+    - ingestor: gtfs/generic
+      name: a
+      url: "path:a.zip"
+    - ingestor: gtfs/generic
+      name: b
+      url: "ftp://example.org/b.zip"
+"#;
+        let (_p, path) = write_config(yaml);
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.contains("build.inputs[1]"), "got: {err}");
+        assert!(err.contains("line 9 column 13"), "got: {err}");
+    }
+
+    #[test]
+    fn type_errors_carry_serde_position() {
+        let err = Config::parse("default_routing:\n  walking_speed_mps: fast\n").unwrap_err();
+        assert!(err.contains("line 2"), "got: {err}");
+    }
+
+    #[test]
+    fn shipped_configs_have_no_unknown_keys() {
+        for path in ["config.yaml", "presets/belgium.yaml"] {
+            let cfg = Config::load(path).unwrap();
+            assert!(cfg.warnings.is_empty(), "{path}: {:?}", cfg.warnings);
+        }
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn env_shortcuts_and_generic_keys_override_the_file() {
+        let yaml = "build:\n  output: a.bin\ndefault_routing:\n  walking_speed_mps: 1.2\n";
+        let overrides = ConfigOverride::from_env(vars(&[
+            ("MAAS_GRAPH", "/data/graph.bin"),
+            ("MAAS_LOG_LEVEL", "debug"),
+            ("MAAS_CACHE_DIR", ""),
+            ("MAAS_CONFIG__DEFAULT_ROUTING__WALKING_SPEED_MPS", "1.5"),
+            ("MAAS_CONFIG__SERVER__PLAN_CACHE__CAPACITY", "16"),
+            ("PATH", "/usr/bin"),
+        ]));
+        assert_eq!(overrides.len(), 4, "empty MAAS_CACHE_DIR is skipped");
+        let cfg = Config::parse_overridden(yaml, &overrides).unwrap();
+        assert_eq!(cfg.build.output, "/data/graph.bin");
+        assert_eq!(cfg.build.cache_dir, None);
+        assert_eq!(cfg.log_level, "debug");
+        assert_eq!(cfg.default_routing.walking_speed_mps, Some(1.5));
+        assert_eq!(cfg.server.plan_cache.capacity, 16);
+    }
+
+    #[test]
+    fn flags_win_over_env() {
+        let mut overrides = ConfigOverride::from_env(vars(&[("MAAS_PORT", "9000")]));
+        let args: Vec<String> = ["maas-rs", "--serve", "--port=9100", "--set", "server.host=0.0.0.0"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        overrides.extend(ConfigOverride::from_args(&args).unwrap());
+        let cfg = Config::parse_overridden("{}", &overrides).unwrap();
+        assert_eq!(cfg.server.port, 9100);
+        assert_eq!(cfg.server.host, "0.0.0.0");
+    }
+
+    #[test]
+    fn override_indexes_into_inputs_and_reports_bad_values() {
+        let yaml = r#"
+build:
+  inputs:
+    - ingestor: gtfs/generic
+      name: bus
+      url: "path:bus.zip"
+"#;
+        let o = ConfigOverride::from_env(vars(&[(
+            "MAAS_CONFIG__BUILD__INPUTS__0__URL",
+            "https://example.org/bus.zip",
+        )]));
+        let cfg = Config::parse_overridden(yaml, &o).unwrap();
+        assert_eq!(cfg.build.inputs[0].url(), "https://example.org/bus.zip");
+
+        let bad = ConfigOverride::from_env(vars(&[("MAAS_PORT", "eighty")]));
+        let err = Config::parse_overridden(yaml, &bad).unwrap_err();
+        assert!(err.contains("MAAS_PORT"), "got: {err}");
+        let args = vec!["--set".to_string(), "build.inputs.3.url=x".to_string()];
+        let err = Config::parse_overridden(yaml, &ConfigOverride::from_args(&args).unwrap())
+            .unwrap_err();
+        assert!(err.contains("out of range"), "got: {err}");
+    }
+
+    #[test]
+    fn numeric_admin_token_stays_a_string() {
+        let o = ConfigOverride::from_env(vars(&[("MAAS_ADMIN_TOKEN", "12345")]));
+        let cfg = Config::parse_overridden("{}", &o).unwrap();
+        assert_eq!(cfg.server.admin_token.as_deref(), Some("12345"));
+    }
 }
@@ -102,7 +102,7 @@ impl KnownEurosEpsilon {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
 pub enum PassengerCategory {
     #[default]
     Adult,
@@ -118,7 +118,7 @@ impl PassengerCategory {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
 pub enum TravelClass {
     #[default]
     Second,
@@ -384,6 +384,9 @@ pub enum OperatorModel {
         rules: SncbTimeRules,
         airport_od_cents: u32,
     },
+    /// One ticket per boarding at `cents`, for an operator priced only by a
+    /// `route_overrides` flat fare.
+    Flat { cents: u32 },
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -580,6 +583,11 @@ impl FareModel {
                         price.sncb_base_credit.max((base_cents as f64 * scale).round() as u32);
                 }
             }
+            OperatorFareId::Modeled { model: OperatorModel::Flat { cents } } => {
+                let bucketed = self.known_euros_epsilon.bucket(cents);
+                price.known_cents = price.known_cents.saturating_add(bucketed);
+                price.end_sncb_run();
+            }
             OperatorFareId::Unknown { slot } => {
                 let s = slot.min(N_OP - 1);
                 price.unknown[s] = price.unknown[s].saturating_add(1);
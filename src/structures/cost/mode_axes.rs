@@ -1,5 +1,5 @@
 use crate::structures::cost::{Axis, CostVector, VarianceModel, edge_time_penalty, edge_variance};
-use crate::structures::{BikeCost, BikeProfile, HighwayClass, StreetEdgeData};
+use crate::structures::{BikeCost, BikeProfile, HighwayClass, Meters, Speed, StreetEdgeData};
 
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
@@ -7,6 +7,11 @@ pub struct CostWeights {
     pub surface_paved: f64,
     pub surface_unknown: f64,
     pub surface_unpaved: f64,
+    /// Walk Surface-axis multiplier on a street tagged without sidewalk.
+    pub walk_no_sidewalk: f64,
+    /// Walk Surface-axis multiplier on an edge not tagged lit, at night safety 1; scaled
+    /// down linearly to 1 at night safety 0.
+    pub walk_unlit: f64,
 }
 
 impl Default for CostWeights {
@@ -15,6 +20,8 @@ impl Default for CostWeights {
             surface_paved: 1.0,
             surface_unknown: 1.3,
             surface_unpaved: 2.5,
+            walk_no_sidewalk: 1.5,
+            walk_unlit: 3.0,
         }
     }
 }
@@ -53,7 +60,7 @@ impl BalanceWeights {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum RoutingMode {
     Walk,
     Bike,
@@ -119,11 +126,7 @@ fn dplus(e: &StreetEdgeData) -> f64 {
 }
 
 fn street_secs(length: usize, speed_mps: f64) -> f64 {
-    let speed_mms = (speed_mps * 1000.0) as u32;
-    if speed_mms == 0 {
-        return 0.0;
-    }
-    (length as u64 * 1000 / speed_mms as u64) as f64
+    Speed::from_mps(speed_mps).travel_time(Meters(length)).0 as f64
 }
 
 fn bike_vector(
@@ -168,6 +171,15 @@ fn surface_factor(e: &StreetEdgeData, w: &CostWeights) -> f64 {
     }
 }
 
+/// Walking comfort on top of the surface: no sidewalk, and unlit when the query asks
+/// for night safety.
+fn walk_comfort(e: &StreetEdgeData, w: &CostWeights) -> f64 {
+    let sidewalk = if e.attrs.no_sidewalk { w.walk_no_sidewalk } else { 1.0 };
+    let night = crate::structures::query_profile::night_safety();
+    let lighting = if e.attrs.lit { 1.0 } else { 1.0 + night * (w.walk_unlit - 1.0) };
+    sidewalk * lighting
+}
+
 fn walk_vector(
     e: &StreetEdgeData,
     weights: &CostWeights,
@@ -184,7 +196,7 @@ fn walk_vector(
         street_secs(e.length, speed_mps) + edge_time_penalty(e, model),
     );
     cv.set(Axis::Dplus, dplus(e));
-    cv.set(Axis::Surface, len * surface_factor(e, weights));
+    cv.set(Axis::Surface, len * surface_factor(e, weights) * walk_comfort(e, weights));
     cv.set(
         Axis::Variance,
         edge_variance(RoutingMode::Walk, e, model, None, (0.0, 0.0)),
@@ -832,4 +844,51 @@ mod tests {
             "bike Time stays kinematic and > 0"
         );
     }
+
+    #[test]
+    fn walk_comfort_penalises_missing_sidewalk_and_unlit_at_night() {
+        use crate::structures::{
+            BikeAttrs, BikeProfile, NodeID, RoutingProfile, StreetEdgeData, Surface, query_profile,
+            cost::{CostWeights, edge_cost_vector},
+        };
+        let edge = |no_sidewalk: bool, lit: bool| {
+            let mut attrs = BikeAttrs::road_default();
+            attrs.surface = Surface::Paved;
+            attrs.no_sidewalk = no_sidewalk;
+            attrs.lit = lit;
+            StreetEdgeData {
+                origin: NodeID(0),
+                destination: NodeID(1),
+                partial: false,
+                length: 100,
+                foot: true,
+                bike: false,
+                car: true,
+                attrs,
+                elev_delta: 0,
+                surface_speed: 100,
+                var_gen: crate::structures::cost::VarGen::NONE,
+            }
+        };
+        let surface = |e: &StreetEdgeData| {
+            let cv = edge_cost_vector(
+                RoutingMode::Walk,
+                e,
+                &BikeProfile::default(),
+                &CostWeights::default(),
+                &VarianceModel::default(),
+                1.4,
+                None,
+                (1.0, 0.0),
+            )
+            .unwrap();
+            (cv.get(Axis::Surface), cv.get(Axis::Time))
+        };
+        assert_eq!(surface(&edge(false, false)).0, 100.0, "lighting ignored by day");
+        assert_eq!(surface(&edge(true, true)).0, 150.0);
+        let _night = query_profile::begin(&RoutingProfile { night_safety: Some(0.5), ..Default::default() });
+        assert_eq!(surface(&edge(false, false)).0, 200.0);
+        assert_eq!(surface(&edge(false, true)).0, 100.0);
+        assert_eq!(surface(&edge(false, false)).1, surface(&edge(false, true)).1, "time unaffected");
+    }
 }
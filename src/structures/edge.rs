@@ -11,18 +11,31 @@ pub enum EdgeData {
     Transit(TransitEdgeData),
 }
 
-/// Classified from the OSM way tag, NOT derived from node levels.
+/// Classified from the OSM way tag (or an adjacent `highway=elevator` node), NOT
+/// derived from node levels.
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Connector {
     Steps,
     Elevator,
     Ramp,
+    /// `highway=steps` + `conveying=*`: moving stairs, not step-free.
+    Escalator,
+}
+
+impl Connector {
+    /// Usable without climbing steps (wheelchair, pram) while in service.
+    pub fn step_free(self) -> bool {
+        matches!(self, Connector::Elevator | Connector::Ramp)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct StreetEdgeData {
     pub origin: NodeID,
     pub destination: NodeID,
+    /// Synthetic connector rather than a mapped street: a GTFS stop ↔ street link, a
+    /// platform fallback, a stop ↔ stop transfer edge, or the query-time stub of a
+    /// split edge. Its geometry is a straight line.
     pub partial: bool,
     pub length: usize,
     pub foot: bool,
@@ -37,6 +50,20 @@ pub struct StreetEdgeData {
     pub var_gen: crate::structures::cost::VarGen,
 }
 
+impl StreetEdgeData {
+    pub fn length_m(&self) -> crate::structures::Meters {
+        crate::structures::Meters(self.length)
+    }
+
+    /// A synthetic link that covers ground. Two of these back to back (stop → node →
+    /// stop, or a platform fallback into a stop link) would be a straight-line
+    /// shortcut with no mapped street in between, so the foot search never chains
+    /// them; a zero-length link (stop placed on its platform node) is exempt.
+    pub fn is_spanning_link(&self) -> bool {
+        self.partial && self.length > 0
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct TransitEdgeData {
     pub origin: NodeID,
@@ -266,7 +266,7 @@ impl BikeCost {
     }
 
     /// `0` means unset (old cache / non-bike edge) and resolves to the unknown default.
-    fn surface_factor(e: &StreetEdgeData) -> f64 {
+    pub(super) fn surface_factor(e: &StreetEdgeData) -> f64 {
         if e.surface_speed == 0 {
             crate::structures::UNKNOWN_SURFACE_FACTOR
         } else {
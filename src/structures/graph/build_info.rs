@@ -0,0 +1,89 @@
+//! Provenance of a built graph: which inputs it was built from, in what state, by
+//! which version, so clients and monitoring can tell how fresh the served data is.
+//! Street-phase sources travel in `osm.bin`, so a transit-only rebuild keeps them.
+
+use serde::{Deserialize, Serialize};
+
+use super::Graph;
+
+/// Recorded while building; empty on graphs assembled in code.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// `CARGO_PKG_VERSION` of the binary that built the graph.
+    pub crate_version: String,
+    /// Unix seconds at which the build finished; `0` when never finished.
+    pub built_at: i64,
+    /// Wall time of the transit phase and finalisation. Street sources report their own
+    /// `load_secs`, as `osm.bin` is usually reused.
+    pub build_secs: f64,
+    /// Inputs in ingestion order, street phase first.
+    pub sources: Vec<SourceInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceInfo {
+    pub label: String,
+    /// Config `ingestor` tag (`osm/pbf`, `gtfs/generic`, `dem/belgian-lambert-2008`, …).
+    pub kind: String,
+    /// Configured URL without its query string (which may hold a key), or local path.
+    pub location: String,
+    /// SHA-256 (hex) of the file read; `None` for a directory feed.
+    pub sha256: Option<String>,
+    pub load_secs: f64,
+    /// OSM header replication timestamp (unix seconds), when the extract carries one.
+    pub osm_timestamp: Option<i64>,
+    /// GTFS `feed_info.txt`, when the feed ships one.
+    pub feed_publisher: Option<String>,
+    pub feed_version: Option<String>,
+    /// `feed_start_date` / `feed_end_date` as `YYYY-MM-DD`.
+    pub feed_start_date: Option<String>,
+    pub feed_end_date: Option<String>,
+}
+
+/// `url` without query string or fragment.
+pub fn redact_location(url: &str) -> String {
+    url.split(['?', '#']).next().unwrap_or(url).to_string()
+}
+
+impl Graph {
+    pub fn build_info(&self) -> &BuildInfo {
+        &self.build_info
+    }
+
+    pub fn record_source(&mut self, source: SourceInfo) {
+        self.build_info.sources.push(source);
+    }
+
+    /// Stamp the finished build; `build_secs` covers the transit phase onwards.
+    pub fn finish_build_info(&mut self, built_at: i64, build_secs: f64) {
+        self.build_info.crate_version = env!("CARGO_PKG_VERSION").to_string();
+        self.build_info.built_at = built_at;
+        self.build_info.build_secs = build_secs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations_lose_their_query_string() {
+        assert_eq!(redact_location("https://x.be/gtfs.zip?key=secret"), "https://x.be/gtfs.zip");
+        assert_eq!(redact_location("https://x.be/a.pbf#frag"), "https://x.be/a.pbf");
+        assert_eq!(redact_location("data/belgium.pbf"), "data/belgium.pbf");
+    }
+
+    #[test]
+    fn build_info_survives_the_osm_view() {
+        let mut g = Graph::new();
+        g.record_source(SourceInfo {
+            label: "osm/pbf".into(),
+            kind: "osm/pbf".into(),
+            location: "data/be.pbf".into(),
+            osm_timestamp: Some(1_700_000_000),
+            ..Default::default()
+        });
+        let back = Graph::from_osm_postcard(&g.to_osm_postcard().unwrap()).unwrap();
+        assert_eq!(back.build_info().sources, g.build_info().sources);
+    }
+}
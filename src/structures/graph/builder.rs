@@ -0,0 +1,355 @@
+//! Programmatic graph construction for tests and embedders. [`GraphBuilder`] takes
+//! stops, streets and trips with plain stop times and does the bookkeeping the GTFS
+//! ingester otherwise does: per-hop `TimetableSegment`s over `transit_departures`,
+//! pattern grouping and offsets, then the RAPTOR index, per-stop elevator access and
+//! street contraction.
+
+use std::collections::BTreeMap;
+
+use gtfs_structures::{Availability, RouteType};
+
+use crate::{
+    ingestion::gtfs::{
+        AgencyId, AgencyInfo, RouteId, RouteInfo, ServiceId, ServicePattern, StopTime,
+        TimetableSegment, TripId, TripInfo, TripSegment, foot_connector_edge,
+    },
+    structures::{
+        BikeAttrs, Connector, EdgeData, Graph, HighwayClass, LatLng, NodeData, NodeID, OsmNodeData, StreetEdgeData,
+        TransitEdgeData, TransitStopData,
+        cost::VarGen,
+        raptor::{Lookup, PatternInfo},
+    },
+};
+
+/// Service running every day of the calendar; trips added with
+/// [`GraphBuilder::add_trip`] use it.
+pub const EVERY_DAY: ServiceId = ServiceId(0);
+
+struct PendingTrip {
+    route: RouteId,
+    service: ServiceId,
+    stops: Vec<NodeID>,
+    times: Vec<StopTime>,
+}
+
+pub struct GraphBuilder {
+    g: Graph,
+    routes: Vec<RouteInfo>,
+    services: Vec<ServicePattern>,
+    trips: Vec<PendingTrip>,
+    contract: bool,
+}
+
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self {
+            g: Graph::new(),
+            routes: Vec::new(),
+            services: vec![ServicePattern {
+                days_of_week: 0x7F,
+                start_date: 0,
+                end_date: 9999,
+                added_dates: vec![],
+                removed_dates: vec![],
+            }],
+            trips: Vec::new(),
+            contract: true,
+        }
+    }
+
+    /// Skip building the contracted street graph (bike/CCH access then run on raw edges).
+    pub fn without_contraction(mut self) -> Self {
+        self.contract = false;
+        self
+    }
+
+    pub fn add_street_node(&mut self, eid: &str, lat: f64, lng: f64) -> NodeID {
+        self.g.add_node(NodeData::OsmNode(OsmNodeData {
+            eid: eid.to_string(),
+            lat_lng: LatLng { latitude: lat, longitude: lng },
+        }))
+    }
+
+    /// A wheelchair-accessible stop whose GTFS `stop_id` is its name.
+    pub fn add_stop(&mut self, name: &str, lat: f64, lng: f64) -> NodeID {
+        self.g.add_node(NodeData::TransitStop(TransitStopData {
+            name: name.to_string(),
+            lat_lng: LatLng { latitude: lat, longitude: lng },
+            accessibility: Availability::Available,
+            id: name.to_string(),
+            platform_code: None,
+            code: None,
+            parent_station: None,
+        }))
+    }
+
+    /// Two-way street open to walking, cycling and driving.
+    pub fn add_street(&mut self, a: NodeID, b: NodeID, length_m: usize) {
+        for (from, to) in [(a, b), (b, a)] {
+            self.add_street_edge(StreetEdgeData {
+                origin: from,
+                destination: to,
+                length: length_m,
+                partial: false,
+                foot: true,
+                bike: true,
+                car: true,
+                attrs: BikeAttrs::road_default(),
+                elev_delta: 0,
+                surface_speed: 100,
+                var_gen: VarGen::NONE,
+            });
+        }
+    }
+
+    /// One directed street edge with caller-chosen access and attributes.
+    pub fn add_street_edge(&mut self, edge: StreetEdgeData) {
+        self.g.add_edge(edge.origin, EdgeData::Street(edge));
+    }
+
+    /// Two-way foot connector between a stop and the street network.
+    pub fn link_stop(&mut self, stop: NodeID, street: NodeID, length_m: usize) {
+        self.g.add_edge(stop, foot_connector_edge(stop, street, length_m));
+        self.g.add_edge(street, foot_connector_edge(street, stop, length_m));
+    }
+
+    /// Two-way foot-only vertical connector; `elevator` names an [`Connector::Elevator`]
+    /// for outage lists. Steps and escalators are tagged `highway=steps` like OSM ones.
+    pub fn add_connector(
+        &mut self,
+        a: NodeID,
+        b: NodeID,
+        length_m: usize,
+        kind: Connector,
+        elevator: Option<&str>,
+    ) {
+        let attrs = match kind {
+            Connector::Steps | Connector::Escalator => BikeAttrs {
+                highway: HighwayClass::Steps,
+                escalator: kind == Connector::Escalator,
+                ..BikeAttrs::road_default()
+            },
+            Connector::Elevator | Connector::Ramp => BikeAttrs::road_default(),
+        };
+        for (from, to) in [(a, b), (b, a)] {
+            self.add_street_edge(StreetEdgeData {
+                origin: from,
+                destination: to,
+                length: length_m,
+                partial: false,
+                foot: true,
+                bike: false,
+                car: false,
+                attrs,
+                elev_delta: 0,
+                surface_speed: 100,
+                var_gen: VarGen::NONE,
+            });
+            self.g.connector_edges.insert((from, to), kind);
+            if let Some(id) = elevator {
+                self.g.elevator_edges.insert((from, to), id.to_string());
+            }
+        }
+    }
+
+    /// OSM `level` of a street node (unset reads as ground level).
+    pub fn set_level(&mut self, node: NodeID, level: i16) {
+        self.g.set_node_level(node, level);
+    }
+
+    pub fn add_agency(&mut self, name: &str) -> AgencyId {
+        let id = AgencyId(self.g.raptor.transit_agencies.len() as u16);
+        self.g.add_transit_agencies(vec![AgencyInfo {
+            name: name.to_string(),
+            url: String::new(),
+            timezone: String::new(),
+        }]);
+        id
+    }
+
+    /// Route under agency 0 (call [`Self::add_agency`] first if the plan should name it).
+    pub fn add_route(&mut self, short_name: &str, long_name: &str, route_type: RouteType) -> RouteId {
+        self.routes.push(RouteInfo {
+            route_short_name: short_name.to_string(),
+            route_long_name: long_name.to_string(),
+            route_type,
+            agency_id: AgencyId(0),
+            route_color: None,
+            route_text_color: None,
+        });
+        RouteId((self.routes.len() - 1) as u32)
+    }
+
+    pub fn add_service(&mut self, service: ServicePattern) -> ServiceId {
+        self.services.push(service);
+        ServiceId((self.services.len() - 1) as u32)
+    }
+
+    /// Trip on [`EVERY_DAY`]; `stops` is `(stop, arrival, departure)` in seconds since
+    /// service-day midnight, in travel order. Its GTFS `trip_id` is `T<index>`.
+    pub fn add_trip(&mut self, route: RouteId, stops: &[(NodeID, u32, u32)]) -> TripId {
+        self.add_trip_on(EVERY_DAY, route, stops)
+    }
+
+    pub fn add_trip_on(
+        &mut self,
+        service: ServiceId,
+        route: RouteId,
+        stops: &[(NodeID, u32, u32)],
+    ) -> TripId {
+        assert!(stops.len() >= 2, "a trip needs at least two stops");
+        assert!((route.0 as usize) < self.routes.len(), "unknown route {route:?}");
+        assert!((service.0 as usize) < self.services.len(), "unknown service {service:?}");
+        self.trips.push(PendingTrip {
+            route,
+            service,
+            stops: stops.iter().map(|&(s, _, _)| s).collect(),
+            times: stops
+                .iter()
+                .map(|&(_, arrival, departure)| StopTime { arrival, departure, ..Default::default() })
+                .collect(),
+        });
+        TripId((self.trips.len() - 1) as u32)
+    }
+
+    /// Lay out timetables and patterns, then build the RAPTOR index and (unless
+    /// disabled) the contracted street graph.
+    pub fn build(mut self) -> Graph {
+        let g = &mut self.g;
+
+        // (from, to, route) → departures; BTreeMap keeps edge order stable.
+        let mut hops: BTreeMap<(NodeID, NodeID, u32), Vec<TripSegment>> = BTreeMap::new();
+        // (route, stop sequence) → trips, in first-seen order.
+        let mut patterns: Vec<(RouteId, Vec<NodeID>, Vec<usize>)> = Vec::new();
+        for (t, trip) in self.trips.iter().enumerate() {
+            for i in 0..trip.stops.len() - 1 {
+                hops.entry((trip.stops[i], trip.stops[i + 1], trip.route.0))
+                    .or_default()
+                    .push(TripSegment {
+                        trip_id: TripId(t as u32),
+                        departure: trip.times[i].departure,
+                        arrival: trip.times[i + 1].arrival,
+                        service_id: trip.service,
+                    });
+            }
+            match patterns
+                .iter_mut()
+                .find(|(r, seq, _)| *r == trip.route && *seq == trip.stops)
+            {
+                Some((_, _, members)) => members.push(t),
+                None => patterns.push((trip.route, trip.stops.clone(), vec![t])),
+            }
+        }
+
+        for (route, sequence, mut members) in patterns {
+            members.sort_by_key(|&t| self.trips[t].times[0].departure);
+            g.push_transit_pattern(PatternInfo { route, num_trips: members.len() as u32 });
+
+            let ps = g.transit_pattern_stops_len();
+            g.extend_transit_pattern_stops(&sequence);
+            g.push_transit_idx_pattern_stops(Lookup { start: ps, len: sequence.len() });
+
+            let pt = g.transit_pattern_trips_len();
+            for &t in &members {
+                g.push_transit_pattern_trip(TripId(t as u32));
+            }
+            g.push_transit_idx_pattern_trips(Lookup { start: pt, len: members.len() });
+
+            let st = g.transit_pattern_stop_times_len();
+            for stop_idx in 0..sequence.len() {
+                for &t in &members {
+                    g.push_transit_pattern_stop_time(self.trips[t].times[stop_idx]);
+                }
+            }
+            g.push_transit_idx_pattern_stop_times(Lookup {
+                start: st,
+                len: sequence.len() * members.len(),
+            });
+        }
+
+        for ((from, to, route), mut segments) in hops {
+            segments.sort_by_key(|s| s.departure);
+            let timetable_segment = TimetableSegment {
+                start: g.get_transit_departures_size() as u32,
+                len: segments.len() as u32,
+            };
+            g.add_transit_departures(segments);
+            let length = g.nodes_distance(from, to);
+            g.add_edge(
+                from,
+                EdgeData::Transit(TransitEdgeData {
+                    origin: from,
+                    destination: to,
+                    route_id: RouteId(route),
+                    timetable_segment,
+                    length,
+                }),
+            );
+        }
+
+        g.add_transit_services(self.services);
+        g.add_transit_routes(self.routes);
+        g.add_transit_trips(
+            self.trips
+                .iter()
+                .map(|t| TripInfo {
+                    trip_headsign: None,
+                    route_id: t.route,
+                    service_id: t.service,
+                    bikes_allowed: None,
+                })
+                .collect(),
+        );
+        g.add_transit_trip_ids((0..self.trips.len()).map(|t| format!("T{t}")).collect());
+
+        g.build_raptor_index();
+        g.build_stop_elevator_access();
+        if self.contract {
+            let mut cg = crate::structures::contraction::ContractedGraph::from_graph_union(g);
+            cg.build_seg_index();
+            g.contracted = Some(cg);
+            g.bake_bike_on_contracted_default();
+        }
+        g.compute_estimator_factors();
+        self.g
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trips_share_hop_timetables_and_patterns() {
+        let mut b = GraphBuilder::new();
+        let s1 = b.add_stop("S1", 50.0, 4.00);
+        let s2 = b.add_stop("S2", 50.0, 4.01);
+        let s3 = b.add_stop("S3", 50.0, 4.02);
+        let r = b.add_route("1", "Line 1", RouteType::Bus);
+        // Added out of departure order: the builder sorts.
+        b.add_trip(r, &[(s1, 600, 600), (s2, 900, 900), (s3, 1200, 1200)]);
+        b.add_trip(r, &[(s1, 0, 0), (s2, 300, 300), (s3, 600, 600)]);
+        b.add_trip(r, &[(s2, 1000, 1000), (s3, 1300, 1300)]);
+        let g = b.without_contraction().build();
+
+        let hop = g
+            .out_edges(s2)
+            .iter()
+            .find_map(|e| match e {
+                EdgeData::Transit(t) if t.destination == s3 => Some(t.timetable_segment),
+                _ => None,
+            })
+            .expect("S2→S3 edge");
+        assert_eq!(hop.len, 3, "every trip serves S2→S3");
+        let deps = &g.raptor.transit_departures.departures[hop.range()];
+        assert_eq!(deps, vec![300, 900, 1000]);
+        assert_eq!(g.raptor.transit_patterns.len(), 2, "S1-S2-S3 and S2-S3");
+        assert_eq!(g.raptor.transit_patterns[0].num_trips, 2);
+    }
+}
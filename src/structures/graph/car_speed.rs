@@ -0,0 +1,143 @@
+//! Free-flow car speeds per OSM highway class (`default_routing.car_speeds_kmh`). A car
+//! edge whose class is listed is driven at that speed, any other at `driving_speed_mps`;
+//! the A* car bound follows the fastest class present in the network.
+
+use crate::structures::cost::RoutingMode;
+use crate::structures::{HighwayClass, StreetEdgeData};
+
+use super::Graph;
+
+const CLASSES: usize = HighwayClass::Other as usize + 1;
+
+/// m/s per [`HighwayClass`]; `None` = the nominal `driving_speed_mps`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CarSpeeds([Option<f64>; CLASSES]);
+
+impl CarSpeeds {
+    pub fn set(&mut self, class: HighwayClass, mps: f64) {
+        self.0[class as usize] = Some(mps);
+    }
+
+    pub fn get(&self, class: HighwayClass) -> Option<f64> {
+        self.0[class as usize]
+    }
+}
+
+impl Graph {
+    /// Drive edges of `class` at `kmh`; non-positive speeds are ignored.
+    pub fn set_car_speed_kmh(&mut self, class: HighwayClass, kmh: f64) {
+        if kmh > 0.0 {
+            self.raptor.car_speeds.set(class, kmh / 3.6);
+        }
+    }
+
+    /// Free-flow speed (m/s) a car drives `street` at.
+    #[inline]
+    pub fn car_speed_mps(&self, street: &StreetEdgeData) -> f64 {
+        self.raptor
+            .car_speeds
+            .get(street.attrs.highway)
+            .unwrap_or(self.raptor.driving_speed_mps)
+    }
+
+    /// `nominal`, or the edge's class speed for a drivable edge under `Drive`.
+    #[inline]
+    pub(super) fn street_speed(&self, mode: RoutingMode, street: &StreetEdgeData, nominal: f64) -> f64 {
+        if mode == RoutingMode::Drive && street.car {
+            self.car_speed_mps(street)
+        } else {
+            nominal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::cost::VarGen;
+    use crate::structures::{BikeAttrs, BikeProfile, EdgeData, LatLng, NodeData, OsmNodeData};
+
+    fn two_edge_graph() -> Graph {
+        let mut g = Graph::new();
+        let node = |eid: &str, lon| {
+            NodeData::OsmNode(OsmNodeData {
+                eid: eid.into(),
+                lat_lng: LatLng { latitude: 50.0, longitude: lon },
+            })
+        };
+        let a = g.add_node(node("a", 4.0));
+        let b = g.add_node(node("b", 4.01));
+        for (from, to, highway) in [(a, b, HighwayClass::Motorway), (b, a, HighwayClass::Residential)] {
+            g.add_edge(from, EdgeData::Street(StreetEdgeData {
+                origin: from,
+                destination: to,
+                length: 720,
+                partial: false,
+                foot: false,
+                bike: false,
+                car: true,
+                attrs: BikeAttrs { highway, ..BikeAttrs::road_default() },
+                elev_delta: 0,
+                surface_speed: 0,
+                var_gen: VarGen::NONE,
+            }));
+        }
+        g
+    }
+
+    fn street(g: &Graph, from: usize) -> StreetEdgeData {
+        match &g.edges[from][0] {
+            EdgeData::Street(s) => *s,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn listed_classes_drive_at_their_speed_others_at_nominal() {
+        let mut g = two_edge_graph();
+        g.set_car_speed_kmh(HighwayClass::Motorway, 120.0);
+        g.set_car_speed_kmh(HighwayClass::Service, 0.0);
+        let (motorway, residential) = (street(&g, 0), street(&g, 1));
+        assert!((g.car_speed_mps(&motorway) - 120.0 / 3.6).abs() < 1e-9);
+        assert_eq!(g.car_speed_mps(&residential), g.raptor.driving_speed_mps);
+        assert_eq!(g.raptor.car_speeds.get(HighwayClass::Service), None);
+        assert_eq!(g.edge_secs(&motorway, crate::structures::StreetProfile::Car), Some(21));
+        assert_eq!(g.street_speed(RoutingMode::Walk, &motorway, 1.3), 1.3);
+        assert_eq!(HighwayClass::from_osm_tag("motorway"), Some(HighwayClass::Motorway));
+        assert_eq!(HighwayClass::from_osm_tag("busway"), None);
+    }
+
+    #[test]
+    fn car_search_slows_down_inside_a_rush_hour() {
+        use super::super::rush_hour::{self, RushHourWindow};
+        use crate::structures::{NodeID, StreetProfile};
+        let mut g = two_edge_graph();
+        g.set_car_speed_kmh(HighwayClass::Motorway, 120.0);
+        let mut w = RushHourWindow::new(8 * 3600, 9 * 3600);
+        w.set(HighwayClass::Motorway, 3.0);
+        g.set_car_rush_hours(vec![w]);
+        g.raptor.transit_node_to_stop = vec![u32::MAX; 2];
+        let drive = |g: &Graph| g.street_dijkstra(NodeID(0), u32::MAX, StreetProfile::Car)[&NodeID(1)];
+        assert_eq!(drive(&g), 21, "no departure clock: free flow");
+        let _clock = rush_hour::begin(Some(8 * 3600));
+        assert_eq!(drive(&g), 63);
+        let _clock = rush_hour::begin(Some(7 * 3600));
+        assert_eq!(drive(&g), 21);
+    }
+
+    #[test]
+    fn car_bound_follows_the_fastest_class_present() {
+        let mut g = two_edge_graph();
+        g.compute_estimator_factors();
+        let bike = BikeProfile::default();
+        assert_eq!(g.estimator_speed(RoutingMode::Drive, &bike), g.raptor.driving_speed_mps);
+
+        g.set_car_speed_kmh(HighwayClass::Motorway, 120.0);
+        g.set_car_speed_kmh(HighwayClass::Residential, 30.0);
+        g.set_car_speed_kmh(HighwayClass::Trunk, 200.0); // absent from the network
+        g.compute_estimator_factors();
+        let bound = g.estimator_speed(RoutingMode::Drive, &bike);
+        assert!((bound - 120.0 / 3.6).abs() < 1e-9, "{bound}");
+        assert!(g.set_estimator_speed(RoutingMode::Drive, 20.0).is_err(), "below the motorway speed");
+    }
+}
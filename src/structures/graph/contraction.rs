@@ -5,7 +5,7 @@ use super::bike_cost::{BikeCost, PrevCtx};
 use super::raptor_access::StreetProfile;
 use super::{EdgeData, Endpoint, Graph};
 use crate::structures::cost::{Axis, CostVector, RoutingMode};
-use crate::structures::{NodeID, StreetEdgeData};
+use crate::structures::{Meters, NodeID, Speed, StreetEdgeData};
 
 impl BakedCost {
     pub fn traverse(&self, entry: Option<PrevCtx>, bike: &BikeCost) -> (CostVector, PrevCtx) {
@@ -360,7 +360,7 @@ impl ContractedGraph {
     }
 
     #[inline]
-    fn seg_slice(&self, se: &SuperEdge) -> &[Seg] {
+    pub(super) fn seg_slice(&self, se: &SuperEdge) -> &[Seg] {
         &self.segs[se.seg_start as usize..(se.seg_start + se.seg_len) as usize]
     }
 
@@ -369,6 +369,12 @@ impl ContractedGraph {
         self.seg_slice(se).iter().map(|s| s.far).collect()
     }
 
+    /// Accumulated street length (m) of `se`: the sum over its contracted chain, not the
+    /// crow-flies junction-to-junction distance.
+    pub fn super_edge_length(&self, se: &SuperEdge) -> usize {
+        self.seg_slice(se).iter().map(|s| s.edge.length).sum()
+    }
+
     pub fn junction_coord_of(&self, id: NodeID) -> Option<crate::structures::LatLng> {
         let ji = *self.junction_of.get(id.0)?;
         (ji != u32::MAX).then(|| self.junction_coord[ji as usize])
@@ -518,12 +524,13 @@ impl ContractedGraph {
         let t = project_t(lat, lon, near, far);
         let len = edge.length;
         let speed = match profile {
-            StreetProfile::Foot => g.raptor.walking_speed_mps,
+            StreetProfile::Foot => g.walking_speed_mps(),
             StreetProfile::Bike => g.raptor.cycling_speed_mps,
+            StreetProfile::Car if edge.car => g.car_speed_mps(&edge),
             StreetProfile::Car => g.raptor.driving_speed_mps,
         };
-        let mms = (speed * 1000.0).max(1.0) as u64;
-        let secs = |d: usize| (d as u64 * 1000 / mms) as u32;
+        let speed = Speed::from_mps(speed);
+        let secs = |d: usize| speed.travel_time(Meters(d)).0;
         let da = ((t * len as f64).round() as usize).min(len); // proj→near (toward from_ji)
         let db = len - da; //                                     proj→far  (toward to_ji)
         let chain = |range: std::ops::Range<u32>| -> Option<u32> {
@@ -708,8 +715,8 @@ impl ContractedGraph {
                 if Self::foot_step_secs(g, a, b).is_none() && Self::foot_step_secs(g, b, a).is_none() {
                     return Vec::new();
                 }
-                let wmms = (g.raptor.walking_speed_mps * 1000.0) as u64;
-                let stub = |d: usize| (d as u64 * 1000 / wmms.max(1)) as u32;
+                let walk = Speed::from_mps(g.walking_speed_mps());
+                let stub = |d: usize| walk.travel_time(Meters(d)).0;
                 let mut out: Vec<(usize, u32)> = Vec::new();
                 for (e, other, d) in [(a, b, dist_a), (b, a, dist_b)] {
                     if let Some((j, s)) = self.entry_from(g, e, other, stub(d)) {
@@ -858,8 +865,8 @@ impl ContractedGraph {
         let far = self.segs[gi as usize].far;
         let t = project_t(lat, lon, near, far);
         let len = edge.length;
-        let wmms = (g.raptor.walking_speed_mps * 1000.0).max(1.0) as u64;
-        let secs = |d: usize| (d as u64 * 1000 / wmms) as u32;
+        let walk = Speed::from_mps(g.walking_speed_mps());
+        let secs = |d: usize| walk.travel_time(Meters(d)).0;
         let da = ((t * len as f64).round() as usize).min(len);
         let db = len - da;
         let foot = |range: std::ops::Range<u32>| -> Option<u32> {
@@ -964,18 +971,37 @@ impl ContractedGraph {
         d: crate::structures::LatLng,
         radius_m: f64,
         bound: u32,
+    ) -> Option<u32> {
+        self.walk_secs_coord_to_coord_with(g, o, d, radius_m, bound, |o_entries, d_entries| {
+            let dist = g.walk_dijkstra_union_seeded(o_entries, bound, self);
+            d_entries
+                .iter()
+                .filter_map(|&(dj, sd)| {
+                    dist.get(&self.junctions[dj]).map(|&dj_dist| dj_dist.saturating_add(sd))
+                })
+                .min()
+        })
+    }
+
+    /// [`Self::walk_secs_coord_to_coord`] with the junction-to-junction part delegated to
+    /// `via_junctions(origin entries, destination entries)`, so a CCH query can stand in for
+    /// the seeded Dijkstra. The same-super-edge direct candidate is still added here.
+    pub(crate) fn walk_secs_coord_to_coord_with(
+        &self,
+        g: &Graph,
+        o: crate::structures::LatLng,
+        d: crate::structures::LatLng,
+        radius_m: f64,
+        bound: u32,
+        via_junctions: impl FnOnce(&[(usize, u32)], &[(usize, u32)]) -> Option<u32>,
     ) -> Option<u32> {
         let (os, ogi, oda, odb, o_entries) = self.foot_snap(g, o.latitude, o.longitude, radius_m)?;
         let (ds, dgi, dda, ddb, d_entries) = self.foot_snap(g, d.latitude, d.longitude, radius_m)?;
         let mut best: Option<u32> = None;
-        let dist = g.walk_dijkstra_union_seeded(&o_entries, bound, self);
-        for &(dj, sd) in &d_entries {
-            if let Some(&dj_dist) = dist.get(&self.junctions[dj]) {
-                let total = dj_dist.saturating_add(sd);
-                if total <= bound {
-                    best = Some(best.map_or(total, |b| b.min(total)));
-                }
-            }
+        if let Some(total) = via_junctions(&o_entries, &d_entries)
+            && total <= bound
+        {
+            best = Some(total);
         }
         // Same-super-edge direct: both projections on one chain ⇒ direct walk is the abs diff
         // of their `from_ji`→proj prefixes (foot cost is direction-symmetric).
@@ -1003,23 +1029,30 @@ impl ContractedGraph {
         gi: u32,
         da: usize,
     ) -> Option<u32> {
-        let wmms = (g.raptor.walking_speed_mps * 1000.0).max(1.0) as u64;
+        let walk = Speed::from_mps(g.walking_speed_mps());
         let mut total = 0u32;
         for i in sm.seg_start..gi {
             total = total.saturating_add(g.edge_secs(&self.segs[i as usize].edge, StreetProfile::Foot)?);
         }
-        total = total.saturating_add((da as u64 * 1000 / wmms) as u32);
+        total = total.saturating_add(walk.travel_time(Meters(da)).0);
         Some(total)
     }
 
-    /// Car seconds to traverse `se` entering in phase `walking` (false = Driving), replayed via
-    /// `car_edge_step`: `(seconds, exit phase)`. Phased Driving→park→Walking, never back. `None`
-    /// if a segment is impassable in the current phase (dead-end), as `street_dijkstra(Car)`.
-    pub fn car_secs(&self, g: &Graph, se: &SuperEdge, walking: bool) -> Option<(u32, bool)> {
+    /// Car seconds to traverse `se` entering in phase `walking` (false = Driving) `elapsed`
+    /// seconds after departure, replayed via `car_edge_step`: `(seconds, exit phase)`. Phased
+    /// Driving→park→Walking, never back. `None` if a segment is impassable in the current
+    /// phase (dead-end), as `street_dijkstra(Car)`.
+    pub fn car_secs(
+        &self,
+        g: &Graph,
+        se: &SuperEdge,
+        walking: bool,
+        elapsed: u32,
+    ) -> Option<(u32, bool)> {
         let mut total = 0u32;
         let mut phase = walking;
         for seg in self.seg_slice(se) {
-            let (t, next) = g.car_edge_step(&seg.edge, phase)?;
+            let (t, next) = g.car_edge_step(&seg.edge, phase, elapsed.saturating_add(total))?;
             total = total.saturating_add(t);
             phase = next;
         }
@@ -1049,8 +1082,8 @@ impl ContractedGraph {
         let far = self.segs[gi as usize].far;
         let t = project_t(lat, lon, near, far);
         let len = edge.length;
-        let wmms = (g.raptor.walking_speed_mps * 1000.0).max(1.0) as u64;
-        let secs = |d: usize| (d as u64 * 1000 / wmms) as u32;
+        let walk = Speed::from_mps(g.walking_speed_mps());
+        let secs = |d: usize| walk.travel_time(Meters(d)).0;
         let da = ((t * len as f64).round() as usize).min(len); // proj→near, matches snap_to_edge
         let db = len - da; // proj→far
         let foot = |range: std::ops::Range<u32>| -> Option<u32> {
@@ -1203,7 +1236,7 @@ impl ContractedGraph {
             let mut total = 0u32;
             let mut phase = false; // Driving
             for e in edges {
-                let (s, next) = g.car_edge_step(e, phase)?;
+                let (s, next) = g.car_edge_step(e, phase, total)?;
                 total = total.saturating_add(s);
                 phase = next;
             }
@@ -1527,7 +1560,7 @@ impl Graph {
                 continue;
             }
             for se in &cg.adjacency[ji] {
-                let Some((t, next)) = cg.car_secs(self, se, walking) else {
+                let Some((t, next)) = cg.car_secs(self, se, walking, d) else {
                     continue;
                 };
                 let nd = d.saturating_add(t);
@@ -1669,7 +1702,7 @@ impl Graph {
     pub fn drop_full_node_arrays(&mut self) {
         self.nodes = Vec::new();
         self.edges = Vec::new();
-        self.nodes_tree = kdtree::KdTree::new(2);
+        self.nodes_tree = super::PointIndex::default();
         self.edge_index = super::edge_index::EdgeIndex::default();
     }
 
@@ -1989,6 +2022,7 @@ mod tests {
             },
             accessibility: Availability::Available,
             platform_code: None,
+            code: None,
             parent_station: None,
         }));
         g.build_raptor_index();
@@ -2057,6 +2091,17 @@ mod tests {
         let _ = b;
     }
 
+    #[test]
+    fn super_edge_length_accumulates_chain_not_crow_flies() {
+        let (g, a, b, m1, m2) = chain_graph();
+        let cg = ContractedGraph::from_graph_union(&g);
+        let se = cg.super_edge(a, m1).expect("a→ super-edge to b");
+        let hop = |x: NodeID, y: NodeID| g.nodes[x.0].loc().dist(g.nodes[y.0].loc()) as usize;
+        let want = hop(a, m1) + hop(m1, m2) + hop(m2, b);
+        assert_eq!(cg.super_edge_length(se), want);
+        assert!(cg.super_edge_length(se) > hop(a, b), "chain is longer than crow-flies");
+    }
+
     /// A foot-only spur off a chain node makes it a union junction even though it stays a
     /// degree-2 pass-through for bike.
     #[test]
@@ -2356,7 +2401,7 @@ mod tests {
         let mut total = 0u32;
         let mut phase = false;
         for e in &edges {
-            let (s, next) = g.car_edge_step(e, phase)?;
+            let (s, next) = g.car_edge_step(e, phase, total)?;
             total = total.saturating_add(s);
             phase = next;
         }
@@ -3069,8 +3114,8 @@ mod tests {
         g.build_raptor_index();
         g.build_edge_index();
         let cg = ContractedGraph::from_graph_union(&g);
-        let wmms = (g.raptor.walking_speed_mps * 1000.0) as u64;
-        let stub = |d: usize| (d as u64 * 1000 / wmms.max(1)) as u32;
+        let walk = Speed::from_mps(g.raptor.walking_speed_mps);
+        let stub = |d: usize| walk.travel_time(Meters(d)).0;
 
         let mut state: u64 = 0x243F6A8885A308D3;
         let mut next = || {
@@ -3162,6 +3207,7 @@ mod tests {
         assert!(interior > 100, "expected interior origins");
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn contraction_measure_belgium() {
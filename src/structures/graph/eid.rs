@@ -0,0 +1,119 @@
+//! External node id (`eid`) lookup without a heap string per node. An eid such as
+//! `map#osm#123` is split at its last `#` into an interned source (`map#osm`) and a
+//! numeric id, so the map key is two integers. Eids without a numeric tail (test
+//! fixtures, hand-built graphs) intern the whole string as their source.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::structures::NodeID;
+
+/// Source prefix of every node imported from an OSM PBF.
+pub const OSM_EID_SOURCE: &str = "map#osm";
+
+/// `id` of an eid with no numeric tail; its source is the whole eid.
+const WHOLE_EID: i64 = i64::MIN;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EidKey {
+    pub source: u32,
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EidMapper {
+    sources: Vec<String>,
+    keys: HashMap<EidKey, NodeID>,
+}
+
+fn split(eid: &str) -> (&str, i64) {
+    match eid.rsplit_once('#') {
+        Some((source, tail)) if !source.is_empty() => match tail.parse::<i64>() {
+            Ok(id) if id != WHOLE_EID => (source, id),
+            _ => (eid, WHOLE_EID),
+        },
+        _ => (eid, WHOLE_EID),
+    }
+}
+
+impl EidMapper {
+    fn source_index(&self, source: &str) -> Option<u32> {
+        self.sources.iter().position(|s| s == source).map(|i| i as u32)
+    }
+
+    fn intern(&mut self, source: &str) -> u32 {
+        self.source_index(source).unwrap_or_else(|| {
+            self.sources.push(source.to_string());
+            (self.sources.len() - 1) as u32
+        })
+    }
+
+    pub fn insert(&mut self, eid: &str, node: NodeID) {
+        let (source, id) = split(eid);
+        let source = self.intern(source);
+        self.keys.insert(EidKey { source, id }, node);
+    }
+
+    pub fn get(&self, eid: &str) -> Option<&NodeID> {
+        let (source, id) = split(eid);
+        self.get_numeric(source, id)
+    }
+
+    /// Lookup by source and numeric id, without formatting an eid string.
+    pub fn get_numeric(&self, source: &str, id: i64) -> Option<&NodeID> {
+        let source = self.source_index(source)?;
+        self.keys.get(&EidKey { source, id })
+    }
+
+    /// The eid string a key was inserted under.
+    pub fn format(&self, key: EidKey) -> Option<String> {
+        let source = self.sources.get(key.source as usize)?;
+        Some(if key.id == WHOLE_EID {
+            source.clone()
+        } else {
+            format!("{source}#{}", key.id)
+        })
+    }
+
+    /// Reverse lookup; a linear scan, meant for API responses and diagnostics.
+    pub fn eid_of(&self, node: NodeID) -> Option<String> {
+        let (&key, _) = self.keys.iter().find(|&(_, &n)| n == node)?;
+        self.format(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osm_eids_share_one_interned_source() {
+        let mut m = EidMapper::default();
+        m.insert("map#osm#1", NodeID(0));
+        m.insert("map#osm#-2", NodeID(1));
+        assert_eq!(m.sources, vec!["map#osm".to_string()]);
+        assert_eq!(m.get("map#osm#-2"), Some(&NodeID(1)));
+        assert_eq!(m.get_numeric(OSM_EID_SOURCE, 1), Some(&NodeID(0)));
+        assert_eq!(m.eid_of(NodeID(1)).as_deref(), Some("map#osm#-2"));
+    }
+
+    #[test]
+    fn free_form_eids_round_trip() {
+        let mut m = EidMapper::default();
+        m.insert("Central", NodeID(3));
+        m.insert("a#b", NodeID(4));
+        assert_eq!(m.get("Central"), Some(&NodeID(3)));
+        assert_eq!(m.get("a#b"), Some(&NodeID(4)));
+        assert!(m.get("Centra").is_none());
+        assert_eq!(m.eid_of(NodeID(4)).as_deref(), Some("a#b"));
+    }
+}
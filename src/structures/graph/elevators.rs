@@ -0,0 +1,217 @@
+//! Elevator outages for the wheelchair profile. At build time each transit stop whose
+//! step-free walk to the ground-level street needs an elevator records the elevator
+//! sets of its alternative step-free paths; an outage list then blocks the stops for
+//! which every alternative has an elevator out of service.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
+
+use crate::structures::{EdgeData, NodeID};
+
+use super::Graph;
+
+/// Walk budget (raw metres) from a stop to the street when tracing step-free access.
+const STEP_FREE_BUDGET_M: usize = 500;
+/// Searches per stop while enumerating alternatives; a station with more elevator
+/// combinations keeps the routes found so far (outages then err towards blocking).
+const MAX_SEARCHES_PER_STOP: usize = 16;
+
+impl Graph {
+    /// Install the OSM id (`node/<id>` or `way/<id>`) of every elevator connector edge.
+    pub fn set_elevator_edges(&mut self, edges: HashMap<(NodeID, NodeID), String>) {
+        self.elevator_edges = edges;
+    }
+
+    pub fn elevator_of(&self, a: NodeID, b: NodeID) -> Option<&str> {
+        self.elevator_edges.get(&(a, b)).map(String::as_str)
+    }
+
+    /// Shortest step-free foot path from `start` to a ground street node that uses no
+    /// elevator in `excluded`; returns the elevators it rides. Reads the OSM connector
+    /// data, so only meaningful during a build.
+    fn step_free_elevators(
+        &self,
+        start: NodeID,
+        excluded: &BTreeSet<String>,
+        platform_nodes: &HashSet<NodeID>,
+    ) -> Option<BTreeSet<String>> {
+        let mut best: HashMap<NodeID, usize> = HashMap::from([(start, 0)]);
+        let mut prev: HashMap<NodeID, NodeID> = HashMap::new();
+        let mut heap = BinaryHeap::from([Reverse((0usize, start))]);
+        while let Some(Reverse((dist, node))) = heap.pop() {
+            if best.get(&node).is_some_and(|&d| d < dist) {
+                continue;
+            }
+            if node != start
+                && matches!(self.nodes.get(node.0), Some(crate::structures::NodeData::OsmNode(_)))
+                && self.is_ground_street_node(node, platform_nodes)
+            {
+                let mut used = BTreeSet::new();
+                let mut at = node;
+                while let Some(&p) = prev.get(&at) {
+                    if let Some(id) = self.elevator_of(p, at) {
+                        used.insert(id.to_string());
+                    }
+                    at = p;
+                }
+                return Some(used);
+            }
+            for e in &self.edges[node.0] {
+                let EdgeData::Street(s) = e else { continue };
+                let v = s.destination;
+                if !s.foot || self.connector_kind(node, v).is_some_and(|k| !k.step_free()) {
+                    continue;
+                }
+                if self.elevator_of(node, v).is_some_and(|id| excluded.contains(id)) {
+                    continue;
+                }
+                let nd = dist + s.length;
+                if nd <= STEP_FREE_BUDGET_M && best.get(&v).is_none_or(|&d| nd < d) {
+                    best.insert(v, nd);
+                    prev.insert(v, node);
+                    heap.push(Reverse((nd, v)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Elevator sets of `stop`'s alternative step-free paths, or empty when a path
+    /// without any elevator exists (or no step-free path at all). Branching on each
+    /// elevator of a found path finds a path avoiding any outage set, if one exists.
+    fn elevator_routes(&self, stop: NodeID, platform_nodes: &HashSet<NodeID>) -> Vec<Vec<String>> {
+        let mut routes: Vec<BTreeSet<String>> = Vec::new();
+        let mut seen: HashSet<BTreeSet<String>> = HashSet::new();
+        let mut queue = VecDeque::from([BTreeSet::new()]);
+        let mut searches = 0;
+        while let Some(excluded) = queue.pop_front() {
+            if searches == MAX_SEARCHES_PER_STOP {
+                break;
+            }
+            searches += 1;
+            let Some(used) = self.step_free_elevators(stop, &excluded, platform_nodes) else {
+                continue;
+            };
+            if used.is_empty() {
+                return Vec::new();
+            }
+            for id in &used {
+                let mut next = excluded.clone();
+                next.insert(id.clone());
+                if seen.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+            if !routes.contains(&used) {
+                routes.push(used);
+            }
+        }
+        routes.into_iter().map(|r| r.into_iter().collect()).collect()
+    }
+
+    /// Record, per compact stop, the elevator sets of its step-free access paths. Run
+    /// after `build_raptor_index`; a no-op without OSM elevator data.
+    pub fn build_stop_elevator_access(&mut self) -> usize {
+        self.raptor.stop_elevator_routes.clear();
+        if self.elevator_edges.is_empty() {
+            return 0;
+        }
+        let platform_nodes = self.all_platform_nodes();
+        let mut routes = HashMap::new();
+        for (compact, &node) in self.raptor.transit_stop_to_node.iter().enumerate() {
+            let r = self.elevator_routes(node, &platform_nodes);
+            if !r.is_empty() {
+                routes.insert(compact as u32, r);
+            }
+        }
+        self.raptor.stop_elevator_routes = routes;
+        self.raptor.stop_elevator_routes.len()
+    }
+
+    /// Mark elevators out of service, replacing any previous list; returns how many
+    /// stops lost every step-free access path.
+    pub fn set_elevator_outages(&mut self, ids: &[String]) -> usize {
+        let out: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        self.raptor.elevator_blocked_stops = self
+            .raptor
+            .stop_elevator_routes
+            .iter()
+            .filter(|(_, routes)| routes.iter().all(|r| r.iter().any(|id| out.contains(id.as_str()))))
+            .map(|(&stop, _)| stop)
+            .collect();
+        self.raptor.elevator_blocked_stops.len()
+    }
+
+    /// Elevator ids the graph knows about (any stop depends on), sorted.
+    pub fn known_elevators(&self) -> BTreeSet<&str> {
+        self.raptor
+            .stop_elevator_routes
+            .values()
+            .flatten()
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::{Connector, GraphBuilder};
+
+    /// Stop on level -1 platform node `p`, reached from ground node `g0` by stairs or
+    /// by elevator `node/1` (optionally also `node/2`) to a lift lobby beside `p`.
+    fn station(second_elevator: bool) -> (Graph, usize) {
+        let mut b = GraphBuilder::new();
+        let g0 = b.add_street_node("g0", 50.0, 4.0);
+        let far = b.add_street_node("far", 50.0, 4.01);
+        b.add_street(g0, far, 700);
+        let p = b.add_street_node("p", 50.0, 4.0001);
+        b.set_level(p, -1);
+        b.add_connector(g0, p, 20, Connector::Steps, None);
+        for (i, id) in ["node/1", "node/2"].into_iter().enumerate() {
+            if i == 1 && !second_elevator {
+                break;
+            }
+            let lobby = b.add_street_node(&format!("lobby{i}"), 50.0, 4.0002);
+            b.set_level(lobby, -1);
+            b.add_connector(g0, lobby, 10, Connector::Elevator, Some(id));
+            b.add_connector(lobby, p, 5, Connector::Ramp, None);
+        }
+        let s = b.add_stop("S", 50.0, 4.0001);
+        b.link_stop(s, p, 5);
+        let t = b.add_stop("T", 50.0, 4.01);
+        b.link_stop(t, far, 5);
+        let r = b.add_route("1", "Line 1", gtfs_structures::RouteType::Subway);
+        b.add_trip(r, &[(s, 0, 0), (t, 60, 60)]);
+        let g = b.build();
+        let stop = g.stop_index_of("S").unwrap();
+        (g, stop)
+    }
+
+    #[test]
+    fn single_elevator_outage_blocks_the_stop() {
+        let (mut g, stop) = station(false);
+        assert_eq!(g.raptor.stop_elevator_routes[&(stop as u32)], vec![vec!["node/1".to_string()]]);
+        assert_eq!(g.set_elevator_outages(&["node/1".into()]), 1);
+        assert!(g.stop_blocks_wheelchair(stop));
+        assert_eq!(g.set_elevator_outages(&[]), 0);
+        assert!(!g.stop_blocks_wheelchair(stop));
+    }
+
+    #[test]
+    fn a_second_elevator_keeps_the_stop_accessible() {
+        let (mut g, stop) = station(true);
+        assert_eq!(g.raptor.stop_elevator_routes[&(stop as u32)].len(), 2);
+        assert_eq!(g.set_elevator_outages(&["node/1".into()]), 0);
+        assert_eq!(g.set_elevator_outages(&["node/1".into(), "node/2".into()]), 1);
+        assert_eq!(g.known_elevators().into_iter().collect::<Vec<_>>(), vec!["node/1", "node/2"]);
+    }
+
+    #[test]
+    fn surface_stops_do_not_depend_on_elevators() {
+        let (g, _) = station(false);
+        let t = g.stop_index_of("T").unwrap() as u32;
+        assert!(!g.raptor.stop_elevator_routes.contains_key(&t));
+    }
+}
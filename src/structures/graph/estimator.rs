@@ -0,0 +1,172 @@
+//! Admissible A* time bounds derived from the loaded street network. Each mode's bound
+//! is its nominal speed times the largest per-edge speed multiplier its edge model
+//! applies anywhere in the graph, so `crow_m / speed` never exceeds the true remaining
+//! time. A configured speed is only accepted when it is at least this fast: a slower
+//! one would over-estimate and A* would silently return suboptimal plans.
+
+use crate::structures::cost::RoutingMode;
+use crate::structures::{BikeCost, BikeProfile, StreetEdgeData};
+
+use super::Graph;
+
+/// Fastest-edge multipliers over each mode's nominal speed, scanned once per graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimatorFactors {
+    pub walk: f64,
+    /// Largest surface factor on a rideable edge; the bike solve never exceeds
+    /// `max_speed × surface factor`.
+    pub bike: f64,
+    /// Fastest highway-class speed on a drivable edge over `driving_speed_mps`.
+    pub car: f64,
+}
+
+impl Default for EstimatorFactors {
+    fn default() -> Self {
+        EstimatorFactors { walk: 1.0, bike: 1.0, car: 1.0 }
+    }
+}
+
+impl EstimatorFactors {
+    fn scan<'a>(
+        edges: impl Iterator<Item = &'a StreetEdgeData>,
+        car_factor: impl Fn(&StreetEdgeData) -> f64,
+    ) -> Self {
+        // Walk charges `length / nominal speed` on every edge; its multiplier stays 1.
+        let mut f = EstimatorFactors::default();
+        let mut car: Option<f64> = None;
+        for e in edges {
+            if e.bike && !BikeCost::is_push(&e.attrs) {
+                f.bike = f.bike.max(BikeCost::surface_factor(e));
+            }
+            if e.car {
+                car = Some(car.unwrap_or(0.0).max(car_factor(e)));
+            }
+        }
+        // No drivable edge: keep the nominal bound rather than a zero speed.
+        f.car = car.unwrap_or(1.0);
+        f
+    }
+}
+
+impl Graph {
+    /// Scan the street edges (raw, or the contracted segments once the raw arrays are
+    /// dropped) for the per-mode estimator multipliers.
+    pub fn compute_estimator_factors(&mut self) {
+        let car_factor = |e: &StreetEdgeData| self.car_speed_mps(e) / self.raptor.driving_speed_mps;
+        let factors = match &self.contracted {
+            Some(cg) if self.edges.is_empty() => {
+                EstimatorFactors::scan(cg.segs.iter().map(|s| &s.edge), car_factor)
+            }
+            _ => EstimatorFactors::scan(
+                self.edges.iter().flatten().filter_map(|e| match e {
+                    crate::structures::EdgeData::Street(s) => Some(s),
+                    _ => None,
+                }),
+                car_factor,
+            ),
+        };
+        self.raptor.estimator_factors = factors;
+    }
+
+    /// Admissible top speed (m/s) for the A* time bound of `mode` under the current
+    /// query's speeds and bike profile, raised to the configured override if faster.
+    pub fn estimator_speed(&self, mode: RoutingMode, bike: &BikeProfile) -> f64 {
+        let pinned = self.raptor.estimator_overrides[override_slot(mode)];
+        self.derived_estimator_speed(mode, bike).max(pinned.unwrap_or(0.0))
+    }
+
+    fn derived_estimator_speed(&self, mode: RoutingMode, bike: &BikeProfile) -> f64 {
+        let f = self.raptor.estimator_factors;
+        match mode {
+            RoutingMode::Walk => self.walking_speed_mps() * f.walk,
+            RoutingMode::Bike => {
+                let ride = (bike.max_speed / 3.6 * f.bike).max(0.5);
+                ride.max(bike.push_speed_mps).max(bike.steps_push_speed_mps)
+            }
+            // Car falls back to foot edges (snap connectors), walked at walking speed.
+            RoutingMode::Drive => (self.raptor.driving_speed_mps * f.car).max(self.walking_speed_mps()),
+        }
+    }
+
+    /// Pin `mode`'s A* speed to `mps`. Refused when slower than the derived bound under
+    /// the graph defaults (inadmissible); a faster value only loosens the bound.
+    pub fn set_estimator_speed(&mut self, mode: RoutingMode, mps: f64) -> Result<(), String> {
+        let derived = self.derived_estimator_speed(mode, &self.raptor.bike_profile);
+        if mps.is_nan() || mps < derived {
+            return Err(format!(
+                "{mps} m/s is below the {derived:.2} m/s the network allows for {mode:?}; \
+                 the A* bound would be inadmissible"
+            ));
+        }
+        self.raptor.estimator_overrides[override_slot(mode)] = Some(mps);
+        Ok(())
+    }
+}
+
+fn override_slot(mode: RoutingMode) -> usize {
+    match mode {
+        RoutingMode::Walk => 0,
+        RoutingMode::Bike => 1,
+        RoutingMode::Drive => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::cost::VarGen;
+    use crate::structures::{BikeAttrs, EdgeData, LatLng, NodeData, OsmNodeData};
+
+    fn graph_with_bike_edge(surface_speed: u8) -> Graph {
+        let mut g = Graph::new();
+        let node = |eid: &str, lon| {
+            NodeData::OsmNode(OsmNodeData {
+                eid: eid.into(),
+                lat_lng: LatLng { latitude: 50.0, longitude: lon },
+            })
+        };
+        let a = g.add_node(node("a", 4.0));
+        let b = g.add_node(node("b", 4.001));
+        g.add_edge(a, EdgeData::Street(StreetEdgeData {
+            origin: a,
+            destination: b,
+            length: 70,
+            partial: false,
+            foot: true,
+            bike: true,
+            car: false,
+            attrs: BikeAttrs::road_default(),
+            elev_delta: 0,
+            surface_speed,
+            var_gen: VarGen::NONE,
+        }));
+        g
+    }
+
+    #[test]
+    fn fast_surface_raises_the_bike_bound() {
+        let mut g = graph_with_bike_edge(120);
+        let p = g.raptor.bike_profile;
+        let before = g.estimator_speed(RoutingMode::Bike, &p);
+        g.compute_estimator_factors();
+        let after = g.estimator_speed(RoutingMode::Bike, &p);
+        assert!((after / before - 1.2).abs() < 1e-9, "{before} -> {after}");
+        assert!(
+            BikeCost::new(p).cruise_speed(match &g.edges[0][0] {
+                EdgeData::Street(s) => s,
+                _ => unreachable!(),
+            }) <= after
+        );
+    }
+
+    #[test]
+    fn override_below_the_derived_bound_is_refused() {
+        let mut g = graph_with_bike_edge(100);
+        g.compute_estimator_factors();
+        let walk = g.estimator_speed(RoutingMode::Walk, &BikeProfile::default());
+        assert!(g.set_estimator_speed(RoutingMode::Walk, walk * 0.5).is_err());
+        assert_eq!(g.estimator_speed(RoutingMode::Walk, &BikeProfile::default()), walk);
+        g.set_estimator_speed(RoutingMode::Walk, walk * 2.0).unwrap();
+        assert_eq!(g.estimator_speed(RoutingMode::Walk, &BikeProfile::default()), walk * 2.0);
+    }
+}
@@ -0,0 +1,557 @@
+//! Registry of the GTFS feeds merged into one graph, and the post-ingestion pass that
+//! ties together stops two feeds publish for the same physical station (a STIB metro
+//! stop next to the SNCB platform of the same name). Linked stops get a pair of foot
+//! transfer edges and share one station group, so RAPTOR transfers and station lookups
+//! see them as one place.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use super::{Graph, PointIndex};
+use crate::ingestion::gtfs::{IngestionReport, TripId, date_to_days, foot_connector_edge};
+use crate::structures::plan::PlanWarning;
+use crate::structures::raptor::Lookup;
+use crate::structures::{EdgeData, LatLng, NodeData, NodeID, OsmNodeData};
+
+/// [`PlanWarning::code`] (and plan error code) for a date outside every feed's validity.
+pub const OUTSIDE_FEED_VALIDITY: &str = "OUTSIDE_FEED_VALIDITY";
+
+/// One ingested GTFS feed. Ranges are half-open and index the graph's global arrays.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedRecord {
+    pub label: String,
+    /// Prefix of this feed's stop/trip/route ids (`"{namespace}:{id}"`); `None` keeps raw ids.
+    pub namespace: Option<String>,
+    /// Node ids allocated while the feed loaded; every stop of the feed lies in here.
+    pub nodes: Range<usize>,
+    pub trips: Range<usize>,
+    pub routes: Range<usize>,
+    /// Raw (un-namespaced) GTFS `stop_id` -> stop node, for every stop the feed loaded.
+    pub stops: HashMap<String, NodeID>,
+    /// Seconds after midnight at which the feed's agencies roll over to the next service
+    /// day; `None` uses the router default (05:00).
+    #[serde(default)]
+    pub service_day_cutover: Option<u32>,    /// `feed_info.txt` `feed_start_date` / `feed_end_date`, as `date_to_days` day numbers.
+    #[serde(default)]
+    pub feed_info_days: (Option<u32>, Option<u32>),
+    /// First and last day any trip of the feed runs (calendar ranges and added dates).
+    #[serde(default)]
+    pub service_days: Option<(u32, u32)>,
+}
+
+impl FeedRecord {
+    /// Days the feed answers for: `feed_info.txt` bounds where given, the service range
+    /// otherwise. `None` when the feed states neither.
+    pub fn valid_days(&self) -> Option<(u32, u32)> {
+        let (start, end) = self.feed_info_days;
+        let service = self.service_days;
+        Some((start.or(service.map(|s| s.0))?, end.or(service.map(|s| s.1))?))
+    }
+}
+
+/// Outcome of [`Graph::link_cross_feed_stops`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrossFeedLinks {
+    /// Stop pairs joined by transfer edges.
+    pub linked: usize,
+    /// Station groups folded into a group of an earlier feed.
+    pub merged_stations: usize,
+    /// Stop ids published by more than one feed (only possible without namespaces).
+    pub id_collisions: usize,
+}
+
+struct FeedStop {
+    node: NodeID,
+    feed: usize,
+    loc: LatLng,
+    tokens: Vec<String>,
+    station: String,
+}
+
+/// Lowercased alphanumeric words of a stop name, minus short particles ("de", "du").
+fn name_tokens(name: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().count() > 2)
+        .map(str::to_lowercase)
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+/// Overlap coefficient of two sorted token sets: shared words over the shorter name, so
+/// "Midi" vs "Bruxelles-Midi" scores 1.0 while "Porte de Hal" vs "Gare du Midi" scores 0.
+fn name_similarity(a: &[String], b: &[String]) -> f64 {
+    let shorter = a.len().min(b.len());
+    if shorter == 0 {
+        return 0.0;
+    }
+    let shared = a.iter().filter(|t| b.binary_search(t).is_ok()).count();
+    shared as f64 / shorter as f64
+}
+
+impl Graph {
+    /// Open a feed: ids of everything ingested until [`Graph::finish_feed`] are attributed
+    /// to it and prefixed with `namespace`.
+    pub fn begin_feed(&mut self, label: &str, namespace: Option<&str>) {
+        self.close_feed();
+        self.feeds.push(FeedRecord {
+            label: label.to_string(),
+            namespace: namespace.map(str::to_string),
+            nodes: self.nodes.len()..self.nodes.len(),
+            trips: self.raptor.transit_trips.len()..self.raptor.transit_trips.len(),
+            routes: self.raptor.transit_routes.len()..self.raptor.transit_routes.len(),
+            stops: HashMap::new(),
+            service_day_cutover: None,
+            feed_info_days: (None, None),
+            service_days: None,
+        });
+        self.feed_open = true;
+    }
+
+    /// Close the open feed and keep its ingestion report.
+    pub fn finish_feed(&mut self, report: IngestionReport) {
+        let Some(label) = self.close_feed() else {
+            return;
+        };
+        self.record_ingestion_report(&label, report);
+    }
+
+    /// Seal the open feed's ranges at the current array lengths; returns its label.
+    fn close_feed(&mut self) -> Option<String> {
+        if !self.feed_open {
+            return None;
+        }
+        self.feed_open = false;
+        let (nodes, trips, routes) = (
+            self.nodes.len(),
+            self.raptor.transit_trips.len(),
+            self.raptor.transit_routes.len(),
+        );
+        let start = self.feeds.last()?.trips.start;
+        let service_days = self.service_days_of_trips(start..trips);
+        let feed = self.feeds.last_mut()?;
+        feed.nodes.end = nodes;
+        feed.trips.end = trips;
+        feed.routes.end = routes;
+        feed.service_days = service_days;
+        Some(feed.label.clone())
+    }
+
+    /// First and last day any of `trips` runs.
+    fn service_days_of_trips(&self, trips: Range<usize>) -> Option<(u32, u32)> {
+        let services = &self.raptor.transit_services;
+        let days = self.raptor.transit_trips[trips].iter().filter_map(|t| services.get(t.service_id.0 as usize));
+        days.flat_map(|s| {
+            let range = (s.start_date <= s.end_date).then_some([s.start_date, s.end_date]);
+            range.into_iter().flatten().chain(s.added_dates.iter().copied())
+        })
+        .fold(None, |acc, d| match acc {
+            Some((lo, hi)) => Some((d.min(lo), d.max(hi))),
+            None => Some((d, d)),
+        })
+    }
+
+    pub fn is_feed_open(&self) -> bool {
+        self.feed_open
+    }
+
+    /// Keep the open feed's `stop_id -> node` map (raw GTFS ids).
+    pub fn record_feed_stops(&mut self, stops: HashMap<String, NodeID>) {
+        if let Some(feed) = self.feeds.last_mut().filter(|_| self.feed_open) {
+            feed.stops = stops;
+        }
+    }
+
+    /// Set the open feed's service day cutover (seconds after midnight).
+    pub fn set_feed_service_day_cutover(&mut self, cutover: Option<u32>) {
+        if let Some(feed) = self.feeds.last_mut().filter(|_| self.feed_open) {
+            feed.service_day_cutover = cutover;
+        }
+    }
+
+    /// Set the open feed's `feed_info.txt` validity (day numbers).
+    pub fn set_feed_info_days(&mut self, start: Option<u32>, end: Option<u32>) {
+        if let Some(feed) = self.feeds.last_mut().filter(|_| self.feed_open) {
+            feed.feed_info_days = (start, end);
+        }
+    }
+
+    /// A warning when feeds state their validity and none covers `date`, so a plan on that
+    /// day is walk-only for lack of data rather than of service.
+    pub fn feed_validity_warning(&self, date: NaiveDate) -> Option<PlanWarning> {
+        let day = date_to_days(date);
+        let ranges: Vec<(&str, (u32, u32))> =
+            self.feeds.iter().filter_map(|f| Some((f.label.as_str(), f.valid_days()?))).collect();
+        if ranges.is_empty() || ranges.iter().any(|&(_, (lo, hi))| (lo..=hi).contains(&day)) {
+            return None;
+        }
+        let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+        let ymd = |d: u32| epoch + Days::new(d as u64);
+        let spans: Vec<String> =
+            ranges.iter().map(|&(label, (lo, hi))| format!("{label}: {} to {}", ymd(lo), ymd(hi))).collect();
+        Some(PlanWarning {
+            code: OUTSIDE_FEED_VALIDITY.to_string(),
+            message: format!("no transit feed covers {date} ({})", spans.join("; ")),
+            distance_m: None,
+        })
+    }
+
+    pub fn feed(&self, label: &str) -> Option<&FeedRecord> {
+        self.feeds.iter().find(|f| f.label == label)
+    }
+
+    /// Node of `stop_id` (the raw id from the feed's `stops.txt`) in the feed `label`.
+    pub fn gtfs_stop_node(&self, label: &str, stop_id: &str) -> Option<NodeID> {
+        self.feed(label)?.stops.get(stop_id).copied()
+    }
+
+    /// Node of a graph-wide stop id, i.e. one carrying its feed's namespace prefix
+    /// (`"stib:1234"`) or a raw id from a feed loaded without a namespace.
+    pub fn gtfs_stop_node_by_id(&self, id: &str) -> Option<NodeID> {
+        self.feeds.iter().find_map(|f| {
+            let raw = match &f.namespace {
+                Some(ns) => id.strip_prefix(ns.as_str())?.strip_prefix(':')?,
+                None => id,
+            };
+            f.stops.get(raw).copied()
+        })
+    }
+
+    /// Namespace of the feed currently being ingested, if any.
+    pub fn open_feed_namespace(&self) -> Option<&str> {
+        if !self.feed_open {
+            return None;
+        }
+        self.feeds.last()?.namespace.as_deref()
+    }
+
+    pub fn feed_of_trip(&self, trip: TripId) -> Option<&FeedRecord> {
+        let t = trip.0 as usize;
+        self.feeds.iter().find(|f| f.trips.contains(&t))
+    }
+
+    /// Tombstone the feed `label` so a new copy can be appended with
+    /// [`Graph::begin_feed`]: its stop nodes lose every edge in or out and become
+    /// edgeless, unindexed OSM nodes (slots stay so later `NodeID`s are stable), its
+    /// patterns leave the RAPTOR timetables, and its trip and route ids are blanked so
+    /// they no longer resolve. Other feeds keep their ids and data. Run on a graph that
+    /// still has its node arrays and has not been finalized (no cross-feed links yet).
+    pub fn remove_feed(&mut self, label: &str) -> Option<FeedRecord> {
+        let fi = self.feeds.iter().position(|f| f.label == label)?;
+        let feed = self.feeds.remove(fi);
+        let owned = |n: NodeID| feed.nodes.contains(&n.0);
+
+        for n in feed.nodes.clone() {
+            self.edges[n].clear();
+            let loc = self.nodes[n].loc();
+            self.nodes[n] = NodeData::OsmNode(OsmNodeData {
+                eid: String::new(),
+                lat_lng: loc,
+            });
+        }
+        for edges in &mut self.edges {
+            edges.retain(|e| match e {
+                EdgeData::Street(s) => !owned(s.destination),
+                EdgeData::Transit(t) => !owned(t.destination),
+            });
+        }
+        self.node_levels.retain(|&id, _| !owned(id));
+        self.connector_edges.retain(|&(a, b), _| !owned(a) && !owned(b));
+        self.elevator_edges.retain(|&(a, b), _| !owned(a) && !owned(b));
+
+        self.drop_patterns_of_routes(&feed.routes);
+        let r = &mut self.raptor;
+        feed.trips.clone().for_each(|t| r.transit_trip_ids[t].clear());
+        feed.routes.clone().for_each(|t| r.transit_route_ids[t].clear());
+
+        self.ingestion_reports.retain(|r| r.feed != label);
+        self.build_info.sources.retain(|s| s.label != label);
+        Some(feed)
+    }
+
+    /// Rewrite the pattern tables without the patterns of `routes`.
+    fn drop_patterns_of_routes(&mut self, routes: &Range<usize>) {
+        fn keep<T: Clone>(table: &mut Vec<T>, idx: &mut Vec<Lookup>, kept: &[bool]) {
+            let mut out = Vec::with_capacity(table.len());
+            let lookups = std::mem::take(idx);
+            for (l, _) in lookups.iter().zip(kept).filter(|(_, k)| **k) {
+                idx.push(Lookup { start: out.len(), len: l.len });
+                out.extend_from_slice(l.of(table));
+            }
+            *table = out;
+        }
+
+        let r = &mut self.raptor;
+        let kept: Vec<bool> =
+            r.transit_patterns.iter().map(|p| !routes.contains(&(p.route.0 as usize))).collect();
+        keep(&mut r.transit_pattern_stops, &mut r.transit_idx_pattern_stops, &kept);
+        keep(&mut r.transit_pattern_trips, &mut r.transit_idx_pattern_trips, &kept);
+        keep(&mut r.transit_pattern_stop_times, &mut r.transit_idx_pattern_stop_times, &kept);
+        let mut k = kept.iter();
+        r.transit_patterns.retain(|_| *k.next().unwrap());
+        if r.transit_pattern_shape_ids.len() == kept.len() {
+            let mut k = kept.iter();
+            r.transit_pattern_shape_ids.retain(|_| *k.next().unwrap());
+            let mut k = kept.iter();
+            r.transit_pattern_shape_stop_idx.retain(|_| *k.next().unwrap());
+        }
+    }
+
+    /// Link stops of different feeds lying within `radius_m` of each other whose names
+    /// score at least `min_similarity` (see [`name_similarity`]): a bidirectional foot
+    /// edge of the straight-line length, and the later feed's station group re-parented
+    /// onto the earlier one. Must run before `build_raptor_index` (which derives
+    /// transfers and stations from the node array). `radius_m <= 0` disables.
+    pub fn link_cross_feed_stops(&mut self, radius_m: f64, min_similarity: f64) -> CrossFeedLinks {
+        let mut out = CrossFeedLinks::default();
+        let mut stops: Vec<FeedStop> = Vec::new();
+        let mut first_feed_of_id: HashMap<&str, usize> = HashMap::new();
+        for (fi, feed) in self.feeds.iter().enumerate() {
+            for n in feed.nodes.clone() {
+                let Some(NodeData::TransitStop(s)) = self.nodes.get(n) else {
+                    continue;
+                };
+                match first_feed_of_id.get(s.id.as_str()) {
+                    Some(&other) if other != fi => out.id_collisions += 1,
+                    Some(_) => {}
+                    None => {
+                        first_feed_of_id.insert(s.id.as_str(), fi);
+                    }
+                }
+                stops.push(FeedStop {
+                    node: NodeID(n),
+                    feed: fi,
+                    loc: s.lat_lng,
+                    tokens: name_tokens(&s.name),
+                    station: s.parent_station.clone().unwrap_or_else(|| s.id.clone()),
+                });
+            }
+        }
+        if out.id_collisions > 0 {
+            tracing::warn!(
+                "{} stop ids appear in more than one feed; set a `namespace` on the GTFS \
+                 inputs to keep them apart",
+                out.id_collisions
+            );
+        }
+        if radius_m <= 0.0 || self.feeds.len() < 2 {
+            return out;
+        }
+
+        let tree = PointIndex::bulk_load(
+            stops
+                .iter()
+                .enumerate()
+                .map(|(i, s)| ([s.loc.latitude, s.loc.longitude], i))
+                .collect(),
+        );
+
+        let mut links: Vec<(NodeID, NodeID, usize)> = Vec::new();
+        // (feed, station key) -> station key of the earlier feed it folds into.
+        let mut station_target: HashMap<(usize, String), String> = HashMap::new();
+        for (i, a) in stops.iter().enumerate() {
+            for (dist, &j) in tree.within(a.loc.latitude, a.loc.longitude, radius_m) {
+                let b = &stops[j];
+                if b.feed <= a.feed || j == i {
+                    continue;
+                }
+                if name_similarity(&a.tokens, &b.tokens) < min_similarity {
+                    continue;
+                }
+                links.push((a.node, b.node, dist.round() as usize));
+                let target = station_target
+                    .get(&(a.feed, a.station.clone()))
+                    .cloned()
+                    .unwrap_or_else(|| a.station.clone());
+                station_target
+                    .entry((b.feed, b.station.clone()))
+                    .or_insert(target);
+            }
+        }
+
+        for &(a, b, len) in &links {
+            self.add_edge(a, foot_connector_edge(a, b, len));
+            self.add_edge(b, foot_connector_edge(b, a, len));
+        }
+        for s in &stops {
+            let Some(target) = station_target.get(&(s.feed, s.station.clone())) else {
+                continue;
+            };
+            if let NodeData::TransitStop(stop) = &mut self.nodes[s.node.0] {
+                stop.parent_station = Some(target.clone());
+            }
+        }
+        out.linked = links.len();
+        out.merged_stations = station_target.len();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TransitStopData;
+
+    fn stop(g: &mut Graph, id: &str, name: &str, parent: Option<&str>, lng: f64) -> NodeID {
+        g.add_node(NodeData::TransitStop(TransitStopData {
+            name: name.to_string(),
+            lat_lng: LatLng {
+                latitude: 50.0,
+                longitude: lng,
+            },
+            accessibility: Default::default(),
+            id: id.to_string(),
+            platform_code: None,
+            code: None,
+            parent_station: parent.map(str::to_string),
+        }))
+    }
+
+    fn two_feeds() -> (Graph, [NodeID; 4]) {
+        let mut g = Graph::new();
+        g.begin_feed("stib", Some("stib"));
+        let a = stop(&mut g, "stib:1", "GARE DU MIDI", None, 4.0);
+        let far = stop(&mut g, "stib:2", "Midi", None, 4.1);
+        g.finish_feed(IngestionReport::default());
+        g.begin_feed("sncb", Some("sncb"));
+        let b = stop(&mut g, "sncb:8_1", "Bruxelles-Midi", Some("sncb:8"), 4.0005);
+        let other = stop(&mut g, "sncb:9", "Porte de Hal", None, 4.0004);
+        g.finish_feed(IngestionReport::default());
+        (g, [a, far, b, other])
+    }
+
+    #[test]
+    fn feed_ranges_and_namespace_follow_begin_and_finish() {
+        let (g, _) = two_feeds();
+        assert_eq!(g.feeds.len(), 2);
+        assert_eq!(g.feeds[0].nodes, 0..2);
+        assert_eq!(g.feeds[1].nodes, 2..4);
+        assert_eq!(g.ingestion_reports[1].feed, "sncb");
+        assert_eq!(g.open_feed_namespace(), None, "no feed open after finish");
+    }
+
+    #[test]
+    fn nearby_same_named_stops_are_linked_and_share_a_station() {
+        let (mut g, [a, far, b, other]) = two_feeds();
+        let links = g.link_cross_feed_stops(100.0, 0.5);
+        assert_eq!(links.linked, 1);
+        assert_eq!(links.merged_stations, 1);
+        assert_eq!(links.id_collisions, 0);
+        let linked = |from: NodeID, to: NodeID| {
+            g.edges[from.0]
+                .iter()
+                .any(|e| matches!(e, EdgeData::Street(s) if s.destination == to && s.foot))
+        };
+        assert!(linked(a, b) && linked(b, a));
+        assert!(!linked(a, other), "different name");
+        assert!(!linked(far, b), "out of radius");
+        let NodeData::TransitStop(s) = &g.nodes[b.0] else {
+            unreachable!()
+        };
+        assert_eq!(s.parent_station.as_deref(), Some("stib:1"));
+    }
+
+    #[test]
+    fn removed_feed_loses_its_edges_and_stops_but_not_the_other_feed() {
+        let (mut g, [a, far, b, other]) = two_feeds();
+        for (x, y) in [(a, b), (b, a), (far, other), (b, other)] {
+            g.add_edge(x, foot_connector_edge(x, y, 10));
+        }
+        let removed = g.remove_feed("stib").unwrap();
+        assert_eq!(removed.nodes, 0..2);
+        assert!(g.remove_feed("stib").is_none(), "already gone");
+        assert_eq!(g.feeds.len(), 1);
+        assert_eq!(g.ingestion_reports.len(), 1);
+        assert!(matches!(g.nodes[a.0], NodeData::OsmNode(_)));
+        assert!(g.edges[a.0].is_empty() && g.edges[far.0].is_empty());
+        let dests: Vec<NodeID> = g.edges[b.0]
+            .iter()
+            .map(|e| match e {
+                EdgeData::Street(s) => s.destination,
+                EdgeData::Transit(t) => t.destination,
+            })
+            .collect();
+        assert_eq!(dests, [other], "the edge into the removed stop is dropped");
+        let NodeData::TransitStop(s) = &g.nodes[b.0] else {
+            unreachable!()
+        };
+        assert_eq!(s.id, "sncb:8_1");
+
+        g.begin_feed("stib", Some("stib"));
+        let again = stop(&mut g, "stib:1", "GARE DU MIDI", None, 4.0);
+        g.finish_feed(IngestionReport::default());
+        assert_eq!(again, NodeID(4), "appended after the other feed");
+        assert_eq!(g.feed("stib").unwrap().nodes, 4..5);
+    }
+
+    #[test]
+    fn stop_ids_resolve_per_feed_and_by_namespaced_id() {
+        let (mut g, [a, _, b, _]) = two_feeds();
+        g.feeds[0].stops.insert("1".into(), a);
+        g.feeds[1].stops.insert("8_1".into(), b);
+        assert_eq!(g.gtfs_stop_node("stib", "1"), Some(a));
+        assert_eq!(g.gtfs_stop_node("sncb", "1"), None, "feed-scoped");
+        assert_eq!(g.gtfs_stop_node_by_id("sncb:8_1"), Some(b));
+        assert_eq!(g.gtfs_stop_node_by_id("8_1"), None, "namespaced feeds need the prefix");
+        assert_eq!(g.gtfs_stop_node_by_id("stib1"), None);
+    }
+
+    #[test]
+    fn colliding_ids_across_unnamespaced_feeds_are_counted() {
+        let mut g = Graph::new();
+        for feed in ["a", "b"] {
+            g.begin_feed(feed, None);
+            stop(&mut g, "1", "Centrale", None, 4.0);
+            g.finish_feed(IngestionReport::default());
+        }
+        assert_eq!(g.link_cross_feed_stops(0.0, 0.5).id_collisions, 1);
+    }
+
+    #[test]
+    fn similarity_ignores_particles_and_case() {
+        let t = |s| name_tokens(s);
+        assert_eq!(name_similarity(&t("GARE DU MIDI"), &t("Bruxelles-Midi")), 0.5);
+        assert_eq!(name_similarity(&t("Midi"), &t("Bruxelles-Midi")), 1.0);
+        assert_eq!(name_similarity(&t("Porte de Hal"), &t("Gare du Midi")), 0.0);
+    }
+
+    #[test]
+    fn validity_comes_from_feed_info_else_the_service_range() {
+        use crate::ingestion::gtfs::{RouteId, ServiceId, ServicePattern, TripInfo};
+        let d = |y, m, day| NaiveDate::from_ymd_opt(y, m, day).unwrap();
+        let mut g = Graph::new();
+        assert!(g.feed_validity_warning(d(2040, 1, 1)).is_none(), "no feeds, no warning");
+
+        g.begin_feed("stib", None);
+        g.set_feed_info_days(Some(date_to_days(d(2026, 1, 1))), Some(date_to_days(d(2026, 6, 30))));
+        g.finish_feed(IngestionReport::default());
+        g.begin_feed("sncb", None);
+        g.add_transit_services(vec![ServicePattern {
+            days_of_week: 0x7f,
+            start_date: date_to_days(d(2026, 3, 1)),
+            end_date: date_to_days(d(2026, 9, 30)),
+            added_dates: vec![date_to_days(d(2026, 12, 25))],
+            removed_dates: vec![],
+        }]);
+        g.add_transit_trips(vec![TripInfo {
+            trip_headsign: None,
+            route_id: RouteId(0),
+            service_id: ServiceId(0),
+            bikes_allowed: None,
+        }]);
+        g.finish_feed(IngestionReport::default());
+
+        let sncb = g.feed("sncb").unwrap().valid_days().unwrap();
+        assert_eq!(sncb, (date_to_days(d(2026, 3, 1)), date_to_days(d(2026, 12, 25))));
+        assert!(g.feed_validity_warning(d(2026, 2, 1)).is_none(), "stib covers it");
+        assert!(g.feed_validity_warning(d(2026, 12, 25)).is_none(), "sncb's added date");
+        let w = g.feed_validity_warning(d(2027, 1, 4)).unwrap();
+        assert_eq!(w.code, OUTSIDE_FEED_VALIDITY);
+        assert!(w.message.contains("stib: 2026-01-01 to 2026-06-30"), "{}", w.message);
+    }
+}
@@ -0,0 +1,274 @@
+//! Street-network island pruning. Small components disconnected from the main network
+//! (private courtyards, parking aisles) attract nearest-node snapping and then yield
+//! "no plan found"; removing them from the snap tree and dropping their edges keeps
+//! snapping, GTFS stop linking and edge snapping on the routable network. Components
+//! kept anyway (those holding a stop) are still reported to queries snapping onto them
+//! ([`Graph::on_street_island`]).
+
+use std::collections::HashSet;
+
+use super::Graph;
+use crate::structures::{EdgeData, NodeData, NodeID};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IslandStats {
+    pub components: usize,
+    pub pruned_components: usize,
+    pub pruned_nodes: usize,
+}
+
+impl Graph {
+    /// Undirected connected components over street edges. Nodes without any street edge
+    /// are left unassigned (`u32::MAX`). Returns `(component per node, component sizes)`.
+    pub(super) fn street_components(&self) -> (Vec<u32>, Vec<usize>) {
+        let n = self.nodes.len();
+        let mut adj: Vec<Vec<u32>> = vec![Vec::new(); n];
+        for (from, out) in self.edges.iter().enumerate() {
+            for e in out {
+                if let EdgeData::Street(s) = e {
+                    adj[from].push(s.destination.0 as u32);
+                    adj[s.destination.0].push(from as u32);
+                }
+            }
+        }
+
+        let mut comp = vec![u32::MAX; n];
+        let mut sizes = Vec::new();
+        let mut stack = Vec::new();
+        for start in 0..n {
+            if comp[start] != u32::MAX || adj[start].is_empty() {
+                continue;
+            }
+            let c = sizes.len() as u32;
+            let mut size = 0;
+            comp[start] = c;
+            stack.push(start as u32);
+            while let Some(u) = stack.pop() {
+                size += 1;
+                for &v in &adj[u as usize] {
+                    if comp[v as usize] == u32::MAX {
+                        comp[v as usize] = c;
+                        stack.push(v);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        (comp, sizes)
+    }
+
+    fn is_snap_indexed(&self, id: NodeID) -> bool {
+        let Some(NodeData::OsmNode(o)) = self.nodes.get(id.0) else {
+            return false;
+        };
+        self.nodes_tree
+            .contains(o.lat_lng.latitude, o.lat_lng.longitude, id)
+    }
+
+    /// Whether fewer than `min_nodes` street nodes are reachable from `node` and the graph
+    /// has others (a small network is not an island of itself). The search stops as soon
+    /// as `min_nodes` are seen, so a node of the main network costs little.
+    pub fn on_street_island(&self, node: NodeID, min_nodes: usize) -> bool {
+        let mut seen: HashSet<NodeID> = HashSet::from([node]);
+        let mut stack = vec![node];
+        match self.contracted.as_ref() {
+            Some(cg) => {
+                if cg.junction_of.get(node.0).is_none_or(|&ji| ji == u32::MAX) {
+                    return false;
+                }
+                while let Some(u) = stack.pop() {
+                    if seen.len() >= min_nodes {
+                        return false;
+                    }
+                    for se in &cg.adjacency[cg.junction_of[u.0] as usize] {
+                        seen.extend(se.nodes.iter().copied());
+                        let to = cg.junctions[se.to as usize];
+                        if seen.insert(to) {
+                            stack.push(to);
+                        }
+                    }
+                }
+            }
+            None => {
+                while let Some(u) = stack.pop() {
+                    if seen.len() >= min_nodes {
+                        return false;
+                    }
+                    for e in self.edges.get(u.0).into_iter().flatten() {
+                        if let EdgeData::Street(s) = e
+                            && seen.insert(s.destination)
+                        {
+                            stack.push(s.destination);
+                        }
+                    }
+                }
+            }
+        }
+        let total = self.contracted.as_ref().map_or(self.nodes.len(), |cg| cg.junction_of.len());
+        seen.len() < min_nodes && seen.len() < total
+    }
+
+    /// Drop street components smaller than `min_nodes`: their nodes leave the snap tree
+    /// and their edges are removed (node slots stay so `NodeID`s are stable). The largest
+    /// component is always kept, and a component holding a transit stop or an unindexed
+    /// platform node is never pruned. Run on the OSM graph, before GTFS stop linking.
+    pub fn prune_street_islands(&mut self, min_nodes: usize) -> IslandStats {
+        let (comp, sizes) = self.street_components();
+        let mut stats = IslandStats {
+            components: sizes.len(),
+            ..Default::default()
+        };
+        if min_nodes == 0 || sizes.len() <= 1 {
+            return stats;
+        }
+        let largest = (0..sizes.len()).max_by_key(|&c| sizes[c]).unwrap_or(0);
+
+        let mut members: Vec<Vec<NodeID>> = vec![Vec::new(); sizes.len()];
+        for (i, &c) in comp.iter().enumerate() {
+            if c != u32::MAX && sizes[c as usize] < min_nodes && c as usize != largest {
+                members[c as usize].push(NodeID(i));
+            }
+        }
+
+        let mut pruned = vec![false; self.nodes.len()];
+        for nodes in members.iter().filter(|m| !m.is_empty()) {
+            let prunable = nodes.iter().all(|&id| {
+                matches!(self.nodes[id.0], NodeData::OsmNode(_)) && self.is_snap_indexed(id)
+            });
+            if !prunable {
+                continue;
+            }
+            for &id in nodes {
+                if let NodeData::OsmNode(o) = &self.nodes[id.0] {
+                    self.nodes_tree
+                        .remove(o.lat_lng.latitude, o.lat_lng.longitude, id);
+                }
+                self.edges[id.0].clear();
+                pruned[id.0] = true;
+            }
+            stats.pruned_components += 1;
+            stats.pruned_nodes += nodes.len();
+        }
+        if stats.pruned_nodes > 0 {
+            self.node_levels.retain(|id, _| !pruned[id.0]);
+            self.connector_edges
+                .retain(|(a, b), _| !pruned[a.0] && !pruned[b.0]);
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::cost::VarGen;
+    use crate::structures::{BikeAttrs, LatLng, OsmNodeData, StreetEdgeData};
+
+    fn node(g: &mut Graph, i: usize, lat: f64, lon: f64) -> NodeID {
+        g.add_node(NodeData::OsmNode(OsmNodeData {
+            eid: format!("map#osm#{i}"),
+            lat_lng: LatLng {
+                latitude: lat,
+                longitude: lon,
+            },
+        }))
+    }
+
+    fn street(g: &mut Graph, a: NodeID, b: NodeID) {
+        for (o, d) in [(a, b), (b, a)] {
+            g.add_edge(
+                o,
+                EdgeData::Street(StreetEdgeData {
+                    origin: o,
+                    destination: d,
+                    partial: false,
+                    length: 100,
+                    foot: true,
+                    bike: true,
+                    car: true,
+                    attrs: BikeAttrs::road_default(),
+                    elev_delta: 0,
+                    surface_speed: 100,
+                    var_gen: VarGen::NONE,
+                }),
+            );
+        }
+    }
+
+    fn graph_with_node_pair() -> Graph {
+        let mut g = Graph::new();
+        let (a, b) = (node(&mut g, 0, 50.0, 4.0), node(&mut g, 1, 50.0, 4.001));
+        street(&mut g, a, b);
+        g
+    }
+
+    /// A 4-node main line plus a 2-node courtyard right next to the query point.
+    fn graph_with_island() -> (Graph, Vec<NodeID>, [NodeID; 2]) {
+        let mut g = Graph::new();
+        let main: Vec<NodeID> = (0..4)
+            .map(|i| node(&mut g, i, 50.0, 4.0 + i as f64 * 0.001))
+            .collect();
+        for w in main.windows(2) {
+            street(&mut g, w[0], w[1]);
+        }
+        let a = node(&mut g, 10, 50.01, 4.0);
+        let b = node(&mut g, 11, 50.01, 4.0005);
+        street(&mut g, a, b);
+        (g, main, [a, b])
+    }
+
+    #[test]
+    fn small_island_is_removed_from_snapping() {
+        let (mut g, main, island) = graph_with_island();
+        assert_eq!(g.nearest_node(50.0101, 4.0), Some(island[0]));
+
+        let stats = g.prune_street_islands(3);
+        assert_eq!(stats.components, 2);
+        assert_eq!(stats.pruned_components, 1);
+        assert_eq!(stats.pruned_nodes, 2);
+
+        assert!(g.out_edges(island[0]).is_empty());
+        assert!(main.contains(&g.nearest_node(50.0101, 4.0).unwrap()));
+        assert_eq!(g.out_edges(main[1]).len(), 2, "main component untouched");
+    }
+
+    #[test]
+    fn largest_component_survives_even_below_threshold() {
+        let (mut g, main, _) = graph_with_island();
+        let stats = g.prune_street_islands(100);
+        assert_eq!(stats.pruned_nodes, 2);
+        assert!(!g.out_edges(main[0]).is_empty());
+    }
+
+    #[test]
+    fn zero_threshold_disables_pruning() {
+        let (mut g, _, island) = graph_with_island();
+        assert_eq!(g.prune_street_islands(0).pruned_nodes, 0);
+        assert!(!g.out_edges(island[0]).is_empty());
+    }
+
+    #[test]
+    fn island_probe_stops_at_the_threshold() {
+        let (g, main, island) = graph_with_island();
+        assert!(g.on_street_island(island[0], 3));
+        assert!(!g.on_street_island(island[0], 2));
+        assert!(!g.on_street_island(main[2], 4));
+        assert!(g.on_street_island(main[2], 5));
+        assert!(!graph_with_node_pair().on_street_island(NodeID(0), 40), "the whole network");
+    }
+
+    #[test]
+    fn island_with_unindexed_platform_node_is_kept() {
+        let (mut g, _, island) = graph_with_island();
+        let plat = g.add_osm_node_unindexed(OsmNodeData {
+            eid: "map#osm#20".into(),
+            lat_lng: LatLng {
+                latitude: 50.01,
+                longitude: 4.001,
+            },
+        });
+        street(&mut g, island[1], plat);
+        assert_eq!(g.prune_street_islands(10).pruned_nodes, 0);
+        assert!(!g.out_edges(island[0]).is_empty());
+    }
+}
@@ -0,0 +1,440 @@
+//! ALT (A*, Landmarks, Triangle inequality) lower bounds for the street search. A few
+//! landmark junctions are picked by farthest-point selection and a street-length table
+//! from each is stored; `|d(L,t) - d(L,v)|` then bounds the remaining length from `v` to
+//! `t` far tighter than the crow-flies distance around rivers, rail corridors and parks.
+//!
+//! Tables are built over the UNDIRECTED all-mode contracted graph, each pair of
+//! junctions weighted by its shortest super-edge in either direction: a superset of
+//! every mode's directed graph, so the bound stays admissible for walk, bike and drive.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+use super::Graph;
+use super::contraction::ContractedGraph;
+use crate::structures::NodeID;
+use crate::structures::cost::RoutingMode;
+
+/// Lower bound used to key the A* street search on remaining time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreetHeuristic {
+    /// Straight-line distance at the mode's top speed.
+    #[default]
+    Geometric,
+    /// `max(geometric, ALT)`; needs [`Graph::prepare_landmarks`], else geometric.
+    Landmarks,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Landmarks {
+    /// Landmark junction indices into the contracted graph, in selection order.
+    pub landmarks: Vec<u32>,
+    /// `dist[ji * k + l]` = street metres between landmark `l` and junction `ji`;
+    /// `u32::MAX` when unreachable.
+    dist: Vec<u32>,
+}
+
+impl Landmarks {
+    /// Pick up to `count` landmarks by farthest-point selection and compute their tables.
+    /// A junction no landmark reaches is picked first, so every component gets one.
+    pub fn build(cg: &ContractedGraph, count: usize) -> Self {
+        Self::build_for(cg, count, None)
+    }
+
+    /// As [`Self::build`], over only the super-edges `mode` can ride end to end (walk:
+    /// foot, bike: bike or pushed on foot, drive: car): a superset of that mode's
+    /// graph, so the bound is admissible for it alone.
+    pub fn build_for(cg: &ContractedGraph, count: usize, mode: Option<RoutingMode>) -> Self {
+        let n = cg.junctions.len();
+        if n == 0 || count == 0 {
+            return Self::default();
+        }
+        let adj = undirected_lengths(cg, mode);
+        let mut landmarks: Vec<u32> = Vec::with_capacity(count);
+        let mut tables: Vec<Vec<u32>> = Vec::with_capacity(count);
+        let mut nearest = vec![u32::MAX; n];
+        // Seed: the farthest junction from junction 0 (a periphery node).
+        let mut next = argmax(&dijkstra(&adj, 0), None);
+        while landmarks.len() < count.min(n) {
+            let Some(l) = next else { break };
+            let d = dijkstra(&adj, l);
+            for (m, &x) in nearest.iter_mut().zip(&d) {
+                *m = (*m).min(x);
+            }
+            landmarks.push(l);
+            tables.push(d);
+            next = nearest
+                .iter()
+                .position(|&x| x == u32::MAX)
+                .map(|i| i as u32)
+                .or_else(|| argmax(&nearest, Some(&landmarks)));
+        }
+
+        let k = landmarks.len();
+        let mut dist = vec![u32::MAX; n * k];
+        for (l, t) in tables.iter().enumerate() {
+            for (ji, &d) in t.iter().enumerate() {
+                dist[ji * k + l] = d;
+            }
+        }
+        Self { landmarks, dist }
+    }
+
+    pub fn count(&self) -> usize {
+        self.landmarks.len()
+    }
+
+    /// Per-landmark distances of junction `ji`, or `None` when out of range.
+    pub fn row(&self, ji: u32) -> Option<&[u32]> {
+        let k = self.count();
+        let s = (ji as usize).checked_mul(k)?;
+        (k > 0).then(|| self.dist.get(s..s + k)).flatten()
+    }
+
+    /// Triangle-inequality lower bound (m) between two junction rows. Landmarks missing
+    /// either end contribute nothing.
+    pub fn lower_bound(a: &[u32], b: &[u32]) -> u32 {
+        a.iter()
+            .zip(b)
+            .filter(|&(&x, &y)| x != u32::MAX && y != u32::MAX)
+            .map(|(&x, &y)| x.abs_diff(y))
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn heap_bytes(&self) -> usize {
+        self.dist.capacity() * 4 + self.landmarks.capacity() * 4
+    }
+}
+
+fn undirected_lengths(cg: &ContractedGraph, mode: Option<RoutingMode>) -> Vec<Vec<(u32, u32)>> {
+    let rideable = |s: &super::contraction::Seg| match mode {
+        None => true,
+        Some(RoutingMode::Walk) => s.edge.foot,
+        Some(RoutingMode::Bike) => s.edge.bike || s.edge.foot,
+        Some(RoutingMode::Drive) => s.edge.car,
+    };
+    let mut adj: Vec<Vec<(u32, u32)>> = vec![Vec::new(); cg.junctions.len()];
+    for (ji, ses) in cg.adjacency.iter().enumerate() {
+        for se in ses {
+            if !cg.seg_slice(se).iter().all(rideable) {
+                continue;
+            }
+            let w = cg.super_edge_length(se).min(u32::MAX as usize - 1) as u32;
+            adj[ji].push((se.to, w));
+            adj[se.to as usize].push((ji as u32, w));
+        }
+    }
+    adj
+}
+
+fn dijkstra(adj: &[Vec<(u32, u32)>], src: u32) -> Vec<u32> {
+    let mut dist = vec![u32::MAX; adj.len()];
+    let mut heap = BinaryHeap::new();
+    dist[src as usize] = 0;
+    heap.push(Reverse((0u32, src)));
+    while let Some(Reverse((d, u))) = heap.pop() {
+        if d > dist[u as usize] {
+            continue;
+        }
+        for &(v, w) in &adj[u as usize] {
+            let nd = d.saturating_add(w);
+            if nd < dist[v as usize] {
+                dist[v as usize] = nd;
+                heap.push(Reverse((nd, v)));
+            }
+        }
+    }
+    dist
+}
+
+/// Index of the largest finite entry, skipping `exclude`.
+fn argmax(d: &[u32], exclude: Option<&[u32]>) -> Option<u32> {
+    d.iter()
+        .enumerate()
+        .filter(|&(i, &x)| x != u32::MAX && !exclude.is_some_and(|e| e.contains(&(i as u32))))
+        .max_by_key(|&(_, &x)| x)
+        .map(|(i, _)| i as u32)
+}
+
+impl Graph {
+    /// Build the ALT tables over the contracted graph (idempotent). No-op without one.
+    pub fn prepare_landmarks(&mut self, count: usize) {
+        if self.landmarks.as_ref().is_some_and(|l| l.count() == count) {
+            return;
+        }
+        let Some(cg) = self.contracted.as_ref() else {
+            return;
+        };
+        let lm = Landmarks::build(cg, count);
+        tracing::info!(
+            landmarks = lm.count(),
+            junctions = cg.junctions.len(),
+            bytes = lm.heap_bytes(),
+            "ALT landmark tables built"
+        );
+        self.landmarks = Some(lm);
+    }
+
+    pub fn set_street_heuristic(&mut self, h: StreetHeuristic) {
+        self.raptor.street_heuristic = h;
+    }
+
+    /// Landmark row of `node` for a `mode` search when `node` is a junction: from the
+    /// query's search profile when one applies, else from the graph's ALT tables when
+    /// `street_heuristic: landmarks`.
+    pub(super) fn landmark_row(&self, node: NodeID, mode: RoutingMode) -> Option<&[u32]> {
+        let lm = match self.selected_search_tables(mode) {
+            Some(lm) => lm,
+            None if self.raptor.street_heuristic == StreetHeuristic::Landmarks => self.landmarks.as_ref()?,
+            None => return None,
+        };
+        let ji = *self.contracted.as_ref()?.junction_of.get(node.0)?;
+        if ji == u32::MAX {
+            return None;
+        }
+        lm.row(ji)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::cost::VarGen;
+    use crate::structures::{BikeAttrs, EdgeData, LatLng, NodeData, OsmNodeData, StreetEdgeData};
+
+    fn node(g: &mut Graph, i: usize, lat: f64, lon: f64) -> NodeID {
+        g.add_node(NodeData::OsmNode(OsmNodeData {
+            eid: format!("map#osm#{i}"),
+            lat_lng: LatLng {
+                latitude: lat,
+                longitude: lon,
+            },
+        }))
+    }
+
+    fn street(g: &mut Graph, a: NodeID, b: NodeID, len: usize) {
+        for (o, d) in [(a, b), (b, a)] {
+            g.add_edge(
+                o,
+                EdgeData::Street(StreetEdgeData {
+                    origin: o,
+                    destination: d,
+                    partial: false,
+                    length: len,
+                    foot: true,
+                    bike: true,
+                    car: false,
+                    attrs: BikeAttrs::road_default(),
+                    elev_delta: 0,
+                    surface_speed: 100,
+                    var_gen: VarGen::NONE,
+                }),
+            );
+        }
+    }
+
+    /// A 4x4 grid of 100 m blocks plus an isolated pair of junctions.
+    fn grid() -> (Graph, Vec<NodeID>) {
+        let mut g = Graph::new();
+        let mut ids = Vec::new();
+        for r in 0..4 {
+            for c in 0..4 {
+                ids.push(node(
+                    &mut g,
+                    r * 4 + c,
+                    50.0 + r as f64 * 0.0009,
+                    4.0 + c as f64 * 0.0014,
+                ));
+            }
+        }
+        for r in 0..4 {
+            for c in 0..4 {
+                let i = r * 4 + c;
+                if c + 1 < 4 {
+                    street(&mut g, ids[i], ids[i + 1], 100);
+                }
+                if r + 1 < 4 {
+                    street(&mut g, ids[i], ids[i + 4], 100);
+                }
+            }
+        }
+        let x = node(&mut g, 100, 51.0, 5.0);
+        let y = node(&mut g, 101, 51.0, 5.001);
+        street(&mut g, x, y, 70);
+        ids.extend([x, y]);
+        g.build_raptor_index();
+        g.contracted = Some(ContractedGraph::from_graph_union(&g));
+        (g, ids)
+    }
+
+    #[test]
+    fn bound_never_exceeds_true_street_distance() {
+        let (g, ids) = grid();
+        let cg = g.contracted.as_ref().unwrap();
+        let lm = Landmarks::build(cg, 4);
+        assert_eq!(lm.count(), 4);
+        let adj = undirected_lengths(cg, None);
+        // Degree-2 grid corners are contracted away; check every remaining junction pair.
+        let js: Vec<u32> = ids
+            .iter()
+            .map(|n| cg.junction_of[n.0])
+            .filter(|&j| j != u32::MAX)
+            .collect();
+        assert!(js.len() >= 12);
+        for &ja in &js {
+            let exact = dijkstra(&adj, ja);
+            for &jb in &js {
+                let lb = Landmarks::lower_bound(lm.row(ja).unwrap(), lm.row(jb).unwrap());
+                assert!(
+                    lb <= exact[jb as usize],
+                    "ALT bound {lb} > {}",
+                    exact[jb as usize]
+                );
+            }
+        }
+        let (a, b) = (cg.junction_of[ids[1].0], cg.junction_of[ids[14].0]);
+        assert!(Landmarks::lower_bound(lm.row(a).unwrap(), lm.row(b).unwrap()) > 0);
+    }
+
+    #[test]
+    fn every_component_gets_a_landmark() {
+        let (g, ids) = grid();
+        let cg = g.contracted.as_ref().unwrap();
+        let lm = Landmarks::build(cg, 2);
+        let island = [cg.junction_of[ids[16].0], cg.junction_of[ids[17].0]];
+        assert!(lm.landmarks.iter().any(|l| island.contains(l)));
+        assert!(lm.landmarks.iter().any(|l| !island.contains(l)));
+    }
+
+    #[test]
+    fn landmark_row_requires_the_heuristic_switch() {
+        let (mut g, ids) = grid();
+        g.prepare_landmarks(3);
+        assert!(g.landmark_row(ids[0], RoutingMode::Walk).is_none(), "geometric by default");
+        g.set_street_heuristic(StreetHeuristic::Landmarks);
+        assert_eq!(g.landmark_row(ids[1], RoutingMode::Walk).map(<[u32]>::len), Some(3));
+    }
+
+    #[test]
+    fn search_profile_tables_apply_only_to_their_mode_and_only_when_selected() {
+        use super::super::search_profiles::{self, SearchProfileSpec};
+        let (mut g, ids) = grid();
+        let spec = |name: &str, mode, landmarks| SearchProfileSpec { name: name.into(), mode, landmarks };
+        g.prepare_search_profiles(&[spec("any", None, 3), spec("car", Some(RoutingMode::Drive), 2)]);
+        assert!(g.landmark_row(ids[1], RoutingMode::Walk).is_none(), "nothing selected");
+        {
+            let _s = search_profiles::begin(g.search_profile_index("any"));
+            assert_eq!(g.landmark_row(ids[1], RoutingMode::Walk).map(<[u32]>::len), Some(3));
+        }
+        {
+            let _s = search_profiles::begin(g.search_profile_index("car"));
+            assert!(g.landmark_row(ids[1], RoutingMode::Walk).is_none(), "a car table is no walk bound");
+            let row = g.landmark_row(ids[1], RoutingMode::Drive).unwrap();
+            assert!(row.contains(&u32::MAX), "the grid has no car streets to reach it over");
+        }
+        assert_eq!(search_profiles::selected(), None);
+
+        g.prepare_search_profiles(&[spec("car", Some(RoutingMode::Drive), 2)]);
+        assert_eq!(g.search_profile_index("car"), Some(0));
+        assert_eq!(g.search_profile_index("any"), None, "unlisted profiles are dropped");
+    }
+
+    /// Two parallel streets 55 m apart joined only at the far end, with forked dead ends:
+    /// crow-flies says the destination is next door, the street network says 1 km.
+    #[test]
+    fn alt_keeps_the_front_and_expands_no_more_labels_around_a_barrier() {
+        use crate::structures::cost::{Epsilon, LegRole, RoutingMode};
+        let mut g = Graph::new();
+        let mut top = Vec::new();
+        let mut bottom = Vec::new();
+        for i in 0..6 {
+            let lon = 4.0 + i as f64 * 0.0014;
+            top.push(node(&mut g, i, 50.0005, lon));
+            bottom.push(node(&mut g, 10 + i, 50.0, lon));
+        }
+        let spurs: Vec<NodeID> = (1..5)
+            .map(|i| node(&mut g, 20 + i, 50.0014, 4.0 + i as f64 * 0.0014))
+            .collect();
+        let leaves: Vec<[NodeID; 2]> = (1..5)
+            .map(|i| {
+                let lon = 4.0 + i as f64 * 0.0014;
+                [
+                    node(&mut g, 30 + i, 50.0023, lon - 0.0003),
+                    node(&mut g, 40 + i, 50.0023, lon + 0.0003),
+                ]
+            })
+            .collect();
+        g.build_raptor_index();
+        for w in 0..5 {
+            street(&mut g, top[w], top[w + 1], 100);
+            street(&mut g, bottom[w], bottom[w + 1], 100);
+        }
+        street(&mut g, top[5], bottom[5], 55);
+        for (i, &s) in spurs.iter().enumerate() {
+            street(&mut g, top[i + 1], s, 100);
+            for &l in &leaves[i] {
+                street(&mut g, s, l, 100);
+            }
+        }
+        g.contracted = Some(ContractedGraph::from_graph_union(&g));
+
+        let bike = g.default_bike_cost();
+        let w = g.raptor.cost_weights;
+        let eps = Epsilon::uniform(0.0, 0.0);
+        let run = |g: &Graph| {
+            g.multiobj_search(
+                top[0],
+                bottom[0],
+                RoutingMode::Walk,
+                LegRole::Neutral,
+                &bike,
+                &w,
+                &eps,
+                f64::INFINITY,
+                true,
+            )
+        };
+        let costs = |r: &super::super::multiobj::MultiObjResult| {
+            r.front.iter().map(|p| p.cost).collect::<Vec<_>>()
+        };
+        let geo = run(&g);
+        g.prepare_landmarks(4);
+        g.set_street_heuristic(StreetHeuristic::Landmarks);
+        let alt = run(&g);
+        assert_eq!(
+            costs(&geo),
+            costs(&alt),
+            "ALT must not change the Pareto front"
+        );
+        let (o, d) = (
+            g.landmark_row(top[0], RoutingMode::Walk).unwrap(),
+            g.landmark_row(bottom[0], RoutingMode::Walk).unwrap(),
+        );
+        assert!(
+            Landmarks::lower_bound(o, d) > 1000,
+            "crow-flies is only 55 m"
+        );
+        assert!(
+            alt.expansions <= geo.expansions,
+            "ALT expands no more labels ({} vs {})",
+            alt.expansions,
+            geo.expansions
+        );
+
+        // The same tables through a walk search profile, with the graph heuristic off.
+        g.set_street_heuristic(StreetHeuristic::Geometric);
+        g.prepare_search_profiles(&[super::super::SearchProfileSpec {
+            name: "walk".into(),
+            mode: Some(RoutingMode::Walk),
+            landmarks: 4,
+        }]);
+        let _s = super::super::search_profiles::begin(Some(0));
+        let profiled = run(&g);
+        assert_eq!(costs(&geo), costs(&profiled));
+        assert!(profiled.expansions <= geo.expansions);
+    }
+}
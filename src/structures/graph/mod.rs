@@ -1,24 +1,40 @@
 use std::collections::HashMap;
 
-use kdtree::{KdTree, distance::squared_euclidean};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     ingestion::gtfs::{AgencyId, AgencyInfo, RouteId, RouteInfo, TripId, TripInfo},
-    ingestion::osm::{ConnectorCost, PlatformIndex},
+    ingestion::osm::{
+        ADDRESS_LABEL_RADIUS_M, ConnectorCost, OsmAddressIndex, PARK_RIDE_RADIUS_M,
+        PARK_RIDE_UNKNOWN_CAPACITY_SECS, ParkingFacility, ParkingIndex, ParkingKind, PlatformIndex,
+        StopAmenities, StopAmenityIndex,
+    },
     structures::{Connector, EdgeData, LatLng, NodeData, NodeID, OsmNodeData, StreetEdgeData},
 };
 
-pub use raptor_index::{RaptorIndex, StationInfo, StationLine};
+pub use raptor_index::{RaptorIndex, RouteFigures, RouteOverride, StationInfo, StationLine};
 
 mod bike_cost;
+mod build_info;
+mod builder;
+mod car_speed;
 pub mod contraction;
 mod edge_index;
+mod eid;
+mod elevators;
+mod estimator;
+mod feeds;
+mod islands;
+mod landmarks;
 pub mod latency_profile;
 mod multiobj;
 mod multiobj_plan;
 mod path_distribution;
 mod platform_reach;
+mod point_index;
+pub mod query_budget;
+pub mod query_profile;
+pub mod query_trace;
 mod railway;
 mod raptor_access;
 mod raptor_backward;
@@ -29,18 +45,44 @@ mod raptor_plan;
 mod raptor_route;
 mod realtime_match;
 mod representatives;
+pub mod rush_hour;
+pub mod search_profiles;
+mod shapes;
+mod stable_id;
+mod stop_links;
+mod street_dedup;
+mod transfer_edges;
 mod street_enrich;
 mod transit;
 mod travel_map;
+mod validate;
+pub mod what_if;
 
 pub use bike_cost::{BikeCost, PrevCtx};
+pub use build_info::{BuildInfo, SourceInfo, redact_location};
+pub use builder::{EVERY_DAY, GraphBuilder};
+pub use car_speed::CarSpeeds;
+pub use eid::{EidKey, EidMapper, OSM_EID_SOURCE};
+pub use estimator::EstimatorFactors;
+pub use feeds::{CrossFeedLinks, FeedRecord, OUTSIDE_FEED_VALIDITY};
+pub use islands::IslandStats;
+pub use landmarks::{Landmarks, StreetHeuristic};
 pub use platform_reach::ConnectorReach;
+pub use point_index::PointIndex;
+pub use query_profile::RoutingProfile;
 pub use raptor_access::StreetProfile;
 pub use raptor_cch::CchAccess;
+pub use search_profiles::{SearchProfile, SearchProfileSpec};
+pub use stable_id::{STABLE_ROUTE_PREFIX, STABLE_STOP_PREFIX, STABLE_TRIP_PREFIX, StableRef};
+pub use shapes::{GeometryLevel, NO_SHAPE, ShapeArena, ShapeStats};
 pub use raptor_route::{OnboardRide, OnboardSeed, QueryEndpoints};
+pub use stop_links::StopLinkStats;
+pub use transfer_edges::TransferEdgeStats;
 pub use realtime_match::{MatchParams, ScheduledArrival, best_match};
-pub use transit::StationBackup;
+pub use transit::{StationBackup, StopDeparture, TimetableTrip};
 pub use travel_map::{TravelAggregation, TravelCell};
+pub use what_if::{ResolvedWhatIf, WhatIf};
+pub use validate::{Severity, ValidationIssue, ValidationReport};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Endpoint {
@@ -59,13 +101,19 @@ pub enum GraphError {
     NodeNotFoundError(NodeID),
 }
 
+/// Immutable once built: every query method takes `&self`, and per-query state
+/// (budgets, profiles, traces, what-if closures, scratch buffers) lives in
+/// thread-locals or the search itself, never in the graph. Servers share one
+/// `Arc<Graph>` across threads without locking and swap in a rebuilt graph whole.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Graph {
     nodes: Vec<NodeData>,
     edges: Vec<Vec<EdgeData>>,
-    nodes_tree: KdTree<f64, NodeID, [f64; 2]>,
-    id_mapper: HashMap<String, NodeID>,
+    nodes_tree: PointIndex<NodeID>,
+    id_mapper: EidMapper,
     pub raptor: RaptorIndex,
+    /// Distinct transit shape polylines; patterns refer to them by id.
+    shapes: ShapeArena,
     #[serde(skip, default)]
     edge_index: edge_index::EdgeIndex,
     /// Serialized; its serde-skipped `seg_index` R-tree is rebuilt post-load.
@@ -74,38 +122,97 @@ pub struct Graph {
     /// Serialized only via the OSM view (`osm.bin`); skipped in `graph.bin`.
     #[serde(skip, default)]
     platforms: PlatformIndex,
+    /// OSM `addr:*` side index labelling street places; in both graph.bin and osm.bin.
+    addresses: OsmAddressIndex,
+    /// OSM stop facilities (`stop_amenities`); in both graph.bin and osm.bin.
+    amenities: StopAmenityIndex,
+    /// OSM car and bicycle parking (`parking`); in both graph.bin and osm.bin.
+    parking: ParkingIndex,
     #[serde(skip, default)]
     node_levels: HashMap<NodeID, i16>,
     #[serde(skip, default)]
     connector_edges: HashMap<(NodeID, NodeID), Connector>,
+    /// OSM id (`node/<id>` / `way/<id>`) per elevator connector edge.
+    #[serde(skip, default)]
+    elevator_edges: HashMap<(NodeID, NodeID), String>,
     #[serde(skip, default)]
     connector_cost: ConnectorCost,
     #[serde(skip, default)]
     pub cch: Option<raptor_cch::CchAccess>,
+    /// ALT distance tables; rebuilt at startup when `street_heuristic: landmarks`.
+    #[serde(skip, default)]
+    pub landmarks: Option<landmarks::Landmarks>,
+    /// Named precomputed street-search profiles, built with the graph and persisted.
+    search_profiles: Vec<search_profiles::SearchProfile>,
+    /// Per-feed GTFS quality counters from the build, in ingestion order.
+    pub ingestion_reports: Vec<crate::ingestion::gtfs::IngestionReport>,
+    /// GTFS feeds in ingestion order, with the id ranges each one owns.
+    pub feeds: Vec<FeedRecord>,
+    /// Inputs and versions the graph was built from; see `dataInfo`.
+    build_info: BuildInfo,
+    #[serde(skip, default)]
+    feed_open: bool,
 }
 
+// Queries run concurrently on a shared graph; a `Cell`/`RefCell` field would break this.
+const _: fn() = || {
+    fn shared<T: Send + Sync>() {}
+    shared::<Graph>();
+};
+
 #[derive(Serialize)]
 struct OsmView<'a> {
     nodes: &'a Vec<NodeData>,
     edges: &'a Vec<Vec<EdgeData>>,
-    nodes_tree: &'a KdTree<f64, NodeID, [f64; 2]>,
-    id_mapper: &'a HashMap<String, NodeID>,
+    nodes_tree: &'a PointIndex<NodeID>,
+    id_mapper: &'a EidMapper,
     platforms: &'a PlatformIndex,
+    addresses: &'a OsmAddressIndex,
+    amenities: &'a StopAmenityIndex,
+    parking: &'a ParkingIndex,
     node_levels: &'a HashMap<NodeID, i16>,
     connector_edges: &'a HashMap<(NodeID, NodeID), Connector>,
+    elevator_edges: &'a HashMap<(NodeID, NodeID), String>,
+    build_info: &'a BuildInfo,
 }
 
 #[derive(Deserialize)]
 struct OsmOwned {
     nodes: Vec<NodeData>,
     edges: Vec<Vec<EdgeData>>,
-    nodes_tree: KdTree<f64, NodeID, [f64; 2]>,
-    id_mapper: HashMap<String, NodeID>,
+    nodes_tree: PointIndex<NodeID>,
+    id_mapper: EidMapper,
     platforms: PlatformIndex,
+    addresses: OsmAddressIndex,
+    amenities: StopAmenityIndex,
+    parking: ParkingIndex,
     #[serde(default)]
     node_levels: HashMap<NodeID, i16>,
     #[serde(default)]
     connector_edges: HashMap<(NodeID, NodeID), Connector>,
+    #[serde(default)]
+    elevator_edges: HashMap<(NodeID, NodeID), String>,
+    #[serde(default)]
+    build_info: BuildInfo,
+}
+
+/// `transit.bin`: the OSM view plus the GTFS feeds merged into it, before `finalize`.
+#[derive(Serialize)]
+struct TransitView<'a> {
+    osm: OsmView<'a>,
+    raptor: &'a RaptorIndex,
+    shapes: &'a ShapeArena,
+    ingestion_reports: &'a Vec<crate::ingestion::gtfs::IngestionReport>,
+    feeds: &'a Vec<FeedRecord>,
+}
+
+#[derive(Deserialize)]
+struct TransitOwned {
+    osm: OsmOwned,
+    raptor: RaptorIndex,
+    shapes: ShapeArena,
+    ingestion_reports: Vec<crate::ingestion::gtfs::IngestionReport>,
+    feeds: Vec<FeedRecord>,
 }
 
 pub static MAX_TRANSFER_DISTANCE_M: f64 = 1000.0;
@@ -123,16 +230,27 @@ impl Graph {
         Graph {
             nodes: Vec::new(),
             edges: Vec::new(),
-            nodes_tree: KdTree::new(2),
-            id_mapper: HashMap::new(),
+            nodes_tree: PointIndex::default(),
+            id_mapper: EidMapper::default(),
             raptor: RaptorIndex::new(),
+            shapes: ShapeArena::default(),
             edge_index: edge_index::EdgeIndex::default(),
             contracted: None,
             platforms: PlatformIndex::default(),
+            addresses: OsmAddressIndex::default(),
+            amenities: StopAmenityIndex::default(),
+            parking: ParkingIndex::default(),
             node_levels: HashMap::new(),
             connector_edges: HashMap::new(),
+            elevator_edges: HashMap::new(),
             connector_cost: ConnectorCost::default(),
             cch: None,
+            landmarks: None,
+            search_profiles: Vec::new(),
+            ingestion_reports: Vec::new(),
+            feeds: Vec::new(),
+            build_info: BuildInfo::default(),
+            feed_open: false,
         }
     }
 
@@ -146,35 +264,92 @@ impl Graph {
     }
 
     pub fn to_osm_postcard(&self) -> Result<Vec<u8>, String> {
-        let view = OsmView {
+        postcard::to_allocvec(&self.osm_view()).map_err(|e| format!("Failed to serialize OSM graph: {e}"))
+    }
+
+    fn osm_view(&self) -> OsmView<'_> {
+        OsmView {
             nodes: &self.nodes,
             edges: &self.edges,
             nodes_tree: &self.nodes_tree,
             id_mapper: &self.id_mapper,
             platforms: &self.platforms,
+            addresses: &self.addresses,
+            amenities: &self.amenities,
+            parking: &self.parking,
             node_levels: &self.node_levels,
             connector_edges: &self.connector_edges,
-        };
-        postcard::to_allocvec(&view).map_err(|e| format!("Failed to serialize OSM graph: {e}"))
+            elevator_edges: &self.elevator_edges,
+            build_info: &self.build_info,
+        }
     }
 
     pub fn from_osm_postcard(bytes: &[u8]) -> Result<Graph, String> {
         let o: OsmOwned = postcard::from_bytes(bytes)
             .map_err(|e| format!("Failed to deserialize OSM graph: {e}"))?;
-        Ok(Graph {
+        Ok(Graph::from_osm_owned(o))
+    }
+
+    /// The merged feeds on top of the OSM view, for replacing one feed in place without
+    /// re-reading the others. Only meaningful before `finalize`.
+    pub fn to_transit_postcard(&self) -> Result<Vec<u8>, String> {
+        let view = TransitView {
+            osm: self.osm_view(),
+            raptor: &self.raptor,
+            shapes: &self.shapes,
+            ingestion_reports: &self.ingestion_reports,
+            feeds: &self.feeds,
+        };
+        postcard::to_allocvec(&view).map_err(|e| format!("Failed to serialize transit graph: {e}"))
+    }
+
+    pub fn from_transit_postcard(bytes: &[u8]) -> Result<Graph, String> {
+        let t: TransitOwned = postcard::from_bytes(bytes)
+            .map_err(|e| format!("Failed to deserialize transit graph: {e}"))?;
+        let mut g = Graph::from_osm_owned(t.osm);
+        g.raptor = t.raptor;
+        g.shapes = t.shapes;
+        g.ingestion_reports = t.ingestion_reports;
+        g.feeds = t.feeds;
+        Ok(g)
+    }
+
+    fn from_osm_owned(o: OsmOwned) -> Graph {
+        Graph {
             nodes: o.nodes,
             edges: o.edges,
             nodes_tree: o.nodes_tree,
             id_mapper: o.id_mapper,
             raptor: RaptorIndex::new(),
+            shapes: ShapeArena::default(),
             edge_index: edge_index::EdgeIndex::default(),
             contracted: None,
             platforms: o.platforms,
+            addresses: o.addresses,
+            amenities: o.amenities,
+            parking: o.parking,
             node_levels: o.node_levels,
             connector_edges: o.connector_edges,
+            elevator_edges: o.elevator_edges,
             connector_cost: ConnectorCost::default(),
             cch: None,
-        })
+            landmarks: None,
+            search_profiles: Vec::new(),
+            ingestion_reports: Vec::new(),
+            feeds: Vec::new(),
+            build_info: o.build_info,
+            feed_open: false,
+        }
+    }
+
+    /// Keep a feed's ingestion report, named by its ingestor label.
+    pub fn record_ingestion_report(
+        &mut self,
+        feed: &str,
+        mut report: crate::ingestion::gtfs::IngestionReport,
+    ) {
+        report.feed = feed.to_string();
+        self.ingestion_reports.push(report);
     }
 
     pub fn set_platform_index(&mut self, idx: PlatformIndex) {
@@ -185,6 +360,57 @@ impl Graph {
         &self.platforms
     }
 
+    pub fn set_address_index(&mut self, idx: OsmAddressIndex) {
+        self.addresses = idx;
+    }
+
+    pub fn address_index(&self) -> &OsmAddressIndex {
+        &self.addresses
+    }
+
+    /// "Street 12" for the OSM address nearest `loc`; `None` when none was indexed
+    /// within [`ADDRESS_LABEL_RADIUS_M`].
+    pub fn address_label(&self, loc: LatLng) -> Option<String> {
+        self.addresses.label_near(loc, ADDRESS_LABEL_RADIUS_M)
+    }
+
+    pub fn set_amenity_index(&mut self, idx: StopAmenityIndex) {
+        self.amenities = idx;
+    }
+
+    /// Facilities mapped around a stop at `loc`; `None` when the build indexed no
+    /// amenities (`stop_amenities` off), so "nothing there" stays distinguishable.
+    pub fn stop_amenities(&self, loc: LatLng) -> Option<StopAmenities> {
+        (!self.amenities.is_empty()).then(|| self.amenities.amenities_near(loc))
+    }
+
+    pub fn set_parking_index(&mut self, idx: ParkingIndex) {
+        self.parking = idx;
+    }
+
+    /// Parking facilities within `radius_m` of `loc`, closest first, with their
+    /// distance in meters; empty when the build indexed no parking.
+    pub fn parking_nearby(
+        &self,
+        loc: LatLng,
+        radius_m: f64,
+        kind: Option<ParkingKind>,
+    ) -> Vec<(f64, &ParkingFacility)> {
+        self.parking.near(loc, radius_m, kind)
+    }
+
+    /// Parking-search allowance a park-and-ride driver adds at a stop at `loc`:
+    /// [`PARK_RIDE_UNKNOWN_CAPACITY_SECS`] unless a car park with known capacity lies
+    /// within [`PARK_RIDE_RADIUS_M`]. Zero when the build indexed no parking, so graphs
+    /// without it plan as before.
+    pub fn park_ride_allowance_secs(&self, loc: LatLng) -> u32 {
+        if self.parking.is_empty() || self.parking.known_car_capacity_near(loc, PARK_RIDE_RADIUS_M) {
+            0
+        } else {
+            PARK_RIDE_UNKNOWN_CAPACITY_SECS
+        }
+    }
+
     /// Install the auxiliary OSM level/connector data parsed during the PBF pass.
     pub fn set_osm_level_data(
         &mut self,
@@ -205,7 +431,7 @@ impl Graph {
     }
 
     /// Move a transit stop's anchor coordinate. Safe only for transit stops (NOT in
-    /// the snap KD-tree, so no resync needed); a no-op on OSM nodes.
+    /// the snap index, so no resync needed); a no-op on OSM nodes.
     pub fn relocate_transit_stop(&mut self, id: NodeID, loc: LatLng) {
         if let Some(NodeData::TransitStop(stop)) = self.nodes.get_mut(id.0) {
             stop.lat_lng = loc;
@@ -231,7 +457,9 @@ impl Graph {
     /// contraction (which bakes the lengths into super-edge segments).
     ///
     /// - Stairs/ramp: `new_len = old_len * walk_speed / connector_speed`
-    /// - Elevator: `new_len = elevator_secs * walk_speed` (fixed time)
+    /// - Elevator: `new_len = elevator_secs * walk_speed` (fixed time), halved on each
+    ///   way into a `highway=elevator` node so passing through it costs one ride
+    /// - Escalator: `new_len = old_len * walk_speed / escalator_speed`
     ///
     /// No-op when `connector_edges` or `edges` is empty.
     pub fn bake_connector_lengths(&mut self, cost: ConnectorCost) {
@@ -257,7 +485,15 @@ impl Graph {
                                 (old_len * walk_speed / cost.ramp_speed_mps).round() as usize
                             }
                             Connector::Elevator => {
-                                (cost.elevator_secs * walk_speed).round() as usize
+                                let via_node = self
+                                    .elevator_edges
+                                    .get(&(a, b))
+                                    .is_some_and(|id| id.starts_with("node/"));
+                                let secs = if via_node { cost.elevator_secs / 2.0 } else { cost.elevator_secs };
+                                (secs * walk_speed).round() as usize
+                            }
+                            Connector::Escalator => {
+                                (old_len * walk_speed / cost.escalator_speed_mps).round() as usize
                             }
                         };
                         s.length = new_len.max(1);
@@ -276,8 +512,16 @@ impl Graph {
         self.raptor.walking_speed_mps = mps;
     }
 
+    /// The current query's profile speed when one is installed, else the graph's.
+    #[inline]
     pub fn walking_speed_mps(&self) -> f64 {
-        self.raptor.walking_speed_mps
+        query_profile::walking_speed().unwrap_or(self.raptor.walking_speed_mps)
+    }
+
+    /// Factor rescaling the precomputed transfer table (baked at the graph speed) to
+    /// the current query's walking speed; `None` when no profile overrides it.
+    pub(crate) fn transfer_walk_scale(&self) -> Option<f64> {
+        query_profile::walking_speed().map(|v| self.raptor.walking_speed_mps / v)
     }
 
     pub fn set_station_merge_radius_m(&mut self, m: f64) {
@@ -348,6 +592,14 @@ impl Graph {
         self.raptor.balance = b;
     }
 
+    /// Install `default_routing.route_overrides`; like [`Self::set_fare_model`], run
+    /// after the transit index is populated.
+    pub fn set_route_overrides(&mut self, overrides: Vec<RouteOverride>) {
+        self.raptor.route_overrides = overrides;
+        self.raptor.rebuild_route_figures();
+        self.raptor.rebuild_operator_fare_lookup();
+    }
+
     /// Install the transit-pricing model and rebuild its route→operator lookup. The
     /// rebuild reads `transit_routes`/`transit_agencies`, so this must run after the
     /// transit index is populated.
@@ -413,6 +665,35 @@ impl Graph {
         self.raptor.max_window_secs = secs;
     }
 
+    pub fn set_crowding_penalty_secs(&mut self, secs: u32) {
+        self.raptor.crowding_penalty_secs = secs;
+    }
+
+    pub fn set_bike_route_types(&mut self, route_types: Vec<gtfs_structures::RouteType>) {
+        self.raptor.bike_route_types = route_types;
+    }
+
+    pub fn set_mode_preferences(
+        &mut self,
+        preferences: Vec<(gtfs_structures::RouteType, crate::structures::ModePreference)>,
+    ) {
+        self.raptor.mode_preferences = preferences;
+    }
+
+    /// Adds or replaces a named routing profile.
+    pub fn set_routing_profile(&mut self, name: String, profile: RoutingProfile) {
+        self.raptor.routing_profiles.insert(name, profile);
+    }
+
+    pub fn routing_profile(&self, name: &str) -> Option<&RoutingProfile> {
+        self.raptor.routing_profiles.get(name)
+    }
+
+    pub fn set_query_budget(&mut self, max_ms: u64, max_expansions: u64) {
+        self.raptor.max_query_ms = max_ms;
+        self.raptor.max_search_expansions = max_expansions;
+    }
+
     pub fn set_travel_map_grid_step_m(&mut self, meters: f64) {
         self.raptor.travel_map_grid_step_m = meters;
     }
@@ -429,14 +710,21 @@ impl Graph {
         self.raptor.max_snap_distance_m = meters;
     }
 
+    pub fn set_snap_warning_m(&mut self, meters: u32) {
+        self.raptor.snap_warning_m = meters;
+    }
+
+    pub fn set_snap_island_nodes(&mut self, nodes: usize) {
+        self.raptor.snap_island_nodes = nodes;
+    }
+
     pub fn add_node(&mut self, node: NodeData) -> NodeID {
         let id = NodeID(self.nodes.len());
 
         if let NodeData::OsmNode(ref osm_node) = node {
-            let _ = self
-                .nodes_tree
-                .add([osm_node.lat_lng.latitude, osm_node.lat_lng.longitude], id);
-            self.id_mapper.insert(osm_node.eid.clone(), id);
+            self.nodes_tree
+                .add(osm_node.lat_lng.latitude, osm_node.lat_lng.longitude, id);
+            self.id_mapper.insert(&osm_node.eid, id);
         }
 
         self.nodes.push(node);
@@ -444,12 +732,12 @@ impl Graph {
         id
     }
 
-    /// Add an OSM node WITHOUT inserting it into the snap KD-tree. Platform-way nodes
+    /// Add an OSM node WITHOUT inserting it into the snap index. Platform-way nodes
     /// must be routable but must NOT be GTFS-stop-snap candidates, else a nearby stop
     /// would snap to a platform and silently relocate.
     pub fn add_osm_node_unindexed(&mut self, node: OsmNodeData) -> NodeID {
         let id = NodeID(self.nodes.len());
-        self.id_mapper.insert(node.eid.clone(), id);
+        self.id_mapper.insert(&node.eid, id);
         self.nodes.push(NodeData::OsmNode(node));
         self.edges.push(Vec::new());
         id
@@ -463,6 +751,20 @@ impl Graph {
         self.id_mapper.get(eid)
     }
 
+    /// Node imported from OSM node `osm_id`; same as `get_id("map#osm#<osm_id>")`.
+    pub fn get_osm_id(&self, osm_id: i64) -> Option<NodeID> {
+        self.id_mapper.get_numeric(OSM_EID_SOURCE, osm_id).copied()
+    }
+
+    /// External id a node was registered under, e.g. `map#osm#123`. Survives the
+    /// node-array drop, at the cost of a scan over every registered id.
+    pub fn node_eid(&self, id: NodeID) -> Option<String> {
+        match self.nodes.get(id.0) {
+            Some(NodeData::OsmNode(o)) => Some(o.eid.clone()),
+            _ => self.id_mapper.eid_of(id),
+        }
+    }
+
     pub fn get_node(&self, id: NodeID) -> Option<&NodeData> {
         self.nodes.get(id.0)
     }
@@ -491,30 +793,73 @@ impl Graph {
         self.edges.len()
     }
 
-    /// Nearest OSM node by squared Euclidean distance (fast, not metrically accurate).
-    /// See `nearest_node_dist` for Haversine meters.
+    /// Nearest OSM node by Haversine meters, the same node `nearest_node_dist` returns.
+    /// Once the node arrays are dropped (finalized or restored graph) this is the
+    /// foot-snap junction of the contracted graph.
     pub fn nearest_node(&self, lat: f64, lon: f64) -> Option<NodeID> {
-        match self
-            .nodes_tree
-            .iter_nearest(&[lat, lon], &squared_euclidean)
+        if self.nodes_tree.size() == 0
+            && let Some(cg) = self.contracted.as_ref()
         {
-            Ok(mut it) => it.next().map(|v| *v.1),
-            Err(_) => {
-                tracing::warn!("KD-tree query failed (empty tree?)");
-                None
-            }
+            return cg.foot_bounding_junction(self, lat, lon, self.raptor.edge_snap_radius_m);
         }
+        self.nearest_node_dist(lat, lon).map(|(_, &n)| n)
     }
 
     /// Nearest OSM node with Haversine distance in meters (accurate; e.g. GTFS snapping).
     pub fn nearest_node_dist(&self, lat: f64, lon: f64) -> Option<(f64, &NodeID)> {
-        match self.nodes_tree.iter_nearest(&[lat, lon], &LatLng::distance) {
-            Ok(mut it) => it.next(),
-            Err(_) => {
-                tracing::warn!("KD-tree query failed (empty tree?)");
-                None
-            }
-        }
+        self.nodes_tree.nearest(lat, lon)
+    }
+
+    /// Nearest OSM street node within `max_m` Haversine meters with at least one
+    /// out-edge `profile` may use. Unlike `nearest_node_dist`, never lands on a transit
+    /// stop or on a node only other modes can leave (e.g. a motorway node for walking).
+    pub fn nearest_street_node(
+        &self,
+        lat: f64,
+        lon: f64,
+        profile: StreetProfile,
+        max_m: f64,
+    ) -> Option<(f64, NodeID)> {
+        self.nearest_street_nodes(lat, lon, profile, max_m, 1).into_iter().next()
+    }
+
+    /// Up to `k` street nodes as in [`Self::nearest_street_node`], closest first.
+    pub fn nearest_street_nodes(
+        &self,
+        lat: f64,
+        lon: f64,
+        profile: StreetProfile,
+        max_m: f64,
+        k: usize,
+    ) -> Vec<(f64, NodeID)> {
+        self.nodes_tree
+            .k_nearest_matching(lat, lon, k, max_m, |&n| {
+                matches!(self.nodes.get(n.0), Some(NodeData::OsmNode(_)))
+                    && self.edges[n.0]
+                        .iter()
+                        .any(|e| matches!(e, EdgeData::Street(s) if profile.permits(s)))
+            })
+            .into_iter()
+            .map(|(d, &n)| (d, n))
+            .collect()
+    }
+
+    /// Snap-indexed street nodes within `radius_m` Haversine meters, closest first.
+    pub fn nodes_within_radius(&self, lat: f64, lon: f64, radius_m: f64) -> Vec<(f64, NodeID)> {
+        self.nodes_tree
+            .within(lat, lon, radius_m)
+            .into_iter()
+            .map(|(d, &n)| (d, n))
+            .collect()
+    }
+
+    /// The `k` snap-indexed street nodes nearest in Haversine meters, closest first.
+    pub fn k_nearest_street_nodes(&self, lat: f64, lon: f64, k: usize) -> Vec<(f64, NodeID)> {
+        self.nodes_tree
+            .k_nearest(lat, lon, k)
+            .into_iter()
+            .map(|(d, &n)| (d, n))
+            .collect()
     }
 
     /// Project a coordinate onto segment `pa→pb`: `(perp_dist_m, t)` with `t∈[0,1]`
@@ -536,6 +881,16 @@ impl Graph {
         ((px * px + py * py).sqrt(), t)
     }
 
+    /// Rebuild every `#[serde(skip)]` index derived from persisted data: the RAPTOR
+    /// runtime maps, the edge index and the contracted graph's segment R-tree.
+    pub fn rebuild_derived_indices(&mut self) {
+        self.raptor.build_runtime_indices();
+        self.build_edge_index();
+        if let Some(cg) = self.contracted.as_mut() {
+            cg.build_seg_index();
+        }
+    }
+
     /// Rebuild the spatial edge index. Never serialized: call after build or load so
     /// edge-aware snapping works. No-op when there are no OSM nodes.
     pub fn build_edge_index(&mut self) {
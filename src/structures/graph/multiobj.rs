@@ -9,6 +9,7 @@ use crate::structures::cost::{
 use crate::structures::{BikeCost, BikeProfile, EdgeData, LatLng, NodeID, StreetEdgeData};
 
 use super::contraction::SuperEdge;
+use super::query_trace::{self, PruneReason};
 use super::{Graph, PrevCtx};
 
 /// Objective-space grid for bucket pruning. `sizes[i] > 0.0` snaps axis `i` to fixed
@@ -159,13 +160,16 @@ struct Label {
 
 /// Heap entry. `BinaryHeap` is a max-heap, so `Ord` is reversed to pop the
 /// lexicographically smallest cost vector first — required for sound label-setting.
+/// Equal keys pop by node id, then label index (insertion order), so ties never depend
+/// on heap internals and the same query always settles the same front.
 struct QLabel {
     key: CostVector,
+    node: NodeID,
     idx: usize,
 }
 impl PartialEq for QLabel {
     fn eq(&self, o: &Self) -> bool {
-        lex_cmp(&self.key, &o.key) == Ordering::Equal
+        self.cmp(o) == Ordering::Equal
     }
 }
 impl Eq for QLabel {}
@@ -177,6 +181,8 @@ impl PartialOrd for QLabel {
 impl Ord for QLabel {
     fn cmp(&self, o: &Self) -> Ordering {
         lex_cmp(&o.key, &self.key)
+            .then_with(|| o.node.cmp(&self.node))
+            .then_with(|| o.idx.cmp(&self.idx))
     }
 }
 
@@ -265,7 +271,7 @@ impl Graph {
                     profile,
                     weights,
                     &self.raptor.variance_model,
-                    speed,
+                    self.street_speed(mode, street, speed),
                     None,
                     this_dir,
                 )
@@ -296,7 +302,7 @@ impl Graph {
                         profile,
                         weights,
                         &self.raptor.variance_model,
-                        speed,
+                        self.street_speed(mode, street, speed),
                         None,
                         this_dir,
                     ) else {
@@ -440,7 +446,13 @@ impl Graph {
         let contract = self.contracted.is_some();
         // Cost-baked super-edges ⇒ front paths' demoted axes (D+/Surface/Variance) are
         // canonical in-search and must be recomputed exactly at the end. Baking is bike-only.
-        let baked_mode = contract && mode == RoutingMode::Bike && self.bike_cg().is_some();
+        // Baked super-edge costs predate the query's what-if street closures and stair
+        // avoidance.
+        let baked_mode = contract
+            && mode == RoutingMode::Bike
+            && self.bike_cg().is_some()
+            && !super::what_if::streets_closed()
+            && !super::query_profile::avoid_stairs();
         // Junctions bounding an interior destination's chain — re-walk (not bake) there.
         let dest_guard = if baked_mode {
             self.dest_guard_junctions(destination)
@@ -497,20 +509,23 @@ impl Graph {
         };
         // A* lower bound on remaining Time = straight-line dist / FASTEST possible speed,
         // so it never exceeds true remaining time (admissible ⇒ front unchanged). Bike's
-        // ceiling is `profile.max_speed`, not the cruising speed.
-        let max_speed = match mode {
-            RoutingMode::Walk => self.raptor.walking_speed_mps,
-            RoutingMode::Bike => bike.profile().max_speed / 3.6,
-            RoutingMode::Drive => self.raptor.driving_speed_mps,
-        };
+        // ceiling is `profile.max_speed` on the fastest surface, not the cruising speed.
+        let max_speed = self.estimator_speed(mode, &bike.profile());
         let inv_max_speed = 1.0 / max_speed.max(0.1);
+        // ALT: the landmark triangle bound on remaining street length, when selected and
+        // both ends are contracted junctions; the larger of the two bounds is used.
+        let dest_row = if astar { self.landmark_row(destination, mode) } else { None };
+        let remain_m = |node: NodeID, crow: f64| match dest_row {
+            Some(dr) => self
+                .landmark_row(node, mode)
+                .map_or(crow, |r| crow.max(super::Landmarks::lower_bound(r, dr) as f64)),
+            None => crow,
+        };
         let f_key = |g: &CostVector, node: NodeID| {
             if astar {
                 let mut h = CostVector::ZERO;
-                h.set(
-                    Axis::Time,
-                    self.node_loc(node).dist(dest_loc) * inv_max_speed,
-                );
+                let crow = self.node_loc(node).dist(dest_loc);
+                h.set(Axis::Time, remain_m(node, crow) * inv_max_speed);
                 g.added(&h)
             } else if let Some(h) = heuristic {
                 g.added(&h.h(node))
@@ -518,8 +533,10 @@ impl Graph {
                 *g
             }
         };
+        let origin_key = f_key(&CostVector::ZERO, origin);
         heap.push(QLabel {
-            key: f_key(&CostVector::ZERO, origin),
+            key: origin_key,
+            node: origin,
             idx: 0,
         });
 
@@ -528,13 +545,18 @@ impl Graph {
         // (non-negative edge costs), so pruning against these is exact: front unchanged,
         // explored region shrinks.
         let mut dest_front: Vec<CostVector> = Vec::new();
+        // Neighbour buffer reused across pops: one allocation per search, not per label.
+        let mut neigh: Vec<(&StreetEdgeData, LatLng, Option<&SuperEdge>)> = Vec::new();
         #[cfg(test)]
         let mut expand_count: u64 = 0;
-        while let Some(QLabel { key, idx }) = heap.pop() {
+        while let Some(QLabel { key, idx, .. }) = heap.pop() {
             #[cfg(test)]
             {
                 expand_count += 1;
             }
+            if super::query_budget::tick() {
+                break;
+            }
             let node = labels[idx].node;
             let g_cost = labels[idx].cost;
             let elev = labels[idx].elev;
@@ -544,6 +566,7 @@ impl Graph {
                 .get(&node.0)
                 .is_some_and(|s| s.contains(&g_cost.project(front_axes)))
             {
+                query_trace::prune(PruneReason::StaleLabel);
                 continue;
             }
             // Distance-budget cap (lazy, re-checked at pop): corridor form len +
@@ -551,13 +574,18 @@ impl Graph {
             if let Some(cap_val) = cap {
                 let d_remain = self.node_loc(node).dist(dest_loc);
                 if cur_len as f64 + d_remain > cap_val as f64 {
+                    query_trace::prune(PruneReason::DistanceBudget);
                     continue;
                 }
             }
             if dest_front.iter().any(|d| d.weakly_dominates(&key)) {
+                query_trace::prune(PruneReason::DestinationDominated);
                 continue;
             }
             if node == destination {
+                if front.is_empty() && (astar || heuristic.is_some()) {
+                    query_trace::heuristic(origin_key.get(Axis::Time), g_cost.get(Axis::Time));
+                }
                 if budget_active && cap.is_none() {
                     cap = Some(((1.0 + distance_budget) * cur_len as f64) as u64);
                 }
@@ -584,6 +612,7 @@ impl Graph {
                 continue;
             }
             if node != origin && self.raptor.transit_node_to_stop[node.0] != u32::MAX {
+                query_trace::prune(PruneReason::TransitStop);
                 continue;
             }
             // Prev-edge context = the last edge arriving at `node` (carried on the label,
@@ -608,36 +637,29 @@ impl Graph {
             } else {
                 None
             };
-            let neigh: Vec<(&StreetEdgeData, LatLng, Option<&SuperEdge>)> = match (arena_ses, cgref) {
-                (Some(ses), Some(cg)) => ses
-                    .iter()
-                    .map(|se| {
-                        let s0 = &cg.segs[se.seg_start as usize];
-                        (&s0.edge, s0.far, Some(se))
-                    })
-                    .collect(),
+            neigh.clear();
+            match (arena_ses, cgref) {
+                (Some(ses), Some(cg)) => neigh.extend(ses.iter().map(|se| {
+                    let s0 = &cg.segs[se.seg_start as usize];
+                    (&s0.edge, s0.far, Some(se))
+                })),
                 _ => {
                     let Some(neighbors) = self.edges.get(node.0) else {
                         continue;
                     };
-                    neighbors
-                        .iter()
-                        .filter_map(|e| match e {
-                            EdgeData::Street(s) => {
-                                Some((s, self.nodes[s.destination.0].loc(), None::<&SuperEdge>))
-                            }
-                            _ => None,
-                        })
-                        .collect()
+                    neigh.extend(neighbors.iter().filter_map(|e| match e {
+                        EdgeData::Street(s) => Some((s, self.nodes[s.destination.0].loc(), None)),
+                        _ => None,
+                    }));
                 }
-            };
+            }
             let node_loc = match cgref {
                 Some(cg) if contract && cg.junction_of[node.0] != u32::MAX => {
                     cg.junction_coord[cg.junction_of[node.0] as usize]
                 }
                 _ => self.nodes[node.0].loc(),
             };
-            for (street, first_step_loc, se_direct) in neigh {
+            for &(street, first_step_loc, se_direct) in &neigh {
                 let first_step = street.destination;
                 let new_len0 = cur_len.saturating_add(street.length as u32);
                 // Geometric length corridor: len + straight-line remainder > cap can't
@@ -645,6 +667,7 @@ impl Graph {
                 if let Some(cap_val) = cap {
                     let dr0 = first_step_loc.dist(dest_loc);
                     if new_len0 as f64 + dr0 > cap_val as f64 {
+                        query_trace::prune(PruneReason::DistanceBudget);
                         continue;
                     }
                 }
@@ -794,12 +817,13 @@ impl Graph {
                 let d_remain = t_node_loc.dist(dest_loc);
                 if let Some(cap_val) = cap {
                     if t_len as f64 + d_remain > cap_val as f64 {
+                        query_trace::prune(PruneReason::DistanceBudget);
                         continue;
                     }
                 }
                 let new_key = if astar {
                     let mut h = CostVector::ZERO;
-                    h.set(Axis::Time, d_remain * inv_max_speed);
+                    h.set(Axis::Time, remain_m(t_node, d_remain) * inv_max_speed);
                     t_cost.added(&h)
                 } else if let Some(hh) = heuristic {
                     t_cost.added(&hh.h(t_node))
@@ -807,6 +831,7 @@ impl Graph {
                     t_cost
                 };
                 if dest_front.iter().any(|d| d.weakly_dominates(&new_key)) {
+                    query_trace::prune(PruneReason::DestinationDominated);
                     continue;
                 }
                 if !sets
@@ -814,6 +839,7 @@ impl Graph {
                     .or_default()
                     .try_add(t_cost.project(front_axes), eps, &buckets)
                 {
+                    query_trace::prune(PruneReason::NodeDominated);
                     continue;
                 }
                 let nidx = labels.len();
@@ -833,6 +859,7 @@ impl Graph {
                 });
                 heap.push(QLabel {
                     key: new_key,
+                    node: t_node,
                     idx: nidx,
                 });
             }
@@ -1102,6 +1129,9 @@ impl Graph {
     ) -> Option<(CostVector, (f64, f64), f64)> {
         #[cfg(test)]
         TRANS_N.with(|c| c.set(c.get() + 1));
+        if super::what_if::street_closed(street) || super::query_profile::stairs_avoided(street) {
+            return None;
+        }
         let this_dir = dir.unwrap_or_else(|| self.dir_between(street.origin, street.destination));
         let incoming = prev.map(|p| p.dir);
         let mut edge_cv = edge_cost_vector(
@@ -1110,10 +1140,15 @@ impl Graph {
             profile,
             weights,
             &self.raptor.variance_model,
-            speed,
+            self.street_speed(mode, street, speed),
             incoming,
             this_dir,
         )?;
+        if mode == RoutingMode::Drive && street.car {
+            // Rush hours: the edge is entered at the label's clock (FIFO, factors >= 1).
+            let free = edge_cv.get(Axis::Time);
+            edge_cv.set(Axis::Time, self.rush_hour_secs(street, free, g_cost.get(Axis::Time)));
+        }
         if mode == RoutingMode::Bike {
             // Speed-change time (corner slow-down + dismount/remount) charged at the
             // boundary into this edge; needs the previous edge's length and cruise speed.
@@ -1156,8 +1191,8 @@ impl Graph {
 
     pub(super) fn mode_speed(&self, mode: RoutingMode) -> f64 {
         match mode {
-            RoutingMode::Walk => self.raptor.walking_speed_mps,
-            RoutingMode::Bike => self.raptor.walking_speed_mps,
+            RoutingMode::Walk => self.walking_speed_mps(),
+            RoutingMode::Bike => self.walking_speed_mps(),
             RoutingMode::Drive => self.raptor.driving_speed_mps,
         }
     }
@@ -1173,6 +1208,17 @@ mod tests {
         CostVector::from_active(&[Axis::Time, Axis::Variance], &[time, variance])
     }
 
+    #[test]
+    fn equal_keys_pop_by_node_then_insertion() {
+        let mut heap = BinaryHeap::new();
+        for (node, idx) in [(7, 0), (3, 1), (3, 2), (5, 3)] {
+            heap.push(QLabel { key: cv(10.0, 0.0), node: NodeID(node), idx });
+        }
+        heap.push(QLabel { key: cv(12.0, 0.0), node: NodeID(0), idx: 4 });
+        let order: Vec<usize> = std::iter::from_fn(|| heap.pop().map(|q| q.idx)).collect();
+        assert_eq!(order, vec![1, 2, 3, 0, 4]);
+    }
+
     #[test]
     fn strip_cycles_splices_revisited_nodes() {
         let n = |i: u32| NodeID(i as usize);
@@ -2127,6 +2173,7 @@ mod tests {
         assert!(cv * cv * t * t > 0.0, "systematic term must be present");
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn astar_perf_diag_real_brussels() {
@@ -2160,6 +2207,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn multiobj_smoke_real_brussels() {
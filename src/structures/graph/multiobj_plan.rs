@@ -132,6 +132,7 @@ impl Graph {
         bike: &BikeCost,
         start_time: u32,
     ) -> Option<Plan> {
+        let _clock = super::rush_hour::begin(Some(start_time));
         let options = self.multiobj_leg_options(origin, destination, mode, role, bike);
         if options.is_empty() {
             return None;
@@ -151,17 +152,19 @@ impl Graph {
             stop_position: None,
             arrival: None,
             departure: Some(start_time),
+            language: None,
         };
         let to = PlanPlace {
             node_id: destination,
             stop_position: None,
             arrival: Some(end),
             departure: None,
+            language: None,
         };
         let steps = self.street_steps(&chosen.nodes, &chosen.edges, mode, bike, start_time, to);
 
         Some(Plan {
-            legs: vec![PlanLeg::Walk(PlanWalkLeg {
+            legs: vec![PlanLeg::street(PlanWalkLeg {
                 from,
                 to,
                 start: start_time,
@@ -186,9 +189,12 @@ impl Graph {
             }],
             expected_end: end,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         })
         .map(|mut plan| {
-            if let PlanLeg::Walk(leg) = &mut plan.legs[0] {
+            if let Some(leg) = plan.legs[0].as_street_mut() {
                 leg.alternatives = options;
             }
             plan
@@ -325,8 +331,9 @@ impl Graph {
         }
     }
 
-    /// Leg steps for a chosen path. Walk/Drive ⇒ one plain step; Bike ⇒ group
-    /// consecutive ride/push runs into dismount-aware steps.
+    /// Leg steps for a chosen path. Walk/Drive ⇒ one plain step, split where the
+    /// path enters or leaves synthetic connectors; Bike ⇒ group consecutive
+    /// ride/push runs into dismount-aware steps, split the same way.
     pub(crate) fn street_steps(
         &self,
         nodes: &[NodeID],
@@ -337,23 +344,38 @@ impl Graph {
         to: PlanPlace,
     ) -> Vec<PlanLegStep> {
         let edges: &[StreetEdgeData] = recon;
-        if mode != RoutingMode::Bike {
+        let is_bike = mode == RoutingMode::Bike;
+        let run_key = |e: &StreetEdgeData| (is_bike && BikeCost::is_push(&e.attrs), e.partial);
+        let total_secs = if is_bike { 0 } else { self.annotate_steps_secs(recon, mode, 0) };
+        if !is_bike && edges.windows(2).all(|w| w[0].partial == w[1].partial) {
             let length: usize = edges.iter().map(|s| s.length).sum();
-            let secs = self.annotate_steps_secs(recon, mode);
-            return vec![PlanLegStep::Walk(PlanWalkLegStep::plain(length, secs, to))];
+            let mut step = PlanWalkLegStep::plain(length, total_secs, to);
+            step.synthetic = edges.first().is_some_and(|e| e.partial);
+            return vec![PlanLegStep::Walk(step)];
         }
         let mut steps: Vec<PlanLegStep> = Vec::new();
         let mut i = 0;
         let mut cum_time = 0u32;
         while i < edges.len() {
-            let push = BikeCost::is_push(&edges[i].attrs);
+            let key = run_key(&edges[i]);
+            let (push, synthetic) = key;
             let start_idx = i;
             let (mut run_len, mut run_time) = (0usize, 0u32);
-            while i < edges.len() && BikeCost::is_push(&edges[i].attrs) == push {
+            while i < edges.len() && run_key(&edges[i]) == key {
                 run_len += edges[i].length;
-                run_time += bike.edge_time(&edges[i]);
+                if is_bike {
+                    run_time += bike.edge_time(&edges[i]);
+                }
                 i += 1;
             }
+            if !is_bike {
+                // The last run takes the remainder so the steps sum to the leg's time.
+                run_time = if i == edges.len() {
+                    total_secs.saturating_sub(cum_time)
+                } else {
+                    self.annotate_steps_secs(&edges[start_idx..i], mode, cum_time)
+                };
+            }
             cum_time += run_time;
             let node_id = nodes[i];
             steps.push(PlanLegStep::Walk(PlanWalkLegStep {
@@ -364,8 +386,10 @@ impl Graph {
                     stop_position: None,
                     arrival: Some(start_time + cum_time),
                     departure: None,
+                    language: None,
                 },
                 dismount: push,
+                synthetic,
                 geom_start: start_idx,
                 geom_end: i,
             }));
@@ -383,7 +407,7 @@ mod tests {
     use super::*;
     use crate::structures::cost::VarGen;
     use crate::structures::cost::{LegRole, RoutingMode};
-    use crate::structures::plan::PlanLeg;
+    use crate::structures::plan::{PlanBikeLeg, PlanLeg};
     use crate::structures::{
         BikeAttrs, EdgeData, HighwayClass, LatLng, NodeData, NodeID, OsmNodeData, StreetEdgeData,
         Surface,
@@ -467,6 +491,7 @@ mod tests {
         assert_eq!(plan.start, 30_000);
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn direct_plan_smoke_real_brussels() {
@@ -492,8 +517,8 @@ mod tests {
             let plan = g
                 .multiobj_direct_plan(o, d, mode, LegRole::Neutral, &bike, 28_800)
                 .unwrap_or_else(|| panic!("direct plan must succeed for {mode:?}"));
-            let PlanLeg::Walk(leg) = &plan.legs[0] else {
-                panic!("expected walk leg")
+            let Some(leg) = plan.legs[0].as_street() else {
+                panic!("expected street leg")
             };
             assert!(
                 !leg.alternatives.is_empty(),
@@ -521,6 +546,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn leg_options_e2e_real_belgium() {
@@ -1152,6 +1178,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn direct_drive_in_rush_hour_takes_longer_from_its_departure() {
+        use super::super::rush_hour::RushHourWindow;
+        let mut g = Graph::new();
+        let mk = |id: &str, lon: f64| {
+            NodeData::OsmNode(OsmNodeData {
+                eid: id.into(),
+                lat_lng: LatLng { latitude: 50.0, longitude: lon },
+            })
+        };
+        let a = g.add_node(mk("a", 4.000));
+        let b = g.add_node(mk("b", 4.005));
+        let c = g.add_node(mk("c", 4.010));
+        g.build_raptor_index();
+        let edge = |o: NodeID, d: NodeID, highway: HighwayClass| {
+            let attrs = BikeAttrs { highway, ..BikeAttrs::road_default() };
+            EdgeData::Street(StreetEdgeData {
+                origin: o, destination: d, partial: false, length: 360,
+                foot: true, bike: true, car: true, attrs, elev_delta: 0,
+                surface_speed: 100, var_gen: VarGen::NONE,
+            })
+        };
+        for (o, d, highway) in [(a, b, HighwayClass::Primary), (b, c, HighwayClass::Residential)] {
+            g.add_edge(o, edge(o, d, highway));
+            g.add_edge(d, edge(d, o, highway));
+        }
+        enable_contraction(&mut g);
+        let mut w = RushHourWindow::new(8 * 3600, 9 * 3600);
+        w.set(HighwayClass::Primary, 2.0);
+        g.set_car_rush_hours(vec![w]);
+        let bike = g.default_bike_cost();
+        let secs = |start: u32| {
+            let plan = g
+                .multiobj_direct_plan(a, c, RoutingMode::Drive, LegRole::Neutral, &bike, start)
+                .expect("drivable");
+            plan.end - plan.start
+        };
+        let free = secs(7 * 3600);
+        let rush = secs(8 * 3600);
+        let primary = (360.0 / g.raptor.driving_speed_mps).round() as u32;
+        assert!(rush >= free + primary - 1, "free {free}, rush {rush}");
+        assert_eq!(secs(8 * 3600 - 3600), free, "windows only apply inside their hours");
+        assert_eq!(super::super::rush_hour::departure(), None, "the plan's clock is scoped");
+    }
+
     #[test]
     fn bike_plan_rebuilds_ride_push_steps() {
         let mut g = Graph::new();
@@ -1202,7 +1273,7 @@ mod tests {
         let plan = g
             .multiobj_direct_plan(a, b, RoutingMode::Bike, LegRole::Neutral, &bike, 0)
             .expect("bike plan");
-        let PlanLeg::Walk(leg) = &plan.legs[0] else {
+        let PlanLeg::Bike(PlanBikeLeg { leg }) = &plan.legs[0] else {
             panic!()
         };
         let dismount: Vec<bool> = leg
@@ -1219,4 +1290,58 @@ mod tests {
             dismount
         );
     }
+
+    #[test]
+    fn walk_steps_split_at_synthetic_links() {
+        let mut g = Graph::new();
+        let mk = |id: &str, lon: f64| {
+            NodeData::OsmNode(OsmNodeData {
+                eid: id.into(),
+                lat_lng: LatLng { latitude: 50.0, longitude: lon },
+            })
+        };
+        let a = g.add_node(mk("a", 4.0000));
+        let m = g.add_node(mk("m", 4.0014));
+        let b = g.add_node(mk("b", 4.0018));
+        g.build_raptor_index();
+        let mk_e = |o, d, len, partial| StreetEdgeData {
+            origin: o,
+            destination: d,
+            partial,
+            length: len,
+            foot: true,
+            bike: false,
+            car: false,
+            attrs: BikeAttrs::road_default(),
+            elev_delta: 0,
+            surface_speed: 100,
+            var_gen: VarGen::NONE,
+        };
+        let edges = [mk_e(a, m, 100, false), mk_e(m, b, 30, true)];
+        let to = PlanPlace {
+            node_id: b,
+            stop_position: None,
+            arrival: None,
+            departure: None,
+            language: None,
+        };
+        let bike = g.default_bike_cost();
+        let steps = g.street_steps(&[a, m, b], &edges, RoutingMode::Walk, &bike, 0, to);
+        let walk: Vec<(usize, bool, u32)> = steps
+            .iter()
+            .map(|s| match s {
+                PlanLegStep::Walk(w) => (w.length, w.synthetic, w.time),
+                _ => panic!(),
+            })
+            .collect();
+        assert_eq!(
+            walk.iter().map(|&(len, syn, _)| (len, syn)).collect::<Vec<_>>(),
+            vec![(100, false), (30, true)]
+        );
+        let total: u32 = walk.iter().map(|w| w.2).sum();
+        assert_eq!(total, g.annotate_steps_secs(&edges, RoutingMode::Walk, 0), "steps sum to the leg time");
+
+        let street_only = g.street_steps(&[a, m], &edges[..1], RoutingMode::Walk, &bike, 0, to);
+        assert!(matches!(street_only[..], [PlanLegStep::Walk(w)] if !w.synthetic));
+    }
 }
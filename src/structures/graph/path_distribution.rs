@@ -19,23 +19,30 @@ impl Graph {
         }
         let steps: Vec<(StreetEdgeData, (f64, f64))> =
             carried.iter().map(|(e, dir, _)| (*e, *dir)).collect();
-        self.annotate_steps(&steps, mode)
+        self.annotate_steps(&steps, mode, 0.0)
     }
 
     /// Walk/Drive only: the dummy dir is sound because their mean is direction-independent.
-    pub(super) fn annotate_steps_secs(&self, recon: &[StreetEdgeData], mode: RoutingMode) -> u32 {
+    /// `elapsed`: seconds into the leg at which `recon` starts (rush-hour clock).
+    pub(super) fn annotate_steps_secs(
+        &self,
+        recon: &[StreetEdgeData],
+        mode: RoutingMode,
+        elapsed: u32,
+    ) -> u32 {
         let steps: Vec<(StreetEdgeData, (f64, f64))> =
             recon.iter().map(|e| (*e, (0.0, 0.0))).collect();
-        self.annotate_steps(&steps, mode).mean.round() as u32
+        self.annotate_steps(&steps, mode, elapsed as f64).mean.round() as u32
     }
 
     fn annotate_steps(
         &self,
         steps: &[(StreetEdgeData, (f64, f64))],
         mode: RoutingMode,
+        elapsed: f64,
     ) -> TimeMoments {
         let speed = match mode {
-            RoutingMode::Walk => self.raptor.walking_speed_mps,
+            RoutingMode::Walk => self.walking_speed_mps(),
             RoutingMode::Bike => self.raptor.cycling_speed_mps,
             RoutingMode::Drive => self.raptor.driving_speed_mps,
         };
@@ -51,8 +58,11 @@ impl Graph {
             let mut mean = if mode == RoutingMode::Bike {
                 bike.edge_time(street) as f64 + edge_time_penalty(street, &model)
             } else {
-                edge_moments(street, speed, &model).mean
+                edge_moments(street, self.street_speed(mode, street, speed), &model).mean
             };
+            if mode == RoutingMode::Drive && street.car {
+                mean = self.rush_hour_secs(street, mean, elapsed + total.mean);
+            }
             let mut var = edge_variance(mode, street, &model, incoming, this_dir);
             if mode == RoutingMode::Bike {
                 mean += bike.speed_change_secs(prev, street, this_dir);
@@ -102,7 +112,7 @@ mod tests {
                 })
             })
             .collect();
-        g.annotate_steps(&steps, mode)
+        g.annotate_steps(&steps, mode, 0.0)
     }
 
     fn straight_path_graph() -> (Graph, Vec<NodeID>) {
@@ -417,6 +427,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn walk_front_fastest_diag_real_brussels() {
@@ -478,6 +489,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn annotate_path_smoke_real_brussels() {
@@ -32,7 +32,12 @@ struct QEntry {
 }
 impl Ord for QEntry {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.dist.cmp(&self.dist) // reversed → min-heap
+        // reversed → min-heap; node/used tie-break keeps equal-distance pops stable
+        other
+            .dist
+            .cmp(&self.dist)
+            .then_with(|| other.node.cmp(&self.node))
+            .then_with(|| other.used.cmp(&self.used))
     }
 }
 impl PartialOrd for QEntry {
@@ -46,7 +51,7 @@ impl Graph {
         self.get_node(id).map(|n| n.loc())
     }
 
-    fn is_ground_street_node(&self, id: NodeID, platform_nodes: &HashSet<NodeID>) -> bool {
+    pub(super) fn is_ground_street_node(&self, id: NodeID, platform_nodes: &HashSet<NodeID>) -> bool {
         if platform_nodes.contains(&id) {
             return false;
         }
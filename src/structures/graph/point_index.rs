@@ -0,0 +1,275 @@
+//! R-tree over `(lat, lon)` points with metric radius and k-nearest queries. The tree
+//! lives in plain degree space; metric queries over-fetch by a latitude-dependent bound
+//! and then rank by Haversine metres, so every query agrees on which point is nearest.
+//! Longitude wraps at ±180°: a query whose radius reaches the antimeridian is repeated at
+//! its mirror one turn away.
+//! Serialized as its flat point list and bulk-loaded back, so the on-disk form never
+//! depends on the tree layout.
+
+use rstar::{RTree, primitives::GeomWithData};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, ser::SerializeSeq};
+
+use crate::structures::LatLng;
+
+type Entry<T> = GeomWithData<[f64; 2], T>;
+
+/// Lower bound of metres per degree used to turn a metric radius into a degree one.
+/// Slightly below `LatLng::distance`'s 111.1 km so the candidate set is a superset.
+const MIN_M_PER_DEG: f64 = 111_000.0;
+
+#[derive(Debug, Clone)]
+pub struct PointIndex<T> {
+    tree: RTree<Entry<T>>,
+}
+
+impl<T> Default for PointIndex<T> {
+    fn default() -> Self {
+        PointIndex { tree: RTree::new() }
+    }
+}
+
+impl<T: Copy + PartialEq> PointIndex<T> {
+    pub fn bulk_load(points: Vec<([f64; 2], T)>) -> Self {
+        PointIndex {
+            tree: RTree::bulk_load(points.into_iter().map(|(p, t)| Entry::new(p, t)).collect()),
+        }
+    }
+
+    pub fn add(&mut self, lat: f64, lon: f64, item: T) {
+        self.tree.insert(Entry::new([lat, lon], item));
+    }
+
+    pub fn remove(&mut self, lat: f64, lon: f64, item: T) -> bool {
+        self.tree.remove(&Entry::new([lat, lon], item)).is_some()
+    }
+
+    pub fn contains(&self, lat: f64, lon: f64, item: T) -> bool {
+        self.tree.contains(&Entry::new([lat, lon], item))
+    }
+
+    pub fn size(&self) -> usize {
+        self.tree.size()
+    }
+
+    /// Metres per degree that no point within `span_deg` of `lat` can fall below
+    /// (zero at the poles, where a degree of longitude shrinks to nothing).
+    fn metres_per_deg_bound(lat: f64, span_deg: f64) -> f64 {
+        MIN_M_PER_DEG * (lat.abs() + span_deg).min(90.0).to_radians().cos().max(0.0)
+    }
+
+    /// The same longitude one turn away, towards the nearer antimeridian.
+    fn mirror_lon(lon: f64) -> f64 {
+        if lon < 0.0 { lon + 360.0 } else { lon - 360.0 }
+    }
+
+    /// Up to `k` items nearest by Haversine metres, closest first.
+    pub fn k_nearest(&self, lat: f64, lon: f64, k: usize) -> Vec<(f64, &T)> {
+        self.k_nearest_matching(lat, lon, k, f64::INFINITY, |_| true)
+    }
+
+    /// Nearest item within `max_m` Haversine metres that satisfies `keep`.
+    pub fn nearest_matching(
+        &self,
+        lat: f64,
+        lon: f64,
+        max_m: f64,
+        keep: impl FnMut(&T) -> bool,
+    ) -> Option<(f64, &T)> {
+        self.k_nearest_matching(lat, lon, 1, max_m, keep).into_iter().next()
+    }
+
+    /// Up to `k` items within `max_m` Haversine metres that satisfy `keep`, closest first.
+    pub fn k_nearest_matching(
+        &self,
+        lat: f64,
+        lon: f64,
+        k: usize,
+        max_m: f64,
+        keep: impl FnMut(&T) -> bool,
+    ) -> Vec<(f64, &T)> {
+        self.k_nearest_scan(lat, lon, k, max_m, keep).0
+    }
+
+    /// [`Self::k_nearest_matching`] plus the number of tree entries it looked at.
+    fn k_nearest_scan(
+        &self,
+        lat: f64,
+        lon: f64,
+        k: usize,
+        max_m: f64,
+        mut keep: impl FnMut(&T) -> bool,
+    ) -> (Vec<(f64, &T)>, usize) {
+        let mut best: Vec<(f64, &T)> = Vec::with_capacity(k + 1);
+        let mut visited = 0;
+        if k == 0 {
+            return (best, visited);
+        }
+        for mirror in [false, true] {
+            let qlon = if mirror {
+                // Only when the search radius (`max_m`, or the k-th hit once there are k)
+                // reaches the antimeridian; from the mirror, candidates sort by their
+                // wrapped distance, so the bound below stays tight.
+                let r_m = if best.len() == k { best[k - 1].0.min(max_m) } else { max_m };
+                let r_deg = r_m / Self::metres_per_deg_bound(lat, r_m / MIN_M_PER_DEG);
+                if lon.abs() + r_deg <= 180.0 {
+                    break;
+                }
+                Self::mirror_lon(lon)
+            } else {
+                lon
+            };
+            for (e, d2) in self.tree.nearest_neighbor_iter_with_distance_2([lat, qlon]) {
+                let d_deg = d2.sqrt();
+                let bound = d_deg * Self::metres_per_deg_bound(lat, d_deg);
+                if bound > max_m || (best.len() == k && bound > best[k - 1].0) {
+                    break;
+                }
+                visited += 1;
+                if best.iter().any(|&(_, b)| std::ptr::eq(b, &e.data)) {
+                    continue;
+                }
+                let [plat, plon] = *e.geom();
+                let m = LatLng::distance(&[lat, lon], &[plat, plon]);
+                let at = best.partition_point(|&(b, _)| b <= m);
+                if m <= max_m && at < k && keep(&e.data) {
+                    best.insert(at, (m, &e.data));
+                    best.truncate(k);
+                }
+            }
+        }
+        (best, visited)
+    }
+
+    /// Nearest item by Haversine metres.
+    pub fn nearest(&self, lat: f64, lon: f64) -> Option<(f64, &T)> {
+        self.k_nearest(lat, lon, 1).into_iter().next()
+    }
+
+    /// Every item within `radius_m` Haversine metres, closest first.
+    pub fn within(&self, lat: f64, lon: f64, radius_m: f64) -> Vec<(f64, &T)> {
+        let mut r_deg = radius_m / MIN_M_PER_DEG;
+        r_deg = radius_m / Self::metres_per_deg_bound(lat, r_deg);
+        // Disjoint from the direct disc as long as the radius stays under a half-turn.
+        let wraps = lon.abs() + r_deg > 180.0 && r_deg < 180.0;
+        let mirror = wraps.then(|| Self::mirror_lon(lon));
+        let mut out: Vec<(f64, &T)> = std::iter::once(lon)
+            .chain(mirror)
+            .flat_map(|qlon| self.tree.locate_within_distance([lat, qlon], r_deg * r_deg))
+            .filter_map(|e| {
+                let [plat, plon] = *e.geom();
+                let m = LatLng::distance(&[lat, lon], &[plat, plon]);
+                (m <= radius_m).then_some((m, &e.data))
+            })
+            .collect();
+        out.sort_by(|a, b| a.0.total_cmp(&b.0));
+        out
+    }
+}
+
+impl<T: Serialize> Serialize for PointIndex<T> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(Some(self.tree.size()))?;
+        for e in self.tree.iter() {
+            seq.serialize_element(&(e.geom(), &e.data))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Copy + PartialEq> Deserialize<'de> for PointIndex<T> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Vec::<([f64; 2], T)>::deserialize(d).map(PointIndex::bulk_load)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> PointIndex<u32> {
+        // Along a parallel at 50°N: 0.001° of longitude is ~71 m.
+        PointIndex::bulk_load((0..10).map(|i| ([50.0, 4.0 + i as f64 * 0.001], i)).collect())
+    }
+
+    #[test]
+    fn radius_is_metric_not_degrees() {
+        let idx = index();
+        let hits: Vec<u32> = idx.within(50.0, 4.0, 150.0).into_iter().map(|(_, &i)| i).collect();
+        assert_eq!(hits, vec![0, 1, 2], "~71 m spacing east-west");
+    }
+
+    #[test]
+    fn k_nearest_is_sorted_by_metres() {
+        let idx = index();
+        let hits = idx.k_nearest(50.0, 4.0052, 3);
+        let ids: Vec<u32> = hits.iter().map(|&(_, &i)| i).collect();
+        assert_eq!(ids, vec![5, 6, 4]);
+        assert!(hits.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert_eq!(idx.nearest(50.0, 3.0).map(|(_, &i)| i), Some(0));
+        let odd = idx.nearest_matching(50.0, 4.0, 500.0, |&i| i % 2 == 1);
+        assert_eq!(odd.map(|(_, &i)| i), Some(1));
+        assert!(idx.nearest_matching(50.0, 4.0, 500.0, |&i| i == 9).is_none(), "9 is ~640 m away");
+        let evens: Vec<u32> = idx
+            .k_nearest_matching(50.0, 4.0052, 3, 500.0, |&i| i % 2 == 0)
+            .into_iter()
+            .map(|(_, &i)| i)
+            .collect();
+        assert_eq!(evens, vec![6, 4, 8]);
+    }
+
+    #[test]
+    fn remove_and_serde_round_trip() {
+        let mut idx = index();
+        assert!(idx.remove(50.0, 4.0, 0));
+        assert!(!idx.contains(50.0, 4.0, 0));
+        let bytes = postcard::to_allocvec(&idx).unwrap();
+        let back: PointIndex<u32> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back.size(), 9);
+        assert_eq!(back.nearest(50.0, 4.0).map(|(_, &i)| i), Some(1));
+    }
+
+    #[test]
+    fn nearest_is_metric_at_high_latitude() {
+        // At 70°N a degree of longitude is ~38 km: 0.3° east (~11 km) is closer than
+        // 0.2° north (~22 km), though nearer in raw degrees the other way round.
+        let idx = PointIndex::bulk_load(vec![([70.2, 20.0], 0u32), ([70.0, 20.3], 1)]);
+        let (m, &i) = idx.nearest(70.0, 20.0).unwrap();
+        assert_eq!(i, 1);
+        assert!((m - 11_400.0).abs() < 500.0, "got {m}");
+        assert_eq!(idx.within(70.0, 20.0, 15_000.0).len(), 1);
+    }
+
+    #[test]
+    fn queries_wrap_at_the_antimeridian() {
+        // Fiji straddles 180°: 179.99°E and 179.99°W are ~2.1 km apart at 18°S.
+        let idx = PointIndex::bulk_load(vec![([-18.0, -179.99], 0u32), ([-18.0, 179.0], 1)]);
+        let (m, &i) = idx.nearest(-18.0, 179.99).unwrap();
+        assert_eq!(i, 0);
+        assert!(m < 2_500.0, "got {m}");
+        let near: Vec<u32> = idx.within(-18.0, 179.99, 5_000.0).into_iter().map(|(_, &i)| i).collect();
+        assert_eq!(near, vec![0]);
+        let both: Vec<u32> = idx.k_nearest(-18.0, -179.99, 5).into_iter().map(|(_, &i)| i).collect();
+        assert_eq!(both, vec![0, 1], "each point once, closest first");
+    }
+
+    #[test]
+    fn nearest_visits_only_a_handful_of_candidates() {
+        // 200 x 200 grid around Brussels, ~70 m apart.
+        let idx = PointIndex::bulk_load(
+            (0..40_000u32)
+                .map(|i| ([50.0 + (i / 200) as f64 * 0.001, 4.0 + (i % 200) as f64 * 0.001], i))
+                .collect(),
+        );
+        let (hits, visited) = idx.k_nearest_scan(50.1, 4.1, 1, f64::INFINITY, |_| true);
+        assert_eq!(hits.len(), 1);
+        assert!(visited < 20, "visited {visited} candidates");
+        let (hits, visited) = idx.k_nearest_scan(50.1, 4.1, 1, 500.0, |&i| i % 7 == 0);
+        assert_eq!(hits.len(), 1);
+        assert!(visited < 50, "visited {visited} candidates");
+        // Near the antimeridian the mirror pass still runs and stays short.
+        let fiji = PointIndex::bulk_load(vec![([-18.0, -179.99], 0u32), ([-18.0, 179.0], 1)]);
+        let (hits, visited) = fiji.k_nearest_scan(-18.0, 179.99, 1, f64::INFINITY, |_| true);
+        assert_eq!(*hits[0].1, 0);
+        assert!(visited <= 4, "visited {visited} candidates");
+    }
+}
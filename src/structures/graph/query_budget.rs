@@ -0,0 +1,212 @@
+//! Per-query search budget: a wall-clock deadline, an expansion cap and a cancellation
+//! flag, installed on the worker thread for the duration of one routing query. Search
+//! loops call [`tick`] per expansion and stop when it returns `true`; the query entry
+//! point then reports [`exhausted`] as a "search exhausted" error instead of a partial
+//! answer.
+
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Deadline and cancellation are checked once per this many ticks.
+const CHECK_EVERY: u64 = 1024;
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+    static MAX_EXPANSIONS: Cell<u64> = const { Cell::new(u64::MAX) };
+    static EXPANSIONS: Cell<u64> = const { Cell::new(0) };
+    static EXHAUSTED: Cell<Option<Exhausted>> = const { Cell::new(None) };
+    static CANCEL: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Why a search stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exhausted {
+    Timeout,
+    Expansions,
+    Cancelled,
+}
+
+impl Exhausted {
+    pub fn code(self) -> &'static str {
+        match self {
+            Exhausted::Timeout => "SEARCH_TIMEOUT",
+            Exhausted::Expansions => "SEARCH_EXPANSION_LIMIT",
+            Exhausted::Cancelled => "SEARCH_CANCELLED",
+        }
+    }
+}
+
+impl std::fmt::Display for Exhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Exhausted::Timeout => "search exhausted its time budget",
+            Exhausted::Expansions => "search exhausted its expansion budget",
+            Exhausted::Cancelled => "search cancelled",
+        })
+    }
+}
+
+/// Limits for one query. `None` = unbounded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryLimits {
+    pub time: Option<Duration>,
+    pub expansions: Option<u64>,
+}
+
+/// Installed limits; restores an unbounded, un-exhausted thread when dropped (blocking
+/// workers are pooled, so a spent budget must not leak into the next job).
+pub struct BudgetGuard(());
+
+impl Drop for BudgetGuard {
+    fn drop(&mut self) {
+        DEADLINE.with(|d| d.set(None));
+        MAX_EXPANSIONS.with(|m| m.set(u64::MAX));
+        EXHAUSTED.with(|x| x.set(None));
+    }
+}
+
+/// Start a query on this thread: resets counters and installs `limits`.
+pub fn begin(limits: QueryLimits) -> BudgetGuard {
+    DEADLINE.with(|d| d.set(limits.time.map(|t| Instant::now() + t)));
+    MAX_EXPANSIONS.with(|m| m.set(limits.expansions.unwrap_or(u64::MAX)));
+    EXPANSIONS.with(|e| e.set(0));
+    EXHAUSTED.with(|x| x.set(None));
+    BudgetGuard(())
+}
+
+/// Cancellation flag observed by [`tick`]; cleared when dropped.
+pub struct CancelGuard(());
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        CANCEL.with(|c| *c.borrow_mut() = None);
+    }
+}
+
+/// Let `flag` (set by the caller when the request is dropped or timed out) stop
+/// searches running on this thread.
+pub fn watch_cancel(flag: Arc<AtomicBool>) -> CancelGuard {
+    CANCEL.with(|c| *c.borrow_mut() = Some(flag));
+    CancelGuard(())
+}
+
+/// Count one expansion; `true` once the budget is spent (sticky until the next
+/// [`begin`]). The expansion cap is exact, the clock and flag are sampled.
+#[inline]
+pub fn tick() -> bool {
+    tick_n(1)
+}
+
+/// Count `k` expansions at once (e.g. one RAPTOR round scanning `k` routes).
+#[inline]
+pub fn tick_n(k: u64) -> bool {
+    if EXHAUSTED.with(|x| x.get()).is_some() {
+        return true;
+    }
+    let (before, n) = EXPANSIONS.with(|e| {
+        let before = e.get();
+        let n = before.saturating_add(k);
+        e.set(n);
+        (before, n)
+    });
+    let hit = if n > MAX_EXPANSIONS.with(|m| m.get()) {
+        Some(Exhausted::Expansions)
+    } else if before / CHECK_EVERY != n / CHECK_EVERY {
+        check_slow()
+    } else {
+        None
+    };
+    if hit.is_some() {
+        EXHAUSTED.with(|x| x.set(hit));
+    }
+    hit.is_some()
+}
+
+#[cold]
+fn check_slow() -> Option<Exhausted> {
+    if CANCEL.with(|c| c.borrow().as_ref().is_some_and(|f| f.load(Ordering::Relaxed))) {
+        return Some(Exhausted::Cancelled);
+    }
+    DEADLINE
+        .with(|d| d.get())
+        .is_some_and(|d| Instant::now() >= d)
+        .then_some(Exhausted::Timeout)
+}
+
+/// Why the current query stopped early, if it did.
+pub fn exhausted() -> Option<Exhausted> {
+    EXHAUSTED.with(|x| x.get())
+}
+
+pub fn expansions() -> u64 {
+    EXPANSIONS.with(|e| e.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unbounded_by_default() {
+        let _g = begin(QueryLimits::default());
+        for _ in 0..5000 {
+            assert!(!tick());
+        }
+        assert_eq!(exhausted(), None);
+        assert_eq!(expansions(), 5000);
+    }
+
+    #[test]
+    fn expansion_cap_is_exact_and_sticky() {
+        let _g = begin(QueryLimits {
+            time: None,
+            expansions: Some(10),
+        });
+        for _ in 0..10 {
+            assert!(!tick());
+        }
+        assert!(tick());
+        assert!(tick());
+        assert_eq!(exhausted(), Some(Exhausted::Expansions));
+        drop(_g);
+        assert_eq!(exhausted(), None, "a finished query leaves the thread clean");
+        assert!(!tick());
+    }
+
+    #[test]
+    fn zero_time_budget_stops_at_first_check() {
+        let _g = begin(QueryLimits {
+            time: Some(Duration::ZERO),
+            expansions: None,
+        });
+        let stopped = (0..CHECK_EVERY * 2).position(|_| tick());
+        assert_eq!(stopped, Some(CHECK_EVERY as usize - 1));
+        assert_eq!(exhausted(), Some(Exhausted::Timeout));
+    }
+
+    #[test]
+    fn bulk_ticks_sample_the_clock_when_crossing_a_check_boundary() {
+        let _g = begin(QueryLimits {
+            time: Some(Duration::ZERO),
+            expansions: None,
+        });
+        assert!(!tick_n(CHECK_EVERY - 1));
+        assert!(tick_n(5));
+        assert_eq!(exhausted(), Some(Exhausted::Timeout));
+    }
+
+    #[test]
+    fn cancellation_flag_stops_the_search() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let _c = watch_cancel(flag.clone());
+        let _g = begin(QueryLimits::default());
+        for _ in 0..CHECK_EVERY {
+            assert!(!tick());
+        }
+        flag.store(true, Ordering::Relaxed);
+        assert!((0..CHECK_EVERY).any(|_| tick()));
+        assert_eq!(exhausted(), Some(Exhausted::Cancelled));
+    }
+}
@@ -0,0 +1,201 @@
+//! Named routing profiles ("senior", "wheelchair", …) and the per-query walking state
+//! they install on the worker thread. A profile bundles the knobs a frontend would
+//! otherwise send raw: walking speed, max access walk, transfer slack, the
+//! wheelchair flag, night safety, stair avoidance and the access/egress walk caps.
+//! The walking state lives in thread-locals for the duration of one query (same
+//! lifecycle as [`super::query_budget`]) because they are read deep inside the street
+//! and RAPTOR searches.
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::structures::StreetEdgeData;
+
+thread_local! {
+    static WALKING_SPEED: Cell<Option<f64>> = const { Cell::new(None) };
+    static WHEELCHAIR: Cell<bool> = const { Cell::new(false) };
+    static NIGHT_SAFETY: Cell<f64> = const { Cell::new(0.0) };
+    static AVOID_STAIRS: Cell<bool> = const { Cell::new(false) };
+    static MAX_ACCESS_WALK_M: Cell<Option<u32>> = const { Cell::new(None) };
+    static MAX_EGRESS_WALK_M: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Unset fields fall back to the graph defaults; explicit query arguments win over
+/// the profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingProfile {
+    pub walking_speed_mps: Option<f64>,
+    /// Street access/egress walk budget (the query's `walkRadiusSecs`).
+    pub max_walk_secs: Option<u32>,
+    /// Extra seconds required at each transfer (the query's `arrivalSlackSecs`).
+    pub transfer_slack_secs: Option<u32>,
+    /// Never board or alight at a stop whose GTFS `wheelchair_boarding` is 2.
+    pub wheelchair: bool,
+    /// 0–1: how strongly walk legs favour lit streets (`CostWeights::walk_unlit`).
+    pub night_safety: Option<f64>,
+    /// Never walk `highway=steps` (escalators excepted): luggage, strollers.
+    pub avoid_stairs: bool,
+    /// Farthest walk (metres) from the origin to a first stop.
+    pub max_access_walk_m: Option<u32>,
+    /// Farthest walk (metres) from a last stop to the destination.
+    pub max_egress_walk_m: Option<u32>,
+}
+
+impl RoutingProfile {
+    /// Profiles every server offers; `routing_profiles` in the config adds to or
+    /// replaces them by name.
+    pub fn builtin() -> BTreeMap<String, RoutingProfile> {
+        BTreeMap::from([
+            ("default".to_string(), RoutingProfile::default()),
+            (
+                "senior".to_string(),
+                RoutingProfile {
+                    walking_speed_mps: Some(0.9),
+                    max_walk_secs: Some(600),
+                    transfer_slack_secs: Some(180),
+                    wheelchair: false,
+                    night_safety: None,
+                    avoid_stairs: false,
+                    max_access_walk_m: None,
+                    max_egress_walk_m: None,
+                },
+            ),
+            (
+                "fast".to_string(),
+                RoutingProfile {
+                    walking_speed_mps: Some(1.6),
+                    max_walk_secs: None,
+                    transfer_slack_secs: Some(0),
+                    wheelchair: false,
+                    night_safety: None,
+                    avoid_stairs: false,
+                    max_access_walk_m: None,
+                    max_egress_walk_m: None,
+                },
+            ),
+            (
+                "wheelchair".to_string(),
+                RoutingProfile {
+                    walking_speed_mps: Some(0.8),
+                    max_walk_secs: Some(600),
+                    transfer_slack_secs: Some(240),
+                    wheelchair: true,
+                    night_safety: None,
+                    avoid_stairs: true,
+                    max_access_walk_m: None,
+                    max_egress_walk_m: None,
+                },
+            ),
+        ])
+    }
+}
+
+/// Restores the graph walking speed and clears the wheelchair flag, night safety,
+/// stair avoidance and walk caps when dropped (blocking workers are pooled).
+pub struct ProfileGuard(());
+
+impl Drop for ProfileGuard {
+    fn drop(&mut self) {
+        WALKING_SPEED.with(|w| w.set(None));
+        WHEELCHAIR.with(|w| w.set(false));
+        NIGHT_SAFETY.with(|n| n.set(0.0));
+        AVOID_STAIRS.with(|a| a.set(false));
+        MAX_ACCESS_WALK_M.with(|m| m.set(None));
+        MAX_EGRESS_WALK_M.with(|m| m.set(None));
+    }
+}
+
+/// Install `profile`'s walking state on this thread for one query.
+pub fn begin(profile: &RoutingProfile) -> ProfileGuard {
+    WALKING_SPEED.with(|w| w.set(profile.walking_speed_mps.filter(|v| *v > 0.0)));
+    WHEELCHAIR.with(|w| w.set(profile.wheelchair));
+    let night = profile.night_safety.filter(|v| v.is_finite()).unwrap_or(0.0);
+    NIGHT_SAFETY.with(|n| n.set(night.clamp(0.0, 1.0)));
+    AVOID_STAIRS.with(|a| a.set(profile.avoid_stairs));
+    MAX_ACCESS_WALK_M.with(|m| m.set(profile.max_access_walk_m));
+    MAX_EGRESS_WALK_M.with(|m| m.set(profile.max_egress_walk_m));
+    ProfileGuard(())
+}
+
+/// Walking speed overriding the graph's for the current query, if any.
+#[inline]
+pub fn walking_speed() -> Option<f64> {
+    WALKING_SPEED.with(|w| w.get())
+}
+
+/// Whether the current query must avoid stops without wheelchair boarding.
+#[inline]
+pub fn wheelchair() -> bool {
+    WHEELCHAIR.with(|w| w.get())
+}
+
+/// Night-safety weight (0–1) of the current query; 0 ignores lighting.
+#[inline]
+pub fn night_safety() -> f64 {
+    NIGHT_SAFETY.with(|n| n.get())
+}
+
+/// Whether the current query must not walk stairs.
+#[inline]
+pub fn avoid_stairs() -> bool {
+    AVOID_STAIRS.with(|a| a.get())
+}
+
+/// Farthest walk (metres) the current query allows from the origin to a first stop.
+#[inline]
+pub fn max_access_walk_m() -> Option<u32> {
+    MAX_ACCESS_WALK_M.with(|m| m.get())
+}
+
+/// Farthest walk (metres) the current query allows from a last stop to the destination.
+#[inline]
+pub fn max_egress_walk_m() -> Option<u32> {
+    MAX_EGRESS_WALK_M.with(|m| m.get())
+}
+
+/// Whether the current query may not take `street` because it is a flight of stairs.
+#[inline]
+pub fn stairs_avoided(street: &StreetEdgeData) -> bool {
+    avoid_stairs() && street.attrs.is_stairs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_restores_defaults() {
+        let senior = RoutingProfile::builtin()["senior"];
+        {
+            let _g = begin(&RoutingProfile { wheelchair: true, ..senior });
+            assert_eq!(walking_speed(), Some(0.9));
+            assert!(wheelchair());
+        }
+        assert_eq!(walking_speed(), None);
+        assert!(!wheelchair());
+        {
+            let _g = begin(&RoutingProfile::builtin()["wheelchair"]);
+            assert!(avoid_stairs(), "a wheelchair never takes stairs");
+        }
+        assert!(!avoid_stairs());
+        {
+            let _g = begin(&RoutingProfile { max_egress_walk_m: Some(400), ..Default::default() });
+            assert_eq!((max_access_walk_m(), max_egress_walk_m()), (None, Some(400)));
+        }
+        assert_eq!(max_egress_walk_m(), None);
+        {
+            let _g = begin(&RoutingProfile { night_safety: Some(3.0), ..Default::default() });
+            assert_eq!(night_safety(), 1.0, "clamped");
+        }
+        assert_eq!(night_safety(), 0.0);
+    }
+
+    #[test]
+    fn non_positive_speed_is_ignored() {
+        let _g = begin(&RoutingProfile { walking_speed_mps: Some(0.0), ..Default::default() });
+        assert_eq!(walking_speed(), None);
+    }
+}
@@ -0,0 +1,115 @@
+//! Opt-in search trace for one query (`raptor(debug: true)`): labels pruned per reason,
+//! A* heuristic tightness and the query's latency decomposition, collected on the
+//! worker thread like `latency_profile`. Parallel route-scan helper threads are not
+//! traced, so RAPTOR cutoffs hit inside a parallel scan go uncounted.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+
+use super::latency_profile::LatencyProfile;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    static TRACE: RefCell<SearchTrace> = RefCell::new(SearchTrace::default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PruneReason {
+    /// Street label superseded at its node before it was popped.
+    StaleLabel,
+    /// Street path could no longer finish within the distance budget.
+    DistanceBudget,
+    /// Street label (plus heuristic) dominated by a path already at the destination.
+    DestinationDominated,
+    /// Street search reached a transit stop other than its origin (a dead end).
+    TransitStop,
+    /// Street label dominated at its node.
+    NodeDominated,
+    /// RAPTOR label arriving after the best known egress arrival (+ slack).
+    TargetCutoff,
+    /// RAPTOR label dominated in its stop/state cell.
+    StopDominated,
+}
+
+impl PruneReason {
+    pub fn name(self) -> &'static str {
+        match self {
+            PruneReason::StaleLabel => "stale_label",
+            PruneReason::DistanceBudget => "distance_budget",
+            PruneReason::DestinationDominated => "destination_dominated",
+            PruneReason::TransitStop => "transit_stop",
+            PruneReason::NodeDominated => "node_dominated",
+            PruneReason::TargetCutoff => "target_cutoff",
+            PruneReason::StopDominated => "stop_dominated",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchTrace {
+    pub pruned: BTreeMap<PruneReason, u64>,
+    /// `(h(origin), time to the first destination arrival)` in seconds, one per
+    /// informed street search.
+    pub heuristic: Vec<(f64, f64)>,
+    /// Filled when the query's latency profile ends.
+    pub latency: Option<LatencyProfile>,
+}
+
+/// Must be paired with one `end` before the next query begins on this thread.
+pub fn begin(on: bool) {
+    ENABLED.with(|e| e.set(on));
+    if on {
+        TRACE.with(|t| *t.borrow_mut() = SearchTrace::default());
+    }
+}
+
+pub fn end() -> Option<SearchTrace> {
+    let on = ENABLED.with(|e| e.replace(false));
+    on.then(|| TRACE.with(|t| std::mem::take(&mut *t.borrow_mut())))
+}
+
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.with(|e| e.get())
+}
+
+#[inline]
+pub fn prune(reason: PruneReason) {
+    if enabled() {
+        TRACE.with(|t| *t.borrow_mut().pruned.entry(reason).or_default() += 1);
+    }
+}
+
+pub fn heuristic(estimate_secs: f64, actual_secs: f64) {
+    if enabled() {
+        TRACE.with(|t| t.borrow_mut().heuristic.push((estimate_secs, actual_secs)));
+    }
+}
+
+pub fn latency(profile: &LatencyProfile) {
+    if enabled() {
+        TRACE.with(|t| t.borrow_mut().latency = Some(profile.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_between_begin_and_end() {
+        prune(PruneReason::StaleLabel);
+        assert!(end().is_none());
+
+        begin(true);
+        prune(PruneReason::StaleLabel);
+        prune(PruneReason::StaleLabel);
+        prune(PruneReason::TargetCutoff);
+        heuristic(60.0, 90.0);
+        let trace = end().expect("tracing was on");
+        assert_eq!(trace.pruned[&PruneReason::StaleLabel], 2);
+        assert_eq!(trace.pruned[&PruneReason::TargetCutoff], 1);
+        assert_eq!(trace.heuristic, vec![(60.0, 90.0)]);
+        assert!(!enabled(), "end disarms the trace");
+    }
+}
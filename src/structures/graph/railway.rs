@@ -661,6 +661,7 @@ mod tests {
                 accessibility: Availability::InformationNotAvailable,
                 id: format!("s{i}"),
                 platform_code: None,
+                code: None,
                 parent_station: None,
             }));
             stop_nodes.push(nid);
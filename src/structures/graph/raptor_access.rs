@@ -3,10 +3,9 @@ use std::{
     collections::{BinaryHeap, HashMap},
 };
 
-use kdtree::distance::squared_euclidean;
 
 use crate::structures::{
-    BikeCost, EdgeData, NodeID, StreetEdgeData, degrees_to_meters, plan::PlanCoordinate,
+    BikeCost, EdgeData, NodeID, Speed, StreetEdgeData, plan::PlanCoordinate,
 };
 
 use super::Graph;
@@ -18,22 +17,35 @@ pub enum StreetProfile {
     Car,
 }
 
+impl StreetProfile {
+    /// Whether this profile may travel `street` under its own mode (no foot fallback).
+    pub fn permits(self, street: &StreetEdgeData) -> bool {
+        match self {
+            StreetProfile::Foot => street.foot,
+            StreetProfile::Bike => street.bike,
+            StreetProfile::Car => street.car,
+        }
+    }
+}
+
 impl Graph {
     #[inline]
     pub(super) fn edge_secs(&self, street: &StreetEdgeData, profile: StreetProfile) -> Option<u32> {
+        if super::what_if::street_closed(street) || super::query_profile::stairs_avoided(street) {
+            return None;
+        }
         let speed_mps = match profile {
-            StreetProfile::Foot if street.foot => self.raptor.walking_speed_mps,
+            StreetProfile::Foot if street.foot => self.walking_speed_mps(),
             StreetProfile::Foot => return None,
             StreetProfile::Bike if street.bike => self.raptor.cycling_speed_mps,
-            StreetProfile::Bike if street.foot => self.raptor.walking_speed_mps,
+            StreetProfile::Bike if street.foot => self.walking_speed_mps(),
             StreetProfile::Bike => return None,
             // Car falls back to foot edges (snap connectors are foot-only).
-            StreetProfile::Car if street.car => self.raptor.driving_speed_mps,
-            StreetProfile::Car if street.foot => self.raptor.walking_speed_mps,
+            StreetProfile::Car if street.car => self.car_speed_mps(street),
+            StreetProfile::Car if street.foot => self.walking_speed_mps(),
             StreetProfile::Car => return None,
         };
-        let speed_mms = (speed_mps * 1000.0) as u32;
-        Some((street.length as u64 * 1000 / speed_mms as u64) as u32)
+        Some(Speed::from_mps(speed_mps).travel_time(street.length_m()).0)
     }
 
     pub(super) fn node_coord(&self, id: NodeID) -> PlanCoordinate {
@@ -121,16 +133,18 @@ impl Graph {
         profile: StreetProfile,
     ) -> HashMap<NodeID, u32> {
         // Car is phased Drive → (park) → Walk, never reversed; the state `bool`
-        // is `walking` (`false` = still in the car). Foot/Bike stay `false`.
+        // is `walking` (`false` = still in the car). Foot/Bike stay `false`. The
+        // second `bool` is "arrived over a synthetic link" (see `is_spanning_link`).
         let car = matches!(profile, StreetProfile::Car);
-        let mut dist: HashMap<(NodeID, bool), u32> = HashMap::new();
-        let mut pq: BinaryHeap<Reverse<(u32, (NodeID, bool))>> = BinaryHeap::new();
+        type State = (NodeID, bool, bool);
+        let mut dist: HashMap<State, u32> = HashMap::new();
+        let mut pq: BinaryHeap<Reverse<(u32, State)>> = BinaryHeap::new();
 
-        dist.insert((origin, false), 0);
-        pq.push(Reverse((0, (origin, false))));
+        dist.insert((origin, false, false), 0);
+        pq.push(Reverse((0, (origin, false, false))));
 
-        while let Some(Reverse((d, (node, walking)))) = pq.pop() {
-            if d > *dist.get(&(node, walking)).unwrap_or(&u32::MAX) {
+        while let Some(Reverse((d, (node, walking, via_link)))) = pq.pop() {
+            if d > *dist.get(&(node, walking, via_link)).unwrap_or(&u32::MAX) {
                 continue;
             }
 
@@ -144,8 +158,11 @@ impl Graph {
             for edge in neighbors {
                 match edge {
                     EdgeData::Street(street) => {
+                        if via_link && street.is_spanning_link() {
+                            continue;
+                        }
                         let step = if car {
-                            self.car_edge_step(street, walking)
+                            self.car_edge_step(street, walking, d)
                         } else {
                             self.edge_secs(street, profile).map(|t| (t, false))
                         };
@@ -154,18 +171,17 @@ impl Graph {
                         };
                         let nd = d.saturating_add(t);
                         if nd <= max_seconds {
-                            let entry = dist
-                                .entry((street.destination, next_walking))
-                                .or_insert(u32::MAX);
+                            let key = (street.destination, next_walking, street.is_spanning_link());
+                            let entry = dist.entry(key).or_insert(u32::MAX);
                             if nd < *entry {
                                 *entry = nd;
-                                pq.push(Reverse((nd, (street.destination, next_walking))));
+                                pq.push(Reverse((nd, key)));
                             }
                         }
                     }
                     EdgeData::Transit(transit) => {
                         let entry = dist
-                            .entry((transit.destination, walking))
+                            .entry((transit.destination, walking, false))
                             .or_insert(u32::MAX);
                         if d < *entry {
                             *entry = d;
@@ -176,24 +192,35 @@ impl Graph {
         }
 
         let mut best: HashMap<NodeID, u32> = HashMap::new();
-        for (&(node, _), &d) in &dist {
+        for (&(node, _, _), &d) in &dist {
             let e = best.entry(node).or_insert(u32::MAX);
             *e = (*e).min(d);
         }
         best
     }
 
-    /// Once `walking`, only foot edges are usable (the car is left behind).
+    /// Once `walking`, only foot edges are usable (the car is left behind). `elapsed`
+    /// is the seconds since departure at which the edge is entered (rush hours).
     #[inline]
-    pub(super) fn car_edge_step(&self, street: &StreetEdgeData, walking: bool) -> Option<(u32, bool)> {
-        let secs = |speed_mps: f64| {
-            let speed_mms = (speed_mps * 1000.0) as u32;
-            (street.length as u64 * 1000 / speed_mms as u64) as u32
-        };
+    pub(super) fn car_edge_step(
+        &self,
+        street: &StreetEdgeData,
+        walking: bool,
+        elapsed: u32,
+    ) -> Option<(u32, bool)> {
+        if super::what_if::street_closed(street) || super::query_profile::stairs_avoided(street) {
+            return None;
+        }
+        let secs = |speed_mps: f64| Speed::from_mps(speed_mps).travel_time(street.length_m()).0;
         if !walking && street.car {
-            Some((secs(self.raptor.driving_speed_mps), false))
+            let free = secs(self.car_speed_mps(street));
+            if super::rush_hour::departure().is_none() {
+                return Some((free, false));
+            }
+            let t = self.rush_hour_secs(street, free as f64, elapsed as f64);
+            Some((t.round() as u32, false))
         } else if street.foot {
-            Some((secs(self.raptor.walking_speed_mps), true))
+            Some((secs(self.walking_speed_mps()), true))
         } else {
             None
         }
@@ -210,13 +237,8 @@ impl Graph {
     ) -> u32 {
         self.raptor
             .transit_stops_tree
-            .nearest(&[loc.latitude, loc.longitude], 1, &squared_euclidean)
-            .ok()
-            .and_then(|v| v.into_iter().next())
-            .map(|(dist_sq, _)| {
-                let dist_m = degrees_to_meters(dist_sq, loc.latitude);
-                (dist_m / self.raptor.walking_speed_mps) as u32
-            })
+            .nearest(loc.latitude, loc.longitude)
+            .map(|(dist_m, _)| (dist_m / self.walking_speed_mps()) as u32)
             .unwrap_or(straight_line_secs)
     }
 
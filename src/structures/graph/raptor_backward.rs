@@ -18,7 +18,7 @@ impl Graph {
         for t in (0..col.len()).rev() {
             if col[t].arrival <= max_arrival && col[t].alight_allowed {
                 let svc = self.raptor.transit_trips[trip_ids[t].0 as usize].service_id;
-                if self.raptor.transit_services[svc.0 as usize].is_active(date, weekday)
+                if self.raptor.is_service_active(svc, date, weekday)
                     && !rt.is_canceled(trip_ids[t])
                 {
                     return Some(t);
@@ -146,6 +146,7 @@ impl Graph {
         marked: &mut Vec<usize>,
         is_marked: &mut [bool],
     ) {
+        let walk_scale = self.transfer_walk_scale();
         let n = marked.len(); // snapshot: appended marks must not be re-relaxed this pass
         for i in 0..n {
             let stop = marked[i];
@@ -155,6 +156,8 @@ impl Graph {
             let rev = self.raptor.transit_idx_stop_reverse_transfers[stop]
                 .of(&self.raptor.transit_stop_reverse_transfers);
             for &(source, walk_time) in rev {
+                let walk_time =
+                    walk_scale.map_or(walk_time, |k| (walk_time as f64 * k).round() as u32);
                 let t = lambda_k[stop].saturating_sub(walk_time);
                 if t > 0 && t > lambda_k[source] {
                     lambda_k[source] = t;
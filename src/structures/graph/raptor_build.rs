@@ -1,15 +1,14 @@
-use kdtree::{KdTree, distance::squared_euclidean};
 
 use std::collections::{BTreeSet, HashMap};
 
 use crate::structures::{
-    LatLng, NodeData, NodeID, meters_to_degrees,
+    LatLng, NodeData, NodeID, PointIndex,
     raptor::{Lookup, PatternID, PatternInfo},
 };
 
 use super::{Graph, MAX_TRANSFER_DISTANCE_M, StationInfo, StationLine};
 
-fn mode_rank(mode: &str) -> u8 {
+pub(super) fn mode_rank(mode: &str) -> u8 {
     match mode {
         "Rail" => 0,
         "Subway" => 1,
@@ -19,7 +18,7 @@ fn mode_rank(mode: &str) -> u8 {
     }
 }
 
-fn natural_key(short_name: &str) -> (u8, u64, String) {
+pub(super) fn natural_key(short_name: &str) -> (u8, u64, String) {
     if !short_name.is_empty() && short_name.bytes().all(|b| b.is_ascii_digit()) {
         (0, short_name.parse::<u64>().unwrap_or(u64::MAX), String::new())
     } else {
@@ -55,7 +54,7 @@ impl Graph {
         {
             return;
         }
-        let has_shapes = r.transit_pattern_shapes.len() == n_patterns
+        let has_shapes = r.transit_pattern_shape_ids.len() == n_patterns
             && r.transit_pattern_shape_stop_idx.len() == n_patterns;
 
         let mut new_patterns: Vec<PatternInfo> = Vec::with_capacity(n_patterns);
@@ -67,7 +66,7 @@ impl Graph {
         let mut new_stop_times: Vec<crate::ingestion::gtfs::StopTime> =
             Vec::with_capacity(r.transit_pattern_stop_times.len());
         let mut new_idx_stop_times: Vec<Lookup> = Vec::with_capacity(n_patterns);
-        let mut new_shapes: Vec<Vec<crate::structures::LatLng>> = Vec::new();
+        let mut new_shape_ids: Vec<u32> = Vec::new();
         let mut new_shape_stop_idx: Vec<Vec<u32>> = Vec::new();
 
         for p in 0..n_patterns {
@@ -130,7 +129,7 @@ impl Graph {
                 });
 
                 if has_shapes {
-                    new_shapes.push(r.transit_pattern_shapes[p].clone());
+                    new_shape_ids.push(r.transit_pattern_shape_ids[p]);
                     new_shape_stop_idx.push(r.transit_pattern_shape_stop_idx[p].clone());
                 }
             }
@@ -152,7 +151,7 @@ impl Graph {
         r.transit_pattern_stop_times = new_stop_times;
         r.transit_idx_pattern_stop_times = new_idx_stop_times;
         if has_shapes {
-            r.transit_pattern_shapes = new_shapes;
+            r.transit_pattern_shape_ids = new_shape_ids;
             r.transit_pattern_shape_stop_idx = new_shape_stop_idx;
         }
     }
@@ -193,7 +192,9 @@ impl Graph {
         self.raptor.transit_stop_ids.clear();
         self.raptor.transit_stop_names.clear();
         self.raptor.transit_stop_platform_codes.clear();
-        self.raptor.transit_stops_tree = KdTree::new(2);
+        self.raptor.transit_stop_codes.clear();
+        self.raptor.transit_stop_wheelchair.clear();
+        self.raptor.transit_stops_tree = PointIndex::default();
 
         for (i, node) in self.nodes.iter().enumerate() {
             if let NodeData::TransitStop(stop) = node {
@@ -205,11 +206,12 @@ impl Graph {
                     .transit_stop_names
                     .push(crate::ingestion::gtfs::harmonize_display_name(&stop.name));
                 self.raptor.transit_stop_platform_codes.push(stop.platform_code.clone());
+                self.raptor.transit_stop_codes.push(stop.code.clone());
+                self.raptor.transit_stop_wheelchair.push(stop.accessibility);
                 let loc = node.loc();
-                let _ = self
-                    .raptor
+                self.raptor
                     .transit_stops_tree
-                    .add([loc.latitude, loc.longitude], compact);
+                    .add(loc.latitude, loc.longitude, compact);
             }
         }
     }
@@ -404,12 +406,7 @@ impl Graph {
             let nearby = self
                 .raptor
                 .transit_stops_tree
-                .within(
-                    &[loc.latitude, loc.longitude],
-                    meters_to_degrees(MAX_TRANSFER_DISTANCE_M),
-                    &squared_euclidean,
-                )
-                .unwrap_or_default();
+                .within(loc.latitude, loc.longitude, MAX_TRANSFER_DISTANCE_M);
 
             for &(_, &compact_neighbor) in &nearby {
                 if compact_neighbor == i {
@@ -1,9 +1,10 @@
 //! Exact foot access/egress via a Customizable Contraction Hierarchy (CCH).
 //!
 //! Foot cost is direction-symmetric, so the same one-to-many sweep answers both
-//! access (origin → stops) and egress (dest → stops).
+//! access (origin → stops) and egress (dest → stops). The same hierarchy also answers
+//! point-to-point street-only walks, replacing the seeded Dijkstra when present.
 
-use routingkit_cch::{CCH, CCHMetric, CCHOneToMany, compute_order_inertial};
+use routingkit_cch::{CCH, CCHMetric, CCHOneToMany, CCHQuery, compute_order_inertial};
 
 use super::Graph;
 use crate::structures::LatLng;
@@ -153,6 +154,30 @@ impl Graph {
         self.cch_one_to_many(cch, dest)
     }
 
+    /// Exact foot seconds between two coordinates via a point-to-point CCH query over the
+    /// ≤2 bounding junctions of each end. Equals `walk_secs_coord_to_coord`; `None` if either
+    /// end is unsnappable or the walk exceeds `bound`.
+    pub fn cch_walk_secs(&self, cch: &CchAccess, o: LatLng, d: LatLng, bound: u32) -> Option<u32> {
+        let cg = self
+            .contracted
+            .as_ref()
+            .expect("cch_walk_secs: contracted graph must be present");
+        let radius = self.raptor.edge_snap_radius_m;
+        cg.walk_secs_coord_to_coord_with(self, o, d, radius, bound, |o_entries, d_entries| {
+            if o_entries.is_empty() || d_entries.is_empty() {
+                return None;
+            }
+            let mut q = CCHQuery::new(&cch.metric);
+            for &(ji, s) in o_entries {
+                q.add_source(ji as u32, s);
+            }
+            for &(ji, s) in d_entries {
+                q.add_target(ji as u32, s);
+            }
+            q.run().distance()
+        })
+    }
+
     fn cch_one_to_many(&self, cch: &CchAccess, coord: LatLng) -> Vec<(usize, u32)> {
         if cch.targets.is_empty() {
             return Vec::new();
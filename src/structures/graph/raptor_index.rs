@@ -1,16 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use gtfs_structures::RouteType;
-use kdtree::KdTree;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     ingestion::gtfs::{
-        AgencyInfo, RouteInfo, ServicePattern, StopTime, TimetableSegment, TripId, TripInfo,
-        TripSegment,
+        ActiveServices, AgencyInfo, NameTranslations, RouteInfo, ServiceCalendar, ServiceId, ServicePattern, StopTime, TimetableSegment, TripId, TripInfo,
+        TripSegments,
     },
     structures::{
-        DelayCDF, LatLng, NodeID,
+        DelayCDF, LatLng, NodeID, PointIndex,
         raptor::{Lookup, PatternID, PatternInfo},
     },
 };
@@ -40,10 +39,34 @@ pub struct StationInfo {
     pub platform_stop_indices: Vec<usize>,
 }
 
+/// One `default_routing.route_overrides` entry: values for the routes of `agency`
+/// (normalized name, as fare operators match) and/or of `route_type`; `None` matches
+/// any.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouteOverride {
+    pub agency: Option<String>,
+    pub route_type: Option<RouteType>,
+    pub co2_g_per_km: Option<f64>,
+    pub flat_fare_cents: Option<u32>,
+    pub average_speed_kmh: Option<f64>,
+}
+
+/// A route's values after [`RouteOverride`] resolution; each field comes from the
+/// first matching override that sets it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RouteFigures {
+    pub co2_g_per_km: Option<f64>,
+    pub flat_fare_cents: Option<u32>,
+    pub average_speed_kmh: Option<f64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RaptorIndex {
-    pub transit_departures: Vec<TripSegment>,
+    pub transit_departures: TripSegments,
     pub transit_services: Vec<ServicePattern>,
+    /// Day-bitset expansion of `transit_services`, rebuilt by `build_runtime_indices`.
+    #[serde(skip)]
+    pub transit_service_calendar: ServiceCalendar,
     pub transit_trips: Vec<TripInfo>,
     pub transit_routes: Vec<RouteInfo>,
     pub transit_agencies: Vec<AgencyInfo>,
@@ -65,7 +88,7 @@ pub struct RaptorIndex {
 
     pub transit_node_to_stop: Vec<u32>,
     pub transit_stop_to_node: Vec<NodeID>,
-    pub transit_stops_tree: KdTree<f64, usize, [f64; 2]>,
+    pub transit_stops_tree: PointIndex<usize>,
 
     /// Must carry the raw GTFS string id to match realtime alert `route_id` fields.
     #[serde(default)]
@@ -75,6 +98,9 @@ pub struct RaptorIndex {
     pub transit_trip_ids: Vec<String>,
     #[serde(skip)]
     pub trip_id_to_index: HashMap<String, TripId>,
+    /// `(pattern, trip column)` per trip; `u32::MAX` pattern for a trip no pattern carries.
+    #[serde(skip)]
+    pub trip_patterns: Vec<(u32, u32)>,
 
     #[serde(default)]
     pub transit_stop_ids: Vec<String>,
@@ -89,6 +115,26 @@ pub struct RaptorIndex {
     #[serde(default)]
     pub transit_stop_platform_codes: Vec<Option<String>>,
 
+    /// GTFS `stop_code` per compact stop (parallel to names).
+    #[serde(default)]
+    pub transit_stop_codes: Vec<Option<String>>,
+
+    /// GTFS `wheelchair_boarding` per compact stop (parallel to names).
+    #[serde(default)]
+    pub transit_stop_wheelchair: Vec<gtfs_structures::Availability>,
+
+    /// Stop and route names per language from GTFS `translations.txt`.
+    #[serde(default)]
+    pub name_translations: NameTranslations,
+
+    /// Compact stops whose every step-free path to the street rides an elevator: the
+    /// elevator ids on each alternative path. Absent stops do not depend on elevators.
+    #[serde(default)]
+    pub stop_elevator_routes: HashMap<u32, Vec<Vec<String>>>,
+    /// Stops cut off by `elevator_outages` (every alternative above hits an outage).
+    #[serde(skip, default)]
+    pub elevator_blocked_stops: HashSet<u32>,
+
     #[serde(default)]
     pub transit_stations: Vec<StationInfo>,
     #[serde(skip)]
@@ -107,8 +153,9 @@ pub struct RaptorIndex {
     #[serde(skip)]
     pub tighten_long_transfers: bool,
 
+    /// Per pattern, its polyline in `Graph::shapes` ([`super::NO_SHAPE`] when none).
     #[serde(default)]
-    pub transit_pattern_shapes: Vec<Vec<LatLng>>,
+    pub transit_pattern_shape_ids: Vec<u32>,
     #[serde(default)]
     pub transit_pattern_shape_stop_idx: Vec<Vec<u32>>,
 
@@ -166,12 +213,57 @@ pub struct RaptorIndex {
     #[serde(skip, default = "RaptorIndex::default_use_cch_access")]
     pub use_cch_access: bool,
 
+    /// A* street-search lower bound. `Landmarks` needs `Graph::prepare_landmarks`.
+    #[serde(skip, default)]
+    pub street_heuristic: super::landmarks::StreetHeuristic,
+
     #[serde(skip, default = "RaptorIndex::default_profile_latency")]
     pub profile_latency: bool,
 
     #[serde(skip, default = "RaptorIndex::default_max_window_secs")]
     pub max_window_secs: u32,
 
+    /// Seconds added to a plan's arrival per crowding step of each transit leg when a
+    /// query asks to `avoidCrowding`.
+    #[serde(skip, default = "RaptorIndex::default_crowding_penalty_secs")]
+    pub crowding_penalty_secs: u32,
+
+    /// Route types whose trips carry bikes when `trips.txt` leaves `bikes_allowed`
+    /// unset; an explicit GTFS value always wins.
+    #[serde(skip, default)]
+    pub bike_route_types: Vec<RouteType>,
+
+    /// Per-mode cost multipliers and board penalties (`default_routing.modes`) the
+    /// RAPTOR scan ranks its labels and plans by; unlisted modes are neutral.
+    #[serde(skip, default)]
+    pub mode_preferences: Vec<(RouteType, crate::structures::ModePreference)>,
+
+    /// Free-flow car speeds per highway class (`car_speeds_kmh`); unset classes drive
+    /// at `driving_speed_mps`.
+    #[serde(skip, default)]
+    pub car_speeds: super::CarSpeeds,
+    /// Daily car congestion windows (`car_rush_hours`); empty = always free flow.
+    #[serde(skip, default)]
+    pub car_rush_hours: Vec<super::rush_hour::RushHourWindow>,
+
+    /// Per-mode fastest-edge multipliers for the A* time bound, scanned at startup.
+    #[serde(skip, default)]
+    pub estimator_factors: super::estimator::EstimatorFactors,
+    /// Configured A* speeds (walk, bike, drive), accepted only when admissible.
+    #[serde(skip, default)]
+    pub estimator_overrides: [Option<f64>; 3],
+
+    /// Named routing profiles selectable per query (built-ins plus `routing_profiles`).
+    #[serde(skip, default = "RaptorIndex::default_routing_profiles")]
+    pub routing_profiles: std::collections::BTreeMap<String, crate::structures::RoutingProfile>,
+
+    /// Per-query search budget: wall-clock milliseconds and label/route expansions.
+    /// `0` = unbounded. An exhausted query errors instead of answering partially.
+    #[serde(skip, default)]
+    pub max_query_ms: u64,
+    #[serde(skip, default)]
+    pub max_search_expansions: u64,
+
     /// Isochrone sampling grid step, in METRES.
     #[serde(skip, default = "RaptorIndex::default_travel_map_grid_step_m")]
     pub travel_map_grid_step_m: f64,
@@ -190,6 +282,14 @@ pub struct RaptorIndex {
     #[serde(skip, default = "RaptorIndex::default_edge_snap_radius_m")]
     pub edge_snap_radius_m: f64,
 
+    /// Snap distance (m) above which plans carry a `FAR_SNAP` warning; 0 = never.
+    #[serde(skip, default = "RaptorIndex::default_snap_warning_m")]
+    pub snap_warning_m: u32,
+
+    /// Street nodes a snapped endpoint must reach not to be `SNAPPED_TO_ISLAND`; 0 = off.
+    #[serde(skip, default = "RaptorIndex::default_snap_island_nodes")]
+    pub snap_island_nodes: usize,
+
     #[serde(skip, default)]
     pub bike_profile: crate::structures::BikeProfile,
 
@@ -259,6 +359,15 @@ pub struct RaptorIndex {
     #[serde(skip, default)]
     pub unknown_operator_names: Vec<String>,
 
+    /// Config-supplied emission factors, flat fares and speeds, in config order.
+    #[serde(skip, default)]
+    pub route_overrides: Vec<RouteOverride>,
+
+    /// RouteId → resolved [`RouteFigures`], parallel to `transit_routes`; empty
+    /// without overrides.
+    #[serde(skip, default)]
+    pub route_figures: Vec<RouteFigures>,
+
     /// Per-pattern cumulative railway distance (m) from the pattern's first stop to
     /// each stop, parallel to `transit_idx_pattern_stops`. Per-km SNCB fare is
     /// `per_km × (cum[alight] - cum[board])`. Rebuilt on load.
@@ -301,8 +410,9 @@ impl Default for RaptorIndex {
 impl RaptorIndex {
     pub fn new() -> Self {
         RaptorIndex {
-            transit_departures: Vec::new(),
+            transit_departures: TripSegments::default(),
             transit_services: Vec::new(),
+            transit_service_calendar: ServiceCalendar::default(),
             transit_trips: Vec::new(),
             transit_routes: Vec::new(),
             transit_agencies: Vec::new(),
@@ -324,15 +434,21 @@ impl RaptorIndex {
 
             transit_node_to_stop: Vec::new(),
             transit_stop_to_node: Vec::new(),
-            transit_stops_tree: KdTree::new(2),
+            transit_stops_tree: PointIndex::default(),
 
             transit_route_ids: Vec::new(),
             transit_trip_ids: Vec::new(),
             trip_id_to_index: HashMap::new(),
+            trip_patterns: Vec::new(),
             transit_stop_ids: Vec::new(),
             stop_id_to_index: HashMap::new(),
             transit_stop_names: Vec::new(),
             transit_stop_platform_codes: Vec::new(),
+            transit_stop_codes: Vec::new(),
+            transit_stop_wheelchair: Vec::new(),
+            name_translations: NameTranslations::default(),
+            stop_elevator_routes: HashMap::new(),
+            elevator_blocked_stops: HashSet::new(),
 
             transit_stations: Vec::new(),
             transit_stop_to_station: Vec::new(),
@@ -342,7 +458,7 @@ impl RaptorIndex {
             transit_idx_stop_reverse_transfers: Vec::new(),
             tighten_long_transfers: false,
 
-            transit_pattern_shapes: Vec::new(),
+            transit_pattern_shape_ids: Vec::new(),
             transit_pattern_shape_stop_idx: Vec::new(),
             transit_pattern_segment_timetables: Vec::new(),
 
@@ -361,13 +477,26 @@ impl RaptorIndex {
             arrival_slack_secs: Self::default_arrival_slack_secs(),
             unrestricted_transfers: Self::default_unrestricted_transfers(),
             use_cch_access: Self::default_use_cch_access(),
+            street_heuristic: Default::default(),
             profile_latency: Self::default_profile_latency(),
             max_window_secs: Self::default_max_window_secs(),
+            crowding_penalty_secs: Self::default_crowding_penalty_secs(),
+            bike_route_types: Vec::new(),
+            mode_preferences: Vec::new(),
+            car_speeds: Default::default(),
+            car_rush_hours: Vec::new(),
+            estimator_factors: Default::default(),
+            estimator_overrides: [None; 3],
+            routing_profiles: Self::default_routing_profiles(),
+            max_query_ms: 0,
+            max_search_expansions: 0,
             travel_map_grid_step_m: Self::default_travel_map_grid_step_m(),
             travel_map_max_cells: Self::default_travel_map_max_cells(),
             travel_map_window_sample_secs: Self::default_travel_map_window_sample_secs(),
             max_snap_distance_m: Self::default_max_snap_distance_m(),
             edge_snap_radius_m: Self::default_edge_snap_radius_m(),
+            snap_warning_m: Self::default_snap_warning_m(),
+            snap_island_nodes: Self::default_snap_island_nodes(),
             bike_profile: crate::structures::BikeProfile::default(),
             street_time: Self::default_street_time(),
             distance_budget: Self::default_distance_budget(),
@@ -386,6 +515,8 @@ impl RaptorIndex {
             fare_model: crate::structures::cost::FareModel::default(),
             operator_fare_of_route: Vec::new(),
             unknown_operator_names: Vec::new(),
+            route_overrides: Vec::new(),
+            route_figures: Vec::new(),
             sncb_pattern_cum_railway_m: Vec::new(),
             sncb_stop_zone: Vec::new(),
             sncb_airport_stop: Vec::new(),
@@ -451,6 +582,22 @@ impl RaptorIndex {
         24 * 3600
     }
 
+    pub fn default_crowding_penalty_secs() -> u32 {
+        300
+    }
+
+    pub fn default_routing_profiles() -> std::collections::BTreeMap<String, crate::structures::RoutingProfile> {
+        crate::structures::RoutingProfile::builtin()
+    }
+
+    pub fn query_limits(&self) -> super::query_budget::QueryLimits {
+        super::query_budget::QueryLimits {
+            time: (self.max_query_ms > 0)
+                .then(|| std::time::Duration::from_millis(self.max_query_ms)),
+            expansions: (self.max_search_expansions > 0).then_some(self.max_search_expansions),
+        }
+    }
+
     pub fn default_travel_map_grid_step_m() -> f64 {
         300.0
     }
@@ -471,6 +618,14 @@ impl RaptorIndex {
         300.0
     }
 
+    pub fn default_snap_warning_m() -> u32 {
+        300
+    }
+
+    pub fn default_snap_island_nodes() -> usize {
+        40
+    }
+
     pub fn default_street_time() -> crate::structures::StreetTimeModel {
         crate::structures::StreetTimeModel::default()
     }
@@ -537,6 +692,7 @@ impl RaptorIndex {
             .transit_trip_ids
             .iter()
             .enumerate()
+            .filter(|(_, s)| !s.is_empty())
             .map(|(i, s)| (s.clone(), TripId(i as u32)))
             .collect();
         self.stop_id_to_index = self
@@ -546,8 +702,33 @@ impl RaptorIndex {
             .filter(|(_, s)| !s.is_empty())
             .map(|(i, s)| (s.clone(), i))
             .collect();
+        let mut trip_patterns = vec![(u32::MAX, 0); self.transit_trips.len()];
+        for (p, lk) in self.transit_idx_pattern_trips.iter().enumerate() {
+            for (t, trip) in lk.of(&self.transit_pattern_trips).iter().enumerate() {
+                if let Some(slot) = trip_patterns.get_mut(trip.0 as usize) {
+                    *slot = (p as u32, t as u32);
+                }
+            }
+        }
+        self.trip_patterns = trip_patterns;
         self.rebuild_station_lookups();
+        self.rebuild_route_figures();
         self.rebuild_operator_fare_lookup();
+        self.transit_service_calendar = ServiceCalendar::build(&self.transit_services);
+    }
+
+    /// Whether `service` runs on `date`: one bit test when the calendar covers the date,
+    /// else the `ServicePattern` exception/range check.
+    #[inline]
+    pub fn is_service_active(&self, service: ServiceId, date: u32, weekday: u8) -> bool {
+        self.transit_service_calendar
+            .is_active(service, date, weekday)
+            .unwrap_or_else(|| self.transit_services[service.0 as usize].is_active(date, weekday))
+    }
+
+    /// Every service's [`Self::is_service_active`] on `date`, resolved once for a request.
+    pub fn active_services(&self, date: u32, weekday: u8) -> ActiveServices {
+        ActiveServices::from_fn(self.transit_services.len(), |s| self.is_service_active(s, date, weekday))
     }
 
     fn normalize_agency_name(name: &str) -> String {
@@ -566,9 +747,44 @@ impl RaptorIndex {
             .any(|tok| tok == op_token)
     }
 
+    /// Resolve `route_overrides` onto every route (`route_figures`).
+    pub fn rebuild_route_figures(&mut self) {
+        if self.route_overrides.is_empty() {
+            self.route_figures = Vec::new();
+            return;
+        }
+        let overrides: Vec<(Option<String>, &RouteOverride)> = self
+            .route_overrides
+            .iter()
+            .map(|o| (o.agency.as_deref().map(Self::normalize_agency_name), o))
+            .collect();
+        self.route_figures = self
+            .transit_routes
+            .iter()
+            .map(|route| {
+                let agency = self
+                    .transit_agencies
+                    .get(route.agency_id.0 as usize)
+                    .map(|a| Self::normalize_agency_name(&a.name))
+                    .unwrap_or_default();
+                let mut figures = RouteFigures::default();
+                for (tok, o) in &overrides {
+                    let agency_ok = tok.as_deref().is_none_or(|t| Self::agency_matches_operator(&agency, t));
+                    if !agency_ok || o.route_type.is_some_and(|t| t != route.route_type) {
+                        continue;
+                    }
+                    figures.co2_g_per_km = figures.co2_g_per_km.or(o.co2_g_per_km);
+                    figures.flat_fare_cents = figures.flat_fare_cents.or(o.flat_fare_cents);
+                    figures.average_speed_kmh = figures.average_speed_kmh.or(o.average_speed_kmh);
+                }
+                figures
+            })
+            .collect();
+    }
+
     /// Build the RouteId → `OperatorFareId` lookup. A route whose agency matches a
-    /// modeled operator resolves to its model; others get a stable `unknown[]` slot
-    /// (`0..N_OP`). No-op when fares are disabled.
+    /// modeled operator resolves to its model, else to a `route_overrides` flat fare;
+    /// others get a stable `unknown[]` slot (`0..N_OP`). No-op when fares are disabled.
     pub fn rebuild_operator_fare_lookup(&mut self) {
         use crate::structures::cost::{N_OP, OperatorFareId};
         if !self.fare_model.enabled {
@@ -632,6 +848,8 @@ impl RaptorIndex {
                     other => other,
                 };
                 OperatorFareId::Modeled { model }
+            } else if let Some(cents) = self.route_figures.get(lookup.len()).and_then(|f| f.flat_fare_cents) {
+                OperatorFareId::Modeled { model: crate::structures::cost::OperatorModel::Flat { cents } }
             } else {
                 let name_for_slot = display_name.clone();
                 let slot = *unknown_slot.entry(agency_name).or_insert_with(|| {
@@ -937,6 +1155,84 @@ mod tests {
         assert_eq!(idx.unknown_operator_names[s2], "TEC");
     }
 
+    #[test]
+    fn route_overrides_price_unmodeled_agencies_first_match_per_field() {
+        use crate::structures::cost::{
+            FareModel, KnownEurosEpsilon, OperatorFare, OperatorFareId, OperatorModel,
+        };
+        let mut idx = RaptorIndex::new();
+        idx.transit_agencies = vec![agency("STIB"), agency("De Lijn"), agency("TEC")];
+        let mut tram = route(1);
+        tram.route_type = gtfs_structures::RouteType::Tramway;
+        idx.transit_routes = vec![route(0), route(1), route(2), tram];
+        idx.route_overrides = vec![
+            RouteOverride {
+                agency: Some("de lijn".into()),
+                route_type: Some(gtfs_structures::RouteType::Tramway),
+                co2_g_per_km: Some(30.0),
+                ..RouteOverride::default()
+            },
+            RouteOverride {
+                agency: Some("De Lijn".into()),
+                co2_g_per_km: Some(70.0),
+                flat_fare_cents: Some(300),
+                ..RouteOverride::default()
+            },
+            RouteOverride {
+                agency: Some("STIB".into()),
+                flat_fare_cents: Some(150),
+                average_speed_kmh: Some(18.0),
+                ..RouteOverride::default()
+            },
+        ];
+        idx.fare_model = FareModel {
+            enabled: true,
+            known_euros_epsilon: KnownEurosEpsilon::default(),
+            operators: vec![OperatorFare {
+                name: "stib".into(),
+                model: OperatorModel::TimeWindowFlat {
+                    ticket_cents: 210,
+                    card_cents: None,
+                    validity_secs: 5400,
+                    operator: crate::structures::cost::TimeWindowOperator::Stib,
+                },
+                express_route_names: Vec::new(),
+                express_route_prefixes: Vec::new(),
+                express_single_cents: 0,
+                express_card6_cents: 0,
+                express_card6_reduced_cents: 0,
+                airport_station_names: Vec::new(),
+            }],
+            agglomerations: Vec::new(),
+            ..FareModel::default()
+        };
+        idx.rebuild_route_figures();
+        idx.rebuild_operator_fare_lookup();
+
+        assert_eq!(idx.route_figures[0].average_speed_kmh, Some(18.0));
+        assert_eq!(idx.route_figures[1].co2_g_per_km, Some(70.0));
+        assert_eq!(idx.route_figures[2], RouteFigures::default());
+        assert_eq!(
+            (idx.route_figures[3].co2_g_per_km, idx.route_figures[3].flat_fare_cents),
+            (Some(30.0), Some(300)),
+            "the tram override wins co2; the agency-wide one still supplies the fare"
+        );
+        assert!(
+            matches!(
+                idx.operator_fare_of_route[0],
+                OperatorFareId::Modeled { model: OperatorModel::TimeWindowFlat { .. } }
+            ),
+            "a modeled operator keeps its model over a flat override"
+        );
+        for r in [1, 3] {
+            assert!(matches!(
+                idx.operator_fare_of_route[r],
+                OperatorFareId::Modeled { model: OperatorModel::Flat { cents: 300 } }
+            ));
+        }
+        assert!(matches!(idx.operator_fare_of_route[2], OperatorFareId::Unknown { .. }));
+    }
+
     #[test]
     fn sncb_operator_matches_slashed_agency_name() {
         use crate::structures::cost::{
@@ -1013,6 +1309,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn service_check_uses_calendar_and_falls_back_for_later_services() {
+        use crate::ingestion::gtfs::{ServicePattern, weekday_bit};
+        let weekdays = |start, end| ServicePattern {
+            days_of_week: 0x1F,
+            start_date: start,
+            end_date: end,
+            added_dates: vec![],
+            removed_dates: vec![9500],
+        };
+        let mut idx = RaptorIndex::new();
+        idx.transit_services.push(weekdays(9400, 9600));
+        idx.build_runtime_indices();
+        // Appended after the calendar was built: not expanded, answered by the fallback.
+        idx.transit_services.push(weekdays(9400, 9600));
+        for date in 9390..9610 {
+            let wd = weekday_bit(date);
+            let expect = idx.transit_services[0].is_active(date, wd);
+            assert_eq!(idx.is_service_active(ServiceId(0), date, wd), expect);
+            assert_eq!(idx.is_service_active(ServiceId(1), date, wd), expect);
+        }
+        assert!(idx.transit_service_calendar.heap_bytes() > 0);
+    }
+
+    #[test]
+    fn active_services_mask_matches_the_per_service_check() {
+        use crate::ingestion::gtfs::{ServicePattern, weekday_bit};
+        let service = |days_of_week| ServicePattern {
+            days_of_week,
+            start_date: 9400,
+            end_date: 9600,
+            added_dates: vec![],
+            removed_dates: vec![],
+        };
+        let mut idx = RaptorIndex::new();
+        // Weekdays, weekends; 70 services so the mask spans two words.
+        idx.transit_services = (0..70).map(|i| service(if i % 2 == 0 { 0x1F } else { 0x60 })).collect();
+        idx.build_runtime_indices();
+        for date in [9450, 9451, 9452, 9700] {
+            let wd = weekday_bit(date);
+            let active = idx.active_services(date, wd);
+            for s in 0..70 {
+                assert_eq!(active.contains(ServiceId(s)), idx.is_service_active(ServiceId(s), date, wd));
+            }
+            assert!(!active.contains(ServiceId(70)), "unknown services never run");
+        }
+        assert!(idx.active_services(9700, weekday_bit(9700)).is_empty(), "past the feed's end");
+    }
+
     #[test]
     fn trip_id_round_trips_through_runtime_index() {
         let mut idx = RaptorIndex::new();
@@ -1185,7 +1530,7 @@ mod tests {
         assert!(idx.stop_id_to_index.is_empty());
         assert!(idx.transit_stop_reverse_transfers.is_empty());
         assert!(idx.transit_idx_stop_reverse_transfers.is_empty());
-        assert!(idx.transit_pattern_shapes.is_empty());
+        assert!(idx.transit_pattern_shape_ids.is_empty());
         assert!(idx.transit_pattern_shape_stop_idx.is_empty());
         assert!(idx.railway_nodes.is_empty());
         assert!(idx.railway_adj.is_empty());
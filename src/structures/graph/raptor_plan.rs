@@ -1,14 +1,16 @@
+use gtfs_structures::RouteType;
+
 use super::raptor_route::{Label, LabelCell, LabelRow, ModeContext, apply_delay};
 
 use crate::{
     ingestion::gtfs::TimetableSegment,
     structures::{
-        Mode, NodeID, RealtimeIndex, ReliabilityBuckets, Scenario, ScenarioBag, VehicleState,
+        Mode, ModePreference, NodeID, RealtimeIndex, ReliabilityBuckets, Scenario, ScenarioBag, VehicleState,
         delay::DelayCDF,
         plan::{
-            AccessAlternative, ArrivalScenario, CandidateStatus, Plan, PlanCandidate,
-            PlanCoordinate, PlanLeg, PlanLegStep, PlanPlace, PlanTransitLeg, PlanTransitLegStep,
-            PlanWalkLeg, PlanWalkLegStep, TransferRisk,
+            AccessAlternative, ArrivalScenario, CandidateStatus, Plan, PlanBikeLeg, PlanCandidate,
+            PlanCarLeg, PlanCoordinate, PlanLeg, PlanLegStep, PlanPlace, PlanTransitLeg,
+            PlanTransitLegStep, PlanWalkLeg, PlanWalkLegStep, RealtimeState, TransferRisk,
         },
     },
 };
@@ -78,8 +80,10 @@ fn plan_transfer_margins(legs: &[PlanLeg]) -> Vec<i32> {
                 prev_transit_end = Some(t.end);
                 walk_acc = 0;
             }
-            PlanLeg::Walk(w) => {
-                if prev_transit_end.is_some() {
+            street => {
+                if let Some(w) = street.as_street()
+                    && prev_transit_end.is_some()
+                {
                     walk_acc += w.duration;
                 }
             }
@@ -96,10 +100,11 @@ fn legs_timing_eq(a: &[PlanLeg], b: &[PlanLeg]) -> bool {
         (PlanLeg::Transit(p), PlanLeg::Transit(q)) => {
             p.trip_id == q.trip_id && p.start == q.start && p.end == q.end
         }
-        (PlanLeg::Walk(p), PlanLeg::Walk(q)) => {
-            p.start == q.start && p.end == q.end && p.duration == q.duration
-        }
-        _ => false,
+        (PlanLeg::Transit(_), _) | (_, PlanLeg::Transit(_)) => false,
+        (p, q) => match (p.as_street(), q.as_street()) {
+            (Some(p), Some(q)) => p.start == q.start && p.end == q.end && p.duration == q.duration,
+            _ => false,
+        },
     })
 }
 
@@ -115,7 +120,7 @@ fn plan_has_long_transfer(legs: &[PlanLeg], max_m: f64) -> bool {
     let (first, last) = (ti[0], *ti.last().unwrap());
     legs[first..last]
         .iter()
-        .any(|l| matches!(l, PlanLeg::Walk(w) if (w.length as f64) > max_m))
+        .any(|l| l.as_street().is_some_and(|w| (w.length as f64) > max_m))
 }
 
 impl Graph {
@@ -168,7 +173,7 @@ impl Graph {
     ) -> Plan {
         let end = start_time + secs;
         let (speed, mode) = match profile {
-            StreetProfile::Foot => (self.raptor.walking_speed_mps, Mode::Walk),
+            StreetProfile::Foot => (self.walking_speed_mps(), Mode::Walk),
             StreetProfile::Bike => (self.raptor.cycling_speed_mps, Mode::Bike),
             StreetProfile::Car => (self.raptor.driving_speed_mps, Mode::Car),
         };
@@ -179,15 +184,17 @@ impl Graph {
             stop_position: None,
             arrival: Some(end),
             departure: None,
+            language: None,
         };
 
         Plan {
-            legs: vec![PlanLeg::Walk(PlanWalkLeg {
+            legs: vec![PlanLeg::street(PlanWalkLeg {
                 from: PlanPlace {
                     node_id: origin,
                     stop_position: None,
                     arrival: None,
                     departure: Some(start_time),
+                    language: None,
                 },
                 to: to_place,
                 start: start_time,
@@ -214,6 +221,9 @@ impl Graph {
             }],
             expected_end: end,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         }
     }
 
@@ -649,6 +659,18 @@ impl Graph {
                         });
                     }
                 }
+                OperatorFareId::Modeled { model: OperatorModel::Flat { .. } } => {
+                    finalize_sncb(
+                        &mut items, &mut sncb_item, &price, sncb_spend_at_open,
+                        sncb_board_stop, b.alight_stop,
+                    );
+                    items.push(FareBreakdownItem {
+                        operator: display,
+                        description: "flat fare (approximate)".to_string(),
+                        euros: price.known_cents.saturating_sub(before_known) as f64 / 100.0,
+                        coverage: None,
+                    });
+                }
                 OperatorFareId::Unknown { .. } => {
                     finalize_sncb(
                         &mut items, &mut sncb_item, &price, sncb_spend_at_open,
@@ -897,7 +919,7 @@ impl Graph {
                         }
                         let b = buckets.bucket(sm.reliability) as usize;
                         let key = class * n_buckets + b;
-                        let arr = sm.earliest.saturating_add(w);
+                        let arr = sm.rank.saturating_add(w);
                         let full = set.full_at(i, arena);
                         let intra_member_transfer = full.trace.is_transfer()
                             && mc
@@ -1063,13 +1085,10 @@ impl Graph {
                         let stop_node = self.raptor.transit_stop_to_node[origin_stop];
                         let board = legs
                             .first()
-                            .map(|l| match l {
-                                PlanLeg::Transit(t) => t.start,
-                                PlanLeg::Walk(w) => w.start,
-                            })
+                            .map(|l| l.span().0)
                             .unwrap_or(start_time + first_walk);
                         let speed = match access_profile {
-                            StreetProfile::Foot => self.raptor.walking_speed_mps,
+                            StreetProfile::Foot => self.walking_speed_mps(),
                             StreetProfile::Bike => self.raptor.cycling_speed_mps,
                             StreetProfile::Car => self.raptor.driving_speed_mps,
                         };
@@ -1080,6 +1099,7 @@ impl Graph {
                             stop_position: None,
                             arrival: Some(walk_start + first_walk),
                             departure: None,
+                            language: None,
                         };
                         let access_leg = PlanWalkLeg {
                             from: PlanPlace {
@@ -1087,6 +1107,7 @@ impl Graph {
                                 stop_position: None,
                                 arrival: None,
                                 departure: Some(walk_start),
+                                language: None,
                             },
                             to: to_place,
                             start: walk_start,
@@ -1103,7 +1124,7 @@ impl Graph {
                             alternatives: vec![],
                             leave_by: None,
                         };
-                        legs.insert(0, PlanLeg::Walk(access_leg));
+                        legs.insert(0, PlanLeg::street(access_leg));
                     }
                 }
 
@@ -1118,7 +1139,7 @@ impl Graph {
                     let alight = chosen_bag.earliest();
                     let stop_node = self.raptor.transit_stop_to_node[best_stop];
                     let speed = match egress_profile {
-                        StreetProfile::Foot => self.raptor.walking_speed_mps,
+                        StreetProfile::Foot => self.walking_speed_mps(),
                         StreetProfile::Bike => self.raptor.cycling_speed_mps,
                         StreetProfile::Car => self.raptor.driving_speed_mps,
                     };
@@ -1128,6 +1149,7 @@ impl Graph {
                         stop_position: None,
                         arrival: Some(alight + best_walk),
                         departure: None,
+                        language: None,
                     };
                     let egress_leg = PlanWalkLeg {
                         from: PlanPlace {
@@ -1135,6 +1157,7 @@ impl Graph {
                             stop_position: None,
                             arrival: None,
                             departure: Some(alight),
+                            language: None,
                         },
                         to: to_place,
                         start: alight,
@@ -1151,7 +1174,7 @@ impl Graph {
                         alternatives: vec![],
                         leave_by: None,
                     };
-                    legs.push(PlanLeg::Walk(egress_leg));
+                    legs.push(PlanLeg::street(egress_leg));
                 }
 
                 let (departure, arrival) = Self::plan_timeline(&mut legs);
@@ -1175,6 +1198,9 @@ impl Graph {
                     arrival_distribution,
                     expected_end,
                     price,
+                    debug: None,
+                    warnings: Vec::new(),
+                    itinerary_id: None,
                 };
 
                 if let Some(ref mut sink) = debug_sink {
@@ -1217,7 +1243,11 @@ impl Graph {
         let mut cursor: Option<u32> = None;
         for leg in legs.iter_mut() {
             match leg {
-                PlanLeg::Walk(w) => {
+                PlanLeg::Transit(t) => cursor = Some(t.end),
+                PlanLeg::Wait(w) => cursor = Some(w.end),
+                PlanLeg::Walk(w)
+                | PlanLeg::Bike(PlanBikeLeg { leg: w })
+                | PlanLeg::Car(PlanCarLeg { leg: w }) => {
                     if let Some(prev_end) = cursor {
                         let dur = w.duration;
                         w.start = prev_end;
@@ -1232,27 +1262,18 @@ impl Graph {
                     }
                     cursor = Some(w.end);
                 }
-                PlanLeg::Transit(t) => cursor = Some(t.end),
             }
         }
-        let leg_start = |l: &PlanLeg| match l {
-            PlanLeg::Walk(w) => w.start,
-            PlanLeg::Transit(t) => t.start,
-        };
-        let leg_end = |l: &PlanLeg| match l {
-            PlanLeg::Walk(w) => w.end,
-            PlanLeg::Transit(t) => t.end,
-        };
-        let start = legs.first().map(leg_start).unwrap_or(0);
-        let end = legs.last().map(leg_end).unwrap_or(start);
+        let start = legs.first().map(|l| l.span().0).unwrap_or(0);
+        let end = legs.last().map(|l| l.span().1).unwrap_or(start);
         (start, end)
     }
 
     pub(super) fn merge_consecutive_walks(legs: Vec<PlanLeg>) -> Vec<PlanLeg> {
         let mut out: Vec<PlanLeg> = Vec::with_capacity(legs.len());
         for leg in legs {
-            match (out.last_mut(), &leg) {
-                (Some(PlanLeg::Walk(prev)), PlanLeg::Walk(next))
+            match (out.last_mut().and_then(PlanLeg::as_street_mut), leg.as_street()) {
+                (Some(prev), Some(next))
                     if prev.street_mode == next.street_mode
                         && prev.alternatives.is_empty()
                         && next.alternatives.is_empty() =>
@@ -1323,7 +1344,7 @@ impl Graph {
                 return Some(set.full_at(i, arena));
             }
         }
-        // Fallback: min-earliest among current-stamp members (first-wins on ties).
+        // Fallback: min-rank among current-stamp members (first-wins on ties).
         let mut best: Option<(usize, u32)> = None;
         for i in 0..set.count() {
             let sm = set.summary_at(i);
@@ -1331,8 +1352,8 @@ impl Graph {
                 continue;
             }
             match best {
-                Some((_, be)) if be <= sm.earliest => {}
-                _ => best = Some((i, sm.earliest)),
+                Some((_, be)) if be <= sm.rank => {}
+                _ => best = Some((i, sm.rank)),
             }
         }
         best.map(|(i, _)| set.full_at(i, arena))
@@ -1375,21 +1396,23 @@ impl Graph {
                 let duration = end.saturating_sub(start);
                 let from_node = self.raptor.transit_stop_to_node[from];
                 let to_node = self.raptor.transit_stop_to_node[to];
-                let length = (duration as f64 * self.raptor.walking_speed_mps) as usize;
+                let length = (duration as f64 * self.walking_speed_mps()) as usize;
 
                 let to_place = PlanPlace {
                     stop_position: None,
                     arrival: Some(end),
                     departure: None,
                     node_id: to_node,
+                    language: None,
                 };
 
-                legs.push(PlanLeg::Walk(PlanWalkLeg {
+                legs.push(PlanLeg::street(PlanWalkLeg {
                     from: PlanPlace {
                         stop_position: None,
                         arrival: None,
                         departure: Some(start),
                         node_id: from_node,
+                        language: None,
                     },
                     to: to_place,
                     start,
@@ -1490,12 +1513,11 @@ impl Graph {
                 .unwrap_or(TimetableSegment { start: 0, len: 0 });
 
                 let departure_index = if s == bp + 1 {
-                    self.raptor.transit_departures
-                        [timetable_segment.start..timetable_segment.start + timetable_segment.len]
+                    let start = timetable_segment.start as usize;
+                    self.raptor.transit_departures.trip_ids[timetable_segment.range()]
                         .iter()
-                        .position(|ts| ts.trip_id == trip_ids[t])
-                        .map(|i| timetable_segment.start + i)
-                        .unwrap_or(timetable_segment.start)
+                        .position(|&trip| trip == trip_ids[t])
+                        .map_or(start, |i| start + i)
                 } else {
                     0
                 };
@@ -1512,6 +1534,7 @@ impl Graph {
                         } else {
                             None
                         },
+                        language: None,
                     },
                     scheduled_arrival: Some(arr),
                     scheduled_departure: if s < ap {
@@ -1556,18 +1579,22 @@ impl Graph {
                     arrival: Some(times[bp * n_trips + t].arrival),
                     departure: Some(board_dep),
                     node_id: pat_stops[bp],
+                    language: None,
                 },
                 to: PlanPlace {
                     stop_position: Some(ap as u32),
                     arrival: Some(alight_arr),
                     departure: Some(times[ap * n_trips + t].departure),
                     node_id: pat_stops[ap],
+                    language: None,
                 },
                 start: board_dep,
                 end: alight_arr,
                 scheduled_start: board_dep,
                 scheduled_end: alight_arr,
                 realtime: false,
+                realtime_state: RealtimeState::Scheduled,
+                occupancy: None,
                 trip_id: trip_ids[t],
                 length: total_length,
                 duration: alight_arr - board_dep,
@@ -1584,8 +1611,11 @@ impl Graph {
                 // Populated later by `link_following_connections`.
                 following_route_type: None,
                 following_margin_secs: None,
-                bikes_allowed: self.get_trip(trip_ids[t]).and_then(|t| t.bikes_allowed),
+                bikes_allowed: self
+                    .trip_allows_bikes(trip_ids[t], self.route_type_of_trip(trip_ids[t])),
                 time_shift: 0,
+                waiting_time: 0,
+                display: self.leg_display(trip_ids[t]),
             }));
 
             origin_stop = bs;
@@ -1618,10 +1648,7 @@ impl Graph {
             let (j, next_start, next_rt) = w[1];
             let walk: u32 = legs[i + 1..j]
                 .iter()
-                .map(|l| match l {
-                    PlanLeg::Walk(wk) => wk.duration,
-                    _ => 0,
-                })
+                .map(|l| l.as_street().map_or(0, |wk| wk.duration))
                 .sum();
             if let PlanLeg::Transit(t) = &mut legs[i] {
                 t.following_route_type = next_rt;
@@ -1635,7 +1662,7 @@ impl Graph {
     /// live delay), re-chain the timeline, and recompute transfer reliability on the new
     /// margins. No-op with an empty index. Runs before access/egress walks are attached,
     /// so `legs` is the transit/transfer chain only.
-    pub(super) fn apply_realtime(&self, legs: &mut [PlanLeg], rt: &RealtimeIndex, onboard: bool) {
+    pub(crate) fn apply_realtime(&self, legs: &mut [PlanLeg], rt: &RealtimeIndex, onboard: bool) {
         if rt.is_empty() {
             return;
         }
@@ -1668,6 +1695,7 @@ impl Graph {
                         t.scheduled_start = t.start;
                         t.scheduled_end = t.end;
                         t.realtime = false;
+                        t.realtime_state = RealtimeState::Canceled;
                         cursor = Some(t.end);
                         continue;
                     }
@@ -1684,6 +1712,24 @@ impl Graph {
                     t.start = apply_delay(t.start, d_board);
                     t.end = apply_delay(t.end, d_alight);
                     t.realtime = has_rt;
+                    // Onboard legs may cross a skipped stop; searches never board or
+                    // alight at one, so elsewhere only intermediate skips show up.
+                    let skips_stop = [t.from.node_id, t.to.node_id]
+                        .into_iter()
+                        .chain(t.steps.iter().filter_map(|step| match step {
+                            PlanLegStep::Transit(s) => Some(s.place.node_id),
+                            PlanLegStep::Walk(_) => None,
+                        }))
+                        .filter_map(compact)
+                        .any(|stop| rt.is_skipped(t.trip_id, stop));
+                    t.realtime_state = if skips_stop {
+                        RealtimeState::Modified
+                    } else if has_rt {
+                        RealtimeState::Updated
+                    } else {
+                        RealtimeState::Scheduled
+                    };
+                    t.occupancy = board.and_then(|s| rt.occupancy(t.trip_id, s));
                     t.duration = t.end.saturating_sub(t.start);
                     t.from.departure = Some(t.start);
                     t.to.arrival = Some(t.end);
@@ -1726,7 +1772,10 @@ impl Graph {
                     }
                     cursor = Some(t.end);
                 }
-                PlanLeg::Walk(w) => {
+                PlanLeg::Wait(w) => cursor = Some(w.end),
+                PlanLeg::Walk(w)
+                | PlanLeg::Bike(PlanBikeLeg { leg: w })
+                | PlanLeg::Car(PlanCarLeg { leg: w }) => {
                     if let Some(prev) = cursor {
                         let dur = w.duration;
                         w.start = prev;
@@ -1884,9 +1933,9 @@ impl Graph {
             // Inter-leg transfer: reconstructed walk (metres, seconds).
             let (plan_walk_len, plan_walk_dur) = legs[prev_ti + 1..ti].iter().fold(
                 (0usize, 0u32),
-                |(len, dur), l| match l {
-                    PlanLeg::Walk(w) => (len + w.length, dur + w.duration),
-                    _ => (len, dur),
+                |(len, dur), l| match l.as_street() {
+                    Some(w) => (len + w.length, dur + w.duration),
+                    None => (len, dur),
                 },
             );
             // A transfer > MAX_TRANSFER_DISTANCE_M is exactly what lambda's capped
@@ -1926,6 +1975,10 @@ impl Graph {
             .filter(|&&(source, _)| source == from_stop)
             .map(|&(_, walk)| walk)
             .min()
+            .map(|walk| {
+                self.transfer_walk_scale()
+                    .map_or(walk, |k| (walk as f64 * k).round() as u32)
+            })
     }
 
     pub fn set_tighten_mode(mode: u8) {
@@ -2138,10 +2191,7 @@ impl Graph {
                 let next_ti = transit_indices[i + 1];
                 legs[ti + 1..next_ti]
                     .iter()
-                    .map(|l| match l {
-                        PlanLeg::Walk(w) => w.duration,
-                        _ => 0,
-                    })
+                    .map(|l| l.as_street().map_or(0, |w| w.duration))
                     .sum()
             } else {
                 0
@@ -2189,8 +2239,9 @@ impl Graph {
                     };
                     if let Ok(mut alts) = cloned.find_alternatives(
                         self,
-                        std::iter::once((dep_idx, &self.raptor.transit_departures[dep_idx])),
+                        std::iter::once((dep_idx, self.raptor.transit_departures.at(dep_idx))),
                         1,
+                        None,
                     ) && let Some(new_leg) = alts.pop()
                     {
                         legs[ti] = PlanLeg::Transit(new_leg);
@@ -2208,7 +2259,7 @@ impl Graph {
 
                 let mut cursor = new_leg_end;
                 for l in legs[ti + 1..next_ti].iter_mut() {
-                    if let PlanLeg::Walk(w) = l {
+                    if let Some(w) = l.as_street_mut() {
                         let new_start = cursor;
                         let new_end = new_start + w.duration;
                         w.start = new_start;
@@ -2286,13 +2337,7 @@ impl Graph {
     fn plan_street_secs(plan: &Plan) -> u32 {
         plan.legs
             .iter()
-            .filter_map(|l| {
-                if let PlanLeg::Walk(w) = l {
-                    Some(w.duration)
-                } else {
-                    None
-                }
-            })
+            .filter_map(|l| l.as_street().map(|w| w.duration))
             .sum()
     }
 
@@ -2388,9 +2433,13 @@ impl Graph {
 
     /// Final pipeline: collapse access twins, drop transit plans no faster than an
     /// equal-or-lighter-burden direct ride, then burden-aware Pareto.
-    pub(super) fn finalize_plans(plans: Vec<Plan>, buckets: &ReliabilityBuckets) -> Vec<Plan> {
+    pub(super) fn finalize_plans(
+        plans: Vec<Plan>,
+        buckets: &ReliabilityBuckets,
+        modes: &[(RouteType, ModePreference)],
+    ) -> Vec<Plan> {
         let grouped = Self::group_access_alternatives(plans);
-        Self::pareto_filter(Self::prune_slower_than_direct(grouped), buckets)
+        Self::pareto_filter(Self::prune_slower_than_direct(grouped), buckets, modes)
     }
 
     /// Drops any transit plan strictly longer than a direct street plan of
@@ -2417,51 +2466,43 @@ impl Graph {
             .collect()
     }
 
-    pub(super) fn pareto_filter(plans: Vec<Plan>, buckets: &ReliabilityBuckets) -> Vec<Plan> {
-        fn transfer_count(plan: &Plan) -> usize {
-            plan.legs
-                .iter()
-                .filter(|l| matches!(l, PlanLeg::Transit(_)))
-                .count()
-                .saturating_sub(1)
-        }
-
+    pub(super) fn pareto_filter(
+        plans: Vec<Plan>,
+        buckets: &ReliabilityBuckets,
+        modes: &[(RouteType, ModePreference)],
+    ) -> Vec<Plan> {
         fn walk_secs(plan: &Plan) -> u32 {
             plan.legs
                 .iter()
-                .filter_map(|l| {
-                    if let PlanLeg::Walk(w) = l {
-                        Some(w.duration)
-                    } else {
-                        None
-                    }
-                })
+                .filter_map(|l| l.as_street().map(|w| w.duration))
                 .sum()
         }
 
         let rel_bucket = |p: &Plan| buckets.bucket(Self::plan_reliability(p));
+        let end = |p: &Plan| p.ranked_end(modes);
 
         // 4-D Pareto (transfers ↓, end ↓, start ↑, reliability_bucket ↑), guarded by
-        // burden: a plan may only dominate equal-or-heavier-burden plans. Burden and
+        // burden: a plan may only dominate equal-or-heavier-burden plans. `end` is the
+        // mode-preference-ranked arrival, as in the search. Burden and
         // walk seconds are NOT axes; they only break exact 4-axis ties.
         let dominates = |a: &Plan, b: &Plan| {
-            let (tc_a, tc_b) = (transfer_count(a), transfer_count(b));
+            let (tc_a, tc_b) = (Plan::transfer_count(a), Plan::transfer_count(b));
             let (rb_a, rb_b) = (rel_bucket(a), rel_bucket(b));
             a.mode.burden() <= b.mode.burden()
                 && tc_a <= tc_b
-                && a.end <= b.end
+                && end(a) <= end(b)
                 && a.start >= b.start
                 && rb_a >= rb_b
                 && (tc_a < tc_b
-                    || a.end < b.end
+                    || end(a) < end(b)
                     || a.start > b.start
                     || rb_a > rb_b
                     || a.mode.burden() < b.mode.burden()
                     || walk_secs(a) < walk_secs(b))
         };
         let equal_4 = |a: &Plan, b: &Plan| {
-            transfer_count(a) == transfer_count(b)
-                && a.end == b.end
+            Plan::transfer_count(a) == Plan::transfer_count(b)
+                && end(a) == end(b)
                 && a.start == b.start
                 && rel_bucket(a) == rel_bucket(b)
         };
@@ -2502,49 +2543,37 @@ impl Graph {
         plan_to_sink_idx: &[usize],
         sink: &mut [PlanCandidate],
         buckets: &ReliabilityBuckets,
+        modes: &[(RouteType, ModePreference)],
     ) -> Vec<Plan> {
-        fn transfer_count(plan: &Plan) -> usize {
-            plan.legs
-                .iter()
-                .filter(|l| matches!(l, PlanLeg::Transit(_)))
-                .count()
-                .saturating_sub(1)
-        }
-
         fn walk_secs(plan: &Plan) -> u32 {
             plan.legs
                 .iter()
-                .filter_map(|l| {
-                    if let PlanLeg::Walk(w) = l {
-                        Some(w.duration)
-                    } else {
-                        None
-                    }
-                })
+                .filter_map(|l| l.as_street().map(|w| w.duration))
                 .sum()
         }
 
         let rel_bucket = |p: &Plan| buckets.bucket(Self::plan_reliability(p));
+        let end = |p: &Plan| p.ranked_end(modes);
 
         // Burden-guarded 4-D Pareto with burden/walk tie-breaks (see `pareto_filter`).
         let dominates = |a: &Plan, b: &Plan| {
-            let (tc_a, tc_b) = (transfer_count(a), transfer_count(b));
+            let (tc_a, tc_b) = (Plan::transfer_count(a), Plan::transfer_count(b));
             let (rb_a, rb_b) = (rel_bucket(a), rel_bucket(b));
             a.mode.burden() <= b.mode.burden()
                 && tc_a <= tc_b
-                && a.end <= b.end
+                && end(a) <= end(b)
                 && a.start >= b.start
                 && rb_a >= rb_b
                 && (tc_a < tc_b
-                    || a.end < b.end
+                    || end(a) < end(b)
                     || a.start > b.start
                     || rb_a > rb_b
                     || a.mode.burden() < b.mode.burden()
                     || walk_secs(a) < walk_secs(b))
         };
         let equal_4 = |a: &Plan, b: &Plan| {
-            transfer_count(a) == transfer_count(b)
-                && a.end == b.end
+            Plan::transfer_count(a) == Plan::transfer_count(b)
+                && end(a) == end(b)
                 && a.start == b.start
                 && rel_bucket(a) == rel_bucket(b)
         };
@@ -2557,19 +2586,19 @@ impl Graph {
         let mut result_sink_idx: Vec<usize> = Vec::new();
 
         'outer: for (plan, &sink_idx) in plans.into_iter().zip(plan_to_sink_idx.iter()) {
-            let tc_p = transfer_count(&plan);
+            let tc_p = Plan::transfer_count(&plan);
             let rb_p = rel_bucket(&plan);
 
             for (i, existing) in result.iter().enumerate() {
                 if dominates(existing, &plan)
                     || (equal_4(existing, &plan) && tie_break_wins(existing, &plan))
                 {
-                    let tc_e = transfer_count(existing);
+                    let tc_e = Plan::transfer_count(existing);
                     let rb_e = rel_bucket(existing);
                     sink[sink_idx].status = CandidateStatus::ParetoDominated {
                         dominator_index: result_sink_idx[i],
                         departure_worse: existing.start > plan.start,
-                        arrival_worse: existing.end < plan.end,
+                        arrival_worse: end(existing) < end(&plan),
                         transfers_worse: tc_e < tc_p,
                         reliability_worse: rb_e > rb_p,
                     };
@@ -2580,13 +2609,13 @@ impl Graph {
             let mut dominated = vec![false; result.len()];
             for (i, existing) in result.iter().enumerate() {
                 if dominates(&plan, existing) {
-                    let tc_e = transfer_count(existing);
+                    let tc_e = Plan::transfer_count(existing);
                     let rb_e = rel_bucket(existing);
                     dominated[i] = true;
                     sink[result_sink_idx[i]].status = CandidateStatus::ParetoDominated {
                         dominator_index: sink_idx,
                         departure_worse: plan.start > existing.start,
-                        arrival_worse: plan.end < existing.end,
+                        arrival_worse: end(&plan) < end(existing),
                         transfers_worse: tc_p < tc_e,
                         reliability_worse: rb_p > rb_e,
                     };
@@ -2766,6 +2795,7 @@ mod tests {
             arrival: arr,
             departure: dep,
             node_id: NodeID(node),
+            language: None,
         };
         let step = |node: usize, arr: u32, dep: Option<u32>| {
             PlanLegStep::Transit(PlanTransitLegStep {
@@ -2789,6 +2819,8 @@ mod tests {
             scheduled_start: 1000,
             scheduled_end: 1300,
             realtime: false,
+            realtime_state: RealtimeState::Scheduled,
+            occupancy: None,
             from: place(0, Some(1000), Some(1000)),
             to: place(2, Some(1300), Some(1300)),
             steps: vec![step(1, 1100, Some(1130)), step(2, 1300, None)],
@@ -2802,6 +2834,8 @@ mod tests {
             following_margin_secs: None,
             bikes_allowed: None,
             time_shift: 0,
+            waiting_time: 0,
+            display: Default::default(),
         };
         let mut legs = vec![PlanLeg::Transit(leg)];
 
@@ -2960,6 +2994,7 @@ mod tests {
             stop_position: None,
             arrival: None,
             departure: None,
+            language: None,
         }
     }
 
@@ -2990,6 +3025,8 @@ mod tests {
             scheduled_start: start,
             scheduled_end: end,
             realtime: false,
+            realtime_state: RealtimeState::Scheduled,
+            occupancy: None,
             from: place(from),
             to: place(to),
             steps: vec![],
@@ -3003,6 +3040,8 @@ mod tests {
             following_margin_secs: None,
             bikes_allowed: None,
             time_shift: 0,
+            waiting_time: 0,
+            display: Default::default(),
         })
     }
 
@@ -3019,6 +3058,9 @@ mod tests {
             }],
             expected_end: end,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         }
     }
 
@@ -3066,7 +3108,7 @@ mod tests {
         let core = || vec![transit_leg(7, 10, 11, 100, 200)];
         let walk = plan(Mode::WalkTransit, 90, 210, core());
         let bike = plan(Mode::BikeTransit, 90, 210, core());
-        let out = Graph::pareto_filter(vec![bike, walk], &buckets());
+        let out = Graph::pareto_filter(vec![bike, walk], &buckets(), &[]);
         assert_eq!(out.len(), 1);
         assert_eq!(out[0].mode, Mode::WalkTransit);
     }
@@ -3085,7 +3127,7 @@ mod tests {
             210,
             vec![transit_leg(8, 10, 11, 100, 200)],
         );
-        let out = Graph::pareto_filter(vec![walk, bike], &buckets());
+        let out = Graph::pareto_filter(vec![walk, bike], &buckets(), &[]);
         assert_eq!(out.len(), 2);
     }
 
@@ -3103,7 +3145,7 @@ mod tests {
             210,
             vec![transit_leg(8, 10, 11, 100, 200)],
         );
-        let out = Graph::pareto_filter(vec![walk, bike], &buckets());
+        let out = Graph::pareto_filter(vec![walk, bike], &buckets(), &[]);
         assert_eq!(
             out.len(),
             2,
@@ -3189,7 +3231,7 @@ mod tests {
             1740,
             vec![transit_leg(7, 10, 11, 400, 1700)],
         );
-        let out = Graph::finalize_plans(vec![bike_direct, bike_transit], &buckets());
+        let out = Graph::finalize_plans(vec![bike_direct, bike_transit], &buckets(), &[]);
         assert!(
             out.iter().all(|p| p.mode != Mode::BikeOnTransit),
             "a bike+transit slower than cycling direct must be dropped: {:?}",
@@ -3208,7 +3250,7 @@ mod tests {
             1740,
             vec![transit_leg(7, 10, 11, 400, 1700)],
         );
-        let out = Graph::finalize_plans(vec![bike_direct, walk_transit], &buckets());
+        let out = Graph::finalize_plans(vec![bike_direct, walk_transit], &buckets(), &[]);
         assert!(
             out.iter().any(|p| p.mode == Mode::WalkTransit),
             "lighter-burden walk+transit must survive a heavier bike-direct"
@@ -3224,7 +3266,7 @@ mod tests {
             1320,
             vec![transit_leg(7, 10, 11, 100, 1300)],
         );
-        let out = Graph::finalize_plans(vec![bike_direct, bike_transit], &buckets());
+        let out = Graph::finalize_plans(vec![bike_direct, bike_transit], &buckets(), &[]);
         assert!(
             out.iter().any(|p| p.mode == Mode::BikeOnTransit),
             "a bike+transit faster than cycling direct must survive"
@@ -3242,6 +3284,7 @@ mod tests {
     /// Real-graph smoke: verify access leg carries multiobj `alternatives` + `leave_by`
     /// and the egress leg carries `alternatives`.
     ///   cargo test --release --lib access_egress_smoke -- --ignored --nocapture
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn access_egress_smoke() {
@@ -3263,7 +3306,7 @@ mod tests {
             t0.elapsed(),
             g.nodes.len()
         );
-        load_gtfs_stib(gtfs, &mut g).expect("GTFS load failed");
+        load_gtfs_stib(gtfs, &mut g, Default::default(), &Default::default()).expect("GTFS load failed");
         eprintln!("SMOKE gtfs_load={:.1?}", t0.elapsed());
         g.build_raptor_index();
         eprintln!("SMOKE raptor_index={:.1?}", t0.elapsed());
@@ -3289,6 +3332,14 @@ mod tests {
             to_station_id: None,
             profile_latency: None,
             fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
         };
 
         eprintln!("SMOKE stop_count={}", g.raptor.transit_stop_to_node.len());
@@ -3346,14 +3397,18 @@ mod tests {
 
         for (i, leg) in transit_plan.legs.iter().enumerate() {
             match leg {
-                PlanLeg::Walk(w) => eprintln!(
-                    "  leg[{i}] Walk alts={} leave_by={:?} from={:?} to={:?}",
-                    w.alternatives.len(),
-                    w.leave_by,
-                    w.from.node_id,
-                    w.to.node_id
-                ),
                 PlanLeg::Transit(_) => eprintln!("  leg[{i}] Transit"),
+                street => {
+                    if let Some(w) = street.as_street() {
+                        eprintln!(
+                            "  leg[{i}] Walk alts={} leave_by={:?} from={:?} to={:?}",
+                            w.alternatives.len(),
+                            w.leave_by,
+                            w.from.node_id,
+                            w.to.node_id
+                        )
+                    }
+                }
             }
         }
 
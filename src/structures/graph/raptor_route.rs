@@ -1,21 +1,26 @@
+use std::borrow::Cow;
 use std::collections::BTreeSet;
 
 use gtfs_structures::RouteType;
 
 use crate::{
-    ingestion::gtfs::{StopTime, TripId},
+    ingestion::gtfs::{ActiveServices, StopTime, TripId},
     structures::{
         ALL_STATES, ActiveModes, NodeID, RealtimeIndex, ReliabilityBuckets, ScenarioBag,
         VehicleState,
         plan::{
-            AccessInfo, CandidateStatus, ExplainResult, Plan, PlanCandidate, PlanCoordinate,
-            PlanLeg, PlanLegStep, StopPathLeg, StopReach,
+            AccessInfo, CandidateStatus, ExplainResult, Plan, PlanBikeLeg, PlanCandidate,
+            PlanCarLeg, PlanCoordinate, PlanLeg, PlanLegStep, StopPathLeg, StopReach,
         },
         raptor::Trace,
     },
 };
 
-use super::{BikeCost, Graph, MAX_ROUNDS, latency_profile, raptor_access::StreetProfile};
+use super::{
+    BikeCost, Graph, MAX_ROUNDS, latency_profile, query_budget,
+    query_trace::{self, PruneReason},
+    raptor_access::StreetProfile,
+};
 
 pub struct QueryEndpoints {
     pub origin: crate::structures::LatLng,
@@ -85,6 +90,12 @@ pub(super) struct ModeContext<'a> {
     pub fare_profile: crate::structures::cost::FareProfile,
     /// Opt-in absolute-time arrival horizon (travel-map only); `None` leaves the pass unbounded.
     pub horizon: Option<u32>,
+    /// The query profile's wheelchair flag, read once on the query thread: parallel route
+    /// scans run on worker threads that do not see its thread-locals.
+    pub wheelchair: bool,
+    /// Per-mode preferences ranked into every label's `mode_cost`; empty = rank by
+    /// arrival alone (travel maps, which measure time).
+    pub mode_preferences: Vec<(RouteType, crate::structures::ModePreference)>,
 }
 
 impl<'a> ModeContext<'a> {
@@ -127,6 +138,8 @@ impl<'a> ModeContext<'a> {
             trip_active_memo: None,
             fare_profile: crate::structures::cost::FareProfile::default(),
             horizon: None,
+            wheelchair: super::query_profile::wheelchair(),
+            mode_preferences: Vec::new(),
         }
     }
 
@@ -204,6 +217,21 @@ pub(super) struct Riding {
     from_round: u8,
     from_arena: u32,
     state: u8,
+    /// The boarding label's `mode_cost` plus this mode's board penalty, less the ride
+    /// multiplier's share of the boarding time: at an arrival `arr` the new label costs
+    /// `cost_base + (multiplier - 1) × arr`.
+    cost_base: f64,
+}
+
+/// `arr` shifted by a label's `mode_cost`: the arrival every Pareto and cutoff
+/// comparison of the scan ranks on. Equal to `arr` without mode preferences; an
+/// unreached `u32::MAX` stays put.
+#[inline]
+pub(super) fn ranked(arr: u32, mode_cost: i32) -> u32 {
+    if mode_cost == 0 || arr == u32::MAX {
+        return arr;
+    }
+    (arr as i64 + mode_cost as i64).clamp(0, u32::MAX as i64 - 1) as u32
 }
 
 #[derive(Clone, Copy)]
@@ -224,6 +252,9 @@ pub(super) struct Label {
     pub parent: u32,
     pub arena_id: u32,
     pub state: u8,
+    /// Seconds the per-mode preferences (`default_routing.modes`) add to this journey's
+    /// arrival so far: board penalties plus `(multiplier - 1)` × each ride. See [`ranked`].
+    pub mode_cost: i32,
 }
 
 impl Label {
@@ -238,8 +269,15 @@ impl Label {
         parent: u32::MAX,
         arena_id: u32::MAX,
         state: 0,
+        mode_cost: 0,
     };
 
+    /// The arrival this label is ranked on ([`ranked`] earliest scheduled arrival).
+    #[inline]
+    pub fn rank(&self) -> u32 {
+        ranked(self.bag.earliest(), self.mode_cost)
+    }
+
     #[inline]
     pub fn arena_push(arena: &mut Vec<Label>, mut lab: Label) -> Label {
         lab.arena_id = arena.len() as u32;
@@ -280,6 +318,11 @@ impl LabelSet {
             .unwrap_or(u32::MAX)
     }
 
+    /// Lowest [`Label::rank`], `u32::MAX` if empty.
+    pub fn min_rank(&self) -> u32 {
+        self.iter().map(Label::rank).min().unwrap_or(u32::MAX)
+    }
+
     pub fn min_arrival_label(&self) -> Option<&Label> {
         self.iter().fold(None, |acc, l| match acc {
             None => Some(l),
@@ -290,17 +333,18 @@ impl LabelSet {
 
     pub fn dominates(&self, cand: Label, buckets: &ReliabilityBuckets) -> bool {
         let cb = buckets.bucket(cand.reliability);
-        let ce = cand.bag.earliest();
+        let ce = cand.rank();
         self.iter()
-            .any(|l| buckets.bucket(l.reliability) >= cb && l.bag.earliest() <= ce)
+            .any(|l| buckets.bucket(l.reliability) >= cb && l.rank() <= ce)
     }
 
     /// Pareto-inserts `cand`. Same-stamp dominance is bucket-level; cross-stamp dominance
     /// MUST require `>=` PRECISE reliability (not bucket), or Pareto-optimal plans are
     /// dropped (same-bucket prefixes can quantize to different final buckets downstream).
+    /// Arrivals compare by [`Label::rank`].
     pub fn insert(&mut self, cand: Label, buckets: &ReliabilityBuckets) -> bool {
         let cb = buckets.bucket(cand.reliability);
-        let ce = cand.bag.earliest();
+        let ce = cand.rank();
 
         for l in self.iter() {
             let dominates = if l.created_by == cand.created_by {
@@ -308,7 +352,7 @@ impl LabelSet {
             } else {
                 l.reliability >= cand.reliability && l.round <= cand.round
             };
-            if dominates && l.bag.earliest() <= ce {
+            if dominates && l.rank() <= ce {
                 return false;
             }
         }
@@ -317,7 +361,7 @@ impl LabelSet {
         for i in 0..self.len as usize {
             let e = self.labels[i];
             let eb = buckets.bucket(e.reliability);
-            let ee = e.bag.earliest();
+            let ee = e.rank();
             let dominated = eb <= cb && ee >= ce && (eb < cb || ee > ce);
             if !dominated {
                 self.labels[w] = e;
@@ -341,8 +385,8 @@ impl LabelSet {
             ghost = Some(match ghost {
                 None => i,
                 Some(g) => {
-                    let (gr, ge) = (self.labels[g].reliability, self.labels[g].bag.earliest());
-                    let (ir, ie) = (self.labels[i].reliability, self.labels[i].bag.earliest());
+                    let (gr, ge) = (self.labels[g].reliability, self.labels[g].rank());
+                    let (ir, ie) = (self.labels[i].reliability, self.labels[i].rank());
                     if ir < gr || (ir == gr && ie > ge) {
                         i
                     } else {
@@ -361,13 +405,13 @@ impl LabelSet {
             let wb = buckets.bucket(self.labels[worst].reliability);
             let ib = buckets.bucket(self.labels[i].reliability);
             if ib < wb
-                || (ib == wb && self.labels[i].bag.earliest() > self.labels[worst].bag.earliest())
+                || (ib == wb && self.labels[i].rank() > self.labels[worst].rank())
             {
                 worst = i;
             }
         }
         let wb = buckets.bucket(self.labels[worst].reliability);
-        if cb > wb || (cb == wb && ce < self.labels[worst].bag.earliest()) {
+        if cb > wb || (cb == wb && ce < self.labels[worst].rank()) {
             self.labels[worst] = cand;
             return true;
         }
@@ -393,10 +437,10 @@ pub(super) fn trip_memo_enabled() -> bool {
     })
 }
 
-/// Tri-state per trip: `0` = unknown, `1` = inactive, `2` = active. Lazy fill is a benign
-/// race: every writer stores the SAME pure value, so `Relaxed` suffices.
+/// The query day's [`ActiveServices`], resolved on the first trip test of the scan so
+/// every later one is a bit test. `OnceLock`: parallel route scans share it.
 pub(super) struct TripActiveMemo {
-    states: Vec<std::sync::atomic::AtomicU8>,
+    services: std::sync::OnceLock<ActiveServices>,
     /// Debug guard: the `(date, weekday)` first queried, packed `(date << 8) | weekday`
     /// (`u64::MAX` = unset). Every later lookup MUST use the same key (memo is per-date).
     #[cfg(debug_assertions)]
@@ -404,21 +448,26 @@ pub(super) struct TripActiveMemo {
 }
 
 impl TripActiveMemo {
-    pub(super) fn new(n_trips: usize) -> Self {
-        let mut states = Vec::with_capacity(n_trips);
-        states.resize_with(n_trips, || std::sync::atomic::AtomicU8::new(0));
+    pub(super) fn new() -> Self {
         TripActiveMemo {
-            states,
+            services: std::sync::OnceLock::new(),
             #[cfg(debug_assertions)]
             dw: std::sync::atomic::AtomicU64::new(u64::MAX),
         }
     }
+
+    /// The mask, built by `resolve` on first use only.
+    fn services(&self, resolve: impl FnOnce() -> ActiveServices) -> &ActiveServices {
+        self.services.get_or_init(resolve)
+    }
 }
 
 #[derive(Clone, Copy)]
 pub(super) struct Summary {
     pub arena_id: u32,
     pub earliest: u32,
+    /// [`Label::rank`].
+    pub rank: u32,
     pub reliability: f32,
     pub created_by: u32,
     pub round: u8,
@@ -430,6 +479,7 @@ impl Summary {
         Summary {
             arena_id: l.arena_id,
             earliest: l.bag.earliest(),
+            rank: l.rank(),
             reliability: l.reliability,
             created_by: l.created_by,
             round: l.round,
@@ -451,6 +501,7 @@ fn label_matches_arena(stored: &Label, arena: &[Label]) -> bool {
         && a.round == stored.round
         && a.parent == stored.parent
         && a.state == stored.state
+        && a.mode_cost == stored.mode_cost
         && a.reliability == stored.reliability
         && a.route_type == stored.route_type
         && a.bag.earliest() == stored.bag.earliest()
@@ -465,7 +516,8 @@ fn label_matches_arena(stored: &Label, arena: &[Label]) -> bool {
 /// A grid cell in either representation: full `LabelSet` or 20-byte-summary `SlimSet`.
 pub(super) trait LabelCell: Copy {
     fn is_reached(&self) -> bool;
-    fn earliest_arrival(&self) -> u32;
+    /// Lowest member [`Label::rank`] (`Summary::rank`), `u32::MAX` if empty.
+    fn min_rank(&self) -> u32;
     fn count(&self) -> usize;
     fn summary_at(&self, i: usize) -> Summary;
     fn full_at(&self, i: usize, arena: &[Label]) -> Label;
@@ -478,8 +530,8 @@ impl LabelCell for LabelSet {
         self.len > 0
     }
     #[inline]
-    fn earliest_arrival(&self) -> u32 {
-        LabelSet::earliest(self)
+    fn min_rank(&self) -> u32 {
+        LabelSet::min_rank(self)
     }
     #[inline]
     fn count(&self) -> usize {
@@ -521,6 +573,7 @@ impl SlimSet {
         summaries: [Summary {
             arena_id: u32::MAX,
             earliest: u32::MAX,
+            rank: u32::MAX,
             reliability: 0.0,
             created_by: 0,
             round: 0,
@@ -540,7 +593,7 @@ impl SlimSet {
 
     fn insert_summary(&mut self, cand: Summary, buckets: &ReliabilityBuckets) -> bool {
         let cb = buckets.bucket(cand.reliability);
-        let ce = cand.earliest;
+        let ce = cand.rank;
 
         for s in self.iter() {
             let dominates = if s.created_by == cand.created_by {
@@ -548,7 +601,7 @@ impl SlimSet {
             } else {
                 s.reliability >= cand.reliability && s.round <= cand.round
             };
-            if dominates && s.earliest <= ce {
+            if dominates && s.rank <= ce {
                 return false;
             }
         }
@@ -557,7 +610,7 @@ impl SlimSet {
         for i in 0..self.len as usize {
             let e = self.summaries[i];
             let eb = buckets.bucket(e.reliability);
-            let ee = e.earliest;
+            let ee = e.rank;
             let dominated = eb <= cb && ee >= ce && (eb < cb || ee > ce);
             if !dominated {
                 self.summaries[w] = e;
@@ -580,8 +633,8 @@ impl SlimSet {
             ghost = Some(match ghost {
                 None => i,
                 Some(g) => {
-                    let (gr, ge) = (self.summaries[g].reliability, self.summaries[g].earliest);
-                    let (ir, ie) = (self.summaries[i].reliability, self.summaries[i].earliest);
+                    let (gr, ge) = (self.summaries[g].reliability, self.summaries[g].rank);
+                    let (ir, ie) = (self.summaries[i].reliability, self.summaries[i].rank);
                     if ir < gr || (ir == gr && ie > ge) {
                         i
                     } else {
@@ -600,13 +653,13 @@ impl SlimSet {
             let wb = buckets.bucket(self.summaries[worst].reliability);
             let ib = buckets.bucket(self.summaries[i].reliability);
             if ib < wb
-                || (ib == wb && self.summaries[i].earliest > self.summaries[worst].earliest)
+                || (ib == wb && self.summaries[i].rank > self.summaries[worst].rank)
             {
                 worst = i;
             }
         }
         let wb = buckets.bucket(self.summaries[worst].reliability);
-        if cb > wb || (cb == wb && ce < self.summaries[worst].earliest) {
+        if cb > wb || (cb == wb && ce < self.summaries[worst].rank) {
             self.summaries[worst] = cand;
             return true;
         }
@@ -620,8 +673,8 @@ impl LabelCell for SlimSet {
         self.len > 0
     }
     #[inline]
-    fn earliest_arrival(&self) -> u32 {
-        self.earliest_val()
+    fn min_rank(&self) -> u32 {
+        self.iter().map(|s| s.rank).min().unwrap_or(u32::MAX)
     }
     #[inline]
     fn count(&self) -> usize {
@@ -824,13 +877,14 @@ pub(super) fn compact_best_enabled() -> bool {
 }
 
 /// Per-pass forward-exploration bound (`best`), reset every pass so all its labels share
-/// one stamp. Same-stamp LabelSet reads (`is_reached`/`earliest`/`dominates`/`insert`)
-/// reduce EXACTLY to per-reliability-bucket minimum scheduled arrival, so the `Compact`
-/// per-bucket-`min` backend reproduces them bit-for-bit while `n_buckets <= MAX_LABELS`.
+/// one stamp. Same-stamp LabelSet reads (`is_reached`/`min_rank`/`dominates`/`insert`)
+/// reduce EXACTLY to per-reliability-bucket minimum ranked arrival ([`Label::rank`]), so
+/// the `Compact` per-bucket-`min` backend reproduces them bit-for-bit while
+/// `n_buckets <= MAX_LABELS`.
 pub(super) enum BestGrid {
     Labels(Vec<LabelSet>),
     Compact {
-        /// Flat `n_cells * n_buckets` min scheduled arrivals (`u32::MAX` = unreached).
+        /// Flat `n_cells * n_buckets` min ranked arrivals (`u32::MAX` = unreached).
         /// Cell `c`, bucket `b` → `arr[c*nb + b]`.
         arr: Vec<u32>,
         n_buckets: usize,
@@ -886,9 +940,9 @@ impl BestGrid {
     }
 
     #[inline]
-    pub(super) fn earliest(&self, cell: usize) -> u32 {
+    pub(super) fn min_rank(&self, cell: usize) -> u32 {
         match self {
-            BestGrid::Labels(v) => v[cell].earliest(),
+            BestGrid::Labels(v) => v[cell].min_rank(),
             BestGrid::Compact { arr, n_buckets, .. } => arr
                 [cell * n_buckets..(cell + 1) * n_buckets]
                 .iter()
@@ -898,14 +952,14 @@ impl BestGrid {
         }
     }
 
-    /// Same predicate as `LabelSet::dominates`: some member with bucket `>=` and arrival `<=`.
+    /// Same predicate as `LabelSet::dominates`: some member with bucket `>=` and rank `<=`.
     #[inline]
     pub(super) fn dominates(&self, cell: usize, cand: Label, buckets: &ReliabilityBuckets) -> bool {
         match self {
             BestGrid::Labels(v) => v[cell].dominates(cand, buckets),
             BestGrid::Compact { arr, n_buckets, .. } => {
                 let cb = buckets.bucket(cand.reliability) as usize;
-                let ce = cand.bag.earliest();
+                let ce = cand.rank();
                 arr[cell * n_buckets + cb..(cell + 1) * n_buckets]
                     .iter()
                     .any(|&a| a <= ce)
@@ -913,7 +967,7 @@ impl BestGrid {
         }
     }
 
-    /// Same-stamp equivalent of `LabelSet::insert`: keep the per-bucket min scheduled arrival.
+    /// Same-stamp equivalent of `LabelSet::insert`: keep the per-bucket min ranked arrival.
     #[inline]
     pub(super) fn insert(&mut self, cell: usize, cand: Label, buckets: &ReliabilityBuckets) {
         match self {
@@ -922,7 +976,7 @@ impl BestGrid {
             }
             BestGrid::Compact { arr, n_buckets, .. } => {
                 let cb = buckets.bucket(cand.reliability) as usize;
-                let ce = cand.bag.earliest();
+                let ce = cand.rank();
                 let slot = &mut arr[cell * *n_buckets + cb];
                 if ce < *slot {
                     *slot = ce;
@@ -978,14 +1032,22 @@ impl Graph {
     }
 
     /// Foot seconds keyed on projected snap coords (`ep`, g-free), else `walk_secs_to`.
+    /// With `use_cch` and a CCH loaded the coord pair is answered by a point-to-point
+    /// CCH query.
     fn walk_secs_to_ep(
         &self,
         origin: NodeID,
         destination: NodeID,
         bound: u32,
         ep: Option<&QueryEndpoints>,
+        use_cch: bool,
     ) -> u32 {
         if let Some(ep) = ep {
+            if use_cch && let Some(cch) = self.cch.as_ref() {
+                return self
+                    .cch_walk_secs(cch, ep.origin, ep.destination, bound)
+                    .unwrap_or(u32::MAX);
+            }
             let cg = self.contracted.as_ref().unwrap();
             let radius = self.raptor.edge_snap_radius_m;
             return cg
@@ -1160,13 +1222,14 @@ impl Graph {
         } else {
             self.foot_nearby_stops_ep(destination, egress_secs, ep.map(|e| e.destination))
         };
+        let raw_egress = self.cap_foot_walk(raw_egress, super::query_profile::max_egress_walk_m());
         let foot_egress = self.egress_times(raw_egress);
         let mc = ModeContext::build(am, &[], &[], &[], &foot_egress, &[], &[], None, unrestricted);
         if !mc.any_egress() {
             return Vec::new();
         }
         let plans = self.raptor_onboard_inner(&mc, ride, date, weekday, destination, buckets, slack, rt);
-        Self::finalize_plans(plans, buckets)
+        Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -1263,7 +1326,7 @@ impl Graph {
         ep: Option<&QueryEndpoints>,
     ) -> u32 {
         let straight_line_secs =
-            (self.endpoint_distance(origin, destination, ep) as f64 / self.raptor.walking_speed_mps)
+            (self.endpoint_distance(origin, destination, ep) as f64 / self.walking_speed_mps())
                 as u32;
         self.nearest_stop_secs_ep(origin, straight_line_secs, ep.map(|e| e.origin))
             .max(min_access_secs)
@@ -1280,17 +1343,18 @@ impl Graph {
         start_time: u32,
         best_arrival: Option<u32>,
         ep: Option<&QueryEndpoints>,
+        use_cch: bool,
     ) -> (u32, Option<u32>) {
         let w_lower_secs = ((self.endpoint_distance(origin, destination, ep) as f64
             - 2.0 * self.raptor.edge_snap_radius_m)
             .max(0.0)
-            / self.raptor.walking_speed_mps) as u32;
+            / self.walking_speed_mps()) as u32;
         let mut w_opt: Option<u32> = None;
         let bound = match best_arrival {
             Some(a) => {
                 let b = a.saturating_sub(start_time);
                 if b > w_lower_secs {
-                    let w = self.walk_secs_to_ep(origin, destination, u32::MAX, ep);
+                    let w = self.walk_secs_to_ep(origin, destination, u32::MAX, ep, use_cch);
                     w_opt = Some(w);
                     b.min(w)
                 } else {
@@ -1298,7 +1362,7 @@ impl Graph {
                 }
             }
             None => {
-                let w = self.walk_secs_to_ep(origin, destination, u32::MAX, ep);
+                let w = self.walk_secs_to_ep(origin, destination, u32::MAX, ep, use_cch);
                 w_opt = Some(w);
                 w
             }
@@ -1328,7 +1392,7 @@ impl Graph {
     {
         if !am.wants_transit() {
             let walk_secs = if am.wants_direct_walk() {
-                self.walk_secs_to_ep(origin, destination, u32::MAX, ep)
+                self.walk_secs_to_ep(origin, destination, u32::MAX, ep, use_cch)
             } else {
                 u32::MAX
             };
@@ -1341,7 +1405,7 @@ impl Graph {
                 bike,
                 ep,
             );
-            return Self::finalize_plans(plans, buckets);
+            return Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences);
         }
 
         let both_stations = ep.is_some_and(|e| {
@@ -1372,17 +1436,17 @@ impl Graph {
         // Pass A is already complete.
         if both_stations {
             if all.is_empty() {
-                let actual = self.walk_secs_to_ep(origin, destination, u32::MAX, ep);
+                let actual = self.walk_secs_to_ep(origin, destination, u32::MAX, ep, use_cch);
                 all = self.direct_fallback_plans(
                     am, origin, destination, start_time, actual, bike, ep,
                 );
             } else {
                 self.append_bounded_direct_plans(
                     am, origin, destination, start_time, slack, bike,
-                    &mut all, ep,
+                    &mut all, ep, use_cch,
                 );
             }
-            return Self::finalize_plans(all, buckets);
+            return Self::finalize_plans(all, buckets, &self.raptor.mode_preferences);
         }
 
         // Pass B — admissible radius `min(W, A_est - start)`. `admissible_access_bound`
@@ -1395,6 +1459,7 @@ impl Graph {
             start_time,
             all.iter().map(|p| p.end).min(),
             ep,
+            use_cch,
         );
         if access_secs < bound {
             access_secs = bound;
@@ -1409,18 +1474,28 @@ impl Graph {
 
         // No transit plan at any radius up to W: fall back to direct / walk-only plans.
         if all.is_empty() {
-            let w = w_opt.unwrap_or_else(|| self.walk_secs_to_ep(origin, destination, u32::MAX, ep));
+            let w = w_opt.unwrap_or_else(|| self.walk_secs_to_ep(origin, destination, u32::MAX, ep, use_cch));
             let plans = self.direct_fallback_plans(
                 am, origin, destination, start_time, w, bike, ep,
             );
-            return Self::finalize_plans(plans, buckets);
+            return Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences);
         }
 
         self.append_bounded_direct_plans(
             am, origin, destination, start_time, slack, bike,
-            &mut all, ep,
+            &mut all, ep, use_cch,
         );
-        Self::finalize_plans(all, buckets)
+        Self::finalize_plans(all, buckets, &self.raptor.mode_preferences)
+    }
+
+    /// Drop stops whose raw walk from the endpoint is longer than `max_m` metres at
+    /// the query's walking speed (`maxAccessWalk` / `maxEgressWalk`).
+    pub(crate) fn cap_foot_walk(&self, stops: Vec<(usize, u32)>, max_m: Option<u32>) -> Vec<(usize, u32)> {
+        let Some(max_m) = max_m else {
+            return stops;
+        };
+        let max_secs = max_m as f64 / self.walking_speed_mps();
+        stops.into_iter().filter(|&(_, secs)| secs as f64 <= max_secs).collect()
     }
 
     /// Inflate access-leg seconds to the conservative percentile (buffer the connection).
@@ -1490,7 +1565,7 @@ impl Graph {
         let has = |s| am.state_of(s).is_some();
         // Bike/car use a wider, trip-length-scaled budget than the local foot radius.
         let crow_secs = (self.endpoint_distance(origin, destination, ep) as f64
-            / self.raptor.walking_speed_mps) as u32;
+            / self.walking_speed_mps()) as u32;
         let vehicle_secs = access_secs.max(self.vehicle_access_budget(crow_secs));
 
         let station_zero = |platforms: &[usize]| -> Vec<(usize, u32)> {
@@ -1508,7 +1583,7 @@ impl Graph {
             } else {
                 self.foot_nearby_stops_ep(origin, access_secs, ep.map(|e| e.origin))
             };
-            self.access_times(raw)
+            self.access_times(self.cap_foot_walk(raw, super::query_profile::max_access_walk_m()))
         } else {
             vec![]
         };
@@ -1522,7 +1597,13 @@ impl Graph {
         let car_access = if let Some(p) = origin_station {
             station_zero(p)
         } else if has(CarParked) {
-            self.access_times(self.car_nearby_stops(origin, vehicle_secs))
+            // Prefer stops next to a car park of known capacity.
+            let mut stops = self.access_times(self.car_nearby_stops(origin, vehicle_secs));
+            for (s, secs) in &mut stops {
+                let loc = self.node_loc(self.raptor.transit_stop_to_node[*s]);
+                *secs = secs.saturating_add(self.park_ride_allowance_secs(loc));
+            }
+            stops
         } else {
             vec![]
         };
@@ -1537,7 +1618,7 @@ impl Graph {
             } else {
                 self.foot_nearby_stops_ep(destination, access_secs, ep.map(|e| e.destination))
             };
-            self.egress_times(raw)
+            self.egress_times(self.cap_foot_walk(raw, super::query_profile::max_egress_walk_m()))
         } else {
             vec![]
         };
@@ -1555,6 +1636,7 @@ impl Graph {
         } else if let Some(p) = dest_station {
             station_zero(p)
         } else if has(CarEgress) {
+            let _free_flow = super::rush_hour::begin(None);
             self.egress_times(self.car_nearby_stops(destination, vehicle_secs))
         } else {
             vec![]
@@ -1588,9 +1670,10 @@ impl Graph {
             unrestricted,
         );
         if trip_memo_enabled() {
-            mc.trip_active_memo = Some(TripActiveMemo::new(self.raptor.transit_trips.len()));
+            mc.trip_active_memo = Some(TripActiveMemo::new());
         }
         mc.fare_profile = fare_profile;
+        mc.mode_preferences = self.raptor.mode_preferences.clone();
         mc
     }
 
@@ -1607,6 +1690,7 @@ impl Graph {
         bike: &BikeCost,
         results: &mut Vec<Plan>,
         ep: Option<&QueryEndpoints>,
+        use_cch: bool,
     ) {
         let best_end = match results.iter().map(|p| p.end).min() {
             Some(e) => e,
@@ -1633,7 +1717,7 @@ impl Graph {
             }
         }
         if am.wants_direct_walk() && !am.selected(crate::structures::Mode::WalkTransit) {
-            let secs = self.walk_secs_to_ep(origin, destination, bound, ep);
+            let secs = self.walk_secs_to_ep(origin, destination, bound, ep, use_cch);
             if secs < u32::MAX {
                 results.push(self.build_walk_plan_ep(origin, destination, start_time, secs, ep));
             }
@@ -2098,6 +2182,7 @@ impl Graph {
                         parent: u32::MAX,
                         arena_id: u32::MAX,
                         state: 0,
+                        mode_cost: 0,
                     },
                 );
                 let cell = seed.at_stop as usize * n_states;
@@ -2121,6 +2206,7 @@ impl Graph {
                             parent: u32::MAX,
                             arena_id: u32::MAX,
                             state: sidx as u8,
+                            mode_cost: 0,
                         },
                     );
                     let cell = stop * n_states + sidx;
@@ -2145,11 +2231,22 @@ impl Graph {
 
         // Round-0 transfer bound: onboard seeds (no access radius) use the egress-based
         // cutoff; foot access uses the uniform radius, saturating so a near-unbounded
-        // `access_secs` (MCR/flood path) reads as u32::MAX instead of wrapping.
+        // `access_secs` (MCR/flood path) reads as u32::MAX instead of wrapping. A
+        // `maxAccessWalk` also caps foot states: walking on from the access stop to
+        // board elsewhere is still the walk to the first stop.
         let seed_bound = if onboard.is_some() {
             Self::target_cutoff(best, mc, slack)
         } else {
-            [start_time.saturating_add(access_secs); ALL_STATES.len()]
+            let mut bound = [start_time.saturating_add(access_secs); ALL_STATES.len()];
+            if let Some(max_m) = super::query_profile::max_access_walk_m() {
+                let cap = start_time.saturating_add((max_m as f64 / self.walking_speed_mps()) as u32);
+                for (sidx, vs) in mc.am.states() {
+                    if matches!(vs, VehicleState::Walked | VehicleState::CarEgress | VehicleState::BikeEgress) {
+                        bound[sidx] = bound[sidx].min(cap);
+                    }
+                }
+            }
+            bound
         };
         if mc.unrestricted_transfers {
             self.apply_transfers_mcr(
@@ -2192,7 +2289,7 @@ impl Graph {
             marked.clear();
             is_marked.fill(false);
 
-            if queue.is_empty() {
+            if queue.is_empty() || query_budget::tick_n(queue.len() as u64) {
                 break;
             }
 
@@ -2435,7 +2532,7 @@ impl Graph {
                 .collect();
             let access = AccessInfo {
                 walk_radius_secs: radius,
-                walk_radius_meters: (radius as f64 * self.raptor.walking_speed_mps) as u32,
+                walk_radius_meters: (radius as f64 * self.walking_speed_mps()) as u32,
                 origin_stops_found: 0,
                 destination_stops_found: 0,
                 access_attempts: extra_passes,
@@ -2474,7 +2571,7 @@ impl Graph {
 
         if both_stations {
             if all_plans.is_empty() {
-                let actual = self.walk_secs_to_ep(origin, destination, u32::MAX, ep);
+                let actual = self.walk_secs_to_ep(origin, destination, u32::MAX, ep, use_cch);
                 return walk_fallback(actual, pass_a_radius, passes_run.saturating_sub(1));
             }
         } else {
@@ -2484,6 +2581,7 @@ impl Graph {
                 start_time,
                 all_plans.iter().map(|p| p.end).min(),
                 ep,
+                use_cch,
             );
             if access_secs < bound {
                 access_secs = bound;
@@ -2508,7 +2606,7 @@ impl Graph {
 
             if all_plans.is_empty() {
                 let w = w_opt
-                    .unwrap_or_else(|| self.walk_secs_to_ep(origin, destination, u32::MAX, ep));
+                    .unwrap_or_else(|| self.walk_secs_to_ep(origin, destination, u32::MAX, ep, use_cch));
                 return walk_fallback(w, access_secs, passes_run.saturating_sub(1));
             }
         }
@@ -2519,12 +2617,17 @@ impl Graph {
             .enumerate()
             .filter_map(|(ci, c)| matches!(c.status, CandidateStatus::Kept).then_some(ci))
             .collect();
-        let final_plans =
-            Self::pareto_filter_with_debug(all_plans, &plan_to_sink_idx, &mut all_cands, buckets);
+        let final_plans = Self::pareto_filter_with_debug(
+            all_plans,
+            &plan_to_sink_idx,
+            &mut all_cands,
+            buckets,
+            &self.raptor.mode_preferences,
+        );
 
         let access = AccessInfo {
             walk_radius_secs: pass_a_radius,
-            walk_radius_meters: (pass_a_radius as f64 * self.raptor.walking_speed_mps) as u32,
+            walk_radius_meters: (pass_a_radius as f64 * self.walking_speed_mps()) as u32,
             origin_stops_found: origin_stops,
             destination_stops_found: dest_stops,
             access_attempts: passes_run.saturating_sub(1),
@@ -2764,6 +2867,13 @@ impl Graph {
 
         let route_id = self.raptor.transit_patterns[pattern].route;
         let pat_rt = self.raptor.transit_routes[route_id.0 as usize].route_type;
+        // This mode's preference, ranked into the labels: penalty at boarding, the
+        // multiplier's excess over the ride at alighting.
+        let (board_penalty, ride_factor) = mc
+            .mode_preferences
+            .iter()
+            .find(|(m, _)| *m == pat_rt)
+            .map_or((0.0, 0.0), |(_, p)| (p.board_penalty_secs as f64, p.cost_multiplier - 1.0));
 
         // Price is annotated post-hoc (`plan_price_posthoc`); the scan is price-blind.
 
@@ -2775,10 +2885,15 @@ impl Graph {
         // Riding Pareto set over (trip index ↓, bucket ↑): a smaller trip index arrives
         // earlier at every downstream stop.
         let mut riding: Vec<Riding> = Vec::new();
+        let wheelchair = mc.wheelchair;
 
         for pos in first_pos as usize..pat_stops.len() {
             let stop = self.raptor.transit_node_to_stop[pat_stops[pos].0] as usize;
             let col = &all_times[pos * n_trips..(pos + 1) * n_trips];
+            // Wheelchair profile: an inaccessible stop is ridden through, never used.
+            if wheelchair && (self.stop_blocks_wheelchair(stop) || rt.stop_inaccessible(stop as u32)) {
+                continue;
+            }
 
             // 1. Settle arrivals at this stop for every riding label.
             for r in &riding {
@@ -2787,7 +2902,8 @@ impl Graph {
                     continue;
                 }
                 let arr = apply_delay(col[r.t].arrival, rt.delay(trip_ids[r.t], stop as u32));
-                if arr >= cutoff[r.state as usize] {
+                let mode_cost = (r.cost_base + ride_factor * arr as f64).round() as i32;
+                if ranked(arr, mode_cost) >= cutoff[r.state as usize] {
                     continue;
                 }
                 let bag = if r.hit_prob < 1.0 {
@@ -2830,6 +2946,7 @@ impl Graph {
                     parent: r.from_arena,
                     arena_id: u32::MAX,
                     state: r.state,
+                    mode_cost,
                 };
                 if best.dominates(stop * n_states + r.state as usize, cand, buckets) {
                     continue;
@@ -2872,8 +2989,7 @@ impl Graph {
                         }
                         // Carrying a bike: only trips that explicitly allow it.
                         if needs_bikes
-                            && self.raptor.transit_trips[trip_ids[t].0 as usize].bikes_allowed
-                                != Some(true)
+                            && self.trip_allows_bikes(trip_ids[t], Some(pat_rt)) != Some(true)
                         {
                             continue;
                         }
@@ -2934,6 +3050,8 @@ impl Graph {
                                 from_round: pl.round,
                                 from_arena: pl.arena_id,
                                 state: sidx as u8,
+                                cost_base: pl.mode_cost as f64 + board_penalty
+                                    - ride_factor * trip_dep as f64,
                             },
                             buckets,
                         );
@@ -2994,6 +3112,8 @@ impl Graph {
             if curr.insert(cell, pushed, buckets) {
                 Self::mark(cell, marked, is_marked);
             }
+        } else {
+            query_trace::prune(PruneReason::StopDominated);
         }
         if let Some((in_hand, dropped)) = drop_to
             && cand.state == in_hand
@@ -3032,19 +3152,29 @@ impl Graph {
         threads.min(queue_len / 8).max(1)
     }
 
-    /// Pareto-inserts a riding label over (trip index ↓, bucket ↑). Domination applies
-    /// ONLY within the same vehicle state (a `Walked` rider must never be pruned by a
-    /// bike-state one, or the walk plan vanishes before the plan-level burden comparison).
+    /// Pareto-inserts a riding label over (trip index ↓, bucket ↑, cost base ↓): an
+    /// earlier trip also ranks no later at every stop when its cost base is no higher.
+    /// Domination applies ONLY within the same vehicle state (a `Walked` rider must never
+    /// be pruned by a bike-state one, or the walk plan vanishes before the plan-level
+    /// burden comparison).
     fn push_riding(riding: &mut Vec<Riding>, cand: Riding, buckets: &ReliabilityBuckets) {
         let cb = buckets.bucket(cand.reliability);
         for r in riding.iter() {
-            if r.state == cand.state && r.t <= cand.t && buckets.bucket(r.reliability) >= cb {
+            if r.state == cand.state
+                && r.t <= cand.t
+                && buckets.bucket(r.reliability) >= cb
+                && r.cost_base <= cand.cost_base
+            {
                 return; // dominated
             }
         }
         riding.retain(|r| {
             let rb = buckets.bucket(r.reliability);
-            !(r.state == cand.state && cand.t <= r.t && cb >= rb && (cand.t < r.t || cb > rb))
+            !(r.state == cand.state
+                && cand.t <= r.t
+                && cb >= rb
+                && cand.cost_base <= r.cost_base
+                && (cand.t < r.t || cb > rb || cand.cost_base < r.cost_base))
         });
         if riding.len() < MAX_LABELS {
             riding.push(cand);
@@ -3122,6 +3252,7 @@ impl Graph {
         n_states: usize,
         drop_to: Option<(u8, u8)>,
     ) {
+        let walk_scale = self.transfer_walk_scale();
         let n = marked.len();
         for i in 0..n {
             let cell = marked[i];
@@ -3129,7 +3260,11 @@ impl Graph {
             let sidx = (cell % n_states) as u8;
             let state_cutoff = cutoff[sidx as usize];
             let src = labels.cell(cell);
-            if !src.is_reached() || src.earliest_arrival() >= state_cutoff {
+            if !src.is_reached() {
+                continue;
+            }
+            if src.min_rank() >= state_cutoff {
+                query_trace::prune(PruneReason::TargetCutoff);
                 continue;
             }
 
@@ -3137,6 +3272,7 @@ impl Graph {
                 .of(&self.raptor.transit_stop_transfers);
             for &(target_node, walk) in transfers {
                 let target = self.raptor.transit_node_to_stop[target_node.0] as usize;
+                let walk = walk_scale.map_or(walk, |k| (walk as f64 * k).round() as u32);
 
                 for li in 0..src.count() {
                     // Transfer ONLY this pass's labels (an `i`-journey descends from the
@@ -3146,7 +3282,8 @@ impl Graph {
                     }
                     let l = src.full_at(li, arena);
                     let bag = l.bag.shifted_by(walk);
-                    if bag.earliest() >= state_cutoff {
+                    if ranked(bag.earliest(), l.mode_cost) >= state_cutoff {
+                        query_trace::prune(PruneReason::TargetCutoff);
                         continue;
                     }
                     let cand = Label {
@@ -3167,6 +3304,7 @@ impl Graph {
                         parent: l.arena_id,
                         arena_id: u32::MAX,
                         state: sidx,
+                        mode_cost: l.mode_cost,
                     };
                     self.insert_candidate(
                         cand, labels, best, buckets, marked, is_marked, arena, n_states, drop_to,
@@ -3249,7 +3387,7 @@ impl Graph {
                         if sm.created_by != stamp {
                             continue;
                         }
-                        if sm.earliest >= state_cutoff {
+                        if sm.rank >= state_cutoff {
                             continue;
                         }
                         match chosen {
@@ -3282,7 +3420,7 @@ impl Graph {
                             continue;
                         };
                         let nd = seed.arr.saturating_add(t);
-                        if nd > state_cutoff {
+                        if ranked(nd, seed.label.mode_cost) > state_cutoff {
                             continue;
                         }
                         let to = se.to as usize;
@@ -3299,6 +3437,9 @@ impl Graph {
                     if scratch.vgen[ji] != scratch.cur_gen || d > scratch.dist[ji] {
                         continue;
                     }
+                    if query_budget::tick() {
+                        break;
+                    }
                     settles += 1;
                     let jn = cg.junctions[ji];
                     let target_compact = self
@@ -3317,8 +3458,8 @@ impl Graph {
                         if target != seed.stop {
                             let walk = d.saturating_sub(seed.arr);
                             let bag = seed.label.bag.shifted_by(walk);
-                            if bag.earliest() < state_cutoff {
                             let l = &seed.label;
+                            if ranked(bag.earliest(), l.mode_cost) < state_cutoff {
                             cands.push(Label {
                                 bag,
                                 route_type: l.route_type,
@@ -3337,6 +3478,7 @@ impl Graph {
                                 parent: l.arena_id,
                                 arena_id: u32::MAX,
                                 state: sidx8,
+                                mode_cost: l.mode_cost,
                                 });
                             }
                         }
@@ -3349,7 +3491,7 @@ impl Graph {
                             continue;
                         };
                         let nd = d.saturating_add(t);
-                        if nd > state_cutoff {
+                        if ranked(nd, seeds[slot as usize].label.mode_cost) > state_cutoff {
                             continue;
                         }
                         let to = se.to as usize;
@@ -3379,12 +3521,12 @@ impl Graph {
     #[inline]
     pub(super) fn is_trip_active(&self, trip_id: TripId, date: u32, weekday: u8) -> bool {
         let svc = self.raptor.transit_trips[trip_id.0 as usize].service_id;
-        self.raptor.transit_services[svc.0 as usize].is_active(date, weekday)
+        self.raptor.is_service_active(svc, date, weekday)
     }
 
-    /// Memoized `is_trip_active`. With a `TripActiveMemo` the tri-state cache is
-    /// consulted/filled; else an exact passthrough. Byte-identical (memo stores the pure
-    /// result for the query's fixed `(date, weekday)`).
+    /// Memoized `is_trip_active`. With a `TripActiveMemo` the trip's service is looked up
+    /// in the query day's mask; else an exact passthrough. Byte-identical (the mask holds
+    /// the same `is_service_active` results for the query's fixed `(date, weekday)`).
     #[inline]
     pub(super) fn is_trip_active_memo(
         &self,
@@ -3393,11 +3535,11 @@ impl Graph {
         date: u32,
         weekday: u8,
     ) -> bool {
-        use std::sync::atomic::Ordering::Relaxed;
         match &mc.trip_active_memo {
             Some(m) => {
                 #[cfg(debug_assertions)]
                 {
+                    use std::sync::atomic::Ordering::Relaxed;
                     let key = ((date as u64) << 8) | weekday as u64;
                     let prev = m.dw.swap(key, Relaxed);
                     debug_assert!(
@@ -3405,16 +3547,8 @@ impl Graph {
                         "TripActiveMemo reused across differing (date, weekday)"
                     );
                 }
-                let slot = &m.states[trip_id.0 as usize];
-                match slot.load(Relaxed) {
-                    1 => false,
-                    2 => true,
-                    _ => {
-                        let active = self.is_trip_active(trip_id, date, weekday);
-                        slot.store(if active { 2 } else { 1 }, Relaxed);
-                        active
-                    }
-                }
+                let svc = self.raptor.transit_trips[trip_id.0 as usize].service_id;
+                m.services(|| self.raptor.active_services(date, weekday)).contains(svc)
             }
             None => self.is_trip_active(trip_id, date, weekday),
         }
@@ -3433,7 +3567,7 @@ impl Graph {
             for &(s, w) in &mc.egress[sidx] {
                 let cell = s * n_states + sidx;
                 if best.is_reached(cell) {
-                    per_burden[b] = per_burden[b].min(best.earliest(cell).saturating_add(w));
+                    per_burden[b] = per_burden[b].min(best.min_rank(cell).saturating_add(w));
                 }
             }
         }
@@ -3755,7 +3889,7 @@ impl Graph {
                         &mut bw_cache,
                     )
                 };
-                Self::finalize_plans(all_plans, buckets)
+                Self::finalize_plans(all_plans, buckets, &self.raptor.mode_preferences)
             },
         )
     }
@@ -4027,7 +4161,7 @@ impl Graph {
                     );
                     all_plans.extend(plans);
                 }
-                Self::finalize_plans(all_plans, buckets)
+                Self::finalize_plans(all_plans, buckets, &self.raptor.mode_preferences)
             },
         )
     }
@@ -4058,16 +4192,36 @@ impl Graph {
         )
     }
     /// Trips departing before this threshold may be overnight extensions of the previous
-    /// service day (GTFS times > 86400).
+    /// service day (GTFS times > 86400). Feeds override it with `service_day_cutover`.
     const OVERNIGHT_THRESHOLD_SECS: u32 = 5 * 3600;
 
+    /// Realtime view for the previous-service-day pass of a query at `start_time`: trips
+    /// of feeds whose service day already rolled over (`start_time` at or past their
+    /// cutover) are canceled. `None` when every feed rolled over, so the pass is skipped.
+    fn previous_day_rt<'a>(&self, start_time: u32, rt: &'a RealtimeIndex) -> Option<Cow<'a, RealtimeIndex>> {
+        if self.feeds.is_empty() {
+            return (start_time < Self::OVERNIGHT_THRESHOLD_SECS).then_some(Cow::Borrowed(rt));
+        }
+        let (open, rolled): (Vec<_>, Vec<_>) = self.feeds.iter().partition(|f| {
+            start_time < f.service_day_cutover.unwrap_or(Self::OVERNIGHT_THRESHOLD_SECS)
+        });
+        if open.is_empty() {
+            return None;
+        }
+        if rolled.is_empty() {
+            return Some(Cow::Borrowed(rt));
+        }
+        let trips = rolled.iter().flat_map(|f| f.trips.clone()).map(|t| TripId(t as u32));
+        Some(Cow::Owned(rt.clone().with_closures(trips, [])))
+    }
+
     /// Rotate a 7-bit weekday bitmask one day backward (Mon=0x01 → Sun=0x40).
-    fn prev_weekday(wd: u8) -> u8 {
+    pub(crate) fn prev_weekday(wd: u8) -> u8 {
         ((wd >> 1) | ((wd & 1) << 6)) & 0x7F
     }
 
     /// Rotate a 7-bit weekday bitmask one day forward (Sun=0x40 → Mon=0x01).
-    fn next_weekday(wd: u8) -> u8 {
+    pub(crate) fn next_weekday(wd: u8) -> u8 {
         ((wd << 1) | ((wd & 0x40) >> 6)) & 0x7F
     }
 
@@ -4075,7 +4229,7 @@ impl Graph {
     /// overnight pass down into the query day; `shift < 0` normalizes a date+1 pass up.
     /// Leg `date`/`weekday` are left UNTOUCHED (the trip's listed service day; recovered
     /// via `raw = displayed + time_shift`). Times clamp at 0.
-    fn shift_plan(mut plan: Plan, shift: i64) -> Plan {
+    pub(crate) fn shift_plan(mut plan: Plan, shift: i64) -> Plan {
         let sub = |x: u32| (x as i64 - shift).max(0) as u32;
         plan.start = sub(plan.start);
         plan.end = sub(plan.end);
@@ -4085,7 +4239,13 @@ impl Graph {
         }
         for leg in &mut plan.legs {
             match leg {
-                PlanLeg::Walk(w) => {
+                PlanLeg::Wait(w) => {
+                    w.start = sub(w.start);
+                    w.end = sub(w.end);
+                }
+                PlanLeg::Walk(w)
+                | PlanLeg::Bike(PlanBikeLeg { leg: w })
+                | PlanLeg::Car(PlanCarLeg { leg: w }) => {
                     w.start = sub(w.start);
                     w.end = sub(w.end);
                     for step in &mut w.steps {
@@ -4216,7 +4376,7 @@ impl Graph {
             fare_profile,
         );
 
-        if start_time < Self::OVERNIGHT_THRESHOLD_SECS && date > 0 {
+        if let Some(prev_rt) = self.previous_day_rt(start_time, rt).filter(|_| date > 0) {
             let overnight = self.raptor_tuned_rt_modes_ep(
                 origin,
                 destination,
@@ -4228,7 +4388,7 @@ impl Graph {
                 slack,
                 unrestricted,
                 use_cch,
-                rt,
+                &prev_rt,
                 am,
                 bike,
                 ep,
@@ -4240,7 +4400,7 @@ impl Graph {
                 .collect();
             if !normalized.is_empty() {
                 plans.extend(normalized);
-                plans = Self::finalize_plans(plans, buckets);
+                plans = Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences);
             }
         }
 
@@ -4268,7 +4428,7 @@ impl Graph {
             );
             if !forward.is_empty() {
                 plans.extend(forward);
-                plans = Self::finalize_plans(plans, buckets);
+                plans = Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences);
             }
         }
 
@@ -4314,7 +4474,7 @@ impl Graph {
             fare_profile,
         );
 
-        if start_time < Self::OVERNIGHT_THRESHOLD_SECS && date > 0 {
+        if let Some(prev_rt) = self.previous_day_rt(start_time, rt).filter(|_| date > 0) {
             let overnight = self.raptor_range_tuned_rt_modes_ep(
                 origin,
                 destination,
@@ -4327,7 +4487,7 @@ impl Graph {
                 slack,
                 unrestricted,
                 use_cch,
-                rt,
+                &prev_rt,
                 am,
                 bike,
                 ep,
@@ -4339,7 +4499,7 @@ impl Graph {
                 .collect();
             if !normalized.is_empty() {
                 plans.extend(normalized);
-                plans = Self::finalize_plans(plans, buckets);
+                plans = Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences);
             }
         }
 
@@ -4384,7 +4544,7 @@ impl Graph {
                 .collect();
             if !normalized.is_empty() {
                 plans.extend(normalized);
-                plans = Self::finalize_plans(plans, buckets);
+                plans = Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences);
             }
         }
 
@@ -4412,7 +4572,7 @@ impl Graph {
             );
             if !forward.is_empty() {
                 plans.extend(forward);
-                plans = Self::finalize_plans(plans, buckets);
+                plans = Self::finalize_plans(plans, buckets, &self.raptor.mode_preferences);
             }
         }
 
@@ -4436,6 +4596,7 @@ mod label_tests {
             parent: u32::MAX,
             arena_id: u32::MAX,
             state: 0,
+            mode_cost: 0,
         }
     }
 
@@ -4468,31 +4629,21 @@ mod label_tests {
     }
 
     #[test]
-    fn trip_active_memo_tristate_roundtrip() {
-        // Memo contract: fresh reads `unknown` (0); a filled slot reads back its bool; fill
-        // is idempotent. This is the invariant `is_trip_active_memo` relies on.
-        use std::sync::atomic::Ordering::Relaxed;
-        let m = TripActiveMemo::new(4);
-        for slot in &m.states {
-            assert_eq!(slot.load(Relaxed), 0, "fresh slot must be unknown");
+    fn trip_active_memo_resolves_the_day_once() {
+        // Memo contract: the mask is built on first use and every later lookup reuses it.
+        // This is the invariant `is_trip_active_memo` relies on.
+        use crate::ingestion::gtfs::ServiceId;
+        let m = TripActiveMemo::new();
+        let mut built = 0;
+        for _ in 0..3 {
+            let active = m.services(|| {
+                built += 1;
+                ActiveServices::from_fn(2, |s| s == ServiceId(1))
+            });
+            assert!(!active.contains(ServiceId(0)));
+            assert!(active.contains(ServiceId(1)));
         }
-        let fill = |i: usize, active: bool| {
-            let slot = &m.states[i];
-            if slot.load(Relaxed) == 0 {
-                slot.store(if active { 2 } else { 1 }, Relaxed);
-            }
-            match slot.load(Relaxed) {
-                1 => false,
-                2 => true,
-                _ => unreachable!(),
-            }
-        };
-        assert!(fill(0, true));
-        assert!(!fill(1, false));
-        // Cache hit: value is stable, independent of the re-passed truth.
-        assert!(fill(0, false));
-        assert!(!fill(1, true));
-        assert_eq!(m.states[2].load(Relaxed), 0, "untouched slot stays unknown");
+        assert_eq!(built, 1, "resolved once per query");
     }
 
     #[test]
@@ -4518,7 +4669,7 @@ mod label_tests {
     }
 
     /// R3 exactness: the compact `best` backend must return BIT-IDENTICAL
-    /// `is_reached`/`earliest`/`dominates` to the legacy backend after any same-stamp
+    /// `is_reached`/`min_rank`/`dominates` to the legacy backend after any same-stamp
     /// insert stream. Driven with a deterministic pseudo-random sequence.
     #[test]
     fn bestgrid_compact_matches_labels_exactly() {
@@ -4550,7 +4701,7 @@ mod label_tests {
             legacy.insert(cell, cand, &b);
 
             assert_eq!(compact.is_reached(cell), legacy.is_reached(cell), "is_reached cell {cell}");
-            assert_eq!(compact.earliest(cell), legacy.earliest(cell), "earliest cell {cell}");
+            assert_eq!(compact.min_rank(cell), legacy.min_rank(cell), "min_rank cell {cell}");
             for &pr in &rels {
                 for pt in [20_000u32, 35_000, 36_000, 42_000, 50_000] {
                     let probe = lbl(pt, pr);
@@ -4564,7 +4715,7 @@ mod label_tests {
         }
         for cell in 0..n_cells {
             assert_eq!(compact.is_reached(cell), legacy.is_reached(cell));
-            assert_eq!(compact.earliest(cell), legacy.earliest(cell));
+            assert_eq!(compact.min_rank(cell), legacy.min_rank(cell));
             for &pr in &rels {
                 for pt in [0u32, 30_000, 40_000, 45_000, u32::MAX] {
                     let probe = lbl(pt, pr);
@@ -4581,8 +4732,8 @@ mod label_tests {
         for cell in 0..n_cells {
             assert!(!compact.is_reached(cell));
             assert!(!legacy.is_reached(cell));
-            assert_eq!(compact.earliest(cell), u32::MAX);
-            assert_eq!(legacy.earliest(cell), u32::MAX);
+            assert_eq!(compact.min_rank(cell), u32::MAX);
+            assert_eq!(legacy.min_rank(cell), u32::MAX);
         }
     }
 
@@ -4595,6 +4746,23 @@ mod label_tests {
         assert_eq!(s.iter().count(), 1);
     }
 
+    #[test]
+    fn labelset_ranks_arrivals_with_the_mode_cost() {
+        let b = ReliabilityBuckets::default();
+        let mut s = LabelSet::EMPTY;
+        let costly_bus = Label { mode_cost: 300, ..lbl(100, 1.0) };
+        assert_eq!(costly_bus.rank(), 400);
+        assert!(s.insert(costly_bus, &b));
+        // Later but cheaper, same bucket: replaces the bus.
+        assert!(s.insert(lbl(200, 1.0), &b));
+        assert_eq!(s.iter().count(), 1);
+        assert_eq!((s.earliest(), s.min_rank()), (200, 200));
+        let favoured = Label { mode_cost: -150, ..lbl(300, 1.0) };
+        assert!(s.insert(favoured, &b));
+        assert_eq!((s.earliest(), s.min_rank()), (300, 150));
+        assert_eq!(ranked(u32::MAX, -150), u32::MAX, "unreached stays unreached");
+    }
+
     /// Within a bucket, dominance is by SCHEDULED arrival, not expected. A label with
     /// an earlier scheduled arrival but worse expected arrival must NOT be overridden
     /// by one with a later scheduled arrival but better expected. (Regression: the
@@ -4614,6 +4782,7 @@ mod label_tests {
             parent: u32::MAX,
             arena_id: u32::MAX,
             state: 0,
+            mode_cost: 0,
         };
         assert_eq!(risky_early.bag.earliest(), 100);
         assert!(s.insert(risky_early, &b));
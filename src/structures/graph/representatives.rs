@@ -197,6 +197,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "pbf")]
     #[test]
     #[ignore]
     fn representatives_smoke_real_brussels() {
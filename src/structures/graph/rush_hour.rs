@@ -0,0 +1,196 @@
+//! Time-of-day car congestion (`default_routing.car_rush_hours`): daily windows in which
+//! driving an edge of a listed highway class takes `factor` times its free-flow time.
+//! Traversal integrates the slowdown across window boundaries, so entering an edge later
+//! never means leaving it earlier (FIFO) and a label-setting search stays exact. Factors
+//! are at least 1, which keeps every free-flow lower bound (A*, ALT) admissible.
+//!
+//! The clock is the query's departure, set for one query in a thread-local like
+//! [`super::query_profile`]: direct car plans and car (P+R) access drive from it, while
+//! car egress, whose start is only known once transit is routed, stays at free flow.
+
+use std::cell::Cell;
+
+use crate::structures::{HighwayClass, StreetEdgeData};
+
+use super::Graph;
+
+const CLASSES: usize = HighwayClass::Other as usize + 1;
+const DAY: f64 = 86_400.0;
+
+thread_local! {
+    static DEPARTURE: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// One daily window, `[start, end)` in seconds after midnight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RushHourWindow {
+    pub start: u32,
+    pub end: u32,
+    factors: [f64; CLASSES],
+}
+
+impl RushHourWindow {
+    /// A window slowing no class yet; see [`Self::set`].
+    pub fn new(start: u32, end: u32) -> Self {
+        Self { start, end, factors: [1.0; CLASSES] }
+    }
+
+    /// Travel time multiplier for `class`; values below 1 are clamped to 1.
+    pub fn set(&mut self, class: HighwayClass, factor: f64) {
+        self.factors[class as usize] = factor.max(1.0);
+    }
+
+    pub fn factor(&self, class: HighwayClass) -> f64 {
+        self.factors[class as usize]
+    }
+
+    fn contains(&self, day_secs: f64) -> bool {
+        (self.start as f64) <= day_secs && day_secs < self.end as f64
+    }
+}
+
+/// Restores the previous departure when dropped (blocking workers are pooled).
+pub struct ClockGuard(Option<u32>);
+
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        DEPARTURE.with(|d| d.set(self.0));
+    }
+}
+
+/// Drive from `departure` (seconds after midnight) on this thread until the guard drops;
+/// `None` times every car edge at free flow.
+pub fn begin(departure: Option<u32>) -> ClockGuard {
+    ClockGuard(DEPARTURE.with(|d| d.replace(departure)))
+}
+
+/// The current query's departure, if its car legs are time-dependent.
+#[inline]
+pub fn departure() -> Option<u32> {
+    DEPARTURE.with(|d| d.get())
+}
+
+/// Seconds to drive an edge of `class` that takes `free` seconds at free flow, entered
+/// at `clock` seconds after midnight (may exceed a day; windows repeat daily).
+pub fn traverse_secs(windows: &[RushHourWindow], class: HighwayClass, free: f64, clock: f64) -> f64 {
+    if !windows.iter().any(|w| w.factor(class) > 1.0) {
+        return free;
+    }
+    let (mut remaining, mut now, mut spent) = (free, clock, 0.0);
+    while remaining > 0.0 {
+        let day = now.rem_euclid(DAY);
+        let factor = windows
+            .iter()
+            .filter(|w| w.contains(day))
+            .map(|w| w.factor(class))
+            .fold(1.0, f64::max);
+        let boundary = windows
+            .iter()
+            .flat_map(|w| [w.start as f64, w.end as f64])
+            .filter(|&b| b > day)
+            .fold(DAY, f64::min);
+        let span = boundary - day;
+        if remaining * factor <= span {
+            return spent + remaining * factor;
+        }
+        spent += span;
+        remaining -= span / factor;
+        now += span;
+    }
+    spent
+}
+
+impl Graph {
+    /// Replace the rush-hour windows; empty ones (`start >= end`) are dropped.
+    pub fn set_car_rush_hours(&mut self, windows: Vec<RushHourWindow>) {
+        self.raptor.car_rush_hours = windows.into_iter().filter(|w| w.start < w.end).collect();
+    }
+
+    /// `free` driving seconds on `street` entered `elapsed` seconds after the query's
+    /// departure; unchanged without a departure clock or configured windows.
+    #[inline]
+    pub(super) fn rush_hour_secs(&self, street: &StreetEdgeData, free: f64, elapsed: f64) -> f64 {
+        match departure() {
+            Some(dep) if !self.raptor.car_rush_hours.is_empty() => traverse_secs(
+                &self.raptor.car_rush_hours,
+                street.attrs.highway,
+                free,
+                dep as f64 + elapsed,
+            ),
+            _ => free,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn morning() -> Vec<RushHourWindow> {
+        let mut w = RushHourWindow::new(8 * 3600, 9 * 3600);
+        w.set(HighwayClass::Motorway, 2.0);
+        vec![w]
+    }
+
+    #[test]
+    fn slowdown_is_integrated_across_window_boundaries() {
+        let w = morning();
+        let at = |h: f64| h * 3600.0;
+        assert_eq!(traverse_secs(&w, HighwayClass::Motorway, 600.0, at(7.0)), 600.0);
+        assert_eq!(traverse_secs(&w, HighwayClass::Motorway, 600.0, at(8.5)), 1200.0);
+        assert_eq!(traverse_secs(&w, HighwayClass::Residential, 600.0, at(8.5)), 600.0);
+        // Enters 5 min before the window: 300 s free, then the rest at half speed.
+        assert_eq!(traverse_secs(&w, HighwayClass::Motorway, 600.0, at(8.0) - 300.0), 900.0);
+        // Enters 5 min before it ends: 300 s cover 150 free-flow s, the last 450 run free.
+        assert_eq!(traverse_secs(&w, HighwayClass::Motorway, 600.0, at(9.0) - 300.0), 750.0);
+        // Windows repeat daily.
+        assert_eq!(traverse_secs(&w, HighwayClass::Motorway, 600.0, DAY + at(8.5)), 1200.0);
+    }
+
+    #[test]
+    fn leaving_later_never_arrives_earlier() {
+        let w = morning();
+        let mut last_exit = f64::MIN;
+        for clock in (7 * 3600..10 * 3600).step_by(60) {
+            let exit = clock as f64 + traverse_secs(&w, HighwayClass::Motorway, 1800.0, clock as f64);
+            assert!(exit >= last_exit, "FIFO broken entering at {clock}");
+            last_exit = exit;
+        }
+    }
+
+    #[test]
+    fn the_clock_applies_only_while_a_departure_is_set() {
+        use crate::structures::cost::VarGen;
+        use crate::structures::{BikeAttrs, NodeID};
+        let mut g = Graph::new();
+        let mut windows = morning();
+        windows.push(RushHourWindow::new(10, 10)); // empty: dropped
+        g.set_car_rush_hours(windows);
+        assert_eq!(g.raptor.car_rush_hours.len(), 1);
+        let street = StreetEdgeData {
+            origin: NodeID(0),
+            destination: NodeID(1),
+            length: 1000,
+            partial: false,
+            foot: false,
+            bike: false,
+            car: true,
+            attrs: BikeAttrs { highway: HighwayClass::Motorway, ..BikeAttrs::road_default() },
+            elev_delta: 0,
+            surface_speed: 0,
+            var_gen: VarGen::NONE,
+        };
+        assert_eq!(g.rush_hour_secs(&street, 60.0, 0.0), 60.0);
+        {
+            let _outer = begin(Some(8 * 3600));
+            assert_eq!(g.rush_hour_secs(&street, 60.0, 0.0), 120.0);
+            assert_eq!(g.rush_hour_secs(&street, 60.0, 3600.0), 60.0, "past the window");
+            {
+                let _egress = begin(None);
+                assert_eq!(g.rush_hour_secs(&street, 60.0, 0.0), 60.0);
+            }
+            assert_eq!(departure(), Some(8 * 3600), "inner guard restores the outer clock");
+        }
+        assert_eq!(departure(), None);
+    }
+}
@@ -0,0 +1,103 @@
+//! Named street-search profiles (`default_routing.search_profiles`): ALT landmark
+//! tables precomputed during the build, optionally over one mode's streets only (a
+//! car-only table bounds a drive far tighter than the all-mode one), persisted with the
+//! graph and picked per request (`searchProfile`). The selection lives in a
+//! thread-local for one query, like [`super::query_profile`], because it is read inside
+//! the street search.
+
+use std::cell::Cell;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Graph, Landmarks};
+use crate::structures::cost::RoutingMode;
+
+thread_local! {
+    static SELECTED: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// What a profile precomputes; two profiles with equal specs hold the same tables.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchProfileSpec {
+    pub name: String,
+    /// Only this mode's streets (`None`: every mode, usable by all of them).
+    pub mode: Option<RoutingMode>,
+    pub landmarks: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchProfile {
+    pub spec: SearchProfileSpec,
+    pub(super) tables: Landmarks,
+}
+
+impl SearchProfile {
+    /// A mode-restricted table is only a lower bound for that mode's searches.
+    pub fn applies_to(&self, mode: RoutingMode) -> bool {
+        self.spec.mode.is_none_or(|m| m == mode)
+    }
+}
+
+/// Clears the selection when dropped (blocking workers are pooled).
+pub struct SelectionGuard(());
+
+impl Drop for SelectionGuard {
+    fn drop(&mut self) {
+        SELECTED.with(|s| s.set(None));
+    }
+}
+
+/// Select the profile at `index` (see [`Graph::search_profile_index`]) on this thread
+/// for one query; `None` keeps the graph's `street_heuristic`.
+pub fn begin(index: Option<usize>) -> SelectionGuard {
+    SELECTED.with(|s| s.set(index));
+    SelectionGuard(())
+}
+
+/// Index of the current query's profile, if any.
+#[inline]
+pub fn selected() -> Option<usize> {
+    SELECTED.with(|s| s.get())
+}
+
+impl Graph {
+    /// Make the registry match `specs`: a profile already built with the same spec
+    /// (restored from graph.bin) is kept as is, the others are built; profiles no longer
+    /// listed are dropped. No-op without a contracted graph.
+    pub fn prepare_search_profiles(&mut self, specs: &[SearchProfileSpec]) {
+        let Some(cg) = self.contracted.as_ref() else {
+            return;
+        };
+        let mut existing = std::mem::take(&mut self.search_profiles);
+        let mut profiles = Vec::with_capacity(specs.len());
+        for spec in specs {
+            if let Some(i) = existing.iter().position(|p| p.spec == *spec) {
+                profiles.push(existing.swap_remove(i));
+                continue;
+            }
+            let tables = Landmarks::build_for(cg, spec.landmarks, spec.mode);
+            tracing::info!(
+                profile = %spec.name,
+                landmarks = tables.count(),
+                bytes = tables.heap_bytes(),
+                "search profile built"
+            );
+            profiles.push(SearchProfile { spec: spec.clone(), tables });
+        }
+        self.search_profiles = profiles;
+    }
+
+    pub fn search_profiles(&self) -> &[SearchProfile] {
+        &self.search_profiles
+    }
+
+    pub fn search_profile_index(&self, name: &str) -> Option<usize> {
+        self.search_profiles.iter().position(|p| p.spec.name == name)
+    }
+
+    /// Tables of the current query's profile when it applies to `mode`.
+    pub(super) fn selected_search_tables(&self, mode: RoutingMode) -> Option<&Landmarks> {
+        let profile = self.search_profiles.get(selected()?)?;
+        profile.applies_to(mode).then_some(&profile.tables)
+    }
+}
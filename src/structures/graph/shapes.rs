@@ -0,0 +1,381 @@
+//! Transit shape storage. Patterns of one route usually share their polyline (both
+//! halves of an overtaking split, every stop-skipping variant running the same
+//! streets), so each distinct polyline is stored once, delta-encoded in a single byte
+//! arena; patterns only keep its id. `build.geometry` can also thin the polylines out
+//! or drop them altogether for memory-constrained deployments.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+use crate::structures::LatLng;
+
+use super::Graph;
+
+/// Pattern shape id of a pattern without a polyline.
+pub const NO_SHAPE: u32 = u32::MAX;
+
+/// Stored coordinate resolution: 1e-7 degrees (about 1 cm), as in OSM.
+const COORD_SCALE: f64 = 1e7;
+
+/// How much transit shape geometry a build keeps (`build.geometry`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeometryLevel {
+    /// No shapes: transit legs are drawn stop to stop.
+    None,
+    /// Vertices within `build.geometry_tolerance_m` of the simplified line are dropped;
+    /// every stop's vertex is kept.
+    Simplified,
+    /// Shapes as published by the feeds.
+    #[default]
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ShapeSpan {
+    /// Byte offset into `ShapeArena::bytes`.
+    start: u32,
+    /// Encoded length in bytes.
+    len: u32,
+    points: u32,
+}
+
+/// Distinct polylines, each a run of zig-zag varint `(Δlat, Δlng)` pairs in 1e-7°,
+/// the first pair relative to `(0, 0)`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShapeArena {
+    bytes: Vec<u8>,
+    spans: Vec<ShapeSpan>,
+    /// Content hash → ids with that hash; rebuilt on the first intern after a load.
+    #[serde(skip, default)]
+    by_content: HashMap<u64, Vec<u32>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeStats {
+    /// Patterns with a polyline.
+    pub patterns: usize,
+    /// Distinct polylines stored.
+    pub distinct: usize,
+    /// Total vertices over the distinct polylines.
+    pub points: usize,
+    /// Encoded arena size.
+    pub bytes: usize,
+}
+
+fn push_varint(out: &mut Vec<u8>, v: i64) {
+    let mut z = ((v << 1) ^ (v >> 63)) as u64;
+    while z >= 0x80 {
+        out.push((z as u8) | 0x80);
+        z >>= 7;
+    }
+    out.push(z as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> i64 {
+    let (mut z, mut shift) = (0u64, 0);
+    loop {
+        let b = bytes[*pos];
+        *pos += 1;
+        z |= ((b & 0x7F) as u64) << shift;
+        if b < 0x80 {
+            break;
+        }
+        shift += 7;
+    }
+    (z >> 1) as i64 ^ -((z & 1) as i64)
+}
+
+fn encode(points: &[LatLng]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(points.len() * 4);
+    let (mut lat, mut lng) = (0i64, 0i64);
+    for p in points {
+        let (a, b) = (
+            (p.latitude * COORD_SCALE).round() as i64,
+            (p.longitude * COORD_SCALE).round() as i64,
+        );
+        push_varint(&mut out, a - lat);
+        push_varint(&mut out, b - lng);
+        (lat, lng) = (a, b);
+    }
+    out
+}
+
+fn content_hash(bytes: &[u8]) -> u64 {
+    let mut h = DefaultHasher::new();
+    bytes.hash(&mut h);
+    h.finish()
+}
+
+impl ShapeArena {
+    /// Id of `points`, storing them if no identical polyline is stored yet;
+    /// [`NO_SHAPE`] for an empty polyline.
+    pub fn intern(&mut self, points: &[LatLng]) -> u32 {
+        if points.is_empty() {
+            return NO_SHAPE;
+        }
+        if self.by_content.is_empty() && !self.spans.is_empty() {
+            for id in 0..self.spans.len() as u32 {
+                let h = content_hash(self.encoded(id));
+                self.by_content.entry(h).or_default().push(id);
+            }
+        }
+        let enc = encode(points);
+        let h = content_hash(&enc);
+        if let Some(&id) = self
+            .by_content
+            .get(&h)
+            .and_then(|ids| ids.iter().find(|&&id| self.encoded(id) == enc.as_slice()))
+        {
+            return id;
+        }
+        let id = self.spans.len() as u32;
+        self.spans.push(ShapeSpan {
+            start: self.bytes.len() as u32,
+            len: enc.len() as u32,
+            points: points.len() as u32,
+        });
+        self.bytes.extend_from_slice(&enc);
+        self.by_content.entry(h).or_default().push(id);
+        id
+    }
+
+    fn encoded(&self, id: u32) -> &[u8] {
+        let s = self.spans[id as usize];
+        &self.bytes[s.start as usize..(s.start + s.len) as usize]
+    }
+
+    /// Decoded polyline `id`; `None` for [`NO_SHAPE`] or an unknown id.
+    pub fn get(&self, id: u32) -> Option<Vec<LatLng>> {
+        let s = *self.spans.get(id as usize)?;
+        let bytes = self.encoded(id);
+        let mut out = Vec::with_capacity(s.points as usize);
+        let (mut pos, mut lat, mut lng) = (0, 0i64, 0i64);
+        for _ in 0..s.points {
+            lat += read_varint(bytes, &mut pos);
+            lng += read_varint(bytes, &mut pos);
+            out.push(LatLng {
+                latitude: lat as f64 / COORD_SCALE,
+                longitude: lng as f64 / COORD_SCALE,
+            });
+        }
+        Some(out)
+    }
+
+    /// Distinct polylines stored.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Encoded size in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+/// Perpendicular distance (m) of `p` from segment `a`–`b`, on a local equirectangular
+/// projection (shapes are short enough for it).
+fn offset_m(p: LatLng, a: LatLng, b: LatLng) -> f64 {
+    let k = a.latitude.to_radians().cos();
+    let xy = |q: LatLng| ((q.longitude - a.longitude) * k, q.latitude - a.latitude);
+    let ((px, py), (bx, by)) = (xy(p), xy(b));
+    let len2 = bx * bx + by * by;
+    let t = if len2 > 0.0 { ((px * bx + py * by) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    let (dx, dy) = (px - t * bx, py - t * by);
+    (dx * dx + dy * dy).sqrt() * 111_320.0
+}
+
+fn rdp(points: &[LatLng], a: usize, b: usize, tolerance_m: f64, keep: &mut [bool]) {
+    if b <= a + 1 {
+        return;
+    }
+    let (far, dist) = (a + 1..b)
+        .map(|i| (i, offset_m(points[i], points[a], points[b])))
+        .fold((a, 0.0), |best, cur| if cur.1 > best.1 { cur } else { best });
+    if dist > tolerance_m {
+        keep[far] = true;
+        rdp(points, a, far, tolerance_m, keep);
+        rdp(points, far, b, tolerance_m, keep);
+    }
+}
+
+/// Ramer–Douglas–Peucker over `points`, never dropping the `pinned` vertices; returns
+/// the kept points and, per input vertex, its index among them (or the nearest kept
+/// one before it).
+fn simplify(points: &[LatLng], pinned: &[u32], tolerance_m: f64) -> (Vec<LatLng>, Vec<u32>) {
+    let n = points.len();
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[n - 1] = true;
+    for &i in pinned {
+        if let Some(k) = keep.get_mut(i as usize) {
+            *k = true;
+        }
+    }
+    let anchors: Vec<usize> = (0..n).filter(|&i| keep[i]).collect();
+    for w in anchors.windows(2) {
+        rdp(points, w[0], w[1], tolerance_m, &mut keep);
+    }
+    let mut kept = Vec::new();
+    let mut remap = Vec::with_capacity(n);
+    for (i, &p) in points.iter().enumerate() {
+        if keep[i] {
+            kept.push(p);
+        }
+        remap.push(kept.len().saturating_sub(1) as u32);
+    }
+    (kept, remap)
+}
+
+impl Graph {
+    /// Re-store every pattern shape at `level` into a fresh arena (which also drops
+    /// polylines no pattern uses any more, e.g. ones overwritten by SNCB's railway
+    /// shapes). `Simplified` keeps each pattern's stop vertices and re-points its stop
+    /// indices at the thinned polyline. Run after `build_raptor_index`.
+    pub fn apply_geometry_level(&mut self, level: GeometryLevel, tolerance_m: f64) -> ShapeStats {
+        let r = &mut self.raptor;
+        let old = std::mem::take(&mut self.shapes);
+        if level == GeometryLevel::None {
+            r.transit_pattern_shape_ids.fill(NO_SHAPE);
+            r.transit_pattern_shape_stop_idx.iter_mut().for_each(Vec::clear);
+            return self.shape_stats();
+        }
+
+        // Stop vertices of every pattern sharing a polyline stay put.
+        let mut pinned: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (p, &id) in r.transit_pattern_shape_ids.iter().enumerate() {
+            if id != NO_SHAPE {
+                pinned.entry(id).or_default().extend(&r.transit_pattern_shape_stop_idx[p]);
+            }
+        }
+        let mut ids: Vec<u32> = pinned.keys().copied().collect();
+        ids.sort_unstable();
+        let mut moved: HashMap<u32, (u32, Vec<u32>)> = HashMap::with_capacity(ids.len());
+        for id in ids {
+            let Some(points) = old.get(id) else { continue };
+            let (points, remap) = match level {
+                GeometryLevel::Simplified => simplify(&points, &pinned[&id], tolerance_m),
+                _ => {
+                    let identity = (0..points.len() as u32).collect();
+                    (points, identity)
+                }
+            };
+            moved.insert(id, (self.shapes.intern(&points), remap));
+        }
+        for (p, id) in r.transit_pattern_shape_ids.iter_mut().enumerate() {
+            let Some((new_id, remap)) = moved.get(id) else {
+                *id = NO_SHAPE;
+                continue;
+            };
+            *id = *new_id;
+            for s in r.transit_pattern_shape_stop_idx[p].iter_mut() {
+                *s = remap.get(*s as usize).copied().unwrap_or(*s);
+            }
+        }
+        self.shape_stats()
+    }
+
+    pub fn shape_stats(&self) -> ShapeStats {
+        let patterns = self
+            .raptor
+            .transit_pattern_shape_ids
+            .iter()
+            .filter(|&&id| id != NO_SHAPE)
+            .count();
+        ShapeStats {
+            patterns,
+            distinct: self.shapes.len(),
+            points: self.shapes.spans.iter().map(|s| s.points as usize).sum(),
+            bytes: self.shapes.byte_len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ll(latitude: f64, longitude: f64) -> LatLng {
+        LatLng { latitude, longitude }
+    }
+
+    #[test]
+    fn arena_round_trips_and_stores_identical_polylines_once() {
+        let mut arena = ShapeArena::default();
+        let line = [ll(50.8503, 4.3517), ll(50.8466, 4.3528), ll(-33.8688, 151.2093)];
+        let a = arena.intern(&line);
+        let bytes = arena.byte_len();
+        assert_eq!(arena.intern(&line), a);
+        assert_eq!(arena.byte_len(), bytes, "a repeat costs nothing");
+        let b = arena.intern(&line[..2]);
+        assert_ne!(a, b);
+        assert_eq!(arena.intern(&[]), NO_SHAPE);
+        assert_eq!(arena.len(), 2);
+
+        let back = arena.get(a).unwrap();
+        assert_eq!(back.len(), 3);
+        for (p, q) in back.iter().zip(&line) {
+            assert!((p.latitude - q.latitude).abs() < 1e-7 && (p.longitude - q.longitude).abs() < 1e-7);
+        }
+        assert!(arena.get(NO_SHAPE).is_none());
+    }
+
+    #[test]
+    fn arena_dedups_after_a_serde_round_trip() {
+        let mut arena = ShapeArena::default();
+        let line = [ll(50.0, 4.0), ll(50.001, 4.001)];
+        let id = arena.intern(&line);
+        let mut restored: ShapeArena =
+            postcard::from_bytes(&postcard::to_allocvec(&arena).unwrap()).unwrap();
+        assert_eq!(restored.intern(&line), id);
+        assert_eq!(restored.len(), 1);
+    }
+
+    #[test]
+    fn simplify_drops_collinear_vertices_but_keeps_stops() {
+        // Straight north-bound line, one vertex every ~11 m; stops at 0, 2 and 5.
+        let line: Vec<LatLng> = (0..6).map(|i| ll(50.0 + i as f64 * 1e-4, 4.0)).collect();
+        let (kept, remap) = simplify(&line, &[0, 2, 5], 1.0);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(remap, vec![0, 0, 1, 1, 1, 2]);
+
+        // A 30 m bulge survives a 5 m tolerance.
+        let mut bent = line.clone();
+        bent[3].longitude += 0.0004;
+        let (kept, _) = simplify(&bent, &[0, 5], 5.0);
+        assert!(kept.iter().any(|p| p.longitude > 4.0));
+    }
+
+    fn two_pattern_graph() -> Graph {
+        let mut g = Graph::new();
+        let line: Vec<LatLng> = (0..6).map(|i| ll(50.0 + i as f64 * 1e-4, 4.0)).collect();
+        g.push_transit_pattern_shape(line.clone(), vec![0, 2, 5]);
+        g.push_transit_pattern_shape(line, vec![0, 5]);
+        g
+    }
+
+    #[test]
+    fn geometry_levels_keep_thin_or_drop_shapes() {
+        let mut g = two_pattern_graph();
+        assert_eq!(g.shape_stats().distinct, 1, "both patterns share one polyline");
+
+        let full = g.apply_geometry_level(GeometryLevel::Full, 5.0);
+        assert_eq!((full.patterns, full.distinct, full.points), (2, 1, 6));
+
+        let thin = g.apply_geometry_level(GeometryLevel::Simplified, 5.0);
+        assert_eq!((thin.patterns, thin.distinct, thin.points), (2, 1, 3));
+        let (pts, idx) = g.get_pattern_shape(0).unwrap();
+        assert_eq!((pts.len(), idx), (3, &[0u32, 1, 2][..]));
+        assert_eq!(g.get_pattern_shape(1).unwrap().1, &[0u32, 2]);
+
+        let none = g.apply_geometry_level(GeometryLevel::None, 5.0);
+        assert_eq!((none.patterns, none.distinct, none.bytes), (0, 0, 0));
+        assert!(g.get_pattern_shape(0).is_none());
+    }
+}
@@ -0,0 +1,146 @@
+//! External ids that survive a rebuild. Positional ids (`maas:stop:<n>`,
+//! `maas:route:<n>`, `NodeID`) are only valid on the graph that produced them; a
+//! stable id names the source record instead: the GTFS id (namespaced when its feed
+//! has a namespace) for stops, routes and trips, the OSM eid (`map#osm#123`) for
+//! street nodes.
+
+use crate::ingestion::gtfs::{RouteId, TripId};
+use crate::structures::NodeID;
+
+use super::Graph;
+
+pub const STABLE_STOP_PREFIX: &str = "gtfs:stop:";
+pub const STABLE_ROUTE_PREFIX: &str = "gtfs:route:";
+pub const STABLE_TRIP_PREFIX: &str = "gtfs:trip:";
+
+/// What a stable (or current positional) id names on this graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StableRef {
+    /// Compact stop index.
+    Stop(usize),
+    Route(RouteId),
+    Trip(TripId),
+    /// Street node.
+    Node(NodeID),
+}
+
+impl Graph {
+    pub fn stop_stable_id(&self, stop: usize) -> Option<String> {
+        self.stop_id_str(stop).map(|id| format!("{STABLE_STOP_PREFIX}{id}"))
+    }
+
+    /// `None` on graphs built before GTFS route ids were kept.
+    pub fn route_stable_id(&self, route: RouteId) -> Option<String> {
+        self.raptor
+            .transit_route_ids
+            .get(route.0 as usize)
+            .map(|id| format!("{STABLE_ROUTE_PREFIX}{id}"))
+    }
+
+    pub fn trip_stable_id(&self, trip: TripId) -> Option<String> {
+        self.trip_id_str(trip).map(|id| format!("{STABLE_TRIP_PREFIX}{id}"))
+    }
+
+    /// The stop's stable id for a transit stop node, else its OSM eid.
+    pub fn node_stable_id(&self, node: NodeID) -> Option<String> {
+        match self.compact_stop_of_node(node) {
+            Some(stop) => self.stop_stable_id(stop),
+            None => self.node_eid(node),
+        }
+    }
+
+    /// Stop by stable id, `maas:stop:<n>` or bare GTFS `stop_id`.
+    pub fn resolve_stop_id(&self, id: &str) -> Option<usize> {
+        if let Some(n) = id.strip_prefix("maas:stop:") {
+            let n = n.parse::<usize>().ok()?;
+            return (n < self.raptor.transit_stop_to_node.len()).then_some(n);
+        }
+        let id = id.strip_prefix(STABLE_STOP_PREFIX).unwrap_or(id);
+        self.stop_index_of(id)
+            .or_else(|| self.gtfs_stop_node_by_id(id).and_then(|n| self.compact_stop_of_node(n)))
+            .or_else(|| (0..self.raptor.transit_stop_to_node.len()).find(|&c| self.stop_id_str(c) == Some(id)))
+    }
+
+    /// Trip by stable id or bare GTFS `trip_id`.
+    pub fn resolve_trip_id(&self, id: &str) -> Option<TripId> {
+        self.trip_index_of(id.strip_prefix(STABLE_TRIP_PREFIX).unwrap_or(id))
+    }
+
+    /// Any stable id, or a positional `maas:stop:` / `maas:route:` id of this graph.
+    /// Bare GTFS ids are ambiguous between kinds and not accepted here.
+    pub fn resolve_stable_id(&self, id: &str) -> Option<StableRef> {
+        if id.starts_with(STABLE_STOP_PREFIX) || id.starts_with("maas:stop:") {
+            self.resolve_stop_id(id).map(StableRef::Stop)
+        } else if id.starts_with(STABLE_ROUTE_PREFIX) || id.starts_with("maas:route:") {
+            self.route_index_of(id).map(StableRef::Route)
+        } else if id.starts_with(STABLE_TRIP_PREFIX) {
+            self.resolve_trip_id(id).map(StableRef::Trip)
+        } else {
+            self.get_id(id)
+                .copied()
+                .filter(|&n| self.compact_stop_of_node(n).is_none())
+                .map(StableRef::Node)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::gtfs::{AgencyId, RouteInfo, ServiceId, TripInfo};
+    use crate::structures::{LatLng, NodeData, OsmNodeData, TransitStopData};
+
+    fn graph() -> (Graph, NodeID, NodeID) {
+        let mut g = Graph::new();
+        let street = g.add_node(NodeData::OsmNode(OsmNodeData {
+            eid: "map#osm#42".into(),
+            lat_lng: LatLng { latitude: 50.0, longitude: 4.0 },
+        }));
+        let stop = g.add_node(NodeData::TransitStop(TransitStopData {
+            name: "Central".into(),
+            id: "stib:8042".into(),
+            lat_lng: LatLng { latitude: 50.001, longitude: 4.0 },
+            accessibility: gtfs_structures::Availability::Available,
+            platform_code: None,
+            code: None,
+            parent_station: None,
+        }));
+        g.add_transit_routes(vec![RouteInfo {
+            route_short_name: "1".into(),
+            route_long_name: String::new(),
+            route_type: gtfs_structures::RouteType::Subway,
+            agency_id: AgencyId(0),
+            route_color: None,
+            route_text_color: None,
+        }]);
+        g.add_transit_route_ids(vec!["stib:1".into()]);
+        g.add_transit_trips(vec![TripInfo {
+            trip_headsign: None,
+            route_id: RouteId(0),
+            service_id: ServiceId(0),
+            bikes_allowed: None,
+        }]);
+        g.add_transit_trip_ids(vec!["stib:T1".into()]);
+        g.build_raptor_index();
+        (g, street, stop)
+    }
+
+    #[test]
+    fn stable_ids_round_trip_through_the_resolver() {
+        let (g, street, stop) = graph();
+        let compact = g.compact_stop_of_node(stop).unwrap();
+        assert_eq!(g.node_stable_id(stop).as_deref(), Some("gtfs:stop:stib:8042"));
+        assert_eq!(g.node_stable_id(street).as_deref(), Some("map#osm#42"));
+        assert_eq!(g.route_stable_id(RouteId(0)).as_deref(), Some("gtfs:route:stib:1"));
+        assert_eq!(g.trip_stable_id(TripId(0)).as_deref(), Some("gtfs:trip:stib:T1"));
+
+        assert_eq!(g.resolve_stable_id("gtfs:stop:stib:8042"), Some(StableRef::Stop(compact)));
+        assert_eq!(g.resolve_stable_id(&format!("maas:stop:{compact}")), Some(StableRef::Stop(compact)));
+        assert_eq!(g.resolve_stable_id("gtfs:route:stib:1"), Some(StableRef::Route(RouteId(0))));
+        assert_eq!(g.resolve_stable_id("gtfs:trip:stib:T1"), Some(StableRef::Trip(TripId(0))));
+        assert_eq!(g.resolve_stable_id("map#osm#42"), Some(StableRef::Node(street)));
+        assert_eq!(g.resolve_stable_id("gtfs:stop:stib:9999"), None);
+        assert_eq!(g.resolve_stable_id("stib:8042"), None, "bare GTFS ids are ambiguous");
+        assert_eq!(g.resolve_stop_id("stib:8042"), Some(compact));
+    }
+}
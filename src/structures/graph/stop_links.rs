@@ -0,0 +1,270 @@
+//! Street-network lengths for GTFS stop links. A stop is linked to its nearest
+//! walkable node with the straight-line distance as the edge length, which hides
+//! barriers: the nearest node can sit across a river or a rail yard from the street
+//! the stop is actually on. This pass measures the walk from the stop's own street
+//! segment to the linked node and lengthens (or moves) the link accordingly.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::structures::{EdgeData, NodeData, NodeID};
+
+use super::Graph;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StopLinkStats {
+    /// Street links examined.
+    pub checked: usize,
+    /// Links whose length grew to the street-network walk.
+    pub lengthened: usize,
+    /// Links moved to the stop's own segment because the old node was out of reach.
+    pub relinked: usize,
+}
+
+/// The walkable street segment closest to a stop.
+struct StopSegment {
+    a: NodeID,
+    b: NodeID,
+    /// Stop → closest point on the segment (m).
+    perp_m: f64,
+    /// Closest point → `a` / → `b` along the segment (m).
+    to_a_m: f64,
+    to_b_m: f64,
+}
+
+impl Graph {
+    /// Recompute every stop → street link against the street network: the closest
+    /// walkable segment within `radius_m` of the stop (via a fresh edge index), then a
+    /// foot Dijkstra bounded by `budget_m` from the linked node. Platform-matched links (length 0) and
+    /// stop-to-stop edges are left alone. Run on raw metres, before
+    /// `bake_connector_lengths`; `budget_m == 0` disables.
+    pub fn refine_stop_links(&mut self, radius_m: f64, budget_m: usize) -> StopLinkStats {
+        let mut stats = StopLinkStats::default();
+        if budget_m == 0 {
+            return stats;
+        }
+        self.build_edge_index();
+        let mut updates: Vec<(NodeID, NodeID, NodeID, usize)> = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let NodeData::TransitStop(stop) = node else { continue };
+            let stop_id = NodeID(i);
+            let links: Vec<(NodeID, usize)> = self.edges[i]
+                .iter()
+                .filter_map(|e| match e {
+                    EdgeData::Street(s)
+                        if s.partial
+                            && matches!(self.nodes.get(s.destination.0), Some(NodeData::OsmNode(_))) =>
+                    {
+                        Some((s.destination, s.length))
+                    }
+                    _ => None,
+                })
+                .collect();
+            if links.iter().any(|&(_, len)| len == 0) {
+                continue;
+            }
+            for (linked, length) in links {
+                stats.checked += 1;
+                let Some(seg) = self.stop_segment(stop.lat_lng.latitude, stop.lat_lng.longitude, radius_m)
+                else {
+                    continue;
+                };
+                if seg.a == linked || seg.b == linked {
+                    continue;
+                }
+                let dist = self.foot_distances(&[(linked, 0)], &[seg.a, seg.b], budget_m);
+                let via = |n: NodeID, along: f64| dist.get(&n).map(|&d| seg.perp_m + along + d as f64);
+                let walk = [via(seg.a, seg.to_a_m), via(seg.b, seg.to_b_m)]
+                    .into_iter()
+                    .flatten()
+                    .min_by(f64::total_cmp);
+                match walk {
+                    Some(w) if w.round() as usize > length => {
+                        updates.push((stop_id, linked, linked, w.round() as usize));
+                        stats.lengthened += 1;
+                    }
+                    Some(_) => {}
+                    None => {
+                        let (target, along) = if seg.to_a_m <= seg.to_b_m {
+                            (seg.a, seg.to_a_m)
+                        } else {
+                            (seg.b, seg.to_b_m)
+                        };
+                        let len = ((seg.perp_m + along).round() as usize).max(1);
+                        updates.push((stop_id, linked, target, len));
+                        stats.relinked += 1;
+                    }
+                }
+            }
+        }
+        for (stop, old, new, length) in updates {
+            self.retarget_link(stop, old, new, length);
+        }
+        stats
+    }
+
+    fn stop_segment(&self, lat: f64, lon: f64, radius_m: f64) -> Option<StopSegment> {
+        let is_street = |n: NodeID| matches!(self.nodes.get(n.0), Some(NodeData::OsmNode(_)));
+        let (s, perp_m) = self.edge_index.nearest_usable(lat, lon, radius_m, |s| {
+            s.foot && !s.partial && is_street(s.origin) && is_street(s.destination)
+        })?;
+        let (_, t) =
+            Self::project_point(lat, lon, self.nodes[s.origin.0].loc(), self.nodes[s.destination.0].loc());
+        Some(StopSegment {
+            a: s.origin,
+            b: s.destination,
+            perp_m,
+            to_a_m: t * s.length as f64,
+            to_b_m: (1.0 - t) * s.length as f64,
+        })
+    }
+
+    /// Foot distances (raw metres) from `origins` (node, starting offset) to each of
+    /// `targets` within `budget_m`, over street nodes only (never through a stop).
+    pub(super) fn foot_distances(
+        &self,
+        origins: &[(NodeID, usize)],
+        targets: &[NodeID],
+        budget_m: usize,
+    ) -> HashMap<NodeID, usize> {
+        let mut best: HashMap<NodeID, usize> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        for &(origin, offset) in origins {
+            if offset <= budget_m && best.get(&origin).is_none_or(|&d| offset < d) {
+                best.insert(origin, offset);
+                heap.push(Reverse((offset, origin)));
+            }
+        }
+        let mut found = HashMap::new();
+        while let Some(Reverse((dist, node))) = heap.pop() {
+            if best.get(&node).is_some_and(|&d| d < dist) {
+                continue;
+            }
+            if targets.contains(&node) {
+                found.insert(node, dist);
+                if found.len() == targets.len() {
+                    break;
+                }
+            }
+            for e in &self.edges[node.0] {
+                let EdgeData::Street(s) = e else { continue };
+                if !s.foot || !matches!(self.nodes.get(s.destination.0), Some(NodeData::OsmNode(_))) {
+                    continue;
+                }
+                let nd = dist + s.length;
+                if nd <= budget_m && best.get(&s.destination).is_none_or(|&d| nd < d) {
+                    best.insert(s.destination, nd);
+                    heap.push(Reverse((nd, s.destination)));
+                }
+            }
+        }
+        found
+    }
+
+    /// Point the stop ↔ `old` link pair at `new` with `length`.
+    fn retarget_link(&mut self, stop: NodeID, old: NodeID, new: NodeID, length: usize) {
+        for e in &mut self.edges[stop.0] {
+            if let EdgeData::Street(s) = e
+                && s.partial
+                && s.destination == old
+            {
+                s.destination = new;
+                s.length = length;
+            }
+        }
+        if old == new {
+            for e in &mut self.edges[old.0] {
+                if let EdgeData::Street(s) = e
+                    && s.partial
+                    && s.destination == stop
+                {
+                    s.length = length;
+                }
+            }
+            return;
+        }
+        let back = self.edges[old.0].iter().position(
+            |e| matches!(e, EdgeData::Street(s) if s.partial && s.destination == stop),
+        );
+        if let Some(pos) = back {
+            let EdgeData::Street(mut s) = self.edges[old.0].remove(pos) else { unreachable!() };
+            s.origin = new;
+            s.length = length;
+            self.edges[new.0].push(EdgeData::Street(s));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::gtfs::foot_connector_edge;
+    use crate::structures::{GraphBuilder, StreetEdgeData};
+
+    /// Two parallel streets 60 m apart (a river between them) joined by a bridge
+    /// `bridge_m` west of the stop. The stop sits 10 m off the south street but its
+    /// nearest node is on the north bank.
+    fn river(bridge_m: usize) -> (Graph, NodeID, NodeID, NodeID) {
+        let mut b = GraphBuilder::new().without_contraction();
+        // 0.00054° lat ≈ 60 m; 0.0014° lng ≈ 100 m at lat 50.
+        let s0 = b.add_street_node("s0", 50.0, 3.9986);
+        let s1 = b.add_street_node("s1", 50.0, 4.0014);
+        let n0 = b.add_street_node("n0", 50.00054, 4.0);
+        let n1 = b.add_street_node("n1", 50.00054, 3.9986);
+        b.add_street(s0, s1, 200);
+        b.add_street(n1, n0, 100);
+        b.add_street(n1, s0, bridge_m);
+        let stop = b.add_stop("S", 50.00009, 4.0);
+        let g = b.build();
+        (g, stop, n0, s0)
+    }
+
+    fn link(g: &mut Graph, stop: NodeID, node: NodeID, length: usize) {
+        g.add_edge(stop, foot_connector_edge(stop, node, length));
+        g.add_edge(node, foot_connector_edge(node, stop, length));
+    }
+
+    fn link_of(g: &Graph, stop: NodeID) -> Vec<(NodeID, usize)> {
+        g.out_edges(stop)
+            .iter()
+            .filter_map(|e| match e {
+                EdgeData::Street(StreetEdgeData { destination, length, .. }) => Some((*destination, *length)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn link_across_a_river_takes_the_bridge_length() {
+        let (mut g, stop, n0, _) = river(60);
+        link(&mut g, stop, n0, 50);
+        let stats = g.refine_stop_links(100.0, 1000);
+        assert_eq!(stats, StopLinkStats { checked: 1, lengthened: 1, relinked: 0 });
+        // 10 m to the south street, 100 m west to the bridge, 60 + 100 m on the north bank.
+        let (to, len) = link_of(&g, stop)[0];
+        assert_eq!(to, n0);
+        assert!((265..=275).contains(&len), "{len}");
+        assert!(g.out_edges(n0).iter().any(|e| matches!(e, EdgeData::Street(s) if s.destination == stop && s.length == len)));
+    }
+
+    #[test]
+    fn unreachable_link_moves_to_the_stops_own_street() {
+        let (mut g, stop, n0, s0) = river(5000);
+        link(&mut g, stop, n0, 50);
+        let stats = g.refine_stop_links(100.0, 1000);
+        assert_eq!(stats.relinked, 1);
+        let (to, len) = link_of(&g, stop)[0];
+        assert_eq!(to, s0);
+        assert!((105..=115).contains(&len), "{len}");
+        assert!(!g.out_edges(n0).iter().any(|e| matches!(e, EdgeData::Street(s) if s.destination == stop)));
+        assert!(g.out_edges(s0).iter().any(|e| matches!(e, EdgeData::Street(s) if s.destination == stop)));
+    }
+
+    #[test]
+    fn link_on_the_stops_own_segment_is_kept() {
+        let (mut g, stop, _, s0) = river(60);
+        link(&mut g, stop, s0, 110);
+        assert_eq!(g.refine_stop_links(100.0, 1000), StopLinkStats { checked: 1, ..Default::default() });
+        assert_eq!(link_of(&g, stop), vec![(s0, 110)]);
+    }
+}
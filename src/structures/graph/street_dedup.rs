@@ -0,0 +1,98 @@
+//! Parallel street edge merging. OSM ways sharing a node pair (a road mapped twice, a
+//! footway drawn over a street) each add an edge between the same two nodes; searches
+//! then relax the same hop twice and the merged permissions are spread over several
+//! edges. Run on the OSM graph, before island pruning and GTFS stop linking.
+
+use super::Graph;
+use crate::structures::{EdgeData, StreetEdgeData};
+
+impl Graph {
+    /// Merge street edges with the same origin and destination into one: the edge open to
+    /// the most modes is kept (the first on ties, so its class and attributes win), with
+    /// foot / bike / car access of every duplicate added to it. Returns the number of
+    /// edges removed.
+    pub fn dedup_street_edges(&mut self) -> usize {
+        let modes = |s: &StreetEdgeData| [s.foot, s.bike, s.car].iter().filter(|&&m| m).count();
+        let mut removed = 0;
+        for out in &mut self.edges {
+            // Per kept street edge: its own mode count, before merging access into it.
+            let mut widest: Vec<usize> = Vec::with_capacity(out.len());
+            let mut kept: Vec<EdgeData> = Vec::with_capacity(out.len());
+            for edge in out.drain(..) {
+                let EdgeData::Street(street) = edge else {
+                    widest.push(usize::MAX);
+                    kept.push(edge);
+                    continue;
+                };
+                let twin = kept.iter_mut().zip(&mut widest).find_map(|(e, w)| match e {
+                    EdgeData::Street(s) if s.destination == street.destination => Some((s, w)),
+                    _ => None,
+                });
+                let Some((twin, twin_modes)) = twin else {
+                    widest.push(modes(&street));
+                    kept.push(EdgeData::Street(street));
+                    continue;
+                };
+                let (foot, bike, car) = (twin.foot | street.foot, twin.bike | street.bike, twin.car | street.car);
+                if modes(&street) > *twin_modes {
+                    *twin_modes = modes(&street);
+                    *twin = street;
+                }
+                (twin.foot, twin.bike, twin.car) = (foot, bike, car);
+                removed += 1;
+            }
+            *out = kept;
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structures::cost::VarGen;
+    use crate::structures::{BikeAttrs, EdgeData, Graph, HighwayClass, LatLng, NodeData, OsmNodeData, StreetEdgeData};
+
+    fn edge(o: usize, d: usize, foot: bool, car: bool, highway: HighwayClass) -> EdgeData {
+        EdgeData::Street(StreetEdgeData {
+            origin: crate::structures::NodeID(o),
+            destination: crate::structures::NodeID(d),
+            partial: false,
+            length: 100,
+            foot,
+            bike: false,
+            car,
+            attrs: BikeAttrs { highway, ..BikeAttrs::road_default() },
+            elev_delta: 0,
+            surface_speed: 100,
+            var_gen: VarGen::NONE,
+        })
+    }
+
+    #[test]
+    fn parallel_edges_merge_their_access_into_the_widest_one() {
+        let mut g = Graph::new();
+        let ids: Vec<_> = (0..3)
+            .map(|i| {
+                g.add_node(NodeData::OsmNode(OsmNodeData {
+                    eid: format!("map#osm#{i}"),
+                    lat_lng: LatLng { latitude: 50.0, longitude: 4.0 + i as f64 * 0.001 },
+                }))
+            })
+            .collect();
+        g.add_edge(ids[0], edge(0, 1, true, false, HighwayClass::Footway));
+        g.add_edge(ids[0], edge(0, 1, false, true, HighwayClass::Residential));
+        g.add_edge(ids[0], edge(0, 1, true, true, HighwayClass::Residential));
+        g.add_edge(ids[0], edge(0, 2, true, false, HighwayClass::Footway));
+        g.add_edge(ids[1], edge(1, 0, true, false, HighwayClass::Footway));
+
+        assert_eq!(g.dedup_street_edges(), 2);
+        let out = g.out_edges(ids[0]);
+        assert_eq!(out.len(), 2);
+        let EdgeData::Street(merged) = &out[0] else { unreachable!() };
+        assert_eq!(merged.destination, ids[1]);
+        assert!(merged.foot && merged.car && !merged.bike);
+        assert_eq!(merged.attrs.highway, HighwayClass::Residential, "the widest edge is kept");
+        assert_eq!(g.out_edges(ids[1]).len(), 1, "opposite directions are not duplicates");
+        assert_eq!(g.dedup_street_edges(), 0);
+    }
+}
@@ -46,8 +46,9 @@ impl Graph {
         let n = plan.legs.len();
         let has_transit = plan.legs.iter().any(|l| matches!(l, PlanLeg::Transit(_)));
         if !has_transit {
+            let old_end = plan.end;
             for i in 0..n {
-                if let PlanLeg::Walk(w) = &plan.legs[i] {
+                if let Some(w) = plan.legs[i].as_street() {
                     let mode = mode_of(w.street_mode);
                     let role = if terminal_deadline {
                         LegRole::Deadline
@@ -57,15 +58,20 @@ impl Graph {
                     let opts =
                         options(self, w.from.node_id, w.to.node_id, mode, role, bike, memo);
                     if let Some(new) = self.rebuild_leg(w, &opts, mode, bike, None) {
-                        plan.legs[i] = PlanLeg::Walk(new);
+                        plan.legs[i] = PlanLeg::street(new);
                     }
                 }
             }
+            // A rebuilt leg keeps its start but takes the chosen option's duration, so
+            // the plan's end follows the last leg.
+            if let Some(last) = plan.legs.last().and_then(PlanLeg::as_street) {
+                shift_arrival(plan, last.end, old_end);
+            }
             return;
         }
-        if let Some(PlanLeg::Walk(w)) = plan.legs.first() {
+        if let Some(w) = plan.legs.first().and_then(PlanLeg::as_street) {
             if w.from.node_id == origin {
-                let board = leg_start(&plan.legs[1]);
+                let board = plan.legs[1].span().0;
                 let mode = mode_of(w.street_mode);
                 let opts = options(
                     self,
@@ -79,13 +85,18 @@ impl Graph {
                 if let Some(new) =
                     self.rebuild_leg(w, &opts, mode, bike, Some((board, plan.start)))
                 {
-                    plan.legs[0] = PlanLeg::Walk(new);
+                    // The leg now ends at boarding, so it may start later than the
+                    // search's earliest departure; the plan starts with it.
+                    plan.start = new.start;
+                    plan.legs[0] = PlanLeg::street(new);
                 }
             }
         }
         if n >= 2 {
-            if let Some(PlanLeg::Walk(w)) = plan.legs.last() {
+            if let Some(w) = plan.legs.last().and_then(PlanLeg::as_street) {
                 if w.to.node_id == destination {
+                    // Egress starts once transit is routed, not at departure: free flow.
+                    let _free_flow = super::rush_hour::begin(None);
                     let alight = w.start;
                     let old_end = w.end;
                     let mode = mode_of(w.street_mode);
@@ -107,6 +118,7 @@ impl Graph {
                             stop_position: None,
                             arrival: Some(end),
                             departure: None,
+                            language: None,
                         };
                         let steps =
                             self.street_steps(&chosen.nodes, &chosen.edges, mode, bike, alight, to);
@@ -121,16 +133,8 @@ impl Graph {
                         new.steps = steps;
                         new.alternatives = opts;
                         new.leave_by = None;
-                        *plan.legs.last_mut().unwrap() = PlanLeg::Walk(new);
-                        // Shift the arrival timeline by the egress delta so the transit
-                        // delay-CDF spread in `arrival_distribution` is preserved.
-                        let delta = end as i64 - old_end as i64;
-                        plan.end = end;
-                        plan.expected_end =
-                            (plan.expected_end as i64 + delta).max(end as i64) as u32;
-                        for sc in &mut plan.arrival_distribution {
-                            sc.time = (sc.time as i64 + delta).max(0) as u32;
-                        }
+                        *plan.legs.last_mut().unwrap() = PlanLeg::street(new);
+                        shift_arrival(plan, end, old_end);
                     }
                 }
             }
@@ -167,6 +171,7 @@ impl Graph {
             stop_position: None,
             arrival: Some(end),
             departure: None,
+            language: None,
         };
         leg.steps = self.street_steps(&chosen.nodes, &chosen.edges, mode, bike, start, to);
         leg.from = PlanPlace {
@@ -174,6 +179,7 @@ impl Graph {
             stop_position: None,
             arrival: None,
             departure: Some(start),
+            language: None,
         };
         leg.to = to;
         leg.start = start;
@@ -198,10 +204,14 @@ fn mode_of(m: Mode) -> RoutingMode {
     }
 }
 
-fn leg_start(l: &PlanLeg) -> u32 {
-    match l {
-        PlanLeg::Walk(w) => w.start,
-        PlanLeg::Transit(t) => t.start,
+/// Moves the plan's end to `end`, shifting the whole arrival timeline by the same
+/// delta so the transit delay-CDF spread in `arrival_distribution` is preserved.
+fn shift_arrival(plan: &mut Plan, end: u32, old_end: u32) {
+    let delta = end as i64 - old_end as i64;
+    plan.end = end;
+    plan.expected_end = (plan.expected_end as i64 + delta).max(end as i64) as u32;
+    for sc in &mut plan.arrival_distribution {
+        sc.time = (sc.time as i64 + delta).max(0) as u32;
     }
 }
 
@@ -238,7 +248,7 @@ mod tests {
     use super::*;
     use crate::structures::cost::VarGen;
     use crate::structures::plan::{
-        ArrivalScenario, Plan, PlanLeg, PlanPlace, PlanTransitLeg, PlanWalkLeg,
+        ArrivalScenario, Plan, PlanLeg, PlanPlace, PlanTransitLeg, PlanWalkLeg, RealtimeState,
     };
     use crate::structures::{
         BikeAttrs, EdgeData, HighwayClass, LatLng, Mode, NodeData, NodeID, OsmNodeData,
@@ -301,6 +311,7 @@ mod tests {
             stop_position: None,
             arrival: arr,
             departure: dep,
+            language: None,
         };
         PlanWalkLeg {
             from: place(from, Some(start), None),
@@ -380,17 +391,21 @@ mod tests {
             scheduled_start: 600,
             scheduled_end: 900,
             realtime: false,
+            realtime_state: RealtimeState::Scheduled,
+            occupancy: None,
             from: PlanPlace {
                 node_id: s,
                 stop_position: None,
                 arrival: None,
                 departure: Some(600),
+                language: None,
             },
             to: PlanPlace {
                 node_id: s,
                 stop_position: None,
                 arrival: Some(900),
                 departure: None,
+                language: None,
             },
             steps: vec![],
             geometry: vec![],
@@ -403,9 +418,11 @@ mod tests {
             following_margin_secs: None,
             bikes_allowed: None,
             time_shift: 0,
+            waiting_time: 0,
+            display: Default::default(),
         };
         let plan = Plan {
-            legs: vec![PlanLeg::Walk(access), PlanLeg::Transit(transit)],
+            legs: vec![PlanLeg::street(access), PlanLeg::Transit(transit)],
             start: 500,
             end: 900,
             mode: Mode::WalkTransit,
@@ -416,6 +433,9 @@ mod tests {
             }],
             expected_end: 900,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         };
         let mut plans = vec![plan];
         g.enrich_street_legs(
@@ -425,7 +445,7 @@ mod tests {
             &bike,
             false,
         );
-        let PlanLeg::Walk(acc) = &plans[0].legs[0] else {
+        let Some(acc) = plans[0].legs[0].as_street() else {
             panic!()
         };
         assert!(
@@ -437,6 +457,7 @@ mod tests {
             acc.end, 600,
             "access leg still ends at the fixed boarding time"
         );
+        assert_eq!(plans[0].start, acc.start, "plan starts with its rebuilt access leg");
     }
 
     fn transit_leg(from: NodeID, to: NodeID, start: u32, end: u32) -> PlanTransitLeg {
@@ -448,17 +469,21 @@ mod tests {
             scheduled_start: start,
             scheduled_end: end,
             realtime: false,
+            realtime_state: RealtimeState::Scheduled,
+            occupancy: None,
             from: PlanPlace {
                 node_id: from,
                 stop_position: None,
                 arrival: None,
                 departure: Some(start),
+                language: None,
             },
             to: PlanPlace {
                 node_id: to,
                 stop_position: None,
                 arrival: Some(end),
                 departure: None,
+                language: None,
             },
             steps: vec![],
             geometry: vec![],
@@ -471,6 +496,8 @@ mod tests {
             following_margin_secs: None,
             bikes_allowed: None,
             time_shift: 0,
+            waiting_time: 0,
+            display: Default::default(),
         }
     }
 
@@ -483,7 +510,7 @@ mod tests {
         let transit = transit_leg(s, o, 1000, alight);
         let egress = walk_leg(o, s, alight, alight + 90);
         let plan = Plan {
-            legs: vec![PlanLeg::Transit(transit), PlanLeg::Walk(egress)],
+            legs: vec![PlanLeg::Transit(transit), PlanLeg::street(egress)],
             start: 1000,
             end: alight + 90,
             mode: Mode::WalkTransit,
@@ -494,6 +521,9 @@ mod tests {
             }],
             expected_end: alight + 90,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         };
         let mut plans = vec![plan];
         g.enrich_street_legs(
@@ -503,7 +533,7 @@ mod tests {
             &bike,
             false,
         );
-        let PlanLeg::Walk(eg) = plans[0].legs.last().unwrap() else {
+        let Some(eg) = plans[0].legs.last().unwrap().as_street() else {
             panic!()
         };
         assert!(
@@ -541,7 +571,7 @@ mod tests {
         let bike = g.default_bike_cost();
         let leg = walk_leg(o, s, 300, 400);
         let plan = Plan {
-            legs: vec![PlanLeg::Walk(leg)],
+            legs: vec![PlanLeg::street(leg)],
             start: 300,
             end: 400,
             mode: Mode::Walk,
@@ -552,6 +582,9 @@ mod tests {
             }],
             expected_end: 400,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         };
         let mut plans = vec![plan];
         g.enrich_street_legs(
@@ -561,7 +594,7 @@ mod tests {
             &bike,
             false,
         );
-        let PlanLeg::Walk(w) = &plans[0].legs[0] else {
+        let Some(w) = plans[0].legs[0].as_street() else {
             panic!()
         };
         assert!(
@@ -579,7 +612,7 @@ mod tests {
         let bike = g.default_bike_cost();
         let leg = bike_leg(o, s, 300, 400);
         let plan = Plan {
-            legs: vec![PlanLeg::Walk(leg)],
+            legs: vec![PlanLeg::street(leg)],
             start: 300,
             end: 400,
             mode: Mode::Bike,
@@ -590,6 +623,9 @@ mod tests {
             }],
             expected_end: 400,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         };
         let mut plans = vec![plan];
         g.enrich_street_legs(
@@ -599,7 +635,7 @@ mod tests {
             &bike,
             false,
         );
-        let PlanLeg::Walk(w) = &plans[0].legs[0] else {
+        let Some(w) = plans[0].legs[0].as_street() else {
             panic!()
         };
         assert!(
@@ -619,7 +655,7 @@ mod tests {
         let access = bike_leg(o, s, 500, 600);
         let transit = transit_leg(s, s, 600, 900);
         let plan = Plan {
-            legs: vec![PlanLeg::Walk(access), PlanLeg::Transit(transit)],
+            legs: vec![PlanLeg::street(access), PlanLeg::Transit(transit)],
             start: 500,
             end: 900,
             mode: Mode::BikeToTransit,
@@ -630,6 +666,9 @@ mod tests {
             }],
             expected_end: 900,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         };
         let mut plans = vec![plan];
         g.enrich_street_legs(
@@ -639,7 +678,7 @@ mod tests {
             &bike,
             false,
         );
-        let PlanLeg::Walk(acc) = &plans[0].legs[0] else {
+        let Some(acc) = plans[0].legs[0].as_street() else {
             panic!()
         };
         assert!(
@@ -662,7 +701,7 @@ mod tests {
         let transit = transit_leg(s, o, 1000, alight);
         let egress = bike_leg(o, s, alight, alight + 90);
         let plan = Plan {
-            legs: vec![PlanLeg::Transit(transit), PlanLeg::Walk(egress)],
+            legs: vec![PlanLeg::Transit(transit), PlanLeg::street(egress)],
             start: 1000,
             end: alight + 90,
             mode: Mode::BikeToTransit,
@@ -673,6 +712,9 @@ mod tests {
             }],
             expected_end: alight + 90,
             price: None,
+            debug: None,
+            warnings: Vec::new(),
+            itinerary_id: None,
         };
         let mut plans = vec![plan];
         g.enrich_street_legs(
@@ -682,7 +724,7 @@ mod tests {
             &bike,
             false,
         );
-        let PlanLeg::Walk(eg) = plans[0].legs.last().unwrap() else {
+        let Some(eg) = plans[0].legs.last().unwrap().as_street() else {
             panic!()
         };
         assert!(
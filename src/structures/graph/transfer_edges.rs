@@ -0,0 +1,175 @@
+//! Walk transfer edges between nearby stops. Two stops a street apart are otherwise
+//! only joined through whichever street nodes each happened to link to, so every
+//! transfer search has to rediscover the walk. This pass measures that walk once over
+//! the street network and stores it as a direct stop → stop foot edge.
+
+use std::collections::HashSet;
+
+use crate::ingestion::gtfs::foot_connector_edge;
+use crate::structures::{EdgeData, NodeData, NodeID};
+
+use super::{Graph, point_index::PointIndex};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TransferEdgeStats {
+    /// Ordered stop pairs within the radius without a direct edge yet.
+    pub pairs: usize,
+    /// Pairs given a transfer edge.
+    pub linked: usize,
+    /// Pairs with no street-network walk within the detour budget.
+    pub unreachable: usize,
+}
+
+impl Graph {
+    /// Add a foot edge `a → b` for every stop pair within `radius_m` of each other,
+    /// its length the street-network walk between them (stop links included). Walks
+    /// longer than `max_detour` times the straight line are dropped, as are pairs
+    /// already joined by a direct edge (e.g. cross-feed links). Run on raw metres,
+    /// after stop links are final and before `build_raptor_index`; `radius_m <= 0`
+    /// disables.
+    pub fn build_transfer_edges(&mut self, radius_m: f64, max_detour: f64) -> TransferEdgeStats {
+        let mut stats = TransferEdgeStats::default();
+        if radius_m <= 0.0 {
+            return stats;
+        }
+        let stops: Vec<NodeID> = (0..self.nodes.len())
+            .map(NodeID)
+            .filter(|n| matches!(self.nodes[n.0], NodeData::TransitStop(_)))
+            .collect();
+        let tree = PointIndex::bulk_load(
+            stops
+                .iter()
+                .map(|&n| {
+                    let loc = self.nodes[n.0].loc();
+                    ([loc.latitude, loc.longitude], n)
+                })
+                .collect(),
+        );
+
+        let mut edges: Vec<(NodeID, NodeID, usize)> = Vec::new();
+        for &a in &stops {
+            let loc = self.nodes[a.0].loc();
+            let joined: HashSet<NodeID> = self.edges[a.0]
+                .iter()
+                .filter_map(|e| match e {
+                    EdgeData::Street(s) => Some(s.destination),
+                    _ => None,
+                })
+                .collect();
+            let neighbours: Vec<(f64, NodeID)> = tree
+                .within(loc.latitude, loc.longitude, radius_m)
+                .into_iter()
+                .filter(|&(_, &b)| b != a && !joined.contains(&b))
+                .map(|(d, &b)| (d, b))
+                .collect();
+            if neighbours.is_empty() {
+                continue;
+            }
+            stats.pairs += neighbours.len();
+
+            let budget = (radius_m * max_detour).ceil() as usize;
+            let origins = self.street_links(a);
+            let mut targets: Vec<NodeID> = neighbours
+                .iter()
+                .flat_map(|&(_, b)| self.street_links(b))
+                .map(|(n, _)| n)
+                .collect();
+            targets.sort_unstable();
+            targets.dedup();
+            let dist = self.foot_distances(&origins, &targets, budget);
+
+            for (straight, b) in neighbours {
+                let walk = self
+                    .street_links(b)
+                    .into_iter()
+                    .filter_map(|(n, len)| dist.get(&n).map(|&d| d + len))
+                    .min();
+                match walk {
+                    Some(w) if w as f64 <= straight.max(1.0) * max_detour => {
+                        edges.push((a, b, w.max(1)));
+                        stats.linked += 1;
+                    }
+                    _ => stats.unreachable += 1,
+                }
+            }
+        }
+        for (a, b, len) in edges {
+            self.add_edge(a, foot_connector_edge(a, b, len));
+        }
+        stats
+    }
+
+    /// A stop's foot links to street nodes, with their lengths.
+    fn street_links(&self, stop: NodeID) -> Vec<(NodeID, usize)> {
+        self.edges[stop.0]
+            .iter()
+            .filter_map(|e| match e {
+                EdgeData::Street(s)
+                    if s.foot && matches!(self.nodes.get(s.destination.0), Some(NodeData::OsmNode(_))) =>
+                {
+                    Some((s.destination, s.length))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::GraphBuilder;
+
+    /// Stops `A` and `B` 50 m apart on either side of a fence: the streets they link to
+    /// only meet `gap_m` away. `C` is 300 m off.
+    fn fence(gap_m: usize) -> (Graph, NodeID, NodeID, NodeID) {
+        let mut b = GraphBuilder::new().without_contraction();
+        // 0.0007° lng ≈ 50 m at lat 50.
+        let w = b.add_street_node("w", 50.0, 4.0);
+        let e = b.add_street_node("e", 50.0, 4.0007);
+        let j = b.add_street_node("j", 50.001, 4.00035);
+        let far = b.add_street_node("far", 50.0, 4.0049);
+        b.add_street(w, j, gap_m);
+        b.add_street(j, e, gap_m);
+        b.add_street(e, far, 300);
+        let sa = b.add_stop("A", 50.0, 4.0);
+        let sb = b.add_stop("B", 50.0, 4.0007);
+        let sc = b.add_stop("C", 50.0, 4.0049);
+        b.link_stop(sa, w, 5);
+        b.link_stop(sb, e, 5);
+        b.link_stop(sc, far, 5);
+        (b.build(), sa, sb, sc)
+    }
+
+    fn transfer(g: &Graph, from: NodeID, to: NodeID) -> Option<usize> {
+        g.out_edges(from).iter().find_map(|e| match e {
+            EdgeData::Street(s) if s.destination == to => Some(s.length),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn nearby_stops_get_the_street_network_walk_both_ways() {
+        let (mut g, a, b, c) = fence(40);
+        let stats = g.build_transfer_edges(200.0, 2.0);
+        assert_eq!(stats, TransferEdgeStats { pairs: 2, linked: 2, unreachable: 0 });
+        // 5 m link + 40 m to the junction + 40 m back + 5 m link, not the 50 m straight line.
+        assert_eq!(transfer(&g, a, b), Some(90));
+        assert_eq!(transfer(&g, b, a), Some(90));
+        assert_eq!(transfer(&g, a, c), None, "C is outside the radius");
+    }
+
+    #[test]
+    fn long_detours_and_existing_links_get_no_edge() {
+        let (mut g, a, b, _) = fence(200);
+        assert_eq!(g.build_transfer_edges(200.0, 2.0).unreachable, 2);
+        assert_eq!(transfer(&g, a, b), None);
+
+        let (mut g, a, b, _) = fence(40);
+        g.add_edge(a, foot_connector_edge(a, b, 50));
+        let stats = g.build_transfer_edges(200.0, 2.0);
+        assert_eq!((stats.pairs, stats.linked), (1, 1), "only b → a is new");
+        assert_eq!(transfer(&g, a, b), Some(50));
+        assert_eq!(transfer(&g, b, a), Some(90));
+    }
+}
@@ -4,11 +4,12 @@ use gtfs_structures::RouteType;
 
 use crate::{
     ingestion::gtfs::{
-        AgencyInfo, RouteInfo, ServicePattern, StopTime, TimetableSegment, TripId,
-        TripInfo, TripSegment, display_route_type,
+        ActiveServices, AgencyInfo, LanguageId, NameField, NameTranslations, RouteId, RouteInfo, ServiceId,
+        ServicePattern, StopTime, TimetableSegment, TripId, TripInfo, TripSegment, display_route_type,
     },
+    ingestion::realtime::ServiceAlert,
     structures::{
-        DelayCDF, LatLng, NodeID, RealtimeIndex,
+        DelayCDF, EdgeData, LatLng, NodeID, RealtimeIndex,
         raptor::{Lookup, PatternInfo},
     },
 };
@@ -26,6 +27,22 @@ pub struct StationBackup {
     pub same_route: bool,
 }
 
+/// One trip of [`Graph::route_timetable`] / [`Graph::trip_timetable`]: its stops in travel order with their
+/// scheduled times.
+#[derive(Debug, Clone)]
+pub struct TimetableTrip {
+    pub trip: TripId,
+    pub stops: Vec<(NodeID, StopTime)>,
+}
+
+/// One scheduled departure from a stop, for departure boards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StopDeparture {
+    pub trip: TripId,
+    pub route: crate::ingestion::gtfs::RouteId,
+    pub scheduled_departure: u32,
+}
+
 impl Graph {
     pub fn get_transit_departures_size(&self) -> usize {
         self.raptor.transit_departures.len()
@@ -95,10 +112,66 @@ impl Graph {
         self.raptor.transit_stop_platform_codes.get(compact as usize)?.as_deref()
     }
 
+    /// GTFS `stop_code` of a transit stop node.
+    pub fn stop_code_of_node(&self, id: NodeID) -> Option<&str> {
+        let compact = *self.raptor.transit_node_to_stop.get(id.0)?;
+        if compact == u32::MAX {
+            return None;
+        }
+        self.raptor.transit_stop_codes.get(compact as usize)?.as_deref()
+    }
+
+    /// GTFS `wheelchair_boarding` of a transit stop node.
+    pub fn stop_accessibility_of_node(&self, id: NodeID) -> Option<gtfs_structures::Availability> {
+        let compact = self.compact_stop_of_node(id)?;
+        self.raptor.transit_stop_wheelchair.get(compact).copied()
+    }
+
+    /// Routes departing a transit stop node (patterns where it is not the last stop),
+    /// one per mode and short name, ordered rail → subway → tram → bus then by name.
+    pub fn routes_departing_node(&self, id: NodeID) -> Vec<&RouteInfo> {
+        let Some(compact) = self.compact_stop_of_node(id) else {
+            return Vec::new();
+        };
+        let pats = self.raptor.transit_idx_stop_patterns[compact]
+            .of(&self.raptor.transit_stop_patterns);
+        let mut routes: Vec<&RouteInfo> = Vec::new();
+        for &(pattern_id, pos) in pats {
+            let p = pattern_id.0 as usize;
+            if pos as usize + 1 >= self.raptor.transit_idx_pattern_stops[p].len {
+                continue;
+            }
+            let route = &self.raptor.transit_routes[self.raptor.transit_patterns[p].route.0 as usize];
+            if !routes.iter().any(|r| {
+                r.route_type == route.route_type && r.route_short_name == route.route_short_name
+            }) {
+                routes.push(route);
+            }
+        }
+        routes.sort_by_cached_key(|r| {
+            let mode = display_route_type(r.route_type);
+            (
+                super::raptor_build::mode_rank(mode),
+                mode,
+                super::raptor_build::natural_key(&r.route_short_name),
+            )
+        });
+        routes
+    }
+
     pub fn platform_code_of_stop(&self, stop: usize) -> Option<&str> {
         self.raptor.transit_stop_platform_codes.get(stop)?.as_deref()
     }
 
+    /// Wheelchair users cannot board or alight at compact `stop`
+    /// (`wheelchair_boarding=2`, or every step-free access rides an elevator that is
+    /// out of service); unknown accessibility counts as usable.
+    pub fn stop_blocks_wheelchair(&self, stop: usize) -> bool {
+        self.raptor.transit_stop_wheelchair.get(stop)
+            == Some(&gtfs_structures::Availability::NotAvailable)
+            || self.raptor.elevator_blocked_stops.contains(&(stop as u32))
+    }
+
     pub fn get_transit_routes_size(&self) -> usize {
         self.raptor.transit_routes.len()
     }
@@ -107,6 +180,24 @@ impl Graph {
         self.raptor.transit_routes.extend(routes);
     }
 
+    /// Merge a feed's `translations.txt` names into the graph-wide table.
+    pub fn add_name_translations(&mut self, translations: NameTranslations) {
+        self.raptor.name_translations.extend(translations);
+    }
+
+    /// The feed language best matching an `Accept-Language`-style list, if any.
+    pub fn match_language(&self, accept: &str) -> Option<LanguageId> {
+        self.raptor.name_translations.match_language(accept)
+    }
+
+    /// `text` translated into `language` when the feeds provide it, else unchanged.
+    pub fn localized(&self, field: NameField, text: &str, language: Option<LanguageId>) -> String {
+        language
+            .and_then(|l| self.raptor.name_translations.translate(field, text, l))
+            .unwrap_or(text)
+            .to_string()
+    }
+
     /// Append raw GTFS `route_id` strings, aligned 1:1 with `add_transit_routes`
     /// (index `i` ↔ ith `RouteInfo`); required by route-level alert matching.
     pub fn add_transit_route_ids(&mut self, ids: Vec<String>) {
@@ -155,6 +246,85 @@ impl Graph {
             .collect()
     }
 
+    /// Transit stops within `radius_m` Haversine meters of a point as
+    /// (stop_index, distance_m, name, lat, lon, mode), closest first, at most `limit`.
+    pub fn stops_nearby(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_m: f64,
+        limit: usize,
+    ) -> Vec<(usize, f64, String, f64, f64, String)> {
+        self.raptor
+            .transit_stops_tree
+            .within(lat, lon, radius_m)
+            .into_iter()
+            .take(limit)
+            .map(|(dist, &stop_idx)| {
+                let loc = self.node_loc(self.raptor.transit_stop_to_node[stop_idx]);
+                let route_type = self
+                    .raptor
+                    .transit_idx_stop_patterns
+                    .get(stop_idx)
+                    .and_then(|l| l.of(&self.raptor.transit_stop_patterns).first())
+                    .map(|&(pat, _)| {
+                        let route = self.raptor.transit_patterns[pat.0 as usize].route;
+                        self.raptor.transit_routes[route.0 as usize].route_type
+                    })
+                    .unwrap_or(RouteType::Bus);
+                (
+                    stop_idx,
+                    dist,
+                    self.raptor.transit_stop_names[stop_idx].clone(),
+                    loc.latitude,
+                    loc.longitude,
+                    display_route_type(route_type).to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// [`Graph::stops_nearby`] with the platforms of one station (GTFS `parent_station`,
+    /// or same-name stops merged within `station_merge_radius_m`) collapsed onto the
+    /// closest one, named after its station. Routing still uses every platform.
+    pub fn stops_nearby_by_station(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_m: f64,
+        limit: usize,
+    ) -> Vec<(usize, f64, String, f64, f64, String)> {
+        let mut seen = std::collections::HashSet::new();
+        self.stops_nearby(lat, lon, radius_m, usize::MAX)
+            .into_iter()
+            .filter_map(|mut hit| {
+                if let Some(station) = self.station_of_stop(hit.0) {
+                    if !seen.insert(station) {
+                        return None;
+                    }
+                    hit.2 = self.raptor.transit_stations[station].name.clone();
+                }
+                Some(hit)
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Index into `transit_stations` of the station grouping compact stop `stop`.
+    pub fn station_of_stop(&self, stop: usize) -> Option<usize> {
+        self.raptor
+            .transit_stop_to_station
+            .get(stop)
+            .filter(|&&s| s != u32::MAX)
+            .map(|&s| s as usize)
+    }
+
+    /// Station id and platform count of the station grouping compact stop `stop`.
+    pub fn stop_station(&self, stop: usize) -> Option<(&str, usize)> {
+        let st = &self.raptor.transit_stations[self.station_of_stop(stop)?];
+        Some((st.id.as_str(), st.platform_stop_indices.len()))
+    }
+
     /// Deduped physical stations as (id, name, lat, lon, operators, modes, lines,
     /// platform_count). Platforms grouped by GTFS `parent_station`; standalone stops
     /// each form one station.
@@ -205,6 +375,74 @@ impl Graph {
         Some((st.lat_lng, st.platform_stop_indices.clone()))
     }
 
+    /// Departure from its origin stop of the first and last run of `route` on `date`
+    /// (seconds from that service day's midnight, past 86400 after it); `None` when
+    /// no run of the route operates that day.
+    pub fn route_service_span(&self, route: RouteId, date: u32, weekday: u8) -> Option<(u32, u32)> {
+        let r = &self.raptor;
+        let mut span: Option<(u32, u32)> = None;
+        for (p, pattern) in r.transit_patterns.iter().enumerate() {
+            if pattern.route != route {
+                continue;
+            }
+            let trips = r.transit_idx_pattern_trips[p].of(&r.transit_pattern_trips);
+            // Stop-major: the origin stop's column comes first.
+            let times = r.transit_idx_pattern_stop_times[p].of(&r.transit_pattern_stop_times);
+            for (trip, time) in trips.iter().zip(times) {
+                let service = r.transit_trips[trip.0 as usize].service_id;
+                if !r.is_service_active(service, date, weekday) {
+                    continue;
+                }
+                let dep = time.departure;
+                span = Some(span.map_or((dep, dep), |(lo, hi)| (lo.min(dep), hi.max(dep))));
+            }
+        }
+        span
+    }
+
+    /// Every distinct service a run of `route` uses.
+    pub fn route_services(&self, route: RouteId) -> Vec<ServiceId> {
+        let mut services: Vec<ServiceId> =
+            self.raptor.transit_trips.iter().filter(|t| t.route_id == route).map(|t| t.service_id).collect();
+        services.sort_unstable_by_key(|s| s.0);
+        services.dedup();
+        services
+    }
+
+    /// Dates in `from..=to` on which at least one of `services` runs, from their
+    /// `ServicePattern`s (weekdays, validity range, added and removed dates).
+    pub fn service_dates(
+        &self,
+        services: &[ServiceId],
+        from: chrono::NaiveDate,
+        to: chrono::NaiveDate,
+    ) -> Vec<chrono::NaiveDate> {
+        use chrono::Datelike;
+
+        from.iter_days()
+            .take_while(|d| *d <= to)
+            .filter(|d| {
+                let (date, weekday) =
+                    (crate::ingestion::gtfs::date_to_days(*d), 1u8 << d.weekday().num_days_from_monday());
+                services.iter().any(|&s| self.raptor.is_service_active(s, date, weekday))
+            })
+            .collect()
+    }
+
+    /// `days_of_week` bits (Mon=0x01 .. Sun=0x40) of every service a run of `route`
+    /// uses, plus the weekdays of their added dates. Removed dates are ignored.
+    pub fn route_operating_days(&self, route: RouteId) -> u8 {
+        let r = &self.raptor;
+        self.route_services(route)
+            .into_iter()
+            .filter_map(|s| r.transit_services.get(s.0 as usize))
+            .fold(0, |days, sp| {
+                sp.added_dates
+                    .iter()
+                    .fold(days | sp.days_of_week, |d, &date| d | crate::ingestion::gtfs::weekday_bit(date))
+            })
+    }
+
     /// G-free plan-node resolution: coordinate via `node_loc` (survives the
     /// interior-node drop) plus, for a transit stop, its name from the serialized
     /// `transit_stop_names` (not `g.nodes`).
@@ -287,71 +525,88 @@ impl Graph {
             .collect()
     }
 
+    /// First departure in `tt` at or after `time` whose service is in `active` (the
+    /// request's `RaptorIndex::active_services`) and whose trip `rt` does not cancel.
+    /// A day without service returns before the binary search.
     pub fn next_transit_departure(
         &self,
         tt: TimetableSegment,
         time: u32,
-        date: u32,
-        weekday: u8,
-    ) -> Option<(usize, &TripSegment)> {
-        let slice = &self.raptor.transit_departures[tt.start..tt.start + tt.len];
-
-        let start_idx = slice.partition_point(|d| d.departure < time);
-
-        for (i, dep) in slice[start_idx..].iter().enumerate() {
-            if self.raptor.transit_services[dep.service_id.0 as usize].is_active(date, weekday) {
-                return Some((tt.start + start_idx + i, dep));
-            }
+        active: &ActiveServices,
+        rt: &RealtimeIndex,
+    ) -> Option<(usize, TripSegment)> {
+        if active.is_empty() {
+            return None;
         }
+        let deps = &self.raptor.transit_departures;
+        let range = tt.range();
+        let start_idx = range.start + deps.departures[range.clone()].partition_point(|&d| d < time);
 
-        None
+        (start_idx..range.end)
+            .find(|&i| active.contains(deps.service_ids[i]) && !rt.is_canceled(deps.trip_ids[i]))
+            .map(|i| (i, deps.at(i)))
     }
 
-    pub fn get_transit_departure_slice(&self, tt: TimetableSegment) -> &[TripSegment] {
-        &self.raptor.transit_departures[tt.start..tt.start + tt.len]
+    pub fn previous_departures<'a>(
+        &'a self,
+        tt: TimetableSegment,
+        active: &'a ActiveServices,
+        initial_index: usize,
+    ) -> impl Iterator<Item = (usize, TripSegment)> + 'a {
+        let range = tt.range();
+        let base = initial_index.clamp(range.start, range.end);
+
+        (range.start..base)
+            .rev()
+            .map(move |i| (i, self.raptor.transit_departures.at(i)))
+            .filter(move |(_, dep)| active.contains(dep.service_id))
     }
 
-    pub fn previous_departures(
-        &self,
+    pub fn next_departures<'a>(
+        &'a self,
         tt: TimetableSegment,
-        date: u32,
-        weekday: u8,
+        active: &'a ActiveServices,
         initial_index: usize,
-    ) -> impl Iterator<Item = (usize, &TripSegment)> {
-        let slice = &self.raptor.transit_departures[tt.start..tt.start + tt.len];
-        let relative_index = initial_index.saturating_sub(tt.start).min(slice.len());
-        let base = tt.start + relative_index;
+    ) -> impl Iterator<Item = (usize, TripSegment)> + 'a {
+        let range = tt.range();
+        let base = initial_index.clamp(range.start, range.end);
 
-        slice[..relative_index]
-            .iter()
-            .rev()
-            .enumerate()
-            .filter(move |(_, dep)| {
-                self.raptor.transit_services[dep.service_id.0 as usize].is_active(date, weekday)
+        (base + 1..range.end)
+            .map(move |i| (i, self.raptor.transit_departures.at(i)))
+            .filter(move |(_, dep)| active.contains(dep.service_id))
+    }
+
+    /// Timetable segment of every transit edge `from`→`to` (one per route serving the
+    /// hop), for inspecting the raw departure runs.
+    pub fn transit_edge_segments(&self, from: NodeID, to: NodeID) -> Vec<(RouteId, TimetableSegment)> {
+        self.edges
+            .get(from.0)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| match e {
+                EdgeData::Transit(te) if te.destination == to => {
+                    Some((te.route_id, te.timetable_segment))
+                }
+                _ => None,
             })
-            .map(move |(i, dep)| (base - 1 - i, dep))
+            .collect()
     }
 
-    pub fn next_departures(
+    /// Whether `tt` lies inside `transit_departures` (a segment from a client may not).
+    pub fn timetable_segment_in_bounds(&self, tt: TimetableSegment) -> bool {
+        tt.range().end <= self.raptor.transit_departures.len()
+    }
+
+    /// Every departure of `tt` running on `date`, earliest first.
+    pub fn departures_on(
         &self,
         tt: TimetableSegment,
         date: u32,
         weekday: u8,
-        initial_index: usize,
-    ) -> impl Iterator<Item = (usize, &TripSegment)> {
-        let slice = &self.raptor.transit_departures[tt.start..tt.start + tt.len];
-        let relative_index = initial_index.saturating_sub(tt.start).min(slice.len());
-        let base = tt.start + relative_index;
-
-        slice
-            .get(relative_index + 1..)
-            .unwrap_or(&[])
-            .iter()
-            .enumerate()
-            .filter(move |(_, dep)| {
-                self.raptor.transit_services[dep.service_id.0 as usize].is_active(date, weekday)
-            })
-            .map(move |(i, dep)| (base + 1 + i, dep))
+    ) -> impl DoubleEndedIterator<Item = (usize, TripSegment)> {
+        tt.range()
+            .map(move |i| (i, self.raptor.transit_departures.at(i)))
+            .filter(move |(_, dep)| self.raptor.is_service_active(dep.service_id, date, weekday))
     }
 
     /// Up to `count` departures from patterns serving `boarding_node`→`alighting_node`,
@@ -375,9 +630,8 @@ impl Graph {
             return vec![];
         }
 
-        let exclude_slice = &self.raptor.transit_departures
-            [exclude_timetable.start..exclude_timetable.start + exclude_timetable.len];
-        let excluded_trips: HashSet<TripId> = exclude_slice.iter().map(|s| s.trip_id).collect();
+        let excluded_trips: HashSet<TripId> =
+            self.raptor.transit_departures.trip_ids[exclude_timetable.range()].iter().copied().collect();
 
         let mut seen_trips: HashSet<TripId> = HashSet::new();
         let mut candidates: Vec<(TripId, u32, u32)> = Vec::new();
@@ -420,7 +674,7 @@ impl Graph {
                         continue;
                     }
                     let service_id = self.raptor.transit_trips[trip_id.0 as usize].service_id;
-                    if self.raptor.transit_services[service_id.0 as usize].is_active(date, weekday)
+                    if self.raptor.is_service_active(service_id, date, weekday)
                     {
                         seen_trips.insert(trip_id);
                         candidates.push((
@@ -438,7 +692,7 @@ impl Graph {
                         continue;
                     }
                     let service_id = self.raptor.transit_trips[trip_id.0 as usize].service_id;
-                    if self.raptor.transit_services[service_id.0 as usize].is_active(date, weekday)
+                    if self.raptor.is_service_active(service_id, date, weekday)
                     {
                         seen_trips.insert(trip_id);
                         candidates.push((
@@ -526,7 +780,7 @@ impl Graph {
                     continue;
                 }
                 let service_id = self.raptor.transit_trips[trip_id.0 as usize].service_id;
-                if !self.raptor.transit_services[service_id.0 as usize].is_active(date, weekday) {
+                if !self.raptor.is_service_active(service_id, date, weekday) {
                     continue;
                 }
                 seen_trips.insert(trip_id);
@@ -554,6 +808,223 @@ impl Graph {
         out
     }
 
+    /// The next `limit` departures from `stop` at or after `from_secs` whose service is in
+    /// `active` (the day's `RaptorIndex::active_services`), chronological. Terminal stops of a pattern (arrival only) are skipped.
+    pub fn stop_departures(
+        &self,
+        stop: usize,
+        from_secs: u32,
+        limit: usize,
+        active: &ActiveServices,
+    ) -> Vec<StopDeparture> {
+        let pats = match self.raptor.transit_idx_stop_patterns.get(stop) {
+            Some(l) if !active.is_empty() => l.of(&self.raptor.transit_stop_patterns),
+            _ => return vec![],
+        };
+
+        let mut out: Vec<StopDeparture> = Vec::new();
+        for &(pattern_id, pos) in pats {
+            let p = pattern_id.0 as usize;
+            let n_trips = self.raptor.transit_patterns[p].num_trips as usize;
+            let n_stops = self.raptor.transit_idx_pattern_stops[p].len;
+            if n_trips == 0 || pos as usize + 1 >= n_stops {
+                continue;
+            }
+            let route = self.raptor.transit_patterns[p].route;
+            let all_times = self.raptor.transit_idx_pattern_stop_times[p]
+                .of(&self.raptor.transit_pattern_stop_times);
+            let trip_ids =
+                self.raptor.transit_idx_pattern_trips[p].of(&self.raptor.transit_pattern_trips);
+            let col = &all_times[pos as usize * n_trips..(pos as usize + 1) * n_trips];
+            for (t, st) in col.iter().enumerate() {
+                if st.departure < from_secs {
+                    continue;
+                }
+                let trip = trip_ids[t];
+                if !active.contains(self.raptor.transit_trips[trip.0 as usize].service_id) {
+                    continue;
+                }
+                out.push(StopDeparture { trip, route, scheduled_departure: st.departure });
+            }
+        }
+        out.sort_by_key(|d| (d.scheduled_departure, d.trip.0));
+        out.dedup_by_key(|d| d.trip);
+        out.truncate(limit);
+        out
+    }
+
+    /// What `alert` affects inside the `min`..`max` lat/lng box: the compact stops it
+    /// names there, and the routes it names (directly or through a trip) with a stop
+    /// there. Both empty when the alert touches nothing in the box.
+    pub fn alert_in_area(
+        &self,
+        alert: &ServiceAlert,
+        min: LatLng,
+        max: LatLng,
+    ) -> (Vec<usize>, Vec<RouteId>) {
+        let inside = |stop: usize| {
+            let loc = self.node_loc(self.raptor.transit_stop_to_node[stop]);
+            (min.latitude..=max.latitude).contains(&loc.latitude)
+                && (min.longitude..=max.longitude).contains(&loc.longitude)
+        };
+        let mut stops = Vec::new();
+        let mut routes = Vec::new();
+        for e in &alert.informed_entity {
+            if let Some(stop) = e.stop_id.as_deref().and_then(|s| self.stop_index_of(s))
+                && inside(stop)
+                && !stops.contains(&stop)
+            {
+                stops.push(stop);
+            }
+            let route = e.route_id.as_deref().and_then(|r| self.route_index_of(r)).or_else(|| {
+                let trip = self.trip_index_of(e.trip_id.as_deref()?)?;
+                Some(self.raptor.transit_trips.get(trip.0 as usize)?.route_id)
+            });
+            if let Some(route) = route
+                && !routes.contains(&route)
+                && self.route_stops(route).any(inside)
+            {
+                routes.push(route);
+            }
+        }
+        (stops, routes)
+    }
+
+    /// Compact stops of every pattern of `route`, with repeats.
+    fn route_stops(&self, route: RouteId) -> impl Iterator<Item = usize> + '_ {
+        let r = &self.raptor;
+        r.transit_patterns
+            .iter()
+            .enumerate()
+            .filter(move |(_, p)| p.route == route)
+            .flat_map(move |(p, _)| r.transit_idx_pattern_stops[p].of(&r.transit_pattern_stops))
+            .map(move |n| r.transit_node_to_stop[n.0] as usize)
+    }
+
+    /// Route by GraphQL id (`maas:route:<index>`), stable id (`gtfs:route:<route_id>`)
+    /// or GTFS `route_id` (namespaced when its feed has a namespace).
+    pub fn route_index_of(&self, id: &str) -> Option<RouteId> {
+        if let Some(idx) = id.strip_prefix("maas:route:").and_then(|i| i.parse::<usize>().ok()) {
+            return (idx < self.raptor.transit_routes.len()).then_some(RouteId(idx as u32));
+        }
+        let id = id.strip_prefix(super::stable_id::STABLE_ROUTE_PREFIX).unwrap_or(id);
+        let idx = self.raptor.transit_route_ids.iter().position(|r| r == id)?;
+        Some(RouteId(idx as u32))
+    }
+
+    /// Every trip of `route` running on `date` (days since 2000-01-01, `weekday` its
+    /// `days_of_week` bit) with its stops in travel order, earliest first departure
+    /// first. Scheduled times since service-day midnight.
+    pub fn route_timetable(&self, route: RouteId, date: u32, weekday: u8) -> Vec<TimetableTrip> {
+        let mut out = Vec::new();
+        for (p, info) in self.raptor.transit_patterns.iter().enumerate() {
+            if info.route != route {
+                continue;
+            }
+            let trip_ids =
+                self.raptor.transit_idx_pattern_trips[p].of(&self.raptor.transit_pattern_trips);
+            for (t, &trip) in trip_ids.iter().enumerate() {
+                if self.trip_runs_on(trip, date, weekday) {
+                    out.push(TimetableTrip { trip, stops: self.pattern_trip_stops(p, t) });
+                }
+            }
+        }
+        out.sort_by_key(|t| (t.stops.first().map(|(_, st)| st.departure), t.trip.0));
+        out
+    }
+
+    /// Whether `trip`'s service runs on `date` (days since 2000-01-01, `weekday` its
+    /// `days_of_week` bit).
+    pub fn trip_runs_on(&self, trip: TripId, date: u32, weekday: u8) -> bool {
+        self.raptor
+            .transit_trips
+            .get(trip.0 as usize)
+            .is_some_and(|i| self.raptor.is_service_active(i.service_id, date, weekday))
+    }
+
+    /// `trip`'s full stop sequence with its scheduled times; `None` when no pattern
+    /// carries it.
+    pub fn trip_timetable(&self, trip: TripId) -> Option<TimetableTrip> {
+        let (p, t) = self.pattern_of_trip(trip)?;
+        Some(TimetableTrip { trip, stops: self.pattern_trip_stops(p, t) })
+    }
+
+    /// Polyline of `trip`'s whole run: its pattern's shape, or the stop coordinates
+    /// when the feed has none. Empty for an unknown trip.
+    pub fn trip_geometry(&self, trip: TripId) -> Vec<LatLng> {
+        let Some((p, _)) = self.pattern_of_trip(trip) else {
+            return vec![];
+        };
+        match self.get_pattern_shape(p) {
+            Some((pts, _)) if pts.len() >= 2 => pts,
+            _ => self.raptor.transit_idx_pattern_stops[p]
+                .of(&self.raptor.transit_pattern_stops)
+                .iter()
+                .map(|&n| self.node_loc(n))
+                .collect(),
+        }
+    }
+
+    /// `(pattern, trip column)` of `trip`; scans the patterns when the runtime index is
+    /// stale (patterns pushed since it was built).
+    fn pattern_of_trip(&self, trip: TripId) -> Option<(usize, usize)> {
+        let r = &self.raptor;
+        if let Some(&(p, t)) = r.trip_patterns.get(trip.0 as usize)
+            && r.transit_idx_pattern_trips
+                .get(p as usize)
+                .and_then(|lk| lk.of(&r.transit_pattern_trips).get(t as usize))
+                == Some(&trip)
+        {
+            return Some((p as usize, t as usize));
+        }
+        self.raptor.transit_idx_pattern_trips.iter().enumerate().find_map(|(p, lk)| {
+            lk.of(&self.raptor.transit_pattern_trips)
+                .iter()
+                .position(|&x| x == trip)
+                .map(|t| (p, t))
+        })
+    }
+
+    /// Where `trip` rides `stops` back to back, leaving `stops[0]` at `departure`:
+    /// `(pattern, trip column, position of stops[0])`. `None` when its pattern does not
+    /// serve them in that order — another branch or the opposite direction sharing a
+    /// hop — and the departure picks the right lap of a loop.
+    pub fn trip_run(&self, trip: TripId, stops: &[NodeID], departure: u32) -> Option<(usize, usize, usize)> {
+        let (p, t) = self.pattern_of_trip(trip)?;
+        let r = &self.raptor;
+        let pattern = r.transit_idx_pattern_stops[p].of(&r.transit_pattern_stops);
+        let times = r.transit_idx_pattern_stop_times[p].of(&r.transit_pattern_stop_times);
+        let n_trips = r.transit_patterns[p].num_trips as usize;
+        let b = pattern
+            .windows(stops.len().max(1))
+            .enumerate()
+            .position(|(b, w)| w == stops && times[b * n_trips + t].departure == departure)?;
+        Some((p, t, b))
+    }
+
+    /// Index in `transit_departures` of `trip` leaving at `departure` within `tt`: a
+    /// binary search of the departure-sorted slice, then the trips leaving that second.
+    pub fn departure_index_of(&self, tt: TimetableSegment, trip: TripId, departure: u32) -> Option<usize> {
+        let deps = &self.raptor.transit_departures;
+        let range = tt.range();
+        let from = range.start + deps.departures[range.clone()].partition_point(|&d| d < departure);
+        (from..range.end)
+            .take_while(|&i| deps.departures[i] == departure)
+            .find(|&i| deps.trip_ids[i] == trip)
+    }
+
+    fn pattern_trip_stops(&self, p: usize, t: usize) -> Vec<(NodeID, StopTime)> {
+        let n_trips = self.raptor.transit_patterns[p].num_trips as usize;
+        let stops = self.raptor.transit_idx_pattern_stops[p].of(&self.raptor.transit_pattern_stops);
+        let times = self.raptor.transit_idx_pattern_stop_times[p]
+            .of(&self.raptor.transit_pattern_stop_times);
+        stops
+            .iter()
+            .enumerate()
+            .map(|(s, &node)| (node, times[s * n_trips + t]))
+            .collect()
+    }
+
     pub fn push_transit_pattern(&mut self, p: PatternInfo) {
         self.raptor.transit_patterns.push(p);
     }
@@ -624,11 +1095,43 @@ impl Graph {
         }
     }
 
+    /// Display fields of `trip_id` and its route for a transit leg.
+    pub fn leg_display(&self, trip_id: TripId) -> crate::structures::plan::PlanLegDisplay {
+        let trip = self.get_trip(trip_id);
+        let route = trip.and_then(|t| self.get_route(t.route_id));
+        let hex = |c: Option<(u8, u8, u8)>| c.map(|(r, g, b)| crate::structures::plan::rgb_to_hex(r, g, b));
+        crate::structures::plan::PlanLegDisplay {
+            route_short_name: route.map(|r| r.route_short_name.clone()).filter(|n| !n.is_empty()),
+            headsign: trip.and_then(|t| t.trip_headsign.clone()),
+            route_color: hex(route.and_then(|r| r.route_color)),
+            route_text_color: hex(route.and_then(|r| r.route_text_color)),
+        }
+    }
+
     pub fn route_type_of_trip(&self, trip_id: TripId) -> Option<RouteType> {
         let route_id = self.get_trip(trip_id)?.route_id;
         self.get_route(route_id).map(|r| r.route_type)
     }
 
+    /// Whether a bike may ride on `route_type` by policy (`bike_route_types`);
+    /// `None` when the policy says nothing about it.
+    pub fn route_type_allows_bikes(&self, route_type: RouteType) -> Option<bool> {
+        self.raptor
+            .bike_route_types
+            .contains(&route_type)
+            .then_some(true)
+    }
+
+    /// Effective bike permission of `trip`: its GTFS `bikes_allowed`, else the
+    /// route-type policy for `route_type`.
+    pub fn trip_allows_bikes(&self, trip: TripId, route_type: Option<RouteType>) -> Option<bool> {
+        self.raptor
+            .transit_trips
+            .get(trip.0 as usize)
+            .and_then(|t| t.bikes_allowed)
+            .or_else(|| route_type.and_then(|rt| self.route_type_allows_bikes(rt)))
+    }
+
     /// Scheduled `(board departure, alight arrival)` (seconds since service midnight)
     /// for `trip` from compact stop `board` to `alight`; `None` when no pattern carries
     /// `trip` with `board` preceding `alight`.
@@ -759,7 +1262,7 @@ impl Graph {
                 // this shared oracle applies the SAME schedule + cancellation predicate;
                 // the tightening pass must never select a CANCELED trip.
                 let svc = self.raptor.transit_trips[trip_id.0 as usize].service_id;
-                if !self.raptor.transit_services[svc.0 as usize].is_active(date, weekday)
+                if !self.raptor.is_service_active(svc, date, weekday)
                     || rt.is_canceled(trip_id)
                 {
                     continue;
@@ -772,11 +1275,11 @@ impl Graph {
                 }
 
                 let dep = boarding_col[t].departure;
-                let dep_abs_idx = match self.raptor.transit_departures[ts.start..ts.start + ts.len]
+                let dep_abs_idx = match self.raptor.transit_departures.trip_ids[ts.range()]
                     .iter()
-                    .position(|seg| seg.trip_id == trip_id)
+                    .position(|&t| t == trip_id)
                 {
-                    Some(i) => ts.start + i,
+                    Some(i) => ts.start as usize + i,
                     None => continue,
                 };
 
@@ -791,20 +1294,19 @@ impl Graph {
     }
 
     /// Push shape data for the next pattern (once per pattern, in order).
+    /// An identical polyline already stored for another pattern is shared.
     pub fn push_transit_pattern_shape(&mut self, points: Vec<LatLng>, stop_idx: Vec<u32>) {
-        self.raptor.transit_pattern_shapes.push(points);
+        let id = self.shapes.intern(&points);
+        self.raptor.transit_pattern_shape_ids.push(id);
         self.raptor.transit_pattern_shape_stop_idx.push(stop_idx);
     }
 
     /// `(shape_points, stop_indices)` for pattern `p`, or `None` if no shape stored.
-    pub fn get_pattern_shape(&self, p: usize) -> Option<(&[LatLng], &[u32])> {
-        let pts = self.raptor.transit_pattern_shapes.get(p)?;
+    /// The points are decoded from the shape arena on every call.
+    pub fn get_pattern_shape(&self, p: usize) -> Option<(Vec<LatLng>, &[u32])> {
+        let id = *self.raptor.transit_pattern_shape_ids.get(p)?;
         let idx = self.raptor.transit_pattern_shape_stop_idx.get(p)?;
-        if pts.is_empty() {
-            None
-        } else {
-            Some((pts, idx))
-        }
+        Some((self.shapes.get(id)?, idx))
     }
 
     pub fn transit_pattern_count(&self) -> usize {
@@ -818,8 +1320,8 @@ impl Graph {
 
     /// Overwrite the shape for pattern `p` (must already exist).
     pub fn set_pattern_shape(&mut self, p: usize, pts: Vec<LatLng>, stop_idx: Vec<u32>) {
-        if p < self.raptor.transit_pattern_shapes.len() {
-            self.raptor.transit_pattern_shapes[p] = pts;
+        if p < self.raptor.transit_pattern_shape_ids.len() {
+            self.raptor.transit_pattern_shape_ids[p] = self.shapes.intern(&pts);
             self.raptor.transit_pattern_shape_stop_idx[p] = stop_idx;
         }
     }
@@ -856,7 +1358,7 @@ impl Graph {
 
             let (shape, stop_idx_in_shape) = self.get_pattern_shape(p)?;
             let shape_vertex = *stop_idx_in_shape.get(stop_pos as usize)? as usize;
-            return Some(advance_along_shape(shape, shape_vertex, distance_m));
+            return Some(advance_along_shape(&shape, shape_vertex, distance_m));
         }
         None
     }
@@ -1018,3 +1520,77 @@ mod outbound_reliability_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod timetable_tests {
+    use gtfs_structures::RouteType;
+
+    use super::*;
+    use crate::structures::GraphBuilder;
+
+    #[test]
+    fn lists_running_trips_of_every_pattern_by_first_departure() {
+        let mut b = GraphBuilder::new().without_contraction();
+        let s1 = b.add_stop("S1", 50.0, 4.0);
+        let s2 = b.add_stop("S2", 50.0, 4.01);
+        let s3 = b.add_stop("S3", 50.0, 4.02);
+        let weekdays = b.add_service(ServicePattern {
+            days_of_week: 0x1F,
+            start_date: 0,
+            end_date: 9999,
+            added_dates: vec![],
+            removed_dates: vec![],
+        });
+        let r = b.add_route("1", "Line 1", RouteType::Bus);
+        let other = b.add_route("2", "Line 2", RouteType::Bus);
+        let late = b.add_trip(r, &[(s1, 600, 600), (s2, 700, 700), (s3, 800, 800)]);
+        let short = b.add_trip(r, &[(s2, 100, 100), (s3, 200, 200)]);
+        let weekday_only = b.add_trip_on(weekdays, r, &[(s1, 50, 50), (s2, 60, 60)]);
+        b.add_trip(other, &[(s1, 0, 0), (s2, 10, 10)]);
+        let g = b.build();
+
+        let monday = 1u8;
+        let trips: Vec<TripId> =
+            g.route_timetable(r, 9000, monday).into_iter().map(|t| t.trip).collect();
+        assert_eq!(trips, vec![weekday_only, short, late]);
+
+        let saturday = 1u8 << 5;
+        let tt = g.route_timetable(r, 9000, saturday);
+        assert_eq!(tt.iter().map(|t| t.trip).collect::<Vec<_>>(), vec![short, late]);
+        let stops: Vec<(NodeID, u32)> =
+            tt[1].stops.iter().map(|(n, st)| (*n, st.departure)).collect();
+        assert_eq!(stops, vec![(s1, 600), (s2, 700), (s3, 800)]);
+    }
+
+    #[test]
+    fn trip_timetable_and_geometry_follow_the_trips_pattern() {
+        let mut b = GraphBuilder::new().without_contraction();
+        let s1 = b.add_stop("S1", 50.0, 4.0);
+        let s2 = b.add_stop("S2", 50.0, 4.01);
+        let r = b.add_route("1", "Line 1", RouteType::Bus);
+        b.add_trip(r, &[(s1, 0, 0), (s2, 10, 10)]);
+        let t = b.add_trip(r, &[(s2, 100, 110), (s1, 200, 200)]);
+        let g = b.build();
+
+        let run = g.trip_timetable(t).expect("trip is in a pattern");
+        let times: Vec<(NodeID, u32, u32)> =
+            run.stops.iter().map(|(n, st)| (*n, st.arrival, st.departure)).collect();
+        assert_eq!(times, vec![(s2, 100, 110), (s1, 200, 200)]);
+        let lngs: Vec<f64> = g.trip_geometry(t).iter().map(|c| c.longitude).collect();
+        assert_eq!(lngs, vec![4.01, 4.0], "stop coordinates without a shape");
+        assert!(g.trip_timetable(TripId(99)).is_none());
+        assert!(g.trip_geometry(TripId(99)).is_empty());
+    }
+
+    #[test]
+    fn route_index_accepts_graphql_and_gtfs_ids() {
+        let mut b = GraphBuilder::new().without_contraction();
+        b.add_route("1", "Line 1", RouteType::Bus);
+        let mut g = b.build();
+        g.raptor.transit_route_ids = vec!["stib:1".into()];
+        assert_eq!(g.route_index_of("maas:route:0"), Some(RouteId(0)));
+        assert_eq!(g.route_index_of("stib:1"), Some(RouteId(0)));
+        assert_eq!(g.route_index_of("maas:route:1"), None);
+        assert_eq!(g.route_index_of("1"), None);
+    }
+}
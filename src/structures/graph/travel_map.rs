@@ -2,13 +2,17 @@
 //! at a day + departure time, compute travel time to many sampled points. Reuses the
 //! RAPTOR forward pass and the exact one-to-many foot machinery (no new traversal):
 //! reach every stop ([`Graph::stop_arrivals`]), fill the reachable bounding box on a
-//! grid ([`Graph::travel_time_map`]), and aggregate across a departure window
-//! ([`Graph::travel_time_map_window`], BEST = min / AVERAGE = mean).
+//! grid ([`Graph::travel_time_map`]) or read given points ([`Graph::travel_times_to`]),
+//! and aggregate across a departure window ([`Graph::travel_time_map_window`], BEST =
+//! min / AVERAGE = mean).
+
+use std::collections::HashMap;
 
 use crate::structures::{
     ActiveModes, BikeCost, LatLng, NodeID, RealtimeIndex, ReliabilityBuckets,
 };
 
+use super::contraction::{ContractedGraph, TravelMapSnap};
 use super::raptor_route::{BestGrid, FullRow, Label, LabelRow, QueryEndpoints, SlimRow};
 use super::Graph;
 
@@ -29,6 +33,50 @@ pub enum TravelAggregation {
     Average,
 }
 
+/// One departure's foot field from [`Graph::reach_field`], read at any coordinate.
+struct ReachField<'a> {
+    cg: &'a ContractedGraph,
+    field: HashMap<NodeID, u32>,
+    center_snap: Option<TravelMapSnap>,
+    max_secs: u32,
+    radius: f64,
+}
+
+impl ReachField<'_> {
+    /// Travel time to `p`: the best of its ≤2 snap junctions' field value plus stub,
+    /// and the direct walk when `p` is on the centre's own chain. `None` when `p` does
+    /// not snap or is beyond `max_secs`.
+    fn seconds_at(&self, g: &Graph, p: LatLng) -> Option<u32> {
+        let p_snap = self.cg.foot_snap_travel_map(g, p.latitude, p.longitude, self.radius)?;
+
+        // Best over P's ≤2 snap junctions of field[junction] + stub.
+        let mut best = u32::MAX;
+        for &(dj, stub) in &p_snap.entries {
+            if let Some(&d) = self.field.get(&self.cg.junctions[dj]) {
+                let t = d.saturating_add(stub);
+                if t < best {
+                    best = t;
+                }
+            }
+        }
+
+        // Centre same-super-edge direct walk: a cell on the centre's own chain
+        // walks straight along it, not via a junction (not in the field).
+        if let Some(cs) = self.center_snap.as_ref() {
+            if cs.seg_start == p_snap.seg_start && cs.seg_len == p_snap.seg_len {
+                if let (Some(pc), Some(pp)) = (cs.from_ji_prefix, p_snap.from_ji_prefix) {
+                    let direct = pc.abs_diff(pp);
+                    if direct <= self.max_secs && direct < best {
+                        best = direct;
+                    }
+                }
+            }
+        }
+
+        (best <= self.max_secs).then_some(best)
+    }
+}
+
 impl Graph {
     /// Earliest arrival (seconds since midnight) at every compact transit stop
     /// reachable from `center` at `start_time`, or `u32::MAX` for stops not reached.
@@ -159,6 +207,8 @@ impl Graph {
         for e in mc.egress.iter_mut() {
             e.clear();
         }
+        // Travel times, not preferences: rank labels by arrival alone.
+        mc.mode_preferences.clear();
         if !mc.any_access() {
             return vec![u32::MAX; n_stops];
         }
@@ -301,7 +351,6 @@ impl Graph {
 
         // Aggregate per grid point, keyed by quantized lat/lng so the same point across
         // departures lands in the same bucket.
-        use std::collections::HashMap;
         let mut acc: HashMap<(i64, i64), (u64, u32, u32)> = HashMap::new(); // (sum, count, min)
 
         for dep in &departures {
@@ -390,7 +439,7 @@ impl Graph {
         let mut min_lng = center.longitude;
         let mut max_lng = center.longitude;
         let mut extend = |loc: LatLng, radius_secs: u32| {
-            let radius_m = radius_secs as f64 * self.raptor.walking_speed_mps;
+            let radius_m = radius_secs as f64 * self.walking_speed_mps();
             let dlat = radius_m / 111_320.0;
             let dlng = radius_m / (111_320.0 * loc.latitude.to_radians().cos().max(0.2));
             min_lat = min_lat.min(loc.latitude - dlat);
@@ -411,7 +460,6 @@ impl Graph {
             extend(self.node_loc(node), residual);
         }
 
-        let radius = self.raptor.edge_snap_radius_m;
         let step_m = self.effective_grid_step_m(
             grid_step_m, center.latitude, min_lat, max_lat, min_lng, max_lng,
         );
@@ -427,11 +475,40 @@ impl Graph {
         min_lat = snap_down(min_lat, center.latitude, dlat_step);
         min_lng = snap_down(min_lng, center.longitude, dlng_step);
 
-        let Some(cg) = self.contracted.as_ref() else {
-            // No contracted graph: no foot cost available, so no cell is <= max_secs.
+        let Some(reach) = self.reach_field(center, start_time, max_secs, arrivals) else {
             return Vec::new();
         };
 
+        let mut cells = Vec::new();
+        let n_lat = ((max_lat - min_lat) / dlat_step).ceil() as i64 + 1;
+        let n_lng = ((max_lng - min_lng) / dlng_step).ceil() as i64 + 1;
+        for i in 0..n_lat {
+            for j in 0..n_lng {
+                let p = LatLng {
+                    latitude: min_lat + i as f64 * dlat_step,
+                    longitude: min_lng + j as f64 * dlng_step,
+                };
+                if let Some(seconds) = reach.seconds_at(self, p) {
+                    cells.push(TravelCell { loc: p, seconds });
+                }
+            }
+        }
+        cells
+    }
+
+    /// The multi-source foot field of one departure behind [`Graph::fill_area`], seeded
+    /// at every stop in `arrivals` and at the centre's snap. `None` without a contracted
+    /// graph (no foot cost available, so nothing is within `max_secs`).
+    fn reach_field(
+        &self,
+        center: LatLng,
+        start_time: u32,
+        max_secs: u32,
+        arrivals: &[u32],
+    ) -> Option<ReachField<'_>> {
+        let cg = self.contracted.as_ref()?;
+        let radius = self.raptor.edge_snap_radius_m;
+
         // Reached stops with offset <= max_secs (a later arrival always caps out),
         // keyed by contracted junction index for the multi-source flood.
         let mut stop_seeds: Vec<(usize, u32)> = Vec::new();
@@ -460,52 +537,37 @@ impl Graph {
 
         let field =
             self.walk_dijkstra_travel_map_field(&stop_seeds, &coord_seeds, max_secs, cg);
+        Some(ReachField { cg, field, center_snap, max_secs, radius })
+    }
 
-        let mut cells = Vec::new();
-        let n_lat = ((max_lat - min_lat) / dlat_step).ceil() as i64 + 1;
-        let n_lng = ((max_lng - min_lng) / dlng_step).ceil() as i64 + 1;
-        for i in 0..n_lat {
-            for j in 0..n_lng {
-                let p = LatLng {
-                    latitude: min_lat + i as f64 * dlat_step,
-                    longitude: min_lng + j as f64 * dlng_step,
-                };
-
-                let Some(p_snap) = cg.foot_snap_travel_map(self, p.latitude, p.longitude, radius)
-                else {
-                    continue;
-                };
-
-                // Best over P's ≤2 snap junctions of field[junction] + stub.
-                let mut best = u32::MAX;
-                for &(dj, stub) in &p_snap.entries {
-                    if let Some(&d) = field.get(&cg.junctions[dj]) {
-                        let t = d.saturating_add(stub);
-                        if t < best {
-                            best = t;
-                        }
-                    }
-                }
-
-                // Centre same-super-edge direct walk: a cell on the centre's own chain
-                // walks straight along it, not via a junction (not in the field).
-                if let Some(cs) = center_snap.as_ref() {
-                    if cs.seg_start == p_snap.seg_start && cs.seg_len == p_snap.seg_len {
-                        if let (Some(pc), Some(pp)) = (cs.from_ji_prefix, p_snap.from_ji_prefix) {
-                            let direct = pc.abs_diff(pp);
-                            if direct <= max_secs && direct < best {
-                                best = direct;
-                            }
-                        }
-                    }
-                }
-
-                if best <= max_secs {
-                    cells.push(TravelCell { loc: p, seconds: best });
-                }
-            }
+    /// Travel time (seconds) from `center` to each of `points`, `None` for points not
+    /// reachable within `max_secs`: [`Graph::travel_time_map`] evaluated at arbitrary
+    /// coordinates instead of a grid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn travel_times_to(
+        &self,
+        center: LatLng,
+        points: &[LatLng],
+        start_time: u32,
+        date: u32,
+        weekday: u8,
+        max_secs: u32,
+        am: &ActiveModes,
+        buckets: &ReliabilityBuckets,
+        slack: u32,
+        unrestricted: bool,
+        use_cch: bool,
+        rt: &RealtimeIndex,
+        bike: &BikeCost,
+    ) -> Vec<Option<u32>> {
+        let arrivals = self.stop_arrivals(
+            center, start_time, date, weekday, max_secs, am, buckets, slack, unrestricted, use_cch,
+            rt, bike,
+        );
+        match self.reach_field(center, start_time, max_secs, &arrivals) {
+            Some(reach) => points.iter().map(|&p| reach.seconds_at(self, p)).collect(),
+            None => vec![None; points.len()],
         }
-        cells
     }
 
     /// Test-only wrapper over [`Graph::fill_area_reference`] taking an externally
@@ -538,7 +600,7 @@ impl Graph {
         let mut min_lng = center.longitude;
         let mut max_lng = center.longitude;
         let mut extend = |loc: LatLng, radius_secs: u32| {
-            let radius_m = radius_secs as f64 * self.raptor.walking_speed_mps;
+            let radius_m = radius_secs as f64 * self.walking_speed_mps();
             let dlat = radius_m / 111_320.0;
             let dlng = radius_m / (111_320.0 * loc.latitude.to_radians().cos().max(0.2));
             min_lat = min_lat.min(loc.latitude - dlat);
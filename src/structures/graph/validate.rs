@@ -0,0 +1,354 @@
+//! Structural graph checks (`--validate`). Broken invariants in a built graph otherwise
+//! only show up as wrong or missing routes at query time; this walks the street,
+//! contracted and transit structures once and reports every violation class with a
+//! few examples.
+
+use super::Graph;
+use crate::structures::{EdgeData, NodeData};
+
+const MAX_EXAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Routing can return wrong answers or index out of bounds.
+    Error,
+    /// Suspicious data the router tolerates.
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub check: &'static str,
+    pub severity: Severity,
+    pub count: usize,
+    pub examples: Vec<String>,
+}
+
+impl ValidationIssue {
+    fn new(check: &'static str, severity: Severity) -> Self {
+        ValidationIssue {
+            check,
+            severity,
+            count: 0,
+            examples: Vec::new(),
+        }
+    }
+
+    fn hit(&mut self, example: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(example());
+        }
+    }
+}
+
+/// One entry per check, including the clean ones, in a fixed order.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn count(&self, check: &str) -> usize {
+        self.issues
+            .iter()
+            .find(|i| i.check == check)
+            .map_or(0, |i| i.count)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|i| i.severity == Severity::Error && i.count > 0)
+    }
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for issue in &self.issues {
+            let status = match (issue.count, issue.severity) {
+                (0, _) => "ok",
+                (_, Severity::Error) => "ERROR",
+                (_, Severity::Warning) => "warn",
+            };
+            writeln!(f, "{status:<5} {:<28} {}", issue.check, issue.count)?;
+            for ex in &issue.examples {
+                writeln!(f, "        {ex}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Graph {
+    /// Check the built graph for dangling edges, unlinked transit stops, unsorted or
+    /// overtaking timetables, dangling trip/service/route references and zero-length
+    /// street edges. Works on a finalized graph (contracted only) as well as on one
+    /// that still holds the full node/edge arrays.
+    pub fn validate(&self) -> ValidationReport {
+        let mut dangling = ValidationIssue::new("dangling_edges", Severity::Error);
+        let mut unlinked = ValidationIssue::new("unlinked_stops", Severity::Error);
+        let mut unsorted = ValidationIssue::new("unsorted_timetables", Severity::Error);
+        let mut overtaking = ValidationIssue::new("overtaking_trips", Severity::Error);
+        let mut refs = ValidationIssue::new("dangling_transit_refs", Severity::Error);
+        let mut zero = ValidationIssue::new("zero_length_edges", Severity::Warning);
+
+        let n = self.nodes.len();
+        for (from, out) in self.edges.iter().enumerate() {
+            for e in out {
+                let (dest, len) = match e {
+                    EdgeData::Street(s) => (s.destination, s.length),
+                    EdgeData::Transit(t) => (t.destination, t.length),
+                };
+                if dest.0 >= n {
+                    dangling.hit(|| format!("edge {from} -> {} (only {n} nodes)", dest.0));
+                } else if len == 0 && dest.0 != from && matches!(e, EdgeData::Street(_)) {
+                    zero.hit(|| format!("street edge {from} -> {}", dest.0));
+                }
+            }
+        }
+
+        if let Some(cg) = self.contracted.as_ref() {
+            let nj = cg.junctions.len();
+            let mut linked = vec![false; nj];
+            for (ji, adj) in cg.adjacency.iter().enumerate() {
+                for se in adj {
+                    let end = se.seg_start as usize + se.seg_len as usize;
+                    if se.to as usize >= nj || end > cg.segs.len() {
+                        dangling.hit(|| {
+                            format!("super-edge from junction {ji} -> {} (segs ..{end})", se.to)
+                        });
+                        continue;
+                    }
+                    linked[ji] = true;
+                    linked[se.to as usize] = true;
+                    let length: usize = cg.segs[se.seg_start as usize..end]
+                        .iter()
+                        .map(|s| s.edge.length)
+                        .sum();
+                    if length == 0 && se.to as usize != ji {
+                        zero.hit(|| format!("super-edge junction {ji} -> {}", se.to));
+                    }
+                }
+            }
+            for (s, node) in self.raptor.transit_stop_to_node.iter().enumerate() {
+                let ji = cg.junction_of.get(node.0).copied().unwrap_or(u32::MAX);
+                if ji == u32::MAX || !linked.get(ji as usize).copied().unwrap_or(false) {
+                    unlinked.hit(|| self.stop_label(s));
+                }
+            }
+        } else if !self.edges.is_empty() {
+            for (s, node) in self.raptor.transit_stop_to_node.iter().enumerate() {
+                let street = self.edges.get(node.0).is_some_and(|out| {
+                    out.iter().any(|e| matches!(e, EdgeData::Street(_)))
+                });
+                if !street {
+                    unlinked.hit(|| self.stop_label(s));
+                }
+            }
+        }
+
+        let r = &self.raptor;
+        let (n_trips, n_routes, n_services) =
+            (r.transit_trips.len(), r.transit_routes.len(), r.transit_services.len());
+        for (t, trip) in r.transit_trips.iter().enumerate() {
+            if trip.service_id.0 as usize >= n_services {
+                refs.hit(|| format!("trip {t} -> service {}", trip.service_id.0));
+            }
+            if trip.route_id.0 as usize >= n_routes {
+                refs.hit(|| format!("trip {t} -> route {}", trip.route_id.0));
+            }
+        }
+        for (i, seg) in r.transit_departures.iter().enumerate() {
+            if seg.trip_id.0 as usize >= n_trips || seg.service_id.0 as usize >= n_services {
+                refs.hit(|| {
+                    format!(
+                        "departure {i} -> trip {} / service {}",
+                        seg.trip_id.0, seg.service_id.0
+                    )
+                });
+            }
+        }
+
+        for (p, pattern) in r.transit_patterns.iter().enumerate() {
+            if pattern.route.0 as usize >= n_routes {
+                refs.hit(|| format!("pattern {p} -> route {}", pattern.route.0));
+            }
+            let (Some(stops), Some(trips), Some(times)) = (
+                r.transit_idx_pattern_stops.get(p),
+                r.transit_idx_pattern_trips.get(p),
+                r.transit_idx_pattern_stop_times.get(p),
+            ) else {
+                refs.hit(|| format!("pattern {p} has no stop/trip/time lookup"));
+                continue;
+            };
+            let (ns, nt) = (stops.len, trips.len);
+            if times.len != ns * nt
+                || times.start + times.len > r.transit_pattern_stop_times.len()
+                || trips.start + trips.len > r.transit_pattern_trips.len()
+            {
+                refs.hit(|| format!("pattern {p}: {ns} stops x {nt} trips, {} times", times.len));
+                continue;
+            }
+            let times = times.of(&r.transit_pattern_stop_times);
+            for &trip in trips.of(&r.transit_pattern_trips) {
+                if trip.0 as usize >= n_trips {
+                    refs.hit(|| format!("pattern {p} -> trip {}", trip.0));
+                }
+            }
+            let dep = |s: usize, t: usize| times[s * nt + t].departure;
+            let arr = |s: usize, t: usize| times[s * nt + t].arrival;
+            // Trips are ordered by their first departure; a later inversion is overtaking.
+            if ns > 0 && (1..nt).any(|t| dep(0, t) < dep(0, t - 1)) {
+                unsorted.hit(|| format!("pattern {p}: trips not ordered by first departure"));
+            } else if let Some(t) = (1..nt).find(|&t| {
+                (1..ns).any(|s| arr(s, t) < arr(s, t - 1) || dep(s, t) < dep(s, t - 1))
+            }) {
+                overtaking.hit(|| format!("pattern {p}: trip #{t} overtakes trip #{}", t - 1));
+            }
+        }
+
+        for (p, segs) in r.transit_pattern_segment_timetables.iter().enumerate() {
+            for (hop, ts) in segs.iter().enumerate() {
+                let Some(deps) = r.transit_departures.departures.get(ts.range()) else {
+                    refs.hit(|| format!("pattern {p} hop {hop}: timetable out of range"));
+                    continue;
+                };
+                if deps.windows(2).any(|w| w[1] < w[0]) {
+                    unsorted.hit(|| format!("pattern {p} hop {hop}: departures out of order"));
+                }
+            }
+        }
+
+        ValidationReport {
+            issues: vec![dangling, unlinked, unsorted, overtaking, refs, zero],
+        }
+    }
+
+    fn stop_label(&self, stop: usize) -> String {
+        let id = self.raptor.transit_stop_ids.get(stop).map_or("?", |s| s.as_str());
+        let name = self
+            .raptor
+            .transit_stop_names
+            .get(stop)
+            .map(|s| s.as_str())
+            .or_else(|| match self.nodes.get(self.raptor.transit_stop_to_node[stop].0) {
+                Some(NodeData::TransitStop(t)) => Some(t.name.as_str()),
+                _ => None,
+            })
+            .unwrap_or("?");
+        format!("stop {id} ({name})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::gtfs::{
+        RouteId, ServiceId, StopTime, TimetableSegment, TripId, TripSegment,
+    };
+    use crate::structures::cost::VarGen;
+    use crate::structures::raptor::{Lookup, PatternInfo};
+    use crate::structures::{
+        BikeAttrs, LatLng, NodeID, OsmNodeData, StreetEdgeData,
+    };
+
+    fn street(g: &mut Graph, a: NodeID, b: NodeID, length: usize) {
+        g.add_edge(
+            a,
+            EdgeData::Street(StreetEdgeData {
+                origin: a,
+                destination: b,
+                partial: false,
+                length,
+                foot: true,
+                bike: true,
+                car: true,
+                attrs: BikeAttrs::road_default(),
+                elev_delta: 0,
+                surface_speed: 100,
+                var_gen: VarGen::NONE,
+            }),
+        );
+    }
+
+    fn osm(g: &mut Graph, i: usize) -> NodeID {
+        g.add_node(NodeData::OsmNode(OsmNodeData {
+            eid: format!("map#osm#{i}"),
+            lat_lng: LatLng {
+                latitude: 50.0,
+                longitude: 4.0 + i as f64 * 0.001,
+            },
+        }))
+    }
+
+    #[test]
+    fn clean_street_graph_reports_no_errors() {
+        let mut g = Graph::new();
+        let (a, b) = (osm(&mut g, 0), osm(&mut g, 1));
+        street(&mut g, a, b, 70);
+        street(&mut g, b, a, 70);
+        let report = g.validate();
+        assert!(!report.has_errors(), "{report}");
+        assert_eq!(report.issues.len(), 6);
+        assert!(report.to_string().contains("ok    dangling_edges"));
+    }
+
+    #[test]
+    fn dangling_and_zero_length_edges_are_reported() {
+        let mut g = Graph::new();
+        let (a, b) = (osm(&mut g, 0), osm(&mut g, 1));
+        street(&mut g, a, b, 0);
+        street(&mut g, b, NodeID(99), 10);
+        let report = g.validate();
+        assert_eq!(report.count("dangling_edges"), 1);
+        assert_eq!(report.count("zero_length_edges"), 1);
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn timetable_order_and_references_are_checked() {
+        let mut g = Graph::new();
+        let r = &mut g.raptor;
+        r.transit_patterns = vec![PatternInfo {
+            route: RouteId(3),
+            num_trips: 2,
+        }];
+        r.transit_pattern_stops = vec![NodeID(0), NodeID(1)];
+        r.transit_idx_pattern_stops = vec![Lookup { start: 0, len: 2 }];
+        r.transit_pattern_trips = vec![TripId(0), TripId(1)];
+        r.transit_idx_pattern_trips = vec![Lookup { start: 0, len: 2 }];
+        let st = |t: u32| StopTime {
+            arrival: t,
+            departure: t,
+            ..Default::default()
+        };
+        // stop-major: [stop0 trip0, stop0 trip1, stop1 trip0, stop1 trip1]; trip 1
+        // leaves later but arrives earlier -> overtakes.
+        r.transit_pattern_stop_times = vec![st(100), st(200), st(900), st(800)];
+        r.transit_idx_pattern_stop_times = vec![Lookup { start: 0, len: 4 }];
+        r.transit_departures = [
+            TripSegment {
+                trip_id: TripId(1),
+                departure: 200,
+                arrival: 800,
+                service_id: ServiceId(0),
+            },
+            TripSegment {
+                trip_id: TripId(0),
+                departure: 100,
+                arrival: 900,
+                service_id: ServiceId(0),
+            },
+        ]
+        .into_iter()
+        .collect();
+        r.transit_pattern_segment_timetables = vec![vec![TimetableSegment { start: 0, len: 2 }]];
+
+        let report = g.validate();
+        assert_eq!(report.count("overtaking_trips"), 1);
+        assert_eq!(report.count("unsorted_timetables"), 1, "hop departures 200, 100");
+        // route 3, trips 0/1 and service 0 do not exist.
+        assert!(report.count("dangling_transit_refs") >= 3, "{report}");
+    }
+}
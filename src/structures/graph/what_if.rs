@@ -0,0 +1,158 @@
+//! "What-if" closures for one routing request: routes, trips and stops taken out of
+//! service and street segments closed, without touching the shared graph. Transit
+//! closures ride on a per-request copy of the realtime snapshot (as cancellations and
+//! skipped stops); street closures live in a thread-local for the duration of the
+//! query, like [`super::query_profile`], because they are read deep inside the
+//! street searches.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+
+use crate::ingestion::gtfs::TripId;
+use crate::structures::{NodeID, RealtimeIndex, StreetEdgeData};
+
+use super::Graph;
+
+thread_local! {
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+    static CLOSED_STREETS: RefCell<HashSet<(NodeID, NodeID)>> = RefCell::new(HashSet::new());
+}
+
+/// Ids as a client sends them. Routes take the `maas:route:` id, the stable
+/// `gtfs:route:` id or the GTFS `route_id`, stops the `maas:stop:` id, `gtfs:stop:` id
+/// or the (namespaced) GTFS `stop_id`, trips the `gtfs:trip:` id or the GTFS `trip_id`; a street segment is a pair of adjacent OSM node ids, closed both ways.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WhatIf {
+    pub closed_routes: Vec<String>,
+    pub closed_stops: Vec<String>,
+    pub canceled_trips: Vec<String>,
+    pub closed_streets: Vec<(i64, i64)>,
+}
+
+/// [`WhatIf`] resolved against one graph.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedWhatIf {
+    pub canceled: HashSet<TripId>,
+    pub closed_stops: HashSet<u32>,
+    pub closed_streets: HashSet<(NodeID, NodeID)>,
+}
+
+impl ResolvedWhatIf {
+    /// `rt` with the transit closures applied.
+    pub fn apply(&self, rt: &RealtimeIndex) -> RealtimeIndex {
+        rt.clone().with_closures(self.canceled.iter().copied(), self.closed_stops.iter().copied())
+    }
+}
+
+impl Graph {
+    /// Resolve every id of `w`; an unknown one is an error rather than silently
+    /// simulating nothing.
+    pub fn resolve_what_if(&self, w: &WhatIf) -> Result<ResolvedWhatIf, String> {
+        let mut out = ResolvedWhatIf::default();
+        for id in &w.closed_routes {
+            let route = self.route_index_of(id).ok_or_else(|| format!("unknown route {id}"))?;
+            out.canceled.extend(
+                (0..self.raptor.transit_trips.len() as u32)
+                    .map(TripId)
+                    .filter(|t| self.raptor.transit_trips[t.0 as usize].route_id == route),
+            );
+        }
+        for id in &w.canceled_trips {
+            out.canceled.insert(self.resolve_trip_id(id).ok_or_else(|| format!("unknown trip {id}"))?);
+        }
+        for id in &w.closed_stops {
+            out.closed_stops.insert(self.resolve_stop_id(id).ok_or_else(|| format!("unknown stop {id}"))? as u32);
+        }
+        for &(a, b) in &w.closed_streets {
+            let node = |osm: i64| self.get_osm_id(osm).ok_or_else(|| format!("unknown OSM node {osm}"));
+            let (a, b) = (node(a)?, node(b)?);
+            out.closed_streets.insert((a, b));
+            out.closed_streets.insert((b, a));
+        }
+        Ok(out)
+    }
+}
+
+/// Reopens every street when dropped (blocking workers are pooled).
+pub struct WhatIfGuard(());
+
+impl Drop for WhatIfGuard {
+    fn drop(&mut self) {
+        ACTIVE.with(|a| a.set(false));
+        CLOSED_STREETS.with(|c| c.borrow_mut().clear());
+    }
+}
+
+/// Close `what_if`'s street segments on this thread for one query.
+pub fn begin(what_if: Option<&ResolvedWhatIf>) -> WhatIfGuard {
+    let closed = what_if.map(|w| w.closed_streets.clone()).unwrap_or_default();
+    ACTIVE.with(|a| a.set(!closed.is_empty()));
+    CLOSED_STREETS.with(|c| *c.borrow_mut() = closed);
+    WhatIfGuard(())
+}
+
+/// Whether the current query closed any street (precomputed street tables such as the
+/// CCH ignore closures, so callers fall back to live searches).
+#[inline]
+pub fn streets_closed() -> bool {
+    ACTIVE.with(|a| a.get())
+}
+
+/// Whether the current query closed `street`.
+#[inline]
+pub fn street_closed(street: &StreetEdgeData) -> bool {
+    streets_closed()
+        && CLOSED_STREETS.with(|c| c.borrow().contains(&(street.origin, street.destination)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::cost::VarGen;
+    use crate::structures::{BikeAttrs, StreetProfile};
+
+    fn street(origin: usize, destination: usize) -> StreetEdgeData {
+        StreetEdgeData {
+            origin: NodeID(origin),
+            destination: NodeID(destination),
+            length: 100,
+            partial: false,
+            foot: true,
+            bike: true,
+            car: false,
+            attrs: BikeAttrs::road_default(),
+            elev_delta: 0,
+            surface_speed: 0,
+            var_gen: VarGen::NONE,
+        }
+    }
+
+    #[test]
+    fn closures_last_for_one_query_and_block_both_directions() {
+        let g = Graph::new();
+        let w = ResolvedWhatIf {
+            closed_streets: HashSet::from([(NodeID(1), NodeID(2)), (NodeID(2), NodeID(1))]),
+            ..Default::default()
+        };
+        {
+            let _g = begin(Some(&w));
+            assert!(streets_closed());
+            assert_eq!(g.edge_secs(&street(2, 1), StreetProfile::Foot), None);
+            assert!(g.edge_secs(&street(2, 3), StreetProfile::Foot).is_some());
+        }
+        assert!(!streets_closed());
+        assert!(g.edge_secs(&street(2, 1), StreetProfile::Foot).is_some());
+        let _g = begin(None);
+        assert!(!street_closed(&street(1, 2)));
+    }
+
+    #[test]
+    fn unknown_ids_are_rejected() {
+        let g = Graph::new();
+        let closed = |w: WhatIf| g.resolve_what_if(&w).unwrap_err();
+        assert_eq!(closed(WhatIf { closed_routes: vec!["R9".into()], ..Default::default() }), "unknown route R9");
+        assert_eq!(closed(WhatIf { closed_stops: vec!["maas:stop:0".into()], ..Default::default() }), "unknown stop maas:stop:0");
+        assert_eq!(closed(WhatIf { closed_streets: vec![(1, 2)], ..Default::default() }), "unknown OSM node 1");
+        assert!(g.resolve_what_if(&WhatIf::default()).is_ok());
+    }
+}
@@ -14,6 +14,7 @@ pub mod raptor;
 mod realtime;
 mod street_time;
 mod surface_speed;
+mod units;
 
 pub use address::{
     ADDRESS_ATTRIBUTION, AddressBox, AddressHit, AddressIndex, AddressIndexBuilder, AddressRecord,
@@ -32,3 +33,4 @@ pub use node::*;
 pub use realtime::*;
 pub use street_time::StreetTimeModel;
 pub use surface_speed::{SurfaceSpeedFactors, UNKNOWN_SURFACE_FACTOR};
+pub use units::{Meters, Seconds, Speed};
@@ -1,9 +1,9 @@
+#[cfg(feature = "web")]
 use async_graphql::Enum;
 use serde::{Deserialize, Serialize};
 
-#[derive(
-    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Enum, Serialize, Deserialize,
-)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(Enum))]
 pub enum Mode {
     Walk,
     Bike,
@@ -18,6 +18,41 @@ pub enum Mode {
 }
 
 impl Mode {
+    pub const ALL: [Mode; 10] = [
+        Mode::Walk,
+        Mode::Bike,
+        Mode::Car,
+        Mode::WalkTransit,
+        Mode::BikeTransit,
+        Mode::BikeToTransit,
+        Mode::BikeOnTransit,
+        Mode::CarDropOff,
+        Mode::CarPickup,
+        Mode::BikePickup,
+    ];
+
+    /// GraphQL enum name (`WALK_TRANSIT`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Walk => "WALK",
+            Mode::Bike => "BIKE",
+            Mode::Car => "CAR",
+            Mode::WalkTransit => "WALK_TRANSIT",
+            Mode::BikeTransit => "BIKE_TRANSIT",
+            Mode::BikeToTransit => "BIKE_TO_TRANSIT",
+            Mode::BikeOnTransit => "BIKE_ON_TRANSIT",
+            Mode::CarDropOff => "CAR_DROP_OFF",
+            Mode::CarPickup => "CAR_PICKUP",
+            Mode::BikePickup => "BIKE_PICKUP",
+        }
+    }
+
+    /// Inverse of [`Mode::name`], case-insensitive and whitespace-trimmed.
+    pub fn from_name(name: &str) -> Option<Mode> {
+        let upper = name.trim().to_ascii_uppercase();
+        Mode::ALL.into_iter().find(|m| m.name() == upper)
+    }
+
     /// Burden 0=foot, 1=bike, 2=car; a heavier mode must strictly beat lighter on some Pareto axis.
     pub fn burden(self) -> u8 {
         match self {
@@ -189,6 +224,16 @@ mod tests {
         assert_eq!(am.state_of(VehicleState::BikeDropped), None);
     }
 
+    #[cfg(feature = "web")]
+    #[test]
+    fn names_match_the_graphql_enum() {
+        let items = <Mode as async_graphql::resolver_utils::EnumType>::items();
+        assert_eq!(items.len(), Mode::ALL.len());
+        for item in items {
+            assert_eq!(item.value.name(), item.name);
+        }
+    }
+
     #[test]
     fn bike_transit_activates_two_bike_states() {
         let am = ActiveModes::new(&[Mode::BikeTransit]);
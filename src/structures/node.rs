@@ -45,6 +45,9 @@ pub struct TransitStopData {
     pub id: String,
     #[serde(default)]
     pub platform_code: Option<String>,
+    /// GTFS `stop_code`: the short number shown to riders on the stop sign.
+    #[serde(default)]
+    pub code: Option<String>,
     /// GTFS `parent_station` (empty/absent → `None`). Platforms sharing a non-empty value collapse into one station.
     #[serde(default)]
     pub parent_station: Option<String>,
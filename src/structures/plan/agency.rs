@@ -1,8 +1,10 @@
+#[cfg(feature = "web")]
 use async_graphql::SimpleObject;
 
 use crate::{ingestion::gtfs::AgencyId, structures::Graph};
 
-#[derive(Debug, SimpleObject)]
+#[derive(Debug)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct PlanAgency {
     pub name: String,
     pub url: String,
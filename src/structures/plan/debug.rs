@@ -0,0 +1,124 @@
+#[cfg(feature = "web")]
+use async_graphql::SimpleObject;
+
+use crate::structures::latency_profile::LatencyProfile;
+use crate::structures::query_trace::SearchTrace;
+
+/// Where one endpoint snapped onto the street network.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct SnapDebug {
+    pub node: usize,
+    pub lat: f64,
+    pub lon: f64,
+    /// Metres from the requested point to the snapped street.
+    pub distance_m: f64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PruneCount {
+    pub reason: String,
+    pub count: u64,
+}
+
+/// Tightness of the A* lower bound: `estimate / actual` per informed street search
+/// (1.0 = exact, near 0 = the search was barely guided).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct HeuristicQuality {
+    pub searches: u32,
+    pub mean_ratio: f64,
+    pub min_ratio: f64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub ms: f64,
+}
+
+/// Diagnostics of the search behind a `raptor(debug: true)` response; the same
+/// object is attached to every plan of the response.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PlanDebug {
+    pub origin: Option<SnapDebug>,
+    pub destination: Option<SnapDebug>,
+    /// Labels popped / routes scanned, as counted by the query budget.
+    pub expansions: u64,
+    pub pruned: Vec<PruneCount>,
+    /// `None` when no street search ran with a heuristic.
+    pub heuristic: Option<HeuristicQuality>,
+    pub phases: Vec<PhaseTiming>,
+    pub total_ms: f64,
+}
+
+impl PlanDebug {
+    pub fn new(
+        origin: Option<SnapDebug>,
+        destination: Option<SnapDebug>,
+        expansions: u64,
+        trace: SearchTrace,
+        total_ms: f64,
+    ) -> Self {
+        let ratios: Vec<f64> = trace
+            .heuristic
+            .iter()
+            .filter(|(_, actual)| *actual > 0.0)
+            .map(|(estimate, actual)| estimate / actual)
+            .collect();
+        let heuristic = (!ratios.is_empty()).then(|| HeuristicQuality {
+            searches: ratios.len() as u32,
+            mean_ratio: ratios.iter().sum::<f64>() / ratios.len() as f64,
+            min_ratio: ratios.iter().copied().fold(f64::INFINITY, f64::min),
+        });
+        PlanDebug {
+            origin,
+            destination,
+            expansions,
+            pruned: trace
+                .pruned
+                .into_iter()
+                .map(|(reason, count)| PruneCount { reason: reason.name().to_string(), count })
+                .collect(),
+            heuristic,
+            phases: trace.latency.as_ref().map(phases).unwrap_or_default(),
+            total_ms,
+        }
+    }
+}
+
+fn phases(profile: &LatencyProfile) -> Vec<PhaseTiming> {
+    [
+        ("discovery", profile.discovery),
+        ("grid_alloc", profile.grid_alloc),
+        ("forward", profile.forward),
+        ("extract", profile.extract),
+        ("backward", profile.backward),
+    ]
+    .into_iter()
+    .map(|(phase, d)| PhaseTiming { phase: phase.to_string(), ms: d.as_secs_f64() * 1000.0 })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::query_trace::PruneReason;
+
+    #[test]
+    fn summarises_prunes_and_heuristic_ratios() {
+        let mut trace = SearchTrace::default();
+        trace.pruned.insert(PruneReason::NodeDominated, 7);
+        trace.heuristic = vec![(50.0, 100.0), (90.0, 100.0), (10.0, 0.0)];
+        let debug = PlanDebug::new(None, None, 42, trace, 3.0);
+        assert_eq!(debug.pruned.len(), 1);
+        assert_eq!((debug.pruned[0].reason.as_str(), debug.pruned[0].count), ("node_dominated", 7));
+        let h = debug.heuristic.unwrap();
+        assert_eq!(h.searches, 2, "a zero-length search has no ratio");
+        assert!((h.mean_ratio - 0.7).abs() < 1e-9 && (h.min_ratio - 0.5).abs() < 1e-9);
+        assert!(debug.phases.is_empty());
+    }
+}
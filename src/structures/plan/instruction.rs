@@ -0,0 +1,160 @@
+#[cfg(feature = "web")]
+use async_graphql::{Enum, SimpleObject};
+
+use crate::structures::LatLng;
+use crate::structures::plan::PlanCoordinate;
+
+/// Bearing changes below this many degrees read as carrying straight on.
+const STRAIGHT_DEG: f64 = 20.0;
+/// Geometry vertices closer than this (m) are merged before measuring bearings.
+const MIN_SEGMENT_M: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Enum))]
+pub enum TurnDirection {
+    Depart,
+    SlightLeft,
+    Left,
+    SharpLeft,
+    SlightRight,
+    Right,
+    SharpRight,
+    UTurn,
+    Arrive,
+}
+
+impl TurnDirection {
+    /// Direction for a signed bearing change (degrees, clockwise positive);
+    /// `None` when it is small enough to carry straight on.
+    fn from_delta(delta: f64) -> Option<Self> {
+        let a = delta.abs();
+        let (slight, turn, sharp) = if delta > 0.0 {
+            (TurnDirection::SlightRight, TurnDirection::Right, TurnDirection::SharpRight)
+        } else {
+            (TurnDirection::SlightLeft, TurnDirection::Left, TurnDirection::SharpLeft)
+        };
+        match a {
+            a if a < STRAIGHT_DEG => None,
+            a if a < 45.0 => Some(slight),
+            a if a < 135.0 => Some(turn),
+            a if a < 170.0 => Some(sharp),
+            _ => Some(TurnDirection::UTurn),
+        }
+    }
+}
+
+/// One manoeuvre along a street leg, derived from its geometry.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct TurnInstruction {
+    pub direction: TurnDirection,
+    /// Metres from this manoeuvre to the next one (0 on `ARRIVE`).
+    pub distance: usize,
+    /// Index into the leg's `geometry` where the manoeuvre happens.
+    pub geometry_index: usize,
+    pub location: PlanCoordinate,
+}
+
+fn to_latlng(c: PlanCoordinate) -> LatLng {
+    LatLng { latitude: c.lat, longitude: c.lon }
+}
+
+/// Initial bearing from `a` to `b`, degrees clockwise from north.
+fn bearing(a: PlanCoordinate, b: PlanCoordinate) -> f64 {
+    let (la, lb) = (a.lat.to_radians(), b.lat.to_radians());
+    let dl = (b.lon - a.lon).to_radians();
+    let y = dl.sin() * lb.cos();
+    let x = la.cos() * lb.sin() - la.sin() * lb.cos() * dl.cos();
+    y.atan2(x).to_degrees()
+}
+
+/// Depart, every turn and arrive along `geometry`; empty for fewer than two points.
+pub fn turn_instructions(geometry: &[PlanCoordinate]) -> Vec<TurnInstruction> {
+    // Indices of vertices at least `MIN_SEGMENT_M` apart, so duplicated or
+    // near-coincident points do not produce spurious bearings.
+    let mut kept: Vec<usize> = Vec::with_capacity(geometry.len());
+    for (i, &c) in geometry.iter().enumerate() {
+        match kept.last() {
+            Some(&j) if to_latlng(geometry[j]).dist(to_latlng(c)) < MIN_SEGMENT_M => {}
+            _ => kept.push(i),
+        }
+    }
+    if kept.len() < 2 {
+        return vec![];
+    }
+
+    let mut out = vec![TurnInstruction {
+        direction: TurnDirection::Depart,
+        distance: 0,
+        geometry_index: kept[0],
+        location: geometry[kept[0]],
+    }];
+    let mut run = 0.0;
+    for w in kept.windows(3) {
+        let (a, b, c) = (geometry[w[0]], geometry[w[1]], geometry[w[2]]);
+        run += to_latlng(a).dist(to_latlng(b));
+        let delta = (bearing(b, c) - bearing(a, b) + 540.0) % 360.0 - 180.0;
+        if let Some(direction) = TurnDirection::from_delta(delta) {
+            out.last_mut().unwrap().distance = run.round() as usize;
+            run = 0.0;
+            out.push(TurnInstruction {
+                direction,
+                distance: 0,
+                geometry_index: w[1],
+                location: b,
+            });
+        }
+    }
+    let (pen, last) = (kept[kept.len() - 2], kept[kept.len() - 1]);
+    run += to_latlng(geometry[pen]).dist(to_latlng(geometry[last]));
+    out.last_mut().unwrap().distance = run.round() as usize;
+    out.push(TurnInstruction {
+        direction: TurnDirection::Arrive,
+        distance: 0,
+        geometry_index: last,
+        location: geometry[last],
+    });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(lat: f64, lon: f64) -> PlanCoordinate {
+        PlanCoordinate { lat, lon }
+    }
+
+    fn directions(geometry: &[PlanCoordinate]) -> Vec<TurnDirection> {
+        turn_instructions(geometry).iter().map(|i| i.direction).collect()
+    }
+
+    #[test]
+    fn right_angle_corners_become_turns() {
+        use TurnDirection::*;
+        // North, then east (right), then north again (left).
+        let g = [c(50.0, 4.0), c(50.001, 4.0), c(50.001, 4.001), c(50.002, 4.001)];
+        assert_eq!(directions(&g), vec![Depart, Right, Left, Arrive]);
+        let steps = turn_instructions(&g);
+        assert_eq!(steps.iter().map(|i| i.geometry_index).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert!((110..=112).contains(&steps[0].distance), "{}", steps[0].distance);
+        assert_eq!(steps[3].distance, 0);
+    }
+
+    #[test]
+    fn gentle_bends_and_duplicate_points_carry_straight_on() {
+        use TurnDirection::*;
+        let g = [c(50.0, 4.0), c(50.001, 4.0001), c(50.001, 4.0001), c(50.002, 4.0)];
+        let steps = turn_instructions(&g);
+        assert_eq!(steps.iter().map(|i| i.direction).collect::<Vec<_>>(), vec![Depart, Arrive]);
+        assert!((221..=225).contains(&steps[0].distance), "{}", steps[0].distance);
+        assert_eq!(directions(&[c(50.0, 4.0)]), vec![]);
+    }
+
+    #[test]
+    fn doubling_back_is_a_u_turn() {
+        use TurnDirection::*;
+        let g = [c(50.0, 4.0), c(50.001, 4.0), c(50.0, 4.00001)];
+        assert_eq!(directions(&g), vec![Depart, UTurn, Arrive]);
+    }
+}
@@ -1,39 +1,190 @@
+#[cfg(feature = "web")]
 use async_graphql::{ComplexObject, Context, Interface, Result, SimpleObject};
 use gtfs_structures::RouteType;
 
 use crate::{
     ingestion::gtfs::{TripId, TripSegment},
     structures::{
-        Graph, Mode, NodeID,
-        plan::{LegOption, PlanLegStep, PlanPlace, PlanTransitLegStep, PlanTrip, PlanWalkLegStep},
+        Graph, Mode, NodeID, RealtimeIndex,
+        plan::{
+            LegOption, PlanError, PlanLegStep, PlanPlace, PlanTransitLegStep, PlanWalkLegStep,
+        },
     },
 };
+#[cfg(feature = "web")]
+use crate::structures::plan::PlanTrip;
 
-#[derive(Debug, SimpleObject, Clone, Copy)]
+const DAY_SECS: u32 = 86_400;
+/// Departures sampled on each side of a leg's own run to estimate its headway.
+const HEADWAY_SAMPLE: usize = 3;
+/// Runs at most this far apart (and evenly spaced) are shown as "every N min".
+pub const FREQUENCY_MAX_HEADWAY_SECS: u32 = 600;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct PlanCoordinate {
     pub lat: f64,
-    #[graphql(name = "lng")]
+    #[cfg_attr(feature = "web", graphql(name = "lng"))]
     pub lon: f64,
 }
 
-#[derive(Debug, Interface, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(Interface))]
 // clippy false positive: distinct fields, lint keys on repeated `ty` values.
 #[allow(clippy::duplicated_attributes)]
-#[graphql(field(name = "length", ty = "&usize"))]
-#[graphql(field(name = "start", ty = "&u32"))]
-#[graphql(field(name = "end", ty = "&u32"))]
-#[graphql(field(name = "duration", ty = "&u32"))]
-#[graphql(field(name = "from", ty = "&PlanPlace"))]
-#[graphql(field(name = "to", ty = "&PlanPlace"))]
-#[graphql(field(name = "steps", ty = "&Vec<PlanLegStep>"))]
-#[graphql(field(name = "geometry", ty = "&Vec<PlanCoordinate>"))]
+#[cfg_attr(feature = "web", graphql(field(name = "length", ty = "&usize")))]
+#[cfg_attr(feature = "web", graphql(field(name = "start", ty = "&u32")))]
+#[cfg_attr(feature = "web", graphql(field(name = "end", ty = "&u32")))]
+#[cfg_attr(feature = "web", graphql(field(name = "duration", ty = "&u32")))]
+#[cfg_attr(feature = "web", graphql(field(name = "from", ty = "&PlanPlace")))]
+#[cfg_attr(feature = "web", graphql(field(name = "to", ty = "&PlanPlace")))]
+#[cfg_attr(feature = "web", graphql(field(name = "steps", ty = "&Vec<PlanLegStep>")))]
+#[cfg_attr(feature = "web", graphql(field(name = "geometry", ty = "&Vec<PlanCoordinate>")))]
 pub enum PlanLeg {
     Transit(PlanTransitLeg),
     Walk(PlanWalkLeg),
+    Bike(PlanBikeLeg),
+    Car(PlanCarLeg),
+    /// Only in [`Plan::timeline_legs`](crate::structures::plan::Plan::timeline_legs),
+    /// never in `legs`.
+    Wait(PlanWaitLeg),
 }
 
-#[derive(Debug, SimpleObject, Clone)]
-#[graphql(complex)]
+impl PlanLeg {
+    /// Wraps a street leg in the variant of its `street_mode`.
+    pub fn street(leg: PlanWalkLeg) -> PlanLeg {
+        match leg.street_mode {
+            Mode::Bike => PlanLeg::Bike(PlanBikeLeg { leg }),
+            Mode::Car => PlanLeg::Car(PlanCarLeg { leg }),
+            _ => PlanLeg::Walk(leg),
+        }
+    }
+
+    /// The street leg of a walk, bike or car variant.
+    pub fn as_street(&self) -> Option<&PlanWalkLeg> {
+        match self {
+            PlanLeg::Walk(leg)
+            | PlanLeg::Bike(PlanBikeLeg { leg })
+            | PlanLeg::Car(PlanCarLeg { leg }) => Some(leg),
+            PlanLeg::Transit(_) | PlanLeg::Wait(_) => None,
+        }
+    }
+
+    pub fn as_street_mut(&mut self) -> Option<&mut PlanWalkLeg> {
+        match self {
+            PlanLeg::Walk(leg)
+            | PlanLeg::Bike(PlanBikeLeg { leg })
+            | PlanLeg::Car(PlanCarLeg { leg }) => Some(leg),
+            PlanLeg::Transit(_) | PlanLeg::Wait(_) => None,
+        }
+    }
+
+    /// `(start, end)` of any leg, in seconds since midnight.
+    pub fn span(&self) -> (u32, u32) {
+        match self {
+            PlanLeg::Transit(t) => (t.start, t.end),
+            PlanLeg::Wait(w) => (w.start, w.end),
+            PlanLeg::Walk(w)
+            | PlanLeg::Bike(PlanBikeLeg { leg: w })
+            | PlanLeg::Car(PlanCarLeg { leg: w }) => (w.start, w.end),
+        }
+    }
+}
+
+/// A street leg ridden on a bike. Carries the same fields as [`PlanWalkLeg`]; the
+/// variant only tells clients what vehicle the leg uses.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PlanBikeLeg {
+    #[cfg_attr(feature = "web", graphql(flatten))]
+    pub leg: PlanWalkLeg,
+}
+
+/// A street leg driven by car; see [`PlanBikeLeg`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PlanCarLeg {
+    #[cfg_attr(feature = "web", graphql(flatten))]
+    pub leg: PlanWalkLeg,
+}
+
+/// The `PlanLeg` interface fields of a flattened street leg, for the interface's
+/// dispatch (flattened fields get no getters of their own).
+#[cfg(feature = "web")]
+macro_rules! street_leg_getters {
+    ($($ty:ty),*) => {$(
+        impl $ty {
+            pub async fn length(&self, ctx: &Context<'_>) -> Result<&usize> {
+                self.leg.length(ctx).await
+            }
+            pub async fn start(&self, ctx: &Context<'_>) -> Result<&u32> {
+                self.leg.start(ctx).await
+            }
+            pub async fn end(&self, ctx: &Context<'_>) -> Result<&u32> {
+                self.leg.end(ctx).await
+            }
+            pub async fn duration(&self, ctx: &Context<'_>) -> Result<&u32> {
+                self.leg.duration(ctx).await
+            }
+            pub async fn from(&self, ctx: &Context<'_>) -> Result<&PlanPlace> {
+                self.leg.from(ctx).await
+            }
+            pub async fn to(&self, ctx: &Context<'_>) -> Result<&PlanPlace> {
+                self.leg.to(ctx).await
+            }
+            pub async fn steps(&self, ctx: &Context<'_>) -> Result<&Vec<PlanLegStep>> {
+                self.leg.steps(ctx).await
+            }
+            pub async fn geometry(&self, ctx: &Context<'_>) -> Result<&Vec<PlanCoordinate>> {
+                self.leg.geometry(ctx).await
+            }
+        }
+    )*};
+}
+#[cfg(feature = "web")]
+street_leg_getters!(PlanBikeLeg, PlanCarLeg);
+
+/// Time spent at a stop before boarding, the transit leg's `waiting_time` as a leg of
+/// its own. Same place at both ends, no length and no steps.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PlanWaitLeg {
+    pub length: usize,
+    pub start: u32,
+    pub end: u32,
+    pub duration: u32,
+    pub from: PlanPlace,
+    pub to: PlanPlace,
+    pub steps: Vec<PlanLegStep>,
+    /// The stop's position, once.
+    pub geometry: Vec<PlanCoordinate>,
+}
+
+impl PlanWaitLeg {
+    /// The wait before boarding `next`, spanning its `waiting_time`; `None` when the
+    /// vehicle leaves on arrival.
+    pub fn before(next: &PlanTransitLeg) -> Option<PlanWaitLeg> {
+        if next.waiting_time == 0 {
+            return None;
+        }
+        let start = next.start.saturating_sub(next.waiting_time);
+        let place = PlanPlace { arrival: Some(start), departure: Some(next.start), ..next.from };
+        Some(PlanWaitLeg {
+            length: 0,
+            start,
+            end: next.start,
+            duration: next.start - start,
+            from: place,
+            to: place,
+            steps: vec![],
+            geometry: next.geometry.first().copied().into_iter().collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct PlanWalkLeg {
     pub length: usize,
     pub cycleroute_length: Option<usize>,
@@ -60,6 +211,7 @@ pub struct PlanWalkLeg {
 }
 
 impl PlanWalkLeg {
+    #[cfg_attr(not(feature = "web"), allow(dead_code))]
     fn reselect_checked(&self, option_index: i32) -> Result<PlanWalkLeg, &'static str> {
         if option_index < 0 {
             return Err("option_index out of range");
@@ -91,15 +243,25 @@ impl PlanWalkLeg {
     }
 }
 
+#[cfg(feature = "web")]
 #[ComplexObject]
 impl PlanWalkLeg {
-    async fn reselect(&self, option_index: i32) -> Result<PlanWalkLeg> {
+    /// Depart, turn-by-turn and arrive manoeuvres read off `geometry`.
+    async fn instructions(&self) -> Vec<crate::structures::plan::TurnInstruction> {
+        crate::structures::plan::turn_instructions(&self.geometry)
+    }
+
+    /// The leg with option `optionIndex` highlighted, as the same leg type.
+    async fn reselect(&self, option_index: i32) -> Result<PlanLeg> {
         self.reselect_checked(option_index)
+            .map(PlanLeg::street)
             .map_err(async_graphql::Error::new)
     }
 }
 
-#[derive(Debug, SimpleObject, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct TransferRisk {
     /// P(0.0–1.0) of boarding this vehicle on time; 1.0 = no delay model.
     pub reliability: f32,
@@ -115,8 +277,47 @@ pub struct TransferRisk {
     pub margin_secs: Option<i32>,
 }
 
-#[derive(Debug, SimpleObject, Clone)]
-#[graphql(complex)]
+#[cfg(feature = "web")]
+#[ComplexObject]
+impl TransferRisk {
+    /// P(0.0–1.0) of missing this connection: `1 − reliability`.
+    async fn missed_probability(&self) -> f32 {
+        1.0 - self.reliability
+    }
+}
+
+/// How realtime data affects a transit leg (GTFS-RT `schedule_relationship`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(async_graphql::Enum))]
+pub enum RealtimeState {
+    /// No realtime data for the leg; timetable times.
+    #[default]
+    Scheduled,
+    /// Realtime delays inform the leg's times.
+    Updated,
+    /// The trip is cancelled.
+    Canceled,
+    /// The trip runs but skips a stop of this leg (boarding, alighting or in between).
+    Modified,
+}
+
+/// Route and trip display fields copied onto a transit leg when it is built, so a
+/// client can label it without `trip { route { … } }`. Names are the feed's own;
+/// `trip.route` carries the translated ones.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PlanLegDisplay {
+    pub route_short_name: Option<String>,
+    pub headsign: Option<String>,
+    /// `RRGGBB` hex from GTFS `route_color`, as on `PlanRoute.color`.
+    pub route_color: Option<String>,
+    /// `RRGGBB` hex from GTFS `route_text_color`.
+    pub route_text_color: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct PlanTransitLeg {
     pub length: usize,
     /// Effective boarding time (secs since midnight); `scheduled_start` unless realtime shifts it.
@@ -131,6 +332,10 @@ pub struct PlanTransitLeg {
     pub scheduled_end: u32,
     /// True when realtime data informs this leg's times.
     pub realtime: bool,
+    /// Cancellation / skipped-stop state from GTFS-RT, finer than `realtime`.
+    pub realtime_state: RealtimeState,
+    /// Live load leaving the boarding stop; `None` when the feed reports none.
+    pub occupancy: Option<crate::structures::Occupancy>,
 
     pub from: PlanPlace,
     pub to: PlanPlace,
@@ -142,7 +347,7 @@ pub struct PlanTransitLeg {
     /// `None` for the first transit leg (walked directly from origin).
     pub transfer_risk: Option<TransferRisk>,
 
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub trip_id: TripId,
 
     /// Arrival (secs since midnight) of the preceding vehicle at this leg's boarding
@@ -150,21 +355,21 @@ pub struct PlanTransitLeg {
     pub preceding_arrival: Option<u32>,
 
     /// Route type of the preceding vehicle, for the correct delay-CDF on alternatives.
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub preceding_route_type: Option<RouteType>,
 
     /// This leg's route type (vehicle being boarded).
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub route_type: Option<RouteType>,
 
     /// Route type of the following transit leg; `None` when last.
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub following_route_type: Option<RouteType>,
 
     /// Outbound connection slack of the original plan (secs): next leg's scheduled
     /// boarding minus this leg's scheduled arrival at that stop. `None` when last. An
     /// alternative arriving `d` later has effective slack `following_margin_secs − d`.
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub following_margin_secs: Option<i32>,
 
     /// `None` = no information available.
@@ -173,17 +378,35 @@ pub struct PlanTransitLeg {
     /// Signed seconds subtracted from raw timetable times for an overnight-pass leg:
     /// `+86400` for a date-1 trip (raw > 24 h), `-86400` for a date+1 trip, `0`
     /// otherwise. `raw_time = displayed_time + time_shift`.
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub time_shift: i64,
+
+    /// Seconds spent at the boarding stop between arriving there (end of the previous
+    /// leg, or the plan's start) and `start`. Set by [`Plan::fill_waiting_times`].
+    ///
+    /// [`Plan::fill_waiting_times`]: crate::structures::plan::Plan::fill_waiting_times
+    pub waiting_time: u32,
+
+    #[cfg_attr(feature = "web", graphql(flatten))]
+    pub display: PlanLegDisplay,
 }
 
+#[cfg(feature = "web")]
 #[ComplexObject]
 impl PlanTransitLeg {
+    /// This leg's vehicle mode, from its route's GTFS `route_type`.
+    async fn mode(&self) -> Option<crate::structures::plan::PlanRouteType> {
+        self.route_type.map(crate::structures::plan::PlanRouteType::from_gtfs_route_type)
+    }
+
     async fn trip(&self, ctx: &Context<'_>) -> Result<Option<PlanTrip>> {
         let graph = ctx
             .data::<crate::services::scheduler::SharedGraph>()?
             .load_full();
-        Ok(PlanTrip::from_trip_id(graph.as_ref(), self.trip_id))
+        Ok(PlanTrip::from_trip_id(graph.as_ref(), self.trip_id).map(|trip| PlanTrip {
+            language: self.from.language,
+            ..trip
+        }))
     }
 
     async fn trip_id(&self, ctx: &Context<'_>) -> Result<Option<String>> {
@@ -193,57 +416,164 @@ impl PlanTransitLeg {
         Ok(graph.trip_id_str(self.trip_id).map(str::to_string))
     }
 
+    /// `realtime: true` shows today's runs at their predicted times and leaves out
+    /// cancelled ones and ones skipping this leg's stops.
     async fn previous_departures(
         &self,
         ctx: &Context<'_>,
         #[graphql(default = 0)] count: usize,
+        #[graphql(default = false)] realtime: bool,
     ) -> Result<Vec<PlanTransitLeg>> {
         let graph = ctx
             .data::<crate::services::scheduler::SharedGraph>()?
             .load_full();
-        self.previous_departures_on(&graph, count)
+        let rt = self.live_realtime(ctx, realtime)?;
+        Ok(self.previous_departures_on(&graph, count, rt.as_deref())?)
     }
 
+    /// See `previousDepartures` for `realtime`.
     async fn next_departures(
         &self,
         ctx: &Context<'_>,
         #[graphql(default = 0)] count: usize,
+        #[graphql(default = false)] realtime: bool,
     ) -> Result<Vec<PlanTransitLeg>> {
         let graph = ctx
             .data::<crate::services::scheduler::SharedGraph>()?
             .load_full();
-        self.next_departures_on(&graph, count)
+        let rt = self.live_realtime(ctx, realtime)?;
+        Ok(self.next_departures_on(&graph, count, rt.as_deref())?)
+    }
+
+    /// Typical seconds between runs of this route at the boarding stop around the
+    /// leg's departure; null when too few runs surround it.
+    async fn headway(&self, ctx: &Context<'_>) -> Result<Option<u32>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        Ok(self.headway_on(&graph).map(|(secs, _)| secs))
+    }
+
+    /// True when runs come every `FREQUENCY_MAX_HEADWAY_SECS` or more often and
+    /// evenly, so clients can show "every N min" instead of exact times.
+    async fn frequency_based(&self, ctx: &Context<'_>) -> Result<bool> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        Ok(self.headway_on(&graph).is_some_and(|(_, frequent)| frequent))
+    }
+
+    /// Approximate CO₂ (g) from the route's configured emission factor
+    /// (`route_overrides`); null when none applies.
+    async fn co2_grams(&self, ctx: &Context<'_>) -> Result<Option<f64>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        Ok(self.co2_grams_on(&graph))
+    }
+}
+
+#[cfg(feature = "web")]
+impl PlanTransitLeg {
+    /// The realtime snapshot when asked for and this leg runs on today's service day
+    /// (GTFS-RT updates are keyed by trip, not by date).
+    fn live_realtime(
+        &self,
+        ctx: &Context<'_>,
+        realtime: bool,
+    ) -> Result<Option<std::sync::Arc<RealtimeIndex>>> {
+        let today = crate::ingestion::gtfs::date_to_days(chrono::Local::now().date_naive());
+        let live = matches!(self.steps.first(), Some(PlanLegStep::Transit(s)) if s.date == today);
+        if !(realtime && live) {
+            return Ok(None);
+        }
+        Ok(Some(ctx.data::<crate::services::realtime_poller::SharedRealtime>()?.load_full()))
     }
 }
 
 impl PlanTransitLeg {
-    /// Earlier same-service + cross-route departures, scored for swap reliability.
-    pub(crate) fn previous_departures_on(
+    /// Leg length × its route's `co2_g_per_km`. A leg without a measured length is
+    /// estimated from its duration at the route's `average_speed_kmh`.
+    pub fn co2_grams_on(&self, graph: &Graph) -> Option<f64> {
+        let route = graph.get_trip(self.trip_id)?.route_id;
+        let figures = graph.raptor.route_figures.get(route.0 as usize)?;
+        let per_km = figures.co2_g_per_km?;
+        let km = if self.length > 0 {
+            self.length as f64 / 1000.0
+        } else {
+            figures.average_speed_kmh? * self.duration as f64 / 3600.0
+        };
+        Some(per_km * km)
+    }
+
+    /// Median gap between the leg's run and up to [`HEADWAY_SAMPLE`] same-day
+    /// departures on each side in its boarding timetable segment, and whether the
+    /// service is frequency-based: at least four runs sampled, a median within
+    /// [`FREQUENCY_MAX_HEADWAY_SECS`] and no gap over 1.5 times the median. `None`
+    /// below three runs.
+    pub fn headway_on(&self, graph: &Graph) -> Option<(u32, bool)> {
+        let Some(PlanLegStep::Transit(first)) = self.steps.first() else {
+            return None;
+        };
+        let own = graph.raptor.transit_departures.get(first.departure_index)?.departure;
+        let tt = first.timetable_segment;
+        let active = graph.raptor.active_services(first.date, first.weekday);
+        let mut runs: Vec<u32> = graph
+            .previous_departures(tt, &active, first.departure_index)
+            .take(HEADWAY_SAMPLE)
+            .chain(graph.next_departures(tt, &active, first.departure_index).take(HEADWAY_SAMPLE))
+            .map(|(_, dep)| dep.departure)
+            .chain(std::iter::once(own))
+            .collect();
+        runs.sort_unstable();
+        let mut gaps: Vec<u32> = runs.windows(2).map(|w| w[1] - w[0]).collect();
+        if gaps.len() < 2 {
+            return None;
+        }
+        let max = *gaps.iter().max()?;
+        gaps.sort_unstable();
+        let median = gaps[gaps.len() / 2];
+        let frequent = gaps.len() >= 3
+            && median > 0
+            && median <= FREQUENCY_MAX_HEADWAY_SECS
+            && max * 2 <= median * 3;
+        Some((median, frequent))
+    }
+
+    /// Earlier same-service + cross-route departures, scored for swap reliability. Just
+    /// after midnight these include the previous service day's late trips.
+    pub fn previous_departures_on(
         &self,
         graph: &Graph,
         count: usize,
-    ) -> Result<Vec<PlanTransitLeg>> {
+        rt: Option<&RealtimeIndex>,
+    ) -> Result<Vec<PlanTransitLeg>, PlanError> {
         if count == 0 {
             return Ok(vec![]);
         }
         let first = match self.steps[0] {
             PlanLegStep::Walk(_) => {
-                return Err(async_graphql::Error::new(
-                    "Found a walk step in a transit leg",
-                ));
+                return Err(PlanError::new("Found a walk step in a transit leg"));
             }
             PlanLegStep::Transit(first) => first,
         };
+        let active = graph.raptor.active_services(first.date, first.weekday);
         let mut results = self.find_alternatives(
             graph,
-            graph.previous_departures(
-                first.timetable_segment,
-                first.date,
-                first.weekday,
-                first.departure_index,
-            ),
+            graph.previous_departures(first.timetable_segment, &active, first.departure_index),
             count,
+            rt,
         )?;
+        // The previous service day's trips still running after midnight.
+        if first.date > 0 {
+            let raw_start = (self.start as i64 + self.time_shift) as u32;
+            let previous_day = graph
+                .departures_on(first.timetable_segment, first.date - 1, Graph::prev_weekday(first.weekday))
+                .rev()
+                .take_while(|(_, dep)| dep.departure >= DAY_SECS)
+                .filter(|(_, dep)| dep.departure - DAY_SECS < raw_start);
+            results.extend(self.alternatives_on_day(graph, previous_day, count, -1)?);
+        }
         let cross = graph.cross_route_departures(
             self.from.node_id,
             self.to.node_id,
@@ -254,51 +584,54 @@ impl PlanTransitLeg {
             false,
             count,
         );
-        results.extend(self.build_cross_route_legs(
-            graph,
-            cross,
-            self.from.node_id,
-            self.to.node_id,
-        ));
+        results.extend(
+            self.build_cross_route_legs(graph, cross, self.from.node_id, self.to.node_id)
+                .into_iter()
+                .filter_map(|l| self.with_realtime(graph, l, rt)),
+        );
         if self.time_shift != 0 {
             results = results
                 .into_iter()
                 .map(|l| shift_transit_leg(l, self.time_shift))
                 .collect();
         }
+        self.rebase_waiting_times(&mut results);
         results.sort_by_key(|l| l.start);
         results.reverse();
         results.truncate(count);
         Ok(results)
     }
 
-    /// Later same-service + cross-route departures, scored for swap reliability.
-    pub(crate) fn next_departures_on(
+    /// Later same-service + cross-route departures, scored for swap reliability; past the
+    /// last trip of the day they continue with the next service day's (times > 86400).
+    pub fn next_departures_on(
         &self,
         graph: &Graph,
         count: usize,
-    ) -> Result<Vec<PlanTransitLeg>> {
+        rt: Option<&RealtimeIndex>,
+    ) -> Result<Vec<PlanTransitLeg>, PlanError> {
         if count == 0 {
             return Ok(vec![]);
         }
         let first = match self.steps[0] {
             PlanLegStep::Walk(_) => {
-                return Err(async_graphql::Error::new(
-                    "Found a walk step in a transit leg",
-                ));
+                return Err(PlanError::new("Found a walk step in a transit leg"));
             }
             PlanLegStep::Transit(first) => first,
         };
+        let active = graph.raptor.active_services(first.date, first.weekday);
         let mut results = self.find_alternatives(
             graph,
-            graph.next_departures(
-                first.timetable_segment,
-                first.date,
-                first.weekday,
-                first.departure_index,
-            ),
+            graph.next_departures(first.timetable_segment, &active, first.departure_index),
             count,
+            rt,
         )?;
+        // Late at night the following service day's first trips come next.
+        let raw_start = (self.start as i64 + self.time_shift) as u32;
+        let next_day = graph
+            .departures_on(first.timetable_segment, first.date + 1, Graph::next_weekday(first.weekday))
+            .filter(|(_, dep)| dep.departure + DAY_SECS > raw_start);
+        results.extend(self.alternatives_on_day(graph, next_day, count, 1)?);
         let cross = graph.cross_route_departures(
             self.from.node_id,
             self.to.node_id,
@@ -309,18 +642,18 @@ impl PlanTransitLeg {
             true,
             count,
         );
-        results.extend(self.build_cross_route_legs(
-            graph,
-            cross,
-            self.from.node_id,
-            self.to.node_id,
-        ));
+        results.extend(
+            self.build_cross_route_legs(graph, cross, self.from.node_id, self.to.node_id)
+                .into_iter()
+                .filter_map(|l| self.with_realtime(graph, l, rt)),
+        );
         if self.time_shift != 0 {
             results = results
                 .into_iter()
                 .map(|l| shift_transit_leg(l, self.time_shift))
                 .collect();
         }
+        self.rebase_waiting_times(&mut results);
         results.sort_by_key(|l| l.start);
         results.truncate(count);
         Ok(results)
@@ -352,6 +685,15 @@ fn shift_transit_leg(mut l: PlanTransitLeg, s: i64) -> PlanTransitLeg {
 }
 
 impl PlanTransitLeg {
+    /// Alternatives wait from the moment this leg's traveller reaches the boarding stop;
+    /// one leaving before that moment has no wait.
+    fn rebase_waiting_times(&self, alternatives: &mut [PlanTransitLeg]) {
+        let at_stop = self.start.saturating_sub(self.waiting_time);
+        for alt in alternatives {
+            alt.waiting_time = alt.start.saturating_sub(at_stop);
+        }
+    }
+
     /// Marginal swap reliability for an alternative of this leg: `P(inbound) ×
     /// P(outbound)`, each `1.0` when this is the first/last transit leg respectively.
     /// `None` only for a lone leg (no connection to score).
@@ -419,18 +761,22 @@ impl PlanTransitLeg {
                     scheduled_start: dep,
                     scheduled_end: arr,
                     realtime: false,
+                    realtime_state: RealtimeState::Scheduled,
+                    occupancy: None,
                     length: 0,
                     from: PlanPlace {
                         departure: Some(dep),
                         arrival: self.from.arrival,
                         stop_position: self.from.stop_position,
                         node_id: boarding_node,
+                        language: None,
                     },
                     to: PlanPlace {
                         arrival: Some(arr),
                         departure: self.to.departure,
                         stop_position: self.to.stop_position,
                         node_id: alighting_node,
+                        language: None,
                     },
                     duration: arr - dep,
                     geometry: vec![],
@@ -442,19 +788,58 @@ impl PlanTransitLeg {
                     following_margin_secs: self.following_margin_secs,
                     bikes_allowed: graph.get_trip(trip_id).and_then(|t| t.bikes_allowed),
                     time_shift: 0,
+                    waiting_time: 0,
+                    display: graph.leg_display(trip_id),
                 }
             })
             .collect()
     }
 
-    pub(crate) fn find_alternatives<'a>(
+    /// [`Self::find_alternatives`] among departures of the service day `days` away from
+    /// this leg's, moved onto this leg's clock (`86400` s per day) and dated accordingly.
+    fn alternatives_on_day(
         &self,
-        graph: &'a Graph,
-        candidates: impl Iterator<Item = (usize, &'a TripSegment)>,
+        graph: &Graph,
+        candidates: impl Iterator<Item = (usize, TripSegment)>,
         count: usize,
-    ) -> Result<Vec<PlanTransitLeg>> {
+        days: i64,
+    ) -> Result<Vec<PlanTransitLeg>, PlanError> {
+        let shift = days * DAY_SECS as i64;
+        let add = |x: u32| (x as i64 + shift) as u32;
+        // Realtime updates describe today's runs, never another service day's.
+        let mut legs = self.find_alternatives(graph, candidates, count, None)?;
+        for l in &mut legs {
+            (l.start, l.end) = (add(l.start), add(l.end));
+            (l.scheduled_start, l.scheduled_end) = (add(l.scheduled_start), add(l.scheduled_end));
+            l.from.departure = l.from.departure.map(add);
+            l.to.arrival = l.to.arrival.map(add);
+            for step in &mut l.steps {
+                let PlanLegStep::Transit(ts) = step else { continue };
+                ts.place.arrival = ts.place.arrival.map(add);
+                ts.place.departure = ts.place.departure.map(add);
+                ts.scheduled_arrival = ts.scheduled_arrival.map(add);
+                ts.scheduled_departure = ts.scheduled_departure.map(add);
+                ts.date = (ts.date as i64 + days) as u32;
+                ts.weekday = if days > 0 {
+                    Graph::next_weekday(ts.weekday)
+                } else {
+                    Graph::prev_weekday(ts.weekday)
+                };
+            }
+            l.transfer_risk = self.alternative_transfer_risk(graph, l.trip_id, l.start, l.end);
+        }
+        Ok(legs)
+    }
+
+    pub fn find_alternatives(
+        &self,
+        graph: &Graph,
+        candidates: impl Iterator<Item = (usize, TripSegment)>,
+        count: usize,
+        rt: Option<&RealtimeIndex>,
+    ) -> Result<Vec<PlanTransitLeg>, PlanError> {
         let first = match self.steps[0] {
-            PlanLegStep::Walk(_) => return Err(async_graphql::Error::new("")),
+            PlanLegStep::Walk(_) => return Err(PlanError::new("")),
             PlanLegStep::Transit(first) => first,
         };
 
@@ -468,51 +853,55 @@ impl PlanTransitLeg {
             })
             .collect();
 
+        // The leg's stops, boarding first: a candidate must ride exactly these.
+        let stops: Vec<NodeID> = std::iter::once(self.from.node_id)
+            .chain(std::iter::once(first.place.node_id))
+            .chain(remaining_steps.iter().map(|s| s.place.node_id))
+            .collect();
+        let r = &graph.raptor;
+
         Ok(candidates
-            .filter_map(|(_idx, segment)| {
+            .filter_map(|(idx, segment)| {
                 let trip_id = segment.trip_id;
+                let (p, t, b) = graph.trip_run(trip_id, &stops, segment.departure)?;
+                let times = r.transit_idx_pattern_stop_times[p].of(&r.transit_pattern_stop_times);
+                let n_trips = r.transit_patterns[p].num_trips as usize;
+                let last = b + stops.len() - 1;
                 let mut current_arrival = segment.arrival;
                 let mut new_steps = Vec::with_capacity(self.steps.len());
 
-                // Derive the first step's departure index from THIS leg's first
-                // timetable segment; if the trip isn't on this segment it isn't a
-                // valid alternative here.
-                let first_slice = graph.get_transit_departure_slice(first.timetable_segment);
-                let (first_local, first_seg) = first_slice
-                    .iter()
-                    .enumerate()
-                    .find(|(_, d)| d.trip_id == trip_id)?;
-
-                new_steps.push(PlanLegStep::Transit(PlanTransitLegStep {
-                    departure_index: first.timetable_segment.start + first_local,
-                    weekday: first.weekday,
-                    date: first.date,
-                    timetable_segment: first.timetable_segment,
-                    time: first_seg.departure,
-                    place: first.place,
-                    scheduled_arrival: Some(first_seg.arrival),
-                    scheduled_departure: Some(first_seg.departure),
-                    length: first.length,
-                }));
-
-                for step in &remaining_steps {
-                    let tt = step.timetable_segment;
-                    let slice = graph.get_transit_departure_slice(tt);
-
-                    let (local_idx, seg) = slice
-                        .iter()
-                        .enumerate()
-                        .find(|(_, dep)| dep.trip_id == trip_id)?;
-
-                    current_arrival = seg.arrival;
+                for (k, step) in std::iter::once(&first).chain(remaining_steps.iter().copied()).enumerate() {
+                    let s = b + k + 1;
+                    let arr = times[s * n_trips + t].arrival;
+                    let prev_dep = times[(s - 1) * n_trips + t].departure;
+                    let dep = (s < last).then(|| times[s * n_trips + t].departure);
+                    let tt = r
+                        .transit_pattern_segment_timetables
+                        .get(p)
+                        .and_then(|segs| segs.get(s - 1).copied())
+                        .filter(|tt| tt.len > 0)
+                        .unwrap_or(step.timetable_segment);
+                    // The candidate itself indexes the boarding hop.
+                    let departure_index = if k == 0 && tt.range().contains(&idx) {
+                        idx
+                    } else {
+                        graph.departure_index_of(tt, trip_id, prev_dep)?
+                    };
+                    current_arrival = arr;
                     new_steps.push(PlanLegStep::Transit(PlanTransitLegStep {
                         length: step.length,
-                        time: seg.departure,
-                        place: step.place,
-                        scheduled_arrival: Some(seg.arrival),
-                        scheduled_departure: Some(seg.departure),
-                        timetable_segment: step.timetable_segment,
-                        departure_index: tt.start + local_idx,
+                        time: arr - prev_dep,
+                        place: PlanPlace {
+                            node_id: step.place.node_id,
+                            stop_position: Some(s as u32),
+                            arrival: Some(arr),
+                            departure: dep,
+                            language: step.place.language,
+                        },
+                        scheduled_arrival: Some(arr),
+                        scheduled_departure: dep,
+                        timetable_segment: tt,
+                        departure_index,
                         date: step.date,
                         weekday: step.weekday,
                     }));
@@ -525,7 +914,7 @@ impl PlanTransitLeg {
                     current_arrival,
                 );
 
-                Some(PlanTransitLeg {
+                let alt = PlanTransitLeg {
                     steps: new_steps,
                     trip_id,
                     start: segment.departure,
@@ -533,18 +922,22 @@ impl PlanTransitLeg {
                     scheduled_start: segment.departure,
                     scheduled_end: current_arrival,
                     realtime: false,
+                    realtime_state: RealtimeState::Scheduled,
+                    occupancy: None,
                     length: 0,
                     from: PlanPlace {
                         departure: Some(segment.departure),
                         arrival: self.from.arrival,
                         stop_position: self.from.stop_position,
                         node_id: self.from.node_id,
+                        language: None,
                     },
                     to: PlanPlace {
                         arrival: Some(current_arrival),
                         departure: self.to.departure,
                         stop_position: self.to.stop_position,
                         node_id: self.to.node_id,
+                        language: None,
                     },
                     duration: current_arrival - segment.departure,
                     geometry: self.geometry.clone(),
@@ -556,11 +949,46 @@ impl PlanTransitLeg {
                     following_margin_secs: self.following_margin_secs,
                     bikes_allowed: graph.get_trip(trip_id).and_then(|t| t.bikes_allowed),
                     time_shift: 0,
-                })
+                    waiting_time: 0,
+                    display: graph.leg_display(trip_id),
+                };
+                self.with_realtime(graph, alt, rt)
             })
             .take(count)
             .collect())
     }
+
+    /// `alt` with `rt` applied (predicted times, state, occupancy, reliability on the
+    /// predicted times); `None` when its trip is cancelled or skips the boarding or
+    /// alighting stop.
+    fn with_realtime(
+        &self,
+        graph: &Graph,
+        alt: PlanTransitLeg,
+        rt: Option<&RealtimeIndex>,
+    ) -> Option<PlanTransitLeg> {
+        let Some(rt) = rt.filter(|rt| !rt.is_empty()) else {
+            return Some(alt);
+        };
+        if rt.is_canceled(alt.trip_id) {
+            return None;
+        }
+        let skipped = |node: NodeID| {
+            graph
+                .compact_stop_of_node(node)
+                .is_some_and(|s| rt.is_skipped(alt.trip_id, s as u32))
+        };
+        if skipped(alt.from.node_id) || skipped(alt.to.node_id) {
+            return None;
+        }
+        let mut legs = [crate::structures::plan::PlanLeg::Transit(alt)];
+        graph.apply_realtime(&mut legs, rt, false);
+        let [crate::structures::plan::PlanLeg::Transit(mut alt)] = legs else {
+            unreachable!()
+        };
+        alt.transfer_risk = self.alternative_transfer_risk(graph, alt.trip_id, alt.start, alt.end);
+        Some(alt)
+    }
 }
 
 #[cfg(test)]
@@ -574,6 +1002,7 @@ mod tests {
             arrival: None,
             departure: None,
             node_id: NodeID(0),
+            language: None,
         };
         PlanWalkLeg {
             length: 50,
@@ -600,6 +1029,29 @@ mod tests {
         assert_eq!(leg.leave_by, Some(28_800));
     }
 
+    #[test]
+    fn street_legs_take_the_variant_of_their_mode() {
+        let leg = |mode| PlanWalkLeg { street_mode: mode, ..sample_walk_leg() };
+        assert!(matches!(PlanLeg::street(leg(Mode::Walk)), PlanLeg::Walk(_)));
+        assert!(matches!(PlanLeg::street(leg(Mode::Bike)), PlanLeg::Bike(_)));
+        assert!(matches!(PlanLeg::street(leg(Mode::Car)), PlanLeg::Car(_)));
+        let car = PlanLeg::street(leg(Mode::Car));
+        assert_eq!(car.as_street().map(|w| w.length), Some(50));
+        assert_eq!(car.span(), (1000, 1060));
+    }
+
+    #[test]
+    fn wait_leg_spans_the_waiting_time_at_the_boarding_stop() {
+        let mut t = sample_transit_leg();
+        assert!(PlanWaitLeg::before(&t).is_none(), "no wait when boarding on arrival");
+        t.waiting_time = 300;
+        let wait = PlanWaitLeg::before(&t).expect("a wait leg");
+        assert_eq!((wait.start, wait.end, wait.duration), (89_700, 90_000, 300));
+        assert_eq!(wait.from.node_id, t.from.node_id);
+        assert_eq!((wait.to.arrival, wait.to.departure), (Some(89_700), Some(90_000)));
+        assert_eq!(wait.length, 0);
+    }
+
     fn sample_transit_leg() -> PlanTransitLeg {
         use crate::ingestion::gtfs::TripId;
         let place = |node: usize, arr: u32, dep: u32| PlanPlace {
@@ -607,6 +1059,7 @@ mod tests {
             arrival: Some(arr),
             departure: Some(dep),
             node_id: NodeID(node),
+            language: None,
         };
         PlanTransitLeg {
             length: 0,
@@ -616,6 +1069,8 @@ mod tests {
             scheduled_start: 90_000,
             scheduled_end: 90_600,
             realtime: false,
+            realtime_state: RealtimeState::Scheduled,
+            occupancy: None,
             from: place(0, 90_000, 90_060),
             to: place(1, 90_600, 90_720),
             steps: vec![],
@@ -629,7 +1084,282 @@ mod tests {
             following_margin_secs: None,
             bikes_allowed: None,
             time_shift: 0,
+            waiting_time: 0,
+            display: Default::default(),
+        }
+    }
+
+    /// A loop route serves A→B twice per trip, so the A→B timetable holds each trip
+    /// twice; an alternative must ride the lap it boards, not the trip's first one.
+    #[test]
+    fn alternatives_ride_the_boarded_lap_of_a_loop() {
+        use crate::ingestion::gtfs::{
+            AgencyId, RouteId, RouteInfo, ServiceId, ServicePattern, StopTime, TimetableSegment,
+            TripId, TripInfo, TripSegment,
+        };
+        use crate::structures::raptor::{Lookup, PatternInfo};
+        use crate::structures::{EdgeData, LatLng, NodeData, TransitEdgeData, TransitStopData};
+
+        let mut g = Graph::new();
+        let stop = |g: &mut Graph, id: &str, lon: f64| {
+            g.add_node(NodeData::TransitStop(TransitStopData {
+                name: id.into(),
+                id: id.into(),
+                lat_lng: LatLng { latitude: 50.0, longitude: lon },
+                accessibility: gtfs_structures::Availability::Available,
+                platform_code: None,
+                code: None,
+                parent_station: None,
+            }))
+        };
+        let (a, b, c) = (stop(&mut g, "A", 4.0), stop(&mut g, "B", 4.01), stop(&mut g, "C", 4.02));
+        // Per trip: A +0, B +600, C +1200, A +1800, B +2400.
+        let laps = [0, 600, 1200, 1800, 2400];
+        let starts = [28_800, 32_400];
+        let seg = |t: usize, from: usize| TripSegment {
+            trip_id: TripId(t as u32),
+            departure: starts[t] + laps[from],
+            arrival: starts[t] + laps[from + 1],
+            service_id: ServiceId(0),
+        };
+        g.add_transit_departures(vec![seg(0, 0), seg(0, 3), seg(1, 0), seg(1, 3), seg(0, 1), seg(1, 1), seg(0, 2), seg(1, 2)]);
+        for (o, d, start, len) in [(a, b, 0, 4), (b, c, 4, 2), (c, a, 6, 2)] {
+            g.add_edge(o, EdgeData::Transit(TransitEdgeData {
+                origin: o,
+                destination: d,
+                route_id: RouteId(0),
+                timetable_segment: TimetableSegment { start, len },
+                length: 700,
+            }));
         }
+        g.add_transit_services(vec![ServicePattern {
+            days_of_week: 0x7F,
+            start_date: 0,
+            end_date: 9999,
+            added_dates: vec![],
+            removed_dates: vec![],
+        }]);
+        g.add_transit_routes(vec![RouteInfo {
+            route_short_name: "8".into(),
+            route_long_name: "Loop".into(),
+            route_type: RouteType::Bus,
+            agency_id: AgencyId(0),
+            route_color: None,
+            route_text_color: None,
+        }]);
+        let trip = TripInfo { trip_headsign: None, route_id: RouteId(0), service_id: ServiceId(0), bikes_allowed: None };
+        g.add_transit_trips(vec![trip.clone(), trip]);
+        g.extend_transit_pattern_stops(&[a, b, c, a, b]);
+        g.push_transit_idx_pattern_stops(Lookup { start: 0, len: 5 });
+        g.push_transit_pattern_trip(TripId(0));
+        g.push_transit_pattern_trip(TripId(1));
+        g.push_transit_idx_pattern_trips(Lookup { start: 0, len: 2 });
+        for lap in laps {
+            for start in starts {
+                g.push_transit_pattern_stop_time(StopTime { arrival: start + lap, departure: start + lap, ..Default::default() });
+            }
+        }
+        g.push_transit_idx_pattern_stop_times(Lookup { start: 0, len: 10 });
+        g.push_transit_pattern(PatternInfo { route: RouteId(0), num_trips: 2 });
+        g.build_raptor_index();
+
+        // Trip 0's second lap, A (08:30) → B (08:40).
+        let mut leg = sample_transit_leg();
+        (leg.start, leg.end, leg.duration) = (30_600, 31_200, 600);
+        (leg.scheduled_start, leg.scheduled_end) = (30_600, 31_200);
+        leg.from = PlanPlace { stop_position: Some(3), arrival: Some(30_600), departure: Some(30_600), node_id: a, language: None };
+        leg.to = PlanPlace { stop_position: Some(4), arrival: Some(31_200), departure: None, node_id: b, language: None };
+        leg.steps = vec![PlanLegStep::Transit(PlanTransitLegStep {
+            length: 700,
+            time: 600,
+            place: leg.to,
+            scheduled_arrival: Some(31_200),
+            scheduled_departure: None,
+            timetable_segment: TimetableSegment { start: 0, len: 4 },
+            departure_index: 1,
+            date: 100,
+            weekday: 1,
+        })];
+
+        let next = leg.next_departures_on(&g, 3, None).unwrap();
+        let runs: Vec<(u32, u32)> = next.iter().map(|l| (l.start, l.end)).collect();
+        // The day's laps run out after 09:30; the third is tomorrow's first, trip 0's
+        // first lap, shifted by a day.
+        assert_eq!(runs, vec![(32_400, 33_000), (34_200, 34_800), (28_800 + 86_400, 29_400 + 86_400)]);
+        let PlanLegStep::Transit(step) = next[1].steps[0] else { unreachable!() };
+        assert_eq!((step.departure_index, step.place.stop_position), (3, Some(4)));
+        let PlanLegStep::Transit(step) = next[2].steps[0] else { unreachable!() };
+        assert_eq!((step.date, step.weekday), (101, 2));
+        assert_eq!((step.departure_index, step.place.stop_position), (0, Some(1)));
+        assert_eq!(step.scheduled_arrival, Some(29_400 + 86_400));
+        let prev = leg.previous_departures_on(&g, 3, None).unwrap();
+        assert_eq!(prev.iter().map(|l| (l.start, l.end)).collect::<Vec<_>>(), vec![(28_800, 29_400)]);
+    }
+
+    /// One A→B route with a 10-minute trip at each of `departures` (ascending).
+    fn shuttle_graph(departures: &[u32]) -> (Graph, NodeID, NodeID) {
+        use crate::ingestion::gtfs::{
+            AgencyId, RouteId, RouteInfo, ServiceId, ServicePattern, StopTime, TimetableSegment,
+            TripId, TripInfo, TripSegment,
+        };
+        use crate::structures::raptor::{Lookup, PatternInfo};
+        use crate::structures::{EdgeData, LatLng, NodeData, TransitEdgeData, TransitStopData};
+
+        let mut g = Graph::new();
+        let stop = |g: &mut Graph, id: &str, lon: f64| {
+            g.add_node(NodeData::TransitStop(TransitStopData {
+                name: id.into(),
+                id: id.into(),
+                lat_lng: LatLng { latitude: 50.0, longitude: lon },
+                accessibility: gtfs_structures::Availability::Available,
+                platform_code: None,
+                code: None,
+                parent_station: None,
+            }))
+        };
+        let (a, b) = (stop(&mut g, "A", 4.0), stop(&mut g, "B", 4.01));
+        let n = departures.len();
+        g.add_transit_departures(
+            departures
+                .iter()
+                .enumerate()
+                .map(|(t, &dep)| TripSegment {
+                    trip_id: TripId(t as u32),
+                    departure: dep,
+                    arrival: dep + 600,
+                    service_id: ServiceId(0),
+                })
+                .collect(),
+        );
+        g.add_edge(a, EdgeData::Transit(TransitEdgeData {
+            origin: a,
+            destination: b,
+            route_id: RouteId(0),
+            timetable_segment: TimetableSegment { start: 0, len: n as u32 },
+            length: 700,
+        }));
+        g.add_transit_services(vec![ServicePattern {
+            days_of_week: 0x7F,
+            start_date: 0,
+            end_date: 9999,
+            added_dates: vec![],
+            removed_dates: vec![],
+        }]);
+        g.add_transit_routes(vec![RouteInfo {
+            route_short_name: "N".into(),
+            route_long_name: "Night".into(),
+            route_type: RouteType::Bus,
+            agency_id: AgencyId(0),
+            route_color: None,
+            route_text_color: None,
+        }]);
+        let trip = TripInfo { trip_headsign: None, route_id: RouteId(0), service_id: ServiceId(0), bikes_allowed: None };
+        g.add_transit_trips(vec![trip; n]);
+        g.extend_transit_pattern_stops(&[a, b]);
+        g.push_transit_idx_pattern_stops(Lookup { start: 0, len: 2 });
+        for t in 0..n {
+            g.push_transit_pattern_trip(TripId(t as u32));
+        }
+        g.push_transit_idx_pattern_trips(Lookup { start: 0, len: n });
+        for offset in [0, 600] {
+            for &dep in departures {
+                g.push_transit_pattern_stop_time(StopTime { arrival: dep + offset, departure: dep + offset, ..Default::default() });
+            }
+        }
+        g.push_transit_idx_pattern_stop_times(Lookup { start: 0, len: 2 * n });
+        g.push_transit_pattern(PatternInfo { route: RouteId(0), num_trips: n as u32 });
+        g.build_raptor_index();
+        (g, a, b)
+    }
+
+    /// The shuttle leg riding trip `t` (departing `dep`) on `date`.
+    fn shuttle_leg(a: NodeID, b: NodeID, t: u32, dep: u32, n: usize, date: u32) -> PlanTransitLeg {
+        use crate::ingestion::gtfs::{TimetableSegment, TripId};
+        let mut leg = sample_transit_leg();
+        leg.trip_id = TripId(t);
+        (leg.start, leg.end, leg.duration) = (dep, dep + 600, 600);
+        (leg.scheduled_start, leg.scheduled_end) = (dep, dep + 600);
+        leg.from = PlanPlace { stop_position: Some(0), arrival: Some(dep), departure: Some(dep), node_id: a, language: None };
+        leg.to = PlanPlace { stop_position: Some(1), arrival: Some(dep + 600), departure: None, node_id: b, language: None };
+        leg.steps = vec![PlanLegStep::Transit(PlanTransitLegStep {
+            length: 700,
+            time: 600,
+            place: leg.to,
+            scheduled_arrival: Some(dep + 600),
+            scheduled_departure: None,
+            timetable_segment: TimetableSegment { start: 0, len: n as u32 },
+            departure_index: t as usize,
+            date,
+            weekday: crate::ingestion::gtfs::weekday_bit(date),
+        })];
+        leg
+    }
+
+    #[test]
+    fn headway_flags_dense_even_service_as_frequency_based() {
+        // Every 4 minutes from 08:00.
+        let metro: Vec<u32> = (0..10).map(|i| 28_800 + i * 240).collect();
+        let (g, a, b) = shuttle_graph(&metro);
+        let leg = shuttle_leg(a, b, 5, metro[5], metro.len(), 100);
+        assert_eq!(leg.headway_on(&g), Some((240, true)));
+        let first = shuttle_leg(a, b, 0, metro[0], metro.len(), 100);
+        assert_eq!(first.headway_on(&g), Some((240, true)), "only later runs sampled");
+
+        // Irregular: 5, 5, 20, 5 minutes apart.
+        let bus = [28_800, 29_100, 29_400, 30_600, 30_900];
+        let (g, a, b) = shuttle_graph(&bus);
+        assert_eq!(shuttle_leg(a, b, 2, bus[2], bus.len(), 100).headway_on(&g), Some((300, false)));
+
+        // Hourly, and a two-run day with no headway at all.
+        let hourly = [28_800, 32_400, 36_000, 39_600];
+        let (g, a, b) = shuttle_graph(&hourly);
+        assert_eq!(shuttle_leg(a, b, 1, hourly[1], 4, 100).headway_on(&g), Some((3_600, false)));
+        let (g, a, b) = shuttle_graph(&hourly[..2]);
+        assert_eq!(shuttle_leg(a, b, 0, hourly[0], 2, 100).headway_on(&g), None);
+    }
+
+    #[test]
+    fn alternatives_continue_across_the_service_day_boundary() {
+        // 06:00, 23:50 and a 24:10 (00:10 next morning) trip, every day.
+        let deps = [21_600, 85_800, 87_000];
+        let (g, a, b) = shuttle_graph(&deps);
+
+        let last = shuttle_leg(a, b, 2, 87_000, 3, 100);
+        let next = last.next_departures_on(&g, 2, None).unwrap();
+        let runs: Vec<(u32, u32)> = next.iter().map(|l| (l.start, l.end)).collect();
+        assert_eq!(runs, vec![(108_000, 108_600), (172_200, 172_800)]);
+        let PlanLegStep::Transit(step) = next[0].steps[0] else { unreachable!() };
+        assert_eq!(step.date, 101);
+        assert_eq!(step.weekday, crate::ingestion::gtfs::weekday_bit(101));
+        assert_eq!(step.place.arrival, Some(108_600));
+
+        // Before the 06:00 trip, the previous day's 24:10 ran at 00:10; its 23:50 did not.
+        let early = shuttle_leg(a, b, 0, 21_600, 3, 101);
+        let prev = early.previous_departures_on(&g, 3, None).unwrap();
+        assert_eq!(prev.iter().map(|l| (l.start, l.end)).collect::<Vec<_>>(), vec![(600, 1_200)]);
+        let PlanLegStep::Transit(step) = prev[0].steps[0] else { unreachable!() };
+        assert_eq!((step.date, step.departure_index), (100, 2));
+    }
+
+    #[test]
+    fn realtime_alternatives_take_predicted_times_and_drop_cancelled_runs() {
+        use crate::ingestion::gtfs::TripId;
+        let (g, a, b) = shuttle_graph(&[28_800, 32_400, 36_000]);
+        let (sa, sb) = (g.compact_stop_of_node(a).unwrap() as u32, g.compact_stop_of_node(b).unwrap() as u32);
+        let rt = RealtimeIndex::from_updates(
+            0,
+            [((TripId(2), sa), 120), ((TripId(2), sb), 180)],
+            [TripId(1)],
+        );
+
+        let leg = shuttle_leg(a, b, 0, 28_800, 3, 100);
+        let next = leg.next_departures_on(&g, 1, Some(&rt)).unwrap();
+        assert_eq!(next.iter().map(|l| (l.start, l.end)).collect::<Vec<_>>(), vec![(36_120, 36_780)]);
+        assert_eq!((next[0].scheduled_start, next[0].scheduled_end), (36_000, 36_600));
+        assert!(next[0].realtime);
+
+        let scheduled = leg.next_departures_on(&g, 2, None).unwrap();
+        assert_eq!(scheduled.iter().map(|l| l.start).collect::<Vec<_>>(), vec![32_400, 36_000]);
     }
 
     #[test]
@@ -799,6 +1529,7 @@ mod tests {
             lat_lng: LatLng { latitude: 50.000, longitude: 4.003 },
             accessibility: Availability::Available,
             platform_code: None,
+            code: None,
             parent_station: None,
         }));
         let stop_b = g.add_node(NodeData::TransitStop(TransitStopData {
@@ -807,6 +1538,7 @@ mod tests {
             lat_lng: LatLng { latitude: 50.000, longitude: 4.030 },
             accessibility: Availability::Available,
             platform_code: None,
+            code: None,
             parent_station: None,
         }));
 
@@ -856,8 +1588,8 @@ mod tests {
             TripInfo { trip_headsign: None, route_id: RouteId(0), service_id: ServiceId(0), bikes_allowed: None },
         ]);
         g.add_transit_departures(vec![
-            TripSegment { trip_id: TripId(0), origin_stop_sequence: 0, destination_stop_sequence: 1, departure: 8 * 3600, arrival: 8 * 3600 + 600, service_id: ServiceId(0) },
-            TripSegment { trip_id: TripId(1), origin_stop_sequence: 0, destination_stop_sequence: 1, departure: 9 * 3600, arrival: 9 * 3600 + 600, service_id: ServiceId(0) },
+            TripSegment { trip_id: TripId(0), departure: 8 * 3600, arrival: 8 * 3600 + 600, service_id: ServiceId(0) },
+            TripSegment { trip_id: TripId(1), departure: 9 * 3600, arrival: 9 * 3600 + 600, service_id: ServiceId(0) },
         ]);
 
         {
@@ -897,8 +1629,8 @@ mod tests {
             })
             .expect("pre-drop plan must contain a transit leg");
 
-        let prev_before = transit_leg_before.previous_departures_on(&g, 3).expect("pre-drop previous_departures_on");
-        let next_before = transit_leg_before.next_departures_on(&g, 3).expect("pre-drop next_departures_on");
+        let prev_before = transit_leg_before.previous_departures_on(&g, 3, None).expect("pre-drop previous_departures_on");
+        let next_before = transit_leg_before.next_departures_on(&g, 3, None).expect("pre-drop next_departures_on");
         assert!(
             !next_before.is_empty(),
             "next_departures_on must return the 9:00 alternative pre-drop (2-trip fixture)"
@@ -918,8 +1650,8 @@ mod tests {
             })
             .expect("post-drop plan must contain a transit leg");
 
-        let prev_after = transit_leg_after.previous_departures_on(&g, 3).expect("post-drop previous_departures_on");
-        let next_after = transit_leg_after.next_departures_on(&g, 3).expect("post-drop next_departures_on");
+        let prev_after = transit_leg_after.previous_departures_on(&g, 3, None).expect("post-drop previous_departures_on");
+        let next_after = transit_leg_after.next_departures_on(&g, 3, None).expect("post-drop next_departures_on");
 
         let starts_ends = |legs: &[PlanTransitLeg]| -> Vec<(u32, u32)> {
             legs.iter().map(|l| (l.start, l.end)).collect()
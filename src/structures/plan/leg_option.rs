@@ -1,3 +1,4 @@
+#[cfg(feature = "web")]
 use async_graphql::SimpleObject;
 
 use crate::structures::NodeID;
@@ -5,13 +6,15 @@ use crate::structures::cost::{Axis, BalanceWeights};
 use crate::structures::plan::PlanCoordinate;
 
 /// Inclusive index range into the option's `geometry`.
-#[derive(Debug, Clone, Copy, SimpleObject)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct DismountRun {
     pub start: usize,
     pub end: usize,
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct LegOption {
     pub time: f64,
     pub dplus: f64,
@@ -27,15 +30,21 @@ pub struct LegOption {
     pub elevation_gain: Option<usize>,
     pub cycleroute_length: Option<usize>,
     pub geometry: Vec<PlanCoordinate>,
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub nodes: Vec<NodeID>,
     /// Aligned to `nodes.windows(2)`; empty when reconstructed off the full graph.
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub edges: Vec<crate::structures::StreetEdgeData>,
 }
 
 /// The ONLY place a weight is read; ties break to lowest index. `options` non-empty.
+/// The query's night safety raises the Surface weight (up to triple), as that axis
+/// prices unlit walking.
 pub fn initial_cursor(options: &[LegOption], balance: &BalanceWeights) -> usize {
+    let balance = &BalanceWeights {
+        surface: balance.surface * (1.0 + 2.0 * crate::structures::query_profile::night_safety()),
+        ..*balance
+    };
     let axes = [
         Axis::Time,
         Axis::Dplus,
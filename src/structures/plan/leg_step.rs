@@ -1,23 +1,29 @@
+#[cfg(feature = "web")]
 use async_graphql::{Interface, SimpleObject};
 
 use crate::{ingestion::gtfs::TimetableSegment, structures::plan::PlanPlace};
 
-#[derive(Clone, Copy, Debug, Interface)]
-#[graphql(field(name = "length", ty = "&usize"))]
-#[graphql(field(name = "time", ty = "&u32"))]
-#[graphql(field(name = "place", ty = "&PlanPlace"))]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "web", derive(Interface))]
+#[cfg_attr(feature = "web", graphql(field(name = "length", ty = "&usize")))]
+#[cfg_attr(feature = "web", graphql(field(name = "time", ty = "&u32")))]
+#[cfg_attr(feature = "web", graphql(field(name = "place", ty = "&PlanPlace")))]
 pub enum PlanLegStep {
     Walk(PlanWalkLegStep),
     Transit(PlanTransitLegStep),
 }
 
-#[derive(Debug, SimpleObject, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct PlanWalkLegStep {
     pub length: usize,
     pub time: u32,
     pub place: PlanPlace,
 
     pub dismount: bool,
+    /// Over synthetic connectors only (stop links, transfer edges): straight lines,
+    /// not a mapped street.
+    pub synthetic: bool,
     /// Inclusive index range into the parent leg's `geometry`.
     pub geom_start: usize,
     pub geom_end: usize,
@@ -30,13 +36,15 @@ impl PlanWalkLegStep {
             time,
             place,
             dismount: false,
+            synthetic: false,
             geom_start: 0,
             geom_end: 0,
         }
     }
 }
 
-#[derive(Debug, SimpleObject, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct PlanTransitLegStep {
     pub length: usize,
     pub time: u32,
@@ -45,12 +53,12 @@ pub struct PlanTransitLegStep {
     pub scheduled_arrival: Option<u32>,
     pub scheduled_departure: Option<u32>,
 
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub timetable_segment: TimetableSegment,
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub departure_index: usize,
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub date: u32,
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub weekday: u8,
 }
@@ -1,4 +1,7 @@
 mod agency;
+mod debug;
+mod error;
+mod instruction;
 mod leg;
 mod leg_option;
 mod leg_step;
@@ -9,6 +12,9 @@ mod route;
 mod trip;
 
 pub use agency::*;
+pub use debug::{HeuristicQuality, PhaseTiming, PlanDebug, PruneCount, SnapDebug};
+pub use error::PlanError;
+pub use instruction::{TurnDirection, TurnInstruction, turn_instructions};
 pub use leg::*;
 pub use leg_option::{DismountRun, LegOption, highlight_index, initial_cursor};
 pub use leg_step::*;
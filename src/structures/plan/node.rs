@@ -1,35 +1,139 @@
-use async_graphql::{Enum, SimpleObject};
+#[cfg(feature = "web")]
+use async_graphql::{ComplexObject, Context, Enum, Result, SimpleObject};
+use gtfs_structures::Availability;
 
-use crate::structures::{Graph, NodeID};
+use crate::{
+    ingestion::gtfs::{LanguageId, NameField},
+    ingestion::osm::StopAmenities,
+    structures::{
+        Graph, NodeID,
+        plan::{PlanRouteType, rgb_to_hex},
+    },
+};
 
-#[derive(Debug, Enum, Clone, Copy, PartialEq, PartialOrd, Ord, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "web", derive(Enum))]
 pub enum PlanNodeType {
     Osm,
     TransitStop,
 }
 
-#[derive(Debug, SimpleObject)]
+/// GTFS `wheelchair_boarding` of a transit stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Enum))]
+pub enum PlanNodeAccessibility {
+    Unknown,
+    Accessible,
+    NotAccessible,
+}
+
+impl From<Availability> for PlanNodeAccessibility {
+    fn from(a: Availability) -> Self {
+        match a {
+            Availability::Available => PlanNodeAccessibility::Accessible,
+            Availability::NotAvailable => PlanNodeAccessibility::NotAccessible,
+            _ => PlanNodeAccessibility::Unknown,
+        }
+    }
+}
+
+/// A route departing a transit stop, for labelling its marker.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PlanNodeRoute {
+    pub short_name: String,
+    pub mode: PlanRouteType,
+    /// 6-char hex, no leading `#`.
+    pub color: Option<String>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct PlanNode {
-    lat: f64,
-    #[graphql(name = "lng")]
-    lon: f64,
-    mode: PlanNodeType,
-    name: Option<String>,
+    pub lat: f64,
+    #[cfg_attr(feature = "web", graphql(name = "lng"))]
+    pub lon: f64,
+    pub mode: PlanNodeType,
+    pub name: Option<String>,
+    /// Nearest OSM address ("Street 12") for street places, when the graph was built
+    /// with `address_labels`.
+    pub label: Option<String>,
+    /// OSM facilities around a transit stop, when the graph was built with
+    /// `stop_amenities`.
+    pub amenities: Option<StopAmenities>,
+    /// GTFS `platform_code` of a transit stop ("3").
+    pub platform: Option<String>,
+    /// GTFS `stop_code` of a transit stop, as printed on the stop sign.
+    pub stop_code: Option<String>,
+    /// `None` for street places.
+    pub accessibility: Option<PlanNodeAccessibility>,
+    /// Routes departing a transit stop; empty for street places.
+    pub routes: Vec<PlanNodeRoute>,
+
+    #[cfg_attr(feature = "web", graphql(skip))]
+    pub node_id: NodeID,
+}
+
+#[cfg(feature = "web")]
+#[ComplexObject]
+impl PlanNode {
+    /// Stable node id: `maas:stop:<n>` for a transit stop (as in `gtfsStops`), else
+    /// the OSM id the street node was imported under (`map#osm#123`).
+    async fn eid(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        Ok(match graph.compact_stop_of_node(self.node_id) {
+            Some(stop) => Some(format!("maas:stop:{stop}")),
+            None => graph.node_eid(self.node_id),
+        })
+    }
+
+    /// Id that resolves to the same place on a rebuilt graph: `gtfs:stop:<stop_id>`
+    /// for a transit stop, the OSM id for a street node. See `resolveStableIds`.
+    async fn stable_id(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        Ok(graph.node_stable_id(self.node_id))
+    }
 }
 
 impl PlanNode {
     pub fn from_node_id(g: &Graph, id: NodeID) -> Option<PlanNode> {
+        Self::from_node_id_in(g, id, None)
+    }
+
+    /// [`PlanNode::from_node_id`] with the stop name translated into `language`.
+    pub fn from_node_id_in(g: &Graph, id: NodeID, language: Option<LanguageId>) -> Option<PlanNode> {
         let (loc, name) = g.plan_node_info(id)?;
-        let mode = if name.is_some() {
-            PlanNodeType::TransitStop
+        let name = name.map(|n| g.localized(NameField::StopName, &n, language));
+        let (mode, label, amenities) = if name.is_some() {
+            (PlanNodeType::TransitStop, None, g.stop_amenities(loc))
         } else {
-            PlanNodeType::Osm
+            (PlanNodeType::Osm, g.address_label(loc), None)
         };
         Some(PlanNode {
             lat: loc.latitude,
             lon: loc.longitude,
             mode,
             name,
+            label,
+            amenities,
+            platform: g.platform_code_of_node(id).map(str::to_string),
+            stop_code: g.stop_code_of_node(id).map(str::to_string),
+            accessibility: g.stop_accessibility_of_node(id).map(PlanNodeAccessibility::from),
+            routes: g
+                .routes_departing_node(id)
+                .into_iter()
+                .map(|r| PlanNodeRoute {
+                    short_name: r.route_short_name.clone(),
+                    mode: PlanRouteType::from_gtfs_route_type(r.route_type),
+                    color: r.route_color.map(|(r, g, b)| rgb_to_hex(r, g, b)),
+                })
+                .collect(),
+            node_id: id,
         })
     }
 }
@@ -1,18 +1,27 @@
+#[cfg(feature = "web")]
 use async_graphql::{ComplexObject, Context, Result, SimpleObject};
 
-use crate::structures::{NodeID, plan::PlanNode};
+use crate::{ingestion::gtfs::LanguageId, structures::NodeID};
+#[cfg(feature = "web")]
+use crate::structures::plan::PlanNode;
 
-#[derive(Debug, SimpleObject, Clone, Copy)]
-#[graphql(complex)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct PlanPlace {
     pub stop_position: Option<u32>,
     pub arrival: Option<u32>,
     pub departure: Option<u32>,
 
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub node_id: NodeID,
+
+    /// Language the query asked names in (`raptor(language:)`); `None` = feed text.
+    #[cfg_attr(feature = "web", graphql(skip))]
+    pub language: Option<LanguageId>,
 }
 
+#[cfg(feature = "web")]
 #[ComplexObject]
 impl PlanPlace {
     pub async fn node(&self, ctx: &Context<'_>) -> Result<Option<PlanNode>> {
@@ -20,7 +29,7 @@ impl PlanPlace {
             .data::<crate::services::scheduler::SharedGraph>()?
             .load_full();
 
-        Ok(PlanNode::from_node_id(graph.as_ref(), self.node_id))
+        Ok(PlanNode::from_node_id_in(graph.as_ref(), self.node_id, self.language))
     }
 
     pub async fn stop_id(&self, ctx: &Context<'_>) -> Result<Option<String>> {
@@ -38,4 +47,13 @@ impl PlanPlace {
 
         Ok(graph.platform_code_of_node(self.node_id).map(str::to_string))
     }
+
+    /// GTFS `stop_code`, the number printed on the stop sign.
+    pub async fn stop_code(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+
+        Ok(graph.stop_code_of_node(self.node_id).map(str::to_string))
+    }
 }
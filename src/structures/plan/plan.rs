@@ -1,16 +1,26 @@
-use async_graphql::SimpleObject;
+#[cfg(feature = "web")]
+use async_graphql::{ComplexObject, SimpleObject};
 
-use crate::structures::Mode;
-use crate::structures::plan::{PlanCoordinate, PlanLeg};
+use std::sync::Arc;
 
-#[derive(Debug, Clone, SimpleObject)]
+use crate::ingestion::gtfs::LanguageId;
+use crate::structures::{Mode, ModePreference};
+use gtfs_structures::RouteType;
+use crate::structures::plan::{
+    PlanBikeLeg, PlanCarLeg, PlanCoordinate, PlanDebug, PlanLeg, PlanLegStep, PlanRouteType,
+    PlanWaitLeg,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct ArrivalScenario {
     pub time: u32,
     /// Individual, not cumulative; sum = 1.0.
     pub probability: f32,
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct AccessAlternative {
     pub mode: Mode,
     pub start: u32,
@@ -26,7 +36,8 @@ pub struct AccessAlternative {
 /// equals `known_euros` when no cap is in force. `unknown_operators` each contribute
 /// an incomparable price token so the plan is never price-dominated by a modeled-only
 /// plan.
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct PlanPrice {
     pub known_euros: f64,
     pub capped_euros: f64,
@@ -42,7 +53,8 @@ pub struct PlanPrice {
 
 /// One fare-breakdown line. `euros` is 0.00 when covered; `coverage` is `None` when
 /// paid, else the reason it is free.
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
 pub struct FareBreakdownItem {
     pub operator: String,
     pub description: String,
@@ -50,7 +62,19 @@ pub struct FareBreakdownItem {
     pub coverage: Option<String>,
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+/// A caveat attached to a plan; `code` is stable, `message` is for humans.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+pub struct PlanWarning {
+    pub code: String,
+    pub message: String,
+    /// Metres, for warnings about a distance (`FAR_SNAP`).
+    pub distance_m: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct Plan {
     pub legs: Vec<PlanLeg>,
     pub start: u32,
@@ -65,6 +89,249 @@ pub struct Plan {
     pub expected_end: u32,
     /// `None` when fares disabled; `Some` (post-hoc from boardings) when enabled.
     pub price: Option<PlanPrice>,
+    /// Search diagnostics, only on `raptor(debug: true)`.
+    pub debug: Option<Arc<PlanDebug>>,
+    /// Caveats about the data the plan was found on, e.g. a date no feed covers.
+    pub warnings: Vec<PlanWarning>,
+    /// Opaque share id, re-hydrated by `planById`; `None` on stitched (via) plans.
+    pub itinerary_id: Option<String>,
+}
+
+impl Plan {
+    /// P(0.0–1.0) of making every transfer: the product of each boarding's
+    /// `transfer_risk.reliability`, connections taken as independent. 1.0 without
+    /// transfers or delay models.
+    pub fn connection_reliability(&self) -> f32 {
+        self.legs
+            .iter()
+            .filter_map(|l| match l {
+                PlanLeg::Transit(t) => t.transfer_risk.as_ref(),
+                _ => None,
+            })
+            .map(|r| r.reliability.clamp(0.0, 1.0))
+            .product()
+    }
+
+    /// Seconds the per-mode preferences `modes` (`default_routing.modes`) add to this
+    /// plan's arrival when ranked: each transit leg's [`ModePreference::extra_secs`] over
+    /// its ride. Unlisted modes are neutral.
+    pub fn mode_cost(&self, modes: &[(RouteType, ModePreference)]) -> f64 {
+        if modes.is_empty() {
+            return 0.0;
+        }
+        self.legs
+            .iter()
+            .filter_map(|l| match l {
+                PlanLeg::Transit(t) => {
+                    let (_, pref) = modes.iter().find(|(m, _)| Some(*m) == t.route_type)?;
+                    Some(pref.extra_secs(t.end.saturating_sub(t.start)))
+                }
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// `end` shifted by [`Self::mode_cost`]: the arrival the planner ranks and prunes on.
+    pub fn ranked_end(&self, modes: &[(RouteType, ModePreference)]) -> i64 {
+        self.end as i64 + self.mode_cost(modes).round() as i64
+    }
+
+    /// Set each transit leg's `waiting_time` from the end of the leg before it (the
+    /// plan's start for a leading transit leg). Run once leg times are final.
+    pub fn fill_waiting_times(&mut self) {
+        let mut cursor = self.start;
+        for leg in &mut self.legs {
+            match leg {
+                PlanLeg::Transit(t) => {
+                    t.waiting_time = t.start.saturating_sub(cursor);
+                    cursor = t.end;
+                }
+                other => cursor = other.span().1,
+            }
+        }
+    }
+
+    /// Resolve every place's name (and, through transit legs, route names) in
+    /// `language`. Applied after the plan cache, which stays language-neutral.
+    pub fn set_language(&mut self, language: LanguageId) {
+        for leg in &mut self.legs {
+            let (from, to, steps) = match leg {
+                PlanLeg::Walk(w)
+                | PlanLeg::Bike(PlanBikeLeg { leg: w })
+                | PlanLeg::Car(PlanCarLeg { leg: w }) => (&mut w.from, &mut w.to, &mut w.steps),
+                PlanLeg::Transit(t) => (&mut t.from, &mut t.to, &mut t.steps),
+                PlanLeg::Wait(w) => (&mut w.from, &mut w.to, &mut w.steps),
+            };
+            from.language = Some(language);
+            to.language = Some(language);
+            for step in steps {
+                match step {
+                    PlanLegStep::Walk(s) => s.place.language = Some(language),
+                    PlanLegStep::Transit(s) => s.place.language = Some(language),
+                }
+            }
+        }
+    }
+
+    /// `end − start` in seconds.
+    pub fn total_duration(&self) -> u32 {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Metres covered on foot (walk legs only, not bike or car legs).
+    pub fn total_walk_distance(&self) -> usize {
+        self.legs
+            .iter()
+            .map(|l| match l {
+                PlanLeg::Walk(w) if w.street_mode == Mode::Walk => w.length,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Metres walked from the origin to the first boarding; `None` without transit.
+    pub fn walk_to_first_stop(&self) -> Option<usize> {
+        Self::walk_until_transit(self.legs.iter())
+    }
+
+    /// Metres walked from the last alighting to the destination; `None` without transit.
+    pub fn walk_from_last_stop(&self) -> Option<usize> {
+        Self::walk_until_transit(self.legs.iter().rev())
+    }
+
+    fn walk_until_transit<'a>(legs: impl Iterator<Item = &'a PlanLeg>) -> Option<usize> {
+        let mut metres = 0;
+        for leg in legs {
+            match leg {
+                PlanLeg::Transit(_) => return Some(metres),
+                PlanLeg::Walk(w) if w.street_mode == Mode::Walk => metres += w.length,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Vehicle changes: transit legs minus one.
+    pub fn transfer_count(&self) -> usize {
+        self.legs
+            .iter()
+            .filter(|l| matches!(l, PlanLeg::Transit(_)))
+            .count()
+            .saturating_sub(1)
+    }
+
+    /// Street modes of the plan's non-transit legs, in order of first use.
+    pub fn street_modes_used(&self) -> Vec<Mode> {
+        let mut out = Vec::new();
+        for l in &self.legs {
+            if let Some(w) = l.as_street()
+                && !out.contains(&w.street_mode)
+            {
+                out.push(w.street_mode);
+            }
+        }
+        out
+    }
+
+    /// Vehicle types ridden, in order of first use.
+    pub fn transit_modes_used(&self) -> Vec<PlanRouteType> {
+        let mut out = Vec::new();
+        for l in &self.legs {
+            if let PlanLeg::Transit(t) = l
+                && let Some(rt) = t.route_type.map(PlanRouteType::from_gtfs_route_type)
+                && !out.contains(&rt)
+            {
+                out.push(rt);
+            }
+        }
+        out
+    }
+
+    /// Total seconds spent waiting at boarding stops.
+    pub fn total_waiting_time(&self) -> u32 {
+        self.legs
+            .iter()
+            .map(|l| match l {
+                PlanLeg::Transit(t) => t.waiting_time,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// `legs` with a [`PlanWaitLeg`] before every transit leg that has a `waiting_time`.
+    pub fn timeline_legs(&self) -> Vec<PlanLeg> {
+        let mut out = Vec::with_capacity(self.legs.len() * 2);
+        for leg in &self.legs {
+            if let PlanLeg::Transit(t) = leg
+                && let Some(wait) = PlanWaitLeg::before(t)
+            {
+                out.push(PlanLeg::Wait(wait));
+            }
+            out.push(leg.clone());
+        }
+        out
+    }
+}
+
+#[cfg(feature = "web")]
+#[ComplexObject]
+impl Plan {
+    /// P(0.0–1.0) of making every transfer; see [`Plan::connection_reliability`].
+    async fn reliability(&self) -> f32 {
+        self.connection_reliability()
+    }
+
+    /// P(0.0–1.0) that at least one connection is missed.
+    async fn missed_connection_probability(&self) -> f32 {
+        1.0 - self.connection_reliability()
+    }
+
+    /// `end − start` in seconds.
+    async fn duration(&self) -> u32 {
+        self.total_duration()
+    }
+
+    /// Metres walked (bike and car legs excluded).
+    async fn walk_distance(&self) -> usize {
+        self.total_walk_distance()
+    }
+
+    /// Metres walked to the first stop; `null` for a plan without transit.
+    async fn access_walk_distance(&self) -> Option<usize> {
+        self.walk_to_first_stop()
+    }
+
+    /// Metres walked from the last stop; `null` for a plan without transit.
+    async fn egress_walk_distance(&self) -> Option<usize> {
+        self.walk_from_last_stop()
+    }
+
+    /// Number of vehicle changes.
+    async fn transfers(&self) -> usize {
+        self.transfer_count()
+    }
+
+    /// Street modes used, in order of first use.
+    async fn street_modes(&self) -> Vec<Mode> {
+        self.street_modes_used()
+    }
+
+    /// Vehicle types ridden, in order of first use.
+    async fn transit_modes(&self) -> Vec<PlanRouteType> {
+        self.transit_modes_used()
+    }
+
+    /// Total seconds waiting at boarding stops; with the legs' durations it makes up
+    /// `end − start`.
+    async fn waiting_time(&self) -> u32 {
+        self.total_waiting_time()
+    }
+
+    /// `legs` with the waits before boardings as `PlanWaitLeg`s, for clients drawing
+    /// the whole journey as a timeline.
+    async fn timeline(&self) -> Vec<PlanLeg> {
+        self.timeline_legs()
+    }
 }
 
 // Debug types used by the raptorExplain GraphQL query.
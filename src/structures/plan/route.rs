@@ -1,12 +1,16 @@
+#[cfg(feature = "web")]
 use async_graphql::{ComplexObject, Context, Enum, Result, SimpleObject};
 use gtfs_structures::RouteType;
 
 use crate::{
-    ingestion::gtfs::{AgencyId, RouteId},
-    structures::{Graph, plan::PlanAgency},
+    ingestion::gtfs::{AgencyId, LanguageId, NameField, RouteId},
+    structures::Graph,
 };
+#[cfg(feature = "web")]
+use crate::structures::plan::PlanAgency;
 
-#[derive(Debug, Enum, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "web", derive(Enum))]
 pub enum PlanRouteType {
     Tramway,
     Subway,
@@ -41,19 +45,60 @@ impl PlanRouteType {
     }
 }
 
-#[derive(Debug, SimpleObject)]
-#[graphql(complex)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Enum))]
+pub enum PlanWeekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl PlanWeekday {
+    const ALL: [PlanWeekday; 7] = [
+        PlanWeekday::Monday,
+        PlanWeekday::Tuesday,
+        PlanWeekday::Wednesday,
+        PlanWeekday::Thursday,
+        PlanWeekday::Friday,
+        PlanWeekday::Saturday,
+        PlanWeekday::Sunday,
+    ];
+
+    /// Days set in a GTFS `days_of_week` mask (Mon=0x01 .. Sun=0x40), Monday first.
+    pub fn from_mask(mask: u8) -> Vec<PlanWeekday> {
+        Self::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, d)| d)
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct PlanRoute {
     pub short_name: String,
     pub long_name: String,
     pub mode: PlanRouteType,
     pub color: Option<String>,
     pub text_color: Option<String>,
+    /// Bikes ride along on this route type per `default_routing.bike_route_types`;
+    /// `None` = no policy (individual trips may still allow them).
+    pub bike_allowed: Option<bool>,
 
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub agency_id: AgencyId,
+    #[cfg_attr(feature = "web", graphql(skip))]
+    pub route_id: RouteId,
 }
 
+#[cfg(feature = "web")]
 #[ComplexObject]
 impl PlanRoute {
     pub async fn agency(&self, ctx: &Context<'_>) -> Result<Option<PlanAgency>> {
@@ -66,6 +111,50 @@ impl PlanRoute {
             Some(self.agency_id),
         ))
     }
+
+    /// `gtfs:route:<route_id>`, valid across graph rebuilds unlike `id`.
+    pub async fn stable_id(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        Ok(graph.route_stable_id(self.route_id))
+    }
+
+    /// Seconds from midnight at which the first run of `date` (`YYYY-MM-DD`, default
+    /// today) leaves its origin stop; null when the route does not run that day.
+    pub async fn first_departure(&self, ctx: &Context<'_>, date: Option<String>) -> Result<Option<u32>> {
+        Ok(self.service_span(ctx, date)?.map(|(first, _)| first))
+    }
+
+    /// Like `firstDeparture`, for the last run; past 86400 when it leaves after
+    /// midnight.
+    pub async fn last_departure(&self, ctx: &Context<'_>, date: Option<String>) -> Result<Option<u32>> {
+        Ok(self.service_span(ctx, date)?.map(|(_, last)| last))
+    }
+
+    /// Weekdays on which any of the route's services run, per the GTFS calendar.
+    pub async fn operating_days(&self, ctx: &Context<'_>) -> Result<Vec<PlanWeekday>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        Ok(PlanWeekday::from_mask(graph.route_operating_days(self.route_id)))
+    }
+}
+
+#[cfg(feature = "web")]
+impl PlanRoute {
+    fn service_span(&self, ctx: &Context<'_>, date: Option<String>) -> Result<Option<(u32, u32)>> {
+        let graph = ctx
+            .data::<crate::services::scheduler::SharedGraph>()?
+            .load_full();
+        let date = match date {
+            Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+                .map_err(|e| async_graphql::Error::new(format!("Invalid date '{}': {}", d, e)))?,
+            None => chrono::Local::now().date_naive(),
+        };
+        let days = crate::ingestion::gtfs::date_to_days(date);
+        Ok(graph.route_service_span(self.route_id, days, crate::ingestion::gtfs::weekday_bit(days)))
+    }
 }
 
 pub(crate) fn rgb_to_hex(r: u8, g: u8, b: u8) -> String {
@@ -74,15 +163,27 @@ pub(crate) fn rgb_to_hex(r: u8, g: u8, b: u8) -> String {
 
 impl PlanRoute {
     pub fn from_route_id(g: &Graph, id: Option<RouteId>) -> Option<PlanRoute> {
-        let route = g.get_route(id?)?;
+        Self::from_route_id_in(g, id, None)
+    }
+
+    /// [`PlanRoute::from_route_id`] with the route names translated into `language`.
+    pub fn from_route_id_in(
+        g: &Graph,
+        id: Option<RouteId>,
+        language: Option<LanguageId>,
+    ) -> Option<PlanRoute> {
+        let id = id?;
+        let route = g.get_route(id)?;
 
         Some(PlanRoute {
-            short_name: route.route_short_name.clone(),
-            long_name: route.route_long_name.clone(),
+            short_name: g.localized(NameField::RouteShortName, &route.route_short_name, language),
+            long_name: g.localized(NameField::RouteLongName, &route.route_long_name, language),
             mode: PlanRouteType::from_gtfs_route_type(route.route_type),
             color: route.route_color.map(|(r, g, b)| rgb_to_hex(r, g, b)),
             text_color: route.route_text_color.map(|(r, g, b)| rgb_to_hex(r, g, b)),
+            bike_allowed: g.route_type_allows_bikes(route.route_type),
             agency_id: route.agency_id,
+            route_id: id,
         })
     }
 }
@@ -108,4 +209,93 @@ mod tests {
     fn rgb_to_hex_mixed_color() {
         assert_eq!(rgb_to_hex(173, 216, 230), "ADD8E6");
     }
+
+    #[test]
+    fn weekday_mask_lists_days_monday_first() {
+        assert_eq!(
+            PlanWeekday::from_mask(0x60),
+            vec![PlanWeekday::Saturday, PlanWeekday::Sunday]
+        );
+        assert_eq!(PlanWeekday::from_mask(0x7F).len(), 7);
+        assert!(PlanWeekday::from_mask(0).is_empty());
+    }
+
+    #[test]
+    fn service_span_and_operating_days_follow_the_calendar() {
+        use crate::ingestion::gtfs::{RouteInfo, ServiceId, ServicePattern, StopTime, TripId, TripInfo};
+        use crate::structures::raptor::{Lookup, PatternInfo};
+
+        // Day 0 is Saturday 2000-01-01: day 1 a Sunday, day 2 a Monday.
+        let (saturday, sunday, monday, next_sunday) = (0, 1, 2, 8);
+        let bit = crate::ingestion::gtfs::weekday_bit;
+        let mut g = Graph::new();
+        let service = |days_of_week, added_dates| ServicePattern {
+            days_of_week,
+            start_date: 0,
+            end_date: 9999,
+            added_dates,
+            removed_dates: vec![],
+        };
+        g.add_transit_services(vec![service(0x1F, vec![]), service(0x20, vec![sunday])]);
+        g.add_transit_routes(vec![RouteInfo {
+            route_short_name: "N".into(),
+            route_long_name: String::new(),
+            route_type: RouteType::Bus,
+            agency_id: AgencyId(0),
+            route_color: None,
+            route_text_color: None,
+        }]);
+        let trip = |service| TripInfo {
+            trip_headsign: None,
+            route_id: RouteId(0),
+            service_id: ServiceId(service),
+            bikes_allowed: None,
+        };
+        g.add_transit_trips(vec![trip(0), trip(0), trip(1)]);
+        // Weekdays at 06:00 and 23:30, weekends at 01:10 past midnight.
+        let departures = [6 * 3600, 23 * 3600 + 1800, 25 * 3600 + 600];
+        for t in 0..3 {
+            g.push_transit_pattern_trip(TripId(t));
+        }
+        g.push_transit_idx_pattern_trips(Lookup { start: 0, len: 3 });
+        for offset in [0, 600] {
+            for dep in departures {
+                g.push_transit_pattern_stop_time(StopTime {
+                    arrival: dep + offset,
+                    departure: dep + offset,
+                    ..Default::default()
+                });
+            }
+        }
+        g.push_transit_idx_pattern_stop_times(Lookup { start: 0, len: 6 });
+        g.push_transit_pattern(PatternInfo { route: RouteId(0), num_trips: 3 });
+
+        let span = |day| g.route_service_span(RouteId(0), day, bit(day));
+        assert_eq!(span(monday), Some((6 * 3600, 23 * 3600 + 1800)));
+        assert_eq!(span(saturday), Some((25 * 3600 + 600, 25 * 3600 + 600)));
+        assert_eq!(span(sunday), span(saturday), "added date runs the weekend service");
+        assert_eq!(span(next_sunday), None);
+        assert_eq!(g.route_service_span(RouteId(1), monday, bit(monday)), None);
+        assert_eq!(g.route_operating_days(RouteId(0)), 0x7F);
+    }
+
+    #[test]
+    fn bike_allowed_follows_route_type_policy() {
+        use crate::ingestion::gtfs::RouteInfo;
+        let mut g = Graph::new();
+        let route = |route_type| RouteInfo {
+            route_short_name: String::new(),
+            route_long_name: String::new(),
+            route_type,
+            agency_id: AgencyId(0),
+            route_color: None,
+            route_text_color: None,
+        };
+        g.add_transit_routes(vec![route(RouteType::Rail), route(RouteType::Bus)]);
+        g.set_bike_route_types(vec![RouteType::Rail]);
+
+        let bike_allowed = |id| PlanRoute::from_route_id(&g, Some(RouteId(id))).unwrap().bike_allowed;
+        assert_eq!(bike_allowed(0), Some(true));
+        assert_eq!(bike_allowed(1), None, "no policy for buses");
+    }
 }
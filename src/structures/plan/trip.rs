@@ -1,19 +1,28 @@
+#[cfg(feature = "web")]
 use async_graphql::{ComplexObject, Context, Result, SimpleObject};
 
 use crate::{
-    ingestion::gtfs::{RouteId, TripId},
-    structures::{Graph, plan::PlanRoute},
+    ingestion::gtfs::{LanguageId, RouteId, TripId},
+    structures::Graph,
 };
+#[cfg(feature = "web")]
+use crate::structures::plan::PlanRoute;
 
-#[derive(Debug, SimpleObject)]
-#[graphql(complex)]
+#[derive(Debug)]
+#[cfg_attr(feature = "web", derive(SimpleObject))]
+#[cfg_attr(feature = "web", graphql(complex))]
 pub struct PlanTrip {
     pub headsign: Option<String>,
 
-    #[graphql(skip)]
+    #[cfg_attr(feature = "web", graphql(skip))]
     pub route_id: RouteId,
+
+    /// Language for the route names; see [`crate::structures::plan::PlanPlace::language`].
+    #[cfg_attr(feature = "web", graphql(skip))]
+    pub language: Option<LanguageId>,
 }
 
+#[cfg(feature = "web")]
 #[ComplexObject]
 impl PlanTrip {
     pub async fn route(&self, ctx: &Context<'_>) -> Result<Option<PlanRoute>> {
@@ -21,9 +30,10 @@ impl PlanTrip {
             .data::<crate::services::scheduler::SharedGraph>()?
             .load_full();
 
-        Ok(PlanRoute::from_route_id(
+        Ok(PlanRoute::from_route_id_in(
             graph.as_ref(),
             Some(self.route_id),
+            self.language,
         ))
     }
 }
@@ -35,6 +45,7 @@ impl PlanTrip {
         Some(PlanTrip {
             headsign: trip.trip_headsign.clone(),
             route_id: trip.route_id,
+            language: None,
         })
     }
 }
@@ -28,6 +28,47 @@ pub enum TripStatus {
     Canceled,
 }
 
+/// Passenger load reported by GTFS-RT `OccupancyStatus`, emptiest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "web", derive(async_graphql::Enum))]
+pub enum Occupancy {
+    Empty,
+    ManySeatsAvailable,
+    FewSeatsAvailable,
+    StandingRoomOnly,
+    CrushedStandingRoomOnly,
+    Full,
+    NotAcceptingPassengers,
+}
+
+impl Occupancy {
+    /// Raw protobuf value → occupancy; `NO_DATA_AVAILABLE`, `NOT_BOARDABLE` and
+    /// unknown values carry no load information and map to `None`.
+    pub fn from_gtfs_rt(status: i32) -> Option<Self> {
+        use crate::ingestion::realtime::proto::vehicle_position::OccupancyStatus as S;
+        match S::try_from(status).ok()? {
+            S::Empty => Some(Self::Empty),
+            S::ManySeatsAvailable => Some(Self::ManySeatsAvailable),
+            S::FewSeatsAvailable => Some(Self::FewSeatsAvailable),
+            S::StandingRoomOnly => Some(Self::StandingRoomOnly),
+            S::CrushedStandingRoomOnly => Some(Self::CrushedStandingRoomOnly),
+            S::Full => Some(Self::Full),
+            S::NotAcceptingPassengers => Some(Self::NotAcceptingPassengers),
+            S::NoDataAvailable | S::NotBoardable => None,
+        }
+    }
+
+    /// Crowding steps past "seats available": 0 while seats remain, 3 once full.
+    pub fn crowding_level(self) -> u32 {
+        match self {
+            Self::Empty | Self::ManySeatsAvailable | Self::FewSeatsAvailable => 0,
+            Self::StandingRoomOnly => 1,
+            Self::CrushedStandingRoomOnly => 2,
+            Self::Full | Self::NotAcceptingPassengers => 3,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct RealtimeIndex {
     /// Delay (secs, positive = late) per `(trip, stop)`. Stop key is the RAPTOR
@@ -44,6 +85,10 @@ pub struct RealtimeIndex {
     /// `(trip, compact_stop)` pairs the feed marked SKIPPED; routing must not board
     /// or alight here, as at a CANCELED trip. Empty on the inert default.
     skipped: HashSet<(TripId, u32)>,
+    /// Departure occupancy per `(trip, compact_stop)` (StopTimeUpdate
+    /// `departure_occupancy_status`); `trip_occupancy` is the vehicle-wide fallback.
+    occupancy: HashMap<(TripId, u32), Occupancy>,
+    trip_occupancy: HashMap<TripId, Occupancy>,
     /// Unix seconds this snapshot was produced (0 for the empty index).
     pub generated_at: i64,
     /// Staleness TTL (secs): routing ignores this snapshot once
@@ -53,6 +98,11 @@ pub struct RealtimeIndex {
     /// ONLY via `delay_with_sticky`/`status_with_sticky`, NEVER by routing; excluded
     /// from `is_empty`/`len` so a sticky-only index is invisible to planning.
     sticky_delays: HashMap<(TripId, u32), (i32, i64)>,
+    /// Compact stops named by an active `ACCESSIBILITY_ISSUE` alert (typically an
+    /// elevator outage): the wheelchair profile neither boards nor alights there.
+    inaccessible_stops: HashSet<u32>,
+    /// Compact stops closed by a what-if request: no trip boards or alights there.
+    closed_stops: HashSet<u32>,
 }
 
 impl RealtimeIndex {
@@ -73,7 +123,11 @@ impl RealtimeIndex {
             generated_at,
             max_age_secs: 0,
             skipped: HashSet::new(),
+            occupancy: HashMap::new(),
+            trip_occupancy: HashMap::new(),
             sticky_delays: HashMap::new(),
+            inaccessible_stops: HashSet::new(),
+            closed_stops: HashSet::new(),
         }
     }
 
@@ -91,7 +145,11 @@ impl RealtimeIndex {
             generated_at,
             max_age_secs: 0,
             skipped: HashSet::new(),
+            occupancy: HashMap::new(),
+            trip_occupancy: HashMap::new(),
             sticky_delays: HashMap::new(),
+            inaccessible_stops: HashSet::new(),
+            closed_stops: HashSet::new(),
         }
     }
 
@@ -110,7 +168,11 @@ impl RealtimeIndex {
             generated_at,
             max_age_secs: 0,
             skipped: HashSet::new(),
+            occupancy: HashMap::new(),
+            trip_occupancy: HashMap::new(),
             sticky_delays: HashMap::new(),
+            inaccessible_stops: HashSet::new(),
+            closed_stops: HashSet::new(),
         }
     }
 
@@ -130,7 +192,11 @@ impl RealtimeIndex {
             generated_at,
             max_age_secs: 0,
             skipped: HashSet::new(),
+            occupancy: HashMap::new(),
+            trip_occupancy: HashMap::new(),
             sticky_delays: HashMap::new(),
+            inaccessible_stops: HashSet::new(),
+            closed_stops: HashSet::new(),
         }
     }
 
@@ -151,7 +217,11 @@ impl RealtimeIndex {
             generated_at,
             max_age_secs: 0,
             skipped: HashSet::new(),
+            occupancy: HashMap::new(),
+            trip_occupancy: HashMap::new(),
             sticky_delays: HashMap::new(),
+            inaccessible_stops: HashSet::new(),
+            closed_stops: HashSet::new(),
         }
     }
 
@@ -170,15 +240,59 @@ impl RealtimeIndex {
         self
     }
 
+    pub fn with_inaccessible_stops(mut self, stops: HashSet<u32>) -> Self {
+        self.inaccessible_stops = stops;
+        self
+    }
+
+    #[inline]
+    pub fn stop_inaccessible(&self, stop: u32) -> bool {
+        self.inaccessible_stops.contains(&stop)
+    }
+
+    /// This snapshot plus what-if closures: `canceled` trips never run and no trip
+    /// serves `closed_stops`.
+    pub fn with_closures(
+        mut self,
+        canceled: impl IntoIterator<Item = TripId>,
+        closed_stops: impl IntoIterator<Item = u32>,
+    ) -> Self {
+        self.canceled.extend(canceled);
+        self.closed_stops.extend(closed_stops);
+        self
+    }
+
     #[inline]
     pub fn is_skipped(&self, trip: TripId, stop: u32) -> bool {
-        self.skipped.contains(&(trip, stop))
+        self.skipped.contains(&(trip, stop)) || self.closed_stops.contains(&stop)
     }
 
     pub fn skipped_len(&self) -> usize {
         self.skipped.len()
     }
 
+    pub fn with_occupancy(
+        mut self,
+        per_stop: HashMap<(TripId, u32), Occupancy>,
+        per_trip: HashMap<TripId, Occupancy>,
+    ) -> Self {
+        self.occupancy = per_stop;
+        self.trip_occupancy = per_trip;
+        self
+    }
+
+    /// Occupancy of `trip` leaving compact `stop`, falling back to the trip-wide value.
+    pub fn occupancy(&self, trip: TripId, stop: u32) -> Option<Occupancy> {
+        self.occupancy
+            .get(&(trip, stop))
+            .or_else(|| self.trip_occupancy.get(&trip))
+            .copied()
+    }
+
+    pub fn occupancy_len(&self) -> usize {
+        self.occupancy.len() + self.trip_occupancy.len()
+    }
+
     pub fn with_sticky_delays(mut self, sticky: HashMap<(TripId, u32), (i32, i64)>) -> Self {
         self.sticky_delays = sticky;
         self
@@ -275,6 +389,11 @@ impl RealtimeIndex {
         self.alerts.len()
     }
 
+    /// Every service alert active at `now_unix_secs`.
+    pub fn active_alerts(&self, now_unix_secs: u64) -> impl Iterator<Item = &ServiceAlert> {
+        self.alerts.iter().filter(move |a| a.is_active(now_unix_secs))
+    }
+
     /// Compact stop index for `(trip, parent_station)` on a platform-level RT stop.
     /// `parent_station` is the stop_id prefix before the last `_`.
     #[inline]
@@ -295,15 +414,7 @@ impl RealtimeIndex {
         now_unix_secs: u64,
     ) -> impl Iterator<Item = &'a ServiceAlert> {
         self.alerts.iter().filter(move |alert| {
-            let active = if alert.active_period.is_empty() {
-                true
-            } else {
-                alert.active_period.iter().any(|(start, end)| {
-                    start.map_or(true, |s| now_unix_secs >= s)
-                        && end.map_or(true, |e| now_unix_secs < e)
-                })
-            };
-            if !active {
+            if !alert.is_active(now_unix_secs) {
                 return false;
             }
             alert.informed_entity.iter().any(|e| {
@@ -321,7 +432,12 @@ impl RealtimeIndex {
     }
 
     pub fn is_empty(&self) -> bool {
-        self.delays.is_empty() && self.canceled.is_empty() && self.skipped.is_empty()
+        self.delays.is_empty()
+            && self.canceled.is_empty()
+            && self.skipped.is_empty()
+            && self.occupancy_len() == 0
+            && self.inaccessible_stops.is_empty()
+            && self.closed_stops.is_empty()
     }
 }
 
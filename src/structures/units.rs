@@ -0,0 +1,81 @@
+//! Units for street travel times. Edge lengths are whole meters, speeds are configured
+//! in m/s and held as whole mm/s, and a traversal takes whole seconds, floored. The
+//! m/s → mm/s step rounds to nearest (`4.1 * 1000.0` is `4099.999…` in `f64`) and
+//! never yields 0, so no caller divides by zero or drifts by a millimetre per second.
+
+use std::ops::Add;
+
+/// Whole meters (edge and sub-edge lengths).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Meters(pub usize);
+
+/// Whole seconds (street traversal times).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Seconds(pub u32);
+
+impl Add for Seconds {
+    type Output = Seconds;
+
+    /// Saturating: an unreachable sum stays at `u32::MAX` rather than wrapping.
+    fn add(self, rhs: Seconds) -> Seconds {
+        Seconds(self.0.saturating_add(rhs.0))
+    }
+}
+
+/// A positive speed, held in whole millimetres per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Speed(u32);
+
+impl Speed {
+    /// Slowest representable speed; non-finite, zero and negative inputs clamp here.
+    pub const MIN: Speed = Speed(1);
+
+    pub fn from_mps(mps: f64) -> Speed {
+        let mms = (mps * 1000.0).round();
+        if mms.is_finite() && mms >= 1.0 {
+            Speed(mms.min(u32::MAX as f64) as u32)
+        } else {
+            Speed::MIN
+        }
+    }
+
+    pub fn mm_per_s(self) -> u32 {
+        self.0
+    }
+
+    pub fn mps(self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+
+    /// Time to cover `d`, floored to whole seconds and saturating at `u32::MAX`.
+    pub fn travel_time(self, d: Meters) -> Seconds {
+        let secs = (d.0 as u64).saturating_mul(1000) / self.0 as u64;
+        Seconds(secs.min(u32::MAX as u64) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_rounds_to_the_nearest_millimetre() {
+        assert_eq!(Speed::from_mps(4.1).mm_per_s(), 4100);
+        assert_eq!(Speed::from_mps(1.4).mm_per_s(), 1400);
+        assert_eq!(Speed::from_mps(4.1).travel_time(Meters(41)), Seconds(10));
+    }
+
+    #[test]
+    fn degenerate_speeds_clamp_instead_of_dividing_by_zero() {
+        for mps in [0.0, -1.0, 0.0001, f64::NAN, f64::INFINITY] {
+            assert_eq!(Speed::from_mps(mps), Speed::MIN, "{mps}");
+        }
+        assert_eq!(Speed::MIN.travel_time(Meters(usize::MAX)), Seconds(u32::MAX));
+    }
+
+    #[test]
+    fn travel_time_floors_and_sums_saturate() {
+        assert_eq!(Speed::from_mps(1.5).travel_time(Meters(100)), Seconds(66));
+        assert_eq!(Seconds(u32::MAX - 1) + Seconds(5), Seconds(u32::MAX));
+    }
+}
@@ -0,0 +1,180 @@
+//! wasm-bindgen surface for running the planner client-side (browser, edge worker):
+//! `initFromBytes` restores a `graph.bin` handed over as bytes, `plan` routes on it.
+//! No file system, network or threads are touched; plans are returned as JSON.
+//!
+//! Build: `cargo build --lib --target wasm32-unknown-unknown --no-default-features --features wasm`.
+
+use std::cell::RefCell;
+
+use chrono::{NaiveDate, NaiveTime};
+use serde_json::{Value, json};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    routing::routing_raptor::{self, RouteQuery},
+    services::persistence,
+    structures::{
+        Graph, Mode, RealtimeIndex,
+        plan::{Plan, PlanBikeLeg, PlanCarLeg, PlanCoordinate, PlanLeg, PlanRoute, PlanTrip},
+    },
+};
+
+thread_local! {
+    // wasm32 is single-threaded; one graph per module instance.
+    static GRAPH: RefCell<Option<Graph>> = const { RefCell::new(None) };
+}
+
+/// Restores a `graph.bin` (schema version checked, fingerprint ignored), replacing
+/// any previously loaded graph.
+#[wasm_bindgen(js_name = initFromBytes)]
+pub fn init_from_bytes(bytes: &[u8]) -> Result<(), JsError> {
+    let graph = persistence::graph_from_bytes(bytes).map_err(|e| JsError::new(&e))?;
+    GRAPH.with(|g| *g.borrow_mut() = Some(graph));
+    Ok(())
+}
+
+/// `date` is `YYYY-MM-DD`, `time` is `HH:MM`, `modes` a comma-separated list of
+/// GraphQL mode names (`WALK_TRANSIT,BIKE`); `None` uses the routing defaults.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn plan(
+    from_lat: f64,
+    from_lng: f64,
+    to_lat: f64,
+    to_lng: f64,
+    date: &str,
+    time: &str,
+    modes: Option<String>,
+) -> Result<String, JsError> {
+    GRAPH.with(|g| {
+        let g = g.borrow();
+        let graph = g
+            .as_ref()
+            .ok_or_else(|| JsError::new("no graph loaded; call initFromBytes first"))?;
+        plan_json(graph, from_lat, from_lng, to_lat, to_lng, date, time, modes.as_deref())
+            .map_err(|e| JsError::new(&e))
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plan_json(
+    graph: &Graph,
+    from_lat: f64,
+    from_lng: f64,
+    to_lat: f64,
+    to_lng: f64,
+    date: &str,
+    time: &str,
+    modes: Option<&str>,
+) -> Result<String, String> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("invalid date '{date}': {e}"))?;
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|e| format!("invalid time '{time}': {e}"))?;
+    let modes = modes
+        .map(|list| {
+            list.split(',')
+                .map(|m| Mode::from_name(m).ok_or_else(|| format!("unknown mode '{m}'")))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let query = RouteQuery {
+        from_lat,
+        from_lng,
+        to_lat,
+        to_lng,
+        date,
+        time,
+        window_minutes: None,
+        min_access_secs: None,
+        arrival_slack_secs: None,
+        unrestricted_transfers: None,
+        use_cch_access: None,
+        reliability_bucket_edges: None,
+        modes,
+        bike_profile: None,
+        terminal_deadline: false,
+        onboard_origin: None,
+        from_station_id: None,
+        to_station_id: None,
+        profile_latency: None,
+        fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
+    };
+    let plans = routing_raptor::route(graph, &query, &RealtimeIndex::new())
+        .map_err(|e| e.to_string())?;
+    let plans: Vec<Value> = plans.iter().map(|p| plan_value(graph, p)).collect();
+    Ok(Value::Array(plans).to_string())
+}
+
+fn geometry_value(geometry: &[PlanCoordinate]) -> Value {
+    geometry.iter().map(|c| json!([c.lat, c.lon])).collect()
+}
+
+fn plan_value(graph: &Graph, plan: &Plan) -> Value {
+    let legs: Vec<Value> = plan
+        .legs
+        .iter()
+        .map(|leg| match leg {
+            PlanLeg::Walk(w)
+            | PlanLeg::Bike(PlanBikeLeg { leg: w })
+            | PlanLeg::Car(PlanCarLeg { leg: w }) => json!({
+                "kind": "street",
+                "mode": w.street_mode.name(),
+                "start": w.start,
+                "end": w.end,
+                "length": w.length,
+                "geometry": geometry_value(&w.geometry),
+            }),
+            PlanLeg::Transit(t) => {
+                let trip = PlanTrip::from_trip_id(graph, t.trip_id);
+                let route = trip
+                    .as_ref()
+                    .and_then(|trip| PlanRoute::from_route_id(graph, Some(trip.route_id)));
+                json!({
+                    "kind": "transit",
+                    "start": t.start,
+                    "end": t.end,
+                    "realtime": t.realtime,
+                    "length": t.length,
+                    "route": route.map(|r| r.short_name),
+                    "headsign": trip.and_then(|trip| trip.headsign),
+                    "geometry": geometry_value(&t.geometry),
+                })
+            }
+            PlanLeg::Wait(_) => unreachable!("wait legs only appear in Plan::timeline"),
+        })
+        .collect();
+    json!({
+        "mode": plan.mode.name(),
+        "start": plan.start,
+        "end": plan.end,
+        "expectedEnd": plan.expected_end,
+        "legs": legs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_json_rejects_bad_inputs_before_routing() {
+        let g = Graph::new();
+        let err = plan_json(&g, 0.0, 0.0, 0.0, 0.0, "2026-13-01", "08:00", None).unwrap_err();
+        assert!(err.starts_with("invalid date"), "got: {err}");
+        let err = plan_json(&g, 0.0, 0.0, 0.0, 0.0, "2026-01-05", "8h", None).unwrap_err();
+        assert!(err.starts_with("invalid time"), "got: {err}");
+        let err =
+            plan_json(&g, 0.0, 0.0, 0.0, 0.0, "2026-01-05", "08:00", Some("walk,TELEPORT"))
+                .unwrap_err();
+        assert_eq!(err, "unknown mode 'TELEPORT'");
+    }
+}
@@ -0,0 +1,576 @@
+//! Opt-in demand analytics (`server.analytics`). Every plan request is reduced to an
+//! anonymised record — origin/destination snapped to `cell_m` grid cells, departure
+//! hour, modes, latency and result count; no exact coordinates, client address or
+//! wall-clock time beyond the day — appended as JSON lines to a size-rotated file by a
+//! writer thread (so no resolver waits on the disk) and folded into in-memory aggregates for the `analyticsStats` admin query. The most recent
+//! requests are also kept in memory, stamped to the minute, for the dashboard queries
+//! over a `since` window (`topOriginDestinations`, `slowestQueries`, `errorRates`).
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, Timelike};
+use serde::Serialize;
+
+use crate::{
+    routing::routing_raptor::RouteQuery,
+    structures::{AnalyticsConfig, plan::Plan},
+};
+
+/// Latencies kept for the percentiles (most recent first out).
+const LATENCY_WINDOW: usize = 10_000;
+/// Distinct OD cell pairs counted; further new pairs are only counted in the totals.
+const MAX_OD_PAIRS: usize = 50_000;
+/// Requests kept for the `since` queries (oldest first out).
+const RECENT_WINDOW: usize = 100_000;
+/// Records queued for the writer thread; past this they are dropped from the file (the
+/// in-memory aggregates still count them) rather than blocking a request.
+const WRITE_QUEUE: usize = 4096;
+
+/// Centre of a `cell_m` grid cell, rounded so records carry no finer position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Cell {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+/// Anonymised fields of an in-flight request, see [`Analytics::start`].
+pub struct PendingRecord {
+    source: &'static str,
+    from: (i64, i64),
+    to: (i64, i64),
+    hour: u32,
+    modes: Vec<&'static str>,
+    started: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PlanRecord {
+    day: String,
+    source: &'static str,
+    from: Cell,
+    to: Cell,
+    hour: u32,
+    modes: Vec<&'static str>,
+    latency_ms: f64,
+    results: usize,
+    error: bool,
+}
+
+/// One request of the recent window; `at` is unix seconds rounded down to the minute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentRequest {
+    pub at: u64,
+    pub source: &'static str,
+    pub from: Cell,
+    pub to: Cell,
+    pub hour: u32,
+    pub modes: Vec<&'static str>,
+    pub latency_ms: f64,
+    pub results: usize,
+    pub error: bool,
+}
+
+/// Outcome counts of the recent requests of one source (`graphql`, `grpc`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutcomeCounts {
+    pub source: String,
+    pub requests: u64,
+    pub empty: u64,
+    pub errors: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OdCount {
+    pub from: Cell,
+    pub to: Cell,
+    pub count: u64,
+}
+
+/// Aggregates since startup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalyticsSnapshot {
+    pub requests: u64,
+    pub empty: u64,
+    pub errors: u64,
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    /// Requests per departure hour, index 0..24.
+    pub by_hour: Vec<u64>,
+    /// Requests per mode name (`default` when the request named none).
+    pub by_mode: Vec<(String, u64)>,
+    /// Busiest OD cell pairs, most requested first.
+    pub top_od: Vec<OdCount>,
+}
+
+#[derive(Default)]
+struct State {
+    requests: u64,
+    empty: u64,
+    errors: u64,
+    latencies: VecDeque<f64>,
+    by_hour: [u64; 24],
+    by_mode: BTreeMap<&'static str, u64>,
+    od: HashMap<(i64, i64, i64, i64), u64>,
+    recent: VecDeque<RecentRequest>,
+}
+
+/// Shared by GraphQL and gRPC; `disabled()` records nothing. Dropping it drains the
+/// queued records to the file.
+pub struct Analytics {
+    config: Option<AnalyticsConfig>,
+    state: Mutex<State>,
+    writer: Option<SyncSender<PlanRecord>>,
+    writer_thread: Option<JoinHandle<()>>,
+}
+
+fn cell_index(cell_m: f64, lat: f64, lng: f64) -> (i64, i64) {
+    let row = (lat * 111_320.0 / cell_m).floor() as i64;
+    let centre_lat = (row as f64 + 0.5) * cell_m / 111_320.0;
+    let cos = centre_lat.to_radians().cos().max(0.2);
+    let col = (lng * 111_320.0 * cos / cell_m).floor() as i64;
+    (row, col)
+}
+
+fn cell_centre(cell_m: f64, (row, col): (i64, i64)) -> Cell {
+    let lat = (row as f64 + 0.5) * cell_m / 111_320.0;
+    let cos = lat.to_radians().cos().max(0.2);
+    let lng = (col as f64 + 0.5) * cell_m / (111_320.0 * cos);
+    let round = |v: f64| (v * 1e5).round() / 1e5;
+    Cell {
+        lat: round(lat),
+        lng: round(lng),
+    }
+}
+
+impl Analytics {
+    pub fn new(config: &AnalyticsConfig) -> Self {
+        if !config.enabled {
+            return Analytics::disabled();
+        }
+        let (tx, rx) = sync_channel(WRITE_QUEUE);
+        let file_config = config.clone();
+        let writer_thread = std::thread::Builder::new()
+            .name("analytics-writer".into())
+            .spawn(move || write_records(rx, &file_config));
+        let (writer, writer_thread) = match writer_thread {
+            Ok(handle) => (Some(tx), Some(handle)),
+            Err(e) => {
+                tracing::warn!("analytics: no writer thread, records stay in memory ({e})");
+                (None, None)
+            }
+        };
+        Analytics {
+            config: Some(config.clone()),
+            state: Mutex::new(State::default()),
+            writer,
+            writer_thread,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Analytics {
+            config: None,
+            state: Mutex::new(State::default()),
+            writer: None,
+            writer_thread: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// Captures the anonymised request before routing consumes `query`; `None` when
+    /// disabled. `source` names the API (`graphql`, `grpc`).
+    pub fn start(&self, source: &'static str, query: &RouteQuery) -> Option<PendingRecord> {
+        let config = self.config.as_ref()?;
+        let cell_m = config.cell_m.max(1.0);
+        Some(PendingRecord {
+            source,
+            from: cell_index(cell_m, query.from_lat, query.from_lng),
+            to: cell_index(cell_m, query.to_lat, query.to_lng),
+            hour: query.time.hour(),
+            modes: match &query.modes {
+                Some(m) if !m.is_empty() => m.iter().map(|m| m.name()).collect(),
+                _ => vec!["default"],
+            },
+            started: Instant::now(),
+        })
+    }
+
+    /// Records a request started with [`Analytics::start`]; `Err` for a failed search.
+    pub fn finish(&self, pending: Option<PendingRecord>, result: Result<&[Plan], ()>) {
+        if let Some(pending) = pending {
+            let latency = pending.started.elapsed();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            self.record(pending, latency, result, now);
+        }
+    }
+
+    fn record(
+        &self,
+        pending: PendingRecord,
+        latency: Duration,
+        result: Result<&[Plan], ()>,
+        now_unix_secs: u64,
+    ) {
+        let Some(config) = &self.config else {
+            return;
+        };
+        let cell_m = config.cell_m.max(1.0);
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.requests += 1;
+        match result {
+            Ok([]) => state.empty += 1,
+            Ok(_) => {}
+            Err(()) => state.errors += 1,
+        }
+        if state.latencies.len() == LATENCY_WINDOW {
+            state.latencies.pop_front();
+        }
+        state.latencies.push_back(latency_ms);
+        state.by_hour[pending.hour as usize % 24] += 1;
+        for m in &pending.modes {
+            *state.by_mode.entry(m).or_default() += 1;
+        }
+        let key = (pending.from.0, pending.from.1, pending.to.0, pending.to.1);
+        if let Some(n) = state.od.get_mut(&key) {
+            *n += 1;
+        } else if state.od.len() < MAX_OD_PAIRS {
+            state.od.insert(key, 1);
+        }
+
+        let record = PlanRecord {
+            day: Local::now().format("%Y-%m-%d").to_string(),
+            source: pending.source,
+            from: cell_centre(cell_m, pending.from),
+            to: cell_centre(cell_m, pending.to),
+            hour: pending.hour,
+            modes: pending.modes,
+            latency_ms: (latency_ms * 10.0).round() / 10.0,
+            results: result.map(<[Plan]>::len).unwrap_or(0),
+            error: result.is_err(),
+        };
+        if state.recent.len() == RECENT_WINDOW {
+            state.recent.pop_front();
+        }
+        state.recent.push_back(RecentRequest {
+            at: now_unix_secs - now_unix_secs % 60,
+            source: record.source,
+            from: record.from,
+            to: record.to,
+            hour: record.hour,
+            modes: record.modes.clone(),
+            latency_ms: record.latency_ms,
+            results: record.results,
+            error: record.error,
+        });
+        drop(state);
+        if let Some(writer) = &self.writer
+            && let Err(TrySendError::Full(_)) = writer.try_send(record)
+        {
+            tracing::warn!("analytics: writer behind, dropping record");
+        }
+    }
+
+    /// Aggregates since startup, with at most `top` OD pairs.
+    pub fn snapshot(&self, top: usize) -> AnalyticsSnapshot {
+        let cell_m = self.config.as_ref().map_or(1.0, |c| c.cell_m.max(1.0));
+        let Ok(state) = self.state.lock() else {
+            return AnalyticsSnapshot::default();
+        };
+        let mut latencies: Vec<f64> = state.latencies.iter().copied().collect();
+        latencies.sort_by(f64::total_cmp);
+        let rank = |p: f64| {
+            if latencies.is_empty() {
+                0.0
+            } else {
+                latencies[((p * latencies.len() as f64).ceil() as usize).clamp(1, latencies.len()) - 1]
+            }
+        };
+        let mut od: Vec<_> = state.od.iter().map(|(k, &n)| (*k, n)).collect();
+        od.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        AnalyticsSnapshot {
+            requests: state.requests,
+            empty: state.empty,
+            errors: state.errors,
+            latency_p50_ms: rank(0.5),
+            latency_p95_ms: rank(0.95),
+            by_hour: state.by_hour.to_vec(),
+            by_mode: state.by_mode.iter().map(|(m, &n)| (m.to_string(), n)).collect(),
+            top_od: od
+                .into_iter()
+                .take(top)
+                .map(|((fr, fc, tr, tc), count)| OdCount {
+                    from: cell_centre(cell_m, (fr, fc)),
+                    to: cell_centre(cell_m, (tr, tc)),
+                    count,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Analytics {
+    /// Recent requests at or after `since` (unix seconds), oldest first.
+    fn recent_since(&self, since: u64) -> Vec<RecentRequest> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        // `at` only grows, so the window is a suffix.
+        let first = state.recent.partition_point(|r| r.at < since);
+        state.recent.range(first..).cloned().collect()
+    }
+
+    /// Busiest OD cell pairs among the recent requests since `since`.
+    pub fn top_od_since(&self, since: u64, top: usize) -> Vec<OdCount> {
+        let mut counts: Vec<OdCount> = Vec::new();
+        for r in self.recent_since(since) {
+            match counts.iter_mut().find(|c| c.from == r.from && c.to == r.to) {
+                Some(c) => c.count += 1,
+                None => counts.push(OdCount { from: r.from, to: r.to, count: 1 }),
+            }
+        }
+        counts.sort_by(|a, b| {
+            let key = |c: &OdCount| (c.from.lat, c.from.lng, c.to.lat, c.to.lng);
+            b.count.cmp(&a.count).then(key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal))
+        });
+        counts.truncate(top);
+        counts
+    }
+
+    /// The `limit` slowest recent requests since `since`, slowest first.
+    pub fn slowest_since(&self, since: u64, limit: usize) -> Vec<RecentRequest> {
+        let mut recent = self.recent_since(since);
+        recent.sort_by(|a, b| b.latency_ms.total_cmp(&a.latency_ms).then(b.at.cmp(&a.at)));
+        recent.truncate(limit);
+        recent
+    }
+
+    /// Outcome counts of the recent requests since `since`: the total (source `all`)
+    /// first, then one entry per source.
+    pub fn outcomes_since(&self, since: u64) -> Vec<OutcomeCounts> {
+        let mut by_source: BTreeMap<&'static str, OutcomeCounts> = BTreeMap::new();
+        let mut total = OutcomeCounts { source: "all".into(), ..Default::default() };
+        for r in self.recent_since(since) {
+            let entry = by_source
+                .entry(r.source)
+                .or_insert_with(|| OutcomeCounts { source: r.source.into(), ..Default::default() });
+            for c in [&mut total, entry] {
+                c.requests += 1;
+                c.empty += u64::from(!r.error && r.results == 0);
+                c.errors += u64::from(r.error);
+            }
+        }
+        std::iter::once(total).chain(by_source.into_values()).collect()
+    }
+}
+
+impl Drop for Analytics {
+    fn drop(&mut self) {
+        self.writer = None;
+        if let Some(handle) = self.writer_thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The open analytics file and its size, owned by the writer thread.
+#[derive(Default)]
+struct LogFile {
+    file: Option<BufWriter<File>>,
+    written: u64,
+}
+
+/// Writer thread body: appends records until every sender is gone. The buffer is
+/// flushed whenever the queue runs dry, so a burst costs one write, not one per line.
+fn write_records(rx: Receiver<PlanRecord>, config: &AnalyticsConfig) {
+    let mut log = LogFile::default();
+    while let Ok(record) = rx.recv() {
+        let mut next = Some(record);
+        while let Some(record) = next {
+            if let Err(e) = append(&mut log, config, &record) {
+                tracing::warn!("analytics: dropping record ({e})");
+                log.file = None;
+            }
+            next = rx.try_recv().ok();
+        }
+        if let Some(file) = log.file.as_mut()
+            && let Err(e) = file.flush()
+        {
+            tracing::warn!("analytics: flush failed ({e})");
+            log.file = None;
+        }
+    }
+}
+
+/// Appends one JSON line, rotating `path` → `path.1` → … → `path.<keep>` once it
+/// exceeds `max_bytes`.
+fn append(state: &mut LogFile, config: &AnalyticsConfig, record: &PlanRecord) -> std::io::Result<()> {
+    if state.file.is_some() && state.written >= config.max_bytes {
+        state.file = None;
+        for i in (1..config.keep.max(1)).rev() {
+            let _ = fs::rename(format!("{}.{i}", config.path), format!("{}.{}", config.path, i + 1));
+        }
+        fs::rename(&config.path, format!("{}.1", config.path))?;
+    }
+    if state.file.is_none() {
+        if let Some(dir) = std::path::Path::new(&config.path).parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        state.written = file.metadata()?.len();
+        state.file = Some(BufWriter::new(file));
+    }
+    let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    if let Some(file) = state.file.as_mut() {
+        writeln!(file, "{line}")?;
+    }
+    state.written += line.len() as u64 + 1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+
+    use super::*;
+    use crate::structures::Mode;
+
+    fn query(from: (f64, f64), to: (f64, f64), hour: u32, modes: Option<Vec<Mode>>) -> RouteQuery {
+        RouteQuery {
+            from_lat: from.0,
+            from_lng: from.1,
+            to_lat: to.0,
+            to_lng: to.1,
+            date: NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+            time: NaiveTime::from_hms_opt(hour, 15, 0).unwrap(),
+            window_minutes: None,
+            min_access_secs: None,
+            arrival_slack_secs: None,
+            unrestricted_transfers: None,
+            use_cch_access: None,
+            reliability_bucket_edges: None,
+            modes,
+            bike_profile: None,
+            terminal_deadline: false,
+            onboard_origin: None,
+            from_station_id: None,
+            to_station_id: None,
+            profile_latency: None,
+            fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
+        }
+    }
+
+    fn config(path: &std::path::Path, max_bytes: u64) -> AnalyticsConfig {
+        AnalyticsConfig {
+            enabled: true,
+            path: path.to_string_lossy().into_owned(),
+            max_bytes,
+            keep: 2,
+            cell_m: 500.0,
+        }
+    }
+
+    #[test]
+    fn disabled_records_nothing() {
+        let a = Analytics::disabled();
+        let pending = a.start("graphql", &query((50.0, 4.0), (50.1, 4.1), 8, None));
+        assert!(pending.is_none());
+        a.finish(pending, Ok(&[]));
+        assert_eq!(a.snapshot(5).requests, 0);
+        assert!(!a.is_enabled());
+    }
+
+    #[test]
+    fn records_are_anonymised_aggregated_and_rotated() {
+        let dir = std::env::temp_dir().join(format!("maas_analytics_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("analytics.jsonl");
+        let a = Analytics::new(&config(&path, 200));
+        let record = |source, q: RouteQuery, ms, result| {
+            let pending = a.start(source, &q).unwrap();
+            a.record(pending, Duration::from_millis(ms), result, 1_772_400_000);
+        };
+        // Two origins 50 m apart share a 500 m cell.
+        record("graphql", query((50.84613, 4.35227), (50.8, 4.4), 8, None), 10, Ok(&[]));
+        let bike = Some(vec![Mode::Bike]);
+        record("grpc", query((50.84650, 4.35249), (50.8, 4.4), 8, bike), 30, Err(()));
+        record("graphql", query((51.0, 3.7), (50.8, 4.4), 17, None), 20, Ok(&[]));
+
+        let s = a.snapshot(1);
+        assert_eq!((s.requests, s.empty, s.errors), (3, 2, 1));
+        assert_eq!(s.latency_p50_ms, 20.0);
+        assert_eq!(s.by_hour[8], 2);
+        assert_eq!(s.by_hour[17], 1);
+        assert_eq!(s.by_mode, vec![("BIKE".to_string(), 1), ("default".to_string(), 2)]);
+        assert_eq!(s.top_od.len(), 1);
+        assert_eq!(s.top_od[0].count, 2);
+        assert!((s.top_od[0].from.lat - 50.84613).abs() < 0.005);
+
+        drop(a);
+        let current = fs::read_to_string(&path).unwrap();
+        let rotated = fs::read_to_string(dir.join("analytics.jsonl.1")).unwrap();
+        assert_eq!(current.lines().count() + rotated.lines().count(), 3);
+        for raw in ["50.84613", "4.35227"] {
+            assert!(!current.contains(raw) && !rotated.contains(raw), "{raw} leaked: {rotated}");
+        }
+        let first: serde_json::Value = serde_json::from_str(rotated.lines().next().unwrap()).unwrap();
+        assert_eq!(first["hour"], 8);
+        assert_eq!(first["modes"][0], "default");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn since_queries_only_see_the_recent_window() {
+        let dir = std::env::temp_dir().join(format!("maas_analytics_since_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let a = Analytics::new(&config(&dir.join("a.jsonl"), 1 << 20));
+        let record = |source, from, ms, result, at| {
+            let pending = a.start(source, &query(from, (50.8, 4.4), 8, None)).unwrap();
+            a.record(pending, Duration::from_millis(ms), result, at);
+        };
+        let day = 1_772_400_000;
+        record("graphql", (51.0, 3.7), 900, Ok(&[]), day - 3_600);
+        record("graphql", (50.85, 4.35), 40, Err(()), day + 59);
+        record("grpc", (50.85, 4.35), 120, Ok(&[]), day + 600);
+        record("graphql", (51.2, 4.4), 80, Ok(&[]), day + 1_200);
+
+        let top = a.top_od_since(day, 5);
+        assert_eq!(top.len(), 2, "the older request is outside the window");
+        assert_eq!(top[0].count, 2);
+        assert!((top[0].from.lat - 50.85).abs() < 0.005);
+
+        let slow = a.slowest_since(day, 2);
+        assert_eq!(slow.iter().map(|r| r.latency_ms).collect::<Vec<_>>(), vec![120.0, 80.0]);
+        assert_eq!(slow[0].source, "grpc");
+        assert_eq!(a.slowest_since(0, 1)[0].latency_ms, 900.0);
+        assert_eq!(a.slowest_since(day, 5).iter().find(|r| r.error).unwrap().at, day, "stamped to the minute");
+
+        let outcomes = a.outcomes_since(day);
+        assert_eq!(outcomes[0], OutcomeCounts { source: "all".into(), requests: 3, empty: 2, errors: 1 });
+        assert_eq!(outcomes[1], OutcomeCounts { source: "graphql".into(), requests: 2, empty: 1, errors: 1 });
+        assert_eq!(outcomes[2].source, "grpc");
+        assert!(Analytics::disabled().outcomes_since(0)[0].requests == 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
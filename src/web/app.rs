@@ -1,10 +1,11 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use async_graphql::{
-    Context, EmptyMutation, EmptySubscription, Error, InputObject, Schema, SimpleObject,
+    ComplexObject, Context, Error, ErrorExtensions, InputObject, Schema, SimpleObject,
     http::GraphiQLSource,
 };
-use async_graphql_poem::GraphQL;
+use async_graphql_poem::{GraphQL, GraphQLSubscription};
 use chrono::{Local, NaiveDate, NaiveTime};
 use poem::{
     EndpointExt, IntoResponse, Response, Result, Route, Server, get, handler,
@@ -12,14 +13,25 @@ use poem::{
 };
 use tokio::sync::Semaphore;
 
+use super::{
+    analytics::Analytics, page_cursor::PageCursors, plan_cache::PlanCache,
+    subscriptions::SubscriptionRoot,
+};
 use crate::{
+    ingestion::gtfs::{
+        IngestionReport, IngestionWarningReason, LINK_DISTANCE_BUCKETS_M, LanguageId, NameField,
+    },
+    ingestion::osm::StopAmenities,
     ingestion::realtime::ServiceAlert,
-    routing::routing_raptor,
-    services::realtime_poller::{self, SharedRealtime},
+    routing::{paging, plan_share, routing_raptor, via},
+    services::persistence::{Fingerprint, save_graph_with_rollback},
+    services::realtime_poller::{self, RealtimeUpdates, SharedRealtime, realtime_updates},
     services::scheduler::{self, SharedGraph},
     structures::{
-        ADDRESS_ATTRIBUTION, AddressIndex, Config, Mode, RealtimeIndex, VehiclePos,
-        plan::{CandidateStatus, Plan, PlanCoordinate, PlanLeg},
+        ADDRESS_ATTRIBUTION, AddressIndex, BuildInfo, Config, Mode, RealtimeIndex, StableRef, VehiclePos,
+        WhatIf,
+        query_budget,
+        plan::{CandidateStatus, Plan, PlanCoordinate, PlanError, PlanLeg, RealtimeState},
     },
 };
 
@@ -29,8 +41,6 @@ pub type SharedAddressIndex = Arc<arc_swap::ArcSwap<AddressIndex>>;
 /// collision with any other `u64` data item.
 struct VehiclePositionMaxAgeSecs(u64);
 
-const HEAVY_QUERY_PERMITS: usize = 4;
-
 const HEAVY_QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
 /// Enforced ceiling on the GraphQL complexity limit regardless of the configured
@@ -38,12 +48,90 @@ const HEAVY_QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(
 /// config value is raised.
 const MAX_COMPLEXITY_CEILING: usize = 2000;
 
-const MAX_WINDOW_MINUTES: i32 = 1440;
+pub(super) const MAX_WINDOW_MINUTES: i32 = 1440;
 const MAX_WALK_RADIUS_SECS: i32 = 3600;
 const MAX_ARRIVAL_SLACK_SECS: i32 = 7200;
 const MAX_TRAVEL_MAP_SECONDS: i32 = 4 * 3600;
+pub(super) const MAX_STOPS_NEARBY_RADIUS_M: i32 = 5000;
+const MAX_BATCH_REQUESTS: usize = 500;
+const MAX_VIA_POINTS: usize = 5;
+const MAX_VIA_STAY_SECS: i32 = 24 * 3600;
+const MAX_RESOLVE_IDS: i32 = 500;
+
+/// Bounds concurrent routing searches; shared by GraphQL and gRPC. A search only reads
+/// the `Arc<Graph>` it loaded, so permits are the one point where searches contend.
+#[derive(Clone)]
+pub(super) struct HeavyQueryLimiter(Arc<Semaphore>, u32);
+
+impl HeavyQueryLimiter {
+    /// One permit per available core.
+    pub(super) fn new() -> Self {
+        Self::with_permits(None)
+    }
+
+    /// `server.max_concurrent_searches`, or one per available core when unset.
+    pub(super) fn with_permits(permits: Option<usize>) -> Self {
+        let permits = permits
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(4, |n| n.get()))
+            .clamp(1, Semaphore::MAX_PERMITS.min(u32::MAX as usize));
+        HeavyQueryLimiter(Arc::new(Semaphore::new(permits)), permits as u32)
+    }
+
+    /// Waits until no routing search holds a permit (used when shutting down).
+    pub(super) async fn drain(&self) {
+        let _ = self.0.acquire_many(self.1).await;
+    }
+
+    /// Runs `f` on the blocking pool once a permit is free, under `HEAVY_QUERY_TIMEOUT`.
+    pub(super) async fn run<T, F>(&self, f: F) -> std::result::Result<T, PlanError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> std::result::Result<T, PlanError> + Send + 'static,
+    {
+        let permit = self
+            .0
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| PlanError::new("routing limiter unavailable"))?;
+        // Raised when this future finishes or is dropped (timeout, client gone), so a search
+        // still running on the blocking pool stops at its next budget check.
+        let cancel = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+        let flag = cancel.0.clone();
+        let mut handle = tokio::task::spawn_blocking(move || {
+            let _watch = query_budget::watch_cancel(flag);
+            f()
+        });
+        match tokio::time::timeout(HEAVY_QUERY_TIMEOUT, &mut handle).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(PlanError::new("routing query failed")),
+            Err(_) => {
+                tokio::spawn(async move {
+                    let _ = handle.await;
+                    drop(permit);
+                });
+                Err(PlanError::new("routing query timed out"))
+            }
+        }
+    }
+}
+
+/// `server.admin_token`; `None` disables admin mutations.
+struct AdminToken(Option<String>);
+
+/// Fingerprint stamped on a `saveGraph` file, recomputed per call so a graph swapped in
+/// by auto-update is saved under its own inputs' digest.
+#[derive(Clone)]
+struct GraphFingerprint(Arc<dyn Fn() -> Fingerprint + Send + Sync>);
 
-struct HeavyQueryLimiter(Arc<Semaphore>);
+impl GraphFingerprint {
+    /// All-zero fingerprint, for schemas built without a config (tests, embedding).
+    fn unset() -> Self {
+        GraphFingerprint(Arc::new(|| [0u8; 32]))
+    }
+}
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 fn reject_over(name: &str, value: i32, max: i32) -> Result<(), Error> {
     if value > max {
@@ -52,28 +140,36 @@ fn reject_over(name: &str, value: i32, max: i32) -> Result<(), Error> {
     Ok(())
 }
 
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// GraphQL error for a core `PlanError`, carrying its `code` as an extension.
+fn plan_error(e: PlanError) -> Error {
+    match e.code {
+        Some(code) => Error::new(e.message).extend_with(|_, ext| ext.set("code", code)),
+        None => Error::new(e.message),
+    }
+}
+
+/// Plans whose stop and route names resolve in `language` (from `translations.txt`).
+fn with_language(mut plans: Vec<Plan>, language: Option<LanguageId>) -> Vec<Plan> {
+    if let Some(language) = language {
+        plans.iter_mut().for_each(|p| p.set_language(language));
+    }
+    plans
+}
+
 async fn run_heavy<T, F>(ctx: &Context<'_>, f: F) -> Result<T, Error>
 where
     T: Send + 'static,
-    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    F: FnOnce() -> Result<T, PlanError> + Send + 'static,
 {
-    let sem = ctx.data::<HeavyQueryLimiter>()?.0.clone();
-    let permit = sem
-        .acquire_owned()
-        .await
-        .map_err(|_| Error::new("routing limiter unavailable"))?;
-    let mut handle = tokio::task::spawn_blocking(f);
-    match tokio::time::timeout(HEAVY_QUERY_TIMEOUT, &mut handle).await {
-        Ok(Ok(result)) => result,
-        Ok(Err(_)) => Err(Error::new("routing query failed")),
-        Err(_) => {
-            tokio::spawn(async move {
-                let _ = handle.await;
-                drop(permit);
-            });
-            Err(Error::new("routing query timed out"))
-        }
-    }
+    ctx.data::<HeavyQueryLimiter>()?.run(f).await.map_err(plan_error)
 }
 
 #[derive(Clone, async_graphql::SimpleObject)]
@@ -94,6 +190,7 @@ impl Default for WebConfig {
 }
 
 #[derive(SimpleObject)]
+#[graphql(complex)]
 struct GtfsStop {
     id: String,
     name: String,
@@ -103,6 +200,32 @@ struct GtfsStop {
     mode: String,
 }
 
+#[ComplexObject]
+impl GtfsStop {
+    /// OSM facilities around the stop; null when the graph was built without
+    /// `stop_amenities`.
+    async fn amenities(&self, ctx: &Context<'_>) -> Result<Option<StopAmenities>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        Ok(graph.stop_amenities(crate::structures::LatLng { latitude: self.lat, longitude: self.lon }))
+    }
+
+    /// `gtfs:stop:<stop_id>`, valid across graph rebuilds unlike `id`.
+    async fn stable_id(&self, ctx: &Context<'_>) -> Result<Option<String>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        Ok(graph.resolve_stop_id(&self.id).and_then(|s| graph.stop_stable_id(s)))
+    }
+}
+
+#[derive(SimpleObject)]
+struct NearbyStop {
+    stop: GtfsStop,
+    distance_m: f64,
+    /// `gtfsStations` id of the station the stop belongs to.
+    station_id: Option<String>,
+    /// Platforms of that station (1 for a standalone stop).
+    platform_count: Option<i32>,
+}
+
 #[derive(SimpleObject)]
 struct StationLine {
     mode: String,
@@ -124,6 +247,31 @@ struct Address {
     municipality: String,
 }
 
+#[derive(SimpleObject)]
+struct GeocodeResult {
+    label: String,
+    kind: crate::ingestion::osm::GeocodeKind,
+    /// POI tag value (`cafe`, `museum`, …); `null` for addresses and streets.
+    category: Option<String>,
+    lat: f64,
+    lng: f64,
+    score: f64,
+}
+
+#[derive(SimpleObject)]
+struct NearbyParking {
+    name: Option<String>,
+    kind: crate::ingestion::osm::ParkingKind,
+    lat: f64,
+    lng: f64,
+    distance_m: f64,
+    /// Tagged spaces (car) or stands (bicycle); `null` when unknown.
+    capacity: Option<i32>,
+    /// `null` when the fee is untagged or conditional.
+    fee: Option<bool>,
+    park_and_ride: bool,
+}
+
 #[derive(SimpleObject)]
 struct GtfsStation {
     id: String,
@@ -155,6 +303,324 @@ struct GtfsAgency {
     routes: Vec<GtfsRoute>,
 }
 
+#[derive(SimpleObject)]
+struct TimetableStopTime {
+    stop_id: Option<String>,
+    stop_name: Option<String>,
+    /// Seconds since service-day midnight (may exceed 86400).
+    arrival: i32,
+    departure: i32,
+}
+
+#[derive(SimpleObject)]
+struct RouteTimetableTrip {
+    trip_id: Option<String>,
+    headsign: Option<String>,
+    stop_times: Vec<TimetableStopTime>,
+}
+
+#[derive(SimpleObject)]
+struct RouteTimetable {
+    route: Option<crate::structures::plan::PlanRoute>,
+    date: String,
+    trips: Vec<RouteTimetableTrip>,
+}
+
+/// One transit edge's raw run of departures in `transit_departures`, for debugging.
+#[derive(SimpleObject)]
+struct TimetableSegmentInfo {
+    start: i32,
+    len: i32,
+    route: Option<crate::structures::plan::PlanRoute>,
+    /// Whether the run is in non-decreasing departure order, as boarding search assumes.
+    sorted: bool,
+}
+
+/// A transit edge between two stops; stop ids as for `WhatIfInput.closedStops`.
+#[derive(InputObject)]
+struct TransitEdgeInput {
+    from_stop_id: String,
+    to_stop_id: String,
+}
+
+/// A `timetableSegment` result passed back to `departures`.
+#[derive(InputObject)]
+struct TimetableSegmentInput {
+    start: i32,
+    len: i32,
+}
+
+#[derive(SimpleObject)]
+struct SegmentDeparture {
+    /// Absolute index in `transit_departures`, usable as `departures(from:)`.
+    index: i32,
+    trip_id: Option<String>,
+    /// Seconds since service-day midnight (may exceed 86400).
+    departure: i32,
+    arrival: i32,
+    service_index: i32,
+}
+
+#[derive(SimpleObject)]
+struct TripStopTime {
+    stop_id: Option<String>,
+    stop_name: Option<String>,
+    lat: f64,
+    lng: f64,
+    scheduled_arrival: i32,
+    scheduled_departure: i32,
+    /// Scheduled time plus the realtime delay; `null` without realtime data for the stop.
+    expected_arrival: Option<i32>,
+    expected_departure: Option<i32>,
+    delay_secs: Option<i32>,
+    skipped: bool,
+    occupancy: Option<crate::structures::Occupancy>,
+}
+
+#[derive(SimpleObject)]
+struct TripDetails {
+    trip_id: String,
+    headsign: Option<String>,
+    route: Option<crate::structures::plan::PlanRoute>,
+    date: String,
+    runs_on_date: bool,
+    realtime_state: RealtimeState,
+    vehicle: Option<LiveVehicleGql>,
+    stop_times: Vec<TripStopTime>,
+    geometry: Vec<PlanCoordinate>,
+}
+
+#[derive(SimpleObject)]
+struct LinkDistanceBucket {
+    max_m: i32,
+    count: i32,
+}
+
+#[derive(SimpleObject)]
+struct ModeCount {
+    mode: String,
+    count: i64,
+}
+
+/// Request count between two anonymised analytics cells (cell centres).
+#[derive(SimpleObject)]
+struct OdPairCount {
+    from_lat: f64,
+    from_lng: f64,
+    to_lat: f64,
+    to_lng: f64,
+    count: i64,
+}
+
+impl From<crate::web::analytics::OdCount> for OdPairCount {
+    fn from(od: crate::web::analytics::OdCount) -> Self {
+        OdPairCount {
+            from_lat: od.from.lat,
+            from_lng: od.from.lng,
+            to_lat: od.to.lat,
+            to_lng: od.to.lng,
+            count: od.count as i64,
+        }
+    }
+}
+
+/// One recent plan request (`slowestQueries`), endpoints as analytics cell centres.
+#[derive(SimpleObject)]
+struct SlowQuery {
+    /// Unix seconds, rounded down to the minute.
+    at: i64,
+    source: String,
+    from_lat: f64,
+    from_lng: f64,
+    to_lat: f64,
+    to_lng: f64,
+    /// Requested departure hour.
+    hour: i32,
+    modes: Vec<String>,
+    latency_ms: f64,
+    results: i32,
+    error: bool,
+}
+
+/// Outcomes of the recent plan requests of one source; `all` sums every source.
+#[derive(SimpleObject)]
+struct ErrorRate {
+    source: String,
+    requests: i64,
+    empty: i64,
+    errors: i64,
+    /// `errors / requests`, 0 without requests.
+    error_rate: f64,
+    empty_rate: f64,
+}
+
+/// Plan-request aggregates since startup (`server.analytics`).
+#[derive(SimpleObject)]
+struct AnalyticsStats {
+    enabled: bool,
+    requests: i64,
+    /// Requests answered with no plan.
+    empty: i64,
+    errors: i64,
+    latency_p50_ms: f64,
+    latency_p95_ms: f64,
+    /// Requests per departure hour, index 0 = 00:00-00:59.
+    by_hour: Vec<i64>,
+    by_mode: Vec<ModeCount>,
+    top_od_pairs: Vec<OdPairCount>,
+}
+
+/// One input the served graph was built from.
+#[derive(SimpleObject)]
+#[graphql(name = "DataSource")]
+struct DataSourceGql {
+    label: String,
+    /// Config `ingestor` tag, e.g. `osm/pbf`, `gtfs/generic`.
+    kind: String,
+    /// URL without query string, or local path.
+    location: String,
+    /// SHA-256 (hex) of the file read.
+    sha256: Option<String>,
+    load_secs: f64,
+    /// OSM extract replication timestamp, unix seconds.
+    osm_timestamp: Option<i64>,
+    feed_publisher: Option<String>,
+    feed_version: Option<String>,
+    /// `feed_info.txt` validity, `YYYY-MM-DD`.
+    feed_start_date: Option<String>,
+    feed_end_date: Option<String>,
+}
+
+/// Provenance of the served graph.
+#[derive(SimpleObject)]
+#[graphql(name = "DataInfo")]
+struct DataInfoGql {
+    /// Version of the binary that built the graph; empty on a graph built before it was recorded.
+    built_with: String,
+    /// Version of the binary serving it.
+    server_version: String,
+    /// Unix seconds; `0` when unknown.
+    built_at: i64,
+    build_secs: f64,
+    sources: Vec<DataSourceGql>,
+}
+
+impl From<&BuildInfo> for DataInfoGql {
+    fn from(b: &BuildInfo) -> Self {
+        DataInfoGql {
+            built_with: b.crate_version.clone(),
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            built_at: b.built_at,
+            build_secs: b.build_secs,
+            sources: b
+                .sources
+                .iter()
+                .map(|s| DataSourceGql {
+                    label: s.label.clone(),
+                    kind: s.kind.clone(),
+                    location: s.location.clone(),
+                    sha256: s.sha256.clone(),
+                    load_secs: s.load_secs,
+                    osm_timestamp: s.osm_timestamp,
+                    feed_publisher: s.feed_publisher.clone(),
+                    feed_version: s.feed_version.clone(),
+                    feed_start_date: s.feed_start_date.clone(),
+                    feed_end_date: s.feed_end_date.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "IngestionReport")]
+struct IngestionReportGql {
+    feed: String,
+    stops_total: i32,
+    stops_loaded: i32,
+    stops_without_coords: i32,
+    stops_without_name: i32,
+    stops_unlinked: i32,
+    stops_platform_matched: i32,
+    link_distances: Vec<LinkDistanceBucket>,
+    trips_total: i32,
+    trips_loaded: i32,
+    trips_without_service: i32,
+    trips_without_route: i32,
+    trips_too_short: i32,
+    routes_total: i32,
+    routes_unmatched_agency: i32,
+    unmatched_agency_ids: Vec<String>,
+    route_colors_invalid: i32,
+    routes_filtered: i32,
+    trips_filtered: i32,
+    stops_filtered: i32,
+}
+
+impl From<&IngestionReport> for IngestionReportGql {
+    fn from(r: &IngestionReport) -> Self {
+        IngestionReportGql {
+            feed: r.feed.clone(),
+            stops_total: r.stops_total as i32,
+            stops_loaded: r.stops_loaded as i32,
+            stops_without_coords: r.stops_without_coords as i32,
+            stops_without_name: r.stops_without_name as i32,
+            stops_unlinked: r.stops_unlinked as i32,
+            stops_platform_matched: r.stops_platform_matched as i32,
+            link_distances: LINK_DISTANCE_BUCKETS_M
+                .iter()
+                .zip(&r.link_distance_histogram)
+                .map(|(&max_m, &count)| LinkDistanceBucket {
+                    max_m: max_m as i32,
+                    count: count as i32,
+                })
+                .collect(),
+            trips_total: r.trips_total as i32,
+            trips_loaded: r.trips_loaded as i32,
+            trips_without_service: r.trips_without_service as i32,
+            trips_without_route: r.trips_without_route as i32,
+            trips_too_short: r.trips_too_short as i32,
+            routes_total: r.routes_total as i32,
+            routes_unmatched_agency: r.routes_unmatched_agency as i32,
+            unmatched_agency_ids: r.unmatched_agency_ids.clone(),
+            route_colors_invalid: r.route_colors_invalid as i32,
+            routes_filtered: r.routes_filtered as i32,
+            trips_filtered: r.trips_filtered as i32,
+            stops_filtered: r.stops_filtered as i32,
+        }
+    }
+}
+
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(name = "IngestionWarningReason", remote = "IngestionWarningReason")]
+enum IngestionWarningReasonGql {
+    StopWithoutCoords,
+    StopWithoutName,
+    StopWithoutStreet,
+    StopTooFarFromStreet,
+    RouteUnmatchedAgency,
+    TripWithoutService,
+    TripWithoutRoute,
+    TripTooShort,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "IngestionWarning")]
+struct IngestionWarningGql {
+    feed: String,
+    reason: IngestionWarningReasonGql,
+    /// Namespaced GTFS id of the stop, route or trip.
+    entity_id: String,
+    detail: Option<String>,
+}
+
+/// One `ingestionWarnings` page; `total` counts every match before `offset`/`limit`.
+#[derive(SimpleObject)]
+struct IngestionWarningPage {
+    total: i32,
+    warnings: Vec<IngestionWarningGql>,
+}
+
 #[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq)]
 #[graphql(name = "CandidateStatus")]
 enum CandidateStatusGql {
@@ -318,18 +784,26 @@ fn parse_date_time(
     date: &Option<String>,
     time: &Option<String>,
 ) -> std::result::Result<(NaiveDate, NaiveTime), Error> {
+    parse_date_time_str(date.as_deref(), time.as_deref()).map_err(Error::new)
+}
+
+/// `YYYY-MM-DD` and `HH:MM[:SS]`; `None` means now (local).
+pub(super) fn parse_date_time_str(
+    date: Option<&str>,
+    time: Option<&str>,
+) -> std::result::Result<(NaiveDate, NaiveTime), String> {
     let now = Local::now().naive_local();
 
     let parsed_date = match date {
         Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
-            .map_err(|e| Error::new(format!("Invalid date '{}': {}", d, e)))?,
+            .map_err(|e| format!("Invalid date '{}': {}", d, e))?,
         None => now.date(),
     };
 
     let parsed_time = match time {
         Some(t) => NaiveTime::parse_from_str(t, "%H:%M:%S")
             .or_else(|_| NaiveTime::parse_from_str(t, "%H:%M"))
-            .map_err(|e| Error::new(format!("Invalid time '{}': {}", t, e)))?,
+            .map_err(|e| format!("Invalid time '{}': {}", t, e))?,
         None => now.time(),
     };
 
@@ -349,6 +823,158 @@ struct LegAlternatives {
     next: Vec<AltDeparture>,
 }
 
+/// One origin/destination/departure of a `batchRaptor` call or a first `raptorPage`;
+/// omitted fields take the same defaults as the matching `raptor` arguments.
+#[derive(InputObject)]
+struct BatchPlanInput {
+    from_lat: f64,
+    from_lng: f64,
+    to_lat: f64,
+    to_lng: f64,
+    date: Option<String>,
+    time: Option<String>,
+    window_minutes: Option<i32>,
+    walk_radius_secs: Option<i32>,
+    arrival_slack_secs: Option<i32>,
+    modes: Option<Vec<Mode>>,
+    profile: Option<String>,
+    night_safety: Option<f64>,
+    avoid_stairs: Option<bool>,
+    max_access_walk: Option<i32>,
+    max_egress_walk: Option<i32>,
+}
+
+/// An intermediate place of a `raptor` journey, left no earlier than `minStay`
+/// seconds (default 0) after arriving.
+#[derive(InputObject)]
+struct ViaInput {
+    lat: f64,
+    lng: f64,
+    min_stay: Option<i32>,
+}
+
+impl ViaInput {
+    fn into_point(self) -> Result<via::ViaPoint, Error> {
+        let min_stay = self.min_stay.unwrap_or(0);
+        if min_stay < 0 {
+            return Err(Error::new("via minStay must be >= 0"));
+        }
+        reject_over("via minStay", min_stay, MAX_VIA_STAY_SECS)?;
+        Ok(via::ViaPoint { lat: self.lat, lng: self.lng, min_stay_secs: min_stay as u32 })
+    }
+}
+
+/// A street segment between two adjacent OSM nodes, closed in both directions.
+#[derive(InputObject)]
+struct StreetSegmentInput {
+    from_osm_node: i64,
+    to_osm_node: i64,
+}
+
+/// Closures simulated for one `raptor` request (a strike, a closed station, road
+/// works); the served graph is unchanged. Routes and trips stop running, closed
+/// stops are neither boarded nor alighted at, and walk/car legs avoid closed streets.
+#[derive(InputObject)]
+struct WhatIfInput {
+    /// `maas:route:` ids or GTFS `route_id`s.
+    closed_routes: Option<Vec<String>>,
+    /// `maas:stop:` ids or GTFS `stop_id`s.
+    closed_stops: Option<Vec<String>>,
+    /// GTFS `trip_id`s.
+    canceled_trips: Option<Vec<String>>,
+    closed_streets: Option<Vec<StreetSegmentInput>>,
+}
+
+impl WhatIfInput {
+    fn into_what_if(self) -> WhatIf {
+        WhatIf {
+            closed_routes: self.closed_routes.unwrap_or_default(),
+            closed_stops: self.closed_stops.unwrap_or_default(),
+            canceled_trips: self.canceled_trips.unwrap_or_default(),
+            closed_streets: self
+                .closed_streets
+                .unwrap_or_default()
+                .into_iter()
+                .map(|s| (s.from_osm_node, s.to_osm_node))
+                .collect(),
+        }
+    }
+}
+
+impl BatchPlanInput {
+    fn into_query(self) -> Result<routing_raptor::RouteQuery, Error> {
+        let (date, time) = parse_date_time(&self.date, &self.time)?;
+        reject_over("windowMinutes", self.window_minutes.unwrap_or(0), MAX_WINDOW_MINUTES)?;
+        reject_over("walkRadiusSecs", self.walk_radius_secs.unwrap_or(0), MAX_WALK_RADIUS_SECS)?;
+        reject_over(
+            "arrivalSlackSecs",
+            self.arrival_slack_secs.unwrap_or(0),
+            MAX_ARRIVAL_SLACK_SECS,
+        )?;
+        Ok(routing_raptor::RouteQuery {
+            from_lat: self.from_lat,
+            from_lng: self.from_lng,
+            to_lat: self.to_lat,
+            to_lng: self.to_lng,
+            date,
+            time,
+            window_minutes: self.window_minutes.map(|w| w.max(0) as u32),
+            min_access_secs: self.walk_radius_secs.map(|s| s.max(0) as u32),
+            arrival_slack_secs: self.arrival_slack_secs.map(|s| s.max(0) as u32),
+            unrestricted_transfers: None,
+            use_cch_access: None,
+            reliability_bucket_edges: None,
+            modes: self.modes,
+            bike_profile: None,
+            terminal_deadline: false,
+            onboard_origin: None,
+            from_station_id: None,
+            to_station_id: None,
+            profile_latency: None,
+            fare_profile: None,
+            avoid_crowding: false,
+            profile: self.profile,
+            what_if: None,
+            night_safety: self.night_safety,
+            avoid_stairs: self.avoid_stairs,
+            max_access_walk_m: self.max_access_walk.map(|m| m.max(0) as u32),
+            max_egress_walk_m: self.max_egress_walk.map(|m| m.max(0) as u32),
+            search_profile: None,
+        })
+    }
+}
+
+/// Outcome of one `batchRaptor` request: its plans, or the error that `raptor`
+/// would have raised for it (`code` as in the error extension).
+#[derive(SimpleObject)]
+struct BatchPlanResult {
+    plans: Vec<Plan>,
+    error: Option<String>,
+    code: Option<String>,
+}
+
+impl From<std::result::Result<Vec<Plan>, PlanError>> for BatchPlanResult {
+    fn from(result: std::result::Result<Vec<Plan>, PlanError>) -> Self {
+        match result {
+            Ok(plans) => BatchPlanResult { plans, error: None, code: None },
+            Err(e) => BatchPlanResult {
+                plans: Vec::new(),
+                error: Some(e.message),
+                code: e.code.map(str::to_owned),
+            },
+        }
+    }
+}
+
+/// One `raptorPage` page. `previousPageCursor` fetches the departures right before
+/// it, `nextPageCursor` those right after.
+#[derive(SimpleObject)]
+struct PlanPage {
+    plans: Vec<Plan>,
+    previous_page_cursor: String,
+    next_page_cursor: String,
+}
+
 #[derive(InputObject)]
 struct LiveLegInput {
     trip_id: String,
@@ -418,6 +1044,40 @@ fn effect_label(v: i32) -> &'static str {
     }
 }
 
+/// An active alert touching an `alertsInArea` box.
+#[derive(SimpleObject)]
+#[graphql(name = "AreaAlert")]
+struct AreaAlertGql {
+    alert: LiveAlertGql,
+    /// `maas:stop:<n>` ids of the stops the alert names inside the box.
+    stop_ids: Vec<String>,
+    /// `maas:route:<n>` ids of the routes the alert names (directly or through a
+    /// trip) with a stop inside the box.
+    route_ids: Vec<String>,
+}
+
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq)]
+enum StableIdKind {
+    Stop,
+    Route,
+    Trip,
+    Street,
+}
+
+/// What a `resolveStableIds` id names on the graph currently served.
+#[derive(SimpleObject)]
+struct ResolvedStableId {
+    stable_id: String,
+    /// Null when the id names nothing on this graph.
+    kind: Option<StableIdKind>,
+    /// Id to pass to other queries on this graph: `maas:stop:<n>`, `maas:route:<n>`,
+    /// the GTFS `trip_id` or the OSM id.
+    id: Option<String>,
+    /// Stops and street nodes only.
+    lat: Option<f64>,
+    lng: Option<f64>,
+}
+
 fn map_alert(alert: &ServiceAlert) -> LiveAlertGql {
     LiveAlertGql {
         header: alert.header.clone(),
@@ -450,6 +1110,8 @@ struct LiveLegGql {
     trip_id: String,
     found: bool,
     status: LiveStatusGql,
+    realtime_state: RealtimeState,
+    occupancy: Option<crate::structures::Occupancy>,
     delay_secs: i32,
     scheduled_start: Option<i32>,
     scheduled_end: Option<i32>,
@@ -833,6 +1495,8 @@ fn live_refresh(
             trip_id: leg.trip_id.clone(),
             found: false,
             status: LiveStatusGql::NotFound,
+            realtime_state: RealtimeState::Scheduled,
+            occupancy: None,
             delay_secs: 0,
             scheduled_start: None,
             scheduled_end: None,
@@ -877,6 +1541,16 @@ fn live_refresh(
                 }
             };
 
+        let realtime_state = if status == LiveStatusGql::Canceled {
+            RealtimeState::Canceled
+        } else if rt.is_skipped(trip, board as u32) || rt.is_skipped(trip, alight as u32) {
+            RealtimeState::Modified
+        } else if matches!(status, LiveStatusGql::OnTime | LiveStatusGql::Delayed) {
+            RealtimeState::Updated
+        } else {
+            RealtimeState::Scheduled
+        };
+
         let leg_route_id = graph.raptor.route_id_of_trip(trip);
         let leg_alerts: Vec<LiveAlertGql> = rt
             .alerts_for_leg(
@@ -896,6 +1570,8 @@ fn live_refresh(
             trip_id: leg.trip_id.clone(),
             found: true,
             status,
+            realtime_state,
+            occupancy: rt.occupancy(trip, board as u32),
             delay_secs,
             scheduled_start: Some(sched_start),
             scheduled_end: Some(sched_end),
@@ -1032,6 +1708,86 @@ fn station_backups(
         .collect()
 }
 
+/// Realtime only overlays `live` (today's) runs: GTFS-RT delays are keyed by trip,
+/// not by service date.
+fn trip_details(
+    graph: &crate::structures::Graph,
+    rt: &RealtimeIndex,
+    trip_id: &str,
+    date: NaiveDate,
+    live: bool,
+    now_unix_secs: u64,
+    max_age_secs: u64,
+) -> Option<TripDetails> {
+    use chrono::Datelike;
+
+    let trip = graph.trip_index_of(trip_id)?;
+    let run = graph.trip_timetable(trip)?;
+    let info = graph.get_trip(trip)?;
+    let runs_on_date = graph.trip_runs_on(
+        trip,
+        crate::ingestion::gtfs::date_to_days(date),
+        1u8 << date.weekday().num_days_from_monday(),
+    );
+    let live = live && runs_on_date;
+
+    let stop_times: Vec<TripStopTime> = run
+        .stops
+        .into_iter()
+        .filter_map(|(node, st)| {
+            let loc = graph.get_node(node)?.loc();
+            let compact = graph.compact_stop_of_node(node);
+            let delay = compact.filter(|_| live).and_then(|c| rt.delay_opt(trip, c as u32));
+            Some(TripStopTime {
+                stop_id: graph.stop_id_of_node(node).map(str::to_string),
+                stop_name: compact.and_then(|c| graph.raptor.transit_stop_names.get(c).cloned()),
+                lat: loc.latitude,
+                lng: loc.longitude,
+                scheduled_arrival: st.arrival as i32,
+                scheduled_departure: st.departure as i32,
+                expected_arrival: delay.map(|d| st.arrival as i32 + d),
+                expected_departure: delay.map(|d| st.departure as i32 + d),
+                delay_secs: delay,
+                skipped: live && compact.is_some_and(|c| rt.is_skipped(trip, c as u32)),
+                occupancy: compact
+                    .filter(|_| live)
+                    .and_then(|c| rt.occupancy(trip, c as u32)),
+            })
+        })
+        .collect();
+
+    let realtime_state = if !live {
+        RealtimeState::Scheduled
+    } else if rt.is_canceled(trip) {
+        RealtimeState::Canceled
+    } else if stop_times.iter().any(|s| s.skipped) {
+        RealtimeState::Modified
+    } else if stop_times.iter().any(|s| s.delay_secs.is_some()) {
+        RealtimeState::Updated
+    } else {
+        RealtimeState::Scheduled
+    };
+
+    Some(TripDetails {
+        trip_id: trip_id.to_string(),
+        headsign: info.trip_headsign.clone(),
+        route: crate::structures::plan::PlanRoute::from_route_id(graph, Some(info.route_id)),
+        date: date.format("%Y-%m-%d").to_string(),
+        runs_on_date,
+        realtime_state,
+        vehicle: rt
+            .vehicle(trip)
+            .filter(|_| live)
+            .map(|v| map_vehicle(v, now_unix_secs, max_age_secs)),
+        stop_times,
+        geometry: graph
+            .trip_geometry(trip)
+            .into_iter()
+            .map(|c| PlanCoordinate { lat: c.latitude, lon: c.longitude })
+            .collect(),
+    })
+}
+
 pub struct QueryRoot;
 
 #[async_graphql::Object]
@@ -1044,6 +1800,18 @@ impl QueryRoot {
         Ok(ctx.data::<WebConfig>()?.clone())
     }
 
+    /// Names accepted by `raptor(profile:)`.
+    async fn routing_profiles(&self, ctx: &Context<'_>) -> Result<Vec<String>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        Ok(graph.raptor.routing_profiles.keys().cloned().collect())
+    }
+
+    /// Names accepted by `raptor(searchProfile:)` / `streetRoute(searchProfile:)`.
+    async fn search_profiles(&self, ctx: &Context<'_>) -> Result<Vec<String>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        Ok(graph.search_profiles().iter().map(|p| p.spec.name.clone()).collect())
+    }
+
     async fn realtime_generated_at(&self, ctx: &Context<'_>) -> Result<i64, Error> {
         let rt = ctx.data::<SharedRealtime>()?.load_full();
         Ok(rt.generated_at)
@@ -1074,8 +1842,28 @@ impl QueryRoot {
         to_station_id: Option<String>,
         profile_latency: Option<bool>,
         fare_profile: Option<FareProfileInput>,
+        avoid_crowding: Option<bool>,
+        profile: Option<String>,
+        language: Option<String>,
+        what_if: Option<WhatIfInput>,
+        night_safety: Option<f64>,
+        avoid_stairs: Option<bool>,
+        max_access_walk: Option<i32>,
+        max_egress_walk: Option<i32>,
+        via: Option<Vec<ViaInput>>,
+        search_profile: Option<String>,
+        #[graphql(default = false)] debug: bool,
     ) -> Result<Vec<Plan>, Error> {
+        let via = via.unwrap_or_default();
+        if via.len() > MAX_VIA_POINTS {
+            return Err(Error::new(format!("via must hold <= {MAX_VIA_POINTS} points")));
+        }
+        if debug && !via.is_empty() {
+            return Err(Error::new("debug is not supported with via"));
+        }
+        let via: Vec<via::ViaPoint> = via.into_iter().map(ViaInput::into_point).collect::<Result<_, _>>()?;
         let graph = ctx.data::<SharedGraph>()?.load_full();
+        let language = language.as_deref().and_then(|l| graph.match_language(l));
         let (parsed_date, parsed_time) = parse_date_time(&date, &time)?;
         reject_over("windowMinutes", window_minutes.unwrap_or(0), MAX_WINDOW_MINUTES)?;
         reject_over("walkRadiusSecs", walk_radius_secs.unwrap_or(0), MAX_WALK_RADIUS_SECS)?;
@@ -1103,13 +1891,158 @@ impl QueryRoot {
             to_station_id,
             profile_latency,
             fare_profile: fare_profile.map(|i| i.into_profile()),
+            avoid_crowding: avoid_crowding.unwrap_or(false),
+            profile,
+            what_if: what_if.map(WhatIfInput::into_what_if),
+            night_safety,
+            avoid_stairs,
+            max_access_walk_m: max_access_walk.map(|m| m.max(0) as u32),
+            max_egress_walk_m: max_egress_walk.map(|m| m.max(0) as u32),
+            search_profile,
         };
 
         let rt = ctx.data::<SharedRealtime>()?.load_full();
-        run_heavy(ctx, move || {
-            routing_raptor::route(graph.as_ref(), &query, rt.as_ref())
+        let cache = ctx.data::<Arc<PlanCache>>()?.clone();
+        let analytics = ctx.data::<Arc<Analytics>>()?;
+        let pending = analytics.start("graphql", &query);
+        if !via.is_empty() {
+            // Chained hops are not cached: the cache key has no via points.
+            let result = run_heavy(ctx, move || via::route(graph.as_ref(), &query, &via, rt.as_ref())).await;
+            analytics.finish(pending, result.as_deref().map_err(|_| ()));
+            return Ok(with_language(result?, language));
+        }
+        // A debug trace describes one fresh search, so it neither reads nor fills the cache.
+        let key = if debug { None } else { cache.key(&graph, &rt, &query) };
+        if let Some(plans) = key.as_ref().and_then(|k| cache.get(k)) {
+            analytics.finish(pending, Ok(&plans));
+            let plans = routing_raptor::with_snap_warnings(&graph, &query, plans);
+            let plans = plan_share::with_itinerary_ids(&graph, &query, plans);
+            return Ok(with_language(plans, language));
+        }
+        let (routed, request) = (graph.clone(), query.clone());
+        let result = run_heavy(ctx, move || {
+            if debug {
+                routing_raptor::route_debug(routed.as_ref(), &request, rt.as_ref())
+            } else {
+                routing_raptor::route(routed.as_ref(), &request, rt.as_ref())
+            }
+        })
+        .await;
+        analytics.finish(pending, result.as_deref().map_err(|_| ()));
+        let plans = result?;
+        if let Some(key) = key {
+            cache.insert(key, plans.clone());
+        }
+        // Snap warnings and ids follow this request's own coordinates, so they are never cached.
+        let plans = routing_raptor::with_snap_warnings(&graph, &query, plans);
+        let plans = plan_share::with_itinerary_ids(&graph, &query, plans);
+        Ok(with_language(plans, language))
+    }
+
+    /// Routes up to `MAX_BATCH_REQUESTS` independent requests concurrently on the
+    /// shared routing pool and returns one result per request, in input order. A
+    /// request that fails (bad input, no route, timeout) does not fail the batch.
+    #[graphql(complexity = "50 + child_complexity")]
+    async fn batch_raptor(
+        &self,
+        ctx: &Context<'_>,
+        requests: Vec<BatchPlanInput>,
+    ) -> Result<Vec<BatchPlanResult>, Error> {
+        if requests.len() > MAX_BATCH_REQUESTS {
+            return Err(Error::new(format!(
+                "requests must hold <= {MAX_BATCH_REQUESTS} entries"
+            )));
+        }
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let rt = ctx.data::<SharedRealtime>()?.load_full();
+        let cache = ctx.data::<Arc<PlanCache>>()?.clone();
+        let limiter = ctx.data::<HeavyQueryLimiter>()?.clone();
+        let analytics = ctx.data::<Arc<Analytics>>()?.clone();
+
+        let mut results: Vec<Option<std::result::Result<Vec<Plan>, PlanError>>> =
+            (0..requests.len()).map(|_| None).collect();
+        // Dropping the set (client gone) aborts the pending searches.
+        let mut tasks = tokio::task::JoinSet::new();
+        for (i, input) in requests.into_iter().enumerate() {
+            let query = match input.into_query() {
+                Ok(query) => query,
+                Err(e) => {
+                    results[i] = Some(Err(PlanError::new(e.message)));
+                    continue;
+                }
+            };
+            let (graph, rt, cache, limiter, analytics) =
+                (graph.clone(), rt.clone(), cache.clone(), limiter.clone(), analytics.clone());
+            tasks.spawn(async move {
+                let pending = analytics.start("graphql", &query);
+                let key = cache.key(&graph, &rt, &query);
+                if let Some(plans) = key.as_ref().and_then(|k| cache.get(k)) {
+                    analytics.finish(pending, Ok(&plans));
+                    let plans = routing_raptor::with_snap_warnings(&graph, &query, plans);
+                    return (i, Ok(plan_share::with_itinerary_ids(&graph, &query, plans)));
+                }
+                let (routed, request) = (graph.clone(), query.clone());
+                let result = limiter
+                    .run(move || routing_raptor::route(routed.as_ref(), &request, rt.as_ref()))
+                    .await;
+                analytics.finish(pending, result.as_deref().map_err(|_| ()));
+                if let (Some(key), Ok(plans)) = (key, &result) {
+                    cache.insert(key, plans.clone());
+                }
+                let result = result.map(|plans| routing_raptor::with_snap_warnings(&graph, &query, plans));
+                (i, result.map(|plans| plan_share::with_itinerary_ids(&graph, &query, plans)))
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            let (i, result) = joined.map_err(|_| Error::new("batch routing task failed"))?;
+            results[i] = Some(result);
+        }
+        Ok(results.into_iter().flatten().map(BatchPlanResult::from).collect())
+    }
+
+    /// Itineraries one departure range at a time, like OTP2's `trip` paging: the first
+    /// call takes `request`, the following ones only a cursor from the previous page.
+    /// Each page searches the range next to the one already shown (the request's
+    /// `windowMinutes`, else an hour) and drops itineraries it already returned. An
+    /// expired or unknown cursor fails with code `UNKNOWN_PAGE_CURSOR`.
+    #[graphql(complexity = "50 + child_complexity")]
+    async fn raptor_page(
+        &self,
+        ctx: &Context<'_>,
+        request: Option<BatchPlanInput>,
+        page_cursor: Option<String>,
+    ) -> Result<PlanPage, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let rt = ctx.data::<SharedRealtime>()?.load_full();
+        let cursors = ctx.data::<Arc<PageCursors>>()?.clone();
+        let (plans, state) = match (request, page_cursor) {
+            (Some(request), None) => {
+                let query = request.into_query()?;
+                run_heavy(ctx, move || {
+                    paging::first_page(graph.as_ref(), &query, rt.as_ref())
+                })
+                .await?
+            }
+            (None, Some(cursor)) => {
+                let (direction, state) = cursors.resolve(&cursor).ok_or_else(|| {
+                    plan_error(
+                        PlanError::new("unknown or expired page cursor")
+                            .with_code("UNKNOWN_PAGE_CURSOR"),
+                    )
+                })?;
+                run_heavy(ctx, move || {
+                    paging::next_page(graph.as_ref(), &state, direction, rt.as_ref())
+                })
+                .await?
+            }
+            _ => return Err(Error::new("exactly one of request and pageCursor is required")),
+        };
+        let (previous_page_cursor, next_page_cursor) = cursors.insert(state);
+        Ok(PlanPage {
+            plans,
+            previous_page_cursor,
+            next_page_cursor,
         })
-        .await
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -1162,17 +2095,71 @@ impl QueryRoot {
             to_station_id: None,
             profile_latency: None,
             fare_profile: fare_profile.map(|i| i.into_profile()),
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
         };
 
         let rt = ctx.data::<SharedRealtime>()?.load_full();
         run_heavy(ctx, move || {
             routing_raptor::route(graph.as_ref(), &query, rt.as_ref())
+                .map(|plans| routing_raptor::with_snap_warnings(graph.as_ref(), &query, plans))
         })
         .await
     }
 
-    #[graphql(
-        complexity = "80 + child_complexity + (window_minutes.unwrap_or(0).max(0) as usize) / 10"
+    /// A single walk, bike or car leg (`mode`, default `WALK`) between two points,
+    /// with geometry, turn instructions and the leg's Pareto alternatives. Skips
+    /// transit entirely, so it is much cheaper than `raptor`.
+    #[allow(clippy::too_many_arguments)]
+    async fn street_route(
+        &self,
+        ctx: &Context<'_>,
+        from_lat: f64,
+        from_lng: f64,
+        to_lat: f64,
+        to_lng: f64,
+        #[graphql(default_with = "Mode::Walk")] mode: Mode,
+        time: Option<String>,
+        bike_profile: Option<BikeProfileInput>,
+        search_profile: Option<String>,
+    ) -> Result<Plan, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let (_, parsed_time) = parse_date_time(&None, &time)?;
+        let query = routing_raptor::StreetRouteQuery {
+            from_lat,
+            from_lng,
+            to_lat,
+            to_lng,
+            time: parsed_time,
+            mode,
+            bike_profile: bike_profile.map(|i| i.merge_into(graph.raptor.bike_profile)),
+            search_profile,
+        };
+        run_heavy(ctx, move || routing_raptor::street_route(graph.as_ref(), &query)).await
+    }
+
+    /// Re-hydrate a plan's `itineraryId` against the current data and realtime: the
+    /// same legs on the same trips, with today's times. Fails with code
+    /// `ITINERARY_UNAVAILABLE` when a trip is gone or a connection no longer holds, and
+    /// `INVALID_ITINERARY_ID` for an id this server cannot read.
+    #[graphql(complexity = "50 + child_complexity")]
+    async fn plan_by_id(&self, ctx: &Context<'_>, id: String) -> Result<Plan, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let rt = ctx.data::<SharedRealtime>()?.load_full();
+        run_heavy(ctx, move || {
+            plan_share::plan_by_id(graph.as_ref(), &id, rt.as_ref())
+        })
+        .await
+    }
+
+    #[graphql(
+        complexity = "80 + child_complexity + (window_minutes.unwrap_or(0).max(0) as usize) / 10"
     )]
     async fn raptor_explain(
         &self,
@@ -1222,6 +2209,14 @@ impl QueryRoot {
             to_station_id: None,
             profile_latency: None,
             fare_profile: fare_profile.map(|i| i.into_profile()),
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
         };
 
         let rt = ctx.data::<SharedRealtime>()?.load_full();
@@ -1320,6 +2315,14 @@ impl QueryRoot {
             to_station_id: None,
             profile_latency: None,
             fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
         };
 
         let rt = ctx.data::<SharedRealtime>()?.load_full();
@@ -1337,8 +2340,8 @@ impl QueryRoot {
             _ => return Err(Error::new("leg_index is not a transit leg")),
         };
 
-        let previous = leg.previous_departures_on(graph.as_ref(), prev_count.max(0) as usize)?;
-        let next = leg.next_departures_on(graph.as_ref(), next_count.max(0) as usize)?;
+        let previous = leg.previous_departures_on(graph.as_ref(), prev_count.max(0) as usize, None)?;
+        let next = leg.next_departures_on(graph.as_ref(), next_count.max(0) as usize, None)?;
 
         let to_alt = |legs: Vec<crate::structures::plan::PlanTransitLeg>| {
             legs.into_iter()
@@ -1376,6 +2379,45 @@ impl QueryRoot {
         Ok(live_refresh(graph.as_ref(), rt.as_ref(), &legs, now_unix_secs, max_age_secs))
     }
 
+    /// Alerts active at `date`/`time` (default now) that name a stop inside the box,
+    /// or a route or trip with a stop inside it.
+    #[allow(clippy::too_many_arguments)]
+    async fn alerts_in_area(
+        &self,
+        ctx: &Context<'_>,
+        min_lat: f64,
+        min_lng: f64,
+        max_lat: f64,
+        max_lng: f64,
+        date: Option<String>,
+        time: Option<String>,
+    ) -> Result<Vec<AreaAlertGql>, Error> {
+        if min_lat > max_lat || min_lng > max_lng {
+            return Err(Error::new("minLat/minLng must not exceed maxLat/maxLng"));
+        }
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let rt = ctx.data::<SharedRealtime>()?.load_full();
+        let (parsed_date, parsed_time) = parse_date_time(&date, &time)?;
+        let now_unix_secs = parsed_date
+            .and_time(parsed_time)
+            .and_local_timezone(Local)
+            .earliest()
+            .map_or(0, |t| t.timestamp().max(0) as u64);
+        let min = crate::structures::LatLng { latitude: min_lat, longitude: min_lng };
+        let max = crate::structures::LatLng { latitude: max_lat, longitude: max_lng };
+        Ok(rt
+            .active_alerts(now_unix_secs)
+            .filter_map(|alert| {
+                let (stops, routes) = graph.alert_in_area(alert, min, max);
+                (!stops.is_empty() || !routes.is_empty()).then(|| AreaAlertGql {
+                    alert: map_alert(alert),
+                    stop_ids: stops.iter().map(|s| format!("maas:stop:{s}")).collect(),
+                    route_ids: routes.iter().map(|r| format!("maas:route:{}", r.0)).collect(),
+                })
+            })
+            .collect())
+    }
+
     async fn station_backups(
         &self,
         ctx: &Context<'_>,
@@ -1495,14 +2537,20 @@ impl QueryRoot {
         })
     }
 
-    async fn gtfs_stops(&self, ctx: &Context<'_>) -> Result<Vec<GtfsStop>, Error> {
+    /// `language` is an `Accept-Language`-style list picking translated stop names.
+    async fn gtfs_stops(
+        &self,
+        ctx: &Context<'_>,
+        language: Option<String>,
+    ) -> Result<Vec<GtfsStop>, Error> {
         let graph = ctx.data::<SharedGraph>()?.load_full();
+        let language = language.as_deref().and_then(|l| graph.match_language(l));
         Ok(graph
             .gtfs_stops()
             .into_iter()
             .map(|(idx, name, lat, lon, mode)| GtfsStop {
                 id: format!("maas:stop:{}", idx),
-                name,
+                name: graph.localized(NameField::StopName, &name, language),
                 lat,
                 lon,
                 mode,
@@ -1510,14 +2558,130 @@ impl QueryRoot {
             .collect())
     }
 
-    async fn gtfs_stations(&self, ctx: &Context<'_>) -> Result<Vec<GtfsStation>, Error> {
+    /// Map stable ids (`stableId` fields, kept from an earlier graph build) onto this
+    /// graph. Also accepts this graph's `maas:stop:` / `maas:route:` ids. At most
+    /// 500 ids.
+    async fn resolve_stable_ids(&self, ctx: &Context<'_>, ids: Vec<String>) -> Result<Vec<ResolvedStableId>, Error> {
+        reject_over("ids", ids.len().min(i32::MAX as usize) as i32, MAX_RESOLVE_IDS)?;
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        Ok(ids
+            .into_iter()
+            .map(|stable_id| {
+                let (kind, id, at) = match graph.resolve_stable_id(&stable_id) {
+                    Some(StableRef::Stop(s)) => {
+                        (Some(StableIdKind::Stop), Some(format!("maas:stop:{s}")), graph.stop_lat_lng(s))
+                    }
+                    Some(StableRef::Route(r)) => (Some(StableIdKind::Route), Some(format!("maas:route:{}", r.0)), None),
+                    Some(StableRef::Trip(t)) => {
+                        (Some(StableIdKind::Trip), graph.trip_id_str(t).map(str::to_owned), None)
+                    }
+                    Some(StableRef::Node(n)) => {
+                        (Some(StableIdKind::Street), graph.node_eid(n), graph.get_node(n).map(|d| d.loc()))
+                    }
+                    None => (None, None, None),
+                };
+                ResolvedStableId {
+                    stable_id,
+                    kind,
+                    id,
+                    lat: at.map(|p| p.latitude),
+                    lng: at.map(|p| p.longitude),
+                }
+            })
+            .collect())
+    }
+
+    /// Transit stops within `radiusM` meters (default 500) of a point, closest first.
+    /// `groupByStation` keeps only the closest platform of each station, named after
+    /// the station.
+    #[allow(clippy::too_many_arguments)]
+    async fn stops_nearby(
+        &self,
+        ctx: &Context<'_>,
+        lat: f64,
+        lng: f64,
+        radius_m: Option<i32>,
+        limit: Option<i32>,
+        language: Option<String>,
+        group_by_station: Option<bool>,
+    ) -> Result<Vec<NearbyStop>, Error> {
+        let radius_m = radius_m.unwrap_or(500).max(0);
+        reject_over("radiusM", radius_m, MAX_STOPS_NEARBY_RADIUS_M)?;
+        let limit = limit.map(|l| l.max(0) as usize).unwrap_or(20);
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let language = language.as_deref().and_then(|l| graph.match_language(l));
+        let hits = if group_by_station.unwrap_or(false) {
+            graph.stops_nearby_by_station(lat, lng, radius_m as f64, limit)
+        } else {
+            graph.stops_nearby(lat, lng, radius_m as f64, limit)
+        };
+        Ok(hits
+            .into_iter()
+            .map(|(idx, distance_m, name, lat, lon, mode)| {
+                let station = graph.stop_station(idx);
+                NearbyStop {
+                    stop: GtfsStop {
+                        id: format!("maas:stop:{}", idx),
+                        name: graph.localized(NameField::StopName, &name, language),
+                        lat,
+                        lon,
+                        mode,
+                    },
+                    distance_m,
+                    station_id: station.map(|(id, _)| id.to_string()),
+                    platform_count: station.map(|(_, n)| n as i32),
+                }
+            })
+            .collect())
+    }
+
+    /// OSM car and bicycle parking within `radiusM` meters (default 500) of a point,
+    /// closest first; empty unless the graph was built with the `osm/pbf` input's
+    /// `parking`.
+    async fn parking_nearby(
+        &self,
+        ctx: &Context<'_>,
+        lat: f64,
+        lng: f64,
+        radius_m: Option<i32>,
+        kind: Option<crate::ingestion::osm::ParkingKind>,
+        limit: Option<i32>,
+    ) -> Result<Vec<NearbyParking>, Error> {
+        let radius_m = radius_m.unwrap_or(500).max(0);
+        reject_over("radiusM", radius_m, MAX_STOPS_NEARBY_RADIUS_M)?;
+        let limit = limit.map(|l| l.max(0) as usize).unwrap_or(20);
         let graph = ctx.data::<SharedGraph>()?.load_full();
+        let loc = crate::structures::LatLng { latitude: lat, longitude: lng };
+        Ok(graph
+            .parking_nearby(loc, radius_m as f64, kind)
+            .into_iter()
+            .take(limit)
+            .map(|(distance_m, f)| NearbyParking {
+                name: f.name.clone(),
+                kind: f.kind,
+                lat: f.loc.latitude,
+                lng: f.loc.longitude,
+                distance_m,
+                capacity: f.capacity.map(|c| c.min(i32::MAX as u32) as i32),
+                fee: f.fee,
+                park_and_ride: f.park_ride,
+            })
+            .collect())
+    }
+
+    async fn gtfs_stations(
+        &self,
+        ctx: &Context<'_>,
+        language: Option<String>,
+    ) -> Result<Vec<GtfsStation>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let language = language.as_deref().and_then(|l| graph.match_language(l));
         Ok(graph
             .gtfs_stations()
             .into_iter()
             .map(|(id, name, lat, lon, operators, modes, lines, platform_count)| GtfsStation {
                 id,
-                name,
+                name: graph.localized(NameField::StopName, &name, language),
                 lat,
                 lon,
                 operators,
@@ -1566,6 +2730,37 @@ impl QueryRoot {
             .collect())
     }
 
+    /// Free-text search over OSM addresses and named POIs (graph built with the
+    /// `osm/pbf` input's `geocoder`), best first; the focus point favours nearby hits.
+    async fn geocode(
+        &self,
+        ctx: &Context<'_>,
+        text: String,
+        focus_lat: Option<f64>,
+        focus_lng: Option<f64>,
+        limit: Option<i32>,
+    ) -> Result<Vec<GeocodeResult>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let limit = limit.map(|l| l.clamp(0, 100) as usize).unwrap_or(10);
+        let focus = match (focus_lat, focus_lng) {
+            (Some(latitude), Some(longitude)) => Some(crate::structures::LatLng { latitude, longitude }),
+            _ => None,
+        };
+        Ok(graph
+            .address_index()
+            .search(&text, focus, limit)
+            .into_iter()
+            .map(|h| GeocodeResult {
+                label: h.label,
+                kind: h.kind,
+                category: h.category,
+                lat: h.loc.latitude,
+                lng: h.loc.longitude,
+                score: h.score,
+            })
+            .collect())
+    }
+
     /// CC-BY 4.0 attribution clients must display alongside BeST-Add results.
     async fn address_attribution(&self) -> &'static str {
         ADDRESS_ATTRIBUTION
@@ -1596,6 +2791,349 @@ impl QueryRoot {
             })
             .collect())
     }
+
+    /// One trip's full run on `date` (`YYYY-MM-DD`, default today): every stop with
+    /// scheduled and, for today, realtime-expected times, the live vehicle and the
+    /// shape. `id` is the GTFS `trip_id`; `null` when unknown.
+    async fn trip(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        date: Option<String>,
+    ) -> Result<Option<TripDetails>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let rt = ctx.data::<SharedRealtime>()?.load_full();
+        let (parsed_date, _) = parse_date_time(&date, &None)?;
+        let max_age_secs = ctx
+            .data::<VehiclePositionMaxAgeSecs>()
+            .map(|v| v.0)
+            .unwrap_or(120);
+        let now_unix_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let live = parsed_date == Local::now().date_naive();
+        Ok(trip_details(
+            graph.as_ref(),
+            rt.as_ref(),
+            &id,
+            parsed_date,
+            live,
+            now_unix_secs,
+            max_age_secs,
+        ))
+    }
+
+    /// Every trip of a route running on `date` (`YYYY-MM-DD`, default today) with
+    /// its scheduled per-stop times, earliest first. `routeId` is the `maas:route:`
+    /// id or the GTFS `route_id`; `null` when the route is unknown.
+    async fn route_timetable(
+        &self,
+        ctx: &Context<'_>,
+        route_id: String,
+        date: Option<String>,
+    ) -> Result<Option<RouteTimetable>, Error> {
+        use chrono::Datelike;
+
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let (parsed_date, _) = parse_date_time(&date, &None)?;
+        let Some(route) = graph.route_index_of(&route_id) else {
+            return Ok(None);
+        };
+        let days = crate::ingestion::gtfs::date_to_days(parsed_date);
+        let weekday = 1u8 << parsed_date.weekday().num_days_from_monday();
+
+        let trips = graph
+            .route_timetable(route, days, weekday)
+            .into_iter()
+            .map(|t| RouteTimetableTrip {
+                trip_id: graph.trip_id_str(t.trip).map(str::to_string),
+                headsign: graph.get_trip(t.trip).and_then(|i| i.trip_headsign.clone()),
+                stop_times: t
+                    .stops
+                    .into_iter()
+                    .map(|(node, st)| TimetableStopTime {
+                        stop_id: graph.stop_id_of_node(node).map(str::to_string),
+                        stop_name: graph
+                            .compact_stop_of_node(node)
+                            .and_then(|c| graph.raptor.transit_stop_names.get(c).cloned()),
+                        arrival: st.arrival as i32,
+                        departure: st.departure as i32,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Some(RouteTimetable {
+            route: crate::structures::plan::PlanRoute::from_route_id(graph.as_ref(), Some(route)),
+            date: parsed_date.format("%Y-%m-%d").to_string(),
+            trips,
+        }))
+    }
+
+    /// Dates in `from..=to` (`YYYY-MM-DD`, at most 366 days) on which the route or the
+    /// trip (exactly one of `routeId` / `tripId`) operates, for greying out days in a
+    /// date picker. `null` for an unknown id.
+    async fn service_calendar(
+        &self,
+        ctx: &Context<'_>,
+        route_id: Option<String>,
+        trip_id: Option<String>,
+        from: String,
+        to: String,
+    ) -> Result<Option<Vec<String>>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let (from, _) = parse_date_time(&Some(from), &None)?;
+        let (to, _) = parse_date_time(&Some(to), &None)?;
+        if to < from {
+            return Err(Error::new("to must not be before from"));
+        }
+        reject_over("serviceCalendar range (days)", (to - from).num_days() as i32 + 1, 366)?;
+        let services = match (route_id, trip_id) {
+            (Some(route), None) => match graph.route_index_of(&route) {
+                Some(route) => graph.route_services(route),
+                None => return Ok(None),
+            },
+            (None, Some(trip)) => match graph.resolve_trip_id(&trip).and_then(|t| graph.get_trip(t)) {
+                Some(info) => vec![info.service_id],
+                None => return Ok(None),
+            },
+            _ => return Err(Error::new("exactly one of routeId or tripId is required")),
+        };
+        Ok(Some(
+            graph
+                .service_dates(&services, from, to)
+                .into_iter()
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .collect(),
+        ))
+    }
+
+    /// Timetable segments of the transit edge `edge` (one per route serving the hop),
+    /// for inspecting raw departure data.
+    async fn timetable_segment(
+        &self,
+        ctx: &Context<'_>,
+        edge: TransitEdgeInput,
+    ) -> Result<Vec<TimetableSegmentInfo>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let node = |id: &str| {
+            graph
+                .resolve_stop_id(id)
+                .map(|c| graph.raptor.transit_stop_to_node[c])
+                .ok_or_else(|| Error::new(format!("unknown stop '{id}'")))
+        };
+        let (from, to) = (node(&edge.from_stop_id)?, node(&edge.to_stop_id)?);
+        Ok(graph
+            .transit_edge_segments(from, to)
+            .into_iter()
+            .map(|(route, tt)| TimetableSegmentInfo {
+                start: tt.start as i32,
+                len: tt.len as i32,
+                route: crate::structures::plan::PlanRoute::from_route_id(graph.as_ref(), Some(route)),
+                sorted: graph.raptor.transit_departures.departures[tt.range()].is_sorted(),
+            })
+            .collect())
+    }
+
+    /// Departures of a raw timetable segment running on `date` (default today):
+    /// the first `count` (default 10, max 500), or with `from` (an absolute index) the
+    /// ones strictly after it, or strictly before it (nearest first) when `before`.
+    async fn departures(
+        &self,
+        ctx: &Context<'_>,
+        segment: TimetableSegmentInput,
+        date: Option<String>,
+        from: Option<i32>,
+        count: Option<i32>,
+        #[graphql(default = false)] before: bool,
+    ) -> Result<Vec<SegmentDeparture>, Error> {
+        use chrono::Datelike;
+
+        let count = count.unwrap_or(10);
+        reject_over("count", count, 500)?;
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let (parsed_date, _) = parse_date_time(&date, &None)?;
+        let days = crate::ingestion::gtfs::date_to_days(parsed_date);
+        let weekday = 1u8 << parsed_date.weekday().num_days_from_monday();
+        let (Ok(start), Ok(len)) = (u32::try_from(segment.start), u32::try_from(segment.len)) else {
+            return Err(Error::new("segment start and len must be >= 0"));
+        };
+        let tt = crate::ingestion::gtfs::TimetableSegment { start, len };
+        if !graph.timetable_segment_in_bounds(tt) {
+            return Err(Error::new(format!(
+                "segment {start}+{len} exceeds the {} departures",
+                graph.get_transit_departures_size()
+            )));
+        }
+        let take = count.max(0) as usize;
+        let rows: Vec<_> = match from {
+            None => graph.departures_on(tt, days, weekday).take(take).collect(),
+            Some(i) => {
+                let active = graph.raptor.active_services(days, weekday);
+                if before {
+                    graph.previous_departures(tt, &active, i.max(0) as usize).take(take).collect()
+                } else {
+                    graph.next_departures(tt, &active, i.max(0) as usize).take(take).collect()
+                }
+            }
+        };
+        Ok(rows
+            .into_iter()
+            .map(|(i, dep)| SegmentDeparture {
+                index: i as i32,
+                trip_id: graph.trip_id_str(dep.trip_id).map(str::to_string),
+                departure: dep.departure as i32,
+                arrival: dep.arrival as i32,
+                service_index: dep.service_id.0 as i32,
+            })
+            .collect())
+    }
+
+    /// Anonymised demand aggregates (admin only): volume, latency, departure hours,
+    /// modes and the `top` (default 20, max 200) busiest OD cell pairs.
+    async fn analytics_stats(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        top: Option<i32>,
+    ) -> Result<AnalyticsStats, Error> {
+        require_admin(ctx, &token)?;
+        let analytics = ctx.data::<Arc<Analytics>>()?;
+        let s = analytics.snapshot(top.unwrap_or(20).clamp(0, 200) as usize);
+        Ok(AnalyticsStats {
+            enabled: analytics.is_enabled(),
+            requests: s.requests as i64,
+            empty: s.empty as i64,
+            errors: s.errors as i64,
+            latency_p50_ms: s.latency_p50_ms,
+            latency_p95_ms: s.latency_p95_ms,
+            by_hour: s.by_hour.into_iter().map(|n| n as i64).collect(),
+            by_mode: s
+                .by_mode
+                .into_iter()
+                .map(|(mode, count)| ModeCount { mode, count: count as i64 })
+                .collect(),
+            top_od_pairs: s.top_od.into_iter().map(OdPairCount::from).collect(),
+        })
+    }
+
+    /// Busiest OD cell pairs among the plan requests since `since` (unix seconds, admin
+    /// only). Only the most recent requests are kept in memory, see `analyticsStats`.
+    async fn top_origin_destinations(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        since: i64,
+        top: Option<i32>,
+    ) -> Result<Vec<OdPairCount>, Error> {
+        require_admin(ctx, &token)?;
+        let analytics = ctx.data::<Arc<Analytics>>()?;
+        let top = top.unwrap_or(20).clamp(0, 200) as usize;
+        Ok(analytics.top_od_since(since.max(0) as u64, top).into_iter().map(OdPairCount::from).collect())
+    }
+
+    /// The `limit` (default 20, max 200) slowest plan requests since `since` (unix
+    /// seconds, admin only), slowest first.
+    async fn slowest_queries(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        since: i64,
+        limit: Option<i32>,
+    ) -> Result<Vec<SlowQuery>, Error> {
+        require_admin(ctx, &token)?;
+        let analytics = ctx.data::<Arc<Analytics>>()?;
+        let limit = limit.unwrap_or(20).clamp(0, 200) as usize;
+        Ok(analytics
+            .slowest_since(since.max(0) as u64, limit)
+            .into_iter()
+            .map(|r| SlowQuery {
+                at: r.at as i64,
+                source: r.source.to_string(),
+                from_lat: r.from.lat,
+                from_lng: r.from.lng,
+                to_lat: r.to.lat,
+                to_lng: r.to.lng,
+                hour: r.hour as i32,
+                modes: r.modes.into_iter().map(str::to_string).collect(),
+                latency_ms: r.latency_ms,
+                results: r.results as i32,
+                error: r.error,
+            })
+            .collect())
+    }
+
+    /// Error and empty-answer rates of the plan requests since `since` (unix seconds,
+    /// admin only): `all` first, then one entry per source.
+    async fn error_rates(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        since: i64,
+    ) -> Result<Vec<ErrorRate>, Error> {
+        require_admin(ctx, &token)?;
+        let analytics = ctx.data::<Arc<Analytics>>()?;
+        let rate = |n: u64, of: u64| if of == 0 { 0.0 } else { n as f64 / of as f64 };
+        Ok(analytics
+            .outcomes_since(since.max(0) as u64)
+            .into_iter()
+            .map(|c| ErrorRate {
+                error_rate: rate(c.errors, c.requests),
+                empty_rate: rate(c.empty, c.requests),
+                source: c.source,
+                requests: c.requests as i64,
+                empty: c.empty as i64,
+                errors: c.errors as i64,
+            })
+            .collect())
+    }
+
+    /// Per-feed GTFS data-quality counters recorded when the served graph was built.
+    async fn ingestion_reports(&self, ctx: &Context<'_>) -> Result<Vec<IngestionReportGql>, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        Ok(graph.ingestion_reports.iter().map(IngestionReportGql::from).collect())
+    }
+
+    /// Records skipped while loading GTFS (no coordinates, unknown service, no street
+    /// within reach, …), optionally narrowed to one feed label and/or reason. At most
+    /// `REPORT_MAX_WARNINGS` are kept per feed; the `ingestionReports` counters are exact.
+    async fn ingestion_warnings(
+        &self,
+        ctx: &Context<'_>,
+        feed: Option<String>,
+        reason: Option<IngestionWarningReasonGql>,
+        #[graphql(default = 0)] offset: i32,
+        #[graphql(default = 100)] limit: i32,
+    ) -> Result<IngestionWarningPage, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let reason = reason.map(IngestionWarningReason::from);
+        let matching = graph
+            .ingestion_reports
+            .iter()
+            .filter(|r| feed.as_deref().is_none_or(|f| r.feed == f))
+            .flat_map(|r| r.warnings.iter().map(move |w| (r, w)))
+            .filter(|(_, w)| reason.is_none_or(|reason| w.reason == reason));
+        let total = matching.clone().count();
+        let warnings = matching
+            .skip(offset.max(0) as usize)
+            .take(limit.clamp(0, 1000) as usize)
+            .map(|(r, w)| IngestionWarningGql {
+                feed: r.feed.clone(),
+                reason: w.reason.into(),
+                entity_id: w.entity_id.clone(),
+                detail: w.detail.clone(),
+            })
+            .collect();
+        Ok(IngestionWarningPage { total: total as i32, warnings })
+    }
+
+    /// What the served graph was built from: inputs with hashes, GTFS validity and OSM
+    /// extract age, plus the building version and when.
+    async fn data_info(&self, ctx: &Context<'_>) -> Result<DataInfoGql, Error> {
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        Ok(DataInfoGql::from(graph.build_info()))
+    }
 }
 
 const INDEX_HTML: &str = include_str!("static/index.html");
@@ -1727,28 +3265,118 @@ pub async fn icon_maskable_svg_handler() -> Svg {
     Svg(ICON_MASKABLE_SVG)
 }
 
+pub struct MutationRoot;
+
+#[async_graphql::Object]
+impl MutationRoot {
+    /// Drop every cached `raptor` result; returns the number of entries removed.
+    async fn flush_plan_cache(&self, ctx: &Context<'_>, token: String) -> Result<i32, Error> {
+        require_admin(ctx, &token)?;
+        Ok(ctx.data::<Arc<PlanCache>>()?.flush() as i32)
+    }
+
+    /// Write the live graph to `path` (previous file kept as `<path>.prev`), so a graph
+    /// built in-process can be captured without restarting with `--save`.
+    async fn save_graph(&self, ctx: &Context<'_>, token: String, path: String) -> Result<bool, Error> {
+        require_admin(ctx, &token)?;
+        if path.is_empty() {
+            return Err(Error::new("path must not be empty"));
+        }
+        let graph = ctx.data::<SharedGraph>()?.load_full();
+        let fingerprint = ctx.data::<GraphFingerprint>()?.clone();
+        tokio::task::spawn_blocking(move || {
+            save_graph_with_rollback(&graph, &(fingerprint.0)(), &path)
+        })
+        .await
+        .map_err(|_| Error::new("graph save failed"))?
+        .map_err(Error::new)?;
+        Ok(true)
+    }
+}
+
+fn require_admin(ctx: &Context<'_>, token: &str) -> Result<(), Error> {
+    match &ctx.data::<AdminToken>()?.0 {
+        Some(expected) if expected == token => Ok(()),
+        Some(_) => Err(Error::new("invalid admin token")),
+        None => Err(Error::new("admin operations are disabled (server.admin_token unset)")),
+    }
+}
+
 #[handler]
 async fn graphiql() -> Html<String> {
-    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+    Html(
+        GraphiQLSource::build()
+            .endpoint("/graphql")
+            .subscription_endpoint("/graphql/ws")
+            .finish(),
+    )
 }
 
-pub fn build_schema(graph: SharedGraph) -> Schema<QueryRoot, EmptyMutation, EmptySubscription> {
+pub fn build_schema(graph: SharedGraph) -> AppSchema {
     let realtime: SharedRealtime = Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new()));
     build_schema_rt(graph, realtime)
 }
 
+/// [`build_schema`] with `permits` concurrent routing searches
+/// (`server.max_concurrent_searches`; `None`: one per core).
+pub fn build_schema_with_permits(graph: SharedGraph, permits: Option<usize>) -> AppSchema {
+    let realtime: SharedRealtime = Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new()));
+    let address: SharedAddressIndex = Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default()));
+    build_schema_with_limiter(
+        graph,
+        realtime,
+        realtime_updates(),
+        120,
+        address,
+        WebConfig::default(),
+        None,
+        None,
+        Arc::new(PlanCache::disabled()),
+        None,
+        GraphFingerprint::unset(),
+        HeavyQueryLimiter::with_permits(permits),
+        true,
+        Arc::new(Analytics::disabled()),
+    )
+}
+
 pub fn build_schema_rt(
     graph: SharedGraph,
     realtime: SharedRealtime,
-) -> Schema<QueryRoot, EmptyMutation, EmptySubscription> {
+) -> AppSchema {
     build_schema_rt_full(graph, realtime, 120)
 }
 
+/// [`build_schema_rt`] whose `stopDepartures` subscriptions listen on `updates`.
+pub fn build_schema_rt_live(
+    graph: SharedGraph,
+    realtime: SharedRealtime,
+    updates: RealtimeUpdates,
+) -> AppSchema {
+    let address: SharedAddressIndex = Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default()));
+    build_schema_with_limiter(
+        graph,
+        realtime,
+        updates,
+        120,
+        address,
+        WebConfig::default(),
+        None,
+        None,
+        Arc::new(PlanCache::disabled()),
+        None,
+        GraphFingerprint::unset(),
+        HeavyQueryLimiter::new(),
+        true,
+        Arc::new(Analytics::disabled()),
+    )
+}
+
 pub fn build_schema_rt_full(
     graph: SharedGraph,
     realtime: SharedRealtime,
     vehicle_position_max_age_secs: u64,
-) -> Schema<QueryRoot, EmptyMutation, EmptySubscription> {
+) -> AppSchema {
     let address: SharedAddressIndex = Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default()));
     build_schema_full(graph, realtime, vehicle_position_max_age_secs, address, WebConfig::default(), None, None)
 }
@@ -1761,14 +3389,80 @@ pub fn build_schema_full(
     web_config: WebConfig,
     max_depth: Option<usize>,
     max_complexity: Option<usize>,
-) -> Schema<QueryRoot, EmptyMutation, EmptySubscription> {
-    let mut builder = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+) -> AppSchema {
+    build_schema_server(
+        graph,
+        realtime,
+        vehicle_position_max_age_secs,
+        address,
+        web_config,
+        max_depth,
+        max_complexity,
+        Arc::new(PlanCache::disabled()),
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn build_schema_server(
+    graph: SharedGraph,
+    realtime: SharedRealtime,
+    vehicle_position_max_age_secs: u64,
+    address: SharedAddressIndex,
+    web_config: WebConfig,
+    max_depth: Option<usize>,
+    max_complexity: Option<usize>,
+    plan_cache: Arc<PlanCache>,
+    admin_token: Option<String>,
+) -> AppSchema {
+    build_schema_with_limiter(
+        graph,
+        realtime,
+        realtime_updates(),
+        vehicle_position_max_age_secs,
+        address,
+        web_config,
+        max_depth,
+        max_complexity,
+        plan_cache,
+        admin_token,
+        GraphFingerprint::unset(),
+        HeavyQueryLimiter::new(),
+        true,
+        Arc::new(Analytics::disabled()),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_schema_with_limiter(
+    graph: SharedGraph,
+    realtime: SharedRealtime,
+    updates: RealtimeUpdates,
+    vehicle_position_max_age_secs: u64,
+    address: SharedAddressIndex,
+    web_config: WebConfig,
+    max_depth: Option<usize>,
+    max_complexity: Option<usize>,
+    plan_cache: Arc<PlanCache>,
+    admin_token: Option<String>,
+    graph_fp: GraphFingerprint,
+    limiter: HeavyQueryLimiter,
+    introspection: bool,
+    analytics: Arc<Analytics>,
+) -> AppSchema {
+    let mut builder = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
         .data(graph)
         .data(realtime)
+        .data(updates)
         .data(address)
         .data(web_config)
         .data(VehiclePositionMaxAgeSecs(vehicle_position_max_age_secs))
-        .data(HeavyQueryLimiter(Arc::new(Semaphore::new(HEAVY_QUERY_PERMITS))));
+        .data(limiter)
+        .data(plan_cache)
+        .data(Arc::new(PageCursors::default()))
+        .data(analytics)
+        .data(AdminToken(admin_token))
+        .data(graph_fp);
     if let Some(depth) = max_depth {
         builder = builder.limit_depth(depth);
     }
@@ -1776,14 +3470,48 @@ pub fn build_schema_full(
         .map(|c| c.min(MAX_COMPLEXITY_CEILING))
         .unwrap_or(MAX_COMPLEXITY_CEILING);
     builder = builder.limit_complexity(complexity);
+    if !introspection {
+        builder = builder.disable_introspection();
+    }
     builder.finish()
 }
 
+/// Resolves on SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Ctrl-C handler unavailable: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("SIGTERM handler unavailable: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 pub async fn server(graph: SharedGraph, config: Arc<Config>) -> std::io::Result<()> {
-    scheduler::spawn(graph.clone(), config.clone());
+    config.check_servable().map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let updater = scheduler::spawn(graph.clone(), config.clone(), shutdown_rx.clone());
 
     let realtime: SharedRealtime = Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new()));
-    realtime_poller::spawn(graph.clone(), realtime.clone(), config.clone());
+    let updates = realtime_updates();
+    realtime_poller::spawn(graph.clone(), realtime.clone(), updates.clone(), config.clone());
 
     let vp_max_age = config
         .realtime
@@ -1808,16 +3536,51 @@ pub async fn server(graph: SharedGraph, config: Arc<Config>) -> std::io::Result<
         tile_attribution: config.server.tiles.attribution.clone(),
         graphiql_enabled: config.server.graphiql_enabled,
     };
-    let schema = build_schema_full(
+    let pc = &config.server.plan_cache;
+    let plan_cache = Arc::new(PlanCache::new(
+        pc.capacity,
+        std::time::Duration::from_secs(pc.ttl_secs),
+        pc.bucket_secs,
+    ));
+    let limiter = HeavyQueryLimiter::with_permits(config.server.max_concurrent_searches);
+    let analytics = Arc::new(Analytics::new(&config.server.analytics));
+    #[cfg(feature = "grpc")]
+    if config.server.grpc.enabled {
+        let bind = format!("{}:{}", config.server.host, config.server.grpc.port);
+        let state = super::grpc::GrpcState {
+            graph: graph.clone(),
+            realtime: realtime.clone(),
+            limiter: limiter.clone(),
+            analytics: analytics.clone(),
+        };
+        let shutdown = shutdown_rx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = super::grpc::serve(state, &bind, shutdown).await {
+                tracing::error!("gRPC server failed: {e}");
+            }
+        });
+    }
+    let schema = build_schema_with_limiter(
         graph,
         realtime,
+        updates,
         vp_max_age,
         address,
         web_config,
         Some(config.server.graphql_max_depth),
         Some(config.server.graphql_max_complexity),
+        plan_cache,
+        config.server.admin_token.clone(),
+        GraphFingerprint(Arc::new({
+            let config = config.clone();
+            move || crate::services::fingerprint::graph_fingerprint(&config, &config.cache_dir())
+        })),
+        limiter.clone(),
+        config.server.introspection_enabled,
+        analytics,
     );
     let mut app = Route::new()
+        .at("/graphql/ws", get(GraphQLSubscription::new(schema.clone())))
         .at("/graphql", GraphQL::new(schema).with(SizeLimit::new(64 * 1024)))
         .at("/maas.js", get(maas_js_handler))
         .at("/static/js/live-db.mjs", get(live_db_js_handler))
@@ -1842,12 +3605,43 @@ pub async fn server(graph: SharedGraph, config: Arc<Config>) -> std::io::Result<
         .at("/", get(index_page));
 
     if config.server.graphiql_enabled {
+        if !config.server.introspection_enabled {
+            tracing::warn!("graphiql_enabled without introspection_enabled: GraphiQL cannot load the schema");
+        }
         app = app.at("/graphiql", get(graphiql));
     }
 
     let bind = format!("{}:{}", config.server.host, config.server.port);
     tracing::info!("serving on {bind}");
-    Server::new(TcpListener::bind(&bind)).run(app).await
+    // Stop accepting on a signal, let in-flight requests finish within the grace
+    // period, then wait (same deadline) for routing searches still on the blocking pool
+    // and for an auto-update whose graph save must not be cut short.
+    let grace = std::time::Duration::from_secs(config.server.shutdown_grace_secs);
+    let deadline = std::sync::OnceLock::new();
+    let signal = async {
+        shutdown_signal().await;
+        tracing::info!("shutdown requested; draining for up to {}s", grace.as_secs());
+        let _ = deadline.set(tokio::time::Instant::now() + grace);
+        let _ = shutdown_tx.send(true);
+    };
+    Server::new(TcpListener::bind(&bind))
+        .run_with_graceful_shutdown(app, signal, Some(grace))
+        .await?;
+    let deadline = deadline
+        .get()
+        .copied()
+        .unwrap_or_else(|| tokio::time::Instant::now() + grace);
+    let drained = tokio::time::timeout_at(deadline, async {
+        limiter.drain().await;
+        if let Some(updater) = updater {
+            let _ = updater.await;
+        }
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!("shutdown grace period elapsed with work still running; exiting");
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1894,4 +3688,125 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().message.contains("Invalid time"));
     }
+
+    #[test]
+    fn limiter_defaults_to_one_permit_per_core() {
+        let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+        assert_eq!(HeavyQueryLimiter::new().0.available_permits(), cores);
+        assert_eq!(HeavyQueryLimiter::with_permits(Some(2)).0.available_permits(), 2);
+        assert_eq!(HeavyQueryLimiter::with_permits(Some(0)).0.available_permits(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn limiter_drain_waits_for_running_searches() {
+        let limiter = HeavyQueryLimiter::new();
+        let done = Arc::new(AtomicBool::new(false));
+        let flag = done.clone();
+        let running = tokio::spawn({
+            let limiter = limiter.clone();
+            async move {
+                limiter
+                    .run(move || {
+                        std::thread::sleep(std::time::Duration::from_millis(100));
+                        flag.store(true, Ordering::SeqCst);
+                        Ok(())
+                    })
+                    .await
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        limiter.drain().await;
+        assert!(done.load(Ordering::SeqCst), "drain returned before the search finished");
+        running.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn introspection_can_be_disabled() {
+        let schema = |introspection| {
+            build_schema_with_limiter(
+                Arc::new(arc_swap::ArcSwap::from_pointee(crate::structures::Graph::new())),
+                Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new())),
+                realtime_updates(),
+                120,
+                Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default())),
+                WebConfig::default(),
+                None,
+                None,
+                Arc::new(PlanCache::disabled()),
+                None,
+                GraphFingerprint::unset(),
+                HeavyQueryLimiter::new(),
+                introspection,
+                Arc::new(Analytics::disabled()),
+            )
+        };
+        let query = "{ __schema { queryType { name } } ping }";
+        let open = schema(true).execute(query).await;
+        assert!(open.errors.is_empty(), "{:?}", open.errors);
+        assert!(open.data.to_string().contains("QueryRoot"));
+        let closed = schema(false).execute(query).await;
+        assert!(!closed.data.to_string().contains("QueryRoot"), "{}", closed.data);
+        let ping = schema(false).execute("{ ping }").await;
+        assert!(ping.errors.is_empty(), "regular queries still work: {:?}", ping.errors);
+    }
+
+    #[tokio::test]
+    async fn analytics_stats_reports_recorded_requests_to_admins() {
+        let dir = std::env::temp_dir().join(format!("maas_app_analytics_{}", std::process::id()));
+        let config = crate::structures::AnalyticsConfig {
+            enabled: true,
+            path: dir.join("a.jsonl").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let schema = build_schema_with_limiter(
+            Arc::new(arc_swap::ArcSwap::from_pointee(crate::structures::Graph::new())),
+            Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new())),
+            realtime_updates(),
+            120,
+            Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default())),
+            WebConfig::default(),
+            None,
+            None,
+            Arc::new(PlanCache::disabled()),
+            Some("s3cret".to_string()),
+            GraphFingerprint::unset(),
+            HeavyQueryLimiter::new(),
+            true,
+            Arc::new(Analytics::new(&config)),
+        );
+        let plan = schema
+            .execute("{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.01, toLng: 4.01, time: \"07:30\") { start } }")
+            .await;
+        assert!(!plan.errors.is_empty(), "empty graph cannot route");
+        let stats = "{ analyticsStats(token: \"s3cret\") { enabled requests errors byHour byMode { mode count } topOdPairs { count } } }";
+        let resp = schema.execute(stats).await;
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        let json = resp.data.into_json().unwrap();
+        let s = &json["analyticsStats"];
+        assert_eq!(s["enabled"], true);
+        assert_eq!((s["requests"].as_i64(), s["errors"].as_i64()), (Some(1), Some(1)));
+        assert_eq!(s["byHour"][7], 1);
+        assert_eq!(s["byMode"][0]["mode"], "default");
+        assert_eq!(s["topOdPairs"][0]["count"], 1);
+        let denied = schema.execute(stats.replace("s3cret", "nope")).await;
+        assert!(!denied.errors.is_empty());
+
+        let dashboard = "{ topOriginDestinations(token: \"s3cret\", since: 0) { count } \
+            slowestQueries(token: \"s3cret\", since: 0) { source hour modes error } \
+            errorRates(token: \"s3cret\", since: 0) { source requests errors errorRate } }";
+        let resp = schema.execute(dashboard).await;
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        let json = resp.data.into_json().unwrap();
+        assert_eq!(json["topOriginDestinations"][0]["count"], 1);
+        let slow = &json["slowestQueries"][0];
+        assert_eq!((&slow["source"], &slow["hour"], &slow["error"]), (&"graphql".into(), &7.into(), &true.into()));
+        assert_eq!(json["errorRates"][0]["source"], "all");
+        assert_eq!(json["errorRates"][0]["errorRate"], 1.0);
+        let future = "{ errorRates(token: \"s3cret\", since: 99999999999) { requests errorRate } }";
+        let json = schema.execute(future).await.data.into_json().unwrap();
+        assert_eq!((&json["errorRates"][0]["requests"], &json["errorRates"][0]["errorRate"]), (&0.into(), &0.0.into()));
+        let denied = schema.execute(dashboard.replace("s3cret", "nope")).await;
+        assert!(!denied.errors.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
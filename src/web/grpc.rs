@@ -0,0 +1,498 @@
+//! gRPC `PlanService` (`proto/maas.proto`) on its own port, sharing the graph, realtime
+//! index and routing limiter with the GraphQL server. All RPCs are unary, so requests
+//! are routed by path over hyper's HTTP/2 and framed by hand (no compression).
+//!
+//! Why not tonic: three unary RPCs need only the 5-byte length-prefixed frame, a path
+//! match and the `grpc-status` trailer, which hyper (already in the tree under poem)
+//! gives us directly; messages are plain `prost` types generated by `build.rs`. tonic
+//! would add tower, its codegen and its own server loop for that, and a second
+//! shutdown/limiter integration next to poem's. What is left out on purpose: message
+//! compression (rejected with `UNIMPLEMENTED`), streaming, reflection and the health
+//! service. A streaming RPC is the point at which switching to tonic pays off.
+//!
+//! Planner errors map to standard status codes; the planner's own code (the one
+//! GraphQL puts in `extensions.code`) travels in the `maas-error-code` trailer.
+
+use std::convert::Infallible;
+use std::future::{Ready, ready};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited, combinators::WithTrailers};
+use hyper::{
+    HeaderMap, Request, Response,
+    body::Incoming,
+    header::{CONTENT_TYPE, HeaderValue},
+    server::conn::http2,
+    service::service_fn,
+};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use prost::Message;
+use tokio::sync::watch;
+
+use super::analytics::Analytics;
+use super::app::{
+    HeavyQueryLimiter, MAX_STOPS_NEARBY_RADIUS_M, MAX_WINDOW_MINUTES, parse_date_time_str,
+};
+use super::subscriptions::departure_board;
+use crate::{
+    routing::routing_raptor::{self, RouteQuery},
+    services::{realtime_poller::SharedRealtime, scheduler::SharedGraph},
+    structures::{
+        Mode,
+        plan::{Plan, PlanBikeLeg, PlanCarLeg, PlanCoordinate, PlanError, PlanLeg},
+    },
+};
+
+pub mod proto {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/maas.v1.rs"));
+}
+
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 200;
+
+// https://grpc.github.io/grpc/core/md_doc_statuscodes.html
+const OK: u16 = 0;
+const CANCELLED: u16 = 1;
+const UNKNOWN: u16 = 2;
+const INVALID_ARGUMENT: u16 = 3;
+const DEADLINE_EXCEEDED: u16 = 4;
+const NOT_FOUND: u16 = 5;
+const RESOURCE_EXHAUSTED: u16 = 8;
+const UNIMPLEMENTED: u16 = 12;
+
+pub(super) struct GrpcState {
+    pub(super) graph: SharedGraph,
+    pub(super) realtime: SharedRealtime,
+    pub(super) limiter: HeavyQueryLimiter,
+    pub(super) analytics: Arc<Analytics>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Status {
+    code: u16,
+    message: String,
+    /// `PlanError::code`, sent as the `maas-error-code` trailer.
+    error_code: Option<&'static str>,
+}
+
+impl Status {
+    fn new(code: u16, message: impl Into<String>) -> Self {
+        Status { code, message: message.into(), error_code: None }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Status::new(INVALID_ARGUMENT, message)
+    }
+}
+
+/// The remaining coded errors (`OUTSIDE_COVERAGE`, `UNKNOWN_PROFILE`,
+/// `INVALID_ITINERARY_ID`, ...) reject something in the request; an uncoded one is
+/// `UNKNOWN`.
+impl From<PlanError> for Status {
+    fn from(e: PlanError) -> Self {
+        let code = match e.code {
+            Some("SEARCH_TIMEOUT") => DEADLINE_EXCEEDED,
+            Some("SEARCH_CANCELLED") => CANCELLED,
+            Some("SEARCH_EXPANSION_LIMIT") => RESOURCE_EXHAUSTED,
+            Some("NO_ROUTE" | "ITINERARY_UNAVAILABLE" | "VIA_UNREACHABLE") => NOT_FOUND,
+            Some(_) => INVALID_ARGUMENT,
+            None => UNKNOWN,
+        };
+        Status { error_code: e.code, ..Status::new(code, e.message) }
+    }
+}
+
+type GrpcBody = WithTrailers<Full<Bytes>, Ready<Option<Result<HeaderMap, Infallible>>>>;
+
+/// Accepts until `shutdown` flips to `true`; searches already running are drained by
+/// the caller through the shared limiter.
+pub(super) async fn serve(
+    state: GrpcState,
+    bind: &str,
+    mut shutdown: watch::Receiver<bool>,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    tracing::info!("gRPC serving on {bind}");
+    let state = Arc::new(state);
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = shutdown.wait_for(|&stop| stop) => return Ok(()),
+        };
+        let (stream, _) = match accepted {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("gRPC accept failed: {e}");
+                continue;
+            }
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            let svc = service_fn(move |req| handle(state.clone(), req));
+            if let Err(e) = http2::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), svc)
+                .await
+            {
+                tracing::debug!("gRPC connection closed: {e}");
+            }
+        });
+    }
+}
+
+async fn handle(
+    state: Arc<GrpcState>,
+    req: Request<Incoming>,
+) -> Result<Response<GrpcBody>, Infallible> {
+    let path = req.uri().path().to_owned();
+    let result = match Limited::new(req.into_body(), MAX_REQUEST_BYTES).collect().await {
+        Ok(body) => dispatch(&state, &path, &body.to_bytes()).await,
+        Err(_) => Err(Status::new(RESOURCE_EXHAUSTED, "request too large")),
+    };
+    Ok(response(result))
+}
+
+/// Status goes in trailers, also on errors (then with an empty body).
+fn response(result: Result<Vec<u8>, Status>) -> Response<GrpcBody> {
+    let (data, status) = match result {
+        Ok(data) => (data, Status::new(OK, "")),
+        Err(status) => (Vec::new(), status),
+    };
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from(status.code));
+    if !status.message.is_empty()
+        && let Ok(v) = HeaderValue::from_str(&percent_encode(&status.message))
+    {
+        trailers.insert("grpc-message", v);
+    }
+    if let Some(code) = status.error_code {
+        trailers.insert("maas-error-code", HeaderValue::from_static(code));
+    }
+    let body = Full::new(Bytes::from(data)).with_trailers(ready(Some(Ok(trailers))));
+    let mut res = Response::new(body);
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+    res
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b' '..=b'~' if b != b'%' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+async fn dispatch(state: &GrpcState, path: &str, body: &[u8]) -> Result<Vec<u8>, Status> {
+    let msg = decode_frame(body)?;
+    match path {
+        "/maas.v1.PlanService/Plan" => plan(state, decode(msg)?).await.map(|r| encode_frame(&r)),
+        "/maas.v1.PlanService/Departures" => departures(state, decode(msg)?).map(|r| encode_frame(&r)),
+        "/maas.v1.PlanService/NearbyStops" => {
+            nearby_stops(state, decode(msg)?).map(|r| encode_frame(&r))
+        }
+        _ => Err(Status::new(UNIMPLEMENTED, format!("unknown method '{path}'"))),
+    }
+}
+
+/// One length-prefixed message: compressed flag (must be 0) + big-endian u32 length.
+fn decode_frame(body: &[u8]) -> Result<&[u8], Status> {
+    let Some((header, msg)) = body.split_first_chunk::<5>() else {
+        return Err(Status::invalid("truncated gRPC frame"));
+    };
+    if header[0] != 0 {
+        return Err(Status::new(UNIMPLEMENTED, "compressed messages are not supported"));
+    }
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if msg.len() != len {
+        return Err(Status::invalid("gRPC frame length does not match the body"));
+    }
+    Ok(msg)
+}
+
+fn encode_frame(msg: &impl Message) -> Vec<u8> {
+    let payload = msg.encode_to_vec();
+    let mut out = Vec::with_capacity(5 + payload.len());
+    out.push(0);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(&payload);
+    out
+}
+
+fn decode<M: Message + Default>(msg: &[u8]) -> Result<M, Status> {
+    M::decode(msg).map_err(|e| Status::invalid(format!("malformed request: {e}")))
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    (!s.is_empty()).then_some(s)
+}
+
+fn limit_or_default(limit: u32) -> usize {
+    let limit = match limit {
+        0 => DEFAULT_LIMIT,
+        l => l.min(MAX_LIMIT),
+    };
+    limit as usize
+}
+
+async fn plan(state: &GrpcState, req: proto::PlanRequest) -> Result<proto::PlanResponse, Status> {
+    let (Some(from), Some(to)) = (req.from, req.to) else {
+        return Err(Status::invalid("from and to are required"));
+    };
+    let (date, time) = parse_date_time_str(non_empty(&req.date), non_empty(&req.time))
+        .map_err(Status::invalid)?;
+    if req.window_minutes > MAX_WINDOW_MINUTES as u32 {
+        return Err(Status::invalid(format!(
+            "window_minutes must be <= {MAX_WINDOW_MINUTES}"
+        )));
+    }
+    let modes = if req.modes.is_empty() {
+        None
+    } else {
+        let modes = req
+            .modes
+            .iter()
+            .map(|m| Mode::from_name(m).ok_or_else(|| Status::invalid(format!("unknown mode '{m}'"))))
+            .collect::<Result<Vec<_>, _>>()?;
+        Some(modes)
+    };
+    let query = RouteQuery {
+        from_lat: from.lat,
+        from_lng: from.lng,
+        to_lat: to.lat,
+        to_lng: to.lng,
+        date,
+        time,
+        window_minutes: (req.window_minutes > 0).then_some(req.window_minutes),
+        min_access_secs: None,
+        arrival_slack_secs: None,
+        unrestricted_transfers: None,
+        use_cch_access: None,
+        reliability_bucket_edges: None,
+        modes,
+        bike_profile: None,
+        terminal_deadline: false,
+        onboard_origin: None,
+        from_station_id: None,
+        to_station_id: None,
+        profile_latency: None,
+        fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
+    };
+
+    let graph = state.graph.load_full();
+    let rt = state.realtime.load_full();
+    let routed = graph.clone();
+    let pending = state.analytics.start("grpc", &query);
+    let result = state
+        .limiter
+        .run(move || routing_raptor::route(routed.as_ref(), &query, rt.as_ref()))
+        .await;
+    state.analytics.finish(pending, result.as_deref().map_err(|_| ()));
+    let plans = result?;
+    Ok(proto::PlanResponse {
+        itineraries: plans.iter().map(itinerary).collect(),
+    })
+}
+
+fn coordinates(geometry: &[PlanCoordinate]) -> Vec<proto::Coordinate> {
+    geometry
+        .iter()
+        .map(|c| proto::Coordinate { lat: c.lat, lng: c.lon })
+        .collect()
+}
+
+fn itinerary(plan: &Plan) -> proto::Itinerary {
+    let legs = plan
+        .legs
+        .iter()
+        .map(|leg| match leg {
+            PlanLeg::Walk(w)
+            | PlanLeg::Bike(PlanBikeLeg { leg: w })
+            | PlanLeg::Car(PlanCarLeg { leg: w }) => proto::Leg {
+                kind: proto::leg::Kind::Street as i32,
+                mode: w.street_mode.name().to_string(),
+                start: w.start,
+                end: w.end,
+                length_m: w.length as u32,
+                geometry: coordinates(&w.geometry),
+                ..Default::default()
+            },
+            PlanLeg::Transit(t) => proto::Leg {
+                kind: proto::leg::Kind::Transit as i32,
+                mode: String::new(),
+                start: t.start,
+                end: t.end,
+                length_m: t.length as u32,
+                route_short_name: t.display.route_short_name.clone().unwrap_or_default(),
+                headsign: t.display.headsign.clone().unwrap_or_default(),
+                realtime: t.realtime,
+                geometry: coordinates(&t.geometry),
+            },
+            PlanLeg::Wait(_) => unreachable!("wait legs only appear in Plan::timeline"),
+        })
+        .collect();
+    proto::Itinerary {
+        mode: plan.mode.name().to_string(),
+        start: plan.start,
+        end: plan.end,
+        expected_end: plan.expected_end,
+        legs,
+    }
+}
+
+fn departures(
+    state: &GrpcState,
+    req: proto::DeparturesRequest,
+) -> Result<proto::DeparturesResponse, Status> {
+    let graph = state.graph.load_full();
+    let stop = graph
+        .stop_index_of(&req.stop_id)
+        .ok_or_else(|| Status::new(NOT_FOUND, format!("unknown stop_id '{}'", req.stop_id)))?;
+    let (date, time) = parse_date_time_str(non_empty(&req.date), non_empty(&req.time))
+        .map_err(Status::invalid)?;
+    let rt = state.realtime.load_full();
+    let departures = departure_board(&graph, &rt, stop, date.and_time(time), limit_or_default(req.limit))
+        .into_iter()
+        .map(|d| proto::Departure {
+            trip_id: d.trip_id,
+            route_short_name: d.route_short_name,
+            headsign: d.headsign.unwrap_or_default(),
+            scheduled_departure: d.scheduled_departure as u32,
+            realtime_departure: d.realtime_departure,
+        })
+        .collect();
+    Ok(proto::DeparturesResponse { departures })
+}
+
+fn nearby_stops(
+    state: &GrpcState,
+    req: proto::NearbyStopsRequest,
+) -> Result<proto::NearbyStopsResponse, Status> {
+    let Some(center) = req.center else {
+        return Err(Status::invalid("center is required"));
+    };
+    let radius_m = match req.radius_m {
+        0 => 500,
+        r => r,
+    };
+    if radius_m > MAX_STOPS_NEARBY_RADIUS_M as u32 {
+        return Err(Status::invalid(format!(
+            "radius_m must be <= {MAX_STOPS_NEARBY_RADIUS_M}"
+        )));
+    }
+    let graph = state.graph.load_full();
+    let stops = graph
+        .stops_nearby(center.lat, center.lng, radius_m as f64, limit_or_default(req.limit))
+        .into_iter()
+        .map(|(idx, distance_m, name, lat, lng, mode)| proto::Stop {
+            id: format!("maas:stop:{}", idx),
+            stop_id: graph.stop_id_str(idx).unwrap_or_default().to_string(),
+            name,
+            lat,
+            lng,
+            mode,
+            distance_m,
+        })
+        .collect();
+    Ok(proto::NearbyStopsResponse { stops })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::{Graph, RealtimeIndex};
+
+    fn state() -> GrpcState {
+        GrpcState {
+            graph: Arc::new(arc_swap::ArcSwap::from_pointee(Graph::new())),
+            realtime: Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new())),
+            limiter: HeavyQueryLimiter::new(),
+            analytics: Arc::new(Analytics::disabled()),
+        }
+    }
+
+    #[test]
+    fn frames_round_trip_and_reject_bad_lengths() {
+        let req = proto::NearbyStopsRequest { center: None, radius_m: 300, limit: 5 };
+        let frame = encode_frame(&req);
+        assert_eq!(&frame[..5], &[0, 0, 0, 0, (frame.len() - 5) as u8]);
+        let back: proto::NearbyStopsRequest = decode(decode_frame(&frame).unwrap()).unwrap();
+        assert_eq!(back, req);
+
+        assert_eq!(decode_frame(&frame[..3]).unwrap_err().code, INVALID_ARGUMENT);
+        assert_eq!(decode_frame(&frame[..frame.len() - 1]).unwrap_err().code, INVALID_ARGUMENT);
+        let mut compressed = frame.clone();
+        compressed[0] = 1;
+        assert_eq!(decode_frame(&compressed).unwrap_err().code, UNIMPLEMENTED);
+    }
+
+    #[tokio::test]
+    async fn dispatch_maps_request_errors_to_status_codes() {
+        let s = state();
+        let nearby = |radius_m| {
+            encode_frame(&proto::NearbyStopsRequest {
+                center: Some(proto::Coordinate { lat: 50.85, lng: 4.35 }),
+                radius_m,
+                limit: 0,
+            })
+        };
+
+        let ok = dispatch(&s, "/maas.v1.PlanService/NearbyStops", &nearby(0)).await.unwrap();
+        let res: proto::NearbyStopsResponse = decode(decode_frame(&ok).unwrap()).unwrap();
+        assert!(res.stops.is_empty());
+
+        let err = dispatch(&s, "/maas.v1.PlanService/NearbyStops", &nearby(9000)).await;
+        assert_eq!(err.unwrap_err().code, INVALID_ARGUMENT);
+
+        let dep = encode_frame(&proto::DeparturesRequest { stop_id: "X".into(), ..Default::default() });
+        let err = dispatch(&s, "/maas.v1.PlanService/Departures", &dep).await;
+        assert_eq!(err.unwrap_err().code, NOT_FOUND);
+
+        let err = dispatch(&s, "/maas.v1.PlanService/Plan", &encode_frame(&proto::PlanRequest::default())).await;
+        assert_eq!(err.unwrap_err(), Status::invalid("from and to are required"));
+
+        let err = dispatch(&s, "/maas.v1.PlanService/Nope", &nearby(0)).await;
+        assert_eq!(err.unwrap_err().code, UNIMPLEMENTED);
+    }
+
+    #[test]
+    fn status_message_is_percent_encoded_in_trailers() {
+        assert_eq!(percent_encode("bad 100% é"), "bad 100%25 %C3%A9");
+    }
+
+    #[tokio::test]
+    async fn plan_errors_map_to_explicit_status_codes() {
+        let code = |c: Option<&'static str>| {
+            let e = PlanError { message: "x".into(), code: c };
+            Status::from(e).code
+        };
+        assert_eq!(code(Some("SEARCH_TIMEOUT")), DEADLINE_EXCEEDED);
+        assert_eq!(code(Some("SEARCH_CANCELLED")), CANCELLED);
+        assert_eq!(code(Some("SEARCH_EXPANSION_LIMIT")), RESOURCE_EXHAUSTED);
+        assert_eq!(code(Some("VIA_UNREACHABLE")), NOT_FOUND);
+        assert_eq!(code(Some("ITINERARY_UNAVAILABLE")), NOT_FOUND);
+        assert_eq!(code(Some("NO_ROUTE")), NOT_FOUND);
+        for c in ["OUTSIDE_COVERAGE", "UNKNOWN_PROFILE", "INVALID_ITINERARY_ID", "UNKNOWN_WHAT_IF_ID"]
+        {
+            assert_eq!(code(Some(c)), INVALID_ARGUMENT, "{c}");
+        }
+        assert_eq!(code(None), UNKNOWN);
+
+        let status = Status::from(PlanError::new("Unknown routing profile x").with_code("UNKNOWN_PROFILE"));
+        let body = response(Err(status)).into_body().collect().await.unwrap();
+        let trailers = body.trailers().unwrap();
+        assert_eq!(trailers["grpc-status"], "3");
+        assert_eq!(trailers["maas-error-code"], "UNKNOWN_PROFILE");
+    }
+}
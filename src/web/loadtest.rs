@@ -0,0 +1,222 @@
+//! `--loadtest`: serve `/graphql` on an ephemeral local port and fire the bench sample
+//! set at it as `raptor` queries from `concurrency` clients at once, reporting throughput
+//! and end-to-end latency. Unlike `--bench` this covers the HTTP stack, the routing
+//! limiter and the blocking pool, so running it at 1, 2, 4, … clients shows whether
+//! throughput scales with cores.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_graphql_poem::GraphQL;
+use poem::{
+    EndpointExt, Route, Server,
+    listener::{Acceptor, Listener, TcpListener},
+    middleware::SizeLimit,
+};
+
+use super::app::build_schema_with_permits;
+use crate::{
+    services::{
+        bench::{LoadTestOptions, Percentiles, sample_pairs},
+        scheduler::SharedGraph,
+    },
+    structures::LatLng,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct LoadTestReport {
+    pub requests: usize,
+    pub concurrency: usize,
+    pub planned: usize,
+    pub no_plan: usize,
+    pub errors: usize,
+    pub latency_ms: Percentiles,
+    pub total_secs: f64,
+}
+
+impl LoadTestReport {
+    pub fn throughput(&self) -> f64 {
+        if self.total_secs > 0.0 {
+            self.requests as f64 / self.total_secs
+        } else {
+            0.0
+        }
+    }
+}
+
+impl std::fmt::Display for LoadTestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "requests {}  concurrency {}  planned {}  no_plan {}  errors {}  wall {:.2}s  {:.1} req/s",
+            self.requests,
+            self.concurrency,
+            self.planned,
+            self.no_plan,
+            self.errors,
+            self.total_secs,
+            self.throughput()
+        )?;
+        let l = self.latency_ms;
+        write!(
+            f,
+            "latency_ms  p50 {:.1}  p90 {:.1}  p99 {:.1}  max {:.1}",
+            l.p50, l.p90, l.p99, l.max
+        )
+    }
+}
+
+enum Outcome {
+    Planned,
+    NoPlan,
+    Error,
+}
+
+fn raptor_query(from: LatLng, to: LatLng, opts: &LoadTestOptions) -> String {
+    let b = &opts.bench;
+    let mut args = format!(
+        "fromLat: {}, fromLng: {}, toLat: {}, toLng: {}, date: \"{}\", time: \"{}\"",
+        from.latitude,
+        from.longitude,
+        to.latitude,
+        to.longitude,
+        b.date.format("%Y-%m-%d"),
+        b.time.format("%H:%M:%S"),
+    );
+    if let Some(w) = b.window_minutes {
+        args.push_str(&format!(", windowMinutes: {w}"));
+    }
+    if let Some(modes) = &b.modes {
+        let names: Vec<&str> = modes.iter().map(|m| m.name()).collect();
+        args.push_str(&format!(", modes: [{}]", names.join(", ")));
+    }
+    serde_json::json!({ "query": format!("{{ raptor({args}) {{ start end }} }}") }).to_string()
+}
+
+fn send(agent: &ureq::Agent, url: &str, body: &str) -> Outcome {
+    let Ok(resp) = agent.post(url).set("Content-Type", "application/json").send_string(body) else {
+        return Outcome::Error;
+    };
+    let Ok(Ok(json)) = resp.into_string().map(|s| serde_json::from_str::<serde_json::Value>(&s)) else {
+        return Outcome::Error;
+    };
+    if json["errors"].as_array().is_some_and(|e| !e.is_empty()) {
+        return Outcome::Error;
+    }
+    match json["data"]["raptor"].as_array() {
+        Some(plans) if plans.is_empty() => Outcome::NoPlan,
+        Some(_) => Outcome::Planned,
+        None => Outcome::Error,
+    }
+}
+
+/// Runs the load test against a private server on `127.0.0.1`, with
+/// `max_concurrent_searches` routing permits as `server` would use. Returns once every
+/// request has been answered and the server has shut down.
+pub async fn run(
+    graph: SharedGraph,
+    opts: &LoadTestOptions,
+    max_concurrent_searches: Option<usize>,
+) -> std::io::Result<LoadTestReport> {
+    let pairs = sample_pairs(&graph.load(), opts.bench.samples, opts.bench.seed);
+    let bodies: Vec<String> = pairs.iter().map(|&(from, to)| raptor_query(from, to, opts)).collect();
+
+    let schema = build_schema_with_permits(graph, max_concurrent_searches);
+    let app = Route::new().at("/graphql", GraphQL::new(schema).with(SizeLimit::new(64 * 1024)));
+    let acceptor = TcpListener::bind("127.0.0.1:0").into_acceptor().await?;
+    let addr = acceptor
+        .local_addr()
+        .into_iter()
+        .find_map(|a| a.as_socket_addr().copied())
+        .ok_or_else(|| std::io::Error::other("load test listener has no socket address"))?;
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(Server::new_with_acceptor(acceptor).run_with_graceful_shutdown(
+        app,
+        async {
+            let _ = stop_rx.await;
+        },
+        Some(Duration::from_secs(5)),
+    ));
+
+    let concurrency = opts.concurrency.max(1);
+    let url = format!("http://{addr}/graphql");
+    let outcome = tokio::task::spawn_blocking(move || {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(bodies.len()));
+        let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(60)).build();
+        let started = Instant::now();
+        std::thread::scope(|s| {
+            for _ in 0..concurrency {
+                s.spawn(|| {
+                    let mut local = Vec::new();
+                    while let Some(body) = bodies.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let t = Instant::now();
+                        let outcome = send(&agent, &url, body);
+                        local.push((t.elapsed().as_secs_f64() * 1000.0, outcome));
+                    }
+                    results.lock().unwrap_or_else(|e| e.into_inner()).extend(local);
+                });
+            }
+        });
+        (results.into_inner().unwrap_or_else(|e| e.into_inner()), started.elapsed())
+    })
+    .await
+    .map_err(std::io::Error::other)?;
+
+    let _ = stop_tx.send(());
+    server.await.map_err(std::io::Error::other)??;
+
+    let (results, wall) = outcome;
+    let mut report = LoadTestReport {
+        requests: results.len(),
+        concurrency,
+        total_secs: wall.as_secs_f64(),
+        ..Default::default()
+    };
+    let mut latencies = Vec::with_capacity(results.len());
+    for (ms, outcome) in results {
+        latencies.push(ms);
+        match outcome {
+            Outcome::Planned => report.planned += 1,
+            Outcome::NoPlan => report.no_plan += 1,
+            Outcome::Error => report.errors += 1,
+        }
+    }
+    report.latency_ms = Percentiles::of(latencies);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{NaiveDate, NaiveTime};
+
+    use super::*;
+    use crate::services::bench::{BenchOptions, tests::graph};
+    use crate::structures::Mode;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn every_request_goes_through_the_server() {
+        let shared: SharedGraph = Arc::new(arc_swap::ArcSwap::from_pointee(graph()));
+        let opts = LoadTestOptions {
+            bench: BenchOptions {
+                samples: 6,
+                seed: 1,
+                date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+                time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                window_minutes: None,
+                modes: Some(vec![Mode::Walk]),
+            },
+            concurrency: 3,
+        };
+        let report = run(shared, &opts, Some(2)).await.unwrap();
+        assert_eq!(report.requests, 6);
+        assert_eq!(report.concurrency, 3);
+        assert_eq!(report.errors, 0, "{report}");
+        assert_eq!(report.planned + report.no_plan, 6);
+        assert!(report.throughput() > 0.0);
+        assert!(report.to_string().contains("req/s"));
+    }
+}
@@ -1 +1,8 @@
+pub mod analytics;
 pub mod app;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod loadtest;
+pub mod page_cursor;
+pub mod plan_cache;
+pub mod subscriptions;
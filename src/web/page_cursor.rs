@@ -0,0 +1,169 @@
+//! Server-side state behind `raptorPage` cursors. A cursor is `earlier:<id>` or
+//! `later:<id>`; `<id>` names the `PageState` of the page it was issued with, so the
+//! client never re-sends the request and the next page only searches the adjacent
+//! departure range. States expire after `PAGE_CURSOR_TTL` and the oldest are evicted
+//! past `PAGE_CURSOR_CAPACITY`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::routing::paging::{PageDirection, PageState};
+
+pub const PAGE_CURSOR_CAPACITY: usize = 4096;
+pub const PAGE_CURSOR_TTL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Default)]
+struct Store {
+    next_id: u64,
+    /// id -> state; ids grow monotonically, so the first entry is the oldest.
+    states: BTreeMap<u64, (PageState, Instant)>,
+}
+
+pub struct PageCursors {
+    capacity: usize,
+    ttl: Duration,
+    inner: Mutex<Store>,
+}
+
+impl Default for PageCursors {
+    fn default() -> Self {
+        PageCursors::new(PAGE_CURSOR_CAPACITY, PAGE_CURSOR_TTL)
+    }
+}
+
+impl PageCursors {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        PageCursors {
+            capacity: capacity.max(1),
+            ttl,
+            inner: Mutex::new(Store::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().map(|s| s.states.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Store `state`; returns its `(earlier, later)` cursors.
+    pub fn insert(&self, state: PageState) -> (String, String) {
+        let id = match self.inner.lock() {
+            Ok(mut store) => {
+                let ttl = self.ttl;
+                store.states.retain(|_, (_, stored)| stored.elapsed() < ttl);
+                while store.states.len() >= self.capacity {
+                    store.states.pop_first();
+                }
+                store.next_id += 1;
+                let id = store.next_id;
+                store.states.insert(id, (state, Instant::now()));
+                id
+            }
+            Err(_) => 0,
+        };
+        (
+            encode(PageDirection::Earlier, id),
+            encode(PageDirection::Later, id),
+        )
+    }
+
+    /// Direction and stored state for `cursor`; `None` when malformed, expired or evicted.
+    pub fn resolve(&self, cursor: &str) -> Option<(PageDirection, PageState)> {
+        let (direction, id) = decode(cursor)?;
+        let store = self.inner.lock().ok()?;
+        let (state, stored) = store.states.get(&id)?;
+        (stored.elapsed() < self.ttl).then(|| (direction, state.clone()))
+    }
+}
+
+fn encode(direction: PageDirection, id: u64) -> String {
+    match direction {
+        PageDirection::Earlier => format!("earlier:{id:x}"),
+        PageDirection::Later => format!("later:{id:x}"),
+    }
+}
+
+fn decode(cursor: &str) -> Option<(PageDirection, u64)> {
+    let (direction, id) = cursor.split_once(':')?;
+    let direction = match direction {
+        "earlier" => PageDirection::Earlier,
+        "later" => PageDirection::Later,
+        _ => return None,
+    };
+    Some((direction, u64::from_str_radix(id, 16).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::routing_raptor::RouteQuery;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn state(lo: i64) -> PageState {
+        PageState {
+            query: RouteQuery {
+                from_lat: 50.0,
+                from_lng: 4.0,
+                to_lat: 50.0,
+                to_lng: 4.1,
+                date: NaiveDate::from_ymd_opt(2026, 6, 23).unwrap(),
+                time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                window_minutes: None,
+                min_access_secs: None,
+                arrival_slack_secs: None,
+                unrestricted_transfers: None,
+                use_cch_access: None,
+                reliability_bucket_edges: None,
+                modes: None,
+                bike_profile: None,
+                terminal_deadline: false,
+                onboard_origin: None,
+                from_station_id: None,
+                to_station_id: None,
+                profile_latency: None,
+                fare_profile: None,
+                avoid_crowding: false,
+                profile: None,
+                what_if: None,
+                night_safety: None,
+                avoid_stairs: None,
+                max_access_walk_m: None,
+                max_egress_walk_m: None,
+                search_profile: None,
+            },
+            lo,
+            hi: lo + 3600,
+        }
+    }
+
+    #[test]
+    fn cursors_resolve_to_their_direction_and_state() {
+        let cursors = PageCursors::default();
+        let (earlier, later) = cursors.insert(state(100));
+        let (dir, s) = cursors.resolve(&earlier).unwrap();
+        assert_eq!((dir, s.lo), (PageDirection::Earlier, 100));
+        let (dir, s) = cursors.resolve(&later).unwrap();
+        assert_eq!((dir, s.hi), (PageDirection::Later, 3700));
+        assert!(cursors.resolve("sideways:1").is_none());
+        assert!(cursors.resolve("later:zz").is_none());
+        assert!(cursors.resolve("later:ff").is_none());
+    }
+
+    #[test]
+    fn oldest_state_is_evicted_past_capacity_and_expired_ones_vanish() {
+        let cursors = PageCursors::new(2, Duration::from_secs(60));
+        let (first, _) = cursors.insert(state(0));
+        cursors.insert(state(1));
+        cursors.insert(state(2));
+        assert_eq!(cursors.len(), 2);
+        assert!(cursors.resolve(&first).is_none());
+
+        let expired = PageCursors::new(8, Duration::ZERO);
+        let (_, later) = expired.insert(state(0));
+        assert!(expired.resolve(&later).is_none());
+    }
+}
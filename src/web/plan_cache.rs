@@ -0,0 +1,338 @@
+//! In-memory LRU of `raptor` plan results. Polling frontends re-issue the same OD pair
+//! every few seconds; a hit skips the whole search. Entries are keyed by the snapped
+//! origin/destination nodes, the departure bucket and every other routing parameter,
+//! and by the identity of the graph and realtime snapshots they were computed on, so a
+//! graph reload or realtime refresh naturally misses.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, Timelike};
+
+use crate::{
+    routing::routing_raptor::{RouteQuery, snapped_junction},
+    structures::{Graph, RealtimeIndex, plan::Plan},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlanKey {
+    graph: usize,
+    realtime: usize,
+    from: usize,
+    to: usize,
+    date: NaiveDate,
+    bucket: u32,
+    params: String,
+}
+
+struct Entry {
+    plans: Vec<Plan>,
+    stored: Instant,
+    used: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    map: HashMap<PlanKey, Entry>,
+    /// `used` stamp -> key, oldest first.
+    order: BTreeMap<u64, PlanKey>,
+    clock: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &PlanKey) {
+        self.clock += 1;
+        if let Some(e) = self.map.get_mut(key) {
+            self.order.remove(&e.used);
+            e.used = self.clock;
+            self.order.insert(self.clock, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &PlanKey) {
+        if let Some(e) = self.map.remove(key) {
+            self.order.remove(&e.used);
+        }
+    }
+}
+
+/// Plan-result cache shared by all GraphQL requests. `capacity == 0` disables it.
+pub struct PlanCache {
+    capacity: usize,
+    ttl: Duration,
+    bucket_secs: u32,
+    inner: Mutex<Lru>,
+}
+
+impl PlanCache {
+    pub fn new(capacity: usize, ttl: Duration, bucket_secs: u32) -> Self {
+        PlanCache {
+            capacity,
+            ttl,
+            bucket_secs: bucket_secs.max(1),
+            inner: Mutex::new(Lru::default()),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        PlanCache::new(0, Duration::ZERO, 60)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0 && !self.ttl.is_zero()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().map(|l| l.map.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cache key for `query`, or `None` when the query must not be cached (cache
+    /// disabled, onboard origin, latency profiling, or an endpoint that does not snap).
+    pub fn key(
+        &self,
+        graph: &Arc<Graph>,
+        realtime: &Arc<RealtimeIndex>,
+        query: &RouteQuery,
+    ) -> Option<PlanKey> {
+        if !self.is_enabled() {
+            return None;
+        }
+        // Exhaustive on purpose: a new `RouteQuery` field must be added to the key.
+        let RouteQuery {
+            from_lat,
+            from_lng,
+            to_lat,
+            to_lng,
+            date,
+            time,
+            window_minutes,
+            min_access_secs,
+            arrival_slack_secs,
+            unrestricted_transfers,
+            use_cch_access,
+            reliability_bucket_edges,
+            modes,
+            bike_profile,
+            terminal_deadline,
+            onboard_origin,
+            from_station_id,
+            to_station_id,
+            profile_latency,
+            fare_profile,
+            avoid_crowding,
+            profile,
+            what_if,
+            night_safety,
+            avoid_stairs,
+            max_access_walk_m,
+            max_egress_walk_m,
+            // Admissible bounds only reorder the search: same plans with or without one.
+            search_profile: _,
+        } = query;
+        // What-if answers are one-off simulations; caching them would only evict real plans.
+        if onboard_origin.is_some() || profile_latency.unwrap_or(false) || what_if.is_some() {
+            return None;
+        }
+        let from = snapped_junction(graph, *from_lat, *from_lng)?;
+        let to = snapped_junction(graph, *to_lat, *to_lng)?;
+        Some(PlanKey {
+            graph: Arc::as_ptr(graph) as usize,
+            realtime: Arc::as_ptr(realtime) as usize,
+            from: from.0,
+            to: to.0,
+            date: *date,
+            bucket: time.num_seconds_from_midnight() / self.bucket_secs,
+            params: format!(
+                "{window_minutes:?}|{min_access_secs:?}|{arrival_slack_secs:?}|\
+                 {unrestricted_transfers:?}|{use_cch_access:?}|{reliability_bucket_edges:?}|\
+                 {modes:?}|{bike_profile:?}|{terminal_deadline}|{from_station_id:?}|\
+                 {to_station_id:?}|{fare_profile:?}|{avoid_crowding}|{profile:?}|{night_safety:?}|\
+                 {avoid_stairs:?}|{max_access_walk_m:?}|{max_egress_walk_m:?}"
+            ),
+        })
+    }
+
+    pub fn get(&self, key: &PlanKey) -> Option<Vec<Plan>> {
+        let mut lru = self.inner.lock().ok()?;
+        let fresh = lru.map.get(key)?.stored.elapsed() < self.ttl;
+        if !fresh {
+            lru.remove(key);
+            return None;
+        }
+        lru.touch(key);
+        lru.map.get(key).map(|e| e.plans.clone())
+    }
+
+    pub fn insert(&self, key: PlanKey, plans: Vec<Plan>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Ok(mut lru) = self.inner.lock() else {
+            return;
+        };
+        lru.remove(&key);
+        while lru.map.len() >= self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break;
+            };
+            lru.map.remove(&oldest);
+        }
+        lru.clock += 1;
+        let used = lru.clock;
+        lru.order.insert(used, key.clone());
+        lru.map.insert(
+            key,
+            Entry {
+                plans,
+                stored: Instant::now(),
+                used,
+            },
+        );
+    }
+
+    /// Drop every entry; returns how many were removed.
+    pub fn flush(&self) -> usize {
+        let Ok(mut lru) = self.inner.lock() else {
+            return 0;
+        };
+        let n = lru.map.len();
+        lru.map.clear();
+        lru.order.clear();
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::contraction::ContractedGraph;
+    use crate::structures::cost::VarGen;
+    use crate::structures::{
+        BikeAttrs, EdgeData, LatLng, NodeData, OsmNodeData, StreetEdgeData,
+    };
+    use chrono::NaiveTime;
+
+    fn graph() -> Arc<Graph> {
+        let mut g = Graph::new();
+        let ids: Vec<_> = [4.0, 4.01]
+            .into_iter()
+            .enumerate()
+            .map(|(i, lon)| {
+                g.add_node(NodeData::OsmNode(OsmNodeData {
+                    eid: format!("map#osm#{i}"),
+                    lat_lng: LatLng {
+                        latitude: 50.0,
+                        longitude: lon,
+                    },
+                }))
+            })
+            .collect();
+        for (o, d) in [(ids[0], ids[1]), (ids[1], ids[0])] {
+            g.add_edge(
+                o,
+                EdgeData::Street(StreetEdgeData {
+                    origin: o,
+                    destination: d,
+                    partial: false,
+                    length: 715,
+                    foot: true,
+                    bike: false,
+                    car: false,
+                    attrs: BikeAttrs::road_default(),
+                    elev_delta: 0,
+                    surface_speed: 100,
+                    var_gen: VarGen::NONE,
+                }),
+            );
+        }
+        let mut cg = ContractedGraph::from_graph_union(&g);
+        cg.build_seg_index();
+        g.contracted = Some(cg);
+        Arc::new(g)
+    }
+
+    fn query(from_lng: f64, secs: u32) -> RouteQuery {
+        RouteQuery {
+            from_lat: 50.0,
+            from_lng,
+            to_lat: 50.0,
+            to_lng: 4.01,
+            date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+            time: NaiveTime::from_num_seconds_from_midnight_opt(secs, 0).unwrap(),
+            window_minutes: None,
+            min_access_secs: None,
+            arrival_slack_secs: None,
+            unrestricted_transfers: None,
+            use_cch_access: None,
+            reliability_bucket_edges: None,
+            modes: None,
+            bike_profile: None,
+            terminal_deadline: false,
+            onboard_origin: None,
+            from_station_id: None,
+            to_station_id: None,
+            profile_latency: None,
+            fare_profile: None,
+            avoid_crowding: false,
+            profile: None,
+            what_if: None,
+            night_safety: None,
+            avoid_stairs: None,
+            max_access_walk_m: None,
+            max_egress_walk_m: None,
+            search_profile: None,
+        }
+    }
+
+    #[test]
+    fn nearby_origin_in_same_minute_shares_a_key() {
+        let cache = PlanCache::new(8, Duration::from_secs(30), 60);
+        let (g, rt) = (graph(), Arc::new(RealtimeIndex::new()));
+        let a = cache.key(&g, &rt, &query(4.0, 8 * 3600 + 5)).unwrap();
+        let b = cache.key(&g, &rt, &query(4.0002, 8 * 3600 + 50)).unwrap();
+        let c = cache.key(&g, &rt, &query(4.0, 8 * 3600 + 65)).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c, "next departure bucket");
+
+        let mut q = query(4.0, 8 * 3600 + 5);
+        q.window_minutes = Some(30);
+        assert_ne!(a, cache.key(&g, &rt, &q).unwrap(), "parameters are part of the key");
+        let rt2 = Arc::new(RealtimeIndex::new());
+        assert_ne!(a, cache.key(&g, &rt2, &query(4.0, 8 * 3600 + 5)).unwrap());
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let cache = PlanCache::new(2, Duration::from_secs(30), 60);
+        let (g, rt) = (graph(), Arc::new(RealtimeIndex::new()));
+        let keys: Vec<PlanKey> = (0..3)
+            .map(|m| cache.key(&g, &rt, &query(4.0, m * 60)).unwrap())
+            .collect();
+        cache.insert(keys[0].clone(), Vec::new());
+        cache.insert(keys[1].clone(), Vec::new());
+        assert!(cache.get(&keys[0]).is_some());
+        cache.insert(keys[2].clone(), Vec::new());
+        assert!(cache.get(&keys[1]).is_none(), "oldest use evicted");
+        assert!(cache.get(&keys[0]).is_some());
+        assert_eq!(cache.flush(), 2);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn expired_and_disabled_caches_miss() {
+        let (g, rt) = (graph(), Arc::new(RealtimeIndex::new()));
+        assert!(PlanCache::disabled().key(&g, &rt, &query(4.0, 0)).is_none());
+
+        let cache = PlanCache::new(4, Duration::from_nanos(1), 60);
+        let key = cache.key(&g, &rt, &query(4.0, 0)).unwrap();
+        cache.insert(key.clone(), Vec::new());
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(cache.get(&key).is_none());
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! GraphQL subscriptions, served over WebSocket at `/graphql/ws`. Pushes are driven by
+//! the realtime poller's [`RealtimeUpdates`] channel, so display boards never poll.
+
+use std::pin::Pin;
+
+use async_graphql::{Context, Error, ErrorExtensions, SimpleObject, Subscription, futures_util::Stream};
+use chrono::{Datelike, Local, NaiveDateTime, Timelike};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    ingestion::gtfs::date_to_days,
+    services::{
+        realtime_poller::{RealtimeUpdates, SharedRealtime},
+        scheduler::SharedGraph,
+    },
+    structures::{Graph, RealtimeIndex},
+};
+
+pub const MAX_BOARD_DEPARTURES: i32 = 50;
+
+/// One row of a stop's departure board.
+#[derive(SimpleObject, Clone, Debug, PartialEq)]
+pub struct BoardDeparture {
+    pub trip_id: String,
+    pub route_short_name: String,
+    pub headsign: Option<String>,
+    /// Seconds since midnight of the service day.
+    pub scheduled_departure: i32,
+    /// `scheduledDeparture` plus the live delay (0 without one).
+    pub realtime_departure: i32,
+    /// A live delay is known for this departure.
+    pub realtime: bool,
+}
+
+/// The next `limit` uncancelled departures from `stop` at local `now`.
+pub fn departure_board(
+    graph: &Graph,
+    rt: &RealtimeIndex,
+    stop: usize,
+    now: NaiveDateTime,
+    limit: usize,
+) -> Vec<BoardDeparture> {
+    let weekday = 1u8 << now.weekday().num_days_from_monday();
+    let active = graph.raptor.active_services(date_to_days(now.date()), weekday);
+    // Over-fetch so cancelled trips do not shorten the board.
+    graph
+        .stop_departures(stop, now.num_seconds_from_midnight(), limit * 2, &active)
+        .into_iter()
+        .filter(|d| !rt.is_canceled(d.trip))
+        .take(limit)
+        .map(|d| {
+            let delay = rt.delay_opt(d.trip, stop as u32);
+            BoardDeparture {
+                trip_id: graph.trip_id_str(d.trip).unwrap_or_default().to_string(),
+                route_short_name: graph
+                    .get_route(d.route)
+                    .map(|r| r.route_short_name.clone())
+                    .unwrap_or_default(),
+                headsign: graph.get_trip(d.trip).and_then(|t| t.trip_headsign.clone()),
+                scheduled_departure: d.scheduled_departure as i32,
+                realtime_departure: d.scheduled_departure as i32 + delay.unwrap_or(0),
+                realtime: delay.is_some(),
+            }
+        })
+        .collect()
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Departure board of `stopId` (`maas:stop:<n>` or GTFS `stop_id`): sent on
+    /// subscribe, then again after each realtime update that changes it. The stop is
+    /// re-resolved on every update, so a graph reload that drops it empties the board.
+    async fn stop_departures(
+        &self,
+        ctx: &Context<'_>,
+        stop_id: String,
+        #[graphql(default = 10)] limit: i32,
+    ) -> Result<Pin<Box<dyn Stream<Item = Vec<BoardDeparture>> + Send>>, Error> {
+        if !(1..=MAX_BOARD_DEPARTURES).contains(&limit) {
+            return Err(Error::new(format!("limit must be in 1..={MAX_BOARD_DEPARTURES}")));
+        }
+        let graph = ctx.data::<SharedGraph>()?.clone();
+        let realtime = ctx.data::<SharedRealtime>()?.clone();
+        let updates = ctx.data::<RealtimeUpdates>()?.subscribe();
+        if graph.load().resolve_stop_id(&stop_id).is_none() {
+            return Err(Error::new(format!("unknown stop {stop_id}"))
+                .extend_with(|_, ext| ext.set("code", "UNKNOWN_STOP")));
+        }
+        let board = move || {
+            let graph = graph.load_full();
+            match graph.resolve_stop_id(&stop_id) {
+                Some(stop) => departure_board(
+                    &graph,
+                    &realtime.load_full(),
+                    stop,
+                    Local::now().naive_local(),
+                    limit as usize,
+                ),
+                None => Vec::new(),
+            }
+        };
+        let first = board();
+        let stream = async_graphql::futures_util::stream::unfold(
+            (updates, board, Some(first), None::<Vec<BoardDeparture>>),
+            |(mut updates, board, mut pending, mut last)| async move {
+                loop {
+                    if let Some(next) = pending.take()
+                        && last.as_ref() != Some(&next)
+                    {
+                        last = Some(next.clone());
+                        return Some((next, (updates, board, None, last)));
+                    }
+                    match updates.recv().await {
+                        // A lagging receiver only missed older indices; the live one is newer.
+                        Ok(_) | Err(RecvError::Lagged(_)) => pending = Some(board()),
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        );
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use async_graphql::{EmptyMutation, Schema, futures_util::StreamExt};
+
+    use crate::services::realtime_poller::realtime_updates;
+
+    #[tokio::test]
+    async fn unknown_stop_is_rejected_with_a_code() {
+        let schema = Schema::build(super::super::app::QueryRoot, EmptyMutation, SubscriptionRoot)
+            .data::<SharedGraph>(Arc::new(arc_swap::ArcSwap::from_pointee(Graph::new())))
+            .data::<SharedRealtime>(Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new())))
+            .data(realtime_updates())
+            .finish();
+        let mut stream =
+            schema.execute_stream(r#"subscription { stopDepartures(stopId: "nope") { tripId } }"#);
+        let resp = stream.next().await.expect("one response");
+        assert_eq!(resp.errors.len(), 1);
+        assert_eq!(
+            resp.errors[0].extensions.as_ref().and_then(|e| e.get("code")),
+            Some(&async_graphql::Value::from("UNKNOWN_STOP"))
+        );
+    }
+}
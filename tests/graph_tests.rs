@@ -17,10 +17,10 @@ use maas_rs::{
     structures::{
         ActiveModes, BikeAttrs, BikeCost, BikeProfile, DelayCDF, EdgeData, Endpoint, Graph,
         HighwayClass, LatLng, Mode, NodeData, NodeID, OnboardRide, OsmNodeData, QueryEndpoints,
-        RealtimeIndex, ReliabilityBuckets, StreetEdgeData, StreetProfile, StreetTimeModel, Surface,
+        RealtimeIndex, ReliabilityBuckets, RoutingProfile, StreetEdgeData, StreetProfile, StreetTimeModel, Surface,
         TransitEdgeData, TransitStopData,
         cost::VarGen,
-        plan::PlanLeg,
+        plan::{PlanLeg, RealtimeState},
         raptor::{Lookup, PatternInfo},
     },
 };
@@ -47,6 +47,7 @@ fn transit_stop(name: &str, lat: f64, lon: f64) -> NodeData {
         accessibility: Availability::Available,
         id: name.to_string(),
         platform_code: None,
+        code: None,
         parent_station: None,
     })
 }
@@ -67,6 +68,7 @@ fn transit_stop_parent(
         accessibility: Availability::Available,
         id: id.to_string(),
         platform_code: None,
+        code: None,
         parent_station: parent.map(|s| s.to_string()),
     })
 }
@@ -210,6 +212,8 @@ fn get_id_finds_osm_node_by_eid() {
     let mut g = Graph::new();
     let id = g.add_node(osm_node("map#osm#42", 50.0, 4.0));
     assert_eq!(g.get_id("map#osm#42"), Some(&id));
+    assert_eq!(g.get_osm_id(42), Some(id));
+    assert_eq!(g.node_eid(id).as_deref(), Some("map#osm#42"));
 }
 
 #[test]
@@ -272,6 +276,16 @@ fn nearest_node_dist_returns_distance() {
     assert!(dist < 1.0, "Same-point distance should be ~0, got {dist}");
 }
 
+#[test]
+fn nearest_node_agrees_with_nearest_node_dist_at_high_latitude() {
+    let mut g = Graph::new();
+    // 0.2° north is nearer in raw degrees, 0.3° east is nearer on the ground at 70°N.
+    let _north = g.add_node(osm_node("north", 70.2, 20.0));
+    let east = g.add_node(osm_node("east", 70.0, 20.3));
+    assert_eq!(g.nearest_node(70.0, 20.0), Some(east));
+    assert_eq!(g.nearest_node_dist(70.0, 20.0).map(|(_, &n)| n), Some(east));
+}
+
 #[test]
 fn nearest_node_ignores_transit_stops() {
     let mut g = Graph::new();
@@ -351,24 +365,18 @@ fn make_transit_graph() -> (Graph, TimetableSegment) {
     let segments = vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 8 * 3600,
             arrival: 8 * 3600 + 600,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 10 * 3600,
             arrival: 10 * 3600 + 600,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 12 * 3600,
             arrival: 12 * 3600 + 600,
             service_id: ServiceId(0),
@@ -377,7 +385,7 @@ fn make_transit_graph() -> (Graph, TimetableSegment) {
 
     let tt = TimetableSegment {
         start: 0,
-        len: segments.len(),
+        len: segments.len() as u32,
     };
     g.add_transit_departures(segments);
 
@@ -388,7 +396,7 @@ fn make_transit_graph() -> (Graph, TimetableSegment) {
 fn next_departure_before_first_returns_first() {
     let (g, tt) = make_transit_graph();
     let (idx, dep) = g
-        .next_transit_departure(tt, 7 * 3600, 500, 0x7F)
+        .next_transit_departure(tt, 7 * 3600, &g.raptor.active_services(500, 0x7F), &RealtimeIndex::new())
         .expect("should find a departure");
     assert_eq!(idx, 0);
     assert_eq!(dep.departure, 8 * 3600);
@@ -398,7 +406,7 @@ fn next_departure_before_first_returns_first() {
 fn next_departure_at_exact_time_returns_that_departure() {
     let (g, tt) = make_transit_graph();
     let (idx, dep) = g
-        .next_transit_departure(tt, 10 * 3600, 500, 0x7F)
+        .next_transit_departure(tt, 10 * 3600, &g.raptor.active_services(500, 0x7F), &RealtimeIndex::new())
         .expect("should find a departure");
     assert_eq!(idx, 1);
     assert_eq!(dep.departure, 10 * 3600);
@@ -408,7 +416,7 @@ fn next_departure_at_exact_time_returns_that_departure() {
 fn next_departure_between_two_returns_later_one() {
     let (g, tt) = make_transit_graph();
     let (idx, dep) = g
-        .next_transit_departure(tt, 9 * 3600, 500, 0x7F)
+        .next_transit_departure(tt, 9 * 3600, &g.raptor.active_services(500, 0x7F), &RealtimeIndex::new())
         .expect("should find a departure");
     assert_eq!(idx, 1);
     assert_eq!(dep.departure, 10 * 3600);
@@ -417,7 +425,8 @@ fn next_departure_between_two_returns_later_one() {
 #[test]
 fn next_departure_after_last_returns_none() {
     let (g, tt) = make_transit_graph();
-    assert!(g.next_transit_departure(tt, 13 * 3600, 500, 0x7F).is_none());
+    let active = g.raptor.active_services(500, 0x7F);
+    assert!(g.next_transit_departure(tt, 13 * 3600, &active, &RealtimeIndex::new()).is_none());
 }
 
 #[test]
@@ -432,22 +441,34 @@ fn next_departure_inactive_service_skips() {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600,
         arrival: 9 * 3600 + 300,
         service_id: ServiceId(0),
     }]);
     let tt = TimetableSegment { start: 0, len: 1 };
-    assert!(g.next_transit_departure(tt, 8 * 3600, 100, 0x01).is_none());
-    assert!(g.next_transit_departure(tt, 8 * 3600, 100, 0x20).is_some());
+    let monday = g.raptor.active_services(100, 0x01);
+    assert!(monday.is_empty(), "no service runs: every hop is skipped up front");
+    assert!(g.next_transit_departure(tt, 8 * 3600, &monday, &RealtimeIndex::new()).is_none());
+    let saturday = g.raptor.active_services(100, 0x20);
+    assert!(g.next_transit_departure(tt, 8 * 3600, &saturday, &RealtimeIndex::new()).is_some());
+}
+
+#[test]
+fn next_departure_skips_canceled_trip() {
+    let (g, tt) = make_transit_graph();
+    let rt = RealtimeIndex::from_updates(0, [], [TripId(1)]);
+    let (idx, dep) = g
+        .next_transit_departure(tt, 9 * 3600, &g.raptor.active_services(500, 0x7F), &rt)
+        .expect("should find a departure");
+    assert_eq!(idx, 2);
+    assert_eq!(dep.trip_id, TripId(2));
 }
 
 
 #[test]
 fn previous_departures_from_middle_yields_earlier_trips() {
     let (g, tt) = make_transit_graph();
-    let prev: Vec<_> = g.previous_departures(tt, 500, 0x7F, 2).collect();
+    let prev: Vec<_> = g.previous_departures(tt, &g.raptor.active_services(500, 0x7F), 2).collect();
     assert_eq!(prev.len(), 2, "Expected 2 earlier departures");
     assert_eq!(prev[0].0, 1);
     assert_eq!(prev[0].1.departure, 10 * 3600);
@@ -458,14 +479,14 @@ fn previous_departures_from_middle_yields_earlier_trips() {
 #[test]
 fn previous_departures_from_first_yields_empty() {
     let (g, tt) = make_transit_graph();
-    let prev: Vec<_> = g.previous_departures(tt, 500, 0x7F, 0).collect();
+    let prev: Vec<_> = g.previous_departures(tt, &g.raptor.active_services(500, 0x7F), 0).collect();
     assert!(prev.is_empty());
 }
 
 #[test]
 fn next_departures_from_middle_yields_later_trips() {
     let (g, tt) = make_transit_graph();
-    let next: Vec<_> = g.next_departures(tt, 500, 0x7F, 0).collect();
+    let next: Vec<_> = g.next_departures(tt, &g.raptor.active_services(500, 0x7F), 0).collect();
     assert_eq!(next.len(), 2);
     assert_eq!(next[0].0, 1);
     assert_eq!(next[0].1.departure, 10 * 3600);
@@ -476,7 +497,7 @@ fn next_departures_from_middle_yields_later_trips() {
 #[test]
 fn next_departures_from_last_yields_empty() {
     let (g, tt) = make_transit_graph();
-    let next: Vec<_> = g.next_departures(tt, 500, 0x7F, 2).collect();
+    let next: Vec<_> = g.next_departures(tt, &g.raptor.active_services(500, 0x7F), 2).collect();
     assert!(next.is_empty());
 }
 
@@ -493,23 +514,19 @@ fn next_departures_filters_inactive_service() {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 8 * 3600,
             arrival: 8 * 3600 + 600,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 10 * 3600,
             arrival: 10 * 3600 + 600,
             service_id: ServiceId(0),
         },
     ]);
     let tt = TimetableSegment { start: 0, len: 2 };
-    let next: Vec<_> = g.next_departures(tt, 100, 0x40, 0).collect();
+    let next: Vec<_> = g.next_departures(tt, &g.raptor.active_services(100, 0x40), 0).collect();
     assert!(next.is_empty(), "Expected no departures on Sunday");
 }
 
@@ -1072,16 +1089,12 @@ fn station_hub_graph() -> (Graph, NodeID, NodeID) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1800,
             arrival: 9 * 3600 + 2700,
             service_id: ServiceId(0),
@@ -1164,6 +1177,14 @@ fn station_query(from_station: Option<&str>, to_station: Option<&str>) -> RouteQ
         to_station_id: to_station.map(|s| s.to_string()),
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     }
 }
 
@@ -1171,8 +1192,8 @@ fn leg_kinds(p: &maas_rs::structures::plan::Plan) -> Vec<&'static str> {
     p.legs
         .iter()
         .map(|l| match l {
-            PlanLeg::Walk(_) => "Walk",
             PlanLeg::Transit(_) => "Transit",
+            _ => "Walk",
         })
         .collect()
 }
@@ -1347,8 +1368,6 @@ fn station_offset_arrival_graph() -> Graph {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600,
         arrival: 9 * 3600 + 900,
         service_id: ServiceId(0),
@@ -1406,6 +1425,14 @@ fn offset_station_query() -> RouteQuery {
         to_station_id: Some(HUB_DEST.to_string()),
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     }
 }
 
@@ -1470,8 +1497,6 @@ fn intra_member_terminal_graph() -> Graph {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600,
         arrival: 9 * 3600 + 900,
         service_id: ServiceId(0),
@@ -1529,6 +1554,14 @@ fn intra_member_terminal_query() -> RouteQuery {
         to_station_id: Some(HUB_DEST.to_string()),
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     }
 }
 
@@ -1594,8 +1627,6 @@ fn intra_member_origin_graph() -> Graph {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600,
         arrival: 9 * 3600 + 900,
         service_id: ServiceId(0),
@@ -1653,6 +1684,14 @@ fn intra_member_origin_query() -> RouteQuery {
         to_station_id: None,
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     }
 }
 
@@ -1753,6 +1792,39 @@ fn station_to_station_mid_journey_transfer_walk_preserved() {
     );
 }
 
+#[test]
+fn every_leg_carries_its_own_time_bounds() {
+    let (g, _osm_origin, _osm_dest) = station_hub_graph();
+    let q = station_query(None, None);
+    let plans = route(&g, &q, &RealtimeIndex::new()).expect("a coordinate-to-coordinate plan");
+    let plan = plans
+        .iter()
+        .find(|p| has_mid_transfer_walk(p) && matches!(p.legs.first(), Some(PlanLeg::Walk(_))))
+        .expect("an access walk + transit + transfer walk + transit plan");
+
+    let bounds: Vec<(u32, u32)> = plan
+        .legs
+        .iter()
+        .map(|l| {
+            if let Some(w) = l.as_street() {
+                assert_eq!(w.end - w.start, w.duration, "walk duration is its own span");
+                assert_eq!((w.from.departure, w.to.arrival), (Some(w.start), Some(w.end)));
+            }
+            l.span()
+        })
+        .collect();
+    assert_eq!((plan.start, plan.end), (bounds[0].0, bounds[bounds.len() - 1].1));
+    assert!(bounds[0].0 >= 8 * 3600 + 1800, "access walk starts no earlier than the query time");
+    for (i, w) in bounds.windows(2).enumerate() {
+        assert!(w[0].1 <= w[1].0, "leg {} ends after leg {} starts: {:?}", i, i + 1, bounds);
+        if let PlanLeg::Walk(_) = plan.legs[i + 1]
+            && i + 2 < plan.legs.len()
+        {
+            assert_eq!(w[1].0, w[0].1, "a mid-journey walk starts when the previous leg ends");
+        }
+    }
+}
+
 #[test]
 fn walk_dijkstra_finds_connected_nodes() {
     let (mut g, a, b, c) = three_node_street_graph();
@@ -1788,6 +1860,32 @@ fn walk_dijkstra_respects_max_seconds_cutoff() {
     assert!(!dist.contains_key(&c), "c (166s) should exceed 90s cutoff");
 }
 
+fn link_edge(origin: NodeID, destination: NodeID, length_m: usize) -> EdgeData {
+    match street_edge(origin, destination, length_m) {
+        EdgeData::Street(s) => EdgeData::Street(StreetEdgeData { partial: true, bike: false, car: false, ..s }),
+        e => e,
+    }
+}
+
+#[test]
+fn walk_dijkstra_never_chains_two_synthetic_links() {
+    let (mut g, a, _b, c) = three_node_street_graph();
+    // c → p and p → q are both synthetic connectors with no mapped street between.
+    let p = g.add_node(osm_node("p", 50.000, 4.003));
+    let q = g.add_node(osm_node("q", 50.000, 4.004));
+    let r = g.add_node(osm_node("r", 50.000, 4.003));
+    g.add_edge(c, link_edge(c, p, 80));
+    g.add_edge(p, link_edge(p, q, 80));
+    // A zero-length link (a stop sitting on its platform node) may follow one.
+    g.add_edge(p, link_edge(p, r, 0));
+    g.build_raptor_index();
+
+    let dist = g.walk_dijkstra(a, 99999);
+    assert!(dist.contains_key(&p), "one link off the street is walkable");
+    assert!(dist.contains_key(&r), "a zero-length link chains freely");
+    assert!(!dist.contains_key(&q), "link → link is a straight-line shortcut");
+}
+
 #[test]
 fn walk_dijkstra_isolated_node_not_reached() {
     let (mut g, a, _b, _c) = three_node_street_graph();
@@ -2090,16 +2188,12 @@ fn two_route_raptor_graph_with_bikes(
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1800,
             arrival: 9 * 3600 + 2700,
             service_id: ServiceId(0),
@@ -2714,11 +2808,11 @@ fn sncb_routable_graph() -> (Graph, NodeID, NodeID, f64) {
     }]);
     g.add_transit_departures(vec![
         TripSegment {
-            trip_id: TripId(0), origin_stop_sequence: 0, destination_stop_sequence: 1,
+            trip_id: TripId(0),
             departure: 9 * 3600, arrival: 9 * 3600 + 600, service_id: ServiceId(0),
         },
         TripSegment {
-            trip_id: TripId(0), origin_stop_sequence: 1, destination_stop_sequence: 2,
+            trip_id: TripId(0),
             departure: 9 * 3600 + 600, arrival: 9 * 3600 + 1200, service_id: ServiceId(0),
         },
     ]);
@@ -2926,11 +3020,11 @@ fn sncb_airport_routable_graph() -> (Graph, NodeID, NodeID) {
     }]);
     g.add_transit_departures(vec![
         TripSegment {
-            trip_id: TripId(0), origin_stop_sequence: 0, destination_stop_sequence: 1,
+            trip_id: TripId(0),
             departure: 9 * 3600, arrival: 9 * 3600 + 600, service_id: ServiceId(0),
         },
         TripSegment {
-            trip_id: TripId(0), origin_stop_sequence: 1, destination_stop_sequence: 2,
+            trip_id: TripId(0),
             departure: 9 * 3600 + 600, arrival: 9 * 3600 + 1200, service_id: ServiceId(0),
         },
     ]);
@@ -3110,11 +3204,11 @@ fn shared_hub_two_access_graph() -> (Graph, NodeID, NodeID) {
         TripInfo { trip_headsign: None, route_id: RouteId(2), service_id: ServiceId(0), bikes_allowed: None },
     ]);
     g.add_transit_departures(vec![
-        TripSegment { trip_id: TripId(0), origin_stop_sequence: 0, destination_stop_sequence: 1,
+        TripSegment { trip_id: TripId(0),
             departure: 9 * 3600, arrival: 9 * 3600 + 600, service_id: ServiceId(0) },
-        TripSegment { trip_id: TripId(1), origin_stop_sequence: 0, destination_stop_sequence: 1,
+        TripSegment { trip_id: TripId(1),
             departure: 9 * 3600 + 720, arrival: 9 * 3600 + 1320, service_id: ServiceId(0) },
-        TripSegment { trip_id: TripId(2), origin_stop_sequence: 0, destination_stop_sequence: 1,
+        TripSegment { trip_id: TripId(2),
             departure: 9 * 3600, arrival: 9 * 3600 + 540, service_id: ServiceId(0) },
     ]);
 
@@ -3318,16 +3412,12 @@ fn long_walk_transfer_graph() -> (Graph, NodeID, NodeID) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 600,
             arrival: 9 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 3180,
             arrival: 9 * 3600 + 3780,
             service_id: ServiceId(0),
@@ -3565,16 +3655,12 @@ fn express_two_leg_graph(
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 480,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 900,
             arrival: 9 * 3600 + 1380,
             service_id: ServiceId(0),
@@ -3646,10 +3732,7 @@ fn transit_leg_count(p: &maas_rs::structures::plan::Plan) -> usize {
 fn street_modes(p: &maas_rs::structures::plan::Plan) -> Vec<Mode> {
     p.legs
         .iter()
-        .filter_map(|l| match l {
-            PlanLeg::Walk(w) => Some(w.street_mode),
-            _ => None,
-        })
+        .filter_map(|l| l.as_street().map(|w| w.street_mode))
         .collect()
 }
 
@@ -3741,6 +3824,37 @@ fn bike_on_transit_rides_egress() {
     );
 }
 
+#[test]
+fn bike_on_transit_route_type_policy_fills_unset_bikes_allowed() {
+    let (mut g, origin, dest) = express_two_leg_graph(Some(true), None);
+    g.set_bike_route_types(vec![RouteType::Bus]);
+    let am = ActiveModes::new(&[Mode::BikeOnTransit]);
+    let plans = g.raptor_modes(origin, dest, 8 * 3600 + 3300, 0, 0x7F, 10 * 60, &am);
+
+    let two_leg = plans
+        .iter()
+        .find(|p| transit_leg_count(p) == 2)
+        .expect("bus policy allows bikes on the unset second leg");
+    assert_eq!(two_leg.mode, Mode::BikeOnTransit);
+    assert!(two_leg.legs.iter().all(|l| match l {
+        PlanLeg::Transit(t) => t.bikes_allowed == Some(true),
+        _ => true,
+    }));
+}
+
+#[test]
+fn bike_on_transit_explicit_no_bikes_beats_route_type_policy() {
+    let (mut g, origin, dest) = express_two_leg_graph(Some(true), Some(false));
+    g.set_bike_route_types(vec![RouteType::Bus]);
+    let am = ActiveModes::new(&[Mode::BikeOnTransit]);
+    let plans = g.raptor_modes(origin, dest, 8 * 3600 + 3300, 0, 0x7F, 10 * 60, &am);
+
+    assert!(
+        plans.iter().all(|p| transit_leg_count(p) <= 1),
+        "trips.txt bikes_allowed=2 must not be overridden by the route-type policy"
+    );
+}
+
 #[test]
 fn bike_access_seeds_dropped_state() {
     let (g, origin, dest) = express_two_leg_graph(None, None);
@@ -3914,8 +4028,6 @@ fn transit_modes_never_emit_zero_transit_plans() {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600 + 600,
         arrival: 9 * 3600 + 900,
         service_id: ServiceId(0),
@@ -4099,8 +4211,6 @@ fn car_drop_off_not_poisoned_when_car_reaches_destination() {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600 + 600,
         arrival: 9 * 3600 + 1800,
         service_id: ServiceId(0),
@@ -4255,8 +4365,6 @@ fn car_drop_off_with_foot_only_connectors() {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600 + 1000,
         arrival: 9 * 3600 + 1300,
         service_id: ServiceId(0),
@@ -4468,24 +4576,18 @@ fn car_drop_off_does_not_starve_walk_transit() {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 600,
             arrival: 9 * 3600 + 1500,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1800,
             arrival: 9 * 3600 + 3000,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 300,
             arrival: 9 * 3600 + 1800,
             service_id: ServiceId(0),
@@ -4740,6 +4842,31 @@ fn car_drop_off_is_park_and_ride() {
     );
 }
 
+#[test]
+fn park_and_ride_allows_parking_search_without_known_capacity() {
+    use maas_rs::ingestion::osm::{PARK_RIDE_UNKNOWN_CAPACITY_SECS, ParkingFacility, ParkingIndex, ParkingKind};
+    let am = ActiveModes::new(&[Mode::CarDropOff]);
+    let depart = 8 * 3600 + 2700;
+    let start_of = |g: &Graph, origin, dest| {
+        let plans = g.raptor_modes(origin, dest, depart, 0, 0x7F, 10 * 60, &am);
+        plans.iter().find(|p| transit_leg_count(p) >= 1).expect("park & ride plan expected").start
+    };
+    let (g, origin, dest) = express_two_leg_graph(None, None);
+    let baseline = start_of(&g, origin, dest);
+
+    // A car park of unknown size, far from every stop: each P+R stop gets the allowance.
+    let (mut g, origin, dest) = express_two_leg_graph(None, None);
+    g.set_parking_index(ParkingIndex::from_facilities(vec![ParkingFacility {
+        loc: LatLng { latitude: 51.0, longitude: 5.0 },
+        kind: ParkingKind::Car,
+        name: None,
+        capacity: None,
+        fee: None,
+        park_ride: false,
+    }]));
+    assert_eq!(baseline - start_of(&g, origin, dest), PARK_RIDE_UNKNOWN_CAPACITY_SECS);
+}
+
 #[test]
 fn car_pickup_is_kiss_and_ride() {
     let (g, origin, dest) = express_two_leg_graph(None, None);
@@ -4801,11 +4928,14 @@ fn raptor_second_transit_leg_has_transfer_risk() {
             .legs
             .iter()
             .map(|l| match l {
-                PlanLeg::Walk(w) => format!("Walk({}s)", w.duration),
                 PlanLeg::Transit(t) => format!(
                     "Transit(risk={:?})",
                     t.transfer_risk.as_ref().map(|r| r.reliability)
                 ),
+                street => {
+                    let (start, end) = street.span();
+                    format!("Street({}s)", end - start)
+                }
             })
             .collect();
         eprintln!("Plan {}: {:?}", i, leg_summary);
@@ -4995,6 +5125,33 @@ fn raptor_transfer_risk_merges_feeder_and_boarding_delays() {
         "reliability {} should equal the two-delay convolution {expected} (margin {margin})",
         risk.reliability
     );
+    assert!(
+        (two_leg.connection_reliability() - expected).abs() < 1e-6,
+        "one transfer: the plan's reliability is that transfer's"
+    );
+}
+
+#[test]
+fn plan_connection_reliability_is_one_without_transfers() {
+    let (mut g, origin, dest) = two_route_raptor_graph();
+    let mut models = HashMap::new();
+    models.insert(
+        RouteType::Bus,
+        DelayCDF {
+            bins: vec![(0, 0.1), (600, 1.0)],
+        },
+    );
+    g.set_transit_delay_models(models);
+
+    let plans = g.raptor(origin, dest, 8 * 3600, 0, 0x7F, 10 * 60);
+    for p in &plans {
+        let boardings = p.legs.iter().filter(|l| matches!(l, PlanLeg::Transit(_))).count();
+        let r = p.connection_reliability();
+        assert!((0.0..=1.0).contains(&r));
+        if boardings <= 1 {
+            assert_eq!(r, 1.0, "nothing to miss with at most one boarding");
+        }
+    }
 }
 
 
@@ -5156,24 +5313,18 @@ fn two_route_multi_trip_graph() -> (Graph, NodeID, NodeID) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 8 * 3600,
             arrival: 8 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1800,
             arrival: 9 * 3600 + 2700,
             service_id: ServiceId(0),
@@ -5400,6 +5551,7 @@ fn raptor_realtime_shows_on_leg_times() {
             if t.trip_id == TripId(2) {
                 saw_tram = true;
                 assert!(t.realtime, "tram leg should be flagged realtime");
+                assert_eq!(t.realtime_state, RealtimeState::Updated);
                 assert_eq!(
                     t.scheduled_end,
                     9 * 3600 + 2700,
@@ -5413,6 +5565,7 @@ fn raptor_realtime_shows_on_leg_times() {
             } else {
                 saw_bus = true;
                 assert!(!t.realtime, "bus leg has no realtime data");
+                assert_eq!(t.realtime_state, RealtimeState::Scheduled);
                 assert_eq!(t.start, t.scheduled_start, "bus leg unshifted");
             }
         }
@@ -5420,6 +5573,31 @@ fn raptor_realtime_shows_on_leg_times() {
     assert!(saw_tram && saw_bus, "expected both a tram and a bus leg");
 }
 
+#[test]
+fn raptor_leg_carries_boarding_stop_occupancy() {
+    use maas_rs::structures::Occupancy;
+    let (g, origin, dest) = two_route_multi_trip_graph();
+    let buckets = ReliabilityBuckets::new(&[0.50, 0.80, 0.95]);
+
+    // Tram (trip 2) vehicle-wide FULL; nothing known about the bus.
+    let rt = RealtimeIndex::new()
+        .with_occupancy(HashMap::new(), HashMap::from([(TripId(2), Occupancy::Full)]));
+    let plans = g.raptor_tuned_rt(origin, dest, 7 * 3600, 0, 0x7F, 10 * 60, &buckets, 900, &rt);
+
+    let mut saw_tram = false;
+    for leg in plans.iter().flat_map(|p| &p.legs) {
+        if let PlanLeg::Transit(t) = leg {
+            if t.trip_id == TripId(2) {
+                saw_tram = true;
+                assert_eq!(t.occupancy, Some(Occupancy::Full));
+            } else {
+                assert_eq!(t.occupancy, None, "no occupancy reported for the bus");
+            }
+        }
+    }
+    assert!(saw_tram, "expected a tram leg");
+}
+
 #[test]
 fn stib_stop_indices_exact_and_prefix() {
     let mut g = Graph::new();
@@ -5664,32 +5842,24 @@ fn over_tighten_break_graph_perm(t1_board: bool) -> (Graph, NodeID, NodeID) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 8 * 3600,
             arrival: 8 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1200,
             arrival: 9 * 3600 + 2100,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(3),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1800,
             arrival: 9 * 3600 + 2700,
             service_id: ServiceId(0),
@@ -5770,8 +5940,10 @@ fn min_transfer_margin(legs: &[PlanLeg]) -> i32 {
                 prev_end = Some(t.end);
                 walk = 0;
             }
-            PlanLeg::Walk(w) => {
-                if prev_end.is_some() {
+            street => {
+                if let Some(w) = street.as_street()
+                    && prev_end.is_some()
+                {
                     walk += w.duration;
                 }
             }
@@ -6101,8 +6273,6 @@ fn single_route_many_trips_graph() -> (Graph, NodeID, NodeID) {
         (0..6u32)
             .map(|i| TripSegment {
                 trip_id: TripId(i),
-                origin_stop_sequence: 0,
-                destination_stop_sequence: 1,
                 departure: base + i * 1800,
                 arrival: base + i * 1800 + 1800,
                 service_id: ServiceId(0),
@@ -6319,8 +6489,6 @@ fn overtaking_pattern_graph() -> (Graph, NodeID, NodeID) {
     for (i, (&d, &a)) in p_dep_a.iter().zip(p_arr_b.iter()).enumerate() {
         deps.push(TripSegment {
             trip_id: TripId(i as u32),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: d,
             arrival: a,
             service_id: if i == 6 { ServiceId(1) } else { ServiceId(0) },
@@ -6328,8 +6496,6 @@ fn overtaking_pattern_graph() -> (Graph, NodeID, NodeID) {
     }
     deps.push(TripSegment {
         trip_id: TripId(8),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: q_dep_a[0],
         arrival: q_arr_b[0],
         service_id: ServiceId(0),
@@ -6404,8 +6570,8 @@ fn raptor_range_overtaking_no_infeasible_departure_tag() {
                 p.legs
                     .iter()
                     .map(|l| match l {
-                        PlanLeg::Walk(w) => ("walk", w.start, w.end),
                         PlanLeg::Transit(t) => ("transit", t.start, t.end),
+                        street => ("street", street.span().0, street.span().1),
                     })
                     .collect::<Vec<_>>(),
             );
@@ -6413,10 +6579,7 @@ fn raptor_range_overtaking_no_infeasible_departure_tag() {
         let bounds: Vec<(u32, u32)> = p
             .legs
             .iter()
-            .map(|l| match l {
-                PlanLeg::Walk(w) => (w.start, w.end),
-                PlanLeg::Transit(t) => (t.start, t.end),
-            })
+            .map(PlanLeg::span)
             .collect();
         for w in bounds.windows(2) {
             assert!(
@@ -6483,8 +6646,6 @@ fn overtaking_midstop_graph() -> (Graph, NodeID, NodeID) {
     let mut ab: Vec<TripSegment> = (0..5)
         .map(|i| TripSegment {
             trip_id: TripId(i as u32),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: a_dep[i],
             arrival: b_arr[i],
             service_id: ServiceId(0),
@@ -6494,8 +6655,6 @@ fn overtaking_midstop_graph() -> (Graph, NodeID, NodeID) {
     let mut bc: Vec<TripSegment> = (0..5)
         .map(|i| TripSegment {
             trip_id: TripId(i as u32),
-            origin_stop_sequence: 1,
-            destination_stop_sequence: 2,
             departure: b_arr[i],
             arrival: c_arr[i],
             service_id: ServiceId(0),
@@ -6826,8 +6985,6 @@ fn raptor_range_connecting_pattern_not_starved_by_dead_end_pattern() {
     let mut segs: Vec<TripSegment> = (0..5u32)
         .map(|i| TripSegment {
             trip_id: TripId(i),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: base + 60 + i * 60,
             arrival: base + 60 + i * 60 + 3600,
             service_id: ServiceId(0),
@@ -6835,8 +6992,6 @@ fn raptor_range_connecting_pattern_not_starved_by_dead_end_pattern() {
         .collect();
     segs.extend((0..3u32).map(|i| TripSegment {
         trip_id: TripId(5 + i),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: base + 1800 + i * 3600,
         arrival: base + 1800 + i * 3600 + 1800,
         service_id: ServiceId(0),
@@ -7025,8 +7180,6 @@ fn raptor_range_probe_gate_does_not_drop_windowed_transit_plan() {
     let d_arr = d_dep + 120;
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: d_dep,
         arrival: d_arr,
         service_id: ServiceId(0),
@@ -7206,16 +7359,12 @@ fn backward_walk_graph() -> (Graph, NodeID, NodeID, NodeID, NodeID) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 10 * 3600,
             arrival: 10 * 3600 + 120,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 1,
-            destination_stop_sequence: 2,
             departure: 10 * 3600 + 120,
             arrival: 10 * 3600 + 1200,
             service_id: ServiceId(0),
@@ -7322,34 +7471,30 @@ fn departures_out_of_segment_index_does_not_panic() {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 100,
             arrival: 200,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 300,
             arrival: 400,
             service_id: ServiceId(0),
         },
     ]);
     let tt = TimetableSegment { start: 1, len: 1 };
-    let prev: Vec<_> = g.previous_departures(tt, 0, 0x7F, 0).collect();
+    let prev: Vec<_> = g.previous_departures(tt, &g.raptor.active_services(0, 0x7F), 0).collect();
     assert!(
         prev.is_empty(),
         "out-of-segment previous_departures should be empty, not panic"
     );
-    let next: Vec<_> = g.next_departures(tt, 0, 0x7F, 0).collect();
+    let next: Vec<_> = g.next_departures(tt, &g.raptor.active_services(0, 0x7F), 0).collect();
     assert!(
         next.is_empty(),
         "out-of-segment next_departures should be empty, not panic"
     );
     let prev_ok: Vec<_> = g
-        .previous_departures(TimetableSegment { start: 0, len: 2 }, 0, 0x7F, 1)
+        .previous_departures(TimetableSegment { start: 0, len: 2 }, &g.raptor.active_services(0, 0x7F), 1)
         .collect();
     assert_eq!(prev_ok.len(), 1);
 }
@@ -7510,24 +7655,18 @@ fn reliability_tradeoff_graph() -> (Graph, NodeID, NodeID) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1200,
             arrival: 9 * 3600 + 2100,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 10 * 3600,
             arrival: 10 * 3600 + 900,
             service_id: ServiceId(0),
@@ -7842,32 +7981,24 @@ fn feeder_tightening_reliability_graph() -> (Graph, NodeID, NodeID) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 8 * 3600,
             arrival: 8 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 900,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1200,
             arrival: 9 * 3600 + 1560,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(3),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600 + 1800,
             arrival: 9 * 3600 + 2700,
             service_id: ServiceId(0),
@@ -8147,10 +8278,10 @@ fn self_pruning_range_real_network_equals_independent() {
                                 t.route_type,
                                 t.transfer_risk.as_ref().map(|r| r.reliability)
                             ),
-                            PlanLeg::Walk(w) => println!(
-                                "        WALK    {}->{} {}s",
-                                w.from.node_id.0, w.to.node_id.0, w.duration
-                            ),
+                            street => {
+                                let (start, end) = street.span();
+                                println!("        STREET  {}s", end - start)
+                            }
                         }
                     }
                 }
@@ -8652,8 +8783,6 @@ fn multiobj_transit_graph() -> (Graph, NodeID, NodeID) {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600 + 600,
         arrival: 9 * 3600 + 600 + 480,
         service_id: ServiceId(0),
@@ -8717,6 +8846,14 @@ fn transit_access_egress_multiobj_alternatives_and_leave_by() {
         to_station_id: None,
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     };
     let plans = route(&g, &q, &RealtimeIndex::new()).expect("route should succeed");
 
@@ -8858,8 +8995,6 @@ fn contraction_t2_graph() -> (Graph, NodeID, NodeID) {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600,
         arrival: 9 * 3600 + 600,
         service_id: ServiceId(0),
@@ -8984,6 +9119,14 @@ fn t4_explain_drop_gate_identical() {
         to_station_id: None,
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     };
 
     let before = route_explain(&g, &q, &RealtimeIndex::new()).expect("pre-drop explain");
@@ -9137,6 +9280,14 @@ fn transit_enrich_drop_gate() {
         to_station_id: None,
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     };
 
     let before: Vec<_> = ods
@@ -9209,6 +9360,14 @@ fn all_modes_drop_gate_identical() {
         to_station_id: None,
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     };
 
     let all_modes = [
@@ -9233,7 +9392,8 @@ fn all_modes_drop_gate_identical() {
     fn leg_geometry(leg: &PlanLeg) -> &Vec<maas_rs::structures::plan::PlanCoordinate> {
         match leg {
             PlanLeg::Transit(l) => &l.geometry,
-            PlanLeg::Walk(l) => &l.geometry,
+            PlanLeg::Wait(l) => &l.geometry,
+            street => &street.as_street().unwrap().geometry,
         }
     }
 
@@ -9483,6 +9643,22 @@ fn station_backups_split_counts_and_unknown_handles() {
     assert!(g.station_backups(TripId(99), board, alight, 5, 5, 1, 0x01).is_empty());
 }
 
+#[test]
+fn stop_departures_lists_every_pattern_from_a_time_and_skips_terminals() {
+    let g = station_backups_graph();
+    let sa = g.stop_index_of("SA").expect("SA resolves");
+    let sb = g.stop_index_of("SB").expect("SB resolves");
+
+    let deps = g.stop_departures(sa, 32000, 10, &g.raptor.active_services(1, 0x01));
+    let got: Vec<_> = deps.iter().map(|d| (d.trip, d.scheduled_departure)).collect();
+    assert_eq!(got, vec![(TripId(0), 32400), (TripId(3), 32700), (TripId(1), 33000)]);
+    assert_eq!(deps[2].route, RouteId(1));
+
+    assert_eq!(g.stop_departures(sa, 0, 2, &g.raptor.active_services(1, 0x01)).len(), 2);
+    assert!(g.stop_departures(sb, 0, 10, &g.raptor.active_services(1, 0x01)).is_empty(), "SB only terminates trips");
+    assert!(g.stop_departures(sa, 0, 10, &g.raptor.active_services(1, 0x00)).is_empty(), "no service on that weekday");
+}
+
 
 fn onboard_bus_cdf() -> DelayCDF {
     DelayCDF {
@@ -9500,20 +9676,21 @@ fn onboard_graph() -> (Graph, NodeID, LatLng) {
     let mut g = Graph::new();
 
     let osm_s2 = g.add_node(osm_node("osm_s2", 50.000, 4.020));
-    let osm_dest = g.add_node(osm_node("osm_dest", 50.000, 4.030));
-    let osm_stub = g.add_node(osm_node("osm_stub", 50.001, 4.030));
+    let osm_dest = g.add_node(osm_node("osm_dest", 50.000, 4.035));
+    let osm_stub = g.add_node(osm_node("osm_stub", 50.001, 4.035));
 
     let s0 = g.add_node(transit_stop("S0", 50.000, 4.000));
     let s1 = g.add_node(transit_stop("S1", 50.000, 4.010));
     let s2 = g.add_node(transit_stop("S2", 50.000, 4.020));
-    let s3 = g.add_node(transit_stop("S3", 50.000, 4.030));
-    let s4 = g.add_node(transit_stop("S4", 50.000, 4.031));
+    let s3 = g.add_node(transit_stop("S3", 50.000, 4.035));
+    let s4 = g.add_node(transit_stop("S4", 50.000, 4.036));
 
     let street = |g: &mut Graph, a: NodeID, b: NodeID, m: usize| {
         g.add_edge(a, street_edge(a, b, m));
         g.add_edge(b, street_edge(b, a, m));
     };
-    street(&mut g, osm_s2, osm_dest, 717);
+    // S2 sits just beyond the 1 km transfer radius of S3/S4.
+    street(&mut g, osm_s2, osm_dest, 1075);
     street(&mut g, osm_dest, osm_stub, 100);
 
     let snap = |g: &mut Graph, stop: NodeID, osm: NodeID, m: usize| {
@@ -9583,56 +9760,42 @@ fn onboard_graph() -> (Graph, NodeID, LatLng) {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 32400,
             arrival: 32700,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 32900,
             arrival: 33000,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 1,
-            destination_stop_sequence: 2,
             departure: 32700,
             arrival: 33000,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 1,
-            destination_stop_sequence: 2,
             departure: 33000,
             arrival: 33300,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 2,
-            destination_stop_sequence: 3,
             departure: 33000,
             arrival: 34200,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(2),
-            origin_stop_sequence: 2,
-            destination_stop_sequence: 3,
             departure: 33300,
             arrival: 34200,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 33060,
             arrival: 33300,
             service_id: ServiceId(0),
@@ -9748,7 +9911,7 @@ fn onboard_graph() -> (Graph, NodeID, LatLng) {
 
     let dest_ll = LatLng {
         latitude: 50.000,
-        longitude: 4.030,
+        longitude: 4.035,
     };
     (g, osm_dest, dest_ll)
 }
@@ -9992,6 +10155,30 @@ fn onboard_canceled_boarded_trip_keeps_scheduled_times() {
         !leg0.realtime,
         "a canceled boarded leg must not be flagged realtime"
     );
+    assert_eq!(leg0.realtime_state, RealtimeState::Canceled);
+}
+
+#[test]
+fn onboard_leg_through_skipped_stop_is_modified() {
+    let (g, osm_dest, dest_ll) = onboard_graph();
+
+    let s2 = g.stop_index_of("S2").unwrap() as u32;
+    let rt = RealtimeIndex::new().with_skipped([(TripId(0), s2)].into_iter().collect());
+    let plans = onboard_plans(&g, osm_dest, dest_ll, 1, &rt, 600);
+
+    let leg0 = plans
+        .iter()
+        .filter_map(|p| match p.legs.first() {
+            Some(PlanLeg::Transit(t)) if t.trip_id == TripId(0) => Some(t),
+            _ => None,
+        })
+        .next()
+        .expect("a plan staying on the boarded trip");
+    assert_eq!(
+        leg0.realtime_state,
+        RealtimeState::Modified,
+        "riding through a skipped stop marks the leg modified"
+    );
 }
 
 #[test]
@@ -10018,6 +10205,14 @@ fn lat_lng_route_unchanged_by_onboard_path() {
         to_station_id: None,
         profile_latency: None,
         fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
     };
     let dbg =
         |ps: &[maas_rs::structures::plan::Plan]| ps.iter().map(|p| format!("{p:?}")).collect::<Vec<_>>();
@@ -10044,9 +10239,208 @@ fn lat_lng_route_unchanged_by_onboard_path() {
             (p.legs.len(), trips, p.start, p.end)
         })
         .collect();
-    assert_eq!(sig, vec![(5, vec![0, 1], 32300, 35100)]);
+    assert_eq!(sig, vec![(5, vec![0, 1], 32400, 35100)], "plan starts with its access leg");
+}
+
+
+fn two_route_query(profile: Option<&str>) -> RouteQuery {
+    RouteQuery {
+        from_lat: 50.000,
+        from_lng: 4.000,
+        to_lat: 50.000,
+        to_lng: 4.041,
+        date: chrono::NaiveDate::from_ymd_opt(2026, 6, 12).unwrap(),
+        time: chrono::NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+        window_minutes: None,
+        min_access_secs: None,
+        arrival_slack_secs: None,
+        unrestricted_transfers: None,
+        use_cch_access: None,
+        reliability_bucket_edges: None,
+        modes: None,
+        bike_profile: None,
+        terminal_deadline: false,
+        onboard_origin: None,
+        from_station_id: None,
+        to_station_id: None,
+        profile_latency: None,
+        fare_profile: None,
+        avoid_crowding: false,
+        profile: profile.map(str::to_string),
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
+    }
+}
+
+#[test]
+fn repeated_queries_return_identical_plans() {
+    let (g, _osm_origin, _osm_dest) = two_route_raptor_graph();
+    let dbg =
+        |ps: &[maas_rs::structures::plan::Plan]| ps.iter().map(|p| format!("{p:?}")).collect::<Vec<_>>();
+    let queries = [
+        RouteQuery { window_minutes: Some(60), ..two_route_query(None) },
+        RouteQuery { modes: Some(vec![Mode::Walk, Mode::WalkTransit]), ..two_route_query(None) },
+        RouteQuery { modes: Some(vec![Mode::Bike]), ..two_route_query(None) },
+    ];
+    for q in &queries {
+        let first = dbg(&route(&g, q, &RealtimeIndex::new()).expect("plans"));
+        assert!(!first.is_empty(), "{:?} must produce a plan", q.modes);
+        for _ in 0..4 {
+            let again = dbg(&route(&g, q, &RealtimeIndex::new()).expect("plans"));
+            assert_eq!(first, again, "{:?}: plans must not depend on run order", q.modes);
+        }
+    }
+}
+
+#[test]
+fn wheelchair_profile_never_boards_or_alights_at_inaccessible_stop() {
+    let (mut g, _osm_origin, _osm_dest) = two_route_raptor_graph();
+    let c = g.stop_index_of("Stop C").unwrap();
+    g.raptor.transit_stop_wheelchair[c] = Availability::NotAvailable;
+    let c_node = g.raptor.transit_stop_to_node[c];
+    g.set_routing_profile(
+        "chair".into(),
+        RoutingProfile { wheelchair: true, ..Default::default() },
+    );
+    let uses_c = |plans: &[maas_rs::structures::plan::Plan]| {
+        plans.iter().any(|p| {
+            p.legs.iter().any(|l| {
+                matches!(l, PlanLeg::Transit(t) if t.from.node_id == c_node || t.to.node_id == c_node)
+            })
+        })
+    };
+
+    let base = route(&g, &two_route_query(None), &RealtimeIndex::new()).expect("plans");
+    assert!(uses_c(&base), "without the profile the tram from C is the best plan");
+    let chair = route(&g, &two_route_query(Some("chair")), &RealtimeIndex::new()).unwrap_or_default();
+    assert!(!uses_c(&chair), "the wheelchair profile must not use Stop C");
+}
+
+#[test]
+fn profile_walking_speed_scales_walk_legs() {
+    let (mut g, _osm_origin, _osm_dest) = two_route_raptor_graph();
+    let base_speed = g.walking_speed_mps();
+    g.set_routing_profile(
+        "slow".into(),
+        RoutingProfile { walking_speed_mps: Some(base_speed / 2.0), ..Default::default() },
+    );
+    let first_walk = |profile| {
+        let plans = route(&g, &two_route_query(profile), &RealtimeIndex::new()).expect("plans");
+        let transit = plans
+            .iter()
+            .find(|p| p.legs.iter().any(|l| matches!(l, PlanLeg::Transit(_))))
+            .expect("a transit plan");
+        match &transit.legs[0] {
+            PlanLeg::Walk(w) => (w.duration, w.length),
+            other => panic!("expected an access walk, got {other:?}"),
+        }
+    };
+    let (base_secs, base_len) = first_walk(None);
+    let (slow_secs, slow_len) = first_walk(Some("slow"));
+    assert!(
+        slow_secs.abs_diff(base_secs * 2) <= 1,
+        "half speed doubles the access walk: {base_secs}s vs {slow_secs}s"
+    );
+    assert!(slow_len.abs_diff(base_len) <= 1, "distance is unchanged");
+    assert_eq!(g.walking_speed_mps(), base_speed, "the override ends with the query");
 }
 
+#[test]
+fn night_safety_walks_the_lit_detour() {
+    let mut g = Graph::new();
+    let o = g.add_node(osm_node("o", 50.000, 4.000));
+    let a = g.add_node(osm_node("a", 50.003, 4.005));
+    let d = g.add_node(osm_node("d", 50.000, 4.010));
+    let mut lit = BikeAttrs::road_default();
+    lit.lit = true;
+    let dark = BikeAttrs::road_default();
+    let pair = |g: &mut Graph, from: NodeID, to: NodeID, len: usize, attrs: BikeAttrs| {
+        for (x, y) in [(from, to), (to, from)] {
+            let mut e = street_edge(x, y, len);
+            if let EdgeData::Street(s) = &mut e {
+                s.attrs = attrs;
+            }
+            g.add_edge(x, e);
+        }
+    };
+    pair(&mut g, o, d, 720, dark);
+    pair(&mut g, o, a, 400, lit);
+    pair(&mut g, a, d, 400, lit);
+    // Stubs make o and d junctions the search can snap to.
+    let o_stub = g.add_node(osm_node("o_stub", 49.999, 4.000));
+    let d_stub = g.add_node(osm_node("d_stub", 49.999, 4.010));
+    pair(&mut g, o, o_stub, 5, lit);
+    pair(&mut g, d, d_stub, 5, lit);
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+
+    let walk_len = |night_safety| {
+        let q = RouteQuery {
+            to_lng: 4.010,
+            modes: Some(vec![Mode::Walk]),
+            night_safety,
+            ..two_route_query(None)
+        };
+        let plans = route(&g, &q, &RealtimeIndex::new()).expect("a walk plan");
+        match &plans[0].legs[0] {
+            PlanLeg::Walk(w) => w.length,
+            other => panic!("expected a walk, got {other:?}"),
+        }
+    };
+    assert_eq!(walk_len(None), 720, "by day the short dark street wins");
+    assert_eq!(walk_len(Some(1.0)), 800, "at night the lit streets win");
+}
+
+#[test]
+fn avoid_stairs_walks_around_the_steps() {
+    let walk_len = |conveying: bool, avoid_stairs| {
+        let mut g = Graph::new();
+        let o = g.add_node(osm_node("o", 50.000, 4.000));
+        let a = g.add_node(osm_node("a", 50.003, 4.005));
+        let d = g.add_node(osm_node("d", 50.000, 4.010));
+        let mut steps = BikeAttrs::road_default();
+        steps.highway = HighwayClass::Steps;
+        steps.escalator = conveying;
+        let road = BikeAttrs::road_default();
+        let pair = |g: &mut Graph, from: NodeID, to: NodeID, len: usize, attrs: BikeAttrs| {
+            for (x, y) in [(from, to), (to, from)] {
+                let mut e = street_edge(x, y, len);
+                if let EdgeData::Street(s) = &mut e {
+                    s.attrs = attrs;
+                }
+                g.add_edge(x, e);
+            }
+        };
+        pair(&mut g, o, d, 720, steps);
+        pair(&mut g, o, a, 400, road);
+        pair(&mut g, a, d, 400, road);
+        let o_stub = g.add_node(osm_node("o_stub", 49.999, 4.000));
+        let d_stub = g.add_node(osm_node("d_stub", 49.999, 4.010));
+        pair(&mut g, o, o_stub, 5, road);
+        pair(&mut g, d, d_stub, 5, road);
+        g.build_raptor_index();
+        enable_contraction(&mut g);
+
+        let q = RouteQuery {
+            to_lng: 4.010,
+            modes: Some(vec![Mode::Walk]),
+            avoid_stairs,
+            ..two_route_query(None)
+        };
+        let plans = route(&g, &q, &RealtimeIndex::new()).expect("a walk plan");
+        match &plans[0].legs[0] {
+            PlanLeg::Walk(w) => w.length,
+            other => panic!("expected a walk, got {other:?}"),
+        }
+    };
+    assert_eq!(walk_len(false, None), 720, "the steps are the short way");
+    assert_eq!(walk_len(false, Some(true)), 800, "avoidStairs walks around them");
+    assert_eq!(walk_len(true, Some(true)), 720, "an escalator is not stairs");
+}
 
 use maas_rs::ingestion::osm::{OsmPlatform, PlatformIndex};
 use maas_rs::structures::Connector;
@@ -10643,16 +11037,12 @@ fn three_stop_pattern_graph(
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 600,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 1,
-            destination_stop_sequence: 2,
             departure: 9 * 3600 + 600,
             arrival: 9 * 3600 + 1200,
             service_id: ServiceId(0),
@@ -10855,8 +11245,6 @@ fn add_two_stop_line(
     let segs: Vec<TripSegment> = (0..n)
         .map(|i| TripSegment {
             trip_id: trips[i],
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: deps[i],
             arrival: arrs[i],
             service_id: ServiceId(0),
@@ -10869,7 +11257,7 @@ fn add_two_stop_line(
             origin: board,
             destination: alight,
             route_id: route,
-            timetable_segment: TimetableSegment { start: seg_start, len: n },
+            timetable_segment: TimetableSegment { start: seg_start as u32, len: n as u32 },
             length: length_m,
         }),
     );
@@ -11484,6 +11872,90 @@ fn cch_split_order_matches_all_in_one() {
     );
 }
 
+/// A 3×3 street grid with uneven block lengths, so the shortest walk is not the
+/// crow-flies one and several junction pairs need a detour.
+fn cch_grid_graph() -> Graph {
+    cch_grid_graph_scaled(1)
+}
+
+/// [`cch_grid_graph`] with every block `scale` times as long.
+fn cch_grid_graph_scaled(scale: usize) -> Graph {
+    let mut g = Graph::new();
+    let mut ids = Vec::new();
+    for r in 0..3 {
+        for c in 0..3 {
+            let name = format!("g{r}{c}");
+            ids.push(g.add_node(osm_node(&name, 50.0 + r as f64 * 0.001, 4.0 + c as f64 * 0.0015)));
+        }
+    }
+    let mut link = |a: usize, b: usize, len: usize| {
+        g.add_edge(ids[a], street_edge(ids[a], ids[b], len * scale));
+        g.add_edge(ids[b], street_edge(ids[b], ids[a], len * scale));
+    };
+    for r in 0..3 {
+        link(r * 3, r * 3 + 1, 110 + r * 40);
+        link(r * 3 + 1, r * 3 + 2, 130 + r * 25);
+    }
+    for c in 0..3 {
+        link(c, 3 + c, 115 + c * 60);
+        link(3 + c, 6 + c, 120 + c * 10);
+    }
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    g
+}
+
+#[test]
+fn cch_point_to_point_walk_matches_seeded_dijkstra() {
+    let g = cch_grid_graph();
+    let cch = g.build_cch_access();
+    let cg = g.contracted.as_ref().unwrap();
+    let radius = g.raptor.edge_snap_radius_m;
+    let pts: Vec<LatLng> = [
+        (50.0000, 4.0007),
+        (50.0004, 4.0000),
+        (50.0010, 4.0022),
+        (50.0016, 4.0030),
+        (50.0020, 4.0011),
+        (50.0002, 4.0030),
+    ]
+    .iter()
+    .map(|&(latitude, longitude)| LatLng { latitude, longitude })
+    .collect();
+    for &o in &pts {
+        for &d in &pts {
+            let want = cg.walk_secs_coord_to_coord(&g, o, d, radius, u32::MAX);
+            let got = g.cch_walk_secs(&cch, o, d, u32::MAX);
+            assert_eq!(got, want, "cch walk {o:?} -> {d:?}");
+        }
+    }
+    let far = g.cch_walk_secs(&cch, pts[0], pts[3], u32::MAX).unwrap();
+    assert_eq!(g.cch_walk_secs(&cch, pts[0], pts[3], far - 1), None, "bound is honoured");
+}
+
+#[test]
+fn walk_only_plans_leave_the_cch_alone_when_the_query_turns_it_off() {
+    // The hierarchy comes from the same grid at half the block lengths: its answers
+    // are stale, so the walk time shows which source was asked.
+    let mut g = cch_grid_graph_scaled(2);
+    g.set_cch(cch_grid_graph().build_cch_access());
+    let o = LatLng { latitude: 50.0, longitude: 4.0007 };
+    let d = LatLng { latitude: 50.0016, longitude: 4.003 };
+    let on = g.nearest_node(o.latitude, o.longitude).unwrap();
+    let dn = g.nearest_node(d.latitude, d.longitude).unwrap();
+    let am = ActiveModes::new(&[Mode::Walk]);
+    let mut walk_secs = |use_cch: bool| {
+        g.raptor.use_cch_access = use_cch;
+        let plans = raptor_modes_ep(&g, on, dn, o, d, 8 * 3600, 0, 0x7F, 0, &am);
+        plans[0].end - plans[0].start
+    };
+    let (off, with_cch) = (walk_secs(false), walk_secs(true));
+    let cg = g.contracted.as_ref().unwrap();
+    let exact = cg.walk_secs_coord_to_coord(&g, o, d, g.raptor.edge_snap_radius_m, u32::MAX).unwrap();
+    assert_eq!(off, exact);
+    assert!(with_cch < exact, "with the flag on, the (stale) hierarchy answers");
+}
+
 #[test]
 fn cch_build_survives_interior_array_drop() {
     let (mut g, a, h) = cch_star_graph();
@@ -11696,16 +12168,12 @@ fn direct_bus_two_trip_graph_perm(
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 8 * 3600,
             arrival: 8 * 3600 + 1800,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 9 * 3600,
             arrival: 9 * 3600 + 1800,
             service_id: ServiceId(0),
@@ -12015,10 +12483,10 @@ fn bus_tram_three_trip_graph() -> (Graph, NodeID, NodeID) {
         TripInfo { trip_headsign: None, route_id: RouteId(1), service_id: ServiceId(0), bikes_allowed: None },
     ]);
     g.add_transit_departures(vec![
-        TripSegment { trip_id: TripId(0), origin_stop_sequence: 0, destination_stop_sequence: 1, departure: 28800, arrival: 29100, service_id: ServiceId(0) },
-        TripSegment { trip_id: TripId(1), origin_stop_sequence: 0, destination_stop_sequence: 1, departure: 30000, arrival: 30600, service_id: ServiceId(0) },
-        TripSegment { trip_id: TripId(2), origin_stop_sequence: 0, destination_stop_sequence: 1, departure: 30600, arrival: 31200, service_id: ServiceId(0) },
-        TripSegment { trip_id: TripId(3), origin_stop_sequence: 0, destination_stop_sequence: 1, departure: 31200, arrival: 31800, service_id: ServiceId(0) },
+        TripSegment { trip_id: TripId(0), departure: 28800, arrival: 29100, service_id: ServiceId(0) },
+        TripSegment { trip_id: TripId(1), departure: 30000, arrival: 30600, service_id: ServiceId(0) },
+        TripSegment { trip_id: TripId(2), departure: 30600, arrival: 31200, service_id: ServiceId(0) },
+        TripSegment { trip_id: TripId(3), departure: 31200, arrival: 31800, service_id: ServiceId(0) },
     ]);
 
     {
@@ -12207,7 +12675,7 @@ fn next_day_route_graph(deps: &[u32]) -> (Graph, NodeID, NodeID) {
             origin: stop_a,
             destination: stop_b,
             route_id: RouteId(0),
-            timetable_segment: TimetableSegment { start: 0, len: n as usize },
+            timetable_segment: TimetableSegment { start: 0, len: n },
             length: 7000,
         }),
     );
@@ -12236,8 +12704,6 @@ fn next_day_route_graph(deps: &[u32]) -> (Graph, NodeID, NodeID) {
         (0..n)
             .map(|i| TripSegment {
                 trip_id: TripId(i),
-                origin_stop_sequence: 0,
-                destination_stop_sequence: 1,
                 departure: deps[i as usize],
                 arrival: deps[i as usize] + 600,
                 service_id: ServiceId(0),
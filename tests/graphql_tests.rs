@@ -8,14 +8,10 @@ use maas_rs::{
         Graph, LatLng, NodeData, OsmNodeData, TransitStopData,
         raptor::{Lookup, PatternInfo},
     },
-    web::app::{QueryRoot, build_schema},
+    web::app::build_schema,
 };
 
-type TestSchema = async_graphql::Schema<
-    QueryRoot,
-    async_graphql::EmptyMutation,
-    async_graphql::EmptySubscription,
->;
+type TestSchema = maas_rs::web::app::AppSchema;
 
 
 fn shared(g: Graph) -> maas_rs::services::scheduler::SharedGraph {
@@ -42,6 +38,7 @@ fn transit_stop(name: &str, lat: f64, lon: f64) -> NodeData {
         accessibility: Availability::Available,
         id: name.to_string(),
         platform_code: None,
+        code: None,
         parent_station: None,
     })
 }
@@ -56,6 +53,7 @@ fn transit_stop_parent(name: &str, id: &str, lat: f64, lon: f64, parent: Option<
         accessibility: Availability::Available,
         id: id.to_string(),
         platform_code: None,
+        code: None,
         parent_station: parent.map(|s| s.to_string()),
     })
 }
@@ -70,6 +68,7 @@ fn transit_stop_with_platform(name: &str, lat: f64, lon: f64, platform: &str) ->
         accessibility: Availability::Available,
         id: name.to_string(),
         platform_code: Some(platform.to_string()),
+        code: None,
         parent_station: None,
     })
 }
@@ -312,6 +311,139 @@ fn graphql_raptor_rejects_empty_modes() {
     );
 }
 
+#[test]
+fn graphql_raptor_reports_exhausted_search_budget() {
+    let mut g = Graph::new();
+    let ids: Vec<_> = (0..6)
+        .map(|i| g.add_node(osm_node(&format!("n{i}"), 50.0, 4.0 + i as f64 * 0.001)))
+        .collect();
+    for w in ids.windows(2) {
+        for (o, d) in [(w[0], w[1]), (w[1], w[0])] {
+            let mut e = foot_street(o, d, 75);
+            if let maas_rs::structures::EdgeData::Street(s) = &mut e {
+                s.bike = true;
+            }
+            g.add_edge(o, e);
+        }
+    }
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    g.set_query_budget(0, 1);
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.005, modes: [BIKE]) { start } }"#,
+    );
+    assert!(!resp.errors.is_empty(), "expected the budget to stop the search");
+    let code = resp.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|x| x.get("code").cloned());
+    assert_eq!(code, Some(Value::from("SEARCH_EXPANSION_LIMIT")));
+}
+
+#[test]
+fn graphql_raptor_profile_sets_walking_speed() {
+    let mut g = Graph::new();
+    let a = g.add_node(osm_node("a", 50.0, 4.0));
+    let b = g.add_node(osm_node("b", 50.0, 4.001));
+    g.add_edge(a, foot_street(a, b, 72));
+    g.add_edge(b, foot_street(b, a, 72));
+    g.set_walking_speed_mps(1.2);
+    g.set_routing_profile(
+        "slow".into(),
+        maas_rs::structures::RoutingProfile { walking_speed_mps: Some(0.6), ..Default::default() },
+    );
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+    let duration = |args: &str| {
+        let resp = execute_sync(
+            &schema,
+            &format!("{{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.001{args}) {{ start end }} }}"),
+        );
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        let Value::List(plans) = &data_obj(resp)["raptor"] else { panic!("expected plans") };
+        let Value::Object(p) = &plans[0] else { panic!("expected plan object") };
+        let secs = |k: &str| match &p[k] {
+            Value::Number(n) => n.as_i64().unwrap(),
+            other => panic!("expected number, got {other:?}"),
+        };
+        secs("end") - secs("start")
+    };
+    let base = duration("");
+    let slow = duration(r#", profile: "slow""#);
+    assert!(slow >= base * 2 - 1, "half speed should double the walk: {base} vs {slow}");
+}
+
+#[test]
+fn graphql_raptor_unknown_profile_is_rejected() {
+    let mut g = Graph::new();
+    let n0 = g.add_node(osm_node("n0", 50.0, 4.0));
+    let n1 = g.add_node(osm_node("n1", 50.001, 4.001));
+    g.add_edge(n0, foot_street(n0, n1, 150));
+    g.add_edge(n1, foot_street(n1, n0, 150));
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+
+    let resp = execute_sync(&schema, "{ routingProfiles }");
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let names = match &data_obj(resp)["routingProfiles"] {
+        Value::List(v) => v.clone(),
+        other => panic!("expected list, got {other:?}"),
+    };
+    for name in ["default", "fast", "senior", "wheelchair"] {
+        assert!(names.contains(&Value::from(name)), "missing built-in {name}");
+    }
+
+    let resp = execute_sync(
+        &schema,
+        r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.001, toLng: 4.001, profile: "jogger") { start } }"#,
+    );
+    assert!(!resp.errors.is_empty(), "expected an unknown-profile error");
+    let code = resp.errors[0]
+        .extensions
+        .as_ref()
+        .and_then(|x| x.get("code").cloned());
+    assert_eq!(code, Some(Value::from("UNKNOWN_PROFILE")));
+}
+
+#[test]
+fn graphql_raptor_warns_outside_feed_validity() {
+    use maas_rs::ingestion::gtfs::{IngestionReport, date_to_days};
+
+    let mut g = Graph::new();
+    let n0 = g.add_node(osm_node("n0", 50.0, 4.0));
+    let n1 = g.add_node(osm_node("n1", 50.001, 4.001));
+    g.add_edge(n0, foot_street(n0, n1, 150));
+    g.add_edge(n1, foot_street(n1, n0, 150));
+    let d = |y, m, day| date_to_days(chrono::NaiveDate::from_ymd_opt(y, m, day).unwrap());
+    g.begin_feed("stib", None);
+    g.set_feed_info_days(Some(d(2026, 1, 1)), Some(d(2026, 6, 30)));
+    g.finish_feed(IngestionReport::default());
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+
+    let query = |date: &str| {
+        format!(
+            r#"{{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.001, toLng: 4.001, date: "{date}", time: "08:00:00") {{ warnings {{ code message }} }} }}"#
+        )
+    };
+    let resp = execute_sync(&schema, &query("2026-03-02"));
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    assert_eq!(json["raptor"][0]["warnings"], serde_json::json!([]));
+
+    let resp = execute_sync(&schema, &query("2027-01-04"));
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    let warning = &json["raptor"][0]["warnings"][0];
+    assert_eq!(warning["code"], "OUTSIDE_FEED_VALIDITY");
+    assert!(warning["message"].as_str().unwrap().contains("2026-06-30"));
+}
+
 #[test]
 fn graphql_walk_only_plan_exposes_walk_mode() {
     let mut g = Graph::new();
@@ -359,6 +491,142 @@ fn graphql_walk_only_plan_exposes_walk_mode() {
     }
 }
 
+#[test]
+fn graphql_street_route_returns_one_leg_with_instructions() {
+    let mut g = Graph::new();
+    let a = g.add_node(osm_node("a", 50.0, 4.0));
+    let b = g.add_node(osm_node("b", 50.002, 4.0));
+    let c = g.add_node(osm_node("c", 50.002, 4.003));
+    for (o, d, len) in [(a, b, 222), (b, c, 214)] {
+        g.add_edge(o, foot_street(o, d, len));
+        g.add_edge(d, foot_street(d, o, len));
+    }
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        r#"{ streetRoute(fromLat: 50.0, fromLng: 4.0, toLat: 50.002, toLng: 4.003, time: "08:00") {
+            mode start legs { ... on PlanWalkLeg { length streetMode instructions { direction distance } } }
+        } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let Value::Object(plan) = &data["streetRoute"] else { panic!("expected plan object") };
+    assert_eq!(plan["mode"], Value::Enum(Name::new("WALK")));
+    assert_eq!(plan["start"], Value::from(28_800));
+    let Value::List(legs) = &plan["legs"] else { panic!("expected leg list") };
+    assert_eq!(legs.len(), 1);
+    let Value::Object(leg) = &legs[0] else { panic!("expected leg object") };
+    assert_eq!(leg["length"], Value::from(436));
+    let Value::List(steps) = &leg["instructions"] else { panic!("expected instructions") };
+    let directions: Vec<&Value> = steps
+        .iter()
+        .map(|s| match s {
+            Value::Object(o) => &o["direction"],
+            other => panic!("expected instruction object, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        directions,
+        ["DEPART", "RIGHT", "ARRIVE"].map(|d| Value::Enum(Name::new(d))).iter().collect::<Vec<_>>()
+    );
+
+    let resp = execute_sync(
+        &schema,
+        r#"{ streetRoute(fromLat: 50.0, fromLng: 4.0, toLat: 50.002, toLng: 4.003, mode: WALK_TRANSIT) { mode } }"#,
+    );
+    assert!(!resp.errors.is_empty(), "transit modes must be rejected");
+}
+
+#[test]
+fn graphql_plan_node_label_names_the_nearest_osm_address() {
+    use maas_rs::ingestion::osm::OsmAddressIndex;
+
+    let mut g = Graph::new();
+    let a = g.add_node(osm_node("a", 50.0, 4.0));
+    let b = g.add_node(osm_node("b", 50.0, 4.001));
+    g.add_edge(a, foot_street(a, b, 80));
+    g.add_edge(b, foot_street(b, a, 80));
+    g.set_address_index(OsmAddressIndex::from_entries(vec![(
+        "Rue Neuve".into(),
+        "12".into(),
+        LatLng { latitude: 50.0001, longitude: 4.0 },
+    )]));
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.001) {
+              legs { ... on PlanWalkLeg { from { node { label } } to { node { label } } } } } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let Value::List(plans) = &data["raptor"] else { panic!("expected plans") };
+    let Value::Object(plan) = &plans[0] else { panic!("expected plan") };
+    let Value::List(legs) = &plan["legs"] else { panic!("expected legs") };
+    let label = |leg: &Value, end: &str| match leg {
+        Value::Object(l) => match &l[end] {
+            Value::Object(place) => match &place["node"] {
+                Value::Object(n) => n["label"].clone(),
+                other => panic!("expected node, got {other:?}"),
+            },
+            other => panic!("expected place, got {other:?}"),
+        },
+        other => panic!("expected leg, got {other:?}"),
+    };
+    assert_eq!(label(&legs[0], "from"), Value::String("Rue Neuve 12".into()));
+    assert_eq!(
+        label(legs.last().unwrap(), "to"),
+        Value::Null,
+        "no address within 50 m of b"
+    );
+}
+
+#[test]
+fn graphql_geocode_searches_osm_addresses_and_pois() {
+    use maas_rs::ingestion::osm::{OsmAddressIndex, OsmPoi};
+
+    let mut g = Graph::new();
+    g.set_address_index(
+        OsmAddressIndex::from_entries(vec![(
+            "Rue Neuve".into(),
+            "12".into(),
+            LatLng { latitude: 50.85, longitude: 4.355 },
+        )])
+        .with_pois(vec![OsmPoi {
+            name: "Musée Magritte".into(),
+            category: "museum".into(),
+            loc: LatLng { latitude: 50.842, longitude: 4.358 },
+        }]),
+    );
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        r#"{ addr: geocode(text: "rue neuve 12", focusLat: 50.85, focusLng: 4.355) { label kind lat }
+            poi: geocode(text: "magritte") { label kind category }
+            none: geocode(text: "nowhere") { label } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let first = |key: &str| match &data[key] {
+        Value::List(v) => match v.first() {
+            Some(Value::Object(m)) => m.clone(),
+            other => panic!("expected a hit for {key}, got {other:?}"),
+        },
+        other => panic!("expected list, got {other:?}"),
+    };
+    let addr = first("addr");
+    assert_eq!(addr["label"], Value::String("Rue Neuve 12".into()));
+    assert_eq!(addr["kind"], Value::Enum(Name::new("ADDRESS")));
+    let poi = first("poi");
+    assert_eq!(poi["label"], Value::String("Musée Magritte".into()));
+    assert_eq!(poi["kind"], Value::Enum(Name::new("POI")));
+    assert_eq!(poi["category"], Value::String("museum".into()));
+    assert_eq!(data["none"], Value::List(vec![]));
+}
+
 #[test]
 fn graphql_raptor_invalid_date_returns_error() {
     let mut g = Graph::new();
@@ -377,6 +645,59 @@ fn graphql_raptor_invalid_date_returns_error() {
     );
 }
 
+#[test]
+fn graphql_batch_raptor_keeps_order_and_isolates_failures() {
+    let mut g = Graph::new();
+    let a = g.add_node(osm_node("a", 50.0, 4.0));
+    let b = g.add_node(osm_node("b", 50.0, 4.001));
+    g.add_edge(a, foot_street(a, b, 80));
+    g.add_edge(b, foot_street(b, a, 80));
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        r#"{ batchRaptor(requests: [
+            { fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.001 },
+            { fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.001, date: "not-a-date" },
+            { fromLat: 50.0, fromLng: 4.001, toLat: 50.0, toLng: 4.0, windowMinutes: 5000 },
+            { fromLat: 50.0, fromLng: 4.001, toLat: 50.0, toLng: 4.0 }
+        ]) { plans { mode } error } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let Value::List(results) = &data["batchRaptor"] else {
+        panic!("expected result list, got {:?}", data["batchRaptor"]);
+    };
+    assert_eq!(results.len(), 4);
+    let field = |i: usize, name: &str| match &results[i] {
+        Value::Object(r) => r[name].clone(),
+        other => panic!("expected result object, got {other:?}"),
+    };
+    for i in [0, 3] {
+        assert_eq!(field(i, "error"), Value::Null);
+        assert!(matches!(field(i, "plans"), Value::List(p) if !p.is_empty()));
+    }
+    let Value::String(msg) = field(1, "error") else { panic!("expected an error") };
+    assert!(msg.to_lowercase().contains("invalid date"), "got: {msg}");
+    let Value::String(msg) = field(2, "error") else { panic!("expected an error") };
+    assert!(msg.contains("windowMinutes"), "got: {msg}");
+    assert_eq!(field(2, "plans"), Value::List(vec![]));
+}
+
+#[test]
+fn graphql_batch_raptor_rejects_oversized_batches() {
+    let schema = build_schema(shared(Graph::new()));
+    let one = "{ fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.001 }";
+    let q = format!(
+        "{{ batchRaptor(requests: [{}]) {{ error }} }}",
+        vec![one; 501].join(",")
+    );
+    let resp = execute_sync(&schema, &q);
+    assert!(!resp.errors.is_empty());
+    assert!(resp.errors[0].message.contains("<= 500"), "got: {}", resp.errors[0].message);
+}
+
 #[test]
 fn graphql_gtfs_stops_empty_on_no_transit() {
     let schema = build_schema(shared(Graph::new()));
@@ -403,6 +724,115 @@ fn graphql_gtfs_agencies_empty_on_no_transit() {
     assert_eq!(data["gtfsAgencies"], Value::List(vec![]));
 }
 
+#[test]
+fn graphql_ingestion_reports_lists_recorded_feeds() {
+    use maas_rs::ingestion::gtfs::IngestionReport;
+
+    let mut g = Graph::new();
+    g.record_ingestion_report(
+        "stib",
+        IngestionReport {
+            stops_total: 3,
+            stops_unlinked: 1,
+            link_distance_histogram: vec![2, 0, 0, 0, 0, 0, 0, 0],
+            unmatched_agency_ids: vec!["X".into()],
+            ..Default::default()
+        },
+    );
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        "{ ingestionReports { feed stopsTotal stopsUnlinked linkDistances { maxM count } unmatchedAgencyIds } }",
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    let r = &json["ingestionReports"][0];
+    assert_eq!(r["feed"], "stib");
+    assert_eq!(r["stopsTotal"], 3);
+    assert_eq!(r["stopsUnlinked"], 1);
+    assert_eq!(r["linkDistances"][0]["maxM"], 5);
+    assert_eq!(r["linkDistances"][0]["count"], 2);
+    assert_eq!(r["unmatchedAgencyIds"][0], "X");
+}
+
+#[test]
+fn graphql_ingestion_warnings_filter_and_paginate() {
+    use maas_rs::ingestion::gtfs::{IngestionReport, IngestionWarning, IngestionWarningReason};
+
+    let warning = |reason, id: &str| IngestionWarning { reason, entity_id: id.into(), detail: None };
+    let mut g = Graph::new();
+    g.record_ingestion_report(
+        "stib",
+        IngestionReport {
+            warnings: vec![
+                warning(IngestionWarningReason::StopWithoutCoords, "stib:S1"),
+                warning(IngestionWarningReason::TripTooShort, "stib:T1"),
+                warning(IngestionWarningReason::StopWithoutCoords, "stib:S2"),
+            ],
+            ..Default::default()
+        },
+    );
+    g.record_ingestion_report(
+        "delijn",
+        IngestionReport {
+            warnings: vec![warning(IngestionWarningReason::StopWithoutCoords, "dl:S9")],
+            ..Default::default()
+        },
+    );
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        "{ ingestionWarnings(reason: STOP_WITHOUT_COORDS, offset: 1, limit: 1) { total warnings { feed reason entityId } } }",
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    let page = &json["ingestionWarnings"];
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["warnings"].as_array().unwrap().len(), 1);
+    assert_eq!(page["warnings"][0]["entityId"], "stib:S2");
+    assert_eq!(page["warnings"][0]["reason"], "STOP_WITHOUT_COORDS");
+
+    let resp = execute_sync(&schema, r#"{ ingestionWarnings(feed: "delijn") { total warnings { entityId } } }"#);
+    let json = resp.data.into_json().unwrap();
+    assert_eq!(json["ingestionWarnings"]["total"], 1);
+    assert_eq!(json["ingestionWarnings"]["warnings"][0]["entityId"], "dl:S9");
+}
+
+#[test]
+fn graphql_data_info_reports_build_provenance() {
+    use maas_rs::structures::SourceInfo;
+
+    let mut g = Graph::new();
+    g.record_source(SourceInfo {
+        label: "stib".into(),
+        kind: "gtfs/stib".into(),
+        location: "https://example.org/stib.zip".into(),
+        sha256: Some("ab".repeat(32)),
+        feed_version: Some("2026-10".into()),
+        feed_end_date: Some("2026-12-31".into()),
+        ..Default::default()
+    });
+    g.finish_build_info(1_790_000_000, 12.5);
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        "{ dataInfo { builtWith serverVersion builtAt buildSecs sources { label kind location sha256 feedVersion feedEndDate osmTimestamp } } }",
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    let info = &json["dataInfo"];
+    assert_eq!(info["builtWith"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["builtWith"], info["serverVersion"]);
+    assert_eq!(info["builtAt"], 1_790_000_000i64);
+    assert_eq!(info["buildSecs"], 12.5);
+    let s = &info["sources"][0];
+    assert_eq!(s["kind"], "gtfs/stib");
+    assert_eq!(s["location"], "https://example.org/stib.zip");
+    assert_eq!(s["feedVersion"], "2026-10");
+    assert_eq!(s["feedEndDate"], "2026-12-31");
+    assert!(s["osmTimestamp"].is_null());
+}
+
 #[test]
 fn graphql_search_addresses_returns_synthetic_hits() {
     use maas_rs::structures::{AddressIndexBuilder, Named, RealtimeIndex};
@@ -595,6 +1025,66 @@ fn graphql_gtfs_stops_returns_stop_data() {
     assert_eq!(stop_obj["mode"], Value::String("Bus".into()));
 }
 
+#[test]
+fn graphql_stops_nearby_filters_by_metric_radius() {
+    let mut g = Graph::new();
+    g.add_node(transit_stop("Near", 50.8450, 4.3570));
+    g.add_node(transit_stop("Mid", 50.8450, 4.3600));
+    g.add_node(transit_stop("Far", 50.8600, 4.3570));
+    g.build_raptor_index();
+
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        "{ stopsNearby(lat: 50.845, lng: 4.357, radiusM: 400) { distanceM stop { name } } }",
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    let hits = json["stopsNearby"].as_array().unwrap();
+    let names: Vec<&str> = hits.iter().map(|h| h["stop"]["name"].as_str().unwrap()).collect();
+    // ~211 m east-west at 50.8°N; "Far" is ~1.7 km north.
+    assert_eq!(names, vec!["Near", "Mid"]);
+    assert!(hits[1]["distanceM"].as_f64().unwrap() > 150.0);
+
+    let resp = execute_sync(&schema, "{ stopsNearby(lat: 50.845, lng: 4.357, radiusM: 99999) { distanceM } }");
+    assert!(!resp.errors.is_empty(), "radius above the cap must be rejected");
+}
+
+#[test]
+fn graphql_stops_nearby_groups_platforms_by_station() {
+    let mut g = Graph::new();
+    g.add_node(transit_stop_parent("Gent P1", "p1", 51.0000, 3.7000, Some("Gent")));
+    g.add_node(transit_stop_parent("Gent P2", "p2", 51.0001, 3.7000, Some("Gent")));
+    g.add_node(transit_stop("Solo Halt", 51.0010, 3.7000));
+    g.build_raptor_index();
+
+    let schema = build_schema(shared(g));
+    let query = |grouped: bool| {
+        let resp = execute_sync(
+            &schema,
+            &format!(
+                "{{ stopsNearby(lat: 51.0, lng: 3.7, groupByStation: {grouped}) \
+                 {{ distanceM stationId platformCount stop {{ name }} }} }}"
+            ),
+        );
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        resp.data.into_json().unwrap()["stopsNearby"].as_array().unwrap().clone()
+    };
+
+    let all = query(false);
+    assert_eq!(all.len(), 3, "every platform listed by default");
+    assert_eq!(all[1]["stationId"], "Gent");
+
+    let grouped = query(true);
+    let rows: Vec<(&str, i64)> = grouped
+        .iter()
+        .map(|h| (h["stop"]["name"].as_str().unwrap(), h["platformCount"].as_i64().unwrap()))
+        .collect();
+    // Named like its `gtfsStations` entry.
+    assert_eq!(rows, vec![("Gent P1", 2), ("Solo Halt", 1)]);
+    assert_eq!(grouped[0]["distanceM"], all[0]["distanceM"], "closest platform stands for the station");
+}
+
 #[test]
 fn graphql_gtfs_stations_returns_station_data() {
     let mut g = Graph::new();
@@ -1071,6 +1561,7 @@ fn graphql_transit_plan_access_leg_has_alternatives_and_leave_by() {
         },
         accessibility: Availability::Available,
         platform_code: None,
+        code: None,
         parent_station: None,
     }));
     let stop_b = g.add_node(NodeData::TransitStop(TransitStopData {
@@ -1082,6 +1573,7 @@ fn graphql_transit_plan_access_leg_has_alternatives_and_leave_by() {
         },
         accessibility: Availability::Available,
         platform_code: None,
+        code: None,
         parent_station: None,
     }));
 
@@ -1177,8 +1669,6 @@ fn graphql_transit_plan_access_leg_has_alternatives_and_leave_by() {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
         departure: 9 * 3600 + 600,
         arrival: 9 * 3600 + 1080,
         service_id: ServiceId(0),
@@ -1301,6 +1791,11 @@ fn graphql_transit_plan_access_leg_has_alternatives_and_leave_by() {
 /// Builds a minimal WALK_TRANSIT graph: origin → Stop A —(trip T0)→ Stop B → dest.
 /// The single trip carries GTFS `trip_id` "T0"; the stops carry GTFS ids "SA"/"SB".
 fn transit_handles_graph() -> Graph {
+    transit_handles_graph_departing(9 * 3600 + 600)
+}
+
+/// Stop A → Stop B on trip `T0` leaving A at `dep` (GTFS seconds) and riding 480 s.
+fn transit_handles_graph_departing(dep: u32) -> Graph {
     use gtfs_structures::{Availability, RouteType};
     use maas_rs::ingestion::gtfs::{
         AgencyId, RouteId, RouteInfo, ServiceId, ServicePattern, StopTime, TimetableSegment,
@@ -1330,6 +1825,7 @@ fn transit_handles_graph() -> Graph {
         },
         accessibility: Availability::Available,
         platform_code: None,
+        code: None,
         parent_station: None,
     }));
     let stop_b = g.add_node(NodeData::TransitStop(TransitStopData {
@@ -1341,6 +1837,7 @@ fn transit_handles_graph() -> Graph {
         },
         accessibility: Availability::Available,
         platform_code: None,
+        code: None,
         parent_station: None,
     }));
 
@@ -1402,7 +1899,7 @@ fn transit_handles_graph() -> Graph {
     g.add_transit_services(vec![ServicePattern {
         days_of_week: 0x7F,
         start_date: 0,
-        end_date: 9999,
+        end_date: u32::MAX,
         added_dates: vec![],
         removed_dates: vec![],
     }]);
@@ -1423,10 +1920,8 @@ fn transit_handles_graph() -> Graph {
     g.add_transit_trip_ids(vec!["T0".into()]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
-        departure: 9 * 3600 + 600,
-        arrival: 9 * 3600 + 1080,
+        departure: dep,
+        arrival: dep + 480,
         service_id: ServiceId(0),
     }]);
     {
@@ -1438,13 +1933,13 @@ fn transit_handles_graph() -> Graph {
         g.push_transit_idx_pattern_trips(Lookup { start: ts, len: 1 });
         let sts = g.transit_pattern_stop_times_len();
         g.push_transit_pattern_stop_time(StopTime {
-            arrival: 9 * 3600 + 600,
-            departure: 9 * 3600 + 600,
+            arrival: dep,
+            departure: dep,
             ..Default::default()
         });
         g.push_transit_pattern_stop_time(StopTime {
-            arrival: 9 * 3600 + 1080,
-            departure: 9 * 3600 + 1080,
+            arrival: dep + 480,
+            departure: dep + 480,
             ..Default::default()
         });
         g.push_transit_idx_pattern_stop_times(Lookup { start: sts, len: 2 });
@@ -1532,6 +2027,107 @@ fn graphql_transit_leg_exposes_trip_id_and_stop_ids() {
     );
 }
 
+#[test]
+fn graphql_raptor_debug_attaches_search_diagnostics() {
+    let schema = build_schema(shared(transit_handles_graph()));
+    let query = |debug: &str| {
+        format!(
+            r#"{{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+                modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00"{debug}) {{
+              debug {{
+                origin {{ node distanceM }}
+                destination {{ distanceM }}
+                expansions
+                pruned {{ reason count }}
+                phases {{ phase ms }}
+                totalMs
+              }}
+            }} }}"#
+        )
+    };
+    let plain = data_obj(execute_sync(&schema, &query("")));
+    let Value::List(plans) = &plain["raptor"] else { panic!("expected plans") };
+    assert!(plans.iter().all(|p| matches!(p, Value::Object(m) if m["debug"] == Value::Null)));
+
+    let resp = execute_sync(&schema, &query(", debug: true"));
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let Value::List(plans) = &data["raptor"] else { panic!("expected plans") };
+    let Value::Object(plan) = &plans[0] else { panic!("expected a plan") };
+    let Value::Object(debug) = &plan["debug"] else { panic!("expected debug, got {plan:?}") };
+    let Value::Object(origin) = &debug["origin"] else { panic!("expected an origin snap") };
+    let Value::Number(dist) = &origin["distanceM"] else { panic!("expected distanceM") };
+    assert!(dist.as_f64().unwrap() < 1.0, "origin sits on a node: {dist}");
+    let Value::Number(expansions) = &debug["expansions"] else { panic!("expected expansions") };
+    assert!(expansions.as_u64().unwrap() > 0);
+    let Value::List(phases) = &debug["phases"] else { panic!("expected phases") };
+    assert!(
+        phases.iter().any(|p| matches!(p, Value::Object(m) if m["phase"] == Value::from("forward"))),
+        "{phases:?}"
+    );
+}
+
+fn plan_page(
+    schema: &TestSchema,
+    args: &str,
+) -> (Vec<i64>, String, String) {
+    let resp = execute_sync(
+        schema,
+        &format!("{{ raptorPage({args}) {{ plans {{ start }} previousPageCursor nextPageCursor }} }}"),
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let page = match &data_obj(resp)["raptorPage"] {
+        Value::Object(m) => m.clone(),
+        other => panic!("expected page object, got {other:?}"),
+    };
+    let starts = match &page["plans"] {
+        Value::List(v) => v
+            .iter()
+            .map(|p| match p {
+                Value::Object(m) => match &m["start"] {
+                    Value::Number(n) => n.as_i64().unwrap(),
+                    other => panic!("expected start, got {other:?}"),
+                },
+                other => panic!("expected plan, got {other:?}"),
+            })
+            .collect(),
+        other => panic!("expected plan list, got {other:?}"),
+    };
+    let cursor = |k: &str| match &page[k] {
+        Value::String(s) => s.clone(),
+        other => panic!("expected {k} cursor, got {other:?}"),
+    };
+    (starts, cursor("previousPageCursor"), cursor("nextPageCursor"))
+}
+
+#[test]
+fn graphql_raptor_page_cursors_walk_later_and_back_earlier() {
+    let schema = build_schema(shared(transit_handles_graph()));
+    // The only trip leaves Stop A at 09:10; the first page covers 08:30–09:30.
+    let (first, before_first, after_first) = plan_page(
+        &schema,
+        r#"request: { fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+            modes: [WALK_TRANSIT], date: "2026-06-23", time: "08:30:00", windowMinutes: 60 }"#,
+    );
+    assert_eq!(first.len(), 1, "{first:?}");
+    assert!((8 * 3600 + 1800..9 * 3600 + 600).contains(&first[0]), "{first:?}");
+
+    let (earlier, _, _) = plan_page(&schema, &format!(r#"pageCursor: "{before_first}""#));
+    assert!(earlier.is_empty(), "nothing runs before 08:30: {earlier:?}");
+    let (later, back, _) = plan_page(&schema, &format!(r#"pageCursor: "{after_first}""#));
+    assert_eq!(later, vec![first[0] + 86_400], "next is the following day's run, not a repeat");
+    let (again, _, _) = plan_page(&schema, &format!(r#"pageCursor: "{back}""#));
+    assert_eq!(again, first, "paging back from the later page finds the trip again");
+
+    let unknown = execute_sync(&schema, r#"{ raptorPage(pageCursor: "later:ffff") { nextPageCursor } }"#);
+    assert_eq!(
+        unknown.errors[0].extensions.as_ref().and_then(|e| e.get("code")),
+        Some(&Value::from("UNKNOWN_PAGE_CURSOR"))
+    );
+    let neither = execute_sync(&schema, "{ raptorPage { nextPageCursor } }");
+    assert!(!neither.errors.is_empty());
+}
+
 #[test]
 fn graphql_transit_leg_trip_id_is_stable_across_queries() {
     let schema = build_schema(shared(transit_handles_graph()));
@@ -1555,6 +2151,368 @@ fn graphql_transit_leg_trip_id_is_stable_across_queries() {
     assert_eq!(first, second, "tripId must be stable across repeated queries");
 }
 
+#[test]
+fn graphql_plan_by_id_rehydrates_on_a_rebuilt_graph_and_flags_missed_trips() {
+    let schema = build_schema(shared(transit_handles_graph()));
+    let query = r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+            modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00") {
+      itineraryId
+      legs { ... on PlanTransitLeg { tripId } }
+   } }"#;
+    let resp = execute_sync(&schema, query);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let Value::List(plans) = &data_obj(resp)["raptor"] else { panic!("expected plans") };
+    let Some(Value::Object(plan)) =
+        plans.iter().find(|p| first_transit_leg(std::slice::from_ref(p)).is_some())
+    else {
+        panic!("expected a transit plan")
+    };
+    let Value::String(id) = &plan["itineraryId"] else { panic!("transit plans carry an itineraryId") };
+
+    let by_id = |schema: &TestSchema, id: &str| {
+        execute_sync(
+            schema,
+            &format!(
+                r#"{{ planById(id: "{id}") {{ itineraryId legs {{ ... on PlanTransitLeg {{ tripId start }} }} }} }}"#
+            ),
+        )
+    };
+    // A rebuild of the same data keeps every stable id: the same trip comes back.
+    let rebuilt = build_schema(shared(transit_handles_graph()));
+    let resp = by_id(&rebuilt, id);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let again = data_obj(resp)["planById"].clone();
+    let Value::Object(again) = again else { panic!("expected a plan") };
+    assert_eq!(&again["itineraryId"], &plan["itineraryId"]);
+    let leg = first_transit_leg(&[Value::Object(again)]).expect("transit leg");
+    assert_eq!(leg["tripId"], Value::String("T0".into()));
+    assert_eq!(leg["start"], Value::from(9 * 3600 + 600));
+
+    // T0 now leaves before the saved search's departure: the connection is gone.
+    let retimed = build_schema(shared(transit_handles_graph_departing(8 * 3600 + 3000)));
+    let resp = by_id(&retimed, id);
+    assert_eq!(
+        resp.errors[0].extensions.as_ref().and_then(|e| e.get("code")),
+        Some(&Value::from("ITINERARY_UNAVAILABLE"))
+    );
+    let resp = by_id(&schema, "AAAA");
+    assert_eq!(
+        resp.errors[0].extensions.as_ref().and_then(|e| e.get("code")),
+        Some(&Value::from("INVALID_ITINERARY_ID"))
+    );
+}
+
+#[test]
+fn graphql_language_argument_translates_stop_and_route_names() {
+    use maas_rs::ingestion::gtfs::{NameField, NameTranslations};
+    let mut g = transit_handles_graph();
+    let mut t = NameTranslations::default();
+    t.insert(NameField::StopName, "Stop A", "nl", "Halte A");
+    t.insert(NameField::RouteLongName, "Metro M", "nl-BE", "Metrolijn M");
+    g.add_name_translations(t);
+    let schema = build_schema(shared(g));
+
+    let query = |language: &str| {
+        let q = format!(
+            r#"{{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+                modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00", language: "{language}") {{
+              legs {{ ... on PlanTransitLeg {{
+                tripId from {{ node {{ name }} }} to {{ node {{ name }} }} trip {{ route {{ longName }} }}
+              }} }}
+            }}
+            gtfsStops(language: "{language}") {{ name }} }}"#
+        );
+        let resp = execute_sync(&schema, &q);
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        resp.data.into_json().unwrap()
+    };
+
+    let nl = query("de, nl-BE;q=0.8");
+    let leg = nl["raptor"][0]["legs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["tripId"].is_string())
+        .expect("transit leg")
+        .clone();
+    assert_eq!(leg["from"]["node"]["name"], "Halte A", "`nl-BE` falls back to the `nl` row");
+    assert_eq!(leg["to"]["node"]["name"], "Stop B", "untranslated names keep the feed text");
+    assert_eq!(leg["trip"]["route"]["longName"], "Metrolijn M");
+    let stops: Vec<_> = nl["gtfsStops"].as_array().unwrap().iter().map(|s| s["name"].clone()).collect();
+    assert!(stops.contains(&"Halte A".into()), "{stops:?}");
+
+    let en = query("en");
+    let leg = en["raptor"][0]["legs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["tripId"].is_string())
+        .expect("transit leg")
+        .clone();
+    assert_eq!(leg["from"]["node"]["name"], "Stop A", "no `en` translation: feed text");
+    assert_eq!(leg["trip"]["route"]["longName"], "Metro M");
+}
+
+#[test]
+fn graphql_plan_nodes_expose_accessibility_routes_and_eid() {
+    let query = r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+            modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00") {
+          legs {
+            ... on PlanWalkLeg { from { node { eid accessibility routes { shortName } } } }
+            ... on PlanTransitLeg {
+              tripId
+              from { node { eid accessibility routes { shortName mode color } } }
+              to { node { eid routes { shortName } } }
+            }
+          } } }"#;
+    let resp = execute_sync(&build_schema(shared(transit_handles_graph())), query);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = resp.data.into_json().unwrap();
+    let legs = data["raptor"][0]["legs"].as_array().unwrap();
+    let walk = &legs[0]["from"]["node"];
+    assert_eq!(walk["eid"], "origin");
+    assert!(walk["accessibility"].is_null(), "street places have no accessibility");
+    assert_eq!(walk["routes"].as_array().unwrap().len(), 0);
+
+    let leg = legs.iter().find(|l| l["tripId"].is_string()).expect("transit leg");
+    let from = &leg["from"]["node"];
+    assert_eq!(from["eid"].as_str().unwrap().split(':').take(2).collect::<Vec<_>>(), ["maas", "stop"]);
+    assert_eq!(from["accessibility"], "ACCESSIBLE");
+    assert_eq!(from["routes"][0]["shortName"], "M");
+    assert_eq!(from["routes"][0]["mode"], "SUBWAY");
+    assert!(from["routes"][0]["color"].is_null());
+    let to = &leg["to"]["node"];
+    assert_ne!(to["eid"], from["eid"]);
+    assert_eq!(to["routes"].as_array().unwrap().len(), 0, "M terminates at Stop B");
+}
+
+#[test]
+fn graphql_transit_leg_carries_route_and_trip_display_fields() {
+    let query = r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+            modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00") {
+          legs { ... on PlanTransitLeg { tripId routeShortName mode headsign routeColor routeTextColor } } } }"#;
+    let mut g = transit_handles_graph();
+    g.raptor.transit_routes[0].route_color = Some((0xC4, 0x00, 0x8F));
+    g.raptor.transit_trips[0].trip_headsign = Some("Stop B".into());
+    let resp = execute_sync(&build_schema(shared(g)), query);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = resp.data.into_json().unwrap();
+    let leg = data["raptor"][0]["legs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["tripId"].is_string())
+        .expect("transit leg")
+        .clone();
+    assert_eq!(leg["routeShortName"], "M");
+    assert_eq!(leg["mode"], "SUBWAY");
+    assert_eq!(leg["headsign"], "Stop B");
+    assert_eq!(leg["routeColor"], "C4008F");
+    assert!(leg["routeTextColor"].is_null());
+}
+
+#[test]
+fn graphql_stop_amenities_on_plan_nodes_and_gtfs_stops() {
+    use maas_rs::ingestion::osm::{AMENITY_SHELTER, AMENITY_TOILETS, StopAmenityIndex};
+    let query = r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+            modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00") {
+          legs { ... on PlanTransitLeg {
+            tripId from { node { amenities { shelter toilets } } } to { node { amenities { shelter } } }
+          } } }
+        gtfsStops { name amenities { shelter bench toilets wheelchairToilets lit } } }"#;
+
+    let resp = execute_sync(&build_schema(shared(transit_handles_graph())), query);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = resp.data.into_json().unwrap();
+    assert!(data["gtfsStops"][0]["amenities"].is_null(), "not built: null, not all-false");
+
+    let mut g = transit_handles_graph();
+    g.set_amenity_index(StopAmenityIndex::from_points(vec![
+        (LatLng { latitude: 50.0001, longitude: 4.0081 }, AMENITY_SHELTER),
+        (LatLng { latitude: 50.0008, longitude: 4.0081 }, AMENITY_TOILETS),
+    ]));
+    let resp = execute_sync(&build_schema(shared(g)), query);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = resp.data.into_json().unwrap();
+    let leg = data["raptor"][0]["legs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["tripId"].is_string())
+        .expect("transit leg")
+        .clone();
+    assert_eq!(leg["from"]["node"]["amenities"]["shelter"], true);
+    assert_eq!(leg["from"]["node"]["amenities"]["toilets"], true, "toilets ~80 m away count");
+    assert_eq!(leg["to"]["node"]["amenities"]["shelter"], false);
+    let stop_a = data["gtfsStops"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["name"] == "Stop A")
+        .expect("Stop A")
+        .clone();
+    assert_eq!(stop_a["amenities"]["shelter"], true);
+    assert_eq!(stop_a["amenities"]["bench"], false);
+}
+
+#[test]
+fn graphql_parking_nearby_lists_facilities_closest_first() {
+    use maas_rs::ingestion::osm::{ParkingFacility, ParkingIndex, ParkingKind};
+    let facility = |lat: f64, kind, capacity, name: Option<&str>| ParkingFacility {
+        loc: LatLng { latitude: lat, longitude: 4.0 },
+        kind,
+        name: name.map(str::to_string),
+        capacity,
+        fee: Some(false),
+        park_ride: name.is_some(),
+    };
+    let query = r#"{ parkingNearby(lat: 50.0, lng: 4.0, radiusM: 400) {
+          name kind capacity fee parkAndRide distanceM }
+        cars: parkingNearby(lat: 50.0, lng: 4.0, kind: CAR) { kind } }"#;
+
+    let resp = execute_sync(&build_schema(shared(transit_handles_graph())), query);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    assert_eq!(resp.data.into_json().unwrap()["parkingNearby"], serde_json::json!([]));
+
+    let mut g = transit_handles_graph();
+    g.set_parking_index(ParkingIndex::from_facilities(vec![
+        facility(50.002, ParkingKind::Car, Some(120), Some("P+R A")),
+        facility(50.0005, ParkingKind::Bicycle, None, None),
+        // ~1.1 km out: beyond the radius.
+        facility(50.01, ParkingKind::Car, Some(40), None),
+    ]));
+    let resp = execute_sync(&build_schema(shared(g)), query);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = resp.data.into_json().unwrap();
+    let found = data["parkingNearby"].as_array().unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0]["kind"], "BICYCLE");
+    assert!(found[0]["capacity"].is_null());
+    assert_eq!(found[1]["name"], "P+R A");
+    assert_eq!((found[1]["capacity"].as_i64(), found[1]["parkAndRide"].as_bool()), (Some(120), Some(true)));
+    assert_eq!(found[1]["fee"], false);
+    assert_eq!(data["cars"], serde_json::json!([{ "kind": "CAR" }]));
+}
+
+#[test]
+fn graphql_what_if_closes_routes_stops_and_trips_for_one_request() {
+    let schema = build_schema(shared(transit_handles_graph()));
+    let trip_ids = |what_if: &str| {
+        let q = format!(
+            r#"{{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+                modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00"{what_if}) {{
+              legs {{ ... on PlanTransitLeg {{ tripId }} }} }} }}"#
+        );
+        let resp = execute_sync(&schema, &q);
+        if resp.errors.iter().any(|e| e.message == "No plan found") {
+            return vec![];
+        }
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        let data = resp.data.into_json().unwrap();
+        data["raptor"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|p| p["legs"].as_array().unwrap().clone())
+            .filter_map(|l| l["tripId"].as_str().map(str::to_string))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(trip_ids(""), vec!["T0"]);
+    assert!(trip_ids(r#", whatIf: { closedRoutes: ["maas:route:0"] }"#).is_empty());
+    assert!(trip_ids(r#", whatIf: { canceledTrips: ["T0"] }"#).is_empty());
+    assert!(trip_ids(r#", whatIf: { closedStops: ["SB"] }"#).is_empty(), "cannot alight at B");
+    assert_eq!(trip_ids(""), vec!["T0"], "the served graph is untouched");
+
+    let resp = execute_sync(
+        &schema,
+        r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.098,
+              whatIf: { closedRoutes: ["nope"] }) { start } }"#,
+    );
+    assert_eq!(resp.errors.len(), 1);
+    assert!(resp.errors[0].message.contains("unknown route nope"), "{:?}", resp.errors);
+}
+
+#[tokio::test]
+async fn stop_departures_subscription_pushes_the_board_on_realtime_updates() {
+    use async_graphql::futures_util::StreamExt;
+    use maas_rs::ingestion::gtfs::TripId;
+    use maas_rs::services::realtime_poller::realtime_updates;
+    use maas_rs::structures::RealtimeIndex;
+    use maas_rs::web::app::build_schema_rt_live;
+
+    // Leaves at 47:00 of today's service day, so always ahead of the wall clock.
+    let dep = 47 * 3600;
+    let g = transit_handles_graph_departing(dep);
+    let stop_a = g.stop_index_of("SA").expect("SA resolves") as u32;
+    let realtime = Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new()));
+    let updates = realtime_updates();
+    let schema = build_schema_rt_live(shared(g), realtime.clone(), updates.clone());
+    let mut boards = schema.execute_stream(
+        r#"subscription { stopDepartures(stopId: "SA") {
+             tripId scheduledDeparture realtimeDeparture realtime } }"#,
+    );
+    let mut next_board = async || {
+        let resp = boards.next().await.expect("a board");
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        resp.data.into_json().unwrap()["stopDepartures"].clone()
+    };
+
+    assert_eq!(
+        next_board().await,
+        serde_json::json!([{
+            "tripId": "T0", "scheduledDeparture": dep, "realtimeDeparture": dep, "realtime": false
+        }]),
+        "the board is sent on subscribe"
+    );
+    updates.send(1).unwrap();
+    let quiet = tokio::time::timeout(std::time::Duration::from_millis(50), next_board()).await;
+    assert!(quiet.is_err(), "an update that leaves the board alone pushes nothing");
+    realtime.store(Arc::new(RealtimeIndex::from_delays(2, [((TripId(0), stop_a), 120)])));
+    updates.send(2).unwrap();
+    assert_eq!(
+        next_board().await,
+        serde_json::json!([{
+            "tripId": "T0", "scheduledDeparture": dep, "realtimeDeparture": dep + 120, "realtime": true
+        }])
+    );
+}
+
+#[test]
+fn graphql_what_if_closed_street_reroutes_the_walk() {
+    let mut g = Graph::new();
+    let a = g.add_node(osm_node("map#osm#1", 50.0, 4.0));
+    let b = g.add_node(osm_node("map#osm#2", 50.0, 4.001));
+    let c = g.add_node(osm_node("map#osm#3", 50.0005, 4.0005));
+    // Spurs make a and b junctions; a bare cycle has none to contract around.
+    let d = g.add_node(osm_node("map#osm#4", 49.9995, 4.0));
+    let e = g.add_node(osm_node("map#osm#5", 49.9995, 4.001));
+    for (x, y, len) in [(a, b, 80), (a, c, 70), (c, b, 70), (a, d, 55), (b, e, 55)] {
+        g.add_edge(x, foot_street(x, y, len));
+        g.add_edge(y, foot_street(y, x, len));
+    }
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+    let walked = |what_if: &str| {
+        let q = format!(
+            r#"{{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.001{what_if}) {{
+              legs {{ ... on PlanWalkLeg {{ length }} }} }} }}"#
+        );
+        let resp = execute_sync(&schema, &q);
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        let data = resp.data.into_json().unwrap();
+        data["raptor"][0]["legs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| l["length"].as_u64().unwrap())
+            .sum::<u64>()
+    };
+    assert_eq!(walked(""), 80);
+    assert_eq!(walked(r#", whatIf: { closedStreets: [{ fromOsmNode: 2, toOsmNode: 1 }] }"#), 140);
+}
+
 #[test]
 fn graphql_realtime_generated_at_is_zero_for_empty_index() {
     let schema = build_schema(shared(Graph::new()));
@@ -1641,6 +2599,7 @@ fn live_refresh_graph() -> Graph {
             },
             accessibility: Availability::Available,
             platform_code: None,
+            code: None,
             parent_station: None,
         }))
     };
@@ -1702,16 +2661,12 @@ fn live_refresh_graph() -> Graph {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 33000,
             arrival: 33480,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(1),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 34500,
             arrival: 35400,
             service_id: ServiceId(0),
@@ -1795,7 +2750,7 @@ fn int_field(obj: &async_graphql::indexmap::IndexMap<Name, Value>, key: &str) ->
 
 const LIVE_LEG0_QUERY: &str = r#"{ liveRefresh(legs: [
         { tripId: "T0", boardStopId: "SA", alightStopId: "SB" }]) {
-      legs { tripId found status delaySecs scheduledStart scheduledEnd realtimeStart realtimeEnd }
+      legs { tripId found status realtimeState delaySecs scheduledStart scheduledEnd realtimeStart realtimeEnd }
       transfers { fromLegIndex marginSecs }
       eta scheduledEta generatedAt
    } }"#;
@@ -1820,6 +2775,7 @@ fn live_refresh_empty_index_matches_schedule() {
     assert_eq!(leg["tripId"], Value::String("T0".into()));
     assert_eq!(leg["found"], Value::Boolean(true));
     assert_eq!(leg["status"], Value::Enum(Name::new("NO_DATA")));
+    assert_eq!(leg["realtimeState"], Value::Enum(Name::new("SCHEDULED")));
     assert_eq!(int_field(leg, "delaySecs"), Some(0));
     // Realtime equals scheduled with no live data.
     assert_eq!(int_field(leg, "scheduledStart"), Some(33000));
@@ -1862,6 +2818,7 @@ fn live_refresh_delay_shifts_realtime_and_eta() {
     assert_eq!(leg["found"], Value::Boolean(true));
     assert_eq!(leg["status"], Value::Enum(Name::new("DELAYED")));
     assert_eq!(int_field(leg, "delaySecs"), Some(120));
+    assert_eq!(leg["realtimeState"], Value::Enum(Name::new("UPDATED")));
     assert_eq!(int_field(leg, "scheduledStart"), Some(33000));
     assert_eq!(int_field(leg, "scheduledEnd"), Some(33480));
     assert_eq!(int_field(leg, "realtimeStart"), Some(33120));
@@ -1874,19 +2831,49 @@ fn live_refresh_delay_shifts_realtime_and_eta() {
 }
 
 #[test]
-fn live_refresh_canceled_keeps_schedule_and_zero_delay() {
+fn live_refresh_canceled_keeps_schedule_and_zero_delay() {
+    use maas_rs::ingestion::gtfs::TripId;
+    use maas_rs::structures::RealtimeIndex;
+
+    let g = live_refresh_graph();
+    let board = g.stop_index_of("SA").expect("SA resolves") as u32;
+    // A canceled trip can still carry a stale per-stop delay; cancellation must
+    // override it — times stay scheduled and delaySecs is 0.
+    let rt = RealtimeIndex::from_updates(
+        1_700_000_500,
+        [((TripId(0), board), 300)],
+        [TripId(0)],
+    );
+
+    let obj = live_refresh_query(g, rt, LIVE_LEG0_QUERY);
+    let legs = match &obj["legs"] {
+        Value::List(v) => v,
+        other => panic!("expected legs list, got {other:?}"),
+    };
+    let leg = match &legs[0] {
+        Value::Object(m) => m,
+        other => panic!("expected leg object, got {other:?}"),
+    };
+    assert_eq!(leg["found"], Value::Boolean(true));
+    assert_eq!(leg["status"], Value::Enum(Name::new("CANCELED")));
+    assert_eq!(int_field(leg, "delaySecs"), Some(0));
+    assert_eq!(leg["realtimeState"], Value::Enum(Name::new("CANCELED")));
+    assert_eq!(int_field(leg, "scheduledStart"), Some(33000));
+    assert_eq!(int_field(leg, "scheduledEnd"), Some(33480));
+    assert_eq!(int_field(leg, "realtimeStart"), Some(33000));
+    assert_eq!(int_field(leg, "realtimeEnd"), Some(33480));
+}
+
+#[test]
+fn live_refresh_skipped_alight_stop_is_modified() {
     use maas_rs::ingestion::gtfs::TripId;
     use maas_rs::structures::RealtimeIndex;
 
     let g = live_refresh_graph();
     let board = g.stop_index_of("SA").expect("SA resolves") as u32;
-    // A canceled trip can still carry a stale per-stop delay; cancellation must
-    // override it — times stay scheduled and delaySecs is 0.
-    let rt = RealtimeIndex::from_updates(
-        1_700_000_500,
-        [((TripId(0), board), 300)],
-        [TripId(0)],
-    );
+    let alight = g.stop_index_of("SB").expect("SB resolves") as u32;
+    let rt = RealtimeIndex::from_delays(1_700_000_000, [((TripId(0), board), 60)])
+        .with_skipped([(TripId(0), alight)].into_iter().collect());
 
     let obj = live_refresh_query(g, rt, LIVE_LEG0_QUERY);
     let legs = match &obj["legs"] {
@@ -1897,13 +2884,8 @@ fn live_refresh_canceled_keeps_schedule_and_zero_delay() {
         Value::Object(m) => m,
         other => panic!("expected leg object, got {other:?}"),
     };
-    assert_eq!(leg["found"], Value::Boolean(true));
-    assert_eq!(leg["status"], Value::Enum(Name::new("CANCELED")));
-    assert_eq!(int_field(leg, "delaySecs"), Some(0));
-    assert_eq!(int_field(leg, "scheduledStart"), Some(33000));
-    assert_eq!(int_field(leg, "scheduledEnd"), Some(33480));
-    assert_eq!(int_field(leg, "realtimeStart"), Some(33000));
-    assert_eq!(int_field(leg, "realtimeEnd"), Some(33480));
+    assert_eq!(leg["status"], Value::Enum(Name::new("DELAYED")));
+    assert_eq!(leg["realtimeState"], Value::Enum(Name::new("MODIFIED")));
 }
 
 #[test]
@@ -2731,16 +3713,12 @@ fn onboard_gql_graph() -> Graph {
     g.add_transit_departures(vec![
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: 32400,
             arrival: 32700,
             service_id: ServiceId(0),
         },
         TripSegment {
             trip_id: TripId(0),
-            origin_stop_sequence: 1,
-            destination_stop_sequence: 2,
             departure: 32700,
             arrival: 33000,
             service_id: ServiceId(0),
@@ -2792,6 +3770,191 @@ fn onboard_gql_graph() -> Graph {
     g
 }
 
+#[test]
+fn graphql_route_timetable_lists_trips_with_stop_times() {
+    let schema = build_schema(shared(onboard_gql_graph()));
+    let resp = execute_sync(
+        &schema,
+        r#"{ routeTimetable(routeId: "maas:route:0", date: "2025-03-04") {
+              route { shortName } date
+              trips { tripId stopTimes { stopId stopName arrival departure } } }
+            unknown: routeTimetable(routeId: "nope") { date } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    assert_eq!(data["unknown"], Value::Null);
+    let Value::Object(tt) = &data["routeTimetable"] else { panic!("expected timetable") };
+    assert_eq!(tt["date"], Value::String("2025-03-04".into()));
+    let Value::Object(route) = &tt["route"] else { panic!("expected route") };
+    assert_eq!(route["shortName"], Value::String("X".into()));
+    let Value::List(trips) = &tt["trips"] else { panic!("expected trips") };
+    assert_eq!(trips.len(), 1);
+    let Value::Object(trip) = &trips[0] else { panic!("expected trip") };
+    assert_eq!(trip["tripId"], Value::String("T1".into()));
+    let Value::List(times) = &trip["stopTimes"] else { panic!("expected stop times") };
+    let got: Vec<(Value, i64)> = times
+        .iter()
+        .map(|t| match t {
+            Value::Object(o) => (o["stopId"].clone(), match &o["departure"] {
+                Value::Number(n) => n.as_i64().unwrap(),
+                other => panic!("expected number, got {other:?}"),
+            }),
+            other => panic!("expected object, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            (Value::String("A".into()), 32400),
+            (Value::String("B".into()), 32700),
+            (Value::String("C".into()), 33000),
+        ]
+    );
+}
+
+#[test]
+fn graphql_service_calendar_lists_operating_dates() {
+    let mut g = onboard_gql_graph();
+    let removed = maas_rs::ingestion::gtfs::date_to_days(chrono::NaiveDate::from_ymd_opt(2025, 3, 5).unwrap());
+    g.raptor.transit_services[0].days_of_week = 0x1F;
+    g.raptor.transit_services[0].removed_dates = vec![removed];
+    g.raptor.build_runtime_indices();
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        r#"{ route: serviceCalendar(routeId: "maas:route:0", from: "2025-03-03", to: "2025-03-09")
+            trip: serviceCalendar(tripId: "T1", from: "2025-03-03", to: "2025-03-09")
+            unknown: serviceCalendar(routeId: "nope", from: "2025-03-03", to: "2025-03-09") }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let expected = Value::List(
+        ["2025-03-03", "2025-03-04", "2025-03-06", "2025-03-07"]
+            .iter()
+            .map(|d| Value::String(d.to_string()))
+            .collect(),
+    );
+    assert_eq!(data["route"], expected);
+    assert_eq!(data["trip"], expected);
+    assert_eq!(data["unknown"], Value::Null);
+
+    let both = execute_sync(
+        &schema,
+        r#"{ serviceCalendar(routeId: "maas:route:0", tripId: "T1", from: "2025-03-03", to: "2025-03-09") }"#,
+    );
+    assert!(both.errors[0].message.contains("exactly one"), "{:?}", both.errors);
+    let long = execute_sync(&schema, r#"{ serviceCalendar(tripId: "T1", from: "2025-01-01", to: "2026-06-01") }"#);
+    assert!(long.errors[0].message.contains("<= 366"), "{:?}", long.errors);
+}
+
+#[test]
+fn graphql_timetable_segment_browses_raw_departures() {
+    let schema = build_schema(shared(onboard_gql_graph()));
+    let resp = execute_sync(
+        &schema,
+        r#"{ timetableSegment(edge: { fromStopId: "B", toStopId: "C" }) {
+              start len sorted route { shortName } }
+            all: departures(segment: { start: 1, len: 1 }, date: "2025-03-04") {
+              index tripId departure arrival serviceIndex }
+            after: departures(segment: { start: 0, len: 2 }, date: "2025-03-04", from: 0) { index }
+            before: departures(segment: { start: 0, len: 2 }, date: "2025-03-04", from: 1, before: true) { index } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let Value::List(segs) = &data["timetableSegment"] else { panic!("expected segments") };
+    assert_eq!(segs.len(), 1);
+    let Value::Object(seg) = &segs[0] else { panic!("expected segment") };
+    assert_eq!(seg["start"], Value::Number(1.into()));
+    assert_eq!(seg["len"], Value::Number(1.into()));
+    assert_eq!(seg["sorted"], Value::Boolean(true));
+    let Value::List(all) = &data["all"] else { panic!("expected departures") };
+    assert_eq!(all.len(), 1);
+    let Value::Object(dep) = &all[0] else { panic!("expected departure") };
+    assert_eq!(dep["index"], Value::Number(1.into()));
+    assert_eq!(dep["tripId"], Value::String("T1".into()));
+    assert_eq!(dep["departure"], Value::Number(32700.into()));
+    assert_eq!(dep["arrival"], Value::Number(33000.into()));
+    let index_of = |v: &Value| match v {
+        Value::List(l) => l
+            .iter()
+            .map(|d| match d {
+                Value::Object(o) => o["index"].clone(),
+                other => panic!("expected object, got {other:?}"),
+            })
+            .collect::<Vec<_>>(),
+        other => panic!("expected list, got {other:?}"),
+    };
+    assert_eq!(index_of(&data["after"]), vec![Value::Number(1.into())]);
+    assert_eq!(index_of(&data["before"]), vec![Value::Number(0.into())]);
+
+    let bad = execute_sync(&schema, r#"{ departures(segment: { start: 1, len: 5 }) { index } }"#);
+    assert!(bad.errors[0].message.contains("exceeds"), "{:?}", bad.errors);
+    let unknown = execute_sync(
+        &schema,
+        r#"{ timetableSegment(edge: { fromStopId: "B", toStopId: "nope" }) { start } }"#,
+    );
+    assert!(unknown.errors[0].message.contains("unknown stop"), "{:?}", unknown.errors);
+}
+
+#[test]
+fn graphql_trip_overlays_realtime_on_todays_run_only() {
+    use maas_rs::ingestion::gtfs::TripId;
+    use maas_rs::services::realtime_poller::SharedRealtime;
+    use maas_rs::structures::RealtimeIndex;
+    use maas_rs::web::app::build_schema_rt;
+
+    let g = live_refresh_graph();
+    let alight = g.stop_index_of("SB").expect("SB resolves") as u32;
+    let realtime: SharedRealtime = Arc::new(arc_swap::ArcSwap::from_pointee(
+        RealtimeIndex::from_delays(1_700_000_000, [((TripId(0), alight), 120)]),
+    ));
+    let schema = build_schema_rt(shared(g), realtime);
+    let query = |date: &str| {
+        let resp = execute_sync(
+            &schema,
+            &format!(
+                r#"{{ trip(id: "T0"{date}) {{ tripId runsOnDate realtimeState
+                     route {{ shortName }} geometry {{ lat }}
+                     stopTimes {{ stopId scheduledArrival expectedArrival delaySecs }} }}
+                   missing: trip(id: "nope") {{ tripId }} }}"#
+            ),
+        );
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        let data = data_obj(resp);
+        assert_eq!(data["missing"], Value::Null);
+        match &data["trip"] {
+            Value::Object(m) => m.clone(),
+            other => panic!("expected trip, got {other:?}"),
+        }
+    };
+    let stop_times = |trip: &async_graphql::indexmap::IndexMap<Name, Value>| match &trip["stopTimes"] {
+        Value::List(v) => v
+            .iter()
+            .map(|s| match s {
+                Value::Object(m) => m.clone(),
+                other => panic!("expected stop time, got {other:?}"),
+            })
+            .collect::<Vec<_>>(),
+        other => panic!("expected list, got {other:?}"),
+    };
+
+    let today = query("");
+    assert_eq!(today["runsOnDate"], Value::Boolean(true));
+    assert_eq!(today["realtimeState"], Value::Enum(Name::new("UPDATED")));
+    assert!(matches!(&today["geometry"], Value::List(v) if v.len() == 2));
+    let times = stop_times(&today);
+    assert_eq!(times.len(), 2);
+    assert_eq!(times[0]["stopId"], Value::String("SA".into()));
+    assert_eq!(int_field(&times[0], "scheduledArrival"), Some(33000));
+    assert_eq!(int_field(&times[0], "expectedArrival"), None, "no realtime data at SA");
+    assert_eq!(int_field(&times[1], "expectedArrival"), Some(33600));
+    assert_eq!(int_field(&times[1], "delaySecs"), Some(120));
+
+    let past = query(r#", date: "2020-01-06""#);
+    assert_eq!(past["realtimeState"], Value::Enum(Name::new("SCHEDULED")));
+    assert_eq!(int_field(&stop_times(&past)[1], "expectedArrival"), None);
+}
+
 /// Test 4a: `onboardRaptor(onboardOrigin: {...})` resolves the trip/stop ids and
 /// returns onboard-rooted plans whose first leg is the boarded transit ride.
 #[test]
@@ -2825,6 +3988,216 @@ fn graphql_onboard_raptor_returns_onboard_rooted_plans() {
     }
 }
 
+#[test]
+fn graphql_plan_reliability_complements_missed_connection_probability() {
+    let schema = build_schema(shared(onboard_gql_graph()));
+    let resp = execute_sync(
+        &schema,
+        r#"{ onboardRaptor(toLat: 50.000, toLng: 4.020,
+              onboardOrigin: { tripId: "T1", fromStopId: "A" }) {
+              reliability missedConnectionProbability
+              legs { ... on PlanTransitLeg { transferRisk { reliability missedProbability } } } } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let num = |v: &Value| match v {
+        Value::Number(n) => n.as_f64().expect("float"),
+        other => panic!("expected number, got {other:?}"),
+    };
+    let data = data_obj(resp);
+    let Value::List(plans) = &data["onboardRaptor"] else {
+        panic!("expected plan list");
+    };
+    assert!(!plans.is_empty());
+    for plan in plans {
+        let Value::Object(p) = plan else { panic!("expected plan object") };
+        let (r, missed) = (num(&p["reliability"]), num(&p["missedConnectionProbability"]));
+        assert!((0.0..=1.0).contains(&r), "reliability {r} out of range");
+        assert!((r + missed - 1.0).abs() < 1e-6, "{r} + {missed} != 1");
+        let Value::List(legs) = &p["legs"] else { panic!("expected legs") };
+        for leg in legs {
+            if let Value::Object(l) = leg
+                && let Some(Value::Object(risk)) = l.get("transferRisk")
+            {
+                let sum = num(&risk["reliability"]) + num(&risk["missedProbability"]);
+                assert!((sum - 1.0).abs() < 1e-6);
+            }
+        }
+    }
+}
+
+#[test]
+fn graphql_plan_summary_matches_its_legs() {
+    let schema = build_schema(shared(onboard_gql_graph()));
+    let resp = execute_sync(
+        &schema,
+        r#"{ onboardRaptor(toLat: 50.000, toLng: 4.020,
+              onboardOrigin: { tripId: "T1", fromStopId: "A" }) {
+              start end duration walkDistance transfers waitingTime streetModes transitModes
+              legs { __typename
+                ... on PlanWalkLeg { length streetMode }
+                ... on PlanTransitLeg { waitingTime } } } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let int = |v: &Value| match v {
+        Value::Number(n) => n.as_u64().expect("integer"),
+        other => panic!("expected number, got {other:?}"),
+    };
+    let data = data_obj(resp);
+    let Value::List(plans) = &data["onboardRaptor"] else {
+        panic!("expected plan list");
+    };
+    assert!(!plans.is_empty());
+    for plan in plans {
+        let Value::Object(p) = plan else { panic!("expected plan object") };
+        assert_eq!(int(&p["duration"]), int(&p["end"]) - int(&p["start"]));
+        let Value::List(legs) = &p["legs"] else { panic!("expected legs") };
+        let (mut rides, mut walked, mut waited) = (0, 0, 0);
+        for leg in legs {
+            let Value::Object(l) = leg else { continue };
+            if l["__typename"] == Value::String("PlanTransitLeg".into()) {
+                rides += 1;
+                waited += int(&l["waitingTime"]);
+            } else if l["streetMode"] == Value::Enum(Name::new("WALK")) {
+                walked += int(&l["length"]);
+            }
+        }
+        assert_eq!(int(&p["transfers"]), rides.max(1) - 1);
+        assert_eq!(int(&p["walkDistance"]), walked);
+        assert_eq!(int(&p["waitingTime"]), waited);
+        assert_eq!(p["transitModes"], Value::List(vec![Value::Enum(Name::new("BUS"))]));
+    }
+}
+
+#[test]
+fn graphql_plan_leg_interface_lists_every_leg_type() {
+    let schema = build_schema(shared(Graph::new()));
+    let resp = execute_sync(&schema, r#"{ __type(name: "PlanLeg") { possibleTypes { name } } }"#);
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let data = data_obj(resp);
+    let Value::Object(t) = &data["__type"] else { panic!("PlanLeg not in schema") };
+    let Value::List(types) = &t["possibleTypes"] else { panic!("expected possibleTypes") };
+    let names: Vec<&Value> = types
+        .iter()
+        .map(|v| match v {
+            Value::Object(o) => &o["name"],
+            other => panic!("expected type object, got {other:?}"),
+        })
+        .collect();
+    for name in ["PlanWalkLeg", "PlanBikeLeg", "PlanCarLeg", "PlanWaitLeg", "PlanTransitLeg"] {
+        assert!(names.contains(&&Value::String(name.into())), "{name} missing from {names:?}");
+    }
+}
+
+#[test]
+fn graphql_street_route_types_legs_by_mode() {
+    let mut g = Graph::new();
+    let a = g.add_node(osm_node("a", 50.0, 4.0));
+    let b = g.add_node(osm_node("b", 50.002, 4.0));
+    let c = g.add_node(osm_node("c", 50.002, 4.003));
+    let street = |o, d, length| {
+        maas_rs::structures::EdgeData::Street(maas_rs::structures::StreetEdgeData {
+            bike: true,
+            car: true,
+            ..match foot_street(o, d, length) {
+                maas_rs::structures::EdgeData::Street(s) => s,
+                _ => unreachable!(),
+            }
+        })
+    };
+    for (o, d, len) in [(a, b, 222), (b, c, 214)] {
+        g.add_edge(o, street(o, d, len));
+        g.add_edge(d, street(d, o, len));
+    }
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+    for (mode, typename) in [("WALK", "PlanWalkLeg"), ("BIKE", "PlanBikeLeg"), ("CAR", "PlanCarLeg")] {
+        let resp = execute_sync(
+            &schema,
+            &format!(
+                r#"{{ streetRoute(fromLat: 50.0, fromLng: 4.0, toLat: 50.002, toLng: 4.003,
+                    time: "08:00", mode: {mode}) {{
+                    legs {{ __typename start end duration
+                      ... on {typename} {{ streetMode length instructions {{ direction }} }} }} }} }}"#
+            ),
+        );
+        assert!(resp.errors.is_empty(), "{mode}: unexpected errors: {:?}", resp.errors);
+        let data = data_obj(resp);
+        let Value::Object(plan) = &data["streetRoute"] else { panic!("expected plan object") };
+        let Value::List(legs) = &plan["legs"] else { panic!("expected leg list") };
+        assert_eq!(legs.len(), 1);
+        let Value::Object(leg) = &legs[0] else { panic!("expected leg object") };
+        assert_eq!(leg["__typename"], Value::String(typename.into()));
+        assert_eq!(leg["streetMode"], Value::Enum(Name::new(mode)));
+        assert_eq!(leg["length"], Value::from(436));
+        assert_eq!(leg["start"], Value::from(28_800), "{mode}: interface fields resolve");
+        assert!(matches!(&leg["instructions"], Value::List(steps) if !steps.is_empty()));
+    }
+}
+
+#[test]
+fn graphql_timeline_puts_wait_legs_before_boardings() {
+    // T0 reaches SB at 33480 and T1 leaves it at 34500: a 17 minute wait.
+    let mut g = live_refresh_graph();
+    let stop_c = maas_rs::structures::NodeID(2);
+    let dest = g.add_node(osm_node("dest", 50.000, 4.101));
+    g.add_edge(stop_c, foot_street(stop_c, dest, 72));
+    g.add_edge(dest, foot_street(dest, stop_c, 72));
+    g.build_raptor_index();
+    enable_contraction(&mut g);
+    let schema = build_schema(shared(g));
+    let resp = execute_sync(
+        &schema,
+        r#"{ onboardRaptor(toLat: 50.000, toLng: 4.101,
+              onboardOrigin: { tripId: "T0", fromStopId: "SA" }) {
+              legs { __typename start end ... on PlanTransitLeg { waitingTime } }
+              timeline { __typename start end duration
+                ... on PlanWaitLeg { length from { arrival departure } } } } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let int = |v: &Value| match v {
+        Value::Number(n) => n.as_u64().expect("integer"),
+        other => panic!("expected number, got {other:?}"),
+    };
+    let is = |v: &Value, name: &str| v == &Value::String(name.into());
+    let data = data_obj(resp);
+    let Value::List(plans) = &data["onboardRaptor"] else { panic!("expected plan list") };
+    let Value::Object(p) = &plans[0] else { panic!("expected plan object") };
+    let (Value::List(legs), Value::List(timeline)) = (&p["legs"], &p["timeline"]) else {
+        panic!("expected legs and timeline")
+    };
+    let obj = |v: &Value| match v {
+        Value::Object(o) => o.clone(),
+        other => panic!("expected leg object, got {other:?}"),
+    };
+    let legs: Vec<_> = legs.iter().map(obj).collect();
+    let timeline: Vec<_> = timeline.iter().map(obj).collect();
+    let waits: Vec<usize> = (0..timeline.len())
+        .filter(|&i| is(&timeline[i]["__typename"], "PlanWaitLeg"))
+        .collect();
+    assert_eq!(waits.len(), 1, "{timeline:?}");
+    let (wait, next) = (&timeline[waits[0]], &timeline[waits[0] + 1]);
+    assert!(is(&next["__typename"], "PlanTransitLeg"));
+    assert_eq!((int(&wait["start"]), int(&wait["end"])), (33480, 34500));
+    assert_eq!(int(&wait["duration"]), 1020);
+    assert_eq!(int(&wait["end"]), int(&next["start"]));
+    assert_eq!(int(&wait["length"]), 0);
+    let Value::Object(at) = &wait["from"] else { panic!("expected place") };
+    assert_eq!((int(&at["arrival"]), int(&at["departure"])), (33480, 34500));
+
+    // Apart from the wait, the timeline is `legs` unchanged.
+    let rest: Vec<_> = timeline.iter().filter(|l| !is(&l["__typename"], "PlanWaitLeg")).collect();
+    assert_eq!(rest.len(), legs.len());
+    for (t, l) in rest.iter().zip(&legs) {
+        let key = |l: &async_graphql::indexmap::IndexMap<Name, Value>| {
+            (l["__typename"].clone(), l["start"].clone(), l["end"].clone())
+        };
+        assert_eq!(key(t), key(l));
+    }
+    let waited: u64 = legs.iter().filter_map(|l| l.get("waitingTime")).map(int).sum();
+    assert_eq!(waited, int(&wait["duration"]));
+}
+
 /// Test 4b: an unknown onboard `tripId` yields a clean error, not a panic.
 #[test]
 fn graphql_onboard_raptor_unknown_trip_errors_cleanly() {
@@ -3026,6 +4399,7 @@ fn transit_graph_with_platform() -> Graph {
         lat_lng: LatLng { latitude: 50.000, longitude: 4.0081 },
         accessibility: Availability::Available,
         platform_code: Some("9".into()),
+        code: Some("5102".into()),
         parent_station: None,
     }));
     let stop_b = g.add_node(NodeData::TransitStop(TransitStopData {
@@ -3034,6 +4408,7 @@ fn transit_graph_with_platform() -> Graph {
         lat_lng: LatLng { latitude: 50.000, longitude: 4.0901 },
         accessibility: Availability::Available,
         platform_code: None,
+        code: None,
         parent_station: None,
     }));
 
@@ -3083,7 +4458,7 @@ fn transit_graph_with_platform() -> Graph {
     }]);
     g.add_transit_trip_ids(vec!["T0".into()]);
     g.add_transit_departures(vec![TripSegment {
-        trip_id: TripId(0), origin_stop_sequence: 0, destination_stop_sequence: 1,
+        trip_id: TripId(0),
         departure: 9 * 3600 + 600, arrival: 9 * 3600 + 1080, service_id: ServiceId(0),
     }]);
     {
@@ -3117,8 +4492,8 @@ fn graphql_transit_leg_from_platform_is_exposed() {
           legs {
             ... on PlanTransitLeg {
               tripId
-              from { stopId platform }
-              to { stopId platform }
+              from { stopId platform stopCode node { platform stopCode } }
+              to { stopId platform stopCode }
             }
           }
        } }"#;
@@ -3140,6 +4515,10 @@ fn graphql_transit_leg_from_platform_is_exposed() {
         Value::String("9".into()),
         "board stop with platform_code '9' must expose platform: '9'"
     );
+    assert_eq!(from["stopCode"], Value::String("5102".into()));
+    let Value::Object(node) = &from["node"] else { panic!("expected node object") };
+    assert_eq!(node["platform"], Value::String("9".into()));
+    assert_eq!(node["stopCode"], Value::String("5102".into()));
 
     let to = match &leg["to"] {
         Value::Object(m) => m,
@@ -3150,6 +4529,7 @@ fn graphql_transit_leg_from_platform_is_exposed() {
         Value::Null,
         "alight stop without platform_code must expose platform: null"
     );
+    assert_eq!(to["stopCode"], Value::Null);
 }
 
 
@@ -3177,6 +4557,7 @@ fn live_refresh_platform_graph() -> Graph {
             lat_lng: LatLng { latitude: 50.0, longitude: lon },
             accessibility: Availability::Available,
             platform_code: plat.map(|s| s.to_string()),
+            code: None,
             parent_station: None,
         }))
     };
@@ -3208,7 +4589,6 @@ fn live_refresh_platform_graph() -> Graph {
     g.add_transit_trip_ids(vec!["T0".into()]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0, destination_stop_sequence: 1,
         departure: 36000, arrival: 36900,
         service_id: ServiceId(0),
     }]);
@@ -3326,6 +4706,124 @@ fn live_refresh_graph_with_route_ids() -> Graph {
     g
 }
 
+#[test]
+fn graphql_alerts_in_area_keeps_alerts_touching_the_box() {
+    use maas_rs::ingestion::realtime::{AlertEntitySelector, ServiceAlert};
+    use maas_rs::services::realtime_poller::SharedRealtime;
+    use maas_rs::structures::RealtimeIndex;
+    use maas_rs::web::app::build_schema_rt;
+
+    let alert = |header: &str, entity: AlertEntitySelector, active_period| ServiceAlert {
+        header: Some(header.into()),
+        description: None,
+        cause: None,
+        effect: Some(1),
+        active_period,
+        informed_entity: vec![entity],
+    };
+    let stop = |id: &str| AlertEntitySelector { trip_id: None, route_id: None, stop_id: Some(id.into()) };
+    let rt = RealtimeIndex::with_alerts(
+        0,
+        [],
+        [],
+        [],
+        [
+            alert("A closed", stop("SA"), vec![]),
+            alert("C closed", stop("SC"), vec![]),
+            alert("A was closed", stop("SA"), vec![(Some(0), Some(1))]),
+            alert(
+                "Line M",
+                AlertEntitySelector { trip_id: None, route_id: Some("R1".into()), stop_id: None },
+                vec![],
+            ),
+            // T1 only runs B → C, but its route also serves A.
+            alert(
+                "T1 late",
+                AlertEntitySelector { trip_id: Some("T1".into()), route_id: None, stop_id: None },
+                vec![],
+            ),
+        ],
+    );
+    let realtime: SharedRealtime = Arc::new(arc_swap::ArcSwap::from_pointee(rt));
+    let schema = build_schema_rt(shared(live_refresh_graph_with_route_ids()), realtime);
+    let headers = |box_: &str| {
+        let resp = execute_sync(
+            &schema,
+            &format!(
+                "{{ alertsInArea({box_}, date: \"2026-06-12\", time: \"08:00\") \
+                 {{ alert {{ header }} stopIds routeIds }} }}"
+            ),
+        );
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        let json = resp.data.into_json().unwrap();
+        json["alertsInArea"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| {
+                let ids = |k: &str| a[k].as_array().unwrap().iter().map(|v| v.as_str().unwrap().to_string()).collect::<Vec<_>>();
+                (a["alert"]["header"].as_str().unwrap().to_string(), ids("stopIds"), ids("routeIds"))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Around Stop A only.
+    let around_a = headers("minLat: 49.99, minLng: 3.99, maxLat: 50.01, maxLng: 4.01");
+    assert_eq!(
+        around_a,
+        vec![
+            ("A closed".to_string(), vec!["maas:stop:0".to_string()], vec![]),
+            ("Line M".to_string(), vec![], vec!["maas:route:0".to_string()]),
+            ("T1 late".to_string(), vec![], vec!["maas:route:0".to_string()]),
+        ],
+        "expired and out-of-box alerts are dropped"
+    );
+    assert!(headers("minLat: 51.0, minLng: 5.0, maxLat: 51.1, maxLng: 5.1").is_empty());
+
+    let resp = execute_sync(&schema, "{ alertsInArea(minLat: 50.1, minLng: 4.0, maxLat: 50.0, maxLng: 4.1) { stopIds } }");
+    assert!(!resp.errors.is_empty(), "an inverted box is rejected");
+}
+
+#[test]
+fn graphql_resolve_stable_ids_maps_gtfs_ids_onto_the_graph() {
+    let schema = build_schema(shared(live_refresh_graph_with_route_ids()));
+    let resp = execute_sync(&schema, "{ gtfsStops { id stableId } }");
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    assert_eq!(json["gtfsStops"][0]["stableId"], "gtfs:stop:SA");
+
+    let resp = execute_sync(
+        &schema,
+        r#"{ resolveStableIds(ids: ["gtfs:stop:SA", "gtfs:route:R1", "gtfs:trip:T1", "maas:stop:1", "gtfs:stop:gone"])
+             { stableId kind id lat lng } }"#,
+    );
+    assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+    let json = resp.data.into_json().unwrap();
+    let got = json["resolveStableIds"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["kind"].as_str().map(str::to_owned), r["id"].as_str().map(str::to_owned)))
+        .collect::<Vec<_>>();
+    let some = |k: &str, id: &str| (Some(k.to_string()), Some(id.to_string()));
+    assert_eq!(
+        got,
+        vec![
+            some("STOP", "maas:stop:0"),
+            some("ROUTE", "maas:route:0"),
+            some("TRIP", "T1"),
+            some("STOP", "maas:stop:1"),
+            (None, None),
+        ]
+    );
+    assert_eq!(json["resolveStableIds"][0]["lng"], 4.0);
+    assert!(json["resolveStableIds"][1]["lat"].is_null());
+
+    let ids = vec!["\"gtfs:stop:SA\""; 501].join(",");
+    let resp = execute_sync(&schema, &format!("{{ resolveStableIds(ids: [{ids}]) {{ kind }} }}"));
+    assert!(!resp.errors.is_empty(), "more than 500 ids is rejected");
+}
+
 #[test]
 fn live_refresh_route_level_alert_surfaces_for_matching_route() {
     use maas_rs::ingestion::realtime::{AlertEntitySelector, ServiceAlert};
@@ -3662,3 +5160,149 @@ fn graphql_complexity_limit_rejects_over_budget_query() {
         resp.errors[0].message
     );
 }
+
+#[test]
+fn graphql_plan_cache_serves_repeats_and_admin_flush_clears_it() {
+    use maas_rs::structures::{AddressIndex, RealtimeIndex};
+    use maas_rs::web::app::{SharedAddressIndex, WebConfig, build_schema_server};
+    use maas_rs::web::plan_cache::PlanCache;
+    let realtime: maas_rs::services::realtime_poller::SharedRealtime =
+        Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new()));
+    let address: SharedAddressIndex =
+        Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default()));
+    let cache = Arc::new(PlanCache::new(8, std::time::Duration::from_secs(60), 60));
+    let schema = build_schema_server(
+        shared(walk_grid_graph()),
+        realtime,
+        120,
+        address,
+        WebConfig::default(),
+        None,
+        None,
+        cache.clone(),
+        Some("s3cret".to_string()),
+    );
+    let plan = r#"{ raptor(fromLat: 50.0, fromLng: 4.0, toLat: 50.0, toLng: 4.001,
+        date: "2025-03-03", time: "08:00:00") { start end } }"#;
+    let first = execute_sync(&schema, plan);
+    assert!(first.errors.is_empty(), "unexpected errors: {:?}", first.errors);
+    assert_eq!(cache.len(), 1);
+    let again = execute_sync(&schema, &plan.replace("08:00:00", "08:00:30"));
+    assert_eq!(first.data, again.data, "same minute is served from the cache");
+    assert_eq!(cache.len(), 1);
+
+    let denied = execute_sync(&schema, r#"mutation { flushPlanCache(token: "nope") }"#);
+    assert!(!denied.errors.is_empty());
+    assert_eq!(cache.len(), 1);
+    let flushed = execute_sync(&schema, r#"mutation { flushPlanCache(token: "s3cret") }"#);
+    assert!(flushed.errors.is_empty(), "unexpected errors: {:?}", flushed.errors);
+    assert_eq!(data_obj(flushed)["flushPlanCache"], Value::from(1));
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn graphql_cached_plans_carry_the_requests_own_ids_and_warnings() {
+    use maas_rs::structures::{AddressIndex, RealtimeIndex};
+    use maas_rs::web::app::{SharedAddressIndex, WebConfig, build_schema_server};
+    use maas_rs::web::plan_cache::PlanCache;
+    let realtime: maas_rs::services::realtime_poller::SharedRealtime =
+        Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new()));
+    let address: SharedAddressIndex =
+        Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default()));
+    let cache = Arc::new(PlanCache::new(8, std::time::Duration::from_secs(60), 60));
+    let mut graph = transit_handles_graph();
+    graph.set_snap_warning_m(5);
+    let schema = build_schema_server(
+        shared(graph),
+        realtime,
+        120,
+        address,
+        WebConfig::default(),
+        None,
+        None,
+        cache.clone(),
+        None,
+    );
+    let plans = |from_lat: &str, from_lng: &str| -> Vec<Value> {
+        let resp = execute_sync(
+            &schema,
+            &format!(
+                r#"{{ raptor(fromLat: {from_lat}, fromLng: {from_lng}, toLat: 50.0, toLng: 4.098,
+                    modes: [WALK_TRANSIT], date: "2026-06-23", time: "09:00:00") {{ itineraryId warnings {{ code }} }} }}"#
+            ),
+        );
+        assert!(resp.errors.is_empty(), "unexpected errors: {:?}", resp.errors);
+        let Value::List(plans) = &data_obj(resp)["raptor"] else { panic!("expected plans") };
+        plans.clone()
+    };
+    let ids = |plans: &[Value]| -> Vec<Value> {
+        plans
+            .iter()
+            .map(|p| match p {
+                Value::Object(p) => p["itineraryId"].clone(),
+                other => panic!("expected a plan, got {other:?}"),
+            })
+            .collect()
+    };
+    let far_snap = |plans: &[Value]| {
+        plans.iter().all(|p| {
+            matches!(p, Value::Object(p) if format!("{:?}", p["warnings"]).contains("FAR_SNAP"))
+        })
+    };
+    let first = plans("50.0", "4.0");
+    // A few metres away: same snapped node, so the cached plans are served.
+    let second = plans("50.0", "4.00002");
+    assert_eq!(cache.len(), 1);
+    assert!(ids(&first).iter().any(|id| *id != Value::Null));
+    assert_ne!(ids(&first), ids(&second), "ids encode each request's own origin, not the cached one's");
+    // Same node again, but 11 m from it: only this request is warned.
+    let far = plans("50.0001", "4.0");
+    assert_eq!(cache.len(), 1);
+    assert!(!first.is_empty() && !far_snap(&first));
+    assert!(far_snap(&far), "{far:?}");
+}
+
+#[test]
+fn graphql_admin_save_graph_writes_a_loadable_graph() {
+    use maas_rs::structures::{AddressIndex, RealtimeIndex};
+    use maas_rs::web::app::{SharedAddressIndex, WebConfig, build_schema_server};
+    use maas_rs::web::plan_cache::PlanCache;
+    let realtime: maas_rs::services::realtime_poller::SharedRealtime =
+        Arc::new(arc_swap::ArcSwap::from_pointee(RealtimeIndex::new()));
+    let address: SharedAddressIndex =
+        Arc::new(arc_swap::ArcSwap::from_pointee(AddressIndex::default()));
+    let graph = walk_grid_graph();
+    let nodes = graph.node_count();
+    let schema = build_schema_server(
+        shared(graph),
+        realtime,
+        120,
+        address,
+        WebConfig::default(),
+        None,
+        None,
+        Arc::new(PlanCache::disabled()),
+        Some("s3cret".to_string()),
+    );
+    let dir = std::env::temp_dir().join(format!("maas_save_graph_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("graph.bin");
+    let path_s = path.to_str().unwrap();
+
+    let denied = execute_sync(
+        &schema,
+        &format!(r#"mutation {{ saveGraph(token: "nope", path: "{path_s}") }}"#),
+    );
+    assert!(!denied.errors.is_empty());
+    assert!(!path.exists(), "a rejected token must not write anything");
+
+    let saved = execute_sync(
+        &schema,
+        &format!(r#"mutation {{ saveGraph(token: "s3cret", path: "{path_s}") }}"#),
+    );
+    assert!(saved.errors.is_empty(), "unexpected errors: {:?}", saved.errors);
+    assert_eq!(data_obj(saved)["saveGraph"], Value::from(true));
+    let loaded = maas_rs::services::persistence::load_graph_unchecked(path_s).unwrap();
+    assert_eq!(loaded.node_count(), nodes);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -3,8 +3,8 @@
 use gtfs_structures::RouteType;
 use maas_rs::{
     ingestion::gtfs::{
-        AgencyId, RouteId, RouteInfo, ServiceId, ServicePattern, StopTime, TimetableSegment,
-        TripId, TripInfo, TripSegment,
+        AgencyId, IngestionReport, RouteId, RouteInfo, ServiceId, ServicePattern, StopTime,
+        TimetableSegment, TripId, TripInfo, TripSegment,
     },
     structures::{
         ActiveModes, BikeAttrs, BikeCost, BikeProfile, EdgeData, Graph, LatLng, NodeData, NodeID,
@@ -30,6 +30,7 @@ fn transit_stop(name: &str, lat: f64, lon: f64) -> NodeData {
         accessibility: gtfs_structures::Availability::Available,
         id: name.to_string(),
         platform_code: None,
+        code: None,
         parent_station: None,
     })
 }
@@ -69,6 +70,16 @@ fn enable_contraction(g: &mut Graph) {
 }
 
 fn corridor_graph() -> (Graph, NodeID, NodeID, LatLng, LatLng) {
+    corridor_graph_with(9 * 3600, 10 * 3600, None)
+}
+
+/// The corridor with one trip at `departure`/`arrival`; `Some(cutover)` loads the
+/// timetable as a registered feed with that `service_day_cutover`.
+fn corridor_graph_with(
+    departure: u32,
+    arrival: u32,
+    feed_cutover: Option<Option<u32>>,
+) -> (Graph, NodeID, NodeID, LatLng, LatLng) {
     let mut g = Graph::new();
     let osm_origin = g.add_node(osm_node("origin", 50.000, 4.000));
     let osm_dest = g.add_node(osm_node("dest", 50.000, 4.700));
@@ -111,6 +122,10 @@ fn corridor_graph() -> (Graph, NodeID, NodeID, LatLng, LatLng) {
         }),
     );
 
+    if let Some(cutover) = feed_cutover {
+        g.begin_feed("rail", None);
+        g.set_feed_service_day_cutover(cutover);
+    }
     g.add_transit_services(vec![all_days_service()]);
     g.add_transit_routes(vec![RouteInfo {
         route_short_name: "R".into(),
@@ -128,10 +143,8 @@ fn corridor_graph() -> (Graph, NodeID, NodeID, LatLng, LatLng) {
     }]);
     g.add_transit_departures(vec![TripSegment {
         trip_id: TripId(0),
-        origin_stop_sequence: 0,
-        destination_stop_sequence: 1,
-        departure: 9 * 3600,
-        arrival: 10 * 3600,
+        departure,
+        arrival,
         service_id: ServiceId(0),
     }]);
     {
@@ -142,19 +155,14 @@ fn corridor_graph() -> (Graph, NodeID, NodeID, LatLng, LatLng) {
         g.push_transit_pattern_trip(TripId(0));
         g.push_transit_idx_pattern_trips(Lookup { start: ts, len: 1 });
         let sts = g.transit_pattern_stop_times_len();
-        g.push_transit_pattern_stop_time(StopTime {
-            arrival: 9 * 3600,
-            departure: 9 * 3600,
-            ..Default::default()
-        });
-        g.push_transit_pattern_stop_time(StopTime {
-            arrival: 10 * 3600,
-            departure: 10 * 3600,
-            ..Default::default()
-        });
+        g.push_transit_pattern_stop_time(StopTime { arrival: departure, departure, ..Default::default() });
+        g.push_transit_pattern_stop_time(StopTime { arrival, departure: arrival, ..Default::default() });
         g.push_transit_idx_pattern_stop_times(Lookup { start: sts, len: 2 });
         g.push_transit_pattern(PatternInfo { route: RouteId(0), num_trips: 1 });
     }
+    if feed_cutover.is_some() {
+        g.finish_feed(IngestionReport::default());
+    }
 
     g.build_raptor_index();
     enable_contraction(&mut g);
@@ -245,3 +253,22 @@ fn no_next_day_plan_when_same_day_reachable_range() {
         plans.iter().map(|p| (p.start, p.end)).collect::<Vec<_>>()
     );
 }
+
+/// A 25:30 trip of the previous service day is still catchable at 01:00 unless its
+/// feed's service day already rolled over.
+#[test]
+fn previous_service_day_follows_the_feed_cutover() {
+    let catches_late_trip = |cutover: Option<Option<u32>>| {
+        let (g, o, d, oll, dll) = corridor_graph_with(25 * 3600 + 1800, 26 * 3600, cutover);
+        overnight_windowless(&g, o, d, oll, dll, 3600)
+            .iter()
+            .any(|p| is_transit(p) && p.end < 3 * 3600)
+    };
+    assert!(catches_late_trip(None), "default cutover (05:00) searches the previous day");
+    assert!(catches_late_trip(Some(None)), "a feed without a cutover keeps the default");
+    assert!(catches_late_trip(Some(Some(3 * 3600))));
+    assert!(
+        !catches_late_trip(Some(Some(3600))),
+        "a 01:00 cutover puts a 01:00 query on the new service day"
+    );
+}
@@ -0,0 +1,110 @@
+//! RAPTOR route scans split across worker threads (`MAAS_SCAN_THREADS`, 32+ queued
+//! routes) must follow the same per-query rules as the sequential scan. A binary of its
+//! own: the thread count is read once per process.
+
+use std::collections::HashSet;
+
+use gtfs_structures::{Availability, RouteType};
+use maas_rs::{
+    routing::routing_raptor::{RouteQuery, route},
+    structures::{
+        Graph, GraphBuilder, RealtimeIndex,
+        plan::{Plan, PlanLeg},
+    },
+};
+
+fn hms(h: u32, m: u32) -> u32 {
+    h * 3600 + m * 60
+}
+
+/// Stops A..E along a street; 40 lines run A → E and one runs B → E.
+fn busy_terminus() -> Graph {
+    let mut b = GraphBuilder::new();
+    let mut stops = Vec::new();
+    let mut prev = None;
+    for (i, name) in ["A", "B", "C", "D", "E"].into_iter().enumerate() {
+        let lng = 4.0 + 0.01 * i as f64;
+        let n = b.add_street_node(&format!("n{i}"), 50.0, lng);
+        let s = b.add_stop(&format!("Stop {name}"), 50.0005, lng);
+        b.link_stop(s, n, 60);
+        if let Some(p) = prev {
+            b.add_street(p, n, 715);
+        }
+        prev = Some(n);
+        stops.push(s);
+    }
+    let (a, s_b, e) = (stops[0], stops[1], stops[4]);
+    for i in 0..40 {
+        let r = b.add_route(&format!("{i}"), &format!("Line {i}"), RouteType::Bus);
+        let dep = hms(9, 0) + i * 60;
+        b.add_trip(r, &[(a, dep, dep), (e, dep + 360, dep + 360)]);
+    }
+    let r = b.add_route("B", "Line B", RouteType::Bus);
+    b.add_trip(r, &[(s_b, hms(9, 30), hms(9, 30)), (e, hms(9, 35), hms(9, 35))]);
+    b.build()
+}
+
+fn wheelchair_query() -> RouteQuery {
+    RouteQuery {
+        from_lat: 50.0,
+        from_lng: 4.0,
+        to_lat: 50.0,
+        to_lng: 4.04,
+        date: chrono::NaiveDate::from_ymd_opt(2026, 6, 12).unwrap(),
+        time: chrono::NaiveTime::from_hms_opt(8, 55, 0).unwrap(),
+        window_minutes: None,
+        min_access_secs: None,
+        arrival_slack_secs: None,
+        unrestricted_transfers: None,
+        use_cch_access: None,
+        reliability_bucket_edges: None,
+        modes: None,
+        bike_profile: None,
+        terminal_deadline: false,
+        onboard_origin: None,
+        from_station_id: None,
+        to_station_id: None,
+        profile_latency: None,
+        fare_profile: None,
+        avoid_crowding: false,
+        profile: Some("wheelchair".into()),
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
+    }
+}
+
+fn boards_at(g: &Graph, plans: &[Plan], stop: usize) -> bool {
+    let node = g.raptor.transit_stop_to_node[stop];
+    plans.iter().flat_map(|p| &p.legs).any(|l| matches!(l, PlanLeg::Transit(t) if t.from.node_id == node))
+}
+
+#[test]
+fn parallel_scans_keep_the_wheelchair_profile() {
+    // SAFETY: set before any scan runs, in the only test of this binary.
+    unsafe { std::env::set_var("MAAS_SCAN_THREADS", "4") };
+    let q = wheelchair_query();
+
+    let mut g = busy_terminus();
+    let a = g.stop_index_of("Stop A").unwrap();
+    let walker = RouteQuery { profile: None, ..q.clone() };
+    assert!(boards_at(&g, &route(&g, &walker, &RealtimeIndex::new()).unwrap(), a));
+
+    g.raptor.transit_stop_wheelchair[a] = Availability::NotAvailable;
+    let plans = route(&g, &q, &RealtimeIndex::new()).unwrap_or_default();
+    assert!(!boards_at(&g, &plans, a), "an inaccessible stop is never boarded");
+
+    // A realtime elevator outage blocks the stop the same way. Realtime only applies to
+    // today's Brussels service day.
+    let g = busy_terminus();
+    let now = chrono::Utc::now();
+    let q = RouteQuery { date: now.with_timezone(&chrono_tz::Europe::Brussels).date_naive(), ..q };
+    let rt = RealtimeIndex::from_delays(now.timestamp(), [])
+        .with_inaccessible_stops(HashSet::from([a as u32]));
+    assert!(boards_at(&g, &route(&g, &q, &RealtimeIndex::new()).unwrap(), a));
+    let plans = route(&g, &q, &rt).unwrap_or_default();
+    assert!(!boards_at(&g, &plans, a), "a stop behind a broken elevator is never boarded");
+}
@@ -0,0 +1,479 @@
+//! End-to-end planner behaviour on small networks laid out with `GraphBuilder`.
+
+use gtfs_structures::RouteType;
+use std::sync::Arc;
+
+use maas_rs::{
+    ingestion::gtfs::{ServicePattern, TripId},
+    routing::{
+        federation::{self, ShardChoice},
+        plan_share,
+        routing_raptor::{RouteQuery, route},
+        via::{self, ViaPoint},
+    },
+    services::graph_set::GraphSet,
+    structures::{
+        Connector, Graph, GraphBuilder, ModePreference, NodeID, RealtimeIndex,
+        plan::{Plan, PlanLeg, PlanTransitLeg},
+    },
+};
+
+/// Five street nodes along lat 50 (≈715 m apart) with stops A..E linked beside each;
+/// callers add the transit.
+struct Line {
+    b: GraphBuilder,
+    stops: [NodeID; 5],
+    streets: [NodeID; 5],
+}
+
+fn line() -> Line {
+    let mut b = GraphBuilder::new();
+    let mut streets = Vec::new();
+    let mut stops = Vec::new();
+    for (i, name) in ["A", "B", "C", "D", "E"].into_iter().enumerate() {
+        let lng = 4.0 + 0.01 * i as f64;
+        let n = b.add_street_node(&format!("n{i}"), 50.0, lng);
+        let s = b.add_stop(&format!("Stop {name}"), 50.0005, lng);
+        b.link_stop(s, n, 60);
+        if let Some(&prev) = streets.last() {
+            b.add_street(prev, n, 715);
+        }
+        streets.push(n);
+        stops.push(s);
+    }
+    Line { b, stops: stops.try_into().unwrap(), streets: streets.try_into().unwrap() }
+}
+
+fn hms(h: u32, m: u32) -> u32 {
+    h * 3600 + m * 60
+}
+
+fn query(day: u32, h: u32, m: u32) -> RouteQuery {
+    RouteQuery {
+        from_lat: 50.0,
+        from_lng: 4.0,
+        to_lat: 50.0,
+        to_lng: 4.04,
+        date: chrono::NaiveDate::from_ymd_opt(2026, 6, day).unwrap(),
+        time: chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap(),
+        window_minutes: None,
+        min_access_secs: None,
+        arrival_slack_secs: None,
+        unrestricted_transfers: None,
+        use_cch_access: None,
+        reliability_bucket_edges: None,
+        modes: None,
+        bike_profile: None,
+        terminal_deadline: false,
+        onboard_origin: None,
+        from_station_id: None,
+        to_station_id: None,
+        profile_latency: None,
+        fare_profile: None,
+        avoid_crowding: false,
+        profile: None,
+        what_if: None,
+        night_safety: None,
+        avoid_stairs: None,
+        max_access_walk_m: None,
+        max_egress_walk_m: None,
+        search_profile: None,
+    }
+}
+
+fn plan(g: &Graph, q: &RouteQuery) -> Vec<Plan> {
+    route(g, q, &RealtimeIndex::new()).expect("plans")
+}
+
+fn rides(p: &Plan) -> Vec<&PlanTransitLeg> {
+    p.legs
+        .iter()
+        .filter_map(|l| match l {
+            PlanLeg::Transit(t) => Some(t),
+            _ => None,
+        })
+        .collect()
+}
+
+fn best_transit(plans: &[Plan]) -> &Plan {
+    plans
+        .iter()
+        .filter(|p| !rides(p).is_empty())
+        .min_by_key(|p| p.end)
+        .expect("a transit plan")
+}
+
+#[test]
+fn direct_trip_is_ridden_end_to_end() {
+    let Line { mut b, stops: [a, .., e], .. } = line();
+    let r = b.add_route("1", "Line 1", RouteType::Bus);
+    let trip = b.add_trip(r, &[(a, hms(9, 0), hms(9, 0)), (e, hms(9, 6), hms(9, 6))]);
+    let g = b.build();
+
+    let plans = plan(&g, &query(12, 8, 50));
+    let best = best_transit(&plans);
+    let legs = rides(best);
+    assert_eq!(legs.len(), 1);
+    assert_eq!(legs[0].trip_id, trip);
+    assert_eq!((legs[0].start, legs[0].end), (hms(9, 0), hms(9, 6)));
+    assert!(best.end >= hms(9, 6));
+    assert!(best.start <= hms(9, 0));
+}
+
+#[test]
+fn next_departure_after_query_time_is_boarded() {
+    let Line { mut b, stops: [a, .., e], .. } = line();
+    let r = b.add_route("1", "Line 1", RouteType::Bus);
+    for dep in [hms(9, 0), hms(9, 30), hms(10, 0)] {
+        b.add_trip(r, &[(a, dep, dep), (e, dep + 360, dep + 360)]);
+    }
+    let g = b.build();
+
+    let plans = plan(&g, &query(12, 9, 5));
+    assert_eq!(rides(best_transit(&plans))[0].start, hms(9, 30));
+}
+
+#[test]
+fn transfer_joins_two_lines() {
+    let Line { mut b, stops: [a, _, c, _, e], .. } = line();
+    let r1 = b.add_route("1", "Line 1", RouteType::Bus);
+    let r2 = b.add_route("2", "Line 2", RouteType::Tramway);
+    let t1 = b.add_trip(r1, &[(a, hms(9, 0), hms(9, 0)), (c, hms(9, 3), hms(9, 3))]);
+    let t2 = b.add_trip(r2, &[(c, hms(9, 8), hms(9, 8)), (e, hms(9, 11), hms(9, 11))]);
+    let g = b.build();
+
+    let plans = plan(&g, &query(12, 8, 50));
+    let trips: Vec<TripId> = rides(best_transit(&plans)).iter().map(|t| t.trip_id).collect();
+    assert_eq!(trips, vec![t1, t2]);
+}
+
+#[test]
+fn missed_connection_waits_for_the_next_trip() {
+    let Line { mut b, stops: [a, _, c, _, e], .. } = line();
+    let r1 = b.add_route("1", "Line 1", RouteType::Bus);
+    let r2 = b.add_route("2", "Line 2", RouteType::Tramway);
+    b.add_trip(r1, &[(a, hms(9, 0), hms(9, 0)), (c, hms(9, 5), hms(9, 5))]);
+    // Leaves C before line 1 arrives.
+    b.add_trip(r2, &[(c, hms(9, 4), hms(9, 4)), (e, hms(9, 7), hms(9, 7))]);
+    let later = b.add_trip(r2, &[(c, hms(9, 20), hms(9, 20)), (e, hms(9, 23), hms(9, 23))]);
+    let g = b.build();
+
+    let plans = plan(&g, &query(12, 8, 55));
+    let best = best_transit(&plans);
+    let last = *rides(best).last().unwrap();
+    assert_eq!(last.trip_id, later);
+    // Same stop for both lines: the whole gap from 9:05 to 9:20 is spent waiting.
+    assert_eq!(last.waiting_time, 15 * 60);
+
+    let moving: u32 = best
+        .legs
+        .iter()
+        .map(|l| match l {
+            PlanLeg::Transit(t) => t.duration,
+            street => street.as_street().map_or(0, |w| w.duration),
+        })
+        .sum();
+    let (first, last) = (best.legs[0].span(), best.legs.last().unwrap().span());
+    assert_eq!(moving + best.total_waiting_time(), last.1 - first.0);
+
+    // The timeline shows that gap as a wait leg right before the second ride.
+    let timeline = best.timeline_legs();
+    let i = timeline
+        .iter()
+        .position(|l| matches!(l, PlanLeg::Transit(t) if t.trip_id == later))
+        .unwrap();
+    let PlanLeg::Wait(wait) = &timeline[i - 1] else {
+        panic!("expected a wait before the second ride, got {:?}", timeline[i - 1])
+    };
+    assert_eq!((wait.start, wait.end), (hms(9, 5), hms(9, 20)));
+    assert_eq!(timeline.len(), best.legs.len() + 1);
+}
+
+#[test]
+fn weekday_service_does_not_run_on_saturday() {
+    let Line { mut b, stops: [a, .., e], .. } = line();
+    let weekdays = b.add_service(ServicePattern {
+        days_of_week: 0x1F,
+        start_date: 0,
+        end_date: 9999,
+        added_dates: vec![],
+        removed_dates: vec![],
+    });
+    let r = b.add_route("1", "Line 1", RouteType::Bus);
+    b.add_trip_on(weekdays, r, &[(a, hms(9, 0), hms(9, 0)), (e, hms(9, 6), hms(9, 6))]);
+    let g = b.build();
+
+    // 2026-06-12 is a Friday, 2026-06-13 a Saturday.
+    assert!(plan(&g, &query(12, 8, 50)).iter().any(|p| !rides(p).is_empty()));
+    assert!(plan(&g, &query(13, 8, 50)).iter().all(|p| rides(p).is_empty()));
+}
+
+/// A–B–C 215 m apart; C's platform sits a level down, reached only by elevator
+/// `node/9`. One trip calls at all three.
+#[test]
+fn max_access_walk_skips_a_far_first_stop() {
+    let Line { mut b, stops: [a, bs, .., e], .. } = line();
+    let r = b.add_route("1", "Line 1", RouteType::Bus);
+    b.add_trip(r, &[(bs, hms(9, 5), hms(9, 5)), (e, hms(9, 10), hms(9, 10))]);
+    b.add_trip(r, &[(a, hms(9, 20), hms(9, 20)), (e, hms(9, 26), hms(9, 26))]);
+    let g = b.build();
+
+    let best = |max_access_walk_m| {
+        let plans = plan(&g, &RouteQuery { max_access_walk_m, ..query(12, 8, 50) });
+        let p = best_transit(&plans);
+        (p.end, p.walk_to_first_stop().expect("a transit plan"))
+    };
+    let (end, walk) = best(None);
+    assert_eq!(end, hms(9, 10), "Stop B's earlier bus");
+    assert!(walk > 700, "about 835 m to Stop B, got {walk}");
+    let (end, walk) = best(Some(300));
+    assert_eq!(end, hms(9, 26), "only Stop A is within 300 m");
+    assert!(walk <= 300, "{walk}");
+}
+
+#[test]
+fn max_egress_walk_alights_nearer_the_destination() {
+    let Line { mut b, stops: [a, .., d, e], streets } = line();
+    // The destination sits on its own street node, 215 m past D's and 500 m short of E's.
+    let dest = b.add_street_node("dest", 50.0, 4.033);
+    b.add_street(dest, streets[3], 215);
+    b.add_street(dest, streets[4], 500);
+    let r = b.add_route("1", "Line 1", RouteType::Bus);
+    b.add_trip(r, &[(a, hms(9, 0), hms(9, 0)), (e, hms(9, 6), hms(9, 6)), (d, hms(9, 14), hms(9, 14))]);
+    let g = b.build();
+
+    let egress = |max_egress_walk_m| {
+        let q = RouteQuery { to_lng: 4.033, max_egress_walk_m, ..query(12, 8, 50) };
+        let plans = plan(&g, &q);
+        best_transit(&plans).walk_from_last_stop().expect("a transit plan")
+    };
+    let far = egress(None);
+    assert!(far > 400, "alights at Stop E, about 560 m away; got {far}");
+    let near = egress(Some(400));
+    assert!(near <= 400, "alights at Stop D instead; got {near}");
+}
+
+fn underground_terminus() -> Graph {
+    let mut b = GraphBuilder::new();
+    let mut streets = Vec::new();
+    for i in 0..3 {
+        let n = b.add_street_node(&format!("n{i}"), 50.0, 4.0 + 0.003 * i as f64);
+        if let Some(&prev) = streets.last() {
+            b.add_street(prev, n, 215);
+        }
+        streets.push(n);
+    }
+    let platform = b.add_street_node("platform", 50.0, 4.0061);
+    b.set_level(platform, -1);
+    b.add_connector(streets[2], platform, 10, Connector::Elevator, Some("node/9"));
+    let a = b.add_stop("A", 50.0, 4.0);
+    let s_b = b.add_stop("B", 50.0, 4.003);
+    let c = b.add_stop("C", 50.0, 4.0061);
+    b.link_stop(a, streets[0], 10);
+    b.link_stop(s_b, streets[1], 10);
+    b.link_stop(c, platform, 10);
+    let r = b.add_route("M", "Metro", RouteType::Subway);
+    b.add_trip(r, &[(a, hms(9, 0), hms(9, 0)), (s_b, hms(9, 2), hms(9, 2)), (c, hms(9, 4), hms(9, 4))]);
+    b.build()
+}
+
+#[test]
+fn wheelchair_profile_avoids_a_stop_behind_a_broken_elevator() {
+    let mut g = underground_terminus();
+    // Leaving at 8:59 so rolling the whole way (≈9 min at wheelchair pace) loses.
+    let q = RouteQuery { to_lng: 4.006, profile: Some("wheelchair".into()), ..query(12, 8, 59) };
+    let alight = |g: &Graph| rides(best_transit(&plan(g, &q)))[0].end;
+    assert_eq!(alight(&g), hms(9, 4));
+
+    assert_eq!(g.set_elevator_outages(&["node/9".into()]), 1);
+    assert_eq!(alight(&g), hms(9, 2), "alights at B and rolls on");
+    // Without the wheelchair profile the outage changes nothing.
+    let walker = RouteQuery { profile: None, ..q.clone() };
+    assert_eq!(rides(best_transit(&plan(&g, &walker)))[0].end, hms(9, 4));
+}
+
+#[test]
+fn mode_preferences_pick_the_metro_inside_the_search() {
+    // Both leave A at 9:00; the bus reaches E two minutes before the metro.
+    let network = |prefs| {
+        let Line { mut b, stops: [a, .., e], .. } = line();
+        let bus = b.add_route("1", "Bus", RouteType::Bus);
+        let metro = b.add_route("M", "Metro", RouteType::Subway);
+        b.add_trip(bus, &[(a, hms(9, 0), hms(9, 0)), (e, hms(9, 10), hms(9, 10))]);
+        b.add_trip(metro, &[(a, hms(9, 0), hms(9, 0)), (e, hms(9, 12), hms(9, 12))]);
+        let mut g = b.build();
+        g.set_mode_preferences(prefs);
+        g
+    };
+    let ridden = |g: &Graph| -> Vec<Option<RouteType>> {
+        plan(g, &query(12, 8, 50)).iter().flat_map(|p| rides(p)).map(|t| t.route_type).collect()
+    };
+    assert_eq!(ridden(&network(vec![])), vec![Some(RouteType::Bus)], "the later metro is dominated");
+
+    // The 10-minute bus ride ranks as 15: the metro now dominates it in the search.
+    let slow_bus = ModePreference { cost_multiplier: 1.5, board_penalty_secs: 0 };
+    assert_eq!(ridden(&network(vec![(RouteType::Bus, slow_bus)])), vec![Some(RouteType::Subway)]);
+    let boarding = ModePreference { cost_multiplier: 1.0, board_penalty_secs: 180 };
+    assert_eq!(ridden(&network(vec![(RouteType::Bus, boarding)])), vec![Some(RouteType::Subway)]);
+    let short_penalty = ModePreference { cost_multiplier: 1.0, board_penalty_secs: 60 };
+    assert_eq!(ridden(&network(vec![(RouteType::Bus, short_penalty)])), vec![Some(RouteType::Bus)]);
+}
+
+/// A street from `lng0` to `lng1` (0.01° steps) with a stop per node, snapping capped at
+/// 500 m so each shard only covers its own corridor.
+fn shard(names: &[&str], lng0: f64, trips: &[(&str, &str, u32, u32)]) -> Graph {
+    let mut b = GraphBuilder::new();
+    let mut prev = None;
+    let mut stops = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        let lng = lng0 + 0.01 * i as f64;
+        let n = b.add_street_node(&format!("{name}-street"), 50.0, lng);
+        let s = b.add_stop(name, 50.0005, lng);
+        b.link_stop(s, n, 60);
+        if let Some(p) = prev {
+            b.add_street(p, n, 715);
+        }
+        prev = Some(n);
+        stops.push((*name, s));
+    }
+    let r = b.add_route("R", "Regional", RouteType::Rail);
+    let stop = |name: &str| stops.iter().find(|(n, _)| *n == name).unwrap().1;
+    for &(from, to, dep, arr) in trips {
+        b.add_trip(r, &[(stop(from), dep, dep), (stop(to), arr, arr)]);
+    }
+    let mut g = b.build();
+    g.set_max_snap_distance_m(500);
+    g
+}
+
+fn two_shards() -> GraphSet {
+    let west = shard(&["W", "W2", "Hub"], 4.00, &[("W", "Hub", hms(9, 0), hms(9, 5))]);
+    let east = shard(
+        &["Hub", "E2", "E3", "E"],
+        4.02,
+        &[("Hub", "E", hms(9, 6), hms(9, 12)), ("Hub", "E", hms(9, 20), hms(9, 26))],
+    );
+    let mut set = GraphSet::new(vec!["Hub".into()], 300);
+    set.add_shard("west", Arc::new(west));
+    set.add_shard("east", Arc::new(east));
+    set
+}
+
+#[test]
+fn federation_routes_within_the_covering_shard() {
+    let set = two_shards();
+    let q = RouteQuery { to_lng: 4.02, ..query(12, 8, 50) };
+    assert_eq!(federation::select_shard(&set, &q).unwrap(), ShardChoice::Within(0));
+    let plans = federation::route(&set, &q, &[]).unwrap();
+    assert!(plans.iter().all(|p| p.leg_shards.iter().all(|&s| s == 0)));
+}
+
+#[test]
+fn federation_stitches_at_boundary_station_with_transfer_slack() {
+    let set = two_shards();
+    let q = RouteQuery { to_lng: 4.05, ..query(12, 8, 50) };
+    assert_eq!(
+        federation::select_shard(&set, &q).unwrap(),
+        ShardChoice::Across { from: 0, to: 1 }
+    );
+    let plans = federation::route(&set, &q, &[]).unwrap();
+    let best = &plans[0];
+    let legs = rides(&best.plan);
+    assert_eq!(legs.len(), 2, "one ride per shard");
+    assert_eq!(legs[0].end, hms(9, 5));
+    // 9:06 leaves inside the 300 s boundary transfer: the 9:20 is the connection.
+    assert_eq!(legs[1].start, hms(9, 20));
+    assert_eq!(best.leg_shards.first(), Some(&0));
+    assert_eq!(best.leg_shards.last(), Some(&1));
+    assert_eq!(best.leg_shards.len(), best.plan.legs.len());
+}
+
+#[test]
+fn federation_rejects_points_outside_every_shard() {
+    let set = two_shards();
+    let q = RouteQuery { to_lng: 4.30, ..query(12, 8, 50) };
+    let err = federation::select_shard(&set, &q).unwrap_err();
+    assert_eq!(err.code, Some("OUTSIDE_COVERAGE"));
+}
+
+#[test]
+fn via_point_waits_out_the_stay_before_the_next_hop() {
+    let Line { mut b, stops: [a, _, c, _, e], .. } = line();
+    let r = b.add_route("1", "Line 1", RouteType::Bus);
+    let trips: Vec<TripId> = [hms(9, 0), hms(9, 30)]
+        .into_iter()
+        .map(|dep| b.add_trip(r, &[(a, dep, dep), (c, dep + 180, dep + 180), (e, dep + 360, dep + 360)]))
+        .collect();
+    let g = b.build();
+
+    let stay = ViaPoint { lat: 50.0, lng: 4.02, min_stay_secs: 20 * 60 };
+    let plans = via::route(&g, &query(12, 8, 50), &[stay], &RealtimeIndex::new()).unwrap();
+    assert_eq!(plans.len(), 1, "one combined plan");
+    let legs = rides(&plans[0]);
+    let ridden: Vec<TripId> = legs.iter().map(|t| t.trip_id).collect();
+    // The 9:03 arrival at C plus 20 minutes misses the 9:00 run's onward leg and the
+    // 9:30 run's 9:33 departure from C is the next hop.
+    assert_eq!(ridden, trips);
+    assert_eq!((legs[0].end, legs[1].start), (hms(9, 3), hms(9, 33)));
+    assert_eq!(plans[0].end, best_transit(&plans).end);
+    assert!(plans[0].start <= hms(9, 0) && plans[0].end >= hms(9, 36));
+
+    let unreachable = ViaPoint { lat: 51.0, lng: 4.02, min_stay_secs: 0 };
+    assert!(via::route(&g, &query(12, 8, 50), &[unreachable], &RealtimeIndex::new()).is_err());
+}
+
+/// Stops A and B along a 4.6 km street, C on a platform below n2 reached by stairs
+/// only; the street ends 360 m past n2.
+fn stairs_terminus() -> Graph {
+    let mut b = GraphBuilder::new();
+    let n: Vec<NodeID> = [4.0, 4.03, 4.06, 4.065]
+        .iter()
+        .enumerate()
+        .map(|(i, &lng)| b.add_street_node(&format!("n{i}"), 50.0, lng))
+        .collect();
+    b.add_street(n[0], n[1], 2145);
+    b.add_street(n[1], n[2], 2145);
+    b.add_street(n[2], n[3], 360);
+    let platform = b.add_street_node("platform", 50.0, 4.0601);
+    b.set_level(platform, -1);
+    b.add_connector(n[2], platform, 10, Connector::Steps, None);
+    let stops = [(n[0], 4.0), (n[1], 4.03), (platform, 4.0601)].map(|(street, lng)| {
+        let s = b.add_stop(&format!("S{lng}"), 50.0, lng);
+        b.link_stop(s, street, 10);
+        s
+    });
+    let r = b.add_route("M", "Metro", RouteType::Subway);
+    let times = [hms(9, 0), hms(9, 2), hms(9, 4)];
+    b.add_trip(r, &[0, 1, 2].map(|i| (stops[i], times[i], times[i])));
+    b.build()
+}
+
+/// The plan of `q` that `pick` selects, shared by id and re-hydrated on the same graph.
+fn replayed(g: &Graph, q: &RouteQuery, pick: impl Fn(&Plan) -> bool) -> Plan {
+    let plans = plan(g, q);
+    let found = plans.iter().find(|p| pick(p)).expect("the picked plan is found");
+    let id = plan_share::itinerary_id(g, q, found).expect("transit plans have an id");
+    let again = plan_share::plan_by_id(g, &id, &RealtimeIndex::new()).expect("the id replays");
+    assert_eq!(again.itinerary_id.as_deref(), Some(id.as_str()));
+    again
+}
+
+#[test]
+fn shared_ids_replay_the_window_and_stair_options() {
+    let Line { mut b, stops: [a, _, _, _, e], .. } = line();
+    let r = b.add_route("1", "Line 1", RouteType::Bus);
+    let [_, later] =
+        [hms(9, 0), hms(9, 30)].map(|dep| b.add_trip(r, &[(a, dep, dep), (e, dep + 360, dep + 360)]));
+    let g = b.build();
+    // Only the 60-minute window reaches the 9:30 run.
+    let q = RouteQuery { window_minutes: Some(60), ..query(12, 8, 55) };
+    let rides_later = |p: &Plan| rides(p).first().is_some_and(|t| t.trip_id == later);
+    assert!(rides_later(&replayed(&g, &q, rides_later)));
+
+    // Stairs down to C: avoiding them means alighting at B and walking on.
+    let g = stairs_terminus();
+    let q = RouteQuery { to_lng: 4.065, avoid_stairs: Some(true), ..query(12, 8, 58) };
+    let alights_at_b = |p: &Plan| rides(p).first().is_some_and(|t| t.end == hms(9, 2));
+    assert!(!plan(&g, &RouteQuery { avoid_stairs: None, ..q.clone() }).iter().any(alights_at_b));
+    assert!(alights_at_b(&replayed(&g, &q, alights_at_b)));
+}
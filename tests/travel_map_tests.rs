@@ -28,6 +28,7 @@ fn transit_stop(name: &str, lat: f64, lon: f64) -> NodeData {
         accessibility: Availability::Available,
         id: name.to_string(),
         platform_code: None,
+        code: None,
         parent_station: None,
     })
 }
@@ -118,8 +119,6 @@ fn add_two_stop_line(
     let segs: Vec<TripSegment> = (0..n)
         .map(|i| TripSegment {
             trip_id: trips[i],
-            origin_stop_sequence: 0,
-            destination_stop_sequence: 1,
             departure: deps[i],
             arrival: arrs[i],
             service_id: ServiceId(0),
@@ -132,7 +131,7 @@ fn add_two_stop_line(
             origin: board,
             destination: alight,
             route_id: route,
-            timetable_segment: TimetableSegment { start: seg_start, len: n },
+            timetable_segment: TimetableSegment { start: seg_start as u32, len: n as u32 },
             length: length_m,
         }),
     );
@@ -264,6 +263,22 @@ fn run(g: &Graph, am: &ActiveModes, max_secs: u32) -> Vec<TravelCell> {
 }
 
 
+#[test]
+fn raster_export_keeps_every_cell_on_its_pixel() {
+    use maas_rs::services::travel_export::{NO_DATA, TravelRaster};
+
+    let g = corridor_graph();
+    let cells = run(&g, &walk_only(), 1200);
+    let raster = TravelRaster::from_cells(&cells, g.raptor.travel_map_grid_step_m).unwrap();
+    let reached = raster.seconds.iter().filter(|&&s| s != NO_DATA).count();
+    assert_eq!(reached, cells.len(), "one pixel per lattice cell, none merged");
+    for c in &cells {
+        let col = ((c.loc.longitude - raster.west) / raster.dlng).round() as usize;
+        let row = ((raster.north - c.loc.latitude) / raster.dlat).round() as usize;
+        assert_eq!(raster.seconds[row * raster.width as usize + col] as u32, c.seconds);
+    }
+}
+
 #[test]
 fn walk_only_covers_near_point_and_omits_far_point() {
     let g = corridor_graph();
@@ -299,6 +314,38 @@ fn transit_makes_far_point_reachable() {
     );
 }
 
+#[test]
+fn point_times_match_the_grid_and_need_transit_for_the_far_point() {
+    let g = corridor_graph();
+    let times = |am: &ActiveModes, points: &[LatLng]| {
+        g.travel_times_to(
+            LatLng { latitude: 50.000, longitude: 4.0 },
+            points,
+            START,
+            DATE,
+            WEEKDAY,
+            1200,
+            am,
+            &buckets(&g),
+            g.raptor.arrival_slack_secs,
+            false,
+            false,
+            &RealtimeIndex::new(),
+            &BikeCost::new(g.raptor.bike_profile),
+        )
+    };
+    let cells = run(&g, &walk_transit(), 1200);
+    let sample: Vec<LatLng> = cells.iter().step_by(7).map(|c| c.loc).collect();
+    let expected: Vec<Option<u32>> = cells.iter().step_by(7).map(|c| Some(c.seconds)).collect();
+    assert_eq!(times(&walk_transit(), &sample), expected, "same field as the grid");
+
+    let [near, far] = times(&walk_transit(), &[near_point(), far_point()])[..] else {
+        unreachable!()
+    };
+    assert!(near.is_some() && far.is_some_and(|s| s <= 1200));
+    assert_eq!(times(&walk_only(), &[far_point()]), vec![None]);
+}
+
 #[test]
 fn cells_never_exceed_max_secs() {
     let g = corridor_graph();